@@ -0,0 +1,310 @@
+//! Ambient loops driven by the surroundings of the player.
+//!
+//! The surroundings are sampled a few times per second: how enclosed the player
+//! is (caves), how close the nearest water surface is, the biome and the weather.
+//! Each loop then fades towards the volume matching the latest sample, so that
+//! walking into a cave slowly replaces the wind with dripping water.
+//!
+//! Loops are read from `<assets>/sounds/ambient/<loop>.ogg`, missing ones are skipped.
+
+use std::collections::HashMap;
+
+use bevy::{
+    audio::{AudioSinkPlayback, Volume},
+    prelude::*,
+};
+use shared::{
+    players::Player,
+    world::{
+        calculate_biome_at_position, calculate_temperature_humidity, BiomeType, BlockId,
+        BlockTransparency, WorldMap, WorldSeed,
+    },
+    GameFolderPaths,
+};
+
+use super::{is_listener_underwater, UNDERWATER_VOLUME_FACTOR};
+use crate::{
+    player::CurrentPlayerMarker,
+    ui::menus::settings::graphics::GraphicsSettings,
+    world::{weather::ClientWeather, ClientWorldMap},
+    GameState,
+};
+
+const AMBIENT_SOUNDS_PATH: &str = "sounds/ambient";
+
+/// Seconds between two samples of the surroundings
+const SAMPLE_INTERVAL: f32 = 0.5;
+/// Seconds for a loop to fade from silent to full volume
+const CROSSFADE_DURATION: f32 = 3.0;
+
+/// Blocks above the head searched for a roof
+const ROOF_SEARCH_HEIGHT: i32 = 24;
+/// Horizontal distance searched for walls, in the 8 surrounding directions
+const WALL_SEARCH_DISTANCE: i32 = 6;
+/// Depth below the surface at which caves sound fully enclosed
+const FULL_CAVE_DEPTH: f32 = 12.0;
+
+/// Horizontal and vertical radius searched for water surfaces
+const WATER_SEARCH_RADIUS: i32 = 8;
+const WATER_SEARCH_HEIGHT: i32 = 4;
+
+/// Height above which the wind is at full volume, wherever the player is
+const WINDY_ALTITUDE: f32 = 100.0;
+
+const WALL_DIRECTIONS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmbientLoop {
+    CaveDrips,
+    Waves,
+    Wind,
+    Rain,
+}
+
+impl AmbientLoop {
+    pub const ALL: [AmbientLoop; 4] = [
+        AmbientLoop::CaveDrips,
+        AmbientLoop::Waves,
+        AmbientLoop::Wind,
+        AmbientLoop::Rain,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            AmbientLoop::CaveDrips => "cave_drips",
+            AmbientLoop::Waves => "waves",
+            AmbientLoop::Wind => "wind",
+            AmbientLoop::Rain => "rain",
+        }
+    }
+
+    /// Volume of the loop when its target is 1
+    fn max_volume(&self) -> f32 {
+        match self {
+            AmbientLoop::CaveDrips => 0.5,
+            AmbientLoop::Waves => 0.6,
+            AmbientLoop::Wind => 0.4,
+            AmbientLoop::Rain => 0.7,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct AmbientSounds {
+    loops: HashMap<AmbientLoop, Handle<AudioSource>>,
+}
+
+impl FromWorld for AmbientSounds {
+    fn from_world(world: &mut World) -> Self {
+        let sounds_path = world
+            .resource::<GameFolderPaths>()
+            .assets_folder_path
+            .join(AMBIENT_SOUNDS_PATH);
+        let asset_server = world.resource::<AssetServer>();
+
+        let loops: HashMap<_, _> = AmbientLoop::ALL
+            .into_iter()
+            .filter_map(|ambient_loop| {
+                let path = sounds_path.join(ambient_loop.name()).with_extension("ogg");
+                path.exists().then(|| {
+                    (
+                        ambient_loop,
+                        asset_server.load(path.to_string_lossy().into_owned()),
+                    )
+                })
+            })
+            .collect();
+
+        info!(
+            "Loaded {} ambient loops from {}",
+            loops.len(),
+            sounds_path.display()
+        );
+
+        Self { loops }
+    }
+}
+
+/// Volume of each loop, between 0 and 1, matching the latest sample
+#[derive(Resource, Default, Debug)]
+pub struct AmbienceTargets {
+    targets: HashMap<AmbientLoop, f32>,
+}
+
+#[derive(Component)]
+pub struct AmbientLoopPlayer {
+    ambient_loop: AmbientLoop,
+    volume: f32,
+}
+
+pub fn setup_ambience(mut commands: Commands, sounds: Res<AmbientSounds>) {
+    for (ambient_loop, handle) in sounds.loops.iter() {
+        commands.spawn((
+            AmbientLoopPlayer {
+                ambient_loop: *ambient_loop,
+                volume: 0.0,
+            },
+            AudioPlayer::new(handle.clone()),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(0.0)),
+            StateScoped(GameState::Game),
+        ));
+    }
+}
+
+fn is_opaque(world_map: &ClientWorldMap, position: IVec3) -> bool {
+    world_map
+        .get_block_by_coordinates(&position)
+        .is_some_and(|block| block.id.get_visibility() == BlockTransparency::Solid)
+}
+
+/// How enclosed the player is: 0 under the open sky, 1 deep in a cave
+fn sample_enclosure(world_map: &ClientWorldMap, head: IVec3) -> f32 {
+    let Some(roof_height) =
+        (1..=ROOF_SEARCH_HEIGHT).find(|dy| is_opaque(world_map, head + IVec3::Y * *dy))
+    else {
+        return 0.0;
+    };
+
+    // Count the solid blocks between the roof and the top of the search area,
+    // as a cheap estimate of the depth below the surface
+    let rock_above = (roof_height..=ROOF_SEARCH_HEIGHT)
+        .filter(|dy| is_opaque(world_map, head + IVec3::Y * *dy))
+        .count() as f32;
+
+    let walls = WALL_DIRECTIONS
+        .iter()
+        .filter(|direction| {
+            (1..=WALL_SEARCH_DISTANCE).any(|distance| {
+                let offset = **direction * distance;
+                is_opaque(world_map, head + IVec3::new(offset.x, 0, offset.y))
+            })
+        })
+        .count() as f32
+        / WALL_DIRECTIONS.len() as f32;
+
+    (rock_above / FULL_CAVE_DEPTH).min(1.0) * walls
+}
+
+/// Closeness of the nearest water surface: 1 when standing next to it, 0 when
+/// further than the search radius
+fn sample_water_proximity(world_map: &ClientWorldMap, feet: IVec3) -> f32 {
+    let is_water = |position: IVec3| {
+        world_map
+            .get_block_by_coordinates(&position)
+            .is_some_and(|block| block.id == BlockId::Water)
+    };
+
+    let mut nearest = f32::MAX;
+    for dx in -WATER_SEARCH_RADIUS..=WATER_SEARCH_RADIUS {
+        for dz in -WATER_SEARCH_RADIUS..=WATER_SEARCH_RADIUS {
+            for dy in -WATER_SEARCH_HEIGHT..=WATER_SEARCH_HEIGHT {
+                let position = feet + IVec3::new(dx, dy, dz);
+                if is_water(position) && !is_water(position + IVec3::Y) {
+                    nearest = nearest.min(IVec3::new(dx, dy, dz).as_vec3().length());
+                }
+            }
+        }
+    }
+
+    (1.0 - nearest / WATER_SEARCH_RADIUS as f32).max(0.0)
+}
+
+fn biome_wind(biome: BiomeType) -> f32 {
+    match biome {
+        BiomeType::HighMountainGrass | BiomeType::IcePlain => 1.0,
+        BiomeType::MediumMountain | BiomeType::Desert => 0.6,
+        BiomeType::ShallowOcean | BiomeType::Ocean | BiomeType::DeepOcean => 0.5,
+        BiomeType::Plains | BiomeType::FlowerPlains => 0.3,
+        BiomeType::Forest | BiomeType::Swamp | BiomeType::MushroomFields => 0.15,
+    }
+}
+
+pub fn ambience_sampling_system(
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<ClientWeather>,
+    time: Res<Time>,
+    mut since_last_sample: Local<f32>,
+    mut targets: ResMut<AmbienceTargets>,
+) {
+    *since_last_sample += time.delta_secs();
+    if *since_last_sample < SAMPLE_INTERVAL {
+        return;
+    }
+    *since_last_sample = 0.0;
+
+    let Ok(player) = player_query.single() else {
+        return;
+    };
+
+    let feet = (player.position - Vec3::Y * (player.height / 2.0))
+        .floor()
+        .as_ivec3();
+    let head = (player.position + Vec3::Y * (player.height / 2.0 - 0.1))
+        .floor()
+        .as_ivec3();
+
+    let enclosure = sample_enclosure(&world_map, head);
+    let outside = 1.0 - enclosure;
+    let altitude_wind = (player.position.y / WINDY_ALTITUDE).clamp(0.0, 1.0);
+    let biome_wind = biome_wind(calculate_biome_at_position(feet.x, feet.z, world_seed.0));
+    let precipitating = weather.is_precipitating();
+    // Precipitation falls as snow in freezing climates, which is silent but windy
+    let snowing = precipitating
+        && calculate_temperature_humidity(feet.x, feet.z, world_seed.0).is_freezing_at(feet.y);
+
+    let mut wind = biome_wind.max(altitude_wind);
+    if snowing {
+        wind = (wind + 0.3).min(1.0);
+    }
+    let rain = if precipitating && !snowing { 1.0 } else { 0.0 };
+
+    targets.targets = HashMap::from([
+        (AmbientLoop::CaveDrips, enclosure),
+        (
+            AmbientLoop::Waves,
+            sample_water_proximity(&world_map, feet) * outside,
+        ),
+        (AmbientLoop::Wind, wind * outside),
+        (AmbientLoop::Rain, rain * outside),
+    ]);
+}
+
+pub fn ambience_crossfade_system(
+    mut loops: Query<(&mut AmbientLoopPlayer, &mut AudioSink)>,
+    targets: Res<AmbienceTargets>,
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    graphics_settings: Res<GraphicsSettings>,
+    time: Res<Time>,
+) {
+    let underwater = player_query
+        .single()
+        .is_ok_and(|player| is_listener_underwater(player, &world_map, &graphics_settings, &time));
+    let max_step = time.delta_secs() / CROSSFADE_DURATION;
+
+    for (mut player, mut sink) in loops.iter_mut() {
+        let target = targets
+            .targets
+            .get(&player.ambient_loop)
+            .copied()
+            .unwrap_or(0.0);
+        player.volume += (target - player.volume).clamp(-max_step, max_step);
+
+        let mut volume = player.volume * player.ambient_loop.max_volume();
+        if underwater {
+            volume *= UNDERWATER_VOLUME_FACTOR;
+        }
+        sink.set_volume(Volume::Linear(volume));
+    }
+}
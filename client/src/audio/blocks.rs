@@ -0,0 +1,229 @@
+//! Block sounds, chosen from the sound group of the block (see `BlockId::sound_group`).
+//!
+//! Sounds are read from `<assets>/sounds/blocks/<group>_<kind>.ogg`, e.g.
+//! `stone_place.ogg`. Missing files are skipped, so a sound pack only needs to
+//! provide the sounds it cares about.
+
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use shared::{
+    physics::water::eyes_under_water,
+    players::{blocks::BlockInteractionOutcome, Player},
+    world::{SoundGroup, WorldMap},
+    GameFolderPaths,
+};
+
+use crate::{
+    player::CurrentPlayerMarker, ui::menus::settings::graphics::GraphicsSettings,
+    world::ClientWorldMap, GameState,
+};
+
+const BLOCK_SOUNDS_PATH: &str = "sounds/blocks";
+
+/// Sounds further away than this aren't played
+pub(super) const MAX_HEARING_DISTANCE: f32 = 16.0;
+
+/// Horizontal distance walked between two footsteps
+const FOOTSTEP_DISTANCE: f32 = 1.6;
+
+/// Sounds are quieter and lower underwater, as a crude low-pass filter
+pub const UNDERWATER_VOLUME_FACTOR: f32 = 0.35;
+pub const UNDERWATER_SPEED_FACTOR: f32 = 0.75;
+
+/// Whether the eyes of the player are under the waves drawn, which muffles sounds
+pub fn is_listener_underwater(
+    player: &Player,
+    world_map: &ClientWorldMap,
+    graphics_settings: &GraphicsSettings,
+    time: &Time,
+) -> bool {
+    eyes_under_water(
+        player,
+        world_map,
+        time.elapsed_secs_wrapped(),
+        graphics_settings.water_quality.amplitude(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockSoundKind {
+    Place,
+    Break,
+    Step,
+}
+
+impl BlockSoundKind {
+    pub const ALL: [BlockSoundKind; 3] = [
+        BlockSoundKind::Place,
+        BlockSoundKind::Break,
+        BlockSoundKind::Step,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            BlockSoundKind::Place => "place",
+            BlockSoundKind::Break => "break",
+            BlockSoundKind::Step => "step",
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        match self {
+            BlockSoundKind::Place | BlockSoundKind::Break => 0.8,
+            BlockSoundKind::Step => 0.3,
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BlockSoundEvent {
+    pub group: SoundGroup,
+    pub kind: BlockSoundKind,
+    pub position: Vec3,
+}
+
+impl BlockSoundEvent {
+    pub fn from_outcome(outcome: &BlockInteractionOutcome) -> Self {
+        let (block, position, kind) = match outcome {
+            BlockInteractionOutcome::Placed { block, position } => {
+                (block, position, BlockSoundKind::Place)
+            }
+            BlockInteractionOutcome::Broken { block, position } => {
+                (block, position, BlockSoundKind::Break)
+            }
+        };
+        Self {
+            group: block.id.sound_group(),
+            kind,
+            position: position.as_vec3() + Vec3::splat(0.5),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BlockSounds {
+    sounds: HashMap<(SoundGroup, BlockSoundKind), Handle<AudioSource>>,
+}
+
+impl FromWorld for BlockSounds {
+    fn from_world(world: &mut World) -> Self {
+        let sounds_path = world
+            .resource::<GameFolderPaths>()
+            .assets_folder_path
+            .join(BLOCK_SOUNDS_PATH);
+        let asset_server = world.resource::<AssetServer>();
+
+        let mut sounds = HashMap::new();
+        for group in SoundGroup::ALL {
+            for kind in BlockSoundKind::ALL {
+                let path = sounds_path
+                    .join(format!("{}_{}", group.name(), kind.name()))
+                    .with_extension("ogg");
+                if path.exists() {
+                    sounds.insert(
+                        (group, kind),
+                        asset_server.load(path.to_string_lossy().into_owned()),
+                    );
+                }
+            }
+        }
+
+        info!(
+            "Loaded {} block sounds from {}",
+            sounds.len(),
+            sounds_path.display()
+        );
+
+        Self { sounds }
+    }
+}
+
+pub fn play_block_sounds_system(
+    mut commands: Commands,
+    mut events: EventReader<BlockSoundEvent>,
+    sounds: Res<BlockSounds>,
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    graphics_settings: Res<GraphicsSettings>,
+    time: Res<Time>,
+) {
+    let Ok(player) = player_query.single() else {
+        events.clear();
+        return;
+    };
+    let underwater = is_listener_underwater(player, &world_map, &graphics_settings, &time);
+
+    for event in events.read() {
+        let Some(sound) = sounds.sounds.get(&(event.group, event.kind)) else {
+            continue;
+        };
+
+        let attenuation = 1.0 - event.position.distance(player.position) / MAX_HEARING_DISTANCE;
+        if attenuation <= 0.0 {
+            continue;
+        }
+
+        let (mut volume, mut speed) = (event.kind.volume() * attenuation, 1.0);
+        if underwater {
+            volume *= UNDERWATER_VOLUME_FACTOR;
+            speed *= UNDERWATER_SPEED_FACTOR;
+        }
+
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::Linear(volume))
+                .with_speed(speed),
+            StateScoped(GameState::Game),
+        ));
+    }
+}
+
+/// Plays a step sound every [`FOOTSTEP_DISTANCE`] walked on the ground or swum
+pub fn footstep_sounds_system(
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    mut walked: Local<(Option<Vec3>, f32)>,
+    mut ev_block_sound: EventWriter<BlockSoundEvent>,
+) {
+    let Ok(player) = player_query.single() else {
+        return;
+    };
+    let (last_position, distance) = &mut *walked;
+
+    let moved = last_position
+        .map(|last| (player.position - last).xz().length())
+        .unwrap_or(0.0);
+    *last_position = Some(player.position);
+
+    let swimming = player.in_water && !player.on_ground;
+    if player.is_flying || !(player.on_ground || swimming) {
+        *distance = 0.0;
+        return;
+    }
+
+    *distance += moved;
+    if *distance < FOOTSTEP_DISTANCE {
+        return;
+    }
+    *distance = 0.0;
+
+    let feet = player.position - Vec3::Y * (player.height / 2.0);
+    let group = if swimming {
+        SoundGroup::Water
+    } else {
+        // Slightly below the feet, to find the block being walked on
+        let below = (feet - Vec3::Y * 0.05).floor().as_ivec3();
+        match world_map.get_block_by_coordinates(&below) {
+            Some(block) => block.id.sound_group(),
+            None => return,
+        }
+    };
+
+    ev_block_sound.write(BlockSoundEvent {
+        group,
+        kind: BlockSoundKind::Step,
+        position: feet,
+    });
+}
@@ -0,0 +1,115 @@
+//! Combat sounds, read from `<assets>/sounds/entities/<kind>.ogg`, e.g. `hurt.ogg`.
+//! Like block sounds, missing files are skipped.
+
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use shared::{players::Player, GameFolderPaths};
+
+use crate::{
+    player::CurrentPlayerMarker, ui::menus::settings::graphics::GraphicsSettings,
+    world::ClientWorldMap, GameState,
+};
+
+use super::{
+    is_listener_underwater, MAX_HEARING_DISTANCE, UNDERWATER_SPEED_FACTOR, UNDERWATER_VOLUME_FACTOR,
+};
+
+const ENTITY_SOUNDS_PATH: &str = "sounds/entities";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntitySoundKind {
+    Swing,
+    Hurt,
+}
+
+impl EntitySoundKind {
+    pub const ALL: [EntitySoundKind; 2] = [EntitySoundKind::Swing, EntitySoundKind::Hurt];
+
+    fn name(&self) -> &'static str {
+        match self {
+            EntitySoundKind::Swing => "swing",
+            EntitySoundKind::Hurt => "hurt",
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        match self {
+            EntitySoundKind::Swing => 0.4,
+            EntitySoundKind::Hurt => 0.8,
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EntitySoundEvent {
+    pub kind: EntitySoundKind,
+    pub position: Vec3,
+}
+
+#[derive(Resource)]
+pub struct EntitySounds {
+    sounds: HashMap<EntitySoundKind, Handle<AudioSource>>,
+}
+
+impl FromWorld for EntitySounds {
+    fn from_world(world: &mut World) -> Self {
+        let sounds_path = world
+            .resource::<GameFolderPaths>()
+            .assets_folder_path
+            .join(ENTITY_SOUNDS_PATH);
+        let asset_server = world.resource::<AssetServer>();
+
+        let sounds = EntitySoundKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let path = sounds_path.join(kind.name()).with_extension("ogg");
+                path.exists()
+                    .then(|| (kind, asset_server.load(path.to_string_lossy().into_owned())))
+            })
+            .collect();
+
+        Self { sounds }
+    }
+}
+
+pub fn play_entity_sounds_system(
+    mut commands: Commands,
+    mut events: EventReader<EntitySoundEvent>,
+    sounds: Res<EntitySounds>,
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    graphics_settings: Res<GraphicsSettings>,
+    time: Res<Time>,
+) {
+    let Ok(player) = player_query.single() else {
+        events.clear();
+        return;
+    };
+    let underwater = is_listener_underwater(player, &world_map, &graphics_settings, &time);
+
+    for event in events.read() {
+        let Some(sound) = sounds.sounds.get(&event.kind) else {
+            continue;
+        };
+
+        let attenuation = 1.0 - event.position.distance(player.position) / MAX_HEARING_DISTANCE;
+        if attenuation <= 0.0 {
+            continue;
+        }
+
+        let (mut volume, mut speed) = (event.kind.volume() * attenuation, 1.0);
+        if underwater {
+            volume *= UNDERWATER_VOLUME_FACTOR;
+            speed *= UNDERWATER_SPEED_FACTOR;
+        }
+
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::Linear(volume))
+                .with_speed(speed),
+            StateScoped(GameState::Game),
+        ));
+    }
+}
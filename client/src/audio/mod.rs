@@ -0,0 +1,9 @@
+mod ambience;
+mod blocks;
+mod entities;
+mod voice;
+
+pub use ambience::*;
+pub use blocks::*;
+pub use entities::*;
+pub use voice::*;
@@ -0,0 +1,286 @@
+//! Proximity voice chat (see `shared::voice`), enabled with `--voice`.
+//!
+//! The microphone and the speakers are driven by cpal streams, which run on their
+//! own threads and exchange samples with the game through shared buffers.
+
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, StreamConfig};
+use shared::{
+    messages::{ClientToServerMessage, PlayerId, ServerToClientMessage},
+    players::Player,
+    voice::{
+        resample, voice_attenuation, VoiceDecoder, VoiceEncoder, VoiceFrame, VOICE_FRAME_SAMPLES,
+        VOICE_SAMPLE_RATE,
+    },
+    STC_VOICE_CHANNEL,
+};
+
+use crate::{
+    input::{data::GameAction, keyboard::is_action_pressed},
+    network::SendGameMessageExtension,
+    player::CurrentPlayerMarker,
+    ui::hud::UIMode,
+    KeyMap,
+};
+
+/// Captured audio kept while nobody reads it, in seconds
+const MAX_CAPTURE_SECS: u32 = 1;
+/// Received audio queued per speaker, older samples are dropped to limit latency
+const MAX_SPEAKER_QUEUE_SECS: f32 = 0.3;
+
+struct Speaker {
+    samples: VecDeque<f32>,
+    gain: f32,
+}
+
+type Speakers = Arc<Mutex<HashMap<PlayerId, Speaker>>>;
+
+/// The cpal streams stop when dropped, and aren't `Send` on every platform
+pub struct VoiceStreams {
+    _input: cpal::Stream,
+    _output: cpal::Stream,
+}
+
+#[derive(Resource)]
+pub struct VoiceChat {
+    /// Mono samples at `input_rate`, filled by the input stream
+    captured: Arc<Mutex<Vec<f32>>>,
+    input_rate: u32,
+    /// Resampled samples waiting for a full frame
+    pending: Vec<f32>,
+    speakers: Speakers,
+    output_rate: u32,
+    encoder: VoiceEncoder,
+    decoders: HashMap<PlayerId, VoiceDecoder>,
+    sequence: u32,
+}
+
+/// Players whose voice isn't played, toggled from the player list
+#[derive(Resource, Default, Debug)]
+pub struct VoiceMutes {
+    muted: HashSet<PlayerId>,
+}
+
+impl VoiceMutes {
+    pub fn toggle(&mut self, id: PlayerId) {
+        if !self.muted.remove(&id) {
+            self.muted.insert(id);
+        }
+    }
+
+    pub fn is_muted(&self, id: PlayerId) -> bool {
+        self.muted.contains(&id)
+    }
+}
+
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    captured: Arc<Mutex<Vec<f32>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let max_samples = (config.sample_rate.0 * MAX_CAPTURE_SECS) as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let mut captured = captured.lock().unwrap();
+            if captured.len() > max_samples {
+                captured.clear();
+            }
+            captured.extend(data.chunks(channels).map(|frame| {
+                frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / channels as f32
+            }));
+        },
+        |err| error!("Voice capture error: {}", err),
+        None,
+    )
+}
+
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    speakers: Speakers,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            let mut speakers = speakers.lock().unwrap();
+            for frame in data.chunks_mut(channels) {
+                let mixed: f32 = speakers
+                    .values_mut()
+                    .filter_map(|speaker| speaker.samples.pop_front().map(|s| s * speaker.gain))
+                    .sum();
+                let value = T::from_sample(mixed.clamp(-1.0, 1.0));
+                frame.iter_mut().for_each(|sample| *sample = value);
+            }
+        },
+        |err| error!("Voice playback error: {}", err),
+        None,
+    )
+}
+
+fn open_voice_streams() -> Result<(VoiceChat, VoiceStreams), String> {
+    let host = cpal::default_host();
+
+    let input = host
+        .default_input_device()
+        .ok_or("no input device available")?;
+    let input_config = input.default_input_config().map_err(|e| e.to_string())?;
+    let input_format = input_config.sample_format();
+    let input_config: StreamConfig = input_config.into();
+
+    let output = host
+        .default_output_device()
+        .ok_or("no output device available")?;
+    let output_config = output.default_output_config().map_err(|e| e.to_string())?;
+    let output_format = output_config.sample_format();
+    let output_config: StreamConfig = output_config.into();
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let speakers: Speakers = Arc::new(Mutex::new(HashMap::new()));
+
+    let input_stream = match input_format {
+        SampleFormat::F32 => build_input_stream::<f32>(&input, &input_config, captured.clone()),
+        SampleFormat::I16 => build_input_stream::<i16>(&input, &input_config, captured.clone()),
+        format => return Err(format!("unsupported input sample format {format}")),
+    }
+    .map_err(|e| e.to_string())?;
+    let output_stream = match output_format {
+        SampleFormat::F32 => build_output_stream::<f32>(&output, &output_config, speakers.clone()),
+        SampleFormat::I16 => build_output_stream::<i16>(&output, &output_config, speakers.clone()),
+        format => return Err(format!("unsupported output sample format {format}")),
+    }
+    .map_err(|e| e.to_string())?;
+
+    input_stream.play().map_err(|e| e.to_string())?;
+    output_stream.play().map_err(|e| e.to_string())?;
+
+    Ok((
+        VoiceChat {
+            captured,
+            input_rate: input_config.sample_rate.0,
+            pending: Vec::new(),
+            speakers,
+            output_rate: output_config.sample_rate.0,
+            encoder: VoiceEncoder::new()?,
+            decoders: HashMap::new(),
+            sequence: 0,
+        },
+        VoiceStreams {
+            _input: input_stream,
+            _output: output_stream,
+        },
+    ))
+}
+
+/// Opens the audio devices. Voice chat stays disabled if they can't be used.
+pub fn setup_voice_chat(app: &mut App) {
+    match open_voice_streams() {
+        Ok((voice_chat, streams)) => {
+            info!(
+                "Voice chat enabled (input: {} Hz, output: {} Hz)",
+                voice_chat.input_rate, voice_chat.output_rate
+            );
+            app.insert_resource(voice_chat)
+                .insert_non_send_resource(streams);
+        }
+        Err(err) => warn!("Voice chat disabled: {}", err),
+    }
+}
+
+pub fn voice_capture_system(
+    mut voice: ResMut<VoiceChat>,
+    mut client: ResMut<RenetClient>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+) {
+    let captured = std::mem::take(&mut *voice.captured.lock().unwrap());
+
+    let talking = *ui_mode != UIMode::Typing
+        && is_action_pressed(GameAction::PushToTalk, &keyboard_input, &key_map);
+    if !talking {
+        voice.pending.clear();
+        return;
+    }
+
+    let resampled = resample(&captured, voice.input_rate, VOICE_SAMPLE_RATE);
+    voice.pending.extend(resampled);
+
+    while voice.pending.len() >= VOICE_FRAME_SAMPLES {
+        let samples: Vec<f32> = voice.pending.drain(..VOICE_FRAME_SAMPLES).collect();
+        let Some(data) = voice.encoder.encode(&samples) else {
+            continue;
+        };
+        let sequence = voice.sequence;
+        voice.sequence = voice.sequence.wrapping_add(1);
+        client.send_game_message(ClientToServerMessage::Voice(VoiceFrame { sequence, data }));
+    }
+}
+
+/// Mixes the received voices. Without voice chat, they are still read so that they
+/// don't pile up in the channel.
+pub fn voice_playback_system(
+    voice: Option<ResMut<VoiceChat>>,
+    mut client: ResMut<RenetClient>,
+    mutes: Res<VoiceMutes>,
+    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+) {
+    let Some(mut voice) = voice else {
+        while client.receive_message(STC_VOICE_CHANNEL).is_some() {}
+        return;
+    };
+
+    let listener = player_query.single().map(|player| player.position).ok();
+    let max_queue = (voice.output_rate as f32 * MAX_SPEAKER_QUEUE_SECS) as usize;
+
+    while let Some(Ok(message)) = client.receive_game_message_by_channel(STC_VOICE_CHANNEL) {
+        let ServerToClientMessage::Voice(packet) = message else {
+            continue;
+        };
+        let (Some(listener), false) = (listener, mutes.is_muted(packet.speaker)) else {
+            continue;
+        };
+
+        let decoder = match voice.decoders.entry(packet.speaker) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match VoiceDecoder::new() {
+                Ok(decoder) => entry.insert(decoder),
+                Err(err) => {
+                    warn!(
+                        "Cannot play the voice of player {}: {}",
+                        packet.speaker, err
+                    );
+                    continue;
+                }
+            },
+        };
+        let Some(decoded) = decoder.decode(&packet.frame.data) else {
+            continue;
+        };
+        let samples = resample(&decoded, VOICE_SAMPLE_RATE, voice.output_rate);
+
+        let mut speakers = voice.speakers.lock().unwrap();
+        let speaker = speakers.entry(packet.speaker).or_insert_with(|| Speaker {
+            samples: VecDeque::new(),
+            gain: 0.0,
+        });
+        speaker.gain = voice_attenuation(packet.position.distance(listener));
+        speaker.samples.extend(samples);
+        let overflow = speaker.samples.len().saturating_sub(max_queue);
+        speaker.samples.drain(..overflow);
+    }
+}
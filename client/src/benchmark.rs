@@ -0,0 +1,309 @@
+//! Fly-through benchmark, started with `--benchmark`.
+//!
+//! The client skips the menus and creates a fresh solo world from a fixed seed.
+//! Once the first chunks are shown, the player flies along a scripted path, each
+//! frame moving it by one fixed step whatever the frame rate, so that every run
+//! meshes and draws the same chunks. The frame times, the chunks meshed and the
+//! chunk vertices and indices drawn are recorded on every frame, and written with
+//! their summary to `benchmark_report.ron` in the game folder before the game
+//! exits.
+
+use std::fs;
+use std::io;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+use shared::messages::NetworkAction;
+use shared::sets::ClientSet;
+use shared::world::{WorldGenPreset, WorldSeed};
+use shared::GameFolderPaths;
+
+use crate::camera::CameraController;
+use crate::constants::SAVE_PATH;
+use crate::menus::solo::SelectedWorld;
+use crate::network::buffered_client::{CurrentFrameInputs, SyncTime};
+use crate::player::{player_movement_system, update_frame_inputs_system};
+use crate::ui::button::MenuState;
+use crate::world::{ChunkMeshStats, FirstChunkReceived};
+use crate::GameState;
+
+const BENCHMARK_WORLD_NAME: &str = "benchmark";
+const BENCHMARK_SEED: WorldSeed = WorldSeed(1234);
+const REPORT_FILE: &str = "benchmark_report.ron";
+
+/// Game time covered by each frame of the benchmark
+const STEP_MS: u64 = 16;
+/// Frames left to the first chunks to settle before the flight starts
+const WARMUP_STEPS: u32 = 120;
+
+enum FlightLeg {
+    /// Flies straight up
+    Climb { steps: u32 },
+    /// Flies forward, turned `yaw_degrees` to the left of the initial heading
+    Cruise { yaw_degrees: f32, steps: u32 },
+}
+
+impl FlightLeg {
+    fn steps(&self) -> u32 {
+        match self {
+            FlightLeg::Climb { steps } | FlightLeg::Cruise { steps, .. } => *steps,
+        }
+    }
+}
+
+/// A climb above the spawn, then a square loop of about 200 blocks a side
+const FLIGHT_PATH: &[FlightLeg] = &[
+    FlightLeg::Climb { steps: 120 },
+    FlightLeg::Cruise {
+        yaw_degrees: 0.0,
+        steps: 600,
+    },
+    FlightLeg::Cruise {
+        yaw_degrees: 90.0,
+        steps: 600,
+    },
+    FlightLeg::Cruise {
+        yaw_degrees: 180.0,
+        steps: 600,
+    },
+    FlightLeg::Cruise {
+        yaw_degrees: 270.0,
+        steps: 600,
+    },
+];
+
+/// Measures of one frame of the flight
+#[derive(Serialize, Clone, Copy)]
+struct FrameRecord {
+    frame_time_ms: f32,
+    /// Chunk meshes built this frame
+    chunks_meshed: usize,
+    /// Chunks with a mesh
+    chunks_drawn: usize,
+    vertices: usize,
+    indices: usize,
+}
+
+#[derive(Serialize)]
+struct FrameTimeSummary {
+    mean_ms: f32,
+    p50_ms: f32,
+    p95_ms: f32,
+    p99_ms: f32,
+    max_ms: f32,
+}
+
+#[derive(Serialize)]
+struct BenchmarkReport {
+    seed: u32,
+    step_ms: u64,
+    frame_count: usize,
+    frame_times: FrameTimeSummary,
+    chunks_meshed: usize,
+    peak_vertices: usize,
+    peak_indices: usize,
+    frames: Vec<FrameRecord>,
+}
+
+#[derive(Resource, Default)]
+struct BenchmarkRun {
+    /// Frames since the first chunk was received
+    step: u32,
+    frames: Vec<FrameRecord>,
+}
+
+impl BenchmarkRun {
+    /// Leg flown at the current step, `None` while warming up or once landed
+    fn current_leg(&self) -> Option<&'static FlightLeg> {
+        let mut step = self.step.checked_sub(WARMUP_STEPS)?;
+        for leg in FLIGHT_PATH {
+            if step < leg.steps() {
+                return Some(leg);
+            }
+            step -= leg.steps();
+        }
+        None
+    }
+
+    fn is_flying(&self) -> bool {
+        self.step >= WARMUP_STEPS
+    }
+}
+
+pub fn benchmark_plugin(app: &mut App) {
+    app.insert_resource(SyncTime {
+        fixed_step_ms: Some(STEP_MS),
+        ..default()
+    })
+    .init_resource::<BenchmarkRun>()
+    .add_systems(
+        Update,
+        start_benchmark_system
+            .in_set(ClientSet::Render)
+            .run_if(in_state(GameState::Menu)),
+    )
+    .add_systems(
+        Update,
+        fly_benchmark_path_system
+            .after(update_frame_inputs_system)
+            .before(player_movement_system)
+            .in_set(ClientSet::Predict)
+            .run_if(in_state(GameState::Game)),
+    )
+    .add_systems(
+        Last,
+        record_benchmark_frame_system
+            .in_set(ClientSet::Render)
+            .run_if(in_state(GameState::Game)),
+    );
+}
+
+/// Launches the benchmark world right away, from scratch so that every run
+/// generates the same chunks
+fn start_benchmark_system(
+    mut selected_world: ResMut<SelectedWorld>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    paths: Res<GameFolderPaths>,
+) {
+    let world_dir = paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(BENCHMARK_WORLD_NAME);
+    match fs::remove_dir_all(&world_dir) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            error!(
+                "Could not remove the previous benchmark world {}: {}",
+                world_dir.display(),
+                err
+            );
+        }
+        _ => {}
+    }
+
+    info!("Starting the benchmark with seed {}", BENCHMARK_SEED.0);
+    *selected_world = SelectedWorld {
+        name: Some(BENCHMARK_WORLD_NAME.to_string()),
+        preset: WorldGenPreset::default(),
+        seed: Some(BENCHMARK_SEED),
+    };
+    game_state.set(GameState::PreGameLoading);
+    menu_state.set(MenuState::Disabled);
+}
+
+/// Steers the player along the flight path, turning fly mode on as it takes off
+fn fly_benchmark_path_system(
+    run: Res<BenchmarkRun>,
+    mut frame_inputs: ResMut<CurrentFrameInputs>,
+    mut camera: Query<&mut CameraController>,
+) {
+    if run.step == WARMUP_STEPS {
+        frame_inputs.0.inputs.insert(NetworkAction::ToggleFlyMode);
+    }
+    let Some(leg) = run.current_leg() else {
+        return;
+    };
+    let Ok(mut controller) = camera.single_mut() else {
+        return;
+    };
+
+    controller.angle_y = 0.0;
+    match leg {
+        FlightLeg::Climb { .. } => {
+            frame_inputs.0.inputs.insert(NetworkAction::JumpOrFlyUp);
+        }
+        FlightLeg::Cruise { yaw_degrees, .. } => {
+            controller.angle_x = yaw_degrees.to_radians();
+            frame_inputs.0.inputs.insert(NetworkAction::MoveForward);
+        }
+    }
+}
+
+/// Records the frame, and writes the report once the flight is over
+fn record_benchmark_frame_system(
+    mut run: ResMut<BenchmarkRun>,
+    first_chunk_received: Res<FirstChunkReceived>,
+    chunks: Query<Ref<ChunkMeshStats>>,
+    time: Res<Time<Real>>,
+    paths: Res<GameFolderPaths>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    if !first_chunk_received.0 {
+        return;
+    }
+
+    if run.is_flying() {
+        let mut record = FrameRecord {
+            frame_time_ms: time.delta_secs() * 1000.0,
+            chunks_meshed: 0,
+            chunks_drawn: 0,
+            vertices: 0,
+            indices: 0,
+        };
+        for stats in chunks.iter() {
+            // Chunks get a new entity each time they are meshed
+            if stats.is_added() {
+                record.chunks_meshed += 1;
+            }
+            record.chunks_drawn += 1;
+            record.vertices += stats.solid_vertex_count + stats.water_vertex_count;
+            record.indices += stats.solid_index_count + stats.water_index_count;
+        }
+        run.frames.push(record);
+    }
+
+    run.step += 1;
+    if !run.is_flying() || run.current_leg().is_some() {
+        return;
+    }
+
+    let report = build_report(&run.frames);
+    info!(
+        "Benchmark done: {} frames, {:.2} ms mean, {:.2} ms p99, {} chunks meshed",
+        report.frame_count,
+        report.frame_times.mean_ms,
+        report.frame_times.p99_ms,
+        report.chunks_meshed
+    );
+    let path = paths.game_folder_path.join(REPORT_FILE);
+    let written = ron::ser::to_string_pretty(&report, PrettyConfig::new())
+        .map_err(io::Error::other)
+        .and_then(|serialized| fs::write(&path, serialized));
+    match written {
+        Ok(()) => info!("Benchmark report written to {}", path.display()),
+        Err(err) => error!(
+            "Could not write the benchmark report {}: {}",
+            path.display(),
+            err
+        ),
+    }
+    app_exit.write(AppExit::Success);
+}
+
+fn build_report(frames: &[FrameRecord]) -> BenchmarkReport {
+    let mut frame_times: Vec<f32> = frames.iter().map(|frame| frame.frame_time_ms).collect();
+    frame_times.sort_by(f32::total_cmp);
+    let percentile = |p: f32| {
+        let index = ((frame_times.len() as f32 * p) as usize).min(frame_times.len() - 1);
+        frame_times[index]
+    };
+
+    BenchmarkReport {
+        seed: BENCHMARK_SEED.0,
+        step_ms: STEP_MS,
+        frame_count: frames.len(),
+        frame_times: FrameTimeSummary {
+            mean_ms: frame_times.iter().sum::<f32>() / frame_times.len() as f32,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            max_ms: frame_times[frame_times.len() - 1],
+        },
+        chunks_meshed: frames.iter().map(|frame| frame.chunks_meshed).sum(),
+        peak_vertices: frames.iter().map(|frame| frame.vertices).max().unwrap_or(0),
+        peak_indices: frames.iter().map(|frame| frame.indices).max().unwrap_or(0),
+        frames: frames.to_vec(),
+    }
+}
@@ -2,7 +2,7 @@ use crate::camera::CameraController;
 use crate::player::*;
 use crate::ui::hud::UIMode;
 use bevy::{input::mouse::MouseMotion, prelude::*, window::PrimaryWindow};
-use shared::players::ViewMode;
+use shared::players::{ViewMode, EYE_HEIGHT};
 
 // System to control the camera based on mouse movement
 pub fn camera_control_system(
@@ -59,7 +59,7 @@ pub fn camera_control_system(
             // adjust the camera's position to be at the player's eye level
             camera_transform.translation = Vec3::new(
                 player_position.x,
-                player_position.y + 0.8, // adjust height for the player's eyes
+                player_position.y + EYE_HEIGHT,
                 player_position.z,
             );
 
@@ -4,7 +4,6 @@ pub const BASE_SPECULAR_HIGHLIGHT: f32 = 0.;
 
 pub const CELESTIAL_SIZE: f32 = 50.;
 pub const CELESTIAL_DISTANCE: f32 = 500.; // Low value for testing ; will be increased later
-pub const DAY_DURATION_IN_TICKS: u64 = 20 * 60; // 20 ticks per second * 60 seconds = 1 minute
 
 pub const MAX_HOTBAR_SLOTS: u32 = 9;
 
@@ -15,8 +14,14 @@ pub const HOTBAR_BORDER: f32 = 5.;
 pub const SAVE_PATH: &str = "saves/";
 pub const SERVER_LIST_SAVE_NAME: &str = "servers.ron";
 pub const BINDS_PATH: &str = "keybindings.ron";
+pub const GRAPHICS_SETTINGS_PATH: &str = "graphics.ron";
+pub const DEBUG_HUD_LAYOUT_PATH: &str = "debug_hud.ron";
+/// Folder of the prefab library, one `<name>.ron` file per prefab
+pub const PREFABS_PATH: &str = "prefabs/";
 
 pub const GRASS_COLOR: [f32; 4] = [0.1, 1.0, 0.3, 1.0];
+pub const SWAMP_GRASS_COLOR: [f32; 4] = [0.3, 0.5, 0.2, 1.0];
+pub const HYDRATED_FARMLAND_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
 
 pub const TEXTURE_PATH_BASE: &str = "graphics/base_textures/";
 pub const TEXTURE_PATH_CUSTOM: &str = "graphics/custom_textures/";
@@ -1,38 +1,102 @@
 use std::collections::HashMap;
 
+use crate::audio::{
+    ambience_crossfade_system, ambience_sampling_system, footstep_sounds_system,
+    play_block_sounds_system, play_entity_sounds_system, setup_ambience, voice_capture_system,
+    voice_playback_system, AmbienceTargets, AmbientSounds, BlockSoundEvent, BlockSounds,
+    EntitySoundEvent, EntitySounds, VoiceChat, VoiceMutes,
+};
 use crate::entities::stack::stack_update_system;
 use crate::mob::*;
 use crate::network::buffered_client::{CurrentFrameInputs, PlayerTickInputsBuffer, SyncTime};
 use crate::shaders::{WaterPlugin, WaterSettings};
 use crate::ui::hud::chat::{render_chat, setup_chat};
 use crate::ui::menus::{setup_server_connect_loading_screen, update_server_connect_loading_screen};
+use crate::world::boats::{
+    boat_display_system, boat_update_system, clear_boats_system, leave_boat_system, ClientBoats,
+};
+use crate::world::dimensions::{dimension_change_system, portal_entry_system};
+use crate::world::fishing::{
+    clear_fishing_bobbers_system, fishing_display_system, fishing_update_system, FishingBobbers,
+};
+use crate::world::heatmap::{
+    clear_heatmap_system, heatmap_display_system, heatmap_update_system, ShownHeatmap,
+};
+use crate::world::item_frames::{
+    clear_item_frames_system, item_frame_display_system, item_frame_update_system, ClientItemFrames,
+};
+use crate::world::prefabs::{
+    load_prefab_library, prefab_command_system, prefab_placement_system, prefab_thumbnail_system,
+    PrefabCommandEvent, PrefabLibrary,
+};
+use crate::world::rendering::far_terrain::{
+    clear_far_terrain_system, far_terrain_update_system, far_terrain_visibility_system, FarTerrain,
+};
+use crate::world::water_prediction::{
+    expire_water_predictions_system, predict_water_inflow_system, LocalBlockEditEvent,
+    WaterPrediction,
+};
+use crate::world::waystones::{waystone_effects_event_system, waystone_effects_system};
+use crate::world::weather::{reset_client_weather_system, weather_update_system, ClientWeather};
 use bevy::prelude::*;
 use bevy_atmosphere::prelude::*;
-use shared::messages::mob::MobUpdateEvent;
-use shared::messages::{ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent};
+use shared::fluid::FluidParticlesUpdate;
+use shared::messages::mob::{MobDespawnEvent, MobUpdateEvent};
+use shared::messages::{
+    BoatUpdate, DimensionChange, FarTerrainUpdate, FishingUpdate, HeatmapUpdate, ItemFrameUpdate,
+    ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent, ServerAnnouncement, UiOverlay,
+    WaystoneUpdate, WeatherUpdate, WorldTimeSkip,
+};
 use shared::physics::RustcraftPhysicsPlugin;
-use shared::players::{Inventory, ViewMode};
+use shared::players::{
+    AnimationEvent, GameMode, Inventory, PlayerRoster, PlayerRosterUpdate, ViewMode,
+};
+use shared::sets::ClientSet;
+use shared::water::WaterAuditReport;
 use shared::TICKS_PER_SECOND;
-use time::time_update_system;
+use time::{time_skip_system, time_update_system};
 
 use crate::world::time::ClientTime;
 use crate::world::ClientWorldMap;
 
+use crate::ui::hud::air::{air_bar_system, AirBarRoot, AIR_BAR_WIDGET};
 use crate::ui::hud::debug::BlockDebugWireframeSettings;
+use crate::ui::hud::emotes::{emote_menu_system, setup_emote_menu};
+use crate::ui::hud::health::{health_bar_system, HealthBarRoot, HEALTH_BAR_WIDGET};
 use crate::ui::hud::loading_overlay::{setup_loading_overlay, update_loading_overlay};
-use crate::ui::hud::reticle::spawn_reticle;
-use crate::ui::menus::pause::{render_pause_menu, setup_pause_menu};
+use crate::ui::hud::overlays::{
+    action_bar_system, boss_bars_system, clear_ui_overlays_system, overlay_fade_system,
+    title_system, ui_overlay_update_system, ActionBarRoot, BossBarsRoot, TitleRoot, UiOverlays,
+    ACTION_BAR_WIDGET, BOSS_BARS_WIDGET, TITLE_WIDGET,
+};
+use crate::ui::hud::player_list::{player_list_update_system, setup_player_list};
+use crate::ui::hud::prefabs::{prefab_panel_system, setup_prefab_panel};
+use crate::ui::hud::reticle::{reticle_system, ReticleRoot, RETICLE_WIDGET};
+use crate::ui::hud::waystones::{setup_waystone_menu, waystone_menu_system};
+use crate::ui::hud::widgets::{hud_widget_visibility_system, HudWidgetAppExt};
+use crate::ui::menus::pause::{pause_sync_system, render_pause_menu, setup_pause_menu};
 use bevy::color::palettes::basic::WHITE;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
 
 use crate::ui::hud::debug::targeted_block::block_text_update_system;
+use crate::ui::menus::settings::graphics::GraphicsSettings;
 use crate::world::celestial::setup_main_lighting;
+use crate::world::rendering::bubbles::{
+    bubble_columns_cleanup_system, bubble_columns_render_system, bubble_columns_scan_system,
+    BubbleColumns,
+};
+use crate::world::rendering::fluid_particles::{
+    fluid_particles_billboard_system, fluid_particles_cleanup_system,
+    fluid_particles_render_system, FluidParticleRenderState,
+};
 use crate::world::rendering::water::{
-    water_cleanup_system, water_render_system, WaterEntities, WaterMaterialHandle,
+    apply_water_quality_system, water_cleanup_system, water_render_system, WaterEntities,
+    WaterMaterialHandle,
 };
 
 use crate::ui::hud::debug::*;
+use crate::ui::hud::effects::*;
 use crate::ui::hud::hotbar::*;
 use crate::ui::hud::set_ui_mode;
 use crate::world::celestial::*;
@@ -42,13 +106,15 @@ use crate::camera::*;
 use crate::input::*;
 use crate::player::*;
 use crate::ui::hud::inventory::*;
-use shared::world::{BlockId, ItemId, WorldSeed};
+use shared::world::{BlockId, ItemId, WorldSeed, WorldSpawn};
 
 use crate::network::{
+    clear_missing_chunks_system, clear_player_roster_system,
     establish_authenticated_connection_to_server, init_server_connection,
-    launch_local_server_system, network_failure_handler, poll_network_messages,
-    terminate_server_connection, upload_player_inputs_system, CurrentPlayerProfile, TargetServer,
-    TargetServerState, UnacknowledgedInputs,
+    launch_local_server_system, network_failure_handler, player_roster_update_system,
+    poll_network_messages, request_missing_chunks_system, server_announcement_system,
+    terminate_server_connection, upload_player_inputs_system, CurrentPlayerProfile, MissingChunks,
+    ServerBlocks, TargetServer, TargetServerState, UnacknowledgedInputs,
 };
 
 use crate::GameState;
@@ -57,6 +123,8 @@ use crate::GameState;
 pub struct PreLoadingCompletion {
     pub textures_loaded: bool,
     pub empty_handles_warning_emitted: bool,
+    /// Atlases packed so far, out of `ATLAS_COUNT`
+    pub atlases_packed: usize,
 }
 
 #[derive(Resource, Default)]
@@ -85,6 +153,10 @@ pub fn game_plugin(app: &mut App) {
         })
         .add_plugins(WaterPlugin)
         .insert_resource(WorldSeed(0))
+        .init_resource::<WorldSpawn>()
+        .init_resource::<ServerBlocks>()
+        .init_resource::<GameMode>()
+        .init_resource::<PlayerRoster>()
         .insert_resource(ClientTime(0))
         .insert_resource(FirstChunkReceived(false))
         .insert_resource(AmbientLight {
@@ -95,7 +167,15 @@ pub fn game_plugin(app: &mut App) {
         .insert_resource(PreLoadingCompletion {
             textures_loaded: false,
             empty_handles_warning_emitted: false,
+            atlases_packed: 0,
         })
+        .insert_resource(AtlasPackingTasks::default())
+        .insert_resource(ClientItemFrames::default())
+        .init_resource::<FishingBobbers>()
+        .init_resource::<ClientBoats>()
+        .init_resource::<ShownHeatmap>()
+        .init_resource::<PrefabLibrary>()
+        .init_resource::<FarTerrain>()
         .insert_resource(PreloadGate::default())
         .insert_resource(BlockDebugWireframeSettings { is_enabled: false })
         .insert_resource(WireframeConfig {
@@ -111,6 +191,11 @@ pub fn game_plugin(app: &mut App) {
         // Water rendering resources (decoupled from chunk system)
         .init_resource::<WaterEntities>()
         .init_resource::<WaterMaterialHandle>()
+        .init_resource::<FluidParticleRenderState>()
+        .init_resource::<BubbleColumns>()
+        .init_resource::<WaterAuditState>()
+        .init_resource::<WaterPrediction>()
+        .register_type::<ChunkMeshStats>()
         .insert_resource(AtlasHandles::<BlockId>::default())
         .insert_resource(AtlasHandles::<ItemId>::default())
         .insert_resource(RenderDistance { ..default() })
@@ -121,13 +206,22 @@ pub fn game_plugin(app: &mut App) {
         .insert_resource(Inventory::new())
         .init_resource::<CurrentPlayerProfile>()
         .init_resource::<ParticleAssets>()
+        .init_resource::<FishAssets>()
+        .init_resource::<BlockSounds>()
+        .init_resource::<EntitySounds>()
+        .init_resource::<AmbientSounds>()
+        .init_resource::<AmbienceTargets>()
+        .init_resource::<VoiceMutes>()
+        .init_resource::<ClientWeather>()
         .init_resource::<FoxFeetTargets>()
         .init_resource::<Animations>()
         .init_resource::<TargetedMob>()
+        .init_resource::<MobLeashes>()
         .init_resource::<PlayerTickInputsBuffer>()
         .init_resource::<CurrentFrameInputs>()
         .init_resource::<SyncTime>()
         .init_resource::<UnacknowledgedInputs>()
+        .init_resource::<MissingChunks>()
         .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND as f64))
         .add_event::<PreloadSignal>()
         .add_event::<WorldRenderRequestUpdateEvent>()
@@ -135,6 +229,27 @@ pub fn game_plugin(app: &mut App) {
         .add_event::<PlayerUpdateEvent>()
         .add_event::<MobUpdateEvent>()
         .add_event::<ItemStackUpdateEvent>()
+        .add_event::<FluidParticlesUpdate>()
+        .add_event::<WaterAuditReport>()
+        .add_event::<PlayerRosterUpdate>()
+        .add_event::<ServerAnnouncement>()
+        .add_event::<BlockSoundEvent>()
+        .add_event::<LocalBlockEditEvent>()
+        .add_event::<EntitySoundEvent>()
+        .add_event::<MobDespawnEvent>()
+        .add_event::<WeatherUpdate>()
+        .add_event::<AnimationEvent>()
+        .add_event::<WaystoneUpdate>()
+        .add_event::<WorldTimeSkip>()
+        .add_event::<ItemFrameUpdate>()
+        .add_event::<FarTerrainUpdate>()
+        .add_event::<FishingUpdate>()
+        .add_event::<BoatUpdate>()
+        .add_event::<HeatmapUpdate>()
+        .add_event::<DimensionChange>()
+        .add_event::<PrefabCommandEvent>()
+        .add_event::<UiOverlay>()
+        .init_resource::<UiOverlays>()
         .add_systems(
             OnEnter(GameState::PreGameLoading),
             (
@@ -146,15 +261,24 @@ pub fn game_plugin(app: &mut App) {
             )
                 .chain(),
         )
+        // The player can only be spawned once the connection has sent its spawn event
         .add_systems(
             Update,
             (
-                establish_authenticated_connection_to_server,
-                create_all_atlases,
-                emit_server_ready_signal,
-                advance_to_game_on_preload,
-                spawn_players_system,
-                update_server_connect_loading_screen,
+                (
+                    establish_authenticated_connection_to_server,
+                    spawn_players_system,
+                    emit_server_ready_signal,
+                )
+                    .chain()
+                    .in_set(ClientSet::NetPoll),
+                create_all_atlases.in_set(ClientSet::Mesh),
+                (
+                    advance_to_game_on_preload,
+                    update_server_connect_loading_screen,
+                )
+                    .chain()
+                    .in_set(ClientSet::Render),
             )
                 .run_if(in_state(GameState::PreGameLoading)),
         )
@@ -163,58 +287,136 @@ pub fn game_plugin(app: &mut App) {
             (
                 spawn_camera,
                 setup_main_lighting,
-                spawn_reticle,
                 setup_loading_overlay,
                 setup_hud,
                 setup_chat,
+                setup_player_list,
+                setup_emote_menu,
+                setup_waystone_menu,
                 setup_pause_menu,
             )
                 .chain(),
         )
         .add_systems(
             OnEnter(GameState::Game),
-            (setup_hotbar, setup_inventory).chain(),
+            (
+                setup_hotbar,
+                setup_inventory,
+                setup_creative_catalog,
+                setup_biome_overlay,
+                setup_noise_playground,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(GameState::Game),
+            (
+                setup_chunk_ghost,
+                setup_ambience,
+                setup_prefab_panel,
+                load_prefab_library,
+                check_block_textures,
+            ),
         )
-        .add_systems(OnEnter(GameState::Game), setup_chunk_ghost)
         .add_systems(
             Update,
             (
-                render_pause_menu,
-                render_chat,
-                render_inventory_hotbar,
-                set_ui_mode,
-                update_loading_overlay,
+                network_failure_handler,
+                (
+                    dimension_change_system,
+                    spawn_players_system,
+                    update_players_system,
+                )
+                    .chain(),
+                (
+                    spawn_mobs_system,
+                    (mob_despawn_system, mob_hurt_event_system, mob_hurt_system).chain(),
+                    update_mob_leashes_system,
+                )
+                    .chain(),
+                (stack_update_system, item_frame_update_system).chain(),
+                player_roster_update_system,
+                server_announcement_system,
+                waystone_effects_event_system,
+                weather_update_system,
+                fishing_update_system,
+                boat_update_system,
+                heatmap_update_system,
+                ui_overlay_update_system,
             )
+                .in_set(ClientSet::NetPoll)
                 .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             Update,
             (
-                render_distance_update_system,
-                lod_transition_system,
+                set_ui_mode,
                 first_and_third_person_view_system,
                 toggle_debug_system,
-                chunk_force_reload_system,
+                handle_mouse_system,
                 (
                     update_frame_inputs_system,
                     handle_block_interactions,
+                    predict_water_inflow_system,
+                    use_bucket_system,
+                    leave_boat_system,
                     player_movement_system,
                     camera_control_system,
+                    portal_entry_system,
                 )
                     .chain(),
-                fps_text_update_system,
-                coords_text_update_system,
-                biome_text_update_system,
-                total_blocks_text_update_system,
-                block_text_update_system,
-                time_text_update_system,
+                voice_capture_system.run_if(resource_exists::<VoiceChat>),
+                toggle_water_audit_system,
+                expire_water_predictions_system,
+            )
+                .in_set(ClientSet::Predict)
+                .run_if(in_state(GameState::Game)),
+        )
+        .add_systems(
+            Update,
+            (
+                render_distance_update_system,
+                lod_transition_system,
+                chunk_force_reload_system,
+                (far_terrain_update_system, far_terrain_visibility_system).chain(),
+                bubble_columns_scan_system,
+            )
+                .in_set(ClientSet::Mesh)
+                .run_if(in_state(GameState::Game)),
+        )
+        .add_systems(
+            Update,
+            (
+                (render_pause_menu, pause_sync_system).chain(),
+                render_chat,
+                render_inventory_hotbar,
+                render_creative_catalog,
+                emote_menu_system,
+                waystone_menu_system,
+                update_loading_overlay,
+                hud_widget_visibility_system,
+                (footstep_sounds_system, play_block_sounds_system).chain(),
+                (
+                    fps_text_update_system,
+                    coords_text_update_system,
+                    (
+                        biome_text_update_system,
+                        biome_overlay_update_system,
+                        noise_playground_system,
+                    ),
+                    total_blocks_text_update_system,
+                    block_text_update_system,
+                    time_text_update_system,
+                    net_stats_text_update_system,
+                ),
                 toggle_hud_system,
                 chunk_ghost_update_system,
                 raycast_debug_update_system,
                 toggle_wireframe_system,
-                handle_mouse_system,
                 update_celestial_bodies,
+                apply_water_quality_system.run_if(resource_changed::<GraphicsSettings>),
             )
+                .in_set(ClientSet::Render)
                 .run_if(in_state(GameState::Game)),
         )
         .add_systems(
@@ -223,8 +425,50 @@ pub fn game_plugin(app: &mut App) {
                 setup_fox_once_loaded,
                 simulate_particles,
                 update_targetted_mob_color,
-                stack_update_system,
+                item_frame_display_system,
+                player_list_update_system,
+                waystone_effects_system,
+                voice_playback_system,
+                (ambience_sampling_system, ambience_crossfade_system).chain(),
+                water_audit_text_update_system,
+                (
+                    fluid_particles_render_system,
+                    fluid_particles_billboard_system,
+                )
+                    .chain(),
+                bubble_columns_render_system,
+                (fishing_display_system, boat_display_system).chain(),
+                heatmap_display_system,
+                (
+                    prefab_command_system,
+                    prefab_thumbnail_system,
+                    prefab_panel_system,
+                    prefab_placement_system,
+                )
+                    .chain(),
+                play_entity_sounds_system,
+                draw_mob_leashes_system,
+                player_labels_system,
+                (player_animation_event_system, player_animation_system).chain(),
             )
+                .in_set(ClientSet::Render)
+                .run_if(in_state(GameState::Game)),
+        )
+        .add_hud_widget::<ReticleRoot, _>(RETICLE_WIDGET, reticle_system)
+        .add_hud_widget::<EffectsHudRoot, _>(EFFECTS_HUD_WIDGET, effects_hud_system)
+        .add_hud_widget::<HealthBarRoot, _>(HEALTH_BAR_WIDGET, health_bar_system)
+        .add_hud_widget::<AirBarRoot, _>(AIR_BAR_WIDGET, air_bar_system)
+        .add_hud_widget::<BossBarsRoot, _>(BOSS_BARS_WIDGET, boss_bars_system)
+        .add_hud_widget::<TitleRoot, _>(TITLE_WIDGET, title_system)
+        .add_hud_widget::<ActionBarRoot, _>(ACTION_BAR_WIDGET, action_bar_system)
+        // Overlays are spawned faded out
+        .add_systems(
+            Update,
+            overlay_fade_system
+                .after(boss_bars_system)
+                .after(title_system)
+                .after(action_bar_system)
+                .in_set(ClientSet::Render)
                 .run_if(in_state(GameState::Game)),
         )
         .add_observer(observe_on_step)
@@ -235,39 +479,61 @@ pub fn game_plugin(app: &mut App) {
                 // Water rendering runs after chunk meshing, listening to the same events
                 water_render_system,
                 water_cleanup_system,
+                chunk_water_stats_system.after(water_render_system),
             )
-                .run_if(in_state(GameState::Game)),
-        )
-        .add_systems(
-            Update,
-            (
-                network_failure_handler,
-                spawn_players_system,
-                update_players_system,
-                spawn_mobs_system,
-                player_labels_system,
-            )
+                .in_set(ClientSet::Mesh)
                 .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             PreUpdate,
-            pre_input_update_system.run_if(in_state(GameState::Game)),
+            pre_input_update_system
+                .in_set(ClientSet::Predict)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             FixedPreUpdate,
-            poll_network_messages.run_if(in_state(GameState::Game)),
+            poll_network_messages
+                .in_set(ClientSet::NetPoll)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             FixedUpdate,
-            (upload_player_inputs_system).run_if(in_state(GameState::Game)),
+            upload_player_inputs_system
+                .in_set(ClientSet::Predict)
+                .run_if(in_state(GameState::Game)),
+        )
+        .add_systems(
+            Update,
+            request_missing_chunks_system
+                .in_set(ClientSet::NetPoll)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             FixedPostUpdate,
-            time_update_system.run_if(in_state(GameState::Game)),
+            (time_skip_system, time_update_system)
+                .chain()
+                .in_set(ClientSet::NetPoll)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             OnExit(GameState::Game),
-            (clear_resources, terminate_server_connection).chain(),
+            (
+                clear_resources,
+                fluid_particles_cleanup_system,
+                bubble_columns_cleanup_system,
+                reset_water_audit_system,
+                clear_player_roster_system,
+                reset_client_weather_system,
+                clear_item_frames_system,
+                clear_fishing_bobbers_system,
+                clear_boats_system,
+                clear_heatmap_system,
+                clear_far_terrain_system,
+                clear_ui_overlays_system,
+                clear_missing_chunks_system,
+                terminate_server_connection,
+            )
+                .chain(),
         );
 }
 
@@ -19,8 +19,17 @@ pub enum GameAction {
     FlyDown,
     ToggleBlockWireframeDebugMode,
     ToggleRaycastDebugMode,
+    ToggleWaterAudit,
+    ToggleBiomeOverlay,
+    ToggleNoisePlayground,
     ToggleInventory,
+    ToggleCreativeCatalog,
+    TogglePrefabs,
+    PlacePrefab,
     OpenChat,
+    ShowPlayerList,
+    PushToTalk,
+    EmoteMenu,
     RenderDistanceMinus,
     RenderDistancePlus,
     ReloadChunks,
@@ -20,12 +20,8 @@ fn write_keybindings_to_path(key_map: &KeyMap, binds_path: &Path) -> Result<(),
         .with_separate_tuple_members(true)
         .with_enumerate_arrays(true);
 
-    let serialized = ron::ser::to_string_pretty(key_map, pretty_config).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("serialization failed: {e}"),
-        )
-    })?;
+    let serialized = ron::ser::to_string_pretty(key_map, pretty_config)
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
     if let Some(parent) = binds_path.parent() {
         fs::create_dir_all(parent)?;
     }
@@ -107,11 +103,20 @@ pub(crate) fn default_key_map() -> BTreeMap<GameAction, Vec<KeyCode>> {
     map.insert(GameAction::ToggleViewMode, vec![KeyCode::F5]);
     map.insert(GameAction::ToggleBlockWireframeDebugMode, vec![KeyCode::F6]);
     map.insert(GameAction::ToggleRaycastDebugMode, vec![KeyCode::F7]);
+    map.insert(GameAction::ToggleWaterAudit, vec![KeyCode::F8]);
+    map.insert(GameAction::ToggleBiomeOverlay, vec![KeyCode::F9]);
+    map.insert(GameAction::ToggleNoisePlayground, vec![KeyCode::F10]);
     map.insert(GameAction::ToggleFlyMode, vec![KeyCode::KeyF]);
     map.insert(GameAction::FlyUp, vec![KeyCode::Space]);
     map.insert(GameAction::FlyDown, vec![KeyCode::ShiftLeft]);
     map.insert(GameAction::ToggleInventory, vec![KeyCode::KeyE]);
+    map.insert(GameAction::ToggleCreativeCatalog, vec![KeyCode::KeyC]);
+    map.insert(GameAction::TogglePrefabs, vec![KeyCode::KeyB]);
+    map.insert(GameAction::PlacePrefab, vec![KeyCode::KeyX]);
     map.insert(GameAction::OpenChat, vec![KeyCode::KeyT]);
+    map.insert(GameAction::ShowPlayerList, vec![KeyCode::Tab]);
+    map.insert(GameAction::PushToTalk, vec![KeyCode::KeyV]);
+    map.insert(GameAction::EmoteMenu, vec![KeyCode::KeyG]);
     map.insert(GameAction::RenderDistanceMinus, vec![KeyCode::KeyO]);
     map.insert(GameAction::RenderDistancePlus, vec![KeyCode::KeyP]);
     map.insert(GameAction::ReloadChunks, vec![KeyCode::KeyR]);
@@ -1,3 +1,5 @@
+mod audio;
+mod benchmark;
 mod camera;
 mod constants;
 mod entities;
@@ -23,12 +25,21 @@ use bevy_inspector_egui::{bevy_egui::EguiPlugin, DefaultInspectorConfigPlugin};
 use clap::Parser;
 use constants::{TEXTURE_PATH_BASE, TEXTURE_PATH_CUSTOM};
 use input::{data::GameAction, keyboard::get_bindings};
-use menus::solo::SelectedWorld;
+use menus::{settings::graphics::get_graphics_settings, solo::SelectedWorld};
 use serde::{Deserialize, Serialize};
+use shared::sets::{configure_client_sets, ClientSet};
+use shared::world::WorldSeed;
 use shared::{get_game_folder_paths, SpecialFlag};
 use std::collections::BTreeMap;
 use ui::{
-    hud::debug::inspector::inspector_ui,
+    hud::{
+        debug::{
+            inspector::inspector_ui,
+            layout::get_debug_hud_layout,
+            snapshot::{capture_ecs_snapshot_system, ecs_snapshot_ui, EcsSnapshot},
+        },
+        toast,
+    },
     menus::{self, splash},
 };
 
@@ -54,6 +65,24 @@ struct Args {
 
     #[arg(short, long, help = "Player name to use for the game")]
     player_name: Option<String>,
+
+    #[arg(
+        long,
+        help = "Show a lightweight ECS browser, also available in release builds"
+    )]
+    ecs_snapshot: bool,
+
+    #[arg(
+        long,
+        help = "Enable proximity voice chat, using the default audio devices"
+    )]
+    voice: bool,
+
+    #[arg(
+        long,
+        help = "Fly through a fixed world and write a performance report, then exit"
+    )]
+    benchmark: bool,
 }
 
 #[derive(Component)]
@@ -74,6 +103,8 @@ pub enum GameState {
 #[derive(Event)]
 pub struct LoadWorldEvent {
     pub world_name: String,
+    /// Seed typed when the world was created, for a world that doesn't exist yet
+    pub seed: Option<WorldSeed>,
 }
 
 #[derive(Resource, Serialize, Deserialize)]
@@ -164,11 +195,28 @@ fn main() {
         enable_multipass_for_primary_context: false,
     })
     .add_plugins(DefaultInspectorConfigPlugin)
-    .add_systems(Update, inspector_ui);
+    .add_systems(Update, inspector_ui.in_set(ClientSet::Render));
+
+    if args.ecs_snapshot {
+        app.init_resource::<EcsSnapshot>()
+            .add_systems(Update, ecs_snapshot_ui.in_set(ClientSet::Render))
+            .add_systems(Last, capture_ecs_snapshot_system.in_set(ClientSet::Render));
+    }
+
+    if args.voice {
+        audio::setup_voice_chat(&mut app);
+    }
+
+    if args.benchmark {
+        app.add_plugins(benchmark::benchmark_plugin);
+    }
 
     app.add_event::<LoadWorldEvent>();
     network::add_base_netcode(&mut app);
+    configure_client_sets(&mut app);
     app.insert_resource(get_bindings(&game_folder_paths))
+        .insert_resource(get_graphics_settings(&game_folder_paths))
+        .insert_resource(get_debug_hud_layout(&game_folder_paths))
         .insert_resource(SelectedWorld::default())
         // Declare the game state, whose starting value is determined by the `Default` trait
         .insert_resource(ClientWorldMap { ..default() })
@@ -183,6 +231,12 @@ fn main() {
         .init_state::<GameState>()
         .enable_state_scoped_entities::<GameState>()
         // Adds the plugins for each state
-        .add_plugins((splash::splash_plugin, menus::menu_plugin, game::game_plugin))
+        .add_plugins((
+            splash::splash_plugin,
+            menus::menu_plugin,
+            game::game_plugin,
+            toast::toast_plugin,
+            shaders::shader_hot_reload_plugin,
+        ))
         .run();
 }
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use shared::messages::mob::MobDespawnEvent;
+use shared::players::{AnimationEvent, AnimationKind, AnimationTarget};
+
+use crate::audio::{EntitySoundEvent, EntitySoundKind};
+
+use super::MobRoot;
+
+/// Scale of a mob at the start of its hurt animation, relative to its normal scale
+const HURT_SQUASH: f32 = 0.8;
+
+/// Mob recoiling after being hit
+#[derive(Component, Debug)]
+pub struct MobHurt {
+    elapsed: f32,
+    scale: Vec3,
+}
+
+pub fn mob_despawn_system(
+    mut commands: Commands,
+    mut events: EventReader<MobDespawnEvent>,
+    mobs: Query<(Entity, &MobRoot)>,
+) {
+    for event in events.read() {
+        for (entity, mob) in mobs.iter() {
+            if mob.id == event.id {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub fn mob_hurt_event_system(
+    mut commands: Commands,
+    mut events: EventReader<AnimationEvent>,
+    mobs: Query<(Entity, &MobRoot, &Transform, Option<&MobHurt>)>,
+    mut ev_entity_sound: EventWriter<EntitySoundEvent>,
+) {
+    for event in events.read() {
+        let (AnimationTarget::Mob(id), AnimationKind::Hurt) = (event.target, event.kind) else {
+            continue;
+        };
+        let Some((entity, _, transform, current)) = mobs.iter().find(|(_, mob, _, _)| mob.id == id)
+        else {
+            continue;
+        };
+
+        commands.entity(entity).insert(MobHurt {
+            elapsed: 0.0,
+            scale: current.map_or(transform.scale, |hurt| hurt.scale),
+        });
+        ev_entity_sound.write(EntitySoundEvent {
+            kind: EntitySoundKind::Hurt,
+            position: transform.translation,
+        });
+    }
+}
+
+/// Squashes hurt mobs, springing back to their normal size
+pub fn mob_hurt_system(
+    mut commands: Commands,
+    mut mobs: Query<(Entity, &mut Transform, &mut MobHurt)>,
+    time: Res<Time>,
+) {
+    let duration = AnimationKind::Hurt.duration().unwrap_or_default();
+    for (entity, mut transform, mut hurt) in mobs.iter_mut() {
+        hurt.elapsed += time.delta_secs();
+        let progress = hurt.elapsed / duration;
+        if progress >= 1.0 {
+            transform.scale = hurt.scale;
+            commands.entity(entity).remove::<MobHurt>();
+            continue;
+        }
+        transform.scale = hurt.scale * (HURT_SQUASH + (1.0 - HURT_SQUASH) * progress);
+    }
+}
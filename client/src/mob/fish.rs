@@ -0,0 +1,70 @@
+//! Fish have no model yet, they are drawn as a small body with a tail fin.
+
+use bevy::{pbr::NotShadowCaster, prelude::*};
+
+use super::{MobMarker, MobRoot};
+
+#[derive(Resource)]
+pub struct FishAssets {
+    body: Handle<Mesh>,
+    tail: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for FishAssets {
+    fn from_world(world: &mut World) -> Self {
+        let (body, tail) = {
+            let mut meshes = world.resource_mut::<Assets<Mesh>>();
+            (
+                meshes.add(Cuboid::new(0.15, 0.3, 0.5)),
+                meshes.add(Cuboid::new(0.05, 0.25, 0.15)),
+            )
+        };
+        Self {
+            body,
+            tail,
+            material: world
+                .resource_mut::<Assets<StandardMaterial>>()
+                .add(StandardMaterial {
+                    base_color: Color::srgb(0.9, 0.5, 0.2),
+                    perceptual_roughness: 0.4,
+                    ..Default::default()
+                }),
+        }
+    }
+}
+
+pub fn setup_fish(id: u128, spawn_pos: Vec3, commands: &mut Commands, assets: &FishAssets) {
+    let name = "Fish".to_string();
+
+    let fish = commands
+        .spawn((
+            Transform::from_translation(spawn_pos),
+            Visibility::default(),
+            MobRoot {
+                name: name.clone(),
+                id,
+            },
+            MobMarker {
+                name: name.clone(),
+                id,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Mesh3d(assets.body.clone()),
+                MeshMaterial3d(assets.material.clone()),
+                NotShadowCaster,
+            ));
+            // Mobs face +Z, so the tail goes behind the body
+            parent.spawn((
+                Mesh3d(assets.tail.clone()),
+                MeshMaterial3d(assets.material.clone()),
+                Transform::from_xyz(0.0, 0.0, -0.3),
+                NotShadowCaster,
+            ));
+        })
+        .id();
+
+    info!("Spawned fish: {:?}", fish);
+}
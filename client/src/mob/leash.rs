@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use shared::messages::mob::MobUpdateEvent;
+use shared::players::Player;
+use shared::world::{catenary_points, fence_knot_position, LeashAnchor, MobId, LEASH_LENGTH};
+
+use super::MobRoot;
+
+const LEASH_COLOR: Color = Color::srgb(0.55, 0.4, 0.25);
+const LEASH_SEGMENTS: usize = 16;
+
+/// Anchors of the leashed mobs, as last sent by the server
+#[derive(Resource, Default, Debug)]
+pub struct MobLeashes {
+    anchors: HashMap<MobId, LeashAnchor>,
+}
+
+pub fn update_mob_leashes_system(
+    mut ev_update: EventReader<MobUpdateEvent>,
+    mut leashes: ResMut<MobLeashes>,
+) {
+    for event in ev_update.read() {
+        match event.mob.leash {
+            Some(anchor) => {
+                leashes.anchors.insert(event.id, anchor);
+            }
+            None => {
+                leashes.anchors.remove(&event.id);
+            }
+        }
+    }
+}
+
+/// Draws leads as ropes hanging between the mobs and their anchor
+pub fn draw_mob_leashes_system(
+    mut gizmos: Gizmos,
+    leashes: Res<MobLeashes>,
+    mobs: Query<(&MobRoot, &Transform)>,
+    players: Query<&Player>,
+) {
+    for (mob, transform) in mobs.iter() {
+        let Some(anchor) = leashes.anchors.get(&mob.id) else {
+            continue;
+        };
+        let anchor_position = match anchor {
+            LeashAnchor::Player(id) => players
+                .iter()
+                .find(|player| player.id == *id)
+                .map(|player| player.position),
+            LeashAnchor::Fence(position) => Some(fence_knot_position(*position)),
+        };
+        let Some(anchor_position) = anchor_position else {
+            continue;
+        };
+
+        gizmos.linestrip(
+            catenary_points(
+                transform.translation,
+                anchor_position,
+                LEASH_LENGTH,
+                LEASH_SEGMENTS,
+            ),
+            LEASH_COLOR,
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use bevy::prelude::*;
 
+mod combat;
+mod fish;
 mod fox;
+mod leash;
 mod spawn;
 
+pub use combat::*;
+pub use fish::*;
 pub use fox::*;
+pub use leash::*;
 pub use spawn::*;
 
 #[derive(Debug, Component, Clone)]
@@ -1,15 +1,22 @@
 use bevy::prelude::*;
 use shared::messages::mob::MobUpdateEvent;
+use shared::world::MobKind;
 
-use crate::{mob::setup_fox, player::CurrentPlayerMarker, world::RenderDistance};
+use crate::{
+    mob::{setup_fish, setup_fox, FishAssets},
+    player::CurrentPlayerMarker,
+    world::RenderDistance,
+};
 
 use super::MobRoot;
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_mobs_system(
     mut ev_update: EventReader<MobUpdateEvent>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
+    fish_assets: Res<FishAssets>,
     mut mobs: Query<(Entity, &MobRoot, &mut Transform), Without<CurrentPlayerMarker>>,
     player_pos: Query<&Transform, With<CurrentPlayerMarker>>,
     render_distance: Res<RenderDistance>,
@@ -29,11 +36,19 @@ pub fn spawn_mobs_system(
             }
         }
 
-        if event.mob.kind == shared::world::MobKind::Fox
-            && event.mob.position.distance(player_pos) < render_distance.distance as f32 * 5.0
-        {
-            info!("Spawning fox at {:?}", position);
-            setup_fox(id, position, &mut commands, &asset_server, &mut graphs);
+        if event.mob.position.distance(player_pos) >= render_distance.distance as f32 * 5.0 {
+            continue;
+        }
+
+        match event.mob.kind {
+            MobKind::Fox => {
+                info!("Spawning fox at {:?}", position);
+                setup_fox(id, position, &mut commands, &asset_server, &mut graphs);
+            }
+            MobKind::Fish => {
+                info!("Spawning fish at {:?}", position);
+                setup_fish(id, position, &mut commands, &fish_assets);
+            }
         }
     }
 
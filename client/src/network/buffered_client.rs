@@ -21,6 +21,7 @@ impl CurrentFrameInputsExt for CurrentFrameInputs {
             inputs: HashSet::default(),
             camera: Transform::default(),
             position: Vec3::default(),
+            boat: None,
             hotbar_slot: 0,
             view_mode: ViewMode::default(),
         };
@@ -34,6 +35,9 @@ impl CurrentFrameInputsExt for CurrentFrameInputs {
 pub struct SyncTime {
     pub last_time_ms: u64,
     pub curr_time_ms: u64,
+    /// Time added by each frame instead of the clock, so that the benchmark flies
+    /// the same path whatever the frame rate
+    pub fixed_step_ms: Option<u64>,
 }
 
 impl Default for SyncTime {
@@ -46,6 +50,7 @@ impl Default for SyncTime {
         Self {
             last_time_ms: current_time_ms,
             curr_time_ms: current_time_ms,
+            fixed_step_ms: None,
         }
     }
 }
@@ -62,6 +67,10 @@ impl SyncTimeExt for SyncTime {
 
     fn advance(&mut self) {
         self.last_time_ms = self.curr_time_ms;
+        if let Some(step) = self.fixed_step_ms {
+            self.curr_time_ms += step;
+            return;
+        }
         self.curr_time_ms = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
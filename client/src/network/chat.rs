@@ -1,5 +1,8 @@
 use bevy::prelude::*;
-use shared::messages::ChatConversation;
+use shared::messages::{ChatConversation, FullChatMessage, ServerAnnouncement};
+
+/// Author shown for server announcements in the chat
+const ANNOUNCEMENT_AUTHOR: &str = "Server";
 
 #[derive(Resource, Default, Debug)]
 pub struct CachedChatConversation {
@@ -21,3 +24,29 @@ pub fn _update_cached_chat_state(
 
     trace!("new CachedChatConversation: {:?}", &chat_state);
 }
+
+/// Adds a message from the server to the chat
+pub fn push_announcement(chat: &mut CachedChatConversation, content: String) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    chat.data
+        .get_or_insert_with(ChatConversation::default)
+        .messages
+        .push(FullChatMessage {
+            author: ANNOUNCEMENT_AUTHOR.to_string(),
+            content,
+            timestamp,
+        });
+}
+
+pub fn server_announcement_system(
+    mut announcements: EventReader<ServerAnnouncement>,
+    mut chat: ResMut<CachedChatConversation>,
+) {
+    for announcement in announcements.read() {
+        push_announcement(&mut chat, announcement.content.clone());
+    }
+}
@@ -0,0 +1,89 @@
+//! Chunks the server should have sent but that never arrived, e.g. lost with a
+//! dropped message, leave holes in the world. The client notices the chunks of
+//! the render distance it still lacks after a while and asks for them again.
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::ClientToServerMessage;
+use shared::world::world_position_to_chunk_position;
+use std::collections::HashMap;
+
+use super::SendGameMessageExtension;
+use crate::player::CurrentPlayerMarker;
+use crate::world::{ClientWorldMap, RenderDistance};
+
+/// Time between two looks for missing chunks, in seconds
+const GAP_CHECK_INTERVAL_SECS: f32 = 2.0;
+
+/// Time a chunk can be missing before being asked again, letting the server
+/// send the chunks it is due first, in seconds
+const MISSING_CHUNK_TIMEOUT_SECS: f32 = 5.0;
+
+/// Most chunks asked in a single request, the nearest first
+const MAX_CHUNKS_PER_REQUEST: usize = 32;
+
+#[derive(Resource, Default, Debug)]
+pub struct MissingChunks {
+    /// Time at which each missing chunk was first noticed, or last asked for
+    since: HashMap<IVec3, f32>,
+    next_check: f32,
+}
+
+pub fn request_missing_chunks_system(
+    mut client: ResMut<RenetClient>,
+    mut missing: ResMut<MissingChunks>,
+    world_map: Res<ClientWorldMap>,
+    render_distance: Res<RenderDistance>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    if now < missing.next_check || client.is_disconnected() {
+        return;
+    }
+    missing.next_check = now + GAP_CHECK_INTERVAL_SECS;
+
+    let Ok(transform) = player.single() else {
+        return;
+    };
+    let player_chunk = world_position_to_chunk_position(transform.translation);
+    let radius = render_distance.server_distance;
+
+    let mut since = HashMap::new();
+    let mut overdue = vec![];
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            for z in -radius..=radius {
+                let offset = IVec3::new(x, y, z);
+                let chunk_pos = player_chunk + offset;
+                if offset.length_squared() > radius * radius
+                    || world_map.map.contains_key(&chunk_pos)
+                {
+                    continue;
+                }
+
+                let noticed_at = missing.since.get(&chunk_pos).copied().unwrap_or(now);
+                if now - noticed_at >= MISSING_CHUNK_TIMEOUT_SECS {
+                    overdue.push(chunk_pos);
+                }
+                since.insert(chunk_pos, noticed_at);
+            }
+        }
+    }
+
+    overdue.sort_by_key(|chunk_pos| (*chunk_pos - player_chunk).length_squared());
+    overdue.truncate(MAX_CHUNKS_PER_REQUEST);
+    for chunk_pos in overdue.iter() {
+        since.insert(*chunk_pos, now);
+    }
+    missing.since = since;
+
+    if !overdue.is_empty() {
+        debug!("Asking again for {} missing chunks", overdue.len());
+        client.send_game_message(ClientToServerMessage::RequestChunks(overdue));
+    }
+}
+
+pub fn clear_missing_chunks_system(mut missing: ResMut<MissingChunks>) {
+    *missing = MissingChunks::default();
+}
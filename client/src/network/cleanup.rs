@@ -1,4 +1,4 @@
-use crate::network::{SendGameMessageExtension, TargetServer, TargetServerState};
+use crate::network::{SendGameMessageExtension, TargetServer};
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 use shared::messages::ClientToServerMessage;
@@ -14,10 +14,7 @@ pub fn terminate_server_connection(
     info!("Terminating server connection");
     client.send_game_message(ClientToServerMessage::Exit);
 
-    target.address = None;
-    target.username = None;
-    target.session_token = None;
-    target.state = TargetServerState::Initial;
+    target.reset();
 
     unacknowledged_inputs.0.clear();
     current_frame.buffer.clear();
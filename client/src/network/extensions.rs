@@ -13,9 +13,9 @@ pub trait SendGameMessageExtension {
         &mut self,
         channel: u8,
     ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
-    fn receive_game_message_except_channel(
+    fn receive_game_message_except_channels(
         &mut self,
-        channel: u8,
+        channels: &[u8],
     ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
     // fn receive_game_message(&mut self) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
 }
@@ -49,13 +49,13 @@ impl SendGameMessageExtension for RenetClient {
         None
     }
 
-    fn receive_game_message_except_channel(
+    fn receive_game_message_except_channels(
         &mut self,
-        excluded_channel_id: u8,
+        excluded_channel_ids: &[u8],
     ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>> {
         let channels = get_customized_server_to_client_channels();
         for channel in channels {
-            if channel.channel_id == excluded_channel_id {
+            if excluded_channel_ids.contains(&channel.channel_id) {
                 continue;
             }
             let res = self.receive_game_message_by_channel(channel.channel_id);
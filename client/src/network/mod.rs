@@ -1,14 +1,18 @@
 pub mod buffered_client;
 mod chat;
+mod chunk_requests;
 mod cleanup;
 pub mod extensions;
 mod inputs;
+mod roster;
 pub mod save;
 mod setup;
 mod world;
 
 pub use chat::*;
+pub use chunk_requests::*;
 pub use cleanup::*;
 pub use extensions::SendGameMessageExtension;
 pub use inputs::*;
+pub use roster::*;
 pub use setup::*;
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use shared::players::{PlayerRoster, PlayerRosterUpdate};
+
+use super::{push_announcement, CachedChatConversation, CurrentPlayerProfile};
+
+pub fn player_roster_update_system(
+    mut updates: EventReader<PlayerRosterUpdate>,
+    mut roster: ResMut<PlayerRoster>,
+    mut chat: ResMut<CachedChatConversation>,
+    profile: Res<CurrentPlayerProfile>,
+) {
+    for update in updates.read() {
+        match update {
+            PlayerRosterUpdate::Snapshot(_) => {}
+            PlayerRosterUpdate::Joined(entry) => {
+                if entry.id != profile.id && roster.name(entry.id).is_none() {
+                    push_announcement(&mut chat, format!("{} joined the game", entry.name));
+                }
+            }
+            PlayerRosterUpdate::Left(entry) => {
+                if entry.id != profile.id {
+                    push_announcement(&mut chat, format!("{} left the game", entry.name));
+                }
+            }
+        }
+        roster.apply(update);
+    }
+}
+
+/// The roster belongs to the server we were connected to
+pub fn clear_player_roster_system(mut roster: ResMut<PlayerRoster>) {
+    roster.clear();
+}
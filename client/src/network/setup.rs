@@ -1,30 +1,39 @@
 use bevy::prelude::*;
-use bevy_renet::netcode::{
-    ClientAuthentication, NetcodeClientPlugin, NetcodeClientTransport, NetcodeTransportError,
-};
+use bevy_renet::netcode::{ClientAuthentication, ConnectToken, NetcodeClientTransport};
 use bevy_renet::{renet::RenetClient, RenetClientPlugin};
 use rand::Rng;
 use shared::constants::{
-    DEFAULT_RENDER_DISTANCE, NETCODE_CLIENT_TRANSPORT_ERROR, SOCKET_BIND_ERROR,
-    SOCKET_LOCAL_ADDR_ERROR, TARGET_SERVER_ADDR_ERROR, UNIX_EPOCH_TIME_ERROR,
-    USERNAME_MISSING_AUTHENTICATED_ERROR,
+    DEFAULT_RENDER_DISTANCE, HANDSHAKE_ERROR, NETCODE_CLIENT_TRANSPORT_ERROR, SOCKET_BIND_ERROR,
+    TARGET_SERVER_ADDR_ERROR, UNIX_EPOCH_TIME_ERROR, USERNAME_MISSING_AUTHENTICATED_ERROR,
+    WEBSOCKET_CONNECT_ERROR,
 };
-use shared::messages::mob::MobUpdateEvent;
+use shared::fluid::{FluidMode, FluidParticlesUpdate, DEFAULT_EVAPORATION_VOLUME};
+use shared::messages::mob::{MobDespawnEvent, MobUpdateEvent};
+use shared::players::{AnimationEvent, GameMode, PlayerRosterUpdate};
+use shared::transport::memory::{memory_transport, MemoryClientTransport};
+use shared::transport::websocket::WebSocketClientTransport;
+use shared::transport::{ClientTransportPlugin, TransportError, TransportProtocol};
+use shared::water::WaterAuditReport;
+use shared::world::{block_registry_hash, unknown_blocks, BlockId};
 use shared::{get_shared_renet_config, GameServerConfig, STC_AUTH_CHANNEL};
 
 use crate::menus::solo::SelectedWorld;
+use crate::menus::ConnectionError;
 use crate::network::world::update_world_from_network;
 use crate::network::CachedChatConversation;
 use crate::world::time::ClientTime;
-use crate::world::WorldRenderRequestUpdateEvent;
-use crate::PlayerNameSupplied;
+use crate::world::water_prediction::WaterPrediction;
+use crate::world::{RenderDistance, WorldRenderRequestUpdateEvent};
+use crate::{GameState, PlayerNameSupplied};
 use shared::messages::{
-    AuthRegisterRequest, ItemStackUpdateEvent, PlayerId, PlayerSpawnEvent, PlayerUpdateEvent,
-    ServerToClientMessage,
+    AuthRegisterRequest, BoatUpdate, ClientToServerMessage, DimensionChange, FarTerrainUpdate,
+    FishingUpdate, HeatmapUpdate, ItemFrameUpdate, ItemStackUpdateEvent, PlayerId,
+    PlayerSpawnEvent, PlayerUpdateEvent, ServerAnnouncement, ServerToClientMessage, UiOverlay,
+    WaystoneUpdate, WeatherUpdate, WorldTimeSkip,
 };
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::{net::UdpSocket, thread, time::SystemTime};
 
 use crate::world::ClientWorldMap;
@@ -79,11 +88,31 @@ impl FromWorld for CurrentPlayerProfile {
 #[derive(Resource, Debug, Clone)]
 pub struct TargetServer {
     pub address: Option<SocketAddr>,
+    /// How to reach `address`
+    pub protocol: TransportProtocol,
     pub username: Option<String>,
     pub session_token: Option<u64>,
+    /// Handed out by the handshake with a dedicated server
+    pub connect_token: Option<ConnectToken>,
     pub state: TargetServerState,
 }
 
+impl TargetServer {
+    /// Forgets the server, to connect to another one
+    pub fn reset(&mut self) {
+        self.address = None;
+        self.protocol = TransportProtocol::Udp;
+        self.username = None;
+        self.session_token = None;
+        self.connect_token = None;
+        self.state = TargetServerState::Initial;
+    }
+}
+
+/// Blocks the server may send, which need a texture
+#[derive(Resource, Default, Debug)]
+pub struct ServerBlocks(pub Vec<BlockId>);
+
 pub fn add_base_netcode(app: &mut App) {
     app.add_plugins(RenetClientPlugin);
 
@@ -91,21 +120,28 @@ pub fn add_base_netcode(app: &mut App) {
     app.insert_resource(client);
 
     // Setup the transport layer
-    app.add_plugins(NetcodeClientPlugin);
+    app.add_plugins(ClientTransportPlugin::<NetcodeClientTransport>::default());
+    app.add_plugins(ClientTransportPlugin::<WebSocketClientTransport>::default());
+    // Solo games talk to their integrated server without a socket
+    app.add_plugins(ClientTransportPlugin::<MemoryClientTransport>::default());
 
     // TODO: change username
     app.insert_resource(TargetServer {
         address: None,
+        protocol: TransportProtocol::Udp,
         username: None,
         session_token: None,
+        connect_token: None,
         state: TargetServerState::Initial,
     });
 }
 
 pub fn launch_local_server_system(
-    mut target: ResMut<TargetServer>,
+    mut commands: Commands,
+    target: Res<TargetServer>,
     selected_world: Res<SelectedWorld>,
     paths: Res<GameFolderPaths>,
+    current_player_id: Res<CurrentPlayerProfile>,
 ) {
     if target.address.is_some() {
         debug!("Skipping launch local server");
@@ -115,37 +151,33 @@ pub fn launch_local_server_system(
     if let Some(world_name) = &selected_world.name {
         info!("Launching local server with world: {}", world_name);
 
-        let socket = match server::acquire_local_ephemeral_udp_socket(IpAddr::V4(Ipv4Addr::new(
-            127, 0, 0, 1,
-        ))) {
-            Ok(socket) => socket,
-            Err(err) => {
-                error!("{}: {err}", SOCKET_BIND_ERROR);
-                return;
-            }
-        };
-        let Ok(addr) = socket.local_addr() else {
-            error!("{}", SOCKET_LOCAL_ADDR_ERROR);
-            return;
-        };
-        debug!("Obtained UDP socket: {}", addr);
+        let (server_transport, client_transport) = memory_transport(current_player_id.id);
 
         let world_name_clone = world_name.clone();
+        let world_preset = selected_world.preset;
+        let world_seed = selected_world.seed;
         let cloned_paths = paths.clone();
 
         thread::spawn(move || {
             server::init(
-                socket,
+                vec![server::ServerTransportKind::Memory(server_transport)],
                 GameServerConfig {
                     world_name: world_name_clone,
                     is_solo: true,
                     broadcast_render_distance: DEFAULT_RENDER_DISTANCE,
+                    fluid_particles: false,
+                    fluid_mode: FluidMode::default(),
+                    evaporation_volume: DEFAULT_EVAPORATION_VOLUME,
+                    // The solo player owns the world
+                    game_mode: GameMode::Creative,
+                    world_preset,
+                    world_seed,
                 },
                 cloned_paths,
             );
         });
 
-        target.address = Some(addr);
+        commands.insert_resource(client_transport);
     } else {
         error!("Error: No world selected. Unable to launch the server.");
     }
@@ -156,52 +188,131 @@ pub fn poll_network_messages(
     // mut chat_state: ResMut<CachedChatConversation>,
     // client_time: ResMut<ClientTime>,
     mut world: ResMut<ClientWorldMap>,
+    mut water_prediction: ResMut<WaterPrediction>,
     mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
     mut ev_player_spawn: EventWriter<PlayerSpawnEvent>,
     mut ev_mob_update: EventWriter<MobUpdateEvent>,
+    mut ev_mob_despawn: EventWriter<MobDespawnEvent>,
     mut ev_item_stacks_update: EventWriter<ItemStackUpdateEvent>,
     mut ev_player_update: EventWriter<PlayerUpdateEvent>,
+    (
+        mut ev_fluid_particles,
+        mut ev_water_audit,
+        mut ev_player_roster,
+        mut ev_announcement,
+        mut ev_weather,
+        mut ev_animation,
+        mut ev_waystone,
+        mut ev_time_skip,
+        mut ev_item_frame,
+        mut ev_far_terrain,
+        mut ev_fishing,
+        mut ev_boats,
+        mut ev_heatmap,
+        mut ev_dimension_change,
+        mut ev_ui_overlay,
+    ): (
+        EventWriter<FluidParticlesUpdate>,
+        EventWriter<WaterAuditReport>,
+        EventWriter<PlayerRosterUpdate>,
+        EventWriter<ServerAnnouncement>,
+        EventWriter<WeatherUpdate>,
+        EventWriter<AnimationEvent>,
+        EventWriter<WaystoneUpdate>,
+        EventWriter<WorldTimeSkip>,
+        EventWriter<ItemFrameUpdate>,
+        EventWriter<FarTerrainUpdate>,
+        EventWriter<FishingUpdate>,
+        EventWriter<BoatUpdate>,
+        EventWriter<HeatmapUpdate>,
+        EventWriter<DimensionChange>,
+        EventWriter<UiOverlay>,
+    ),
 ) {
     // poll_reliable_ordered_messages(&mut client, &mut chat_state);
     update_world_from_network(
         &mut client,
         &mut world,
+        &mut water_prediction,
         &mut ev_render,
         &mut ev_player_spawn,
         &mut ev_mob_update,
+        &mut ev_mob_despawn,
         &mut ev_item_stacks_update,
         &mut ev_player_update,
+        &mut ev_fluid_particles,
+        &mut ev_water_audit,
+        &mut ev_player_roster,
+        &mut ev_announcement,
+        &mut ev_weather,
+        &mut ev_animation,
+        &mut ev_waystone,
+        &mut ev_time_skip,
+        &mut ev_item_frame,
+        &mut ev_far_terrain,
+        &mut ev_fishing,
+        &mut ev_boats,
+        &mut ev_heatmap,
+        &mut ev_dimension_change,
+        &mut ev_ui_overlay,
     );
 }
 
-pub fn init_server_connection(
-    mut commands: Commands,
-    target: Res<TargetServer>,
-    current_player_id: Res<CurrentPlayerProfile>,
-) {
-    let Some(addr) = target.address else {
-        error!("{TARGET_SERVER_ADDR_ERROR}");
-        return;
-    };
-    let id = current_player_id.into_inner().id;
+pub fn init_server_connection(mut commands: Commands, target: Res<TargetServer>) {
+    let address = target.address;
+    let protocol = target.protocol;
+    let connect_token = target.connect_token.clone();
     commands.queue(move |world: &mut World| {
         world.remove_resource::<RenetClient>();
         world.remove_resource::<NetcodeClientTransport>();
+        world.remove_resource::<WebSocketClientTransport>();
         world.remove_resource::<CachedChatConversation>();
 
-        let authentication = ClientAuthentication::Unsecure {
-            server_addr: addr,
-            client_id: id,
-            user_data: None,
-            protocol_id: shared::PROTOCOL_ID,
+        let Some(addr) = address else {
+            // Launched along with the integrated server
+            if !world.contains_resource::<MemoryClientTransport>() {
+                error!("{TARGET_SERVER_ADDR_ERROR}");
+                return;
+            }
+            info!("Connecting to the integrated server");
+            world.insert_resource(MemoryClientTransport::new_client(get_shared_renet_config()));
+            world.insert_resource(CachedChatConversation { ..default() });
+            return;
         };
+        world.remove_resource::<MemoryClientTransport>();
 
-        info!(
-            "Attempting to connect to: {} with data {:?}",
-            addr, authentication
-        );
+        let Some(connect_token) = connect_token else {
+            error!("{HANDSHAKE_ERROR}: no connect token for {}", addr);
+            return;
+        };
 
-        let socket = match UdpSocket::bind("0.0.0.0:0") {
+        if protocol == TransportProtocol::WebSocket {
+            info!("Attempting to connect to: ws://{}", addr);
+            let transport = match WebSocketClientTransport::connect(addr, &connect_token) {
+                Ok(transport) => transport,
+                Err(err) => {
+                    error!("{}: {err}", WEBSOCKET_CONNECT_ERROR);
+                    return;
+                }
+            };
+            world.insert_resource(RenetClient::new(get_shared_renet_config()));
+            world.insert_resource(transport);
+            world.insert_resource(CachedChatConversation { ..default() });
+            info!("Network subsystem initialized");
+            return;
+        }
+
+        let authentication = ClientAuthentication::Secure { connect_token };
+
+        info!("Attempting to connect to: {}", addr);
+
+        // a socket of the IP version of the server
+        let local_addr: SocketAddr = if addr.is_ipv6() {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = match UdpSocket::bind(local_addr) {
             Ok(socket) => socket,
             Err(err) => {
                 error!("{}: {err}", SOCKET_BIND_ERROR);
@@ -233,7 +344,7 @@ pub fn init_server_connection(
     })
 }
 
-pub fn network_failure_handler(mut renet_error: EventReader<NetcodeTransportError>) {
+pub fn network_failure_handler(mut renet_error: EventReader<TransportError>) {
     for e in renet_error.read() {
         error!("network error: {}", e);
     }
@@ -246,6 +357,13 @@ pub fn establish_authenticated_connection_to_server(
     mut ev_spawn: EventWriter<PlayerSpawnEvent>,
     mut client_time: ResMut<ClientTime>,
     mut world_seed: ResMut<shared::world::WorldSeed>,
+    mut world_spawn: ResMut<shared::world::WorldSpawn>,
+    mut game_mode: ResMut<GameMode>,
+    mut render_distance: ResMut<RenderDistance>,
+    mut water_prediction: ResMut<WaterPrediction>,
+    mut server_blocks: ResMut<ServerBlocks>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
 ) {
     if target.session_token.is_some() {
         let Some(username) = target.username.as_ref() else {
@@ -265,6 +383,7 @@ pub fn establish_authenticated_connection_to_server(
 
         let auth_msg = AuthRegisterRequest {
             username: username.clone(),
+            block_registry_hash: block_registry_hash(),
         };
         info!("Sending auth request: {:?}", auth_msg);
         client.send_game_message(auth_msg.into());
@@ -274,12 +393,40 @@ pub fn establish_authenticated_connection_to_server(
     while let Some(Ok(message)) = client.receive_game_message_by_channel(STC_AUTH_CHANNEL) {
         match message {
             ServerToClientMessage::AuthRegisterResponse(message) => {
+                server_blocks.0 = match message.block_registry {
+                    None => BlockId::ALL.to_vec(),
+                    Some(registry) => {
+                        let unknown = unknown_blocks(&registry);
+                        if !unknown.is_empty() {
+                            error!(
+                                "The server has blocks this client doesn't know: {}",
+                                unknown.join(", ")
+                            );
+                            client.send_game_message(ClientToServerMessage::Exit);
+                            target.reset();
+                            commands.insert_resource(ConnectionError {
+                                reason:
+                                    "The server has blocks this version of the game doesn't know"
+                                        .into(),
+                                details: unknown,
+                            });
+                            game_state.set(GameState::Menu);
+                            return;
+                        }
+                        // The blocks added by this build are never sent by the server
+                        BlockId::ALL[..registry.len()].to_vec()
+                    }
+                };
                 target.username = Some(message.username);
                 target.session_token = Some(message.session_token);
                 target.state = TargetServerState::ConnectionEstablished;
                 client_time.0 = message.tick;
                 world_seed.0 = message.world_seed;
                 info!("Received world seed: {}", message.world_seed);
+                world_spawn.0 = message.world_spawn;
+                *game_mode = message.game_mode;
+                render_distance.server_distance = message.render_distance;
+                water_prediction.fluid_mode = message.fluid_mode;
                 // TODO: handle clock sync using the timestamp_ms field
                 // it will become very important if the lantency is high
                 for player in message.players {
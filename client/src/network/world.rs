@@ -4,28 +4,54 @@ use std::time::Instant;
 use crate::world::ClientChunk;
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
+use shared::fluid::FluidParticlesUpdate;
 use shared::messages::{
-    mob::MobUpdateEvent, ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent,
-    ServerToClientMessage,
+    mob::{MobDespawnEvent, MobUpdateEvent},
+    BoatUpdate, DimensionChange, FarTerrainUpdate, FishingUpdate, HeatmapUpdate, ItemFrameUpdate,
+    ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent, ServerAnnouncement,
+    ServerToClientMessage, UiOverlay, WaystoneUpdate, WeatherUpdate, WorldTimeSkip,
 };
-use shared::STC_AUTH_CHANNEL;
+use shared::players::{AnimationEvent, PlayerRosterUpdate};
+use shared::water::WaterAuditReport;
+use shared::{STC_AUTH_CHANNEL, STC_VOICE_CHANNEL};
 
 use crate::world::ClientWorldMap;
 
+use crate::world::water_prediction::WaterPrediction;
 use crate::world::WorldRenderRequestUpdateEvent;
 
 use super::SendGameMessageExtension;
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_world_from_network(
     client: &mut ResMut<RenetClient>,
     world: &mut ResMut<ClientWorldMap>,
+    water_prediction: &mut ResMut<WaterPrediction>,
     ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
     ev_player_spawn: &mut EventWriter<PlayerSpawnEvent>,
     ev_mob_update: &mut EventWriter<MobUpdateEvent>,
+    ev_mob_despawn: &mut EventWriter<MobDespawnEvent>,
     ev_item_stacks_update: &mut EventWriter<ItemStackUpdateEvent>,
     ev_player_update: &mut EventWriter<PlayerUpdateEvent>,
+    ev_fluid_particles: &mut EventWriter<FluidParticlesUpdate>,
+    ev_water_audit: &mut EventWriter<WaterAuditReport>,
+    ev_player_roster: &mut EventWriter<PlayerRosterUpdate>,
+    ev_announcement: &mut EventWriter<ServerAnnouncement>,
+    ev_weather: &mut EventWriter<WeatherUpdate>,
+    ev_animation: &mut EventWriter<AnimationEvent>,
+    ev_waystone: &mut EventWriter<WaystoneUpdate>,
+    ev_time_skip: &mut EventWriter<WorldTimeSkip>,
+    ev_item_frame: &mut EventWriter<ItemFrameUpdate>,
+    ev_far_terrain: &mut EventWriter<FarTerrainUpdate>,
+    ev_fishing: &mut EventWriter<FishingUpdate>,
+    ev_boats: &mut EventWriter<BoatUpdate>,
+    ev_heatmap: &mut EventWriter<HeatmapUpdate>,
+    ev_dimension_change: &mut EventWriter<DimensionChange>,
+    ev_ui_overlay: &mut EventWriter<UiOverlay>,
 ) {
-    while let Some(Ok(msg)) = client.receive_game_message_except_channel(STC_AUTH_CHANNEL) {
+    while let Some(Ok(msg)) =
+        client.receive_game_message_except_channels(&[STC_AUTH_CHANNEL, STC_VOICE_CHANNEL])
+    {
         // truncate the message to 1000 characters
         // let debug_msg = format!("{:?}", msg).chars().take(1000).collect::<String>();
         // info!("Received message: {}", debug_msg);
@@ -36,7 +62,8 @@ pub fn update_world_from_network(
                     world_update.new_map.len()
                 );
 
-                for (pos, chunk) in world_update.new_map {
+                for (pos, mut chunk) in world_update.new_map {
+                    water_prediction.reconcile(pos, &mut chunk.map);
                     let chunk = Arc::new(ClientChunk {
                         map: chunk.map,
                         entity: {
@@ -48,6 +75,7 @@ pub fn update_world_from_network(
                         },
                         last_mesh_ts: Instant::now(),
                         current_lod: shared::world::LodLevel::default(),
+                        missing_neighbors: Vec::new(),
                     });
 
                     world.map.insert(pos, chunk);
@@ -73,11 +101,64 @@ pub fn update_world_from_network(
                 // info!("Received mob update event {:?}", update_event);
                 ev_mob_update.write(update_event);
             }
+            ServerToClientMessage::MobDespawn(despawn_event) => {
+                ev_mob_despawn.write(despawn_event);
+            }
             ServerToClientMessage::PlayerUpdate(update) => {
                 ev_player_update.write(update);
             }
+            ServerToClientMessage::FluidParticles(update) => {
+                ev_fluid_particles.write(update);
+            }
+            ServerToClientMessage::WaterAudit(report) => {
+                ev_water_audit.write(report);
+            }
+            ServerToClientMessage::PlayerRoster(update) => {
+                ev_player_roster.write(update);
+            }
+            ServerToClientMessage::Announcement(announcement) => {
+                ev_announcement.write(announcement);
+            }
+            ServerToClientMessage::Weather(update) => {
+                ev_weather.write(update);
+            }
+            ServerToClientMessage::Animation(animation) => {
+                ev_animation.write(animation);
+            }
+            ServerToClientMessage::Waystone(update) => {
+                ev_waystone.write(update);
+            }
+            ServerToClientMessage::TimeSkip(skip) => {
+                ev_time_skip.write(skip);
+            }
+            ServerToClientMessage::ItemFrame(update) => {
+                ev_item_frame.write(update);
+            }
+            ServerToClientMessage::FarTerrain(update) => {
+                ev_far_terrain.write(update);
+            }
+            ServerToClientMessage::Fishing(update) => {
+                ev_fishing.write(update);
+            }
+            ServerToClientMessage::Boats(update) => {
+                ev_boats.write(update);
+            }
+            ServerToClientMessage::Heatmap(update) => {
+                ev_heatmap.write(update);
+            }
+            ServerToClientMessage::DimensionChange(change) => {
+                // Chunks of the new dimension follow on the same channel
+                world.map.clear();
+                world.mark_dirty();
+                ev_dimension_change.write(change);
+            }
+            ServerToClientMessage::UiOverlay(overlay) => {
+                ev_ui_overlay.write(overlay);
+            }
             ServerToClientMessage::AuthRegisterResponse(_) => {}
             ServerToClientMessage::ChatConversation(_) => {}
+            // Voice has its own channel, read by the voice chat
+            ServerToClientMessage::Voice(_) => {}
         }
     }
 }
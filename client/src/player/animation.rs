@@ -0,0 +1,171 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use shared::players::{AnimationEvent, AnimationKind, AnimationTarget, Emote, Player};
+
+use crate::audio::{EntitySoundEvent, EntitySoundKind};
+
+/// Distance a player can move before a lasting animation, such as sitting, stops
+const ANIMATION_CANCEL_DISTANCE: f32 = 0.1;
+
+const LIMB_WIDTH: f32 = 0.15;
+const LIMB_LENGTH: f32 = 0.6;
+/// Seconds for a limb to be raised at the start of an animation
+const LIMB_RAISE_DURATION: f32 = 0.2;
+
+/// Height of a sitting player, relative to their standing height
+const SIT_SCALE: f32 = 0.6;
+/// Angle a hurt player tilts backwards by, in radians
+const HURT_TILT: f32 = 0.3;
+
+/// Animation currently played on a player model
+#[derive(Component, Debug)]
+pub struct PlayerAnimation {
+    kind: AnimationKind,
+    elapsed: f32,
+    start_position: Vec3,
+    limb: Option<Entity>,
+}
+
+/// Arm spawned on the side of a player model for the duration of an animation
+#[derive(Component)]
+pub struct AnimatedLimb;
+
+fn needs_limb(kind: AnimationKind) -> bool {
+    match kind {
+        AnimationKind::Emote(Emote::Wave | Emote::Point) | AnimationKind::Swing => true,
+        AnimationKind::Emote(Emote::Sit) | AnimationKind::Hurt => false,
+    }
+}
+
+pub fn player_animation_event_system(
+    mut commands: Commands,
+    mut events: EventReader<AnimationEvent>,
+    players: Query<(
+        Entity,
+        &Player,
+        &MeshMaterial3d<StandardMaterial>,
+        Option<&PlayerAnimation>,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut ev_entity_sound: EventWriter<EntitySoundEvent>,
+) {
+    for event in events.read() {
+        // Mobs play their own animations
+        let AnimationTarget::Player(id) = event.target else {
+            continue;
+        };
+        let Some((entity, player, material, current)) =
+            players.iter().find(|(_, player, _, _)| player.id == id)
+        else {
+            debug!("Animation {:?} for unknown player {}", event.kind, id);
+            continue;
+        };
+
+        if event.kind == AnimationKind::Swing {
+            ev_entity_sound.write(EntitySoundEvent {
+                kind: EntitySoundKind::Swing,
+                position: player.position,
+            });
+        }
+
+        // A new animation replaces the current one
+        if let Some(limb) = current.and_then(|animation| animation.limb) {
+            commands.entity(limb).despawn();
+        }
+
+        let limb = needs_limb(event.kind).then(|| {
+            commands
+                .spawn((
+                    AnimatedLimb,
+                    Mesh3d(meshes.add(Cuboid::new(LIMB_WIDTH, LIMB_LENGTH, LIMB_WIDTH))),
+                    material.clone(),
+                    Transform::default(),
+                    ChildOf(entity),
+                ))
+                .id()
+        });
+
+        commands.entity(entity).insert(PlayerAnimation {
+            kind: event.kind,
+            elapsed: 0.0,
+            start_position: player.position,
+            limb,
+        });
+    }
+}
+
+/// Transform of a limb hanging from the right shoulder, rotated by `rotation`
+fn hanging_limb_transform(player: &Player, rotation: Quat) -> Transform {
+    let shoulder = Vec3::new((player.width + LIMB_WIDTH) / 2.0, player.height * 0.3, 0.0);
+    Transform::from_translation(shoulder + rotation * (Vec3::NEG_Y * LIMB_LENGTH / 2.0))
+        .with_rotation(rotation)
+}
+
+/// Plays animations on player models. Runs after the player transforms are updated,
+/// so that animations are applied on top of the latest position.
+pub fn player_animation_system(
+    mut commands: Commands,
+    mut players: Query<(Entity, &Player, &mut Transform, &mut PlayerAnimation)>,
+    mut limbs: Query<&mut Transform, (With<AnimatedLimb>, Without<PlayerAnimation>)>,
+    time: Res<Time>,
+) {
+    for (entity, player, mut transform, mut animation) in players.iter_mut() {
+        animation.elapsed += time.delta_secs();
+
+        let moved = animation.start_position.distance(player.position) > ANIMATION_CANCEL_DISTANCE;
+        let finished = match animation.kind.duration() {
+            Some(duration) => animation.elapsed >= duration,
+            None => moved,
+        };
+
+        if finished {
+            *transform = Transform::from_translation(player.position);
+            if let Some(limb) = animation.limb {
+                commands.entity(limb).despawn();
+            }
+            commands.entity(entity).remove::<PlayerAnimation>();
+            continue;
+        }
+
+        let raised = (animation.elapsed / LIMB_RAISE_DURATION).min(1.0);
+        let limb_rotation = match animation.kind {
+            AnimationKind::Emote(Emote::Wave) => {
+                // Raised above the head, swinging from side to side
+                let swing = (animation.elapsed * 10.0).sin() * 0.4;
+                Some(Quat::from_rotation_z((PI * 0.8 + swing) * raised))
+            }
+            AnimationKind::Emote(Emote::Point) => {
+                // Held forward, in the direction the player is looking
+                let (yaw, _, _) = player.camera_transform.rotation.to_euler(EulerRot::YXZ);
+                Some(Quat::from_rotation_y(yaw) * Quat::from_rotation_x(FRAC_PI_2 * raised))
+            }
+            AnimationKind::Emote(Emote::Sit) => {
+                // Squashed down, keeping the feet on the ground
+                let lowered = player.height * (1.0 - SIT_SCALE) / 2.0;
+                transform.translation = player.position - Vec3::Y * lowered;
+                transform.scale = Vec3::new(1.0, SIT_SCALE, 1.0);
+                None
+            }
+            AnimationKind::Swing => {
+                // Swung down in front of the player, in the direction they are looking
+                let (yaw, _, _) = player.camera_transform.rotation.to_euler(EulerRot::YXZ);
+                let progress = animation.elapsed / animation.kind.duration().unwrap_or(1.0);
+                let swing = (progress * PI).sin();
+                Some(Quat::from_rotation_y(yaw) * Quat::from_rotation_x(FRAC_PI_2 * (0.5 + swing)))
+            }
+            AnimationKind::Hurt => {
+                // Briefly tilted backwards
+                let progress = animation.elapsed / animation.kind.duration().unwrap_or(1.0);
+                transform.rotation = Quat::from_rotation_x(-HURT_TILT * (1.0 - progress));
+                None
+            }
+        };
+
+        if let (Some(rotation), Some(limb)) = (limb_rotation, animation.limb) {
+            if let Ok(mut limb_transform) = limbs.get_mut(limb) {
+                *limb_transform = hanging_limb_transform(player, rotation);
+            }
+        }
+    }
+}
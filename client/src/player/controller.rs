@@ -105,6 +105,7 @@ pub fn player_movement_system(
     simulate_player_movement_rapier(&mut player, world_map.as_ref(), &frame_inputs.0);
 
     frame_inputs.0.position = player.position;
+    frame_inputs.0.boat = player.boat;
 
     player_transform.translation = player.position;
 
@@ -164,6 +165,10 @@ pub fn toggle_debug_system(
             GameAction::ToggleRaycastDebugMode,
             DebugOptions::toggle_raycast_debug_mode,
         ),
+        (
+            GameAction::ToggleBiomeOverlay,
+            DebugOptions::toggle_biome_overlay,
+        ),
     ];
 
     for (action, toggle_fn) in TOGGLES {
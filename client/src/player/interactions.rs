@@ -1,11 +1,16 @@
+use crate::audio::BlockSoundEvent;
 use crate::mob::{MobMarker, TargetedMob, TargetedMobData};
 use crate::network::buffered_client::CurrentFrameInputs;
+use crate::network::SendGameMessageExtension;
 use crate::ui::hud::UIMode;
+use crate::world::water_prediction::LocalBlockEditEvent;
 use crate::world::ClientWorldMap;
 use bevy::color::palettes::css::WHITE;
 use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use shared::messages::NetworkAction;
+use bevy_renet::renet::RenetClient;
+use shared::fluid::{bucket_target, BucketUse};
+use shared::messages::{ClientToServerMessage, NetworkAction};
 use shared::players::blocks::{simulate_player_block_interactions, CallerType};
 use shared::players::{Player, ViewMode};
 use shared::world::raycast;
@@ -28,6 +33,8 @@ pub struct PlayerInteractionResources<'w> {
     view_mode: Res<'w, ViewMode>,
     targeted_mob: ResMut<'w, TargetedMob>,
     frame_inputs: ResMut<'w, CurrentFrameInputs>,
+    ev_block_sound: EventWriter<'w, BlockSoundEvent>,
+    ev_local_edit: EventWriter<'w, LocalBlockEditEvent>,
 }
 
 // Function to handle block placement and breaking
@@ -50,6 +57,8 @@ pub fn handle_block_interactions(
         view_mode,
         mut targeted_mob,
         mut frame_inputs,
+        mut ev_block_sound,
+        mut ev_local_edit,
     } = resources;
 
     let mut player = player_query.single_mut().unwrap();
@@ -87,7 +96,8 @@ pub fn handle_block_interactions(
     }
 
     if mouse_input.just_pressed(MouseButton::Left) && targeted_mob.target.is_some() {
-        // TODO: Attack the targeted
+        // The server checks the reach and cooldown, and finds the mob hit on its side
+        frame_inputs.0.inputs.insert(NetworkAction::Attack);
 
         targeted_mob.target = None;
 
@@ -113,11 +123,44 @@ pub fn handle_block_interactions(
             frame_inputs.0.inputs.insert(NetworkAction::RightClick);
         }
 
-        simulate_player_block_interactions(
+        let outcomes = simulate_player_block_interactions(
             &mut player,
             world_map,
             &frame_inputs.0,
             CallerType::Client,
         );
+        ev_block_sound.write_batch(outcomes.iter().map(BlockSoundEvent::from_outcome));
+        ev_local_edit.write_batch(outcomes.iter().copied().map(LocalBlockEditEvent));
+    }
+}
+
+/// Asks the server to fill or pour out the held bucket, on press only
+pub fn use_bucket_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    camera: Query<&Transform, (With<Camera>, Without<CurrentPlayerMarker>)>,
+    world_map: Res<ClientWorldMap>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    ui_mode: Res<UIMode>,
+    view_mode: Res<ViewMode>,
+    frame_inputs: Res<CurrentFrameInputs>,
+    mut client: ResMut<RenetClient>,
+) {
+    if *ui_mode == UIMode::Opened || !mouse_input.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let (Ok(player), Ok(camera)) = (player.single(), camera.single()) else {
+        return;
+    };
+    let hotbar_slot = frame_inputs.0.hotbar_slot;
+    let Some(stack) = player.inventory.inner.get(&hotbar_slot) else {
+        return;
+    };
+
+    let (origin, direction) = raycast::camera_ray(camera, &player.position, *view_mode);
+    if let Some(target) = bucket_target(world_map.as_ref(), stack.item_id, origin, direction) {
+        client.send_game_message(ClientToServerMessage::UseBucket(BucketUse {
+            hotbar_slot,
+            target,
+        }));
     }
 }
@@ -12,7 +12,7 @@ pub struct PlayerLabel {
 pub fn player_labels_system(
     camera: Single<(&mut Camera, &GlobalTransform), With<Camera>>,
     mut labels: Query<(&mut Node, &mut Visibility, &PlayerLabel)>,
-    labeled: Query<&GlobalTransform>,
+    labeled: Query<(&GlobalTransform, &Visibility)>,
     view: Res<ViewMode>,
 ) {
     let (camera, camera_global_transform) = camera.into_inner();
@@ -24,13 +24,16 @@ pub fn player_labels_system(
             *vis = Visibility::Hidden;
         }
     } else {
-        for (_, mut vis, _label) in &mut labels.iter_mut() {
-            *vis = Visibility::Visible;
-        }
-
-        for (mut node, _vis, label) in &mut labels {
+        for (mut node, mut vis, label) in &mut labels {
             let entity = labeled.get(label.entity);
-            if let Ok(entity) = entity {
+            if let Ok((entity, entity_visibility)) = entity {
+                // Players of other dimensions are hidden, and so are their labels
+                *vis = if entity_visibility == Visibility::Hidden {
+                    Visibility::Hidden
+                } else {
+                    Visibility::Visible
+                };
+
                 let offset = Vec3::new(0.0, 1.2, 0.0);
                 let world_position = entity.translation() + offset;
 
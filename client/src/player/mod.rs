@@ -1,8 +1,10 @@
+mod animation;
 mod controller;
 mod interactions;
 mod labels;
 mod update;
 
+pub use animation::*;
 pub use controller::*;
 pub use interactions::*;
 pub use labels::*;
@@ -49,6 +49,7 @@ pub fn spawn_players_system(
             position: event.data.position,
             camera_transform: event.data.camera_transform,
             is_flying: event.data.is_flying,
+            dimension: event.data.dimension,
             ..default()
         };
 
@@ -133,7 +134,7 @@ pub fn spawn_players_system(
 }
 
 pub fn update_players_system(
-    mut players: Query<(&mut Player, &mut Transform)>,
+    mut players: Query<(&mut Player, &mut Transform, &mut Visibility)>,
     mut ev_player_update: EventReader<PlayerUpdateEvent>,
     mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
     client: Res<TargetServer>,
@@ -147,9 +148,12 @@ pub fn update_players_system(
     // Read all updates
     for event in ev_player_update.read() {
         // Get the player associated with the event
-        for (mut player, mut transform) in players.iter_mut() {
+        for (mut player, mut transform, _) in players.iter_mut() {
             if player.id == event.id && event.id == my_id {
                 player.inventory = event.inventory.clone();
+                player.health = event.health;
+                player.breath.air = event.air;
+                player.effects = event.effects.clone();
                 inventory.inner = event.inventory.inner.clone();
 
                 // Get the local input matching this update event
@@ -159,7 +163,8 @@ pub fn update_players_system(
                     .find(|input| input.time_ms == event.last_ack_time);
 
                 if let Some(matching_input) = matching_input {
-                    let does_position_match = event.position == matching_input.position;
+                    let does_position_match = event.position == matching_input.position
+                        && event.boat == matching_input.boat;
 
                     if !does_position_match {
                         warn!(
@@ -169,6 +174,7 @@ pub fn update_players_system(
 
                         // Reconcile the player position
                         player.position = event.position;
+                        player.boat = event.boat;
 
                         let remaining_inputs = unacknowledged_inputs
                             .0
@@ -199,6 +205,7 @@ pub fn update_players_system(
                         event.last_ack_time, unacknowledged_inputs
                     );
                     player.position = event.position;
+                    player.boat = event.boat;
                     if !unacknowledged_inputs.0.is_empty() && event.last_ack_time != 0 {
                         warn!(
                             "Unacknowledged inputs: {:?}",
@@ -226,8 +233,28 @@ pub fn update_players_system(
                     player.id, event.position
                 );
                 player.position = event.position;
+                player.dimension = event.dimension;
+                player.boat = event.boat;
                 *transform = Transform::from_translation(event.position);
             }
         }
     }
+
+    // Players of other dimensions are not drawn
+    let Some(my_dimension) = players
+        .iter()
+        .find(|(player, _, _)| player.id == my_id)
+        .map(|(player, _, _)| player.dimension)
+    else {
+        return;
+    };
+    for (player, _, mut visibility) in players.iter_mut() {
+        if player.id != my_id {
+            visibility.set_if_neq(if player.dimension == my_dimension {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
 }
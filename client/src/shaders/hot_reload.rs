@@ -0,0 +1,274 @@
+//! Hot reloading of water shaders from the shaders folder
+//!
+//! Dropping a copy of one of bevy_water's shaders (e.g. `water_fragment.wgsl`) into
+//! `GameFolderPaths::shaders_folder_path` overrides the embedded version. The folder is
+//! polled, and any change is pushed straight into `Assets<Shader>` so the pipelines get
+//! recompiled without restarting the game.
+//!
+//! A broken shader never takes the water down: the render world reports pipeline
+//! compilation results back to the main world, and on failure the last shader that
+//! compiled is restored and the error is shown in a toast.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{CachedPipelineState, PipelineCache, PipelineDescriptor};
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy_water::material::{
+    WATER_BINDINGS_HANDLE, WATER_FRAGMENT_SHADER_HANDLE, WATER_FUNCTIONS_HANDLE,
+    WATER_VERTEX_SHADER_HANDLE,
+};
+use shared::sets::ClientSet;
+use shared::GameFolderPaths;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::ui::hud::toast::ToastEvent;
+
+/// How often the shaders folder is checked for changes, in seconds
+const POLL_INTERVAL_SECS: f32 = 1.0;
+
+/// Shader files that can be overridden, and the embedded shader they replace
+const OVERRIDABLE_SHADERS: [(&str, Handle<Shader>); 4] = [
+    ("water_vertex.wgsl", WATER_VERTEX_SHADER_HANDLE),
+    ("water_fragment.wgsl", WATER_FRAGMENT_SHADER_HANDLE),
+    ("water_functions.wgsl", WATER_FUNCTIONS_HANDLE),
+    ("water_bindings.wgsl", WATER_BINDINGS_HANDLE),
+];
+
+/// Shaders used as pipeline entry points. Pipelines built from them are the ones
+/// affected by an override, including overrides of the imported modules.
+const ENTRY_SHADERS: [Handle<Shader>; 2] =
+    [WATER_VERTEX_SHADER_HANDLE, WATER_FRAGMENT_SHADER_HANDLE];
+
+struct ShaderOverride {
+    handle: Handle<Shader>,
+    modified: Option<SystemTime>,
+    /// Embedded shader, restored when the override file is deleted
+    original: Option<Shader>,
+    /// Last version known to compile, restored on compilation failure
+    last_good: Option<Shader>,
+    /// Whether the current version is waiting for its first compilation
+    pending: bool,
+}
+
+#[derive(Resource)]
+pub struct ShaderHotReload {
+    folder: PathBuf,
+    timer: Timer,
+    overrides: HashMap<&'static str, ShaderOverride>,
+}
+
+enum PipelineReport {
+    Compiled,
+    Failed(String),
+}
+
+/// Compilation results, written by the render world and drained by the main world
+#[derive(Resource, Clone, Default)]
+struct PipelineReports(Arc<Mutex<Vec<PipelineReport>>>);
+
+pub fn shader_hot_reload_plugin(app: &mut App) {
+    let folder = app
+        .world()
+        .resource::<GameFolderPaths>()
+        .shaders_folder_path
+        .clone();
+
+    let overrides = OVERRIDABLE_SHADERS
+        .iter()
+        .map(|(file_name, handle)| {
+            (
+                *file_name,
+                ShaderOverride {
+                    handle: handle.clone(),
+                    modified: None,
+                    original: None,
+                    last_good: None,
+                    pending: false,
+                },
+            )
+        })
+        .collect();
+
+    let reports = PipelineReports::default();
+
+    app.insert_resource(ShaderHotReload {
+        folder,
+        timer: Timer::from_seconds(POLL_INTERVAL_SECS, TimerMode::Repeating),
+        overrides,
+    })
+    .insert_resource(reports.clone())
+    .add_systems(
+        Update,
+        (poll_shader_overrides_system, apply_pipeline_reports_system)
+            .chain()
+            .in_set(ClientSet::Render),
+    );
+
+    if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+        render_app.insert_resource(reports).add_systems(
+            Render,
+            report_pipeline_states_system.in_set(RenderSet::Cleanup),
+        );
+    }
+}
+
+fn poll_shader_overrides_system(
+    time: Res<Time>,
+    mut hot_reload: ResMut<ShaderHotReload>,
+    mut shaders: ResMut<Assets<Shader>>,
+) {
+    if !hot_reload.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let ShaderHotReload {
+        folder, overrides, ..
+    } = &mut *hot_reload;
+
+    for (file_name, entry) in overrides.iter_mut() {
+        let path = folder.join(file_name);
+        let modified = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        if modified == entry.modified {
+            continue;
+        }
+        entry.modified = modified;
+
+        if entry.original.is_none() {
+            entry.original = shaders.get(&entry.handle).cloned();
+        }
+
+        if modified.is_none() {
+            // Override was deleted, go back to the embedded shader
+            if let Some(original) = entry.original.clone() {
+                info!("Shader override {} removed, restoring default", file_name);
+                shaders.insert(&entry.handle, original);
+            }
+            entry.last_good = None;
+            entry.pending = false;
+            continue;
+        }
+
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                warn!("Could not read shader {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        if entry.last_good.is_none() {
+            entry.last_good = entry.original.clone();
+        }
+        entry.pending = true;
+
+        info!("Reloading shader {}", path.display());
+        shaders.insert(
+            &entry.handle,
+            Shader::from_wgsl(source, path.display().to_string()),
+        );
+    }
+}
+
+fn apply_pipeline_reports_system(
+    reports: Res<PipelineReports>,
+    mut hot_reload: ResMut<ShaderHotReload>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let drained: Vec<PipelineReport> = {
+        let mut reports = reports.0.lock().unwrap();
+        if reports.is_empty() {
+            return;
+        }
+        reports.drain(..).collect()
+    };
+
+    let failure = drained.iter().find_map(|report| match report {
+        PipelineReport::Failed(error) => Some(error.clone()),
+        PipelineReport::Compiled => None,
+    });
+
+    for entry in hot_reload.overrides.values_mut().filter(|e| e.pending) {
+        entry.pending = false;
+
+        if failure.is_some() {
+            if let Some(last_good) = entry.last_good.clone() {
+                shaders.insert(&entry.handle, last_good);
+            }
+        } else {
+            entry.last_good = shaders.get(&entry.handle).cloned();
+        }
+    }
+
+    if let Some(error) = failure {
+        error!("Water shader failed to compile, keeping previous version:\n{error}");
+        toasts.write(ToastEvent::error(format!(
+            "Water shader failed to compile, keeping previous version:\n{error}"
+        )));
+    }
+}
+
+/// Runs in the render world, after pipelines were processed for this frame.
+/// Failures are reported as soon as they happen, while success is only reported
+/// once no water pipeline is left compiling, so that a variant compiling fine
+/// doesn't hide another one failing later.
+fn report_pipeline_states_system(
+    pipeline_cache: Res<PipelineCache>,
+    reports: Res<PipelineReports>,
+    mut last_states: Local<HashMap<usize, bool>>,
+    mut has_new_success: Local<bool>,
+) {
+    let mut any_in_flight = false;
+
+    for (id, pipeline) in pipeline_cache.pipelines().enumerate() {
+        let PipelineDescriptor::RenderPipelineDescriptor(descriptor) = &pipeline.descriptor else {
+            continue;
+        };
+
+        let uses_water_shader = ENTRY_SHADERS.iter().any(|handle| {
+            descriptor.vertex.shader.id() == handle.id()
+                || descriptor
+                    .fragment
+                    .as_ref()
+                    .is_some_and(|fragment| fragment.shader.id() == handle.id())
+        });
+        if !uses_water_shader {
+            continue;
+        }
+
+        let compiled = match &pipeline.state {
+            CachedPipelineState::Ok(_) => true,
+            CachedPipelineState::Err(_) => false,
+            _ => {
+                // Recompiling, forget the previous state so the next result gets reported
+                last_states.remove(&id);
+                any_in_flight = true;
+                continue;
+            }
+        };
+
+        if last_states.insert(id, compiled) == Some(compiled) {
+            continue;
+        }
+
+        if let CachedPipelineState::Err(error) = &pipeline.state {
+            reports
+                .0
+                .lock()
+                .unwrap()
+                .push(PipelineReport::Failed(error.to_string()));
+        } else {
+            *has_new_success = true;
+        }
+    }
+
+    if *has_new_success && !any_in_flight {
+        *has_new_success = false;
+        reports.0.lock().unwrap().push(PipelineReport::Compiled);
+    }
+}
@@ -1,8 +1,11 @@
 //! Custom shader system for Rustcraft
 //!
-//! This module provides water rendering integration using the bevy_water crate.
+//! This module provides water rendering integration using the bevy_water crate,
+//! and hot reloading of overridden water shaders from the shaders folder.
 
+pub mod hot_reload;
 pub mod water;
 
+pub use hot_reload::shader_hot_reload_plugin;
 // Re-export water types for convenience
 pub use water::{WaterPlugin, WaterSettings};
@@ -4,13 +4,74 @@
 //! for water meshes in chunk rendering.
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // Re-export bevy_water types for use throughout the codebase
 pub use bevy_water::material::{StandardWaterMaterial, WaterMaterial};
-pub use bevy_water::{WaterPlugin, WaterSettings};
+pub use bevy_water::{WaterPlugin, WaterQuality, WaterSettings};
 
 /// Component marker for entities using water material
 ///
 /// This is used to identify water mesh entities in the chunk rendering system.
 #[derive(Component)]
 pub struct WaterMesh;
+
+/// Water rendering quality, selectable in the graphics settings.
+/// Lower presets keep low-end GPUs playable near oceans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WaterQualityPreset {
+    /// Flat tinted quads using a plain `StandardMaterial`, no displacement
+    Off,
+    /// Water shader with a single wave and no reflections
+    Low,
+    /// Water shader with every wave and reflections
+    #[default]
+    High,
+}
+
+impl WaterQualityPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WaterQualityPreset::Off => "Off",
+            WaterQualityPreset::Low => "Low",
+            WaterQualityPreset::High => "High",
+        }
+    }
+
+    /// Preset shown after this one when cycling through them in the settings menu
+    pub fn next(&self) -> Self {
+        match self {
+            WaterQualityPreset::Off => WaterQualityPreset::Low,
+            WaterQualityPreset::Low => WaterQualityPreset::High,
+            WaterQualityPreset::High => WaterQualityPreset::Off,
+        }
+    }
+
+    /// Whether water surfaces use the animated water shader, or the flat fallback material
+    pub fn uses_water_shader(&self) -> bool {
+        *self != WaterQualityPreset::Off
+    }
+
+    /// Shader quality level, which controls how many waves are summed on the GPU
+    pub fn shader_quality(&self) -> WaterQuality {
+        match self {
+            WaterQualityPreset::Off | WaterQualityPreset::Low => WaterQuality::Basic,
+            WaterQualityPreset::High => WaterQuality::Ultra,
+        }
+    }
+
+    pub fn amplitude(&self) -> f32 {
+        match self {
+            WaterQualityPreset::Off => 0.0,
+            WaterQualityPreset::Low => 0.1,
+            WaterQualityPreset::High => shared::water::WAVE_AMPLITUDE,
+        }
+    }
+
+    pub fn reflectance(&self) -> f32 {
+        match self {
+            WaterQualityPreset::Off | WaterQualityPreset::Low => 0.0,
+            WaterQualityPreset::High => 0.5,
+        }
+    }
+}
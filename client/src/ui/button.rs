@@ -25,6 +25,8 @@ pub enum MenuButtonAction {
     Multi,
     Settings,
     SettingsControls,
+    SettingsGraphics,
+    SettingsDebugHud,
     BackToMainMenu,
     BackToSettings,
     Quit,
@@ -37,6 +39,10 @@ pub enum MenuState {
     Multi,
     Settings,
     SettingsControls,
+    SettingsGraphics,
+    SettingsDebugHud,
+    /// Why joining the last server failed, see `ConnectionError`
+    ConnectionError,
     #[default]
     Disabled,
 }
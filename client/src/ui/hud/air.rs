@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use shared::players::{Player, MAX_PLAYER_AIR};
+
+use super::widgets::{HudAnchor, HudContext, HudLayer, HudWidget};
+use crate::{
+    constants::{HOTBAR_CELL_SIZE, MAX_HOTBAR_SLOTS},
+    player::CurrentPlayerMarker,
+};
+
+const AIR_BAR_WIDTH: f32 = MAX_HOTBAR_SLOTS as f32 * HOTBAR_CELL_SIZE / 2.;
+const AIR_BAR_HEIGHT: f32 = 4.;
+const AIR_COLOR: Color = Color::srgb(0.35, 0.65, 1.0);
+
+/// Air left to the player under water, between the health bar and the action
+/// bar. Hidden while the player has all their air.
+#[derive(Component, Default)]
+pub struct AirBarRoot;
+
+#[derive(Component)]
+pub struct AirBarFill;
+
+pub const AIR_BAR_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::BottomCenter,
+    offset: Vec2::new(0., 124.),
+    layer: HudLayer::Hud,
+    visible_when: HudContext::survival,
+};
+
+pub fn air_bar_system(
+    mut commands: Commands,
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    root: Query<Entity, With<AirBarRoot>>,
+    mut fill: Query<(&mut Node, &ChildOf), With<AirBarFill>>,
+    mut bars: Query<&mut Visibility, Without<AirBarRoot>>,
+) {
+    let (Ok(player), Ok(root)) = (player.single(), root.single()) else {
+        return;
+    };
+
+    let Ok((mut node, bar)) = fill.single_mut() else {
+        commands.entity(root).with_children(|root| {
+            root.spawn((
+                Node {
+                    width: Val::Px(AIR_BAR_WIDTH),
+                    height: Val::Px(AIR_BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                Visibility::Hidden,
+            ))
+            .with_child((
+                AirBarFill,
+                Node {
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(AIR_COLOR),
+            ));
+        });
+        return;
+    };
+
+    let width = Val::Percent(100. * player.breath.air as f32 / MAX_PLAYER_AIR as f32);
+    if node.width != width {
+        node.width = width;
+    }
+    if let Ok(mut visibility) = bars.get_mut(bar.parent()) {
+        visibility.set_if_neq(if player.breath.is_full() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+    }
+}
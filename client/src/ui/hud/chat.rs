@@ -4,10 +4,18 @@ use crate::network::CachedChatConversation;
 use crate::network::SendGameMessageExtension;
 use crate::ui::assets::chat_text_font;
 use crate::ui::hud::UiDialog;
+use crate::world::heatmap::ShownHeatmap;
+use crate::world::prefabs::PrefabCommandEvent;
 use crate::KeyMap;
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 use bevy_simple_text_input::*;
+use shared::messages::REGENERATE_CHUNKS_COMMAND;
+use shared::players::{parse_emote_command, parse_spawnpoint_command, SPAWN_COMMAND};
+use shared::world::{
+    parse_difficulty_command, parse_heatmap_command, parse_locate_command, parse_prefab_command,
+    parse_rollback_command, HeatmapCommand, SEED_COMMAND,
+};
 use shared::GameFolderPaths;
 
 use super::UIMode;
@@ -135,6 +143,8 @@ pub fn render_chat(
     mut event: EventReader<TextInputSubmitEvent>,
     mut commands: Commands,
     _paths: Res<GameFolderPaths>,
+    mut heatmap: ResMut<ShownHeatmap>,
+    mut ev_prefab: EventWriter<PrefabCommandEvent>,
 ) {
     let (cached_conv, asset_server, mut client, keyboard_input, key_map, ui_mode) = resources;
     let (mut text_query, mut visibility_query, parent_query, mut animation_query) = queries;
@@ -230,12 +240,66 @@ pub fn render_chat(
     *inactive = TextInputInactive(true);
 
     for message in event.read() {
-        if entity_check == message.entity {
-            client.send_game_message(shared::messages::ClientToServerMessage::ChatMessage(
-                shared::messages::ChatMessageRequest {
-                    content: message.value.clone(),
-                },
+        if entity_check != message.entity {
+            continue;
+        }
+        // Emote commands are played instead of being sent to the chat
+        if let Some(emote) = parse_emote_command(&message.value) {
+            client.send_game_message(shared::messages::ClientToServerMessage::Emote(emote));
+            continue;
+        }
+        if let Some(difficulty) = parse_difficulty_command(&message.value) {
+            client.send_game_message(shared::messages::ClientToServerMessage::SetDifficulty(
+                difficulty,
+            ));
+            continue;
+        }
+        if message.value.trim() == SEED_COMMAND {
+            client.send_game_message(shared::messages::ClientToServerMessage::RequestSeed);
+            continue;
+        }
+        if let Some(name) = parse_locate_command(&message.value) {
+            client.send_game_message(shared::messages::ClientToServerMessage::Locate(name));
+            continue;
+        }
+        if let Some(command) = parse_heatmap_command(&message.value) {
+            match command {
+                HeatmapCommand::Show(kind) => client.send_game_message(
+                    shared::messages::ClientToServerMessage::RequestHeatmap(kind),
+                ),
+                HeatmapCommand::Hide => heatmap.0 = None,
+            }
+            continue;
+        }
+        if let Some(request) = parse_rollback_command(&message.value) {
+            client.send_game_message(shared::messages::ClientToServerMessage::Rollback(request));
+            continue;
+        }
+        // Prefabs are stored by the client, only their placement goes to the server
+        if let Some(command) = parse_prefab_command(&message.value) {
+            ev_prefab.write(PrefabCommandEvent(command));
+            continue;
+        }
+        if message.value.trim() == REGENERATE_CHUNKS_COMMAND {
+            client.send_game_message(
+                shared::messages::ClientToServerMessage::RegenerateCorruptChunks,
+            );
+            continue;
+        }
+        if message.value.trim() == SPAWN_COMMAND {
+            client.send_game_message(shared::messages::ClientToServerMessage::TeleportToSpawn);
+            continue;
+        }
+        if let Some(command) = parse_spawnpoint_command(&message.value) {
+            client.send_game_message(shared::messages::ClientToServerMessage::SetSpawnPoint(
+                command,
             ));
+            continue;
         }
+        client.send_game_message(shared::messages::ClientToServerMessage::ChatMessage(
+            shared::messages::ChatMessageRequest {
+                content: message.value.clone(),
+            },
+        ));
     }
 }
@@ -1,6 +1,20 @@
+use super::DebugOptions;
 use crate::player::CurrentPlayerMarker;
+use crate::GameState;
+use bevy::asset::RenderAssetUsages;
 use bevy::prelude::*;
-use shared::world::{block_to_chunk_coord, calculate_biome_at_position, WorldSeed};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use shared::world::{
+    block_to_chunk_coord, calculate_biome_at_position, calculate_temperature_humidity_with_noises,
+    BiomeType, ClimateNoises, WorldSeed,
+};
+
+/// Side of the biome overlay texture, in pixels
+const BIOME_OVERLAY_SIZE: u32 = 96;
+/// Blocks between two samples of the overlay, so it covers 768 blocks around the player
+const BIOME_OVERLAY_BLOCKS_PER_PIXEL: i32 = 8;
+/// Size of the overlay on screen, in pixels
+const BIOME_OVERLAY_DISPLAY_SIZE: f32 = 192.0;
 
 #[derive(Component)]
 pub struct BiomeText;
@@ -48,3 +62,190 @@ pub fn biome_text_update_system(
         }
     }
 }
+
+/// Top-down map of the biomes around the player, F9 by default.
+/// North (-Z) is up and the player is the white dot in the middle.
+#[derive(Component)]
+pub struct BiomeOverlay;
+
+/// Image of the overlay, redrawn whenever the player enters a new chunk
+#[derive(Component)]
+pub struct BiomeOverlayImage;
+
+pub(super) fn biome_overlay_color(biome: BiomeType) -> [u8; 4] {
+    match biome {
+        BiomeType::Plains => [124, 189, 80, 255],
+        BiomeType::Forest => [40, 110, 40, 255],
+        BiomeType::MediumMountain => [130, 130, 110, 255],
+        BiomeType::HighMountainGrass => [180, 190, 170, 255],
+        BiomeType::Desert => [222, 205, 130, 255],
+        BiomeType::IcePlain => [235, 245, 255, 255],
+        BiomeType::FlowerPlains => [200, 170, 60, 255],
+        BiomeType::Swamp => [70, 90, 50, 255],
+        BiomeType::MushroomFields => [150, 110, 150, 255],
+        BiomeType::ShallowOcean => [70, 140, 210, 255],
+        BiomeType::Ocean => [40, 90, 180, 255],
+        BiomeType::DeepOcean => [20, 45, 120, 255],
+    }
+}
+
+fn biome_overlay_image() -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: BIOME_OVERLAY_SIZE,
+            height: BIOME_OVERLAY_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Classifies every pixel of the overlay with the same climate functions as the
+/// world generation, centered on the `center` block column
+fn draw_biome_overlay(data: &mut [u8], center: IVec2, noises: &mut ClimateNoises) {
+    let half = BIOME_OVERLAY_SIZE as i32 / 2;
+    for py in 0..BIOME_OVERLAY_SIZE as i32 {
+        for px in 0..BIOME_OVERLAY_SIZE as i32 {
+            let x = center.x + (px - half) * BIOME_OVERLAY_BLOCKS_PER_PIXEL;
+            let z = center.y + (py - half) * BIOME_OVERLAY_BLOCKS_PER_PIXEL;
+            let climate = calculate_temperature_humidity_with_noises(x, z, noises);
+            let color = if (px - half).abs() <= 1 && (py - half).abs() <= 1 {
+                [255, 255, 255, 255]
+            } else {
+                biome_overlay_color(BiomeType::from_climate(climate))
+            };
+            let index = ((py * BIOME_OVERLAY_SIZE as i32 + px) * 4) as usize;
+            data[index..index + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+pub fn setup_biome_overlay(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(biome_overlay_image());
+
+    commands
+        .spawn((
+            BiomeOverlay,
+            StateScoped(GameState::Game),
+            Visibility::Hidden,
+            BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            GlobalZIndex(i32::MAX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Percent(1.),
+                bottom: Val::Percent(12.),
+                padding: UiRect::all(Val::Px(4.0)),
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+        ))
+        .with_children(|root| {
+            root.spawn((
+                BiomeOverlayImage,
+                ImageNode::new(image),
+                Node {
+                    width: Val::Px(BIOME_OVERLAY_DISPLAY_SIZE),
+                    height: Val::Px(BIOME_OVERLAY_DISPLAY_SIZE),
+                    ..default()
+                },
+            ));
+            root.spawn(Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.0),
+                ..default()
+            })
+            .with_children(|legend| {
+                for biome in BiomeType::ALL {
+                    let [r, g, b, _] = biome_overlay_color(biome);
+                    legend
+                        .spawn(Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(4.0),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                BackgroundColor(Color::srgb_u8(r, g, b)),
+                                Node {
+                                    width: Val::Px(10.0),
+                                    height: Val::Px(10.0),
+                                    ..default()
+                                },
+                            ));
+                            row.spawn((
+                                Text::new(biome.name()),
+                                TextFont::from_font_size(12.0),
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                }
+            });
+        });
+}
+
+/// Shows or hides the biome overlay, and redraws it when the player enters a new
+/// chunk while it's visible
+pub fn biome_overlay_update_system(
+    debug_options: Res<DebugOptions>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    mut root: Query<&mut Visibility, With<BiomeOverlay>>,
+    overlay: Query<&ImageNode, With<BiomeOverlayImage>>,
+    mut images: ResMut<Assets<Image>>,
+    world_seed: Res<WorldSeed>,
+    mut noises: Local<Option<(u32, ClimateNoises)>>,
+    mut last_chunk: Local<Option<IVec2>>,
+) {
+    let enabled = debug_options.is_biome_overlay_enabled;
+    for mut visibility in root.iter_mut() {
+        visibility.set_if_neq(if enabled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+    }
+    if !enabled {
+        // Redraw as soon as the overlay is shown again
+        *last_chunk = None;
+        return;
+    }
+
+    let Ok(transform) = player.single() else {
+        return;
+    };
+    let center = IVec2::new(
+        transform.translation.x.floor() as i32,
+        transform.translation.z.floor() as i32,
+    );
+    let chunk = IVec2::new(
+        block_to_chunk_coord(center.x),
+        block_to_chunk_coord(center.y),
+    );
+    if *last_chunk == Some(chunk) {
+        return;
+    }
+
+    if noises
+        .as_ref()
+        .is_none_or(|(seed, _)| *seed != world_seed.0)
+    {
+        *noises = Some((world_seed.0, ClimateNoises::new(world_seed.0)));
+    }
+    let Some((_, noises)) = noises.as_mut() else {
+        return;
+    };
+    let Ok(image_node) = overlay.single() else {
+        return;
+    };
+    let Some(data) = images
+        .get_mut(&image_node.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+
+    draw_biome_overlay(data, center, noises);
+    *last_chunk = Some(chunk);
+}
@@ -1,6 +1,6 @@
 use crate::player::CurrentPlayerMarker;
 use bevy::prelude::*;
-use shared::world::block_to_chunk_coord;
+use shared::world::{block_to_chunk_coord, WorldSpawn};
 
 #[derive(Component)]
 pub struct CoordsText;
@@ -8,6 +8,7 @@ pub struct CoordsText;
 pub fn coords_text_update_system(
     player: Query<&Transform, With<CurrentPlayerMarker>>,
     query: Query<Entity, With<CoordsText>>,
+    world_spawn: Res<WorldSpawn>,
     mut writer: TextUiWriter,
 ) {
     let coords = player.single().unwrap();
@@ -19,8 +20,14 @@ pub fn coords_text_update_system(
 
     for entity in query.iter() {
         *writer.text(entity, 0) = format!(
-            "X/Y/Z = {:.2}/{:.2}/{:.2}\nChunk pos : {:?}",
-            coords.translation.x, coords.translation.y, coords.translation.z, player_chunk
+            "X/Y/Z = {:.2}/{:.2}/{:.2}\nChunk pos : {:?}\nWorld spawn : {}/{}/{}",
+            coords.translation.x,
+            coords.translation.y,
+            coords.translation.z,
+            player_chunk,
+            world_spawn.0.x,
+            world_spawn.0.y,
+            world_spawn.0.z
         );
     }
 }
@@ -0,0 +1,157 @@
+//! Order and visibility of the debug HUD widgets.
+//!
+//! The layout is edited from the "Debug HUD" settings screen and saved next to the
+//! other options, so a minimal overlay survives restarts.
+
+use crate::constants::DEBUG_HUD_LAYOUT_PATH;
+use bevy::prelude::*;
+use ron::{from_str, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugHudWidget {
+    Fps,
+    Coords,
+    Biome,
+    Blocks,
+    Chunks,
+    TargetedBlock,
+    Time,
+    WaterAudit,
+    Network,
+}
+
+impl DebugHudWidget {
+    pub const ALL: [DebugHudWidget; 9] = [
+        DebugHudWidget::Fps,
+        DebugHudWidget::Coords,
+        DebugHudWidget::Biome,
+        DebugHudWidget::Blocks,
+        DebugHudWidget::Chunks,
+        DebugHudWidget::TargetedBlock,
+        DebugHudWidget::Time,
+        DebugHudWidget::WaterAudit,
+        DebugHudWidget::Network,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugHudWidget::Fps => "FPS",
+            DebugHudWidget::Coords => "Coordinates",
+            DebugHudWidget::Biome => "Biome",
+            DebugHudWidget::Blocks => "Blocks",
+            DebugHudWidget::Chunks => "Chunks",
+            DebugHudWidget::TargetedBlock => "Targeted block",
+            DebugHudWidget::Time => "Time",
+            DebugHudWidget::WaterAudit => "Water audit",
+            DebugHudWidget::Network => "Network",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugHudEntry {
+    pub widget: DebugHudWidget,
+    pub visible: bool,
+}
+
+/// Widgets of the debug HUD, from top to bottom
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DebugHudLayout {
+    pub widgets: Vec<DebugHudEntry>,
+}
+
+impl Default for DebugHudLayout {
+    fn default() -> Self {
+        Self {
+            widgets: DebugHudWidget::ALL
+                .into_iter()
+                .map(|widget| DebugHudEntry {
+                    widget,
+                    visible: true,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DebugHudLayout {
+    /// Drops duplicated widgets and adds the missing ones at the bottom, so that
+    /// layouts saved by older versions keep working
+    fn normalized(mut self) -> Self {
+        let mut seen = Vec::new();
+        self.widgets.retain(|entry| {
+            let first = !seen.contains(&entry.widget);
+            seen.push(entry.widget);
+            first
+        });
+        for widget in DebugHudWidget::ALL {
+            if !seen.contains(&widget) {
+                self.widgets.push(DebugHudEntry {
+                    widget,
+                    visible: true,
+                });
+            }
+        }
+        self
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(entry) = self.widgets.get_mut(index) {
+            entry.visible = !entry.visible;
+        }
+    }
+
+    /// Moves the widget at `index` one row up, or down if `up` is false
+    pub fn move_widget(&mut self, index: usize, up: bool) {
+        let target = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1)
+        };
+        if let Some(target) = target.filter(|target| *target < self.widgets.len()) {
+            self.widgets.swap(index, target);
+        }
+    }
+}
+
+pub fn get_debug_hud_layout(game_folder_paths: &GameFolderPaths) -> DebugHudLayout {
+    let path = game_folder_paths
+        .assets_folder_path
+        .join(DEBUG_HUD_LAYOUT_PATH);
+
+    if let Ok(content) = fs::read_to_string(&path) {
+        match from_str::<DebugHudLayout>(&content) {
+            Ok(layout) => return layout.normalized(),
+            Err(e) => warn!(
+                "Failed to deserialize debug HUD layout at {:?}, using defaults: {}",
+                path, e
+            ),
+        }
+    }
+
+    DebugHudLayout::default()
+}
+
+fn write_debug_hud_layout_to_path(
+    layout: &DebugHudLayout,
+    path: &Path,
+) -> Result<(), std::io::Error> {
+    let serialized = ron::ser::to_string_pretty(layout, PrettyConfig::new())
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serialized)
+}
+
+pub fn save_debug_hud_layout(layout: Res<DebugHudLayout>, paths: Res<GameFolderPaths>) {
+    let path = paths.assets_folder_path.join(DEBUG_HUD_LAYOUT_PATH);
+    match write_debug_hud_layout_to_path(&layout, &path) {
+        Ok(_) => info!("Debug HUD layout successfully saved to {:?}", path),
+        Err(e) => error!("Failed to save debug HUD layout to {:?}: {}", path, e),
+    }
+}
@@ -33,8 +33,8 @@ pub fn total_blocks_text_update_system(
 
         let chunk_table_bytes: usize = world_map
             .map
-            .iter()
-            .map(|(_, chunk)| {
+            .values()
+            .map(|chunk| {
                 let entry_bytes = chunk.map.len()
                     * (size_of::<IVec3>() + size_of::<BlockData>() + slot_overhead_bytes);
                 let control_bytes = chunk.map.capacity() * control_overhead_bytes;
@@ -4,10 +4,15 @@ pub mod chunks;
 pub mod coords;
 pub mod fps;
 pub mod inspector;
+pub mod layout;
 mod loaded_stats;
+pub mod network;
+pub mod noise_playground;
 pub mod raycast;
 pub mod setup;
+pub mod snapshot;
 pub mod targeted_block;
+pub mod water_audit;
 
 use bevy::prelude::Resource;
 pub use biome::*;
@@ -15,14 +20,19 @@ pub use blocks::*;
 pub use chunks::*;
 pub use coords::*;
 pub use fps::*;
+pub use layout::*;
 pub use loaded_stats::*;
+pub use network::*;
+pub use noise_playground::*;
 pub use raycast::*;
 pub use setup::*;
+pub use water_audit::*;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Debug)]
 pub struct DebugOptions {
     is_chunk_debug_mode_enabled: bool,
     is_raycast_debug_mode_enabled: bool,
+    is_biome_overlay_enabled: bool,
 }
 
 impl DebugOptions {
@@ -37,4 +47,8 @@ impl DebugOptions {
             self.is_raycast_debug_mode_enabled
         );
     }
+
+    pub fn toggle_biome_overlay(&mut self) {
+        self.is_biome_overlay_enabled = !self.is_biome_overlay_enabled;
+    }
 }
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+
+#[derive(Component)]
+pub struct NetStatsText;
+
+pub fn net_stats_text_update_system(
+    client: Option<Res<RenetClient>>,
+    query: Query<Entity, With<NetStatsText>>,
+    mut writer: TextUiWriter,
+) {
+    let Some(client) = client else {
+        return;
+    };
+    let info = client.network_info();
+
+    for entity in query.iter() {
+        *writer.text(entity, 0) = format!(
+            "RTT: {:.1} ms, loss: {:.1} %\nUp: {:.1} kB/s, down: {:.1} kB/s",
+            info.rtt,
+            info.packet_loss * 100.,
+            info.bytes_sent_per_second / 1000.,
+            info.bytes_received_per_second / 1000.
+        );
+    }
+}
@@ -0,0 +1,572 @@
+//! Dev screen to tune the terrain noise without creating a new world each time.
+//!
+//! It previews the heights and biomes around the player for the current seed with
+//! the shared terrain functions, and saves the chosen parameters to the
+//! `generation.ron` file of the game folder, which the server reads for new worlds.
+
+use super::biome::biome_overlay_color;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::UiDialog;
+use crate::{GameState, KeyMap};
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use ron::ser::PrettyConfig;
+use shared::world::{
+    block_to_chunk_coord, calculate_temperature_humidity_with_noises, BiomeType, ClimateNoises,
+    GenerationConfig, TerrainNoise, WorldSeed, WorldType, GENERATION_CONFIG_FILE,
+};
+use shared::{GameFolderPaths, SEA_LEVEL};
+use std::fs;
+
+/// Side of the previews, in pixels
+const PREVIEW_SIZE: u32 = 96;
+/// Blocks between two samples of the previews
+const PREVIEW_BLOCKS_PER_PIXEL: i32 = 4;
+/// Size of the previews on screen, in pixels
+const PREVIEW_DISPLAY_SIZE: f32 = 256.0;
+
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+const HOVERED_BUTTON_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+
+/// A tunable field of the generation config
+struct PlaygroundParameter {
+    name: &'static str,
+    step: f64,
+    min: f64,
+    max: f64,
+    get: fn(&GenerationConfig) -> f64,
+    set: fn(&mut GenerationConfig, f64),
+    format: fn(f64) -> String,
+}
+
+fn decimal(value: f64) -> String {
+    format!("{value:.3}")
+}
+
+fn integer(value: f64) -> String {
+    format!("{value:.0}")
+}
+
+const PARAMETERS: &[PlaygroundParameter] = &[
+    PlaygroundParameter {
+        name: "Terrain scale",
+        step: 0.01,
+        min: 0.01,
+        max: 1.0,
+        get: |config| config.terrain_scale as f64,
+        set: |config, value| config.terrain_scale = value as f32,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Biome blend distance",
+        step: 1.0,
+        min: 1.0,
+        max: 16.0,
+        get: |config| config.biome_blend_distance as f64,
+        set: |config, value| config.biome_blend_distance = value.round() as i32,
+        format: integer,
+    },
+    PlaygroundParameter {
+        name: "Height variation",
+        step: 0.25,
+        min: 0.0,
+        max: 8.0,
+        get: |config| config.height_variation_multiplier,
+        set: |config, value| config.height_variation_multiplier = value,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Octaves",
+        step: 1.0,
+        min: 1.0,
+        max: 8.0,
+        get: |config| config.octaves as f64,
+        set: |config, value| config.octaves = value.round() as u32,
+        format: integer,
+    },
+    PlaygroundParameter {
+        name: "Persistence",
+        step: 0.05,
+        min: 0.1,
+        max: 0.9,
+        get: |config| config.persistence,
+        set: |config, value| config.persistence = value,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Lacunarity",
+        step: 0.1,
+        min: 1.5,
+        max: 3.0,
+        get: |config| config.lacunarity as f64,
+        set: |config, value| config.lacunarity = value as f32,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Ridge scale",
+        step: 0.002,
+        min: 0.002,
+        max: 0.05,
+        get: |config| config.ridge_scale as f64,
+        set: |config, value| config.ridge_scale = value as f32,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Ridge height",
+        step: 0.25,
+        min: 0.0,
+        max: 4.0,
+        get: |config| config.ridge_multiplier,
+        set: |config, value| config.ridge_multiplier = value,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Terrace height",
+        step: 1.0,
+        min: 0.0,
+        max: 16.0,
+        get: |config| config.terrace_height as f64,
+        set: |config, value| config.terrace_height = value.round() as i32,
+        format: integer,
+    },
+    PlaygroundParameter {
+        name: "World type",
+        step: 1.0,
+        min: 0.0,
+        max: (WorldType::ALL.len() - 1) as f64,
+        get: |config| {
+            WorldType::ALL
+                .iter()
+                .position(|world_type| *world_type == config.world_type)
+                .unwrap_or_default() as f64
+        },
+        set: |config, value| config.world_type = WorldType::ALL[value.round() as usize],
+        format: |value| WorldType::ALL[value.round() as usize].name().to_string(),
+    },
+    PlaygroundParameter {
+        name: "Density scale",
+        step: 0.01,
+        min: 0.01,
+        max: 0.2,
+        get: |config| config.density_scale as f64,
+        set: |config, value| config.density_scale = value as f32,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "Density strength",
+        step: 1.0,
+        min: 0.0,
+        max: 32.0,
+        get: |config| config.density_strength,
+        set: |config, value| config.density_strength = value,
+        format: integer,
+    },
+    PlaygroundParameter {
+        name: "Island altitude",
+        step: 8.0,
+        min: 80.0,
+        max: 240.0,
+        get: |config| config.island_altitude as f64,
+        set: |config, value| config.island_altitude = value.round() as i32,
+        format: integer,
+    },
+    PlaygroundParameter {
+        name: "River scale",
+        step: 0.001,
+        min: 0.001,
+        max: 0.02,
+        get: |config| config.river_scale as f64,
+        set: |config, value| config.river_scale = value as f32,
+        format: decimal,
+    },
+    PlaygroundParameter {
+        name: "River width",
+        step: 0.01,
+        min: 0.0,
+        max: 0.2,
+        get: |config| config.river_width,
+        set: |config, value| config.river_width = value,
+        format: decimal,
+    },
+];
+
+/// Parameters being tuned, starting from the saved ones
+#[derive(Resource)]
+pub struct NoisePlayground {
+    config: GenerationConfig,
+    needs_redraw: bool,
+}
+
+#[derive(Component)]
+pub struct NoisePlaygroundRoot;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum NoisePlaygroundPreview {
+    Heights,
+    Biomes,
+}
+
+#[derive(Component)]
+pub struct NoisePlaygroundValue(usize);
+
+#[derive(Component)]
+pub struct NoisePlaygroundStatus;
+
+#[derive(Component, Clone, Copy)]
+pub enum NoisePlaygroundButton {
+    Adjust { parameter: usize, steps: f64 },
+    Save,
+}
+
+fn load_playground_config(paths: &GameFolderPaths) -> GenerationConfig {
+    fs::read_to_string(paths.game_folder_path.join(GENERATION_CONFIG_FILE))
+        .ok()
+        .and_then(|content| ron::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_playground_config(
+    config: &GenerationConfig,
+    paths: &GameFolderPaths,
+) -> Result<String, std::io::Error> {
+    let path = paths.game_folder_path.join(GENERATION_CONFIG_FILE);
+    let serialized = ron::ser::to_string_pretty(config, PrettyConfig::new())
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    fs::write(&path, serialized)?;
+    Ok(path.display().to_string())
+}
+
+fn preview_image() -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: PREVIEW_SIZE,
+            height: PREVIEW_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Blue under the sea level, from green to white above it
+fn height_color(height: i32) -> [u8; 4] {
+    if height <= SEA_LEVEL {
+        let depth = ((SEA_LEVEL - height) as f32 / 16.0).clamp(0.0, 1.0);
+        let shade = 1.0 - 0.6 * depth;
+        return [
+            (40.0 * shade) as u8,
+            (110.0 * shade) as u8,
+            (210.0 * shade) as u8,
+            255,
+        ];
+    }
+    let t = ((height - SEA_LEVEL) as f32 / 24.0).clamp(0.0, 1.0);
+    let lerp = |from: f32, to: f32| (from + (to - from) * t) as u8;
+    [
+        lerp(70.0, 240.0),
+        lerp(140.0, 240.0),
+        lerp(60.0, 240.0),
+        255,
+    ]
+}
+
+fn button_node() -> Node {
+    Node {
+        width: Val::Px(24.),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }
+}
+
+pub fn setup_noise_playground(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    paths: Res<GameFolderPaths>,
+) {
+    commands.insert_resource(NoisePlayground {
+        config: load_playground_config(&paths),
+        needs_redraw: true,
+    });
+
+    let heights = images.add(preview_image());
+    let biomes = images.add(preview_image());
+
+    commands
+        .spawn((
+            UiDialog,
+            NoisePlaygroundRoot,
+            StateScoped(GameState::Game),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.),
+                right: Val::Percent(0.),
+                top: Val::Percent(0.),
+                bottom: Val::Percent(0.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            GlobalZIndex(2),
+            Visibility::Hidden,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.)),
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                BorderRadius::all(Val::Px(10.)),
+            ))
+            .with_children(|dialog| {
+                dialog.spawn((Text::new("Noise playground"), TextFont::from_font_size(24.)));
+                dialog
+                    .spawn(Node {
+                        column_gap: Val::Px(8.),
+                        ..default()
+                    })
+                    .with_children(|previews| {
+                        for (preview, image) in [
+                            (NoisePlaygroundPreview::Heights, heights),
+                            (NoisePlaygroundPreview::Biomes, biomes),
+                        ] {
+                            previews.spawn((
+                                preview,
+                                ImageNode::new(image),
+                                Node {
+                                    width: Val::Px(PREVIEW_DISPLAY_SIZE),
+                                    height: Val::Px(PREVIEW_DISPLAY_SIZE),
+                                    ..default()
+                                },
+                            ));
+                        }
+                    });
+
+                for (index, parameter) in PARAMETERS.iter().enumerate() {
+                    dialog
+                        .spawn(Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(6.),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new(parameter.name),
+                                TextFont::from_font_size(16.),
+                                Node {
+                                    width: Val::Px(200.),
+                                    ..default()
+                                },
+                            ));
+                            for (label, steps) in [("-", -1.0), ("+", 1.0)] {
+                                row.spawn((
+                                    NoisePlaygroundButton::Adjust {
+                                        parameter: index,
+                                        steps,
+                                    },
+                                    Button,
+                                    button_node(),
+                                    BorderRadius::all(Val::Px(4.)),
+                                    BackgroundColor(BUTTON_COLOR),
+                                ))
+                                .with_child((Text::new(label), TextFont::from_font_size(16.)));
+                            }
+                            row.spawn((
+                                NoisePlaygroundValue(index),
+                                Text::new(""),
+                                TextFont::from_font_size(16.),
+                            ));
+                        });
+                }
+
+                dialog
+                    .spawn((
+                        NoisePlaygroundButton::Save,
+                        Button,
+                        Node {
+                            padding: UiRect::all(Val::Px(6.)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BorderRadius::all(Val::Px(4.)),
+                        BackgroundColor(BUTTON_COLOR),
+                    ))
+                    .with_child((
+                        Text::new("Save for new worlds"),
+                        TextFont::from_font_size(18.),
+                    ));
+                dialog.spawn((
+                    NoisePlaygroundStatus,
+                    Text::new(""),
+                    TextFont::from_font_size(14.),
+                ));
+            });
+        });
+}
+
+/// Draws both previews centered on `center`, and returns the lowest and highest
+/// generated heights
+fn draw_previews(
+    terrain: &mut TerrainNoise,
+    climate: &mut ClimateNoises,
+    center: IVec2,
+    heights: &mut [u8],
+    biomes: &mut [u8],
+) -> (i32, i32) {
+    let half = PREVIEW_SIZE as i32 / 2;
+    let (mut lowest, mut highest) = (i32::MAX, i32::MIN);
+    for py in 0..PREVIEW_SIZE as i32 {
+        for px in 0..PREVIEW_SIZE as i32 {
+            let x = center.x + (px - half) * PREVIEW_BLOCKS_PER_PIXEL;
+            let z = center.y + (py - half) * PREVIEW_BLOCKS_PER_PIXEL;
+            let height = terrain.height(x, z);
+            lowest = lowest.min(height);
+            highest = highest.max(height);
+
+            let biome =
+                BiomeType::from_climate(calculate_temperature_humidity_with_noises(x, z, climate));
+            let is_player = (px - half).abs() <= 1 && (py - half).abs() <= 1;
+            let index = ((py * PREVIEW_SIZE as i32 + px) * 4) as usize;
+            let (height_color, biome_color) = if is_player {
+                ([255, 0, 0, 255], [255, 255, 255, 255])
+            } else {
+                (height_color(height), biome_overlay_color(biome))
+            };
+            heights[index..index + 4].copy_from_slice(&height_color);
+            biomes[index..index + 4].copy_from_slice(&biome_color);
+        }
+    }
+    (lowest, highest)
+}
+
+/// Opens and closes the playground, applies the button presses and redraws the
+/// previews when a parameter changes or the player enters a new chunk
+#[allow(clippy::too_many_arguments)]
+pub fn noise_playground_system(
+    mut playground: ResMut<NoisePlayground>,
+    mut root: Query<&mut Visibility, With<NoisePlaygroundRoot>>,
+    previews: Query<(&NoisePlaygroundPreview, &ImageNode)>,
+    mut values: Query<(&NoisePlaygroundValue, &mut Text), Without<NoisePlaygroundStatus>>,
+    mut status: Query<&mut Text, With<NoisePlaygroundStatus>>,
+    mut buttons: Query<(
+        &NoisePlaygroundButton,
+        Ref<Interaction>,
+        &mut BackgroundColor,
+    )>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    mut images: ResMut<Assets<Image>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    world_seed: Res<WorldSeed>,
+    paths: Res<GameFolderPaths>,
+    mut last_chunk: Local<Option<IVec2>>,
+) {
+    let (Ok(mut visibility), Ok(mut status)) = (root.single_mut(), status.single_mut()) else {
+        return;
+    };
+
+    if is_action_just_pressed(GameAction::ToggleNoisePlayground, &keyboard_input, &key_map) {
+        *visibility = if *visibility == Visibility::Hidden {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    } else if is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map) {
+        *visibility = Visibility::Hidden;
+    }
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    for (button, interaction, mut background) in buttons.iter_mut() {
+        let color = match *interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => HOVERED_BUTTON_COLOR,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+        if *interaction != Interaction::Pressed || !interaction.is_changed() {
+            continue;
+        }
+
+        match *button {
+            NoisePlaygroundButton::Adjust { parameter, steps } => {
+                let parameter = &PARAMETERS[parameter];
+                let value = (parameter.get)(&playground.config) + parameter.step * steps;
+                (parameter.set)(
+                    &mut playground.config,
+                    value.clamp(parameter.min, parameter.max),
+                );
+                playground.needs_redraw = true;
+            }
+            NoisePlaygroundButton::Save => {
+                status.0 = match save_playground_config(&playground.config, &paths) {
+                    Ok(path) => format!("Saved to {path}"),
+                    Err(err) => format!("Could not save the generation config: {err}"),
+                };
+            }
+        }
+    }
+
+    for (value, mut text) in values.iter_mut() {
+        let parameter = &PARAMETERS[value.0];
+        let formatted = (parameter.format)((parameter.get)(&playground.config));
+        if text.0 != formatted {
+            text.0 = formatted;
+        }
+    }
+
+    let Ok(transform) = player.single() else {
+        return;
+    };
+    let center = IVec2::new(
+        transform.translation.x.floor() as i32,
+        transform.translation.z.floor() as i32,
+    );
+    let chunk = IVec2::new(
+        block_to_chunk_coord(center.x),
+        block_to_chunk_coord(center.y),
+    );
+    if !playground.needs_redraw && *last_chunk == Some(chunk) {
+        return;
+    }
+
+    let mut heights = vec![0; (PREVIEW_SIZE * PREVIEW_SIZE * 4) as usize];
+    let mut biomes = heights.clone();
+    let mut terrain = TerrainNoise::new(world_seed.0, playground.config);
+    let mut climate = ClimateNoises::new(world_seed.0);
+    let (lowest, highest) = draw_previews(
+        &mut terrain,
+        &mut climate,
+        center,
+        &mut heights,
+        &mut biomes,
+    );
+
+    for (preview, image_node) in previews.iter() {
+        let Some(image) = images.get_mut(&image_node.image) else {
+            continue;
+        };
+        image.data = Some(match preview {
+            NoisePlaygroundPreview::Heights => heights.clone(),
+            NoisePlaygroundPreview::Biomes => biomes.clone(),
+        });
+    }
+    status.0 = format!(
+        "Heights from {} to {} over {} blocks",
+        lowest,
+        highest,
+        PREVIEW_SIZE as i32 * PREVIEW_BLOCKS_PER_PIXEL
+    );
+    playground.needs_redraw = false;
+    *last_chunk = Some(chunk);
+}
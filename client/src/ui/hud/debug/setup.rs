@@ -1,7 +1,10 @@
 use super::biome::BiomeText;
+use super::layout::{DebugHudLayout, DebugHudWidget};
 use super::loaded_stats::TimeText;
 use super::loaded_stats::{BlocksNumberText, ChunksNumberText};
+use super::network::NetStatsText;
 use super::targeted_block::BlockText;
+use super::water_audit::WaterAuditText;
 use super::{CoordsText, FpsText};
 use crate::input::data::GameAction;
 use crate::input::keyboard::get_action_keys;
@@ -12,7 +15,7 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub struct HudRoot;
 
-pub fn setup_hud(mut commands: Commands) {
+pub fn setup_hud(mut commands: Commands, layout: Res<DebugHudLayout>) {
     // create our UI root node
     // this is the wrapper/container for the text
     let root = commands
@@ -110,15 +113,30 @@ pub fn setup_hud(mut commands: Commands) {
     let blocks_number_text = spawn_debug_text(&mut commands, BlocksNumberText, "...");
     let chunks_number_text = spawn_debug_text(&mut commands, ChunksNumberText, "...");
     let time_text = spawn_debug_text(&mut commands, TimeText, "Time: N/A");
-    commands.entity(root).add_children(&[
-        text_fps,
-        coords_text,
-        biome_text,
-        blocks_number_text,
-        chunks_number_text,
-        block_text,
-        time_text,
-    ]);
+    let water_audit_text = spawn_debug_text(&mut commands, WaterAuditText, "");
+    let net_stats_text = spawn_debug_text(&mut commands, NetStatsText, "...");
+
+    // Children follow the saved layout, hidden widgets take no room
+    for entry in layout.widgets.iter() {
+        let entity = match entry.widget {
+            DebugHudWidget::Fps => text_fps,
+            DebugHudWidget::Coords => coords_text,
+            DebugHudWidget::Biome => biome_text,
+            DebugHudWidget::Blocks => blocks_number_text,
+            DebugHudWidget::Chunks => chunks_number_text,
+            DebugHudWidget::TargetedBlock => block_text,
+            DebugHudWidget::Time => time_text,
+            DebugHudWidget::WaterAudit => water_audit_text,
+            DebugHudWidget::Network => net_stats_text,
+        };
+        if !entry.visible {
+            commands.entity(entity).insert(Node {
+                display: Display::None,
+                ..default()
+            });
+        }
+        commands.entity(root).add_child(entity);
+    }
 }
 
 /// Toggle the FPS counter when pressing F3
@@ -0,0 +1,215 @@
+//! Compact ECS browser, enabled with `--ecs-snapshot`.
+//!
+//! Unlike the egui inspector, this doesn't rely on reflection being registered
+//! for every type, which makes it cheap enough to ship in release builds. It
+//! shows entity counts by archetype, the systems of each schedule grouped by
+//! module (which is how our plugins are organized), and the value of a few
+//! safelisted resources.
+
+use std::collections::BTreeMap;
+
+use bevy::{ecs::schedule::Schedules, prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::bevy_egui::EguiContext;
+use bevy_renet::renet::RenetClient;
+
+use super::DebugOptions;
+use crate::world::RenderDistance;
+
+/// Seconds between two snapshots, walking every schedule each frame isn't free
+const SNAPSHOT_INTERVAL: f32 = 1.0;
+
+/// Archetypes listed individually, the others are summed up
+const MAX_LISTED_ARCHETYPES: usize = 30;
+
+#[derive(Default)]
+pub struct ArchetypeSummary {
+    pub entities: usize,
+    pub components: Vec<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct EcsSnapshot {
+    last_capture: Option<f32>,
+    pub entity_count: u32,
+    /// Non-empty archetypes, largest first
+    pub archetypes: Vec<ArchetypeSummary>,
+    /// Schedule name -> module -> system names
+    pub systems: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+/// Strips the crate and module path from a type or system name
+fn short_name(name: &str) -> &str {
+    // Generic parameters may contain paths as well
+    let base = name.split('<').next().unwrap_or(name);
+    let start = base.rfind("::").map(|i| i + 2).unwrap_or(0);
+    &name[start..]
+}
+
+/// Module a system is declared in, e.g. `client::ui::hud::chat`
+fn system_module(name: &str) -> &str {
+    let base = name.split('<').next().unwrap_or(name);
+    base.rfind("::").map(|i| &name[..i]).unwrap_or("<unknown>")
+}
+
+/// Runs in `Last`, so that every game schedule is available in `Schedules`
+/// (the running schedule is temporarily removed from it).
+pub fn capture_ecs_snapshot_system(world: &mut World) {
+    let now = world.resource::<Time>().elapsed_secs();
+    let mut snapshot = world.resource_mut::<EcsSnapshot>();
+    if snapshot
+        .last_capture
+        .is_some_and(|last| now - last < SNAPSHOT_INTERVAL)
+    {
+        return;
+    }
+    snapshot.last_capture = Some(now);
+
+    let mut archetypes: Vec<ArchetypeSummary> = world
+        .archetypes()
+        .iter()
+        .filter(|archetype| !archetype.is_empty())
+        .map(|archetype| {
+            let mut components: Vec<String> = archetype
+                .components()
+                .filter_map(|id| world.components().get_name(id))
+                .map(|name| short_name(&name).to_string())
+                .collect();
+            components.sort();
+            ArchetypeSummary {
+                entities: archetype.len(),
+                components,
+            }
+        })
+        .collect();
+    archetypes.sort_by_key(|archetype| std::cmp::Reverse(archetype.entities));
+
+    let mut systems: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for (label, schedule) in world.resource::<Schedules>().iter() {
+        let Ok(schedule_systems) = schedule.systems() else {
+            continue;
+        };
+        let modules = systems.entry(format!("{label:?}")).or_default();
+        for (_, system) in schedule_systems {
+            let name = system.name();
+            modules
+                .entry(system_module(&name).to_string())
+                .or_default()
+                .push(short_name(&name).to_string());
+        }
+    }
+
+    let entity_count = world.entities().len();
+
+    let mut snapshot = world.resource_mut::<EcsSnapshot>();
+    snapshot.entity_count = entity_count;
+    snapshot.archetypes = archetypes;
+    snapshot.systems = systems;
+}
+
+/// Debug representation of the resources that are safe to show to players
+fn safelisted_resources(world: &World) -> Vec<(&'static str, String)> {
+    let mut resources = vec![
+        (
+            "RenderDistance",
+            world
+                .get_resource::<RenderDistance>()
+                .map(|res| format!("{res:?}")),
+        ),
+        (
+            "DebugOptions",
+            world
+                .get_resource::<DebugOptions>()
+                .map(|res| format!("{res:?}")),
+        ),
+    ];
+
+    if let Some(client) = world.get_resource::<RenetClient>() {
+        let info = client.network_info();
+        resources.push((
+            "Network",
+            Some(format!(
+                "rtt: {:.1} ms, packet loss: {:.1} %, sent: {:.1} kB/s, received: {:.1} kB/s",
+                info.rtt,
+                info.packet_loss * 100.,
+                info.bytes_sent_per_second / 1000.,
+                info.bytes_received_per_second / 1000.
+            )),
+        ));
+    }
+
+    resources
+        .into_iter()
+        .map(|(name, value)| (name, value.unwrap_or_else(|| "<missing>".to_string())))
+        .collect()
+}
+
+pub fn ecs_snapshot_ui(world: &mut World) {
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    let resources = safelisted_resources(world);
+    let snapshot = world.resource::<EcsSnapshot>();
+
+    egui::Window::new("ECS snapshot")
+        .default_open(false)
+        .show(egui_context.get_mut(), |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.label(format!("{} entities", snapshot.entity_count));
+
+                egui::CollapsingHeader::new(format!("Archetypes ({})", snapshot.archetypes.len()))
+                    .show(ui, |ui| {
+                        for archetype in snapshot.archetypes.iter().take(MAX_LISTED_ARCHETYPES) {
+                            ui.label(format!(
+                                "{:>6}  {}",
+                                archetype.entities,
+                                archetype.components.join(", ")
+                            ));
+                        }
+                        let remaining: usize = snapshot
+                            .archetypes
+                            .iter()
+                            .skip(MAX_LISTED_ARCHETYPES)
+                            .map(|archetype| archetype.entities)
+                            .sum();
+                        if remaining > 0 {
+                            ui.label(format!("{remaining:>6}  in other archetypes"));
+                        }
+                    });
+
+                egui::CollapsingHeader::new("Systems").show(ui, |ui| {
+                    for (schedule, modules) in snapshot.systems.iter() {
+                        let count: usize = modules.values().map(Vec::len).sum();
+                        egui::CollapsingHeader::new(format!("{schedule} ({count})"))
+                            .id_salt(schedule)
+                            .show(ui, |ui| {
+                                for (module, systems) in modules.iter() {
+                                    egui::CollapsingHeader::new(format!(
+                                        "{module} ({})",
+                                        systems.len()
+                                    ))
+                                    .id_salt((schedule, module))
+                                    .show(ui, |ui| {
+                                        for system in systems {
+                                            ui.label(system);
+                                        }
+                                    });
+                                }
+                            });
+                    }
+                });
+
+                egui::CollapsingHeader::new("Resources")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for (name, value) in resources.iter() {
+                            ui.label(format!("{name}: {value}"));
+                        }
+                    });
+            });
+        });
+}
@@ -0,0 +1,75 @@
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::network::SendGameMessageExtension;
+use crate::KeyMap;
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::ClientToServerMessage;
+use shared::water::WaterAuditReport;
+
+#[derive(Component)]
+pub struct WaterAuditText;
+
+/// Latest water volume audit received from the server
+#[derive(Resource, Default)]
+pub struct WaterAuditState {
+    pub requested: bool,
+    pub report: WaterAuditReport,
+}
+
+pub fn toggle_water_audit_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    mut state: ResMut<WaterAuditState>,
+    mut client: ResMut<RenetClient>,
+) {
+    if !is_action_just_pressed(GameAction::ToggleWaterAudit, &keyboard_input, &key_map) {
+        return;
+    }
+
+    state.requested = !state.requested;
+    client.send_game_message(ClientToServerMessage::SetWaterAudit(state.requested));
+}
+
+pub fn water_audit_text_update_system(
+    mut reports: EventReader<WaterAuditReport>,
+    mut state: ResMut<WaterAuditState>,
+    query: Query<Entity, With<WaterAuditText>>,
+    mut writer: TextUiWriter,
+) {
+    if let Some(report) = reports.read().last() {
+        state.report = report.clone();
+    }
+    if !state.is_changed() {
+        return;
+    }
+
+    let report = &state.report;
+    let text = if !state.requested {
+        String::new()
+    } else if !report.enabled {
+        "Water audit: waiting for server...".to_string()
+    } else {
+        let mut text = format!(
+            "Water audit: {} bodies, {:.0} blocks ({:+.2}), {} violations",
+            report.body_count, report.total_volume, report.net_change, report.total_violations
+        );
+        for violation in report.violations.iter() {
+            text.push_str(&format!(
+                "\n  {} {:+.2} unexplained",
+                violation.anchor,
+                violation.unexplained_change()
+            ));
+        }
+        text
+    };
+
+    for entity in query.iter() {
+        *writer.text(entity, 0) = text.clone();
+    }
+}
+
+/// The audit is per server, so it starts disabled in every game
+pub fn reset_water_audit_system(mut state: ResMut<WaterAuditState>) {
+    *state = WaterAuditState::default();
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use shared::{players::Player, world::StatusEffects};
+
+use super::widgets::{HudAnchor, HudContext, HudLayer, HudWidget};
+use crate::{constants::HOTBAR_CELL_SIZE, player::CurrentPlayerMarker, world::MaterialResource};
+
+/// Status effects of the player, listed in the top right corner with their
+/// remaining time
+#[derive(Component, Default)]
+pub struct EffectsHudRoot;
+
+pub const EFFECTS_HUD_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::TopRight,
+    offset: Vec2::ZERO,
+    layer: HudLayer::Hud,
+    visible_when: HudContext::always,
+};
+
+fn format_remaining(secs: f32) -> String {
+    let secs = secs.ceil() as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn roman_level(level: u32) -> &'static str {
+    match level {
+        1 => "",
+        2 => " II",
+        3 => " III",
+        4 => " IV",
+        _ => " V",
+    }
+}
+
+pub fn effects_hud_system(
+    mut commands: Commands,
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    root: Query<Entity, With<EffectsHudRoot>>,
+    materials: Res<MaterialResource>,
+    mut shown: Local<StatusEffects>,
+) {
+    let (Ok(player), Ok(root), Some(atlas)) = (player.single(), root.single(), &materials.items)
+    else {
+        return;
+    };
+
+    // Rebuilt only when the displayed seconds change
+    let same_as_shown = player.effects.0.len() == shown.0.len()
+        && player.effects.0.iter().zip(shown.0.iter()).all(|(a, b)| {
+            a.kind == b.kind
+                && a.amplifier == b.amplifier
+                && a.remaining_secs().ceil() == b.remaining_secs().ceil()
+        });
+    if same_as_shown {
+        return;
+    }
+    *shown = player.effects.clone();
+
+    commands.entity(root).despawn_related::<Children>();
+    for effect in player.effects.0.iter() {
+        let icon = atlas
+            .sources
+            .handle(
+                atlas.layout.clone_weak(),
+                if let Some(handle) = atlas
+                    .handles
+                    .get(&format!("{:?}", effect.kind.potion()))
+                    .as_ref()
+                {
+                    handle.id()
+                } else {
+                    AssetId::default()
+                },
+            )
+            .unwrap_or_default();
+
+        commands.entity(root).with_children(|root| {
+            root.spawn((
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.),
+                    padding: UiRect::all(Val::Px(4.)),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Node {
+                        width: Val::Px(HOTBAR_CELL_SIZE / 2.),
+                        height: Val::Px(HOTBAR_CELL_SIZE / 2.),
+                        ..default()
+                    },
+                    ImageNode::from_atlas_image(atlas.texture.clone_weak(), icon),
+                ));
+                row.spawn((
+                    Text::new(format!(
+                        "{}{} {}",
+                        effect.kind.name(),
+                        roman_level(effect.level()),
+                        format_remaining(effect.remaining_secs())
+                    )),
+                    TextFont::from_font_size(15.),
+                    TextColor(Color::WHITE),
+                ));
+            });
+        });
+    }
+}
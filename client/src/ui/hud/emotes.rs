@@ -0,0 +1,117 @@
+use super::{UIMode, UiDialog};
+use crate::input::data::GameAction;
+use crate::input::keyboard::{is_action_just_pressed, is_action_just_released};
+use crate::network::SendGameMessageExtension;
+use crate::{GameState, KeyMap};
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::ClientToServerMessage;
+use shared::players::Emote;
+
+/// Distance between the center of the screen and the emote buttons
+const RADIAL_MENU_RADIUS: f32 = 120.0;
+const RADIAL_BUTTON_SIZE: f32 = 80.0;
+
+const BUTTON_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.7);
+const HOVERED_BUTTON_COLOR: Color = Color::srgba(0.35, 0.35, 0.35, 0.9);
+
+/// Radial menu shown while the emote key is held. The emote under the cursor
+/// is played when the key is released, or when it is clicked.
+#[derive(Component)]
+pub struct EmoteMenuRoot;
+
+#[derive(Component)]
+pub struct EmoteButton {
+    pub emote: Emote,
+}
+
+pub fn setup_emote_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            UiDialog,
+            EmoteMenuRoot,
+            StateScoped(GameState::Game),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.),
+                top: Val::Percent(50.),
+                ..default()
+            },
+            GlobalZIndex(2),
+            Visibility::Hidden,
+        ))
+        .with_children(|root| {
+            for (index, emote) in Emote::ALL.iter().enumerate() {
+                // Evenly spread around the center, starting at the top
+                let angle = index as f32 / Emote::ALL.len() as f32 * std::f32::consts::TAU;
+                let offset = Vec2::new(angle.sin(), -angle.cos()) * RADIAL_MENU_RADIUS;
+
+                root.spawn((
+                    EmoteButton { emote: *emote },
+                    Button,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(offset.x - RADIAL_BUTTON_SIZE / 2.),
+                        top: Val::Px(offset.y - RADIAL_BUTTON_SIZE / 2.),
+                        width: Val::Px(RADIAL_BUTTON_SIZE),
+                        height: Val::Px(RADIAL_BUTTON_SIZE),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(BUTTON_COLOR),
+                ))
+                .with_children(|button| {
+                    button.spawn((Text::new(emote.name()), TextFont::from_font_size(18.)));
+                });
+            }
+        });
+}
+
+pub fn emote_menu_system(
+    mut root: Query<&mut Visibility, With<EmoteMenuRoot>>,
+    mut buttons: Query<(&EmoteButton, &Interaction, &mut BackgroundColor)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+    mut client: ResMut<RenetClient>,
+) {
+    let Ok(mut visibility) = root.single_mut() else {
+        return;
+    };
+
+    if *visibility == Visibility::Hidden {
+        if *ui_mode == UIMode::Closed
+            && is_action_just_pressed(GameAction::EmoteMenu, &keyboard_input, &key_map)
+        {
+            *visibility = Visibility::Visible;
+        }
+        return;
+    }
+
+    let released = is_action_just_released(GameAction::EmoteMenu, &keyboard_input, &key_map);
+    let mut selected = None;
+    for (button, interaction, mut background) in buttons.iter_mut() {
+        let color = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => HOVERED_BUTTON_COLOR,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+
+        if *interaction == Interaction::Pressed
+            || (released && *interaction == Interaction::Hovered)
+        {
+            selected = Some(button.emote);
+        }
+    }
+
+    if let Some(emote) = selected {
+        client.send_game_message(ClientToServerMessage::Emote(emote));
+    }
+    if released || selected.is_some() {
+        *visibility = Visibility::Hidden;
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use shared::players::{Player, MAX_PLAYER_HEALTH};
+
+use super::widgets::{HudAnchor, HudContext, HudLayer, HudWidget};
+use crate::{
+    constants::{HOTBAR_CELL_SIZE, MAX_HOTBAR_SLOTS},
+    player::CurrentPlayerMarker,
+};
+
+const HEALTH_BAR_WIDTH: f32 = MAX_HOTBAR_SLOTS as f32 * HOTBAR_CELL_SIZE / 2.;
+const HEALTH_BAR_HEIGHT: f32 = 8.;
+const HEALTH_COLOR: Color = Color::srgb(0.8, 0.15, 0.15);
+/// Color of the bar once the player has less than `LOW_HEALTH` left
+const LOW_HEALTH_COLOR: Color = Color::srgb(1.0, 0.45, 0.1);
+const LOW_HEALTH: f32 = MAX_PLAYER_HEALTH / 4.;
+
+/// Health of the player, right above the hotbar in survival
+#[derive(Component, Default)]
+pub struct HealthBarRoot;
+
+#[derive(Component)]
+pub struct HealthBarFill;
+
+pub const HEALTH_BAR_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::BottomCenter,
+    offset: Vec2::new(0., 114.),
+    layer: HudLayer::Hud,
+    visible_when: HudContext::survival,
+};
+
+pub fn health_bar_system(
+    mut commands: Commands,
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    root: Query<Entity, With<HealthBarRoot>>,
+    mut fill: Query<(&mut Node, &mut BackgroundColor), With<HealthBarFill>>,
+) {
+    let (Ok(player), Ok(root)) = (player.single(), root.single()) else {
+        return;
+    };
+
+    let Ok((mut node, mut background)) = fill.single_mut() else {
+        commands.entity(root).with_children(|root| {
+            root.spawn((
+                Node {
+                    width: Val::Px(HEALTH_BAR_WIDTH),
+                    height: Val::Px(HEALTH_BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            ))
+            .with_child((
+                HealthBarFill,
+                Node {
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(HEALTH_COLOR),
+            ));
+        });
+        return;
+    };
+
+    let width = Val::Percent(100. * (player.health / MAX_PLAYER_HEALTH).clamp(0., 1.));
+    if node.width != width {
+        node.width = width;
+    }
+    let color = if player.health < LOW_HEALTH {
+        LOW_HEALTH_COLOR
+    } else {
+        HEALTH_COLOR
+    };
+    background.set_if_neq(BackgroundColor(color));
+}
@@ -13,7 +13,7 @@ pub struct Hotbar {
 }
 
 pub fn setup_hotbar(mut commands: Commands, materials_resource: Res<MaterialResource>) {
-    let atlas = materials_resource.items.as_ref().unwrap();
+    let atlas = materials_resource.icons.as_ref().unwrap();
 
     commands
         .spawn((
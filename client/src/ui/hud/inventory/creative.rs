@@ -0,0 +1,286 @@
+use super::{UIMode, UiDialog};
+use crate::constants::{HOTBAR_BORDER, HOTBAR_CELL_SIZE, HOTBAR_PADDING};
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::network::SendGameMessageExtension;
+use crate::world::MaterialResource;
+use crate::{GameState, KeyMap};
+use bevy::{prelude::*, ui::FocusPolicy};
+use bevy_renet::renet::RenetClient;
+use bevy_simple_text_input::{
+    TextInput, TextInputInactive, TextInputPlaceholder, TextInputTextColor, TextInputTextFont,
+    TextInputValue,
+};
+use shared::messages::ClientToServerMessage;
+use shared::players::GameMode;
+use shared::world::ItemId;
+
+/// Columns of the catalog grid
+const CATALOG_COLUMNS: u16 = 9;
+
+/// Root of the creative catalog, only opened by creative players
+#[derive(Component)]
+pub struct CreativeCatalogRoot;
+
+#[derive(Component)]
+pub struct CreativeCatalogSearch;
+
+/// Name of the hovered item
+#[derive(Component)]
+pub struct CreativeCatalogHoverText;
+
+#[derive(Component)]
+pub struct CreativeCatalogCell {
+    pub item_id: ItemId,
+}
+
+/// Human readable name of an item, e.g. "Fox Spawn Egg"
+pub fn item_label(item_id: ItemId) -> String {
+    let name = format!("{item_id:?}");
+    let mut label = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            label.push(' ');
+        }
+        label.push(c);
+    }
+    label
+}
+
+pub fn setup_creative_catalog(mut commands: Commands, materials_resource: Res<MaterialResource>) {
+    let atlas = materials_resource.icons.as_ref().unwrap();
+
+    commands
+        .spawn((
+            UiDialog,
+            CreativeCatalogRoot,
+            StateScoped(GameState::Game),
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    bottom: Val::Percent(0.),
+                    top: Val::Percent(0.),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.4)),
+                GlobalZIndex(2),
+                Visibility::Hidden,
+            ),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.)),
+                    row_gap: Val::Px(10.),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                BorderRadius::all(Val::Px(10.)),
+            ))
+            .with_children(|dialog| {
+                dialog.spawn((
+                    Text::new("Creative catalog"),
+                    TextFont {
+                        font_size: 24.,
+                        ..default()
+                    },
+                ));
+
+                dialog.spawn((
+                    CreativeCatalogSearch,
+                    Interaction::default(),
+                    Node {
+                        width: Val::Percent(100.),
+                        padding: UiRect::all(Val::Px(5.)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                    (
+                        TextInput,
+                        TextInputValue("".into()),
+                        TextInputPlaceholder {
+                            value: "Search items...".to_string(),
+                            ..default()
+                        },
+                        TextInputTextFont(TextFont::from_font_size(18.)),
+                        TextInputTextColor(TextColor(Color::WHITE)),
+                        TextInputInactive(true),
+                    ),
+                ));
+
+                dialog
+                    .spawn(Node {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::auto(CATALOG_COLUMNS),
+                        ..default()
+                    })
+                    .with_children(|grid| {
+                        for item_id in ItemId::ALL {
+                            let texture_index = atlas
+                                .sources
+                                .handle(
+                                    atlas.layout.clone_weak(),
+                                    if let Some(handle) =
+                                        atlas.handles.get(&format!("{item_id:?}")).as_ref()
+                                    {
+                                        handle.id()
+                                    } else {
+                                        AssetId::default()
+                                    },
+                                )
+                                .unwrap_or_default();
+
+                            grid.spawn((
+                                CreativeCatalogCell { item_id },
+                                Button,
+                                BorderColor(Color::srgb(0.3, 0.3, 0.3)),
+                                FocusPolicy::Block,
+                                Node {
+                                    width: Val::Px(HOTBAR_CELL_SIZE),
+                                    height: Val::Px(HOTBAR_CELL_SIZE),
+                                    padding: UiRect::all(Val::Px(HOTBAR_PADDING)),
+                                    border: UiRect::all(Val::Px(HOTBAR_BORDER)),
+                                    ..default()
+                                },
+                            ))
+                            .with_children(|cell| {
+                                cell.spawn((
+                                    ImageNode::from_atlas_image(
+                                        atlas.texture.clone_weak(),
+                                        texture_index,
+                                    ),
+                                    Node {
+                                        width: Val::Px(
+                                            HOTBAR_CELL_SIZE
+                                                - 2. * (HOTBAR_PADDING + HOTBAR_BORDER),
+                                        ),
+                                        ..default()
+                                    },
+                                ));
+                            });
+                        }
+                    });
+
+                dialog.spawn((
+                    CreativeCatalogHoverText,
+                    Text::new(""),
+                    TextFont::from_font_size(16.),
+                    Node {
+                        min_height: Val::Px(20.),
+                        ..default()
+                    },
+                ));
+            });
+        });
+}
+
+pub fn render_creative_catalog(
+    (keyboard_input, key_map, ui_mode, game_mode): (
+        Res<ButtonInput<KeyCode>>,
+        Res<KeyMap>,
+        Res<UIMode>,
+        Res<GameMode>,
+    ),
+    mut root_query: Query<&mut Visibility, With<CreativeCatalogRoot>>,
+    mut search_query: Query<
+        (&Interaction, &mut TextInputInactive, &TextInputValue),
+        With<CreativeCatalogSearch>,
+    >,
+    mut cell_query: Query<(
+        &CreativeCatalogCell,
+        &Interaction,
+        &mut BorderColor,
+        &mut Node,
+    )>,
+    clicked_query: Query<(&CreativeCatalogCell, &Interaction), Changed<Interaction>>,
+    mut hover_text_query: Query<&mut Text, With<CreativeCatalogHoverText>>,
+    mut client: ResMut<RenetClient>,
+) {
+    let Ok(mut visibility) = root_query.single_mut() else {
+        return;
+    };
+    let Ok((search_interaction, mut search_inactive, search_value)) = search_query.single_mut()
+    else {
+        return;
+    };
+
+    if *game_mode != GameMode::Creative {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    // Keys typed in the search field must not close the catalog
+    if *ui_mode != UIMode::Typing
+        && is_action_just_pressed(GameAction::ToggleCreativeCatalog, &keyboard_input, &key_map)
+        && ((*visibility == Visibility::Hidden) ^ (*ui_mode != UIMode::Closed))
+    {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+
+    if *visibility == Visibility::Visible
+        && is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map)
+    {
+        *visibility = Visibility::Hidden;
+    }
+
+    if *visibility == Visibility::Hidden {
+        if !search_inactive.0 {
+            search_inactive.0 = true;
+        }
+        return;
+    }
+
+    // The search field only grabs the keyboard once clicked
+    if *search_interaction == Interaction::Pressed {
+        search_inactive.0 = false;
+    } else if !search_inactive.0 && keyboard_input.just_pressed(KeyCode::Enter) {
+        search_inactive.0 = true;
+    }
+
+    let search = search_value.0.trim().to_lowercase();
+    let mut hovered = None;
+
+    for (cell, interaction, mut border, mut node) in cell_query.iter_mut() {
+        let label = item_label(cell.item_id);
+        let display = if search.is_empty() || label.to_lowercase().contains(&search) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if node.display != display {
+            node.display = display;
+        }
+
+        border.0 = if *interaction == Interaction::None {
+            Color::srgb(0.3, 0.3, 0.3)
+        } else {
+            hovered = Some(label);
+            Color::WHITE
+        };
+    }
+
+    if let Ok(mut hover_text) = hover_text_query.single_mut() {
+        let label = hovered.unwrap_or_default();
+        if hover_text.0 != label {
+            hover_text.0 = label;
+        }
+    }
+
+    // The server checks that the player is allowed to take items
+    for (cell, interaction) in clicked_query.iter() {
+        if *interaction == Interaction::Pressed {
+            client.send_game_message(ClientToServerMessage::CreativeTakeItem(cell.item_id));
+        }
+    }
+}
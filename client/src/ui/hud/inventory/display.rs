@@ -219,7 +219,7 @@ pub fn update_inventory_cell(
     atlas: &mut TextureAtlas,
     materials: &MaterialResource,
 ) {
-    let items_atlas = materials.items.as_ref().unwrap();
+    let items_atlas = materials.icons.as_ref().unwrap();
 
     // Set content
     if let Some(fstack) = stack {
@@ -59,11 +59,13 @@ pub fn set_ui_mode(
     *ui_mode = UIMode::Closed;
 }
 
+mod creative;
 mod display;
 pub mod items;
 mod setup;
 
 use bevy_simple_text_input::TextInputInactive;
+pub use creative::*;
 pub use display::*;
 use items::*;
 pub use setup::*;
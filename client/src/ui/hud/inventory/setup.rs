@@ -7,7 +7,7 @@ use bevy::{prelude::*, ui::FocusPolicy};
 use shared::MAX_INVENTORY_SLOTS;
 
 pub fn setup_inventory(mut commands: Commands, materials_resource: Res<MaterialResource>) {
-    let atlas = materials_resource.items.as_ref().unwrap();
+    let atlas = materials_resource.icons.as_ref().unwrap();
 
     // Inventory root: root container for the inventory
     let root = commands
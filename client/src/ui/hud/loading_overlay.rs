@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use shared::players::Player;
+use shared::world::{spawn_ground_chunks, world_position_to_chunk_position, WorldMap};
 
-use crate::{player::CurrentPlayerMarker, GameState};
+use crate::{player::CurrentPlayerMarker, world::ClientWorldMap, GameState};
 
 /// Marker component for the loading overlay UI
 #[derive(Component)]
@@ -52,12 +53,14 @@ pub fn setup_loading_overlay(mut commands: Commands) {
         });
 }
 
-/// Shows/hides the loading overlay based on player's gravity_enabled state
+/// Shows the loading overlay while the player's chunks are missing, and hides it
+/// as soon as the ground under the player and the ring around it can be meshed
 pub fn update_loading_overlay(
-    player_query: Query<&Player, With<CurrentPlayerMarker>>,
+    player_query: Query<(&Player, &Transform), With<CurrentPlayerMarker>>,
     mut overlay_query: Query<&mut Visibility, With<LoadingOverlay>>,
+    world_map: Res<ClientWorldMap>,
 ) {
-    let Ok(player) = player_query.single() else {
+    let Ok((player, transform)) = player_query.single() else {
         return;
     };
 
@@ -65,8 +68,11 @@ pub fn update_loading_overlay(
         return;
     };
 
+    let player_chunk = world_position_to_chunk_position(transform.translation);
+    let ground_loaded = spawn_ground_chunks(player_chunk).all(|chunk| world_map.has_chunk(&chunk));
+
     // Show overlay when gravity is disabled (chunks not loaded yet)
-    *visibility = if player.gravity_enabled {
+    *visibility = if player.gravity_enabled || ground_loaded {
         Visibility::Hidden
     } else {
         Visibility::Visible
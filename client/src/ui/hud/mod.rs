@@ -1,8 +1,18 @@
+pub mod air;
 pub mod chat;
 pub mod debug;
+pub mod effects;
+pub mod emotes;
+pub mod health;
 pub mod hotbar;
 pub mod inventory;
 pub mod loading_overlay;
+pub mod overlays;
+pub mod player_list;
+pub mod prefabs;
 pub mod reticle;
+pub mod toast;
+pub mod waystones;
+pub mod widgets;
 
 pub use inventory::*;
@@ -0,0 +1,319 @@
+//! Boss bars, titles and action bar messages sent by the server, see
+//! `shared::messages::UiOverlay`. They fade in when shown and out when removed
+//! or when their time is up.
+
+use bevy::prelude::*;
+use shared::messages::{BossBar, BossBarColor, UiOverlay, OVERLAY_FADE_SECS};
+
+use super::widgets::{HudAnchor, HudContext, HudLayer, HudWidget};
+
+const BOSS_BAR_WIDTH: f32 = 300.;
+const BOSS_BAR_HEIGHT: f32 = 6.;
+
+/// Overlay shown since `shown_at`, fully faded out at `hidden_at`
+struct TimedOverlay<T> {
+    content: T,
+    shown_at: f32,
+    hidden_at: Option<f32>,
+}
+
+impl<T> TimedOverlay<T> {
+    fn new(content: T, now: f32, duration_secs: Option<f32>) -> Self {
+        Self {
+            content,
+            shown_at: now,
+            hidden_at: duration_secs.map(|duration| now + duration),
+        }
+    }
+
+    fn fade(&self) -> OverlayFade {
+        OverlayFade {
+            shown_at: self.shown_at,
+            hidden_at: self.hidden_at,
+            alpha: 1.,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct UiOverlays {
+    boss_bars: Vec<TimedOverlay<BossBar>>,
+    title: Option<TimedOverlay<(String, String)>>,
+    action_bar: Option<TimedOverlay<String>>,
+}
+
+/// Alpha of the texts and backgrounds of an overlay, following its fades
+#[derive(Component, Clone, Copy)]
+pub struct OverlayFade {
+    shown_at: f32,
+    hidden_at: Option<f32>,
+    /// Alpha once faded in
+    alpha: f32,
+}
+
+impl OverlayFade {
+    fn with_alpha(self, alpha: f32) -> Self {
+        Self { alpha, ..self }
+    }
+
+    fn alpha_at(&self, now: f32) -> f32 {
+        let fade_in = (now - self.shown_at) / OVERLAY_FADE_SECS;
+        let fade_out = self
+            .hidden_at
+            .map_or(1., |hidden_at| (hidden_at - now) / OVERLAY_FADE_SECS);
+        self.alpha * fade_in.min(fade_out).clamp(0., 1.)
+    }
+}
+
+#[derive(Component, Default)]
+pub struct BossBarsRoot;
+
+#[derive(Component, Default)]
+pub struct TitleRoot;
+
+#[derive(Component, Default)]
+pub struct ActionBarRoot;
+
+pub const BOSS_BARS_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::TopCenter,
+    offset: Vec2::ZERO,
+    layer: HudLayer::Hud,
+    visible_when: HudContext::always,
+};
+
+/// Readable over the menus, titles mark important moments
+pub const TITLE_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::Center,
+    offset: Vec2::new(0., -80.),
+    layer: HudLayer::Overlay,
+    visible_when: HudContext::always,
+};
+
+/// Right above the health bar
+pub const ACTION_BAR_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::BottomCenter,
+    offset: Vec2::new(0., 130.),
+    layer: HudLayer::Hud,
+    visible_when: HudContext::always,
+};
+
+fn boss_bar_color(color: BossBarColor) -> Color {
+    match color {
+        BossBarColor::Red => Color::srgb(0.85, 0.15, 0.15),
+        BossBarColor::Blue => Color::srgb(0.2, 0.45, 0.9),
+        BossBarColor::Green => Color::srgb(0.25, 0.8, 0.25),
+        BossBarColor::Yellow => Color::srgb(0.95, 0.85, 0.2),
+        BossBarColor::Purple => Color::srgb(0.65, 0.25, 0.85),
+        BossBarColor::White => Color::WHITE,
+    }
+}
+
+pub fn ui_overlay_update_system(
+    mut events: EventReader<UiOverlay>,
+    mut shown: ResMut<UiOverlays>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    // Rebuilding the overlays is only needed when they change, not every frame
+    let overlays = shown.bypass_change_detection();
+    let mut changed = false;
+
+    for overlay in events.read() {
+        changed = true;
+        match overlay.clone() {
+            UiOverlay::BossBar(bar) => {
+                match overlays
+                    .boss_bars
+                    .iter_mut()
+                    .find(|shown| shown.content.id == bar.id && shown.hidden_at.is_none())
+                {
+                    Some(shown) => shown.content = bar,
+                    None => overlays.boss_bars.push(TimedOverlay::new(bar, now, None)),
+                }
+            }
+            UiOverlay::RemoveBossBar(id) => {
+                for shown in overlays.boss_bars.iter_mut() {
+                    if shown.content.id == id && shown.hidden_at.is_none() {
+                        shown.hidden_at = Some(now + OVERLAY_FADE_SECS);
+                    }
+                }
+            }
+            UiOverlay::Title {
+                title,
+                subtitle,
+                duration_secs,
+            } => {
+                overlays.title = Some(TimedOverlay::new(
+                    (title, subtitle),
+                    now,
+                    Some(duration_secs),
+                ));
+            }
+            UiOverlay::ActionBar {
+                text,
+                duration_secs,
+            } => {
+                overlays.action_bar = Some(TimedOverlay::new(text, now, Some(duration_secs)));
+            }
+        }
+    }
+
+    let expired = |hidden_at: Option<f32>| hidden_at.is_some_and(|hidden_at| hidden_at <= now);
+    let bars = overlays.boss_bars.len();
+    overlays.boss_bars.retain(|bar| !expired(bar.hidden_at));
+    changed |= overlays.boss_bars.len() != bars;
+    if overlays
+        .title
+        .as_ref()
+        .is_some_and(|title| expired(title.hidden_at))
+    {
+        overlays.title = None;
+        changed = true;
+    }
+    if overlays
+        .action_bar
+        .as_ref()
+        .is_some_and(|action_bar| expired(action_bar.hidden_at))
+    {
+        overlays.action_bar = None;
+        changed = true;
+    }
+
+    if changed {
+        shown.set_changed();
+    }
+}
+
+pub fn boss_bars_system(
+    mut commands: Commands,
+    overlays: Res<UiOverlays>,
+    root: Query<Entity, With<BossBarsRoot>>,
+) {
+    let Ok(root) = root.single() else {
+        return;
+    };
+    if !overlays.is_changed() {
+        return;
+    }
+
+    commands.entity(root).despawn_related::<Children>();
+    for bar in overlays.boss_bars.iter() {
+        let fade = bar.fade();
+        commands.entity(root).with_children(|root| {
+            root.spawn(Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(2.),
+                ..default()
+            })
+            .with_children(|column| {
+                column.spawn((
+                    Text::new(bar.content.title.clone()),
+                    TextFont::from_font_size(16.),
+                    TextColor(Color::WHITE),
+                    fade,
+                ));
+                column
+                    .spawn((
+                        Node {
+                            width: Val::Px(BOSS_BAR_WIDTH),
+                            height: Val::Px(BOSS_BAR_HEIGHT),
+                            ..default()
+                        },
+                        BackgroundColor(Color::BLACK),
+                        fade.with_alpha(0.5),
+                    ))
+                    .with_child((
+                        Node {
+                            width: Val::Percent(100. * bar.content.progress.clamp(0., 1.)),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        BackgroundColor(boss_bar_color(bar.content.color)),
+                        fade,
+                    ));
+            });
+        });
+    }
+}
+
+pub fn title_system(
+    mut commands: Commands,
+    overlays: Res<UiOverlays>,
+    root: Query<Entity, With<TitleRoot>>,
+) {
+    let Ok(root) = root.single() else {
+        return;
+    };
+    if !overlays.is_changed() {
+        return;
+    }
+
+    commands.entity(root).despawn_related::<Children>();
+    if let Some(title) = overlays.title.as_ref() {
+        let (text, subtitle) = &title.content;
+        commands.entity(root).with_children(|root| {
+            root.spawn((
+                Text::new(text.clone()),
+                TextFont::from_font_size(48.),
+                TextColor(Color::WHITE),
+                title.fade(),
+            ));
+            if !subtitle.is_empty() {
+                root.spawn((
+                    Text::new(subtitle.clone()),
+                    TextFont::from_font_size(24.),
+                    TextColor(Color::WHITE),
+                    title.fade(),
+                ));
+            }
+        });
+    }
+}
+
+pub fn action_bar_system(
+    mut commands: Commands,
+    overlays: Res<UiOverlays>,
+    root: Query<Entity, With<ActionBarRoot>>,
+) {
+    let Ok(root) = root.single() else {
+        return;
+    };
+    if !overlays.is_changed() {
+        return;
+    }
+
+    commands.entity(root).despawn_related::<Children>();
+    if let Some(action_bar) = overlays.action_bar.as_ref() {
+        commands.entity(root).with_child((
+            Text::new(action_bar.content.clone()),
+            TextFont::from_font_size(18.),
+            TextColor(Color::WHITE),
+            action_bar.fade(),
+        ));
+    }
+}
+
+pub fn overlay_fade_system(
+    time: Res<Time>,
+    mut faded: Query<(
+        &OverlayFade,
+        Option<&mut TextColor>,
+        Option<&mut BackgroundColor>,
+    )>,
+) {
+    let now = time.elapsed_secs();
+    for (fade, text, background) in faded.iter_mut() {
+        let alpha = fade.alpha_at(now);
+        if let Some(mut text) = text {
+            text.0.set_alpha(alpha);
+        }
+        if let Some(mut background) = background {
+            background.0.set_alpha(alpha);
+        }
+    }
+}
+
+pub fn clear_ui_overlays_system(mut overlays: ResMut<UiOverlays>) {
+    *overlays = UiOverlays::default();
+}
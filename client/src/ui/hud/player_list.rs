@@ -0,0 +1,112 @@
+use crate::audio::VoiceMutes;
+use crate::input::data::GameAction;
+use crate::input::keyboard::get_action_keys;
+use crate::ui::hud::UIMode;
+use crate::{GameState, KeyMap};
+use bevy::prelude::*;
+use shared::players::PlayerRoster;
+
+/// Keys toggling the voice mute of the players shown in the list, in order
+const MUTE_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// List of connected players, shown while the player list key is held.
+/// While it is shown, the number keys mute or unmute the voice of the listed players.
+#[derive(Component)]
+pub struct PlayerListRoot;
+
+#[derive(Component)]
+pub struct PlayerListText;
+
+pub fn setup_player_list(mut commands: Commands) {
+    commands
+        .spawn((
+            PlayerListRoot,
+            StateScoped(GameState::Game),
+            (
+                BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                GlobalZIndex(3),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(5.),
+                    left: Val::Percent(40.),
+                    width: Val::Percent(20.),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                Visibility::Hidden,
+            ),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                PlayerListText,
+                Text::new(""),
+                TextFont::from_font_size(18.),
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+        });
+}
+
+pub fn player_list_update_system(
+    mut root: Query<&mut Visibility, With<PlayerListRoot>>,
+    mut text: Query<&mut Text, With<PlayerListText>>,
+    roster: Res<PlayerRoster>,
+    mut mutes: ResMut<VoiceMutes>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+) {
+    let Ok(mut visibility) = root.single_mut() else {
+        return;
+    };
+
+    let held = *ui_mode != UIMode::Typing
+        && get_action_keys(GameAction::ShowPlayerList, &key_map)
+            .into_iter()
+            .any(|key| keyboard_input.pressed(key));
+    let wanted = if held {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != wanted {
+        *visibility = wanted;
+    }
+
+    if !held {
+        return;
+    }
+
+    let entries = roster.entries();
+    for (key, (id, _)) in MUTE_KEYS.iter().zip(entries.iter()) {
+        if keyboard_input.just_pressed(*key) {
+            mutes.toggle(*id);
+        }
+    }
+
+    if !(roster.is_changed() || mutes.is_changed() || visibility.is_changed()) {
+        return;
+    }
+
+    if let Ok(mut text) = text.single_mut() {
+        let mut content = format!("Players online: {}", roster.len());
+        for (index, (id, name)) in entries.iter().enumerate() {
+            content.push_str(&format!("\n{}. {}", index + 1, name));
+            if mutes.is_muted(*id) {
+                content.push_str(" (muted)");
+            }
+        }
+        text.0 = content;
+    }
+}
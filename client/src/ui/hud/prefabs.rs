@@ -0,0 +1,184 @@
+use super::{UIMode, UiDialog};
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::world::prefabs::PrefabLibrary;
+use crate::{GameState, KeyMap};
+use bevy::prelude::*;
+use shared::players::GameMode;
+
+const CELL_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+const HOVERED_CELL_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+const SELECTED_CELL_COLOR: Color = Color::srgb(0.3, 0.5, 0.7);
+/// Columns of the prefab grid
+const PANEL_COLUMNS: u16 = 5;
+const THUMBNAIL_DISPLAY_SIZE: f32 = 64.;
+
+/// Prefab library of a creative player, to pick the prefab to place
+#[derive(Component)]
+pub struct PrefabPanelRoot;
+
+#[derive(Component)]
+pub struct PrefabPanelGrid;
+
+#[derive(Component)]
+pub struct PrefabCell {
+    name: String,
+}
+
+pub fn setup_prefab_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            UiDialog,
+            PrefabPanelRoot,
+            StateScoped(GameState::Game),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.),
+                right: Val::Percent(0.),
+                top: Val::Percent(0.),
+                bottom: Val::Percent(0.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            GlobalZIndex(2),
+            Visibility::Hidden,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.)),
+                    row_gap: Val::Px(8.),
+                    min_width: Val::Px(300.),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                BorderRadius::all(Val::Px(10.)),
+            ))
+            .with_children(|dialog| {
+                dialog.spawn((Text::new("Prefabs"), TextFont::from_font_size(24.)));
+                dialog.spawn((
+                    PrefabPanelGrid,
+                    Node {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::auto(PANEL_COLUMNS),
+                        row_gap: Val::Px(4.),
+                        column_gap: Val::Px(4.),
+                        ..default()
+                    },
+                ));
+                dialog.spawn((
+                    Text::new("Select a prefab, then place it on the targeted block"),
+                    TextFont::from_font_size(16.),
+                ));
+            });
+        });
+}
+
+pub fn prefab_panel_system(
+    mut commands: Commands,
+    (keyboard_input, key_map, ui_mode, game_mode): (
+        Res<ButtonInput<KeyCode>>,
+        Res<KeyMap>,
+        Res<UIMode>,
+        Res<GameMode>,
+    ),
+    mut library: ResMut<PrefabLibrary>,
+    mut root: Query<&mut Visibility, With<PrefabPanelRoot>>,
+    grid: Query<Entity, With<PrefabPanelGrid>>,
+    mut cells: Query<(&PrefabCell, Ref<Interaction>, &mut BackgroundColor)>,
+) {
+    let (Ok(mut visibility), Ok(grid)) = (root.single_mut(), grid.single()) else {
+        return;
+    };
+
+    if *game_mode != GameMode::Creative {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    if *ui_mode != UIMode::Typing
+        && is_action_just_pressed(GameAction::TogglePrefabs, &keyboard_input, &key_map)
+        && ((*visibility == Visibility::Hidden) ^ (*ui_mode != UIMode::Closed))
+    {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+    if *visibility == Visibility::Visible
+        && is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map)
+    {
+        *visibility = Visibility::Hidden;
+    }
+
+    if library.changed {
+        library.changed = false;
+        commands.entity(grid).despawn_related::<Children>();
+        if library.prefabs.is_empty() {
+            commands.entity(grid).with_child((
+                Text::new("Save a region with /prefab pos1, /prefab pos2 and /prefab save <name>"),
+                TextFont::from_font_size(16.),
+                Node {
+                    grid_column: GridPlacement::span(PANEL_COLUMNS),
+                    ..default()
+                },
+            ));
+        }
+        for prefab in library.prefabs.iter() {
+            let mut cell = commands.spawn((
+                PrefabCell {
+                    name: prefab.name.clone(),
+                },
+                Button,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(6.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(4.)),
+                BackgroundColor(CELL_COLOR),
+                ChildOf(grid),
+            ));
+            if let Some(thumbnail) = library.thumbnails.get(&prefab.name) {
+                cell.with_child((
+                    ImageNode::new(thumbnail.clone()),
+                    Node {
+                        width: Val::Px(THUMBNAIL_DISPLAY_SIZE),
+                        height: Val::Px(THUMBNAIL_DISPLAY_SIZE),
+                        ..default()
+                    },
+                ));
+            }
+            cell.with_child((
+                Text::new(prefab.name.clone()),
+                TextFont::from_font_size(14.),
+            ));
+        }
+    }
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    for (cell, interaction, mut background) in cells.iter_mut() {
+        if *interaction == Interaction::Pressed && interaction.is_changed() {
+            library.selected = match library.selected {
+                Some(ref selected) if *selected == cell.name => None,
+                _ => Some(cell.name.clone()),
+            };
+        }
+        let color = match *interaction {
+            _ if library.selected.as_ref() == Some(&cell.name) => SELECTED_CELL_COLOR,
+            Interaction::None => CELL_COLOR,
+            Interaction::Hovered | Interaction::Pressed => HOVERED_CELL_COLOR,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
@@ -1,24 +1,29 @@
 use bevy::prelude::*;
 
-use crate::GameState;
+use super::widgets::{HudAnchor, HudContext, HudLayer, HudWidget};
 
-pub fn spawn_reticle(mut commands: Commands) {
-    // Main container for the reticle
-    commands
-        .spawn((
-            StateScoped(GameState::Game), // Link the reticle to the Game state
-            Node {
-                position_type: PositionType::Absolute,
-                margin: UiRect {
-                    left: Val::Auto,
-                    right: Val::Auto,
-                    top: Val::Auto,
-                    bottom: Val::Auto,
-                },
-                ..default()
-            },
-        ))
-        .with_children(|parent| {
+/// Cross in the middle of the screen, hidden while a dialog is open
+#[derive(Component, Default)]
+pub struct ReticleRoot;
+
+pub const RETICLE_WIDGET: HudWidget = HudWidget {
+    anchor: HudAnchor::Center,
+    offset: Vec2::ZERO,
+    layer: HudLayer::Hud,
+    visible_when: HudContext::ui_closed,
+};
+
+pub fn reticle_system(
+    mut commands: Commands,
+    root: Query<Entity, (With<ReticleRoot>, Without<Children>)>,
+) {
+    let Ok(root) = root.single() else {
+        return;
+    };
+
+    // Zero-sized center of the screen, the bars are placed around it
+    commands.entity(root).with_children(|root| {
+        root.spawn(Node::default()).with_children(|parent| {
             // Horizontal line (horizontal bar of the cross)
             parent.spawn((
                 Node {
@@ -45,4 +50,5 @@ pub fn spawn_reticle(mut commands: Commands) {
                 BackgroundColor(Color::WHITE),
             ));
         });
+    });
 }
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use shared::sets::ClientSet;
+
+/// How long a toast stays on screen, in seconds
+const TOAST_DURATION_SECS: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Error,
+}
+
+/// Send this event to briefly show a message at the top of the screen.
+/// Toasts are not tied to a game state, so they can be shown from menus too.
+#[derive(Event, Debug, Clone)]
+pub struct ToastEvent {
+    pub message: String,
+    pub kind: ToastKind,
+}
+
+impl ToastEvent {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind: ToastKind::Info,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind: ToastKind::Error,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Toast {
+    timer: Timer,
+}
+
+pub fn toast_plugin(app: &mut App) {
+    app.add_event::<ToastEvent>().add_systems(
+        Update,
+        (spawn_toast_system, update_toasts_system)
+            .chain()
+            .in_set(ClientSet::Render),
+    );
+}
+
+fn spawn_toast_system(
+    mut commands: Commands,
+    mut events: EventReader<ToastEvent>,
+    existing: Query<Entity, With<Toast>>,
+) {
+    // Only the most recent toast is displayed, older ones are replaced
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    for entity in existing.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let background = match event.kind {
+        ToastKind::Info => Color::BLACK.with_alpha(0.7),
+        ToastKind::Error => Color::srgba(0.6, 0.05, 0.05, 0.85),
+    };
+
+    commands
+        .spawn((
+            Name::new("Toast"),
+            Toast {
+                timer: Timer::from_seconds(TOAST_DURATION_SECS, TimerMode::Once),
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                left: Val::Percent(25.0),
+                width: Val::Percent(50.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(background),
+            GlobalZIndex(i32::MAX - 1),
+        ))
+        .with_child((
+            Text::new(event.message.clone()),
+            TextFont::from_font_size(14.0),
+            TextColor(Color::WHITE),
+        ));
+}
+
+fn update_toasts_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast)>,
+) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
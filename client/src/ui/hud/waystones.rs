@@ -0,0 +1,179 @@
+use super::UiDialog;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::network::SendGameMessageExtension;
+use crate::{GameState, KeyMap};
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{ClientToServerMessage, WaystoneUpdate};
+
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+const HOVERED_BUTTON_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+const DISABLED_BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+
+/// List of the discovered waystones, opened by using an activated waystone
+#[derive(Component)]
+pub struct WaystoneMenuRoot;
+
+#[derive(Component)]
+pub struct WaystoneMenuList;
+
+#[derive(Component)]
+pub struct WaystoneMenuStatus;
+
+#[derive(Component)]
+pub struct WaystoneButton {
+    from: IVec3,
+    to: IVec3,
+}
+
+pub fn setup_waystone_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            UiDialog,
+            WaystoneMenuRoot,
+            StateScoped(GameState::Game),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.),
+                right: Val::Percent(0.),
+                top: Val::Percent(0.),
+                bottom: Val::Percent(0.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            GlobalZIndex(2),
+            Visibility::Hidden,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.)),
+                    row_gap: Val::Px(8.),
+                    min_width: Val::Px(300.),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.4, 0.4, 0.4)),
+                BorderRadius::all(Val::Px(10.)),
+            ))
+            .with_children(|dialog| {
+                dialog.spawn((Text::new("Waystones"), TextFont::from_font_size(24.)));
+                dialog.spawn((
+                    WaystoneMenuList,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.),
+                        ..default()
+                    },
+                ));
+                dialog.spawn((
+                    WaystoneMenuStatus,
+                    Text::new(""),
+                    TextFont::from_font_size(16.),
+                ));
+            });
+        });
+}
+
+pub fn waystone_menu_system(
+    mut commands: Commands,
+    mut events: EventReader<WaystoneUpdate>,
+    mut root: Query<&mut Visibility, With<WaystoneMenuRoot>>,
+    list: Query<Entity, With<WaystoneMenuList>>,
+    mut status: Query<&mut Text, With<WaystoneMenuStatus>>,
+    mut buttons: Query<(&WaystoneButton, &Interaction, &mut BackgroundColor)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    time: Res<Time>,
+    mut cooldown_end: Local<f32>,
+    mut client: ResMut<RenetClient>,
+) {
+    let (Ok(mut visibility), Ok(list), Ok(mut status)) =
+        (root.single_mut(), list.single(), status.single_mut())
+    else {
+        return;
+    };
+
+    for event in events.read() {
+        let WaystoneUpdate::Menu {
+            from,
+            destinations,
+            cooldown_secs,
+        } = event
+        else {
+            continue;
+        };
+
+        *cooldown_end = time.elapsed_secs() + cooldown_secs;
+        commands.entity(list).despawn_related::<Children>();
+        if destinations.is_empty() {
+            commands.entity(list).with_child((
+                Text::new("Activate other waystones to travel to them"),
+                TextFont::from_font_size(16.),
+            ));
+        }
+        for destination in destinations {
+            commands
+                .spawn((
+                    WaystoneButton {
+                        from: *from,
+                        to: destination.position,
+                    },
+                    Button,
+                    Node {
+                        padding: UiRect::all(Val::Px(6.)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(4.)),
+                    BackgroundColor(BUTTON_COLOR),
+                    ChildOf(list),
+                ))
+                .with_child((
+                    Text::new(destination.name.clone()),
+                    TextFont::from_font_size(18.),
+                ));
+        }
+        *visibility = Visibility::Visible;
+    }
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+    if is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map) {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    let cooldown = (*cooldown_end - time.elapsed_secs()).max(0.0);
+    let status_text = if cooldown > 0.0 {
+        format!("Available in {}s", cooldown.ceil())
+    } else {
+        String::new()
+    };
+    if status.0 != status_text {
+        status.0 = status_text;
+    }
+
+    for (button, interaction, mut background) in buttons.iter_mut() {
+        let color = match interaction {
+            _ if cooldown > 0.0 => DISABLED_BUTTON_COLOR,
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => HOVERED_BUTTON_COLOR,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+
+        if cooldown <= 0.0 && *interaction == Interaction::Pressed {
+            client.send_game_message(ClientToServerMessage::WaystoneTeleport {
+                from: button.from,
+                to: button.to,
+            });
+            *visibility = Visibility::Hidden;
+            break;
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Declarative HUD widgets.
+//!
+//! A widget is a root node placed at an anchor of the screen, on a layer, and shown
+//! while its condition holds. Its content is built by its own update system, below
+//! the root tagged with the widget's marker component, so a new widget is added
+//! with `App::add_hud_widget` without touching the rest of the HUD.
+//!
+//! Widgets are not `UiDialog`s: they never show the mouse nor change the `UIMode`.
+
+use super::UIMode;
+use crate::GameState;
+use bevy::ecs::schedule::ScheduleConfigs;
+use bevy::ecs::system::ScheduleSystem;
+use bevy::prelude::*;
+use shared::players::GameMode;
+use shared::sets::ClientSet;
+
+/// Space between the edge of the screen and the widgets anchored to it
+const HUD_MARGIN: f32 = 12.;
+/// Space between the children of a widget
+const HUD_ROW_GAP: f32 = 4.;
+
+/// Where a widget is placed on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudAnchor {
+    TopCenter,
+    TopRight,
+    Center,
+    BottomCenter,
+}
+
+impl HudAnchor {
+    /// Root node of a widget, moved away from the anchor by `offset` pixels
+    fn node(&self, offset: Vec2) -> Node {
+        let mut node = Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(HUD_ROW_GAP),
+            ..default()
+        };
+
+        let (horizontal, vertical) = match self {
+            HudAnchor::TopCenter => (0, -1),
+            HudAnchor::TopRight => (1, -1),
+            HudAnchor::Center => (0, 0),
+            HudAnchor::BottomCenter => (0, 1),
+        };
+        match horizontal {
+            1 => {
+                node.right = Val::Px(HUD_MARGIN + offset.x);
+                node.align_items = AlignItems::FlexEnd;
+            }
+            _ => {
+                // Centered by stretching over the screen width
+                node.left = Val::Px(offset.x);
+                node.right = Val::Px(-offset.x);
+                node.align_items = AlignItems::Center;
+            }
+        }
+        match vertical {
+            -1 => node.top = Val::Px(HUD_MARGIN + offset.y),
+            1 => node.bottom = Val::Px(HUD_MARGIN + offset.y),
+            _ => {
+                node.top = Val::Px(offset.y);
+                node.bottom = Val::Px(-offset.y);
+                node.justify_content = JustifyContent::Center;
+            }
+        }
+        node
+    }
+}
+
+/// Stacking order of the widgets, relative to the dialogs at `GlobalZIndex(2)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudLayer {
+    /// Under the dialogs, like the hotbar
+    Hud,
+    /// Over the dialogs, for what must stay readable when a menu is open
+    Overlay,
+}
+
+impl HudLayer {
+    fn z_index(&self) -> GlobalZIndex {
+        match self {
+            HudLayer::Hud => GlobalZIndex(1),
+            HudLayer::Overlay => GlobalZIndex(3),
+        }
+    }
+}
+
+/// State the visibility of the widgets depends on
+#[derive(Clone, Copy)]
+pub struct HudContext {
+    pub game_mode: GameMode,
+    pub ui_mode: UIMode,
+}
+
+impl HudContext {
+    pub fn always(&self) -> bool {
+        true
+    }
+
+    pub fn survival(&self) -> bool {
+        self.game_mode == GameMode::Survival
+    }
+
+    pub fn ui_closed(&self) -> bool {
+        self.ui_mode == UIMode::Closed
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HudWidget {
+    pub anchor: HudAnchor,
+    /// Pixels away from the anchor, towards the center of the screen
+    pub offset: Vec2,
+    pub layer: HudLayer,
+    pub visible_when: fn(&HudContext) -> bool,
+}
+
+#[derive(Component)]
+pub struct HudWidgetRoot {
+    visible_when: fn(&HudContext) -> bool,
+}
+
+pub trait HudWidgetAppExt {
+    /// Spawns the root of the widget, tagged with `C`, when entering the game,
+    /// and runs `update` to fill it in while in game
+    fn add_hud_widget<C: Component + Default, M>(
+        &mut self,
+        widget: HudWidget,
+        update: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self;
+}
+
+impl HudWidgetAppExt for App {
+    fn add_hud_widget<C: Component + Default, M>(
+        &mut self,
+        widget: HudWidget,
+        update: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        let update: ScheduleConfigs<ScheduleSystem> = update.into_configs();
+        self.add_systems(OnEnter(GameState::Game), move |mut commands: Commands| {
+            commands.spawn((
+                C::default(),
+                HudWidgetRoot {
+                    visible_when: widget.visible_when,
+                },
+                StateScoped(GameState::Game),
+                widget.anchor.node(widget.offset),
+                widget.layer.z_index(),
+                Visibility::Hidden,
+            ));
+        })
+        .add_systems(
+            Update,
+            update
+                .in_set(ClientSet::Render)
+                .run_if(in_state(GameState::Game)),
+        )
+    }
+}
+
+pub fn hud_widget_visibility_system(
+    game_mode: Res<GameMode>,
+    ui_mode: Res<UIMode>,
+    mut roots: Query<(&HudWidgetRoot, &mut Visibility)>,
+) {
+    let context = HudContext {
+        game_mode: *game_mode,
+        ui_mode: *ui_mode,
+    };
+    for (root, mut visibility) in roots.iter_mut() {
+        let wanted = if (root.visible_when)(&context) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
@@ -69,19 +69,15 @@ pub fn spawn_list_item_row(commands: &mut Commands, config: ListItemConfig) -> L
         .id();
 
     // Spawn secondary text if provided
-    let secondary_txt = if let Some(secondary) = config.secondary_text {
-        Some(
-            commands
-                .spawn((
-                    Text::new(secondary),
-                    secondary_text_font(config.asset_server),
-                    secondary_text_color(),
-                ))
-                .id(),
-        )
-    } else {
-        None
-    };
+    let secondary_txt = config.secondary_text.map(|secondary| {
+        commands
+            .spawn((
+                Text::new(secondary),
+                secondary_text_font(config.asset_server),
+                secondary_text_color(),
+            ))
+            .id()
+    });
 
     let mut children = vec![play_btn, delete_btn, txt];
     if let Some(sec) = secondary_txt {
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use crate::ui::assets::*;
+use crate::ui::style::{big_button_style, text_font, NORMAL_BUTTON};
+use crate::TEXT_COLOR;
+
+use super::{MenuButtonAction, MenuState};
+
+/// Entries shown at most, the rest being counted
+const MAX_SHOWN_DETAILS: usize = 12;
+
+/// Why the client gave up joining a server, shown once back in the menu
+#[derive(Resource, Debug, Clone)]
+pub struct ConnectionError {
+    pub reason: String,
+    /// Entries the reason is about, e.g. the blocks this build doesn't know
+    pub details: Vec<String>,
+}
+
+pub fn connection_error_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    error: Res<ConnectionError>,
+) {
+    let background_image = load_background_image(&asset_server);
+    let button_background_image = load_button_background_image(&asset_server);
+    let font = load_font(&asset_server);
+
+    let mut details = error
+        .details
+        .iter()
+        .take(MAX_SHOWN_DETAILS)
+        .cloned()
+        .collect::<Vec<_>>();
+    if error.details.len() > MAX_SHOWN_DETAILS {
+        details.push(format!(
+            "and {} more",
+            error.details.len() - MAX_SHOWN_DETAILS
+        ));
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::NONE),
+            ImageNode::new(background_image),
+            StateScoped(MenuState::ConnectionError),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Could not join the server"),
+                text_font(font.clone(), 40.0),
+                TextColor(TEXT_COLOR),
+            ));
+            parent.spawn((
+                Text::new(error.reason.clone()),
+                text_font(font.clone(), 24.0),
+                TextColor(TEXT_COLOR),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
+            ));
+            for detail in details {
+                parent.spawn((
+                    Text::new(detail),
+                    secondary_text_font(&asset_server),
+                    white_text_color(),
+                ));
+            }
+
+            parent
+                .spawn((
+                    (
+                        Button,
+                        big_button_style(),
+                        BackgroundColor(NORMAL_BUTTON),
+                        ImageNode::new(button_background_image),
+                    ),
+                    MenuButtonAction::BackToMainMenu,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Back"),
+                        text_font(font, 33.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                });
+        });
+}
+
+pub fn clear_connection_error(mut commands: Commands) {
+    commands.remove_resource::<ConnectionError>();
+}
@@ -1,3 +1,4 @@
+pub mod connection_error;
 pub mod home;
 pub mod multi;
 pub mod pause;
@@ -7,6 +8,7 @@ pub mod solo;
 pub mod splash;
 
 use bevy::prelude::*;
+pub use connection_error::ConnectionError;
 pub use home::*;
 pub use server_connect_loading::*;
 
@@ -15,9 +17,13 @@ use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::app::AppExit;
 use multi::multiplayer_action;
 use settings::controls::{controls_menu_setup, controls_update_system};
+use settings::debug_hud::{debug_hud_menu_action, debug_hud_menu_setup};
+use settings::graphics::{graphics_menu_action, graphics_menu_setup, save_graphics_settings};
 
 use crate::input::keyboard::save_keybindings;
+use crate::ui::hud::debug::save_debug_hud_layout;
 use crate::{GameState, MenuCamera};
+use shared::sets::ClientSet;
 
 use super::button::*;
 
@@ -27,12 +33,29 @@ pub fn menu_plugin(app: &mut App) {
         .add_systems(OnEnter(GameState::Menu), menu_setup)
         // Systems to handle the main menu screen
         .add_systems(OnEnter(MenuState::Main), home_setup)
+        .add_systems(
+            OnEnter(MenuState::ConnectionError),
+            connection_error::connection_error_setup,
+        )
+        .add_systems(
+            OnExit(MenuState::ConnectionError),
+            connection_error::clear_connection_error,
+        )
         // Systems to handle the play menu screen
         .add_systems(
             OnEnter(MenuState::Solo),
             (solo::solo_menu_setup, solo::list_worlds).chain(),
         )
-        .add_systems(Update, solo::solo_action.run_if(in_state(MenuState::Solo)))
+        .add_systems(
+            Update,
+            (
+                solo::solo_action,
+                solo::world_type_button_system,
+                solo::world_inputs_focus_system,
+            )
+                .in_set(ClientSet::Render)
+                .run_if(in_state(MenuState::Solo)),
+        )
         // Systems to handle the settings menu screen
         .add_systems(OnEnter(MenuState::Settings), settings::settings_menu_setup)
         // Systems to handle the display settings screen
@@ -46,26 +69,56 @@ pub fn menu_plugin(app: &mut App) {
         )
         .add_systems(
             Update,
-            (multiplayer_action).run_if(in_state(MenuState::Multi)),
+            (multiplayer_action, multi::finish_handshake_system)
+                .chain()
+                .in_set(ClientSet::Render)
+                .run_if(in_state(MenuState::Multi)),
         )
         .add_systems(OnExit(MenuState::Multi), multi::save_server_list)
         .add_systems(
             Update,
-            controls_update_system.run_if(in_state(MenuState::SettingsControls)),
+            controls_update_system
+                .in_set(ClientSet::Render)
+                .run_if(in_state(MenuState::SettingsControls)),
         )
         // Common systems to all screens that handles buttons behavior
         .add_systems(
             Update,
             (menu_action, escape_button, button_system, mouse_scroll)
+                .in_set(ClientSet::Render)
                 .run_if(in_state(GameState::Menu)),
         )
-        .add_systems(OnEnter(MenuState::SettingsControls), controls_menu_setup);
+        .add_systems(OnEnter(MenuState::SettingsControls), controls_menu_setup)
+        .add_systems(OnEnter(MenuState::SettingsGraphics), graphics_menu_setup)
+        .add_systems(
+            Update,
+            graphics_menu_action
+                .in_set(ClientSet::Render)
+                .run_if(in_state(MenuState::SettingsGraphics)),
+        )
+        .add_systems(OnExit(MenuState::SettingsGraphics), save_graphics_settings)
+        .add_systems(OnEnter(MenuState::SettingsDebugHud), debug_hud_menu_setup)
+        .add_systems(
+            Update,
+            debug_hud_menu_action
+                .in_set(ClientSet::Render)
+                .run_if(in_state(MenuState::SettingsDebugHud)),
+        )
+        .add_systems(OnExit(MenuState::SettingsDebugHud), save_debug_hud_layout);
 }
 
 /// Tag component for scrolling UI lists
-fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>, mut commands: Commands) {
+fn menu_setup(
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut commands: Commands,
+    connection_error: Option<Res<ConnectionError>>,
+) {
     commands.spawn((Camera2d, MenuCamera, StateScoped(GameState::Menu)));
-    menu_state.set(MenuState::Main);
+    menu_state.set(if connection_error.is_some() {
+        MenuState::ConnectionError
+    } else {
+        MenuState::Main
+    });
 }
 
 fn menu_action(
@@ -90,6 +143,8 @@ fn menu_action(
                 }
                 MenuButtonAction::Multi => menu_state.set(MenuState::Multi),
                 MenuButtonAction::SettingsControls => menu_state.set(MenuState::SettingsControls),
+                MenuButtonAction::SettingsGraphics => menu_state.set(MenuState::SettingsGraphics),
+                MenuButtonAction::SettingsDebugHud => menu_state.set(MenuState::SettingsDebugHud),
             }
         }
     }
@@ -106,9 +161,10 @@ fn escape_button(
             MenuState::Main => {
                 app_exit_events.write(AppExit::Success);
             }
-            MenuState::Solo | MenuState::Multi | MenuState::Settings => {
-                next_menu_state.set(MenuState::Main)
-            }
+            MenuState::Solo
+            | MenuState::Multi
+            | MenuState::Settings
+            | MenuState::ConnectionError => next_menu_state.set(MenuState::Main),
             // todo: decide how we want to bypass keyboard set dialog
             // MenuState::SettingsControls => next_menu_state.set(MenuState::Settings),
             _ => (),
@@ -1,12 +1,13 @@
 use super::{MenuButtonAction, MenuState, ScrollingList};
 use crate::constants::SERVER_LIST_SAVE_NAME;
-use crate::network::{TargetServer, TargetServerState};
+use crate::network::{CurrentPlayerProfile, TargetServer, TargetServerState};
 use crate::ui::assets::*;
 use crate::ui::list_item::{spawn_list_item_row, ListItemConfig};
 use crate::ui::style::*;
 use crate::GameState;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, IoTaskPool, Task};
 use bevy::{
     asset::AssetServer,
     color::Color,
@@ -22,10 +23,14 @@ use bevy_simple_text_input::{
     TextInput, TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputValue,
 };
 use ron::{from_str, ser::PrettyConfig};
+use shared::constants::HANDSHAKE_ERROR;
+use shared::transport::secure::{request_connect_token, HandshakeError, ServerHello};
+use shared::transport::{parse_server_address, TransportProtocol};
 use shared::GameFolderPaths;
 use std::{
     fs,
     io::Write,
+    net::SocketAddr,
     path::{Path, PathBuf},
 };
 
@@ -33,6 +38,9 @@ use std::{
 pub struct ServerItem {
     pub name: String,
     pub ip: String,
+    /// Of the identity key of the server, pinned on the first connection
+    #[serde(default)]
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Component, Default)]
@@ -45,8 +53,18 @@ pub enum MultiplayerButtonAction {
     Add,
     Connect(Entity),
     Delete(Entity),
+    /// Pins the new identity of a server, then connects to it
+    TrustIdentity {
+        server: Entity,
+        fingerprint: String,
+    },
+    CancelConnect,
 }
 
+/// Warns that a server showed another identity than the pinned one
+#[derive(Component)]
+pub struct IdentityMismatchDialog;
+
 #[derive(Component)]
 pub struct ServerIpInput;
 
@@ -215,6 +233,7 @@ pub fn multiplayer_menu_setup(
 pub fn add_server_item(
     name: String,
     ip: String,
+    fingerprint: Option<String>,
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     list: &mut ServerList,
@@ -247,6 +266,7 @@ pub fn add_server_item(
         ServerItem {
             name: name.clone(),
             ip: ip.clone(),
+            fingerprint,
         },
     );
 }
@@ -281,6 +301,7 @@ pub fn load_server_list(
         add_server_item(
             "localhost".into(),
             "127.0.0.1:8000".into(),
+            None,
             &mut commands,
             &assets,
             &mut list,
@@ -303,6 +324,7 @@ pub fn load_server_list(
         add_server_item(
             "localhost".into(),
             "127.0.0.1:8000".into(),
+            None,
             &mut commands,
             &assets,
             &mut list,
@@ -315,6 +337,7 @@ pub fn load_server_list(
         add_server_item(
             srv.name,
             srv.ip,
+            srv.fingerprint,
             &mut commands,
             &assets,
             &mut list,
@@ -376,21 +399,209 @@ pub fn save_server_list(list: Query<&ServerList>, game_folder_path: Res<GameFold
     }
 }
 
+/// Spawns the warning shown when `server` presents the identity `fingerprint`
+/// instead of the `pinned` one
+fn spawn_identity_mismatch_dialog(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    server: Entity,
+    srv: &ServerItem,
+    pinned: &str,
+    fingerprint: &str,
+) {
+    let button_background_image = load_button_background_large_image(asset_server);
+    let txt_font = menu_text_font(asset_server);
+    let txt_color = white_text_color();
+    let btn_style = menu_list_button_style();
+
+    commands
+        .spawn((
+            StateScoped(MenuState::Multi),
+            IdentityMismatchDialog,
+            GlobalZIndex(5),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Vw(100.0),
+                height: Val::Vh(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::horizontal(Val::Percent(20.)),
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|dialog| {
+            dialog.spawn((
+                Text::new(format!(
+                    "The identity of {} changed!\n\
+                     Someone may be intercepting the connection, or the server got a new key.\n\n\
+                     Expected {}\nReceived {}",
+                    srv.name, pinned, fingerprint
+                )),
+                txt_font.clone(),
+                txt_color,
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+
+            dialog
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    display: Display::Grid,
+                    grid_template_columns: vec![GridTrack::flex(1.0), GridTrack::flex(1.0)],
+                    column_gap: Val::Px(5.0),
+                    ..default()
+                })
+                .with_children(|wrapper| {
+                    let buttons = [
+                        (
+                            "Connect anyway",
+                            MultiplayerButtonAction::TrustIdentity {
+                                server,
+                                fingerprint: fingerprint.to_string(),
+                            },
+                        ),
+                        ("Cancel", MultiplayerButtonAction::CancelConnect),
+                    ];
+                    for (label, action) in buttons {
+                        wrapper
+                            .spawn((
+                                (
+                                    Button,
+                                    BorderColor(Color::BLACK),
+                                    BackgroundColor(BACKGROUND_COLOR),
+                                    btn_style.clone(),
+                                    ImageNode::new(button_background_image.clone()),
+                                ),
+                                action,
+                            ))
+                            .with_children(|btn| {
+                                btn.spawn((Text::new(label), txt_font.clone(), txt_color));
+                            });
+                    }
+                });
+        });
+}
+
+/// Handshake with a saved server, running on the IO task pool so that the menu
+/// keeps responding while the server is slow or unreachable
+#[derive(Component)]
+pub struct PendingHandshake {
+    server: Entity,
+    address: SocketAddr,
+    protocol: TransportProtocol,
+    task: Task<Result<ServerHello, HandshakeError>>,
+}
+
+/// Starts the handshake with a saved server, which goes through the port of the
+/// server whatever the transport
+fn connect_to_server(server: Entity, list: &ServerList, player_id: u64, commands: &mut Commands) {
+    let Some(srv) = list.servers.get(&server) else {
+        return;
+    };
+    info!("Server : name={}, ip={}", srv.name, srv.ip);
+
+    let Some((address, protocol)) = parse_server_address(&srv.ip) else {
+        error!("Invalid server address: {}", srv.ip);
+        return;
+    };
+
+    let task = IoTaskPool::get().spawn(async move { request_connect_token(address, player_id) });
+    commands.spawn((
+        StateScoped(MenuState::Multi),
+        PendingHandshake {
+            server,
+            address,
+            protocol,
+            task,
+        },
+    ));
+}
+
+/// Connects to the servers whose handshake is done, pinning their identity the
+/// first time. Shows the mismatch dialog instead when the identity differs from
+/// the pinned one.
+pub fn finish_handshake_system(
+    mut commands: Commands,
+    mut handshakes: Query<(Entity, &mut PendingHandshake)>,
+    mut list_query: Query<&mut ServerList>,
+    asset_server: Res<AssetServer>,
+    mut target_server: ResMut<TargetServer>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    for (entity, mut handshake) in handshakes.iter_mut() {
+        let Some(result) = block_on(future::poll_once(&mut handshake.task)) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+
+        let hello = match result {
+            Ok(hello) => hello,
+            Err(err) => {
+                error!("{HANDSHAKE_ERROR}: {err}");
+                continue;
+            }
+        };
+        let Ok(mut list) = list_query.single_mut() else {
+            continue;
+        };
+        let Some(srv) = list.servers.get_mut(&handshake.server) else {
+            continue;
+        };
+        match &srv.fingerprint {
+            Some(pinned) if *pinned != hello.fingerprint => {
+                warn!(
+                    "The identity of {} changed from {} to {}",
+                    srv.name, pinned, hello.fingerprint
+                );
+                let pinned = pinned.clone();
+                spawn_identity_mismatch_dialog(
+                    &mut commands,
+                    &asset_server,
+                    handshake.server,
+                    srv,
+                    &pinned,
+                    &hello.fingerprint,
+                );
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                info!(
+                    "Trusting {} on first use, its fingerprint is {}",
+                    srv.name, hello.fingerprint
+                );
+                srv.fingerprint = Some(hello.fingerprint);
+            }
+        }
+
+        target_server.address = Some(handshake.address);
+        target_server.protocol = handshake.protocol;
+        target_server.connect_token = Some(hello.connect_token);
+        target_server.state = TargetServerState::Initial;
+        game_state.set(GameState::PreGameLoading);
+        menu_state.set(MenuState::Disabled);
+    }
+}
+
 pub fn multiplayer_action(
     queries: (
         Query<(&Interaction, &MultiplayerButtonAction), (Changed<Interaction>, With<Button>)>,
         Query<&TextInputValue, (With<ServerNameInput>, Without<ServerIpInput>)>,
         Query<&TextInputValue, (With<ServerIpInput>, Without<ServerNameInput>)>,
         Query<(Entity, &mut ServerList), With<ServerList>>,
+        Query<Entity, With<IdentityMismatchDialog>>,
+        Query<(), With<PendingHandshake>>,
     ),
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut target_server: ResMut<TargetServer>,
-    mut game_state: ResMut<NextState<GameState>>,
-    mut menu_state: ResMut<NextState<MenuState>>,
     paths: Res<GameFolderPaths>,
+    profile: Res<CurrentPlayerProfile>,
 ) {
-    let (interaction_query, name_query, ip_query, mut list_query) = queries;
+    let (interaction_query, name_query, ip_query, mut list_query, dialog_query, pending_query) =
+        queries;
     if list_query.is_empty() {
         return;
     }
@@ -399,7 +610,7 @@ pub fn multiplayer_action(
 
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
-            match *menu_button_action {
+            match menu_button_action {
                 MultiplayerButtonAction::Add => {
                     if !name_query.is_empty() && !ip_query.is_empty() {
                         let name = name_query.single().unwrap();
@@ -408,6 +619,7 @@ pub fn multiplayer_action(
                         add_server_item(
                             name.0.clone(),
                             ip.0.clone(),
+                            None,
                             &mut commands,
                             &asset_server,
                             &mut list,
@@ -417,22 +629,39 @@ pub fn multiplayer_action(
                     }
                 }
                 MultiplayerButtonAction::Connect(serv_entity) => {
-                    if let Some(srv) = list.servers.get(&serv_entity) {
-                        info!("Server : name={}, ip={}", srv.name, srv.ip);
-
-                        target_server.address = Some(srv.ip.parse().unwrap());
-                        target_server.state = TargetServerState::Initial;
-                        game_state.set(GameState::PreGameLoading);
-                        menu_state.set(MenuState::Disabled);
+                    // the dialog is already asking about a server, or a handshake
+                    // is already running
+                    if !dialog_query.is_empty() || !pending_query.is_empty() {
+                        continue;
                     }
+                    connect_to_server(*serv_entity, &list, profile.id, &mut commands);
                 }
                 MultiplayerButtonAction::Delete(serv_entity) => {
                     debug!("Old list : {:?}", list.servers);
-                    commands.entity(entity).remove_children(&[serv_entity]);
-                    commands.entity(serv_entity).despawn();
-                    list.servers.remove(&serv_entity);
+                    commands.entity(entity).remove_children(&[*serv_entity]);
+                    commands.entity(*serv_entity).despawn();
+                    list.servers.remove(serv_entity);
                     debug!("New list : {:?}", list.servers);
                 }
+                MultiplayerButtonAction::TrustIdentity {
+                    server,
+                    fingerprint,
+                } => {
+                    for dialog in dialog_query.iter() {
+                        commands.entity(dialog).despawn();
+                    }
+                    if let Some(srv) = list.servers.get_mut(server) {
+                        info!("Pinning the new identity {} of {}", fingerprint, srv.name);
+                        srv.fingerprint = Some(fingerprint.clone());
+                    }
+                    // a new handshake, the token of the previous one may have expired
+                    connect_to_server(*server, &list, profile.id, &mut commands);
+                }
+                MultiplayerButtonAction::CancelConnect => {
+                    for dialog in dialog_query.iter() {
+                        commands.entity(dialog).despawn();
+                    }
+                }
             }
         }
     }
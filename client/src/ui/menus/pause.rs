@@ -1,4 +1,5 @@
 use crate::network::save::send_save_request_to_server;
+use crate::network::SendGameMessageExtension;
 use bevy::{
     asset::AssetServer,
     color::{Alpha, Color},
@@ -10,6 +11,7 @@ use bevy::{
     },
 };
 use bevy_renet::renet::RenetClient;
+use shared::messages::ClientToServerMessage;
 use shared::GameFolderPaths;
 
 use crate::{input::keyboard::is_action_just_pressed, GameState, KeyMap};
@@ -150,3 +152,20 @@ pub fn render_pause_menu(
         }
     }
 }
+
+/// Tells the server when the pause menu opens or closes, so that the integrated
+/// server of solo games stops the world meanwhile
+pub fn pause_sync_system(
+    menu: Query<&Visibility, (With<PauseMenu>, Changed<Visibility>)>,
+    mut client: ResMut<RenetClient>,
+    mut paused: Local<bool>,
+) {
+    let Ok(visibility) = menu.single() else {
+        return;
+    };
+    let is_open = *visibility == Visibility::Visible;
+    if is_open != *paused {
+        *paused = is_open;
+        client.send_game_message(ClientToServerMessage::SetPause(is_open));
+    }
+}
@@ -1,7 +1,4 @@
-use crate::{
-    network::{TargetServer, TargetServerState},
-    GameState,
-};
+use crate::{game::PreLoadingCompletion, network::TargetServer, world::ATLAS_COUNT, GameState};
 use bevy::{color::palettes::tailwind::YELLOW_500, prelude::*};
 
 #[derive(Component)]
@@ -10,6 +7,9 @@ pub struct CancelButtonMarker;
 #[derive(Component)]
 pub struct LoadingTextMarker;
 
+#[derive(Component)]
+pub struct TextureProgressTextMarker;
+
 pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((Camera2d, StateScoped(GameState::PreGameLoading)));
 
@@ -37,6 +37,16 @@ pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server:
         LoadingTextMarker,
     );
 
+    let texture_progress_bundle = (
+        Text::new("Loading textures"),
+        TextFont {
+            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+            font_size: 24.0,
+            ..default()
+        },
+        TextureProgressTextMarker,
+    );
+
     let cancel_button_bundle = (
         Text::new("[Cancel]"),
         TextFont {
@@ -51,6 +61,7 @@ pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server:
 
     commands.spawn(root_bundle).with_children(|p| {
         p.spawn(loading_text_bundle);
+        p.spawn(texture_progress_bundle);
         p.spawn(cancel_button_bundle);
     });
 }
@@ -60,6 +71,11 @@ pub fn update_server_connect_loading_screen(
     mut game_state: ResMut<NextState<GameState>>,
     mut target: ResMut<TargetServer>,
     mut loading_text_query: Query<&mut Text, With<LoadingTextMarker>>,
+    mut texture_progress_query: Query<
+        &mut Text,
+        (With<TextureProgressTextMarker>, Without<LoadingTextMarker>),
+    >,
+    loading: Res<PreLoadingCompletion>,
     mut main_counter: Local<u64>,
     mut dot_counter: Local<u64>,
 ) {
@@ -75,14 +91,24 @@ pub fn update_server_connect_loading_screen(
         *dot_counter += 1;
     }
 
+    if loading.is_changed() {
+        for mut text in texture_progress_query.iter_mut() {
+            text.0 = if loading.textures_loaded {
+                "Textures ready".into()
+            } else {
+                format!(
+                    "Packing textures ({}/{})",
+                    loading.atlases_packed, ATLAS_COUNT
+                )
+            };
+        }
+    }
+
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
             info!("Cancel button clicked");
             game_state.set(GameState::Menu);
-            target.address = None;
-            target.username = None;
-            target.session_token = None;
-            target.state = TargetServerState::Initial;
+            target.reset();
         }
     }
 }
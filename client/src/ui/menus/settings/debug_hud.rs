@@ -0,0 +1,166 @@
+use crate::menus::{MenuButtonAction, MenuState};
+use crate::ui::assets::*;
+use crate::ui::hud::debug::DebugHudLayout;
+use crate::TEXT_COLOR;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub enum DebugHudButtonAction {
+    Toggle(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+}
+
+/// Text of the toggle button of the row at this index
+#[derive(Component)]
+pub struct DebugHudRowText(usize);
+
+fn row_label(layout: &DebugHudLayout, index: usize) -> String {
+    layout
+        .widgets
+        .get(index)
+        .map(|entry| {
+            format!(
+                "{}: {}",
+                entry.widget.name(),
+                if entry.visible { "shown" } else { "hidden" }
+            )
+        })
+        .unwrap_or_default()
+}
+
+pub fn debug_hud_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    layout: Res<DebugHudLayout>,
+) {
+    let background_image = load_background_image(&asset_server);
+    let font = load_font(&asset_server);
+
+    let toggle_style = Node {
+        width: Val::Px(400.0),
+        height: Val::Px(40.0),
+        margin: UiRect::all(Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let arrow_style = Node {
+        width: Val::Px(60.0),
+        ..toggle_style.clone()
+    };
+
+    let button_font = TextFont {
+        font: font.clone(),
+        font_size: 25.0,
+        ..default()
+    };
+
+    let button_color = TextColor(TEXT_COLOR);
+
+    commands
+        .spawn((
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::NONE),
+            ),
+            ImageNode::new(background_image),
+            StateScoped(MenuState::SettingsDebugHud),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    for index in 0..layout.widgets.len() {
+                        parent
+                            .spawn(Node {
+                                flex_direction: FlexDirection::Row,
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn((
+                                        (
+                                            Button,
+                                            toggle_style.clone(),
+                                            BackgroundColor(Color::NONE),
+                                        ),
+                                        DebugHudButtonAction::Toggle(index),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            DebugHudRowText(index),
+                                            Text::new(row_label(&layout, index)),
+                                            button_font.clone(),
+                                            button_color,
+                                        ));
+                                    });
+                                for (action, text) in [
+                                    (DebugHudButtonAction::MoveUp(index), "Up"),
+                                    (DebugHudButtonAction::MoveDown(index), "Down"),
+                                ] {
+                                    parent
+                                        .spawn((
+                                            (
+                                                Button,
+                                                arrow_style.clone(),
+                                                BackgroundColor(Color::NONE),
+                                            ),
+                                            action,
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                Text::new(text),
+                                                button_font.clone(),
+                                                button_color,
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    parent
+                        .spawn((
+                            (Button, toggle_style.clone(), BackgroundColor(Color::NONE)),
+                            MenuButtonAction::BackToSettings,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((Text::new("Back"), button_font.clone(), button_color));
+                        });
+                });
+        });
+}
+
+pub fn debug_hud_menu_action(
+    interaction_query: Query<
+        (&Interaction, &DebugHudButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut layout: ResMut<DebugHudLayout>,
+    mut text_query: Query<(&mut Text, &DebugHudRowText)>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            DebugHudButtonAction::Toggle(index) => layout.toggle(*index),
+            DebugHudButtonAction::MoveUp(index) => layout.move_widget(*index, true),
+            DebugHudButtonAction::MoveDown(index) => layout.move_widget(*index, false),
+        }
+        // A move changes two rows, relabel them all
+        for (mut text, row) in text_query.iter_mut() {
+            **text = row_label(&layout, row.0);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::constants::GRAPHICS_SETTINGS_PATH;
+use crate::menus::{MenuButtonAction, MenuState};
+use crate::shaders::water::WaterQualityPreset;
+use crate::ui::assets::*;
+use crate::TEXT_COLOR;
+use bevy::prelude::*;
+use ron::{from_str, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+use std::fs;
+use std::path::Path;
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphicsSettings {
+    #[serde(default)]
+    pub water_quality: WaterQualityPreset,
+}
+
+#[derive(Component)]
+pub enum GraphicsButtonAction {
+    CycleWaterQuality,
+}
+
+/// Marker for the text of the water quality button
+#[derive(Component)]
+pub struct WaterQualityText;
+
+pub fn get_graphics_settings(game_folder_paths: &GameFolderPaths) -> GraphicsSettings {
+    let path = game_folder_paths
+        .assets_folder_path
+        .join(GRAPHICS_SETTINGS_PATH);
+
+    if let Ok(content) = fs::read_to_string(&path) {
+        match from_str::<GraphicsSettings>(&content) {
+            Ok(settings) => return settings,
+            Err(e) => warn!(
+                "Failed to deserialize graphics settings at {:?}, using defaults: {}",
+                path, e
+            ),
+        }
+    }
+
+    GraphicsSettings::default()
+}
+
+fn write_graphics_settings_to_path(
+    settings: &GraphicsSettings,
+    path: &Path,
+) -> Result<(), std::io::Error> {
+    let serialized = ron::ser::to_string_pretty(settings, PrettyConfig::new())
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serialized)
+}
+
+pub fn save_graphics_settings(settings: Res<GraphicsSettings>, paths: Res<GameFolderPaths>) {
+    let path = paths.assets_folder_path.join(GRAPHICS_SETTINGS_PATH);
+    match write_graphics_settings_to_path(&settings, &path) {
+        Ok(_) => info!("Graphics settings successfully saved to {:?}", path),
+        Err(e) => error!("Failed to save graphics settings to {:?}: {}", path, e),
+    }
+}
+
+fn water_quality_label(preset: WaterQualityPreset) -> String {
+    format!("Water: {}", preset.name())
+}
+
+pub fn graphics_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<GraphicsSettings>,
+) {
+    let background_image = load_background_image(&asset_server);
+    let font = load_font(&asset_server);
+
+    let button_style = Node {
+        width: Val::Px(400.0),
+        height: Val::Px(60.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_font = TextFont {
+        font: font.clone(),
+        font_size: 33.0,
+        ..default()
+    };
+
+    let button_color = TextColor(TEXT_COLOR);
+
+    commands
+        .spawn((
+            (
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::NONE),
+            ),
+            ImageNode::new(background_image),
+            StateScoped(MenuState::SettingsGraphics),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            (Button, button_style.clone(), BackgroundColor(Color::NONE)),
+                            GraphicsButtonAction::CycleWaterQuality,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                WaterQualityText,
+                                Text::new(water_quality_label(settings.water_quality)),
+                                button_font.clone(),
+                                button_color,
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            (Button, button_style.clone(), BackgroundColor(Color::NONE)),
+                            MenuButtonAction::BackToSettings,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((Text::new("Back"), button_font.clone(), button_color));
+                        });
+                });
+        });
+}
+
+pub fn graphics_menu_action(
+    interaction_query: Query<
+        (&Interaction, &GraphicsButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut settings: ResMut<GraphicsSettings>,
+    mut text_query: Query<&mut Text, With<WaterQualityText>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            GraphicsButtonAction::CycleWaterQuality => {
+                settings.water_quality = settings.water_quality.next();
+                for mut text in text_query.iter_mut() {
+                    **text = water_quality_label(settings.water_quality);
+                }
+            }
+        }
+    }
+}
@@ -59,6 +59,8 @@ pub fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer
                 .with_children(|parent| {
                     for (action, text) in [
                         (MenuButtonAction::SettingsControls, "Controls"),
+                        (MenuButtonAction::SettingsGraphics, "Graphics"),
+                        (MenuButtonAction::SettingsDebugHud, "Debug HUD"),
                         (MenuButtonAction::BackToMainMenu, "Back"),
                     ] {
                         parent
@@ -1,4 +1,6 @@
 pub mod controls;
+pub mod debug_hud;
+pub mod graphics;
 pub mod menu;
 
 pub use menu::*;
@@ -25,6 +25,7 @@ use bevy_simple_text_input::TextInputTextFont;
 use bevy_simple_text_input::{
     TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputValue,
 };
+use shared::world::{WorldGenPreset, WorldSeed};
 use shared::GameFolderPaths;
 use std::io;
 use std::{
@@ -34,6 +35,10 @@ use std::{
 
 pub struct WorldItem {
     pub name: String,
+    /// Preset of the world if it gets created when loaded, saved worlds keep theirs
+    pub preset: WorldGenPreset,
+    /// Seed of the world if it gets created when loaded, random if unset
+    pub seed: Option<WorldSeed>,
 }
 
 #[derive(Component, Default)]
@@ -51,9 +56,22 @@ pub enum MultiplayerButtonAction {
 #[derive(Component)]
 pub struct WorldNameInput;
 
+#[derive(Component)]
+pub struct WorldSeedInput;
+
+/// Button cycling through the presets of the next created world
+#[derive(Component, Default)]
+pub struct WorldTypeButton(pub WorldGenPreset);
+
+fn world_type_label(preset: WorldGenPreset) -> String {
+    format!("World type: {}", preset.name())
+}
+
 #[derive(Resource, Default, Debug, Clone)]
 pub struct SelectedWorld {
     pub name: Option<String>,
+    pub preset: WorldGenPreset,
+    pub seed: Option<WorldSeed>,
 }
 
 pub fn solo_menu_setup(
@@ -164,6 +182,57 @@ pub fn solo_menu_setup(
                         ),
                     ));
 
+                    wrapper.spawn((
+                        (
+                            BorderColor(BACKGROUND_COLOR),
+                            BackgroundColor(Color::BLACK),
+                            {
+                                let mut style = btn_style.clone();
+                                style.grid_column = GridPlacement::span(2);
+                                style
+                            },
+                        ),
+                        WorldSeedInput,
+                        (
+                            TextInput,
+                            TextInputSettings {
+                                retain_on_submit: true,
+                                mask_character: None,
+                            },
+                            TextInputPlaceholder {
+                                value: "Seed (random if empty)".into(),
+                                ..default()
+                            },
+                            TextInputInactive(true),
+                            TextInputTextFont(txt_font.clone()),
+                            TextInputTextColor(txt_color),
+                            TextInputValue("".to_string()),
+                        ),
+                    ));
+
+                    wrapper
+                        .spawn((
+                            (
+                                Button,
+                                BorderColor(Color::BLACK),
+                                BackgroundColor(BACKGROUND_COLOR),
+                                {
+                                    let mut style = btn_style.clone();
+                                    style.grid_column = GridPlacement::span(2);
+                                    style
+                                },
+                                ImageNode::new(button_background_image.clone()),
+                            ),
+                            WorldTypeButton::default(),
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((
+                                Text::new(world_type_label(WorldGenPreset::default())),
+                                txt_font.clone(),
+                                txt_color,
+                            ));
+                        });
+
                     wrapper
                         .spawn((
                             (
@@ -236,6 +305,8 @@ pub fn list_worlds(
             if world_ron_path.exists() {
                 add_world_item(
                     path_str,
+                    WorldGenPreset::default(),
+                    None,
                     &mut commands,
                     &assets,
                     &mut list,
@@ -252,6 +323,8 @@ pub fn list_worlds(
 
 fn add_world_item(
     name: String,
+    preset: WorldGenPreset,
+    seed: Option<WorldSeed>,
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     list: &mut WorldList,
@@ -287,7 +360,7 @@ fn add_world_item(
     commands.entity(list_entity).add_children(&[entities.row]);
 
     list.worlds
-        .insert(entities.row, WorldItem { name: name.clone() });
+        .insert(entities.row, WorldItem { name, preset, seed });
 }
 
 fn generate_new_world_name(world_list: &WorldList) -> String {
@@ -306,11 +379,54 @@ fn generate_new_world_name(world_list: &WorldList) -> String {
     }
 }
 
+/// Types in the text input last clicked, out of the world name and the seed
+pub fn world_inputs_focus_system(
+    mut inputs: Query<
+        (Entity, &Interaction, &mut TextInputInactive),
+        Or<(With<WorldNameInput>, With<WorldSeedInput>)>,
+    >,
+) {
+    let Some(clicked) = inputs
+        .iter()
+        .find(|(_, interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(entity, ..)| entity)
+    else {
+        return;
+    };
+    for (entity, _, mut inactive) in inputs.iter_mut() {
+        inactive.0 = entity != clicked;
+    }
+}
+
+/// Picks the next preset when the world type button is pressed
+pub fn world_type_button_system(
+    mut buttons: Query<(&Interaction, &mut WorldTypeButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (interaction, mut button, children) in buttons.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let index = WorldGenPreset::ALL
+            .iter()
+            .position(|preset| *preset == button.0)
+            .unwrap_or(0);
+        button.0 = WorldGenPreset::ALL[(index + 1) % WorldGenPreset::ALL.len()];
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = world_type_label(button.0);
+            }
+        }
+    }
+}
+
 pub fn solo_action(
-    (interaction_query, mut name_query, mut list_query): (
+    (interaction_query, mut name_query, mut seed_query, mut list_query, world_type_query): (
         Query<(&Interaction, &MultiplayerButtonAction), (Changed<Interaction>, With<Button>)>,
-        Query<&mut TextInputValue, With<WorldNameInput>>,
+        Query<&mut TextInputValue, (With<WorldNameInput>, Without<WorldSeedInput>)>,
+        Query<&mut TextInputValue, With<WorldSeedInput>>,
         Query<(Entity, &mut WorldList), With<WorldList>>,
+        Query<&WorldTypeButton>,
     ),
     (asset_server, mut menu_state, mut game_state, mut world_map, mut selected_world): (
         Res<AssetServer>,
@@ -344,8 +460,19 @@ pub fn solo_action(
                             name.0.clone()
                         };
 
+                        let preset = world_type_query
+                            .single()
+                            .map(|button| button.0)
+                            .unwrap_or_default();
+                        let seed = seed_query.single_mut().ok().and_then(|mut seed| {
+                            let typed = WorldSeed::from_input(&seed.0);
+                            seed.0 = "".into();
+                            typed
+                        });
                         add_world_item(
                             new_name,
+                            preset,
+                            seed,
                             &mut commands,
                             &asset_server,
                             &mut list,
@@ -362,9 +489,12 @@ pub fn solo_action(
                     if let Some(world) = list.worlds.get(&world_entity) {
                         // update ressource name
                         selected_world.name = Some(world.name.clone());
+                        selected_world.preset = world.preset;
+                        selected_world.seed = world.seed;
 
                         load_event.write(LoadWorldEvent {
                             world_name: world.name.clone(),
+                            seed: world.seed,
                         });
                         game_state.set(GameState::PreGameLoading);
                         menu_state.set(MenuState::Disabled);
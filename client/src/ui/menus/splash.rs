@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::GameState;
+use shared::sets::ClientSet;
 
 // This plugin will display a splash screen with Bevy logo for 1 second before switching to the menu
 pub fn splash_plugin(app: &mut App) {
@@ -9,7 +10,12 @@ pub fn splash_plugin(app: &mut App) {
         // When entering the state, spawn everything needed for this screen
         .add_systems(OnEnter(GameState::Splash), splash_setup)
         // While in this state, run the `countdown` system
-        .add_systems(Update, countdown.run_if(in_state(GameState::Splash)));
+        .add_systems(
+            Update,
+            countdown
+                .in_set(ClientSet::Render)
+                .run_if(in_state(GameState::Splash)),
+        );
 }
 
 // Newtype to use a `Timer` for this screen as a resource
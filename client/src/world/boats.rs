@@ -0,0 +1,126 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{BoatUpdate, ClientToServerMessage, PlayerId};
+use shared::players::Player;
+use shared::water::water_surface_height;
+use shared::world::{Boat, BoatId, BOAT_HALF_SIZE};
+
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::network::SendGameMessageExtension;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::UIMode;
+use crate::ui::menus::settings::graphics::GraphicsSettings;
+use crate::world::ClientWorldMap;
+use crate::{GameState, KeyMap};
+
+const BOAT_COLOR: Color = Color::srgb(0.55, 0.38, 0.2);
+
+/// Boats nobody rides, as last sent by the server
+#[derive(Resource, Default, Debug)]
+pub struct ClientBoats(HashMap<BoatId, Boat>);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoatDisplay {
+    OnWater(BoatId),
+    RiddenBy(PlayerId),
+}
+
+pub fn boat_update_system(mut events: EventReader<BoatUpdate>, mut boats: ResMut<ClientBoats>) {
+    for update in events.read() {
+        for (id, boat) in update.boats.iter() {
+            match boat {
+                Some(boat) => {
+                    boats.0.insert(*id, *boat);
+                }
+                None => {
+                    boats.0.remove(id);
+                }
+            }
+        }
+    }
+}
+
+/// Draws the boats on the water, rocking the ones nobody rides on the animated
+/// waves, and the boats under the players riding them
+pub fn boat_display_system(
+    mut commands: Commands,
+    boats: Res<ClientBoats>,
+    world_map: Res<ClientWorldMap>,
+    players: Query<&Player>,
+    mut displays: Query<(Entity, &BoatDisplay, &mut Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    graphics_settings: Res<GraphicsSettings>,
+    time: Res<Time>,
+) {
+    let elapsed = time.elapsed_secs_wrapped();
+    let amplitude = graphics_settings.water_quality.amplitude();
+    let mut shown: HashMap<BoatDisplay, Transform> = boats
+        .0
+        .iter()
+        .map(|(id, boat)| {
+            let mut position = boat.position;
+            if let Some(water) = boat.water_block(world_map.as_ref()) {
+                position.y = water_surface_height(water.y, position.xz(), elapsed, amplitude);
+            }
+            (
+                BoatDisplay::OnWater(*id),
+                Transform::from_translation(position)
+                    .with_rotation(Quat::from_rotation_y(boat.yaw)),
+            )
+        })
+        .collect();
+    shown.extend(players.iter().filter_map(|player| {
+        let boat = player.boat?;
+        Some((
+            BoatDisplay::RiddenBy(player.id),
+            Transform::from_translation(boat.position)
+                .with_rotation(Quat::from_rotation_y(boat.yaw)),
+        ))
+    }));
+
+    for (entity, display, mut transform) in displays.iter_mut() {
+        match shown.remove(display) {
+            Some(target) => *transform = target,
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (display, transform) in shown {
+        commands.spawn((
+            Name::new("Boat"),
+            display,
+            Mesh3d(meshes.add(Cuboid::from_size(BOAT_HALF_SIZE * 2.0))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: BOAT_COLOR,
+                ..default()
+            })),
+            transform,
+            StateScoped(GameState::Game),
+        ));
+    }
+}
+
+/// Asks the server to get the player off their boat when they sneak
+pub fn leave_boat_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+    mut client: ResMut<RenetClient>,
+) {
+    if *ui_mode == UIMode::Opened
+        || !is_action_just_pressed(GameAction::FlyDown, &keyboard_input, &key_map)
+    {
+        return;
+    }
+    if player.single().is_ok_and(|player| player.boat.is_some()) {
+        client.send_game_message(ClientToServerMessage::LeaveBoat);
+    }
+}
+
+pub fn clear_boats_system(mut boats: ResMut<ClientBoats>) {
+    boats.0.clear();
+}
@@ -3,13 +3,14 @@ use crate::world::materials::MaterialResource;
 use crate::world::time::ClientTime;
 use crate::GameState;
 use crate::{
-    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE, DAY_DURATION_IN_TICKS},
+    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE},
     world::GlobalMaterial,
 };
 use bevy::{
     pbr::{NotShadowCaster, NotShadowReceiver},
     prelude::*,
 };
+use shared::world::day_progress;
 use std::f32::consts::PI;
 
 //
@@ -125,7 +126,7 @@ pub fn update_celestial_bodies(
     time: Res<ClientTime>,
 ) {
     // Calculate the angle for the rotation (normalization between 0 and 1)
-    let normalized_time = (time.0 % DAY_DURATION_IN_TICKS) as f32 / DAY_DURATION_IN_TICKS as f32;
+    let normalized_time = day_progress(time.0);
     let angle = normalized_time * 2.0 * PI;
 
     // Apply the rotation to celestial bodies
@@ -26,6 +26,9 @@ pub struct ClientChunk {
     pub entity: Option<Entity>,
     pub last_mesh_ts: Instant, // When was the last time a mesh was created for this chunk ?
     pub current_lod: LodLevel, // Current LOD level of this chunk's mesh
+    /// Neighbor chunks that weren't loaded when the mesh was made, the chunk is
+    /// meshed again when one of them arrives
+    pub missing_neighbors: Vec<IVec3>,
 }
 
 impl Default for ClientChunk {
@@ -35,6 +38,7 @@ impl Default for ClientChunk {
             entity: None,
             last_mesh_ts: Instant::now(),
             current_lod: LodLevel::default(),
+            missing_neighbors: Vec::new(),
         }
     }
 }
@@ -0,0 +1,69 @@
+//! Going through portals, see `shared::world::dimensions`.
+//!
+//! The client asks the server to change dimension when the player walks into a
+//! portal. Once the server answers, the chunks of the previous dimension have
+//! already been dropped from the map (see `update_world_from_network`) and their
+//! entities, the mobs and the far terrain are despawned here, before the chunks of
+//! the new dimension come in.
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{ClientToServerMessage, DimensionChange};
+use shared::players::Player;
+use shared::world::is_in_portal;
+
+use crate::mob::MobRoot;
+use crate::network::SendGameMessageExtension;
+use crate::player::CurrentPlayerMarker;
+use crate::world::rendering::far_terrain::FarTerrain;
+use crate::world::{ChunkMeshStats, ClientWorldMap};
+
+/// Asks to go through a portal when the player walks into one, once per entry
+pub fn portal_entry_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    mut client: ResMut<RenetClient>,
+    mut was_in_portal: Local<bool>,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+
+    let in_portal = is_in_portal(world_map.as_ref(), player, 0.0);
+    if in_portal && !*was_in_portal {
+        info!("Walked into a portal of the {}", player.dimension.name());
+        client.send_game_message(ClientToServerMessage::ChangeDimension);
+    }
+    *was_in_portal = in_portal;
+}
+
+/// Leaves the previous dimension behind once the server moved the player
+pub fn dimension_change_system(
+    mut commands: Commands,
+    mut events: EventReader<DimensionChange>,
+    mut player: Query<(&mut Player, &mut Transform), With<CurrentPlayerMarker>>,
+    chunks: Query<Entity, With<ChunkMeshStats>>,
+    mobs: Query<Entity, With<MobRoot>>,
+    mut far_terrain: ResMut<FarTerrain>,
+) {
+    let Some(change) = events.read().last() else {
+        return;
+    };
+    info!(
+        "Arrived in the {} at {:?}",
+        change.dimension.name(),
+        change.position
+    );
+
+    for entity in chunks.iter().chain(mobs.iter()) {
+        commands.entity(entity).despawn();
+    }
+    far_terrain.despawn_tiles(&mut commands);
+
+    if let Ok((mut player, mut transform)) = player.single_mut() {
+        player.dimension = change.dimension;
+        player.position = change.position;
+        player.velocity = Vec3::ZERO;
+        transform.translation = change.position;
+    }
+}
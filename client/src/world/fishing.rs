@@ -0,0 +1,120 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use shared::messages::{FishingBobber, FishingUpdate, PlayerId};
+use shared::players::Player;
+use shared::water::water_surface_height;
+use shared::world::{catenary_points, BobberState};
+
+use crate::ui::menus::settings::graphics::GraphicsSettings;
+use crate::GameState;
+
+const LINE_COLOR: Color = Color::srgb(0.85, 0.85, 0.85);
+const LINE_SEGMENTS: usize = 16;
+/// The line hangs a bit, as if it was that much longer than the distance
+const LINE_SLACK: f32 = 1.05;
+const BOBBER_RADIUS: f32 = 0.1;
+/// How far the rod reaches in front of the player
+const ROD_LENGTH: f32 = 1.0;
+/// Depth of the dips of a biting bobber
+const BITE_DIP: f32 = 0.15;
+
+/// Bobbers of the fishing lines, as last sent by the server
+#[derive(Resource, Default, Debug)]
+pub struct FishingBobbers(HashMap<PlayerId, FishingBobber>);
+
+#[derive(Component)]
+pub struct BobberDisplay(PlayerId);
+
+pub fn fishing_update_system(
+    mut events: EventReader<FishingUpdate>,
+    mut bobbers: ResMut<FishingBobbers>,
+) {
+    for update in events.read() {
+        match update.bobber {
+            Some(bobber) => {
+                bobbers.0.insert(update.player, bobber);
+            }
+            None => {
+                bobbers.0.remove(&update.player);
+            }
+        }
+    }
+}
+
+fn rod_tip(player: &Player) -> Vec3 {
+    let forward = player.camera_transform.rotation * Vec3::NEG_Z;
+    player.position + Vec3::Y * (player.height / 2.0) + forward * ROD_LENGTH
+}
+
+/// Puts the bobbers on the animated water surface, dipping them while a fish
+/// bites, and draws the lines from the rods to them
+pub fn fishing_display_system(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    bobbers: Res<FishingBobbers>,
+    players: Query<&Player>,
+    mut displays: Query<(Entity, &BobberDisplay, &mut Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    graphics_settings: Res<GraphicsSettings>,
+    time: Res<Time>,
+) {
+    let elapsed = time.elapsed_secs_wrapped();
+    let amplitude = graphics_settings.water_quality.amplitude();
+    let bobber_position = |bobber: &FishingBobber| {
+        let mut position = bobber.position;
+        if let BobberState::Floating { water_y } = bobber.state {
+            position.y = water_surface_height(water_y, position.xz(), elapsed, amplitude);
+            if bobber.biting {
+                position.y -= BITE_DIP * (1.0 + (elapsed * 20.0).sin()) / 2.0;
+            }
+        }
+        position
+    };
+
+    let mut shown = Vec::new();
+    for (entity, display, mut transform) in displays.iter_mut() {
+        match bobbers.0.get(&display.0) {
+            Some(bobber) => {
+                transform.translation = bobber_position(bobber);
+                shown.push(display.0);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (id, bobber) in bobbers.0.iter() {
+        let position = bobber_position(bobber);
+        if !shown.contains(id) {
+            commands.spawn((
+                Name::new("Fishing bobber"),
+                BobberDisplay(*id),
+                Mesh3d(meshes.add(Sphere::new(BOBBER_RADIUS))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::srgb(0.8, 0.1, 0.1),
+                    ..default()
+                })),
+                Transform::from_translation(position),
+                StateScoped(GameState::Game),
+            ));
+        }
+
+        let Some(player) = players.iter().find(|player| player.id == *id) else {
+            continue;
+        };
+        let tip = rod_tip(player);
+        gizmos.linestrip(
+            catenary_points(
+                tip,
+                position,
+                tip.distance(position) * LINE_SLACK,
+                LINE_SEGMENTS,
+            ),
+            LINE_COLOR,
+        );
+    }
+}
+
+pub fn clear_fishing_bobbers_system(mut bobbers: ResMut<FishingBobbers>) {
+    bobbers.0.clear();
+}
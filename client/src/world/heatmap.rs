@@ -0,0 +1,48 @@
+//! Block activity heatmap asked for by an operator with `/heatmap <kind>`.
+//!
+//! Every chunk of the heatmap is outlined, from green for the quiet ones to red
+//! for the busiest, until `/heatmap off`.
+
+use bevy::prelude::*;
+use shared::messages::HeatmapUpdate;
+use shared::CHUNK_SIZE;
+
+/// Last heatmap sent by the server, if it is shown
+#[derive(Resource, Default, Debug)]
+pub struct ShownHeatmap(pub Option<HeatmapUpdate>);
+
+pub fn heatmap_update_system(
+    mut events: EventReader<HeatmapUpdate>,
+    mut shown: ResMut<ShownHeatmap>,
+) {
+    if let Some(update) = events.read().last() {
+        shown.0 = Some(update.clone());
+    }
+}
+
+pub fn heatmap_display_system(mut gizmos: Gizmos, shown: Res<ShownHeatmap>) {
+    let Some(heatmap) = &shown.0 else {
+        return;
+    };
+    let busiest = heatmap
+        .chunks
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(1) as f32;
+
+    let size = CHUNK_SIZE as f32;
+    for (chunk_pos, count) in heatmap.chunks.iter() {
+        let heat = *count as f32 / busiest;
+        let center = (chunk_pos.as_vec3() + Vec3::splat(0.5)) * size;
+        // Slightly smaller than the chunk, so that the outlines of neighbors don't overlap
+        gizmos.cuboid(
+            Transform::from_translation(center).with_scale(Vec3::splat(size - 0.2)),
+            Color::srgb(heat, 1.0 - heat, 0.0),
+        );
+    }
+}
+
+pub fn clear_heatmap_system(mut shown: ResMut<ShownHeatmap>) {
+    shown.0 = None;
+}
@@ -0,0 +1,125 @@
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use shared::messages::ItemFrameUpdate;
+use shared::world::{
+    item_frame_normal, BlockDirection, BlockId, ItemFrame, WorldMap, ITEM_FRAME_THICKNESS,
+};
+
+use crate::world::{ClientWorldMap, GlobalMaterial, MaterialResource};
+use crate::GameState;
+
+/// Side of the item shown in a frame
+const DISPLAY_SIZE: f32 = 0.5;
+const DISPLAY_THICKNESS: f32 = 0.05;
+
+/// Content of the item frames, as last sent by the server
+#[derive(Resource, Default, Debug)]
+pub struct ClientItemFrames(pub HashMap<IVec3, ItemFrame>);
+
+/// Item shown in the frame at `position`
+#[derive(Component)]
+pub struct ItemFrameDisplay {
+    position: IVec3,
+    direction: BlockDirection,
+    frame: ItemFrame,
+}
+
+pub fn item_frame_update_system(
+    mut events: EventReader<ItemFrameUpdate>,
+    mut frames: ResMut<ClientItemFrames>,
+) {
+    for update in events.read() {
+        for (position, frame) in update.frames.iter() {
+            if frame.item.is_some() {
+                frames.0.insert(*position, *frame);
+            } else {
+                frames.0.remove(position);
+            }
+        }
+    }
+}
+
+fn display_transform(position: IVec3, direction: BlockDirection, frame: &ItemFrame) -> Transform {
+    let normal = item_frame_normal(direction);
+    // Just in front of the board of the frame
+    let offset = -normal * (0.5 - ITEM_FRAME_THICKNESS - DISPLAY_THICKNESS / 2.0);
+    Transform::from_translation(position.as_vec3() + Vec3::splat(0.5) + offset).with_rotation(
+        Quat::from_rotation_arc(Vec3::Z, normal) * Quat::from_rotation_z(frame.angle()),
+    )
+}
+
+/// Shows the items of the frames whose block is loaded, and removes the items
+/// of the frames that were emptied, turned or broken
+pub fn item_frame_display_system(
+    mut commands: Commands,
+    frames: Res<ClientItemFrames>,
+    world_map: Res<ClientWorldMap>,
+    displays: Query<(Entity, &ItemFrameDisplay)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material_resource: Res<MaterialResource>,
+) {
+    let frame_direction = |position: &IVec3| {
+        world_map
+            .get_block_by_coordinates(position)
+            .filter(|block| block.id == BlockId::ItemFrame)
+            .map(|block| block.direction)
+    };
+
+    let mut shown = HashSet::new();
+    for (entity, display) in displays.iter() {
+        if frames.0.get(&display.position) == Some(&display.frame)
+            && frame_direction(&display.position) == Some(display.direction)
+        {
+            shown.insert(display.position);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let (Some(items), Some(material)) = (
+        material_resource.items.as_ref(),
+        material_resource
+            .global_materials
+            .get(&GlobalMaterial::Items),
+    ) else {
+        return;
+    };
+
+    for (position, frame) in frames.0.iter() {
+        if shown.contains(position) {
+            continue;
+        }
+        let (Some(direction), Some(item)) = (frame_direction(position), frame.item) else {
+            continue;
+        };
+
+        let mut mesh = Cuboid::new(DISPLAY_SIZE, DISPLAY_SIZE, DISPLAY_THICKNESS)
+            .mesh()
+            .build();
+        if let (Some(VertexAttributeValues::Float32x2(uv_attribute)), Some(uv_coords)) = (
+            mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0),
+            items.uvs.get(&format!("{item:?}")),
+        ) {
+            for uv in uv_attribute.iter_mut() {
+                uv[0] = uv[0].clamp(uv_coords.u0, uv_coords.u1);
+                uv[1] = uv[1].clamp(uv_coords.v0, uv_coords.v1);
+            }
+        }
+
+        commands.spawn((
+            ItemFrameDisplay {
+                position: *position,
+                direction,
+                frame: *frame,
+            },
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material.clone_weak()),
+            display_transform(*position, direction, frame),
+            StateScoped(GameState::Game),
+        ));
+    }
+}
+
+pub fn clear_item_frames_system(mut frames: ResMut<ClientItemFrames>) {
+    frames.0.clear();
+}
@@ -1,7 +1,16 @@
+pub mod boats;
 pub mod celestial;
 pub mod data;
+pub mod dimensions;
+pub mod fishing;
+pub mod heatmap;
+pub mod item_frames;
+pub mod prefabs;
 pub mod rendering;
 pub mod time;
+pub mod water_prediction;
+pub mod waystones;
+pub mod weather;
 
 pub use data::*;
 pub use rendering::*;
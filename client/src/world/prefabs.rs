@@ -0,0 +1,375 @@
+//! Prefab library of the player, see `shared::world::prefabs`.
+//!
+//! Prefabs are kept in the `prefabs/` folder of the game, so they can be built on
+//! any server. Each one gets a top-down thumbnail for the prefab panel, drawn from
+//! the top texture of its highest blocks. The selected prefab follows the targeted
+//! block as an outline and is sent to the server with the place key.
+
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_renet::renet::RenetClient;
+use ron::ser::PrettyConfig;
+use shared::messages::ClientToServerMessage;
+use shared::players::{GameMode, ViewMode};
+use shared::world::{
+    raycast, BlockData, BlockDirection, BlockId, FaceDirectionExt, Prefab, PrefabCommand,
+};
+use shared::GameFolderPaths;
+
+use super::rendering::voxel::VoxelShape;
+use super::{ClientWorldMap, MaterialResource};
+use crate::constants::PREFABS_PATH;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::network::SendGameMessageExtension;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::toast::ToastEvent;
+use crate::ui::hud::UIMode;
+use crate::KeyMap;
+
+/// Side of a prefab thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 48;
+/// Brightness of the lowest blocks of a thumbnail, the highest being fully lit
+const THUMBNAIL_LOW_SHADE: f32 = 0.5;
+
+/// A `/prefab` command typed in the chat
+#[derive(Event, Debug, Clone)]
+pub struct PrefabCommandEvent(pub PrefabCommand);
+
+#[derive(Resource, Default)]
+pub struct PrefabLibrary {
+    /// Sorted by name
+    pub prefabs: Vec<Prefab>,
+    pub thumbnails: HashMap<String, Handle<Image>>,
+    pub selected: Option<String>,
+    /// Corners of the region to save, from `/prefab pos1` and `/prefab pos2`
+    corners: [Option<IVec3>; 2],
+    /// Set when prefabs or thumbnails change, so that the panel gets rebuilt
+    pub changed: bool,
+}
+
+impl PrefabLibrary {
+    pub fn selected_prefab(&self) -> Option<&Prefab> {
+        let selected = self.selected.as_ref()?;
+        self.prefabs.iter().find(|prefab| &prefab.name == selected)
+    }
+
+    fn insert(&mut self, prefab: Prefab) {
+        self.remove(&prefab.name);
+        let index = self
+            .prefabs
+            .partition_point(|other| other.name < prefab.name);
+        self.prefabs.insert(index, prefab);
+        self.changed = true;
+    }
+
+    fn remove(&mut self, name: &str) -> bool {
+        let count = self.prefabs.len();
+        self.prefabs.retain(|prefab| prefab.name != name);
+        self.thumbnails.remove(name);
+        if self.selected.as_deref() == Some(name) {
+            self.selected = None;
+        }
+        self.changed = true;
+        self.prefabs.len() != count
+    }
+}
+
+fn prefabs_folder(paths: &GameFolderPaths) -> PathBuf {
+    paths.game_folder_path.join(PREFABS_PATH)
+}
+
+fn prefab_path(paths: &GameFolderPaths, name: &str) -> PathBuf {
+    prefabs_folder(paths).join(format!("{name}.ron"))
+}
+
+fn write_prefab(paths: &GameFolderPaths, prefab: &Prefab) -> Result<(), std::io::Error> {
+    let serialized = ron::ser::to_string_pretty(prefab, PrettyConfig::new())
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    fs::create_dir_all(prefabs_folder(paths))?;
+    fs::write(prefab_path(paths, &prefab.name), serialized)
+}
+
+pub fn load_prefab_library(mut library: ResMut<PrefabLibrary>, paths: Res<GameFolderPaths>) {
+    *library = PrefabLibrary {
+        changed: true,
+        ..default()
+    };
+
+    let folder = prefabs_folder(&paths);
+    let Ok(entries) = fs::read_dir(&folder) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_none_or(|extension| extension != "ron") {
+            continue;
+        }
+        let prefab = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| ron::from_str::<Prefab>(&content).map_err(|e| e.to_string()));
+        match prefab {
+            Ok(prefab) => library.insert(prefab),
+            Err(e) => warn!("Failed to load prefab {:?}: {}", path, e),
+        }
+    }
+    info!("Loaded {} prefabs from {:?}", library.prefabs.len(), folder);
+}
+
+/// Marks corners, saves and deletes prefabs from the chat commands
+pub fn prefab_command_system(
+    mut events: EventReader<PrefabCommandEvent>,
+    mut library: ResMut<PrefabLibrary>,
+    mut toasts: EventWriter<ToastEvent>,
+    world_map: Res<ClientWorldMap>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    camera: Query<&Transform, (With<Camera>, Without<CurrentPlayerMarker>)>,
+    view_mode: Res<ViewMode>,
+    paths: Res<GameFolderPaths>,
+) {
+    for PrefabCommandEvent(command) in events.read() {
+        match command {
+            PrefabCommand::FirstCorner | PrefabCommand::SecondCorner => {
+                let (Ok(player), Ok(camera)) = (player.single(), camera.single()) else {
+                    continue;
+                };
+                let Some(target) =
+                    raycast::raycast(world_map.as_ref(), camera, &player.translation, *view_mode)
+                else {
+                    toasts.write(ToastEvent::error("Look at a block to mark a corner"));
+                    continue;
+                };
+                let index = usize::from(*command == PrefabCommand::SecondCorner);
+                library.corners[index] = Some(target.position);
+                toasts.write(ToastEvent::info(format!(
+                    "Corner {} set to {}, {}, {}",
+                    index + 1,
+                    target.position.x,
+                    target.position.y,
+                    target.position.z
+                )));
+            }
+            PrefabCommand::Save(name) => {
+                let [Some(corner), Some(other_corner)] = library.corners else {
+                    toasts.write(ToastEvent::error(
+                        "Mark both corners with /prefab pos1 and /prefab pos2 first",
+                    ));
+                    continue;
+                };
+                let prefab =
+                    match Prefab::from_region(world_map.as_ref(), name, corner, other_corner) {
+                        Ok(prefab) => prefab,
+                        Err(error) => {
+                            toasts.write(ToastEvent::error(error.message()));
+                            continue;
+                        }
+                    };
+                if let Err(e) = write_prefab(&paths, &prefab) {
+                    error!("Failed to save prefab {}: {}", name, e);
+                    toasts.write(ToastEvent::error(format!("Could not save {name}")));
+                    continue;
+                }
+                info!("Saved prefab {} of {} blocks", name, prefab.blocks.len());
+                toasts.write(ToastEvent::info(format!(
+                    "Saved {name}, {} blocks",
+                    prefab.blocks.len()
+                )));
+                library.insert(prefab);
+            }
+            PrefabCommand::Delete(name) => {
+                if !library.remove(name) {
+                    toasts.write(ToastEvent::error(format!("No prefab named {name}")));
+                    continue;
+                }
+                if let Err(e) = fs::remove_file(prefab_path(&paths, name)) {
+                    warn!("Failed to delete prefab {}: {}", name, e);
+                }
+                toasts.write(ToastEvent::info(format!("Deleted {name}")));
+            }
+        }
+    }
+}
+
+/// Average color of the top face of a block, as it is tinted in the world
+fn block_top_color(block: BlockId, materials: &MaterialResource, images: &Assets<Image>) -> Color {
+    let shape = VoxelShape::create_from_block(&BlockData::new(block, BlockDirection::Front));
+    let top = shape
+        .faces
+        .iter()
+        .find(|face| face.normals.first() == Some(&[0.0, 1.0, 0.0]))
+        .or(shape.faces.first());
+    let Some(top) = top else {
+        return Color::WHITE;
+    };
+    let tint = top.colors.first().copied().unwrap_or([1.0; 4]);
+    let texture = materials
+        .blocks
+        .as_ref()
+        .and_then(|blocks| blocks.handles.get(&top.texture))
+        .and_then(|handle| images.get(handle));
+
+    let mut sum = Vec4::ZERO;
+    if let Some(texture) = texture {
+        let size = texture.size();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                if let Ok(color) = texture.get_color_at(x, y) {
+                    let color = color.to_srgba();
+                    // Transparent pixels, like the gaps of leaves, don't count
+                    sum += Vec4::new(color.red, color.green, color.blue, 1.0) * color.alpha;
+                }
+            }
+        }
+    }
+    if sum.w == 0.0 {
+        return Color::srgb(tint[0], tint[1], tint[2]);
+    }
+    Color::srgb(
+        sum.x / sum.w * tint[0],
+        sum.y / sum.w * tint[1],
+        sum.z / sum.w * tint[2],
+    )
+}
+
+/// Top-down view of the prefab, lower blocks being darker
+fn render_thumbnail(prefab: &Prefab, color_of: &mut impl FnMut(BlockId) -> Color) -> Image {
+    let mut highest = HashMap::<(i32, i32), (i32, BlockId)>::new();
+    for (offset, block) in prefab.blocks.iter() {
+        let column = highest
+            .entry((offset.x, offset.z))
+            .or_insert((offset.y, block.id));
+        if offset.y >= column.0 {
+            *column = (offset.y, block.id);
+        }
+    }
+
+    let mut thumbnail = Image::new_fill(
+        Extent3d {
+            width: THUMBNAIL_SIZE,
+            height: THUMBNAIL_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    // Blocks stay square, the shorter side of the prefab is centered
+    let side = prefab.size.x.max(prefab.size.z) as f32;
+    let margin = Vec2::new(side - prefab.size.x as f32, side - prefab.size.z as f32) / 2.0;
+    for py in 0..THUMBNAIL_SIZE {
+        for px in 0..THUMBNAIL_SIZE {
+            let x = ((px as f32 + 0.5) / THUMBNAIL_SIZE as f32 * side - margin.x).floor();
+            let z = ((py as f32 + 0.5) / THUMBNAIL_SIZE as f32 * side - margin.y).floor();
+            let Some((y, block)) = highest.get(&(x as i32, z as i32)) else {
+                continue;
+            };
+            let height = (*y + 1) as f32 / prefab.size.y as f32;
+            let shade = THUMBNAIL_LOW_SHADE + (1.0 - THUMBNAIL_LOW_SHADE) * height;
+            let color = color_of(*block).to_srgba();
+            let _ = thumbnail.set_color_at(
+                px,
+                py,
+                Color::srgb(color.red * shade, color.green * shade, color.blue * shade),
+            );
+        }
+    }
+    thumbnail
+}
+
+/// Draws the thumbnails of the prefabs that don't have one yet
+pub fn prefab_thumbnail_system(
+    mut library: ResMut<PrefabLibrary>,
+    materials: Res<MaterialResource>,
+    mut images: ResMut<Assets<Image>>,
+    mut colors: Local<HashMap<BlockId, Color>>,
+) {
+    let library = library.as_mut();
+    for prefab in library.prefabs.iter() {
+        if library.thumbnails.contains_key(&prefab.name) {
+            continue;
+        }
+        let thumbnail = render_thumbnail(prefab, &mut |block| {
+            *colors
+                .entry(block)
+                .or_insert_with(|| block_top_color(block, &materials, &images))
+        });
+        library
+            .thumbnails
+            .insert(prefab.name.clone(), images.add(thumbnail));
+        library.changed = true;
+    }
+}
+
+/// Outlines the selected prefab against the targeted block, and asks the server
+/// to build it with the place key
+pub fn prefab_placement_system(
+    (keyboard_input, key_map, ui_mode, game_mode): (
+        Res<ButtonInput<KeyCode>>,
+        Res<KeyMap>,
+        Res<UIMode>,
+        Res<GameMode>,
+    ),
+    library: Res<PrefabLibrary>,
+    world_map: Res<ClientWorldMap>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    camera: Query<&Transform, (With<Camera>, Without<CurrentPlayerMarker>)>,
+    view_mode: Res<ViewMode>,
+    mut gizmos: Gizmos,
+    mut client: ResMut<RenetClient>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    // The region being selected is outlined too, so that it can be checked before saving
+    if let [Some(corner), Some(other_corner)] = library.corners {
+        let min = corner.min(other_corner).as_vec3();
+        let max = corner.max(other_corner).as_vec3() + Vec3::ONE;
+        gizmos.cuboid(
+            Transform::from_translation((min + max) / 2.0).with_scale(max - min),
+            Color::srgb(1.0, 0.8, 0.0),
+        );
+    }
+
+    if *game_mode != GameMode::Creative || *ui_mode != UIMode::Closed {
+        return;
+    }
+    let Some(prefab) = library.selected_prefab() else {
+        return;
+    };
+    let (Ok(player), Ok(camera)) = (player.single(), camera.single()) else {
+        return;
+    };
+    let Some(target) =
+        raycast::raycast(world_map.as_ref(), camera, &player.translation, *view_mode)
+    else {
+        return;
+    };
+
+    // The prefab rests on the targeted face, centered on the block next to it
+    let anchor = target.position + target.face.to_ivec3();
+    let origin = IVec3::new(
+        anchor.x - prefab.size.x / 2,
+        anchor.y,
+        anchor.z - prefab.size.z / 2,
+    );
+    let min = origin.as_vec3();
+    let max = (origin + prefab.size).as_vec3();
+    gizmos.cuboid(
+        Transform::from_translation((min + max) / 2.0).with_scale(max - min),
+        Color::srgb(0.2, 0.8, 1.0),
+    );
+
+    if is_action_just_pressed(GameAction::PlacePrefab, &keyboard_input, &key_map) {
+        let placement = prefab.placement(origin);
+        // The server checks it again, this only avoids sending prefabs that can't fit
+        if let Err(error) = placement.validate() {
+            toasts.write(ToastEvent::error(error.message()));
+            return;
+        }
+        debug!("Placing prefab {} at {:?}", prefab.name, origin);
+        client.send_game_message(ClientToServerMessage::PlacePrefab(placement));
+    }
+}
@@ -0,0 +1,193 @@
+//! Bubbles of the bubble columns around the player.
+//!
+//! The columns near the player are looked for about once a second. Each one then
+//! shows a few bubbles going up from its soul sand or down towards its magma,
+//! looping over the height of the water. Billboards are pooled and reused like the
+//! fluid particles.
+
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use shared::water::{BubbleColumn, BUBBLE_COLUMN_MAX_HEIGHT};
+use shared::world::{to_global_pos, world_position_to_chunk_position, BlockId, WorldMap};
+use shared::CHUNK_SIZE;
+
+use crate::camera::CameraController;
+use crate::player::CurrentPlayerMarker;
+use crate::world::ClientWorldMap;
+use crate::GameState;
+
+/// Bubble columns further than this from the player aren't shown
+const BUBBLE_SCAN_RADIUS: f32 = 12.0;
+const BUBBLE_SCAN_INTERVAL_SECS: f32 = 1.0;
+/// At most this many columns are shown, the nearest ones
+const MAX_BUBBLE_COLUMNS: usize = 48;
+const BUBBLES_PER_COLUMN: usize = 4;
+/// Speed of the bubbles, in blocks per second
+const BUBBLE_SPEED: f32 = 1.5;
+const BUBBLE_SIZE: f32 = 0.15;
+
+struct ShownColumn {
+    /// Lowest water block of the column
+    bottom: IVec3,
+    /// Number of water blocks of the column
+    height: i32,
+    column: BubbleColumn,
+}
+
+#[derive(Component)]
+pub struct BubbleBillboard;
+
+/// Columns near the player, with the shared mesh and material of their bubbles
+#[derive(Resource, Default)]
+pub struct BubbleColumns {
+    columns: Vec<ShownColumn>,
+    since_scan: f32,
+    mesh: Option<Handle<Mesh>>,
+    material: Option<Handle<StandardMaterial>>,
+    pool: Vec<Entity>,
+}
+
+pub fn bubble_columns_scan_system(
+    world_map: Res<ClientWorldMap>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    time: Res<Time>,
+    mut bubbles: ResMut<BubbleColumns>,
+) {
+    bubbles.since_scan += time.delta_secs();
+    if bubbles.since_scan < BUBBLE_SCAN_INTERVAL_SECS {
+        return;
+    }
+    bubbles.since_scan = 0.0;
+
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let center = player.translation;
+    let is_water = |position: IVec3| {
+        world_map
+            .get_block_by_coordinates(&position)
+            .is_some_and(|block| block.id == BlockId::Water)
+    };
+
+    let reach = (BUBBLE_SCAN_RADIUS / CHUNK_SIZE as f32).ceil() as i32;
+    let center_chunk = world_position_to_chunk_position(center);
+    let mut columns = Vec::new();
+    for dx in -reach..=reach {
+        for dy in -reach..=reach {
+            for dz in -reach..=reach {
+                let chunk_pos = center_chunk + IVec3::new(dx, dy, dz);
+                let Some(chunk) = world_map.map.get(&chunk_pos) else {
+                    continue;
+                };
+                for (local_pos, block) in chunk.map.iter() {
+                    let Some(column) = BubbleColumn::from_source(block.id) else {
+                        continue;
+                    };
+                    let source = to_global_pos(&chunk_pos, local_pos);
+                    if source.as_vec3().distance(center) > BUBBLE_SCAN_RADIUS {
+                        continue;
+                    }
+                    let height = (1..=BUBBLE_COLUMN_MAX_HEIGHT)
+                        .take_while(|above| is_water(source + IVec3::Y * *above))
+                        .count() as i32;
+                    if height > 0 {
+                        columns.push(ShownColumn {
+                            bottom: source + IVec3::Y,
+                            height,
+                            column,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    columns.sort_by(|a, b| {
+        let distance = |column: &ShownColumn| column.bottom.as_vec3().distance_squared(center);
+        distance(a).total_cmp(&distance(b))
+    });
+    columns.truncate(MAX_BUBBLE_COLUMNS);
+    bubbles.columns = columns;
+}
+
+pub fn bubble_columns_render_system(
+    mut commands: Commands,
+    mut bubbles: ResMut<BubbleColumns>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+    camera: Query<&GlobalTransform, With<CameraController>>,
+    mut billboards: Query<(&mut Transform, &mut Visibility), With<BubbleBillboard>>,
+) {
+    let bubbles = &mut *bubbles;
+    let mesh = bubbles
+        .mesh
+        .get_or_insert_with(|| meshes.add(Circle::new(BUBBLE_SIZE / 2.0)))
+        .clone();
+    let material = bubbles
+        .material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::srgba(0.85, 0.95, 1.0, 0.6),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            })
+        })
+        .clone();
+
+    while bubbles.pool.len() < bubbles.columns.len() * BUBBLES_PER_COLUMN {
+        let entity = commands
+            .spawn((
+                Name::new("Bubble"),
+                BubbleBillboard,
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+                NotShadowCaster,
+                NotShadowReceiver,
+                StateScoped(GameState::Game),
+            ))
+            .id();
+        bubbles.pool.push(entity);
+    }
+
+    let rotation = camera
+        .single()
+        .map(|camera| camera.compute_transform().rotation)
+        .unwrap_or_default();
+    let elapsed = time.elapsed_secs();
+
+    for (index, entity) in bubbles.pool.iter().enumerate() {
+        // Billboards spawned this frame are picked up by the next one
+        let Ok((mut transform, mut visibility)) = billboards.get_mut(*entity) else {
+            continue;
+        };
+        let Some(shown) = bubbles.columns.get(index / BUBBLES_PER_COLUMN) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let height = shown.height as f32;
+        let phase = (index % BUBBLES_PER_COLUMN) as f32 / BUBBLES_PER_COLUMN as f32;
+        let travelled = (elapsed * BUBBLE_SPEED / height + phase).fract() * height;
+        let y = match shown.column {
+            BubbleColumn::Rising => travelled,
+            BubbleColumn::Sinking => height - travelled,
+        };
+        let wobble = (elapsed * 3.0 + index as f32).sin() * 0.15;
+
+        transform.translation = shown.bottom.as_vec3() + Vec3::new(0.5 + wobble, y, 0.5 - wobble);
+        transform.rotation = rotation;
+        *visibility = Visibility::Visible;
+    }
+}
+
+pub fn bubble_columns_cleanup_system(mut bubbles: ResMut<BubbleColumns>) {
+    // Billboards are state scoped, only the pool and the columns need to be forgotten
+    bubbles.pool.clear();
+    bubbles.columns.clear();
+}
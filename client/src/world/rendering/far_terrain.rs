@@ -0,0 +1,249 @@
+//! Low poly landscape drawn from the far terrain tiles sent by the server, see
+//! `shared::world::far_terrain`.
+//!
+//! Each sample of a tile is a flat column textured with its surface block, the
+//! texture being stretched over the sample like the LOD 1 chunks, with walls down
+//! to its lower neighbours. Tiles covered by full chunks are hidden, and tiles that
+//! left the ring of the server are despawned.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use shared::messages::FarTerrainUpdate;
+use shared::world::{
+    is_far_terrain_column, world_position_to_chunk_position, BlockData, BlockDirection, BlockId,
+    FarTerrainTile, FAR_TERRAIN_SAMPLES, FAR_TERRAIN_SAMPLE_SIZE,
+};
+use shared::CHUNK_SIZE;
+
+use super::meshing::UvCoords;
+use super::voxel::VoxelShape;
+use crate::player::CurrentPlayerMarker;
+use crate::world::{GlobalMaterial, MaterialResource};
+use crate::GameState;
+
+/// Depth of the walls at the border of the tiles, hiding the gaps between tiles
+const TILE_SKIRT: f32 = 8.0;
+/// Brightness of the walls, so that slopes stand out from afar
+const WALL_SHADE: f32 = 0.7;
+
+/// Far terrain tiles received from the server, with their entity
+#[derive(Resource, Default, Debug)]
+pub struct FarTerrain {
+    inner_radius: i32,
+    outer_radius: i32,
+    tiles: HashMap<IVec2, Entity>,
+}
+
+#[derive(Component)]
+pub struct FarTerrainTileMarker(IVec2);
+
+/// Texture and tint of the top and sides of a block
+struct SampleLook {
+    top: (UvCoords, [f32; 4]),
+    side: (UvCoords, [f32; 4]),
+}
+
+fn sample_look(block: BlockId, uvs: &HashMap<String, UvCoords>) -> Option<SampleLook> {
+    let shape = VoxelShape::create_from_block(&BlockData::new(block, BlockDirection::Front));
+    let face = |normal: [f32; 3], shade: f32| {
+        let (texture, color) = shape
+            .faces
+            .iter()
+            .find(|face| face.normals.first() == Some(&normal))
+            .map(|face| {
+                (
+                    face.texture.clone(),
+                    face.colors.first().copied().unwrap_or([1.0; 4]),
+                )
+            })
+            .unwrap_or_else(|| (format!("{block:?}"), [1.0; 4]));
+        let uv = *uvs.get(&texture)?;
+        Some((
+            uv,
+            [color[0] * shade, color[1] * shade, color[2] * shade, 1.0],
+        ))
+    };
+    Some(SampleLook {
+        top: face([0.0, 1.0, 0.0], 1.0)?,
+        side: face([1.0, 0.0, 0.0], WALL_SHADE)?,
+    })
+}
+
+#[derive(Default)]
+struct TileMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl TileMesh {
+    /// Adds a quad whose corners go counterclockwise from the bottom left, seen from the front
+    fn quad(&mut self, corners: [Vec3; 4], normal: Vec3, (uv, color): (UvCoords, [f32; 4])) {
+        let start = self.positions.len() as u32;
+        self.positions
+            .extend(corners.map(|corner| corner.to_array()));
+        self.normals.extend([normal.to_array(); 4]);
+        self.uvs.extend([
+            [uv.u0, uv.v1],
+            [uv.u1, uv.v1],
+            [uv.u1, uv.v0],
+            [uv.u0, uv.v0],
+        ]);
+        self.colors.extend([color; 4]);
+        self.indices
+            .extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, Default::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_indices(Indices::U32(self.indices));
+        mesh
+    }
+}
+
+/// Mesh of the tile, relative to the corner of its column
+fn tile_mesh(tile: &FarTerrainTile, uvs: &HashMap<String, UvCoords>) -> Mesh {
+    let size = FAR_TERRAIN_SAMPLE_SIZE as f32;
+    let mut mesh = TileMesh::default();
+    for sz in 0..FAR_TERRAIN_SAMPLES {
+        for sx in 0..FAR_TERRAIN_SAMPLES {
+            let (height, block) = tile.sample(sx, sz);
+            let Some(look) = sample_look(block, uvs) else {
+                continue;
+            };
+            let (x0, z0) = (sx as f32 * size, sz as f32 * size);
+            let (x1, z1) = (x0 + size, z0 + size);
+            let top = height as f32 + 1.0;
+
+            mesh.quad(
+                [
+                    Vec3::new(x0, top, z1),
+                    Vec3::new(x1, top, z1),
+                    Vec3::new(x1, top, z0),
+                    Vec3::new(x0, top, z0),
+                ],
+                Vec3::Y,
+                look.top,
+            );
+
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let (nx, nz) = (sx + offset.x, sz + offset.y);
+                let bottom = if (0..FAR_TERRAIN_SAMPLES).contains(&nx)
+                    && (0..FAR_TERRAIN_SAMPLES).contains(&nz)
+                {
+                    tile.sample(nx, nz).0 as f32 + 1.0
+                } else {
+                    top - TILE_SKIRT
+                };
+                if bottom >= top {
+                    continue;
+                }
+                let (corners, normal) = match offset {
+                    IVec2::X => ([(x1, z1), (x1, z0), (x1, z0), (x1, z1)], Vec3::X),
+                    IVec2::NEG_X => ([(x0, z0), (x0, z1), (x0, z1), (x0, z0)], Vec3::NEG_X),
+                    IVec2::Y => ([(x0, z1), (x1, z1), (x1, z1), (x0, z1)], Vec3::Z),
+                    _ => ([(x1, z0), (x0, z0), (x0, z0), (x1, z0)], Vec3::NEG_Z),
+                };
+                let heights = [bottom, bottom, top, top];
+                mesh.quad(
+                    std::array::from_fn(|i| Vec3::new(corners[i].0, heights[i], corners[i].1)),
+                    normal,
+                    look.side,
+                );
+            }
+        }
+    }
+    mesh.build()
+}
+
+pub fn far_terrain_update_system(
+    mut commands: Commands,
+    mut events: EventReader<FarTerrainUpdate>,
+    mut far_terrain: ResMut<FarTerrain>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material_resource: Res<MaterialResource>,
+) {
+    let (Some(blocks), Some(material)) = (
+        material_resource.blocks.as_ref(),
+        material_resource
+            .global_materials
+            .get(&GlobalMaterial::Blocks),
+    ) else {
+        return;
+    };
+
+    for update in events.read() {
+        far_terrain.inner_radius = update.inner_radius;
+        far_terrain.outer_radius = update.outer_radius;
+        for tile in update.tiles.iter() {
+            let entity = commands
+                .spawn((
+                    Name::new(format!("Far terrain {}", tile.column)),
+                    FarTerrainTileMarker(tile.column),
+                    Mesh3d(meshes.add(tile_mesh(tile, &blocks.uvs))),
+                    MeshMaterial3d(material.clone_weak()),
+                    Transform::from_xyz(
+                        (tile.column.x * CHUNK_SIZE) as f32,
+                        0.0,
+                        (tile.column.y * CHUNK_SIZE) as f32,
+                    ),
+                    Visibility::Hidden,
+                    StateScoped(GameState::Game),
+                ))
+                .id();
+            if let Some(previous) = far_terrain.tiles.insert(tile.column, entity) {
+                commands.entity(previous).despawn();
+            }
+        }
+    }
+}
+
+/// Hides the tiles covered by full chunks and forgets those that left the ring
+pub fn far_terrain_visibility_system(
+    mut commands: Commands,
+    mut far_terrain: ResMut<FarTerrain>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    mut tiles: Query<(&FarTerrainTileMarker, &mut Visibility)>,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let chunk = world_position_to_chunk_position(player.translation);
+    let center = IVec2::new(chunk.x, chunk.z);
+    let (inner_radius, outer_radius) = (far_terrain.inner_radius, far_terrain.outer_radius);
+
+    for (FarTerrainTileMarker(column), mut visibility) in tiles.iter_mut() {
+        if is_far_terrain_column(center, *column, inner_radius, outer_radius) {
+            visibility.set_if_neq(Visibility::Visible);
+        } else if (*column - center).length_squared() > outer_radius * outer_radius {
+            if let Some(entity) = far_terrain.tiles.remove(column) {
+                commands.entity(entity).despawn();
+            }
+        } else {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
+
+impl FarTerrain {
+    /// Despawns every tile, the next ones being sent by the server
+    pub fn despawn_tiles(&mut self, commands: &mut Commands) {
+        for (_, entity) in self.tiles.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn clear_far_terrain_system(mut far_terrain: ResMut<FarTerrain>) {
+    *far_terrain = FarTerrain::default();
+}
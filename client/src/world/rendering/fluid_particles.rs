@@ -0,0 +1,148 @@
+//! Rendering of the server-simulated fluid particles (`--fluid-particles`).
+//!
+//! Each particle is drawn as a camera-facing quad with a soft radial falloff.
+//! Overlapping quads blend together into blobs, which gives a cheap metaball look
+//! without a dedicated screen-space pass. Entities are pooled and reused between
+//! updates since the server sends particle positions many times per second.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use shared::fluid::FluidParticlesUpdate;
+
+use crate::camera::CameraController;
+use crate::GameState;
+
+/// Size of the generated falloff texture, in pixels
+const PARTICLE_TEXTURE_SIZE: u32 = 32;
+/// Width of a particle billboard, in blocks
+const PARTICLE_SIZE: f32 = 0.45;
+
+#[derive(Component)]
+pub struct FluidParticleBillboard;
+
+/// Shared mesh and material, plus the pool of spawned billboards
+#[derive(Resource, Default)]
+pub struct FluidParticleRenderState {
+    mesh: Option<Handle<Mesh>>,
+    material: Option<Handle<StandardMaterial>>,
+    pool: Vec<Entity>,
+}
+
+fn create_falloff_image() -> Image {
+    let size = PARTICLE_TEXTURE_SIZE;
+    let center = (size as f32 - 1.0) / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 - center) / center;
+            let dy = (y as f32 - center) / center;
+            let distance = (dx * dx + dy * dy).sqrt();
+            // Smooth falloff so neighbouring particles merge instead of showing hard edges
+            let alpha = (1.0 - distance).clamp(0.0, 1.0).powi(2);
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+pub fn fluid_particles_render_system(
+    mut commands: Commands,
+    mut updates: EventReader<FluidParticlesUpdate>,
+    mut state: ResMut<FluidParticleRenderState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut billboards: Query<(&mut Transform, &mut Visibility), With<FluidParticleBillboard>>,
+) {
+    // Only the most recent update matters, older positions are already stale
+    let Some(update) = updates.read().last() else {
+        return;
+    };
+
+    let mesh = state
+        .mesh
+        .get_or_insert_with(|| meshes.add(Rectangle::new(PARTICLE_SIZE, PARTICLE_SIZE)))
+        .clone();
+    let material = state
+        .material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::srgba(0.25, 0.45, 0.85, 0.8),
+                base_color_texture: Some(images.add(create_falloff_image())),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            })
+        })
+        .clone();
+
+    while state.pool.len() < update.positions.len() {
+        let entity = commands
+            .spawn((
+                Name::new("Fluid particle"),
+                FluidParticleBillboard,
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+                NotShadowCaster,
+                NotShadowReceiver,
+                StateScoped(GameState::Game),
+            ))
+            .id();
+        state.pool.push(entity);
+    }
+
+    for (index, entity) in state.pool.iter().enumerate() {
+        // Billboards spawned this frame are picked up by the next update
+        let Ok((mut transform, mut visibility)) = billboards.get_mut(*entity) else {
+            continue;
+        };
+
+        match update.positions.get(index) {
+            Some(position) => {
+                transform.translation = *position;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+pub fn fluid_particles_billboard_system(
+    camera: Query<&GlobalTransform, With<CameraController>>,
+    mut billboards: Query<(&mut Transform, &Visibility), With<FluidParticleBillboard>>,
+) {
+    let Ok(camera_transform) = camera.single() else {
+        return;
+    };
+    let camera_rotation = camera_transform.compute_transform().rotation;
+
+    for (mut transform, visibility) in billboards.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        transform.rotation = camera_rotation;
+    }
+}
+
+pub fn fluid_particles_cleanup_system(mut state: ResMut<FluidParticleRenderState>) {
+    // Billboards are state scoped, only the pool needs to be forgotten
+    state.pool.clear();
+}
@@ -0,0 +1,209 @@
+//! Isometric thumbnails of the blocks, used by the hotbar, the inventory and the
+//! creative catalog so that cubes look like cubes.
+//!
+//! Icons are drawn on the CPU from the block textures, then packed into their own
+//! atlas. They are built with the other atlases during `PreGameLoading`, so they
+//! always match the loaded textures. Items that aren't blocks, and blocks without a
+//! top face such as flowers, keep their flat texture.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use shared::world::{BlockData, BlockDirection, BlockId, ItemId, ItemType};
+
+use super::materials::{pack_textures, AtlasHandles, AtlasWrapper, PackedAtlas};
+use super::voxel::VoxelShape;
+
+/// Side of a block icon, in pixels
+const ICON_SIZE: u32 = 32;
+/// Brightness of the two visible sides, so that the edges of the cube stand out
+const ICON_RIGHT_SHADE: f32 = 0.8;
+const ICON_BACK_SHADE: f32 = 0.6;
+
+/// Texture of one visible face of the cube, tinted like in the world
+struct IconFace {
+    texture: Image,
+    tint: [f32; 4],
+}
+
+enum IconSource {
+    Flat(Image),
+    Cube {
+        min: Vec3,
+        max: Vec3,
+        /// Top, `+x` and `+z` faces
+        faces: Box<[IconFace; 3]>,
+    },
+}
+
+fn block_data(block: BlockId) -> BlockData {
+    match block {
+        BlockId::SnowLayer => BlockData::snow_layer(1),
+        _ => BlockData::new(block, BlockDirection::Front),
+    }
+}
+
+/// Faces of the block seen by the icon camera, which looks at the top, `+x` and `+z` sides
+fn icon_source(
+    block: BlockId,
+    blocks: &AtlasWrapper,
+    images: &Assets<Image>,
+) -> Option<IconSource> {
+    // Frames are thin boards, their flat texture reads better
+    if block == BlockId::ItemFrame {
+        return None;
+    }
+    let shape = VoxelShape::create_from_block(&block_data(block));
+    let face = |normal: [f32; 3], shade: f32| {
+        let face = shape
+            .faces
+            .iter()
+            .find(|face| face.normals.first() == Some(&normal))?;
+        let texture = images.get(blocks.handles.get(&face.texture)?)?.clone();
+        let color = face.colors.first().copied().unwrap_or([1.0; 4]);
+        Some(IconFace {
+            texture,
+            tint: [
+                color[0] * shade,
+                color[1] * shade,
+                color[2] * shade,
+                color[3],
+            ],
+        })
+    };
+
+    let (min, max) = shape
+        .faces
+        .iter()
+        .flat_map(|face| &face.vertices)
+        .fold((Vec3::MAX, Vec3::MIN), |(min, max), vertex| {
+            (min.min(Vec3::from(*vertex)), max.max(Vec3::from(*vertex)))
+        });
+
+    Some(IconSource::Cube {
+        min,
+        max,
+        faces: Box::new([
+            face([0.0, 1.0, 0.0], 1.0)?,
+            face([1.0, 0.0, 0.0], ICON_RIGHT_SHADE)?,
+            face([0.0, 0.0, 1.0], ICON_BACK_SHADE)?,
+        ]),
+    })
+}
+
+fn sample(face: &IconFace, u: f32, v: f32) -> Option<Color> {
+    let size = face.texture.size();
+    let x = ((u.clamp(0.0, 1.0) * size.x as f32) as u32).min(size.x - 1);
+    let y = ((v.clamp(0.0, 1.0) * size.y as f32) as u32).min(size.y - 1);
+    let color = face.texture.get_color_at(x, y).ok()?.to_srgba();
+    Some(Color::srgba(
+        color.red * face.tint[0],
+        color.green * face.tint[1],
+        color.blue * face.tint[2],
+        color.alpha * face.tint[3],
+    ))
+}
+
+/// Color of the icon pixel at `(px, py)`, with pixels going from 0 to 1 across the icon.
+///
+/// The projection is the inverse of `px = 1/2 + (x - z) / 2`, `py = 1/2 + (x + z) / 4 - y / 2`,
+/// solved for each visible face of the box.
+fn cube_pixel(min: Vec3, max: Vec3, faces: &[IconFace; 3], px: f32, py: f32) -> Option<Color> {
+    let [top, right, back] = faces;
+    let size = max - min;
+    let d = (px - 0.5) * 2.0;
+    let inside = |value: f32, low: f32, high: f32| value >= low && value <= high;
+
+    // Top face, y = max.y
+    let e = (py - 0.5 + max.y * 0.5) * 4.0;
+    let (x, z) = ((d + e) / 2.0, (e - d) / 2.0);
+    if inside(x, min.x, max.x) && inside(z, min.z, max.z) {
+        return sample(top, (x - min.x) / size.x, (z - min.z) / size.z);
+    }
+
+    // Right face, x = max.x
+    let z = max.x - d;
+    let y = (0.5 + (max.x + z) * 0.25 - py) * 2.0;
+    if inside(z, min.z, max.z) && inside(y, min.y, max.y) {
+        return sample(right, (max.z - z) / size.z, (max.y - y) / size.y);
+    }
+
+    // Back face, z = max.z
+    let x = d + max.z;
+    let y = (0.5 + (x + max.z) * 0.25 - py) * 2.0;
+    if inside(x, min.x, max.x) && inside(y, min.y, max.y) {
+        return sample(back, (x - min.x) / size.x, (max.y - y) / size.y);
+    }
+
+    None
+}
+
+fn render_icon(source: IconSource) -> Image {
+    let (min, max, faces) = match source {
+        IconSource::Flat(texture) => return texture,
+        IconSource::Cube { min, max, faces } => (min, max, faces),
+    };
+
+    let mut icon = Image::new_fill(
+        Extent3d {
+            width: ICON_SIZE,
+            height: ICON_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    for py in 0..ICON_SIZE {
+        for px in 0..ICON_SIZE {
+            let Some(color) = cube_pixel(
+                min,
+                max,
+                &faces,
+                (px as f32 + 0.5) / ICON_SIZE as f32,
+                (py as f32 + 0.5) / ICON_SIZE as f32,
+            ) else {
+                continue;
+            };
+            let _ = icon.set_color_at(px, py, color);
+        }
+    }
+    icon
+}
+
+/// Draws the icon of every item texture and packs them on the async compute pool.
+///
+/// Icons use the asset ids of the item textures, so the icon atlas is looked up
+/// exactly like the item atlas.
+pub(super) fn spawn_icon_packing(
+    blocks: &AtlasWrapper,
+    items: &AtlasHandles<ItemId>,
+    images: &Assets<Image>,
+) -> Option<Task<Option<PackedAtlas>>> {
+    let mut sources = Vec::with_capacity(items.handles.len());
+    for (handle, name) in items.handles.iter() {
+        let block = ItemId::ALL
+            .into_iter()
+            .find(|item| format!("{item:?}") == *name)
+            .and_then(|item| match item.get_default_type() {
+                ItemType::Block(block) => Some(block),
+                _ => None,
+            });
+        let source = match block.and_then(|block| icon_source(block, blocks, images)) {
+            Some(source) => source,
+            None => IconSource::Flat(images.get(handle)?.clone()),
+        };
+        sources.push((handle.id(), source));
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    Some(pool.spawn(async move {
+        let icons = sources
+            .into_iter()
+            .map(|(id, source)| (id, render_icon(source)))
+            .collect();
+        pack_textures(icons, None)
+    }))
+}
@@ -1,18 +1,27 @@
 use crate::constants::{BASE_ROUGHNESS, BASE_SPECULAR_HIGHLIGHT};
 use crate::game::{PreLoadingCompletion, PreloadSignal};
+use crate::network::ServerBlocks;
 use crate::world::GlobalMaterial;
 use crate::TexturePath;
 use bevy::asset::LoadState;
+use bevy::asset::RenderAssetUsages;
 use bevy::image::ImageSampler;
 use bevy::prelude::*;
-use bevy::render::render_resource::Face;
-use shared::world::{BlockId, GameElementId, ItemId};
+use bevy::render::render_resource::{Extent3d, Face, TextureDimension, TextureFormat};
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use shared::world::{BlockData, BlockDirection, BlockId, GameElementId, ItemId};
 use shared::GameFolderPaths;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::marker::PhantomData;
 
+use super::icons::spawn_icon_packing;
 use super::meshing::UvCoords;
+use super::voxel::VoxelShape;
+
+/// Name of the texture drawn in place of the ones missing from the texture pack
+pub const MISSING_TEXTURE: &str = "_Missing";
+const MISSING_TEXTURE_SIZE: u32 = 16;
 
 #[derive(Resource, Debug)]
 pub struct AtlasWrapper {
@@ -28,6 +37,8 @@ pub struct MaterialResource {
     pub global_materials: HashMap<GlobalMaterial, Handle<StandardMaterial>>,
     pub items: Option<AtlasWrapper>,
     pub blocks: Option<AtlasWrapper>,
+    /// Isometric block icons for the inventory UI, keyed like `items`
+    pub icons: Option<AtlasWrapper>,
 }
 
 #[derive(Resource)]
@@ -48,8 +59,35 @@ impl<T> Default for AtlasHandles<T> {
     }
 }
 
+/// Magenta and black checkerboard, which can't be mistaken for an actual texture
+fn missing_texture_image() -> Image {
+    let half = MISSING_TEXTURE_SIZE / 2;
+    let data = (0..MISSING_TEXTURE_SIZE * MISSING_TEXTURE_SIZE)
+        .flat_map(|i| {
+            let (x, y) = (i % MISSING_TEXTURE_SIZE, i / MISSING_TEXTURE_SIZE);
+            if (x / half + y / half).is_multiple_of(2) {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: MISSING_TEXTURE_SIZE,
+            height: MISSING_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
 pub fn setup_materials(
     asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut material_resource: ResMut<MaterialResource>,
     mut block_atlas_handles: ResMut<AtlasHandles<BlockId>>,
@@ -137,6 +175,33 @@ pub fn setup_materials(
             blocks_path.display()
         );
     }
+
+    // Always there, so that the blocks and items without a texture are still drawn
+    let missing_texture = images.add(missing_texture_image());
+    block_atlas_handles
+        .handles
+        .push((missing_texture.clone(), MISSING_TEXTURE.to_owned()));
+    item_atlas_handles
+        .handles
+        .push((missing_texture, MISSING_TEXTURE.to_owned()));
+}
+
+/// Number of atlases packed during `PreGameLoading`: blocks, items and icons
+pub const ATLAS_COUNT: usize = 3;
+
+/// Result of packing an atlas, before its texture is added to the assets
+pub(super) struct PackedAtlas {
+    layout: TextureAtlasLayout,
+    sources: TextureAtlasSources,
+    texture: Image,
+}
+
+/// Atlases being packed in the background, so that large texture sets don't stall the frame
+#[derive(Resource, Default)]
+pub struct AtlasPackingTasks {
+    blocks: Option<Task<Option<PackedAtlas>>>,
+    items: Option<Task<Option<PackedAtlas>>>,
+    icons: Option<Task<Option<PackedAtlas>>>,
 }
 
 pub fn create_all_atlases(
@@ -145,6 +210,7 @@ pub fn create_all_atlases(
     mut images: ResMut<Assets<Image>>,
     mut material_resource: ResMut<MaterialResource>,
     mut loading: ResMut<PreLoadingCompletion>,
+    mut tasks: ResMut<AtlasPackingTasks>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut preload_signals: EventWriter<PreloadSignal>,
@@ -169,23 +235,23 @@ pub fn create_all_atlases(
         return;
     }
 
-    let all_loaded = all_handles
-        .iter()
-        .all(|id| matches!(asset_server.get_load_state(*id), Some(LoadState::Loaded)));
+    // The missing texture is added to the assets rather than loaded
+    let all_loaded = all_handles.iter().all(|id| {
+        images.contains(*id) || matches!(asset_server.get_load_state(*id), Some(LoadState::Loaded))
+    });
 
     let any_failed = all_handles
         .iter()
         .any(|id| matches!(asset_server.get_load_state(*id), Some(LoadState::Failed(_))));
 
-    let textures_ready = if all_loaded {
-        let mut textures_ready = true;
-
+    if all_loaded {
         if material_resource.blocks.is_none() {
-            if let Some(blocks) = build_texture_atlas(
-                &mut atlases.0,
+            if let Some(blocks) = poll_atlas_packing(
+                &mut tasks.blocks,
+                |images| spawn_atlas_packing(&atlases.0, images, None),
+                &atlases.0,
                 &mut images,
                 &mut texture_atlases,
-                None,
                 Some(ImageSampler::nearest()),
             ) {
                 material_resource.global_materials.insert(
@@ -201,18 +267,16 @@ pub fn create_all_atlases(
 
                 material_resource.blocks = Some(blocks);
                 atlases.0.loaded = true;
-            } else {
-                warn!("Failed to finalize block textures after load");
-                textures_ready = false;
             }
         }
 
         if material_resource.items.is_none() {
-            if let Some(items) = build_texture_atlas(
-                &mut atlases.1,
+            if let Some(items) = poll_atlas_packing(
+                &mut tasks.items,
+                |images| spawn_atlas_packing(&atlases.1, images, None),
+                &atlases.1,
                 &mut images,
                 &mut texture_atlases,
-                None,
                 Some(ImageSampler::nearest()),
             ) {
                 material_resource.global_materials.insert(
@@ -227,22 +291,34 @@ pub fn create_all_atlases(
                 );
                 material_resource.items = Some(items);
                 atlases.1.loaded = true;
-            } else {
-                warn!("Failed to finalize item textures after load");
-                textures_ready = false;
             }
         }
 
-        textures_ready
-    } else {
-        false
-    };
+        // Icons are drawn from the block textures, so they wait for the block atlas
+        if let (None, Some(blocks)) = (&material_resource.icons, &material_resource.blocks) {
+            let icons = poll_atlas_packing(
+                &mut tasks.icons,
+                |images| spawn_icon_packing(blocks, &atlases.1, images),
+                &atlases.1,
+                &mut images,
+                &mut texture_atlases,
+                Some(ImageSampler::nearest()),
+            );
+            if icons.is_some() {
+                material_resource.icons = icons;
+            }
+        }
+    }
+
+    loading.atlases_packed = material_resource.blocks.is_some() as usize
+        + material_resource.items.is_some() as usize
+        + material_resource.icons.is_some() as usize;
 
     if any_failed {
         warn!("Texture loading failed; check asset paths and filenames");
     }
 
-    let new_ready = !any_failed && all_loaded && textures_ready;
+    let new_ready = !any_failed && all_loaded && loading.atlases_packed == ATLAS_COUNT;
     if new_ready && !was_ready {
         preload_signals.write(PreloadSignal::TexturesReady);
     }
@@ -250,33 +326,87 @@ pub fn create_all_atlases(
     loading.textures_loaded = new_ready;
 }
 
-fn build_texture_atlas<T: GameElementId>(
-    atlas_handles: &mut AtlasHandles<T>,
+/// Starts packing the atlas in the background if it isn't already, and returns it
+/// once the task is done and its texture has been added to the assets
+fn poll_atlas_packing<T: GameElementId>(
+    task: &mut Option<Task<Option<PackedAtlas>>>,
+    spawn: impl FnOnce(&Assets<Image>) -> Option<Task<Option<PackedAtlas>>>,
+    atlas_handles: &AtlasHandles<T>,
     images: &mut ResMut<Assets<Image>>,
     texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
-    padding: Option<UVec2>,
     sampling: Option<ImageSampler>,
 ) -> Option<AtlasWrapper> {
-    if atlas_handles.loaded {
-        // Blocks if this atlas is loaded but game setup phase is not done yet
+    let Some(running) = task else {
+        *task = spawn(images);
         return None;
-    }
+    };
 
-    let mut texture_atlas_builder = TextureAtlasBuilder::default();
-    texture_atlas_builder.padding(padding.unwrap_or_default());
+    let packed = block_on(future::poll_once(running))?;
+    *task = None;
+    let Some(packed) = packed else {
+        // Dropping the task makes the next frame try again
+        warn!("Failed to pack textures into an atlas");
+        return None;
+    };
+
+    Some(finish_texture_atlas(
+        packed,
+        atlas_handles,
+        images,
+        texture_atlases,
+        sampling,
+    ))
+}
 
+/// Copies the textures of the atlas and packs them on the async compute pool
+fn spawn_atlas_packing<T: GameElementId>(
+    atlas_handles: &AtlasHandles<T>,
+    images: &Assets<Image>,
+    padding: Option<UVec2>,
+) -> Option<Task<Option<PackedAtlas>>> {
+    let mut textures = Vec::with_capacity(atlas_handles.handles.len());
     for handle in atlas_handles.handles.iter() {
         let id = handle.0.id();
-        let Some(texture) = images.get(id) else {
-            // Not all images are loaded yet
-            return None;
-        };
+        // Not all images are loaded yet
+        let texture = images.get(id)?;
+        textures.push((id, texture.clone()));
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    Some(pool.spawn(async move { pack_textures(textures, padding) }))
+}
 
-        texture_atlas_builder.add_texture(Some(id), texture);
+/// Packs the textures into a single atlas, looked up by their asset ids
+pub(super) fn pack_textures(
+    textures: Vec<(AssetId<Image>, Image)>,
+    padding: Option<UVec2>,
+) -> Option<PackedAtlas> {
+    let mut texture_atlas_builder = TextureAtlasBuilder::default();
+    texture_atlas_builder.padding(padding.unwrap_or_default());
+    for (id, texture) in textures.iter() {
+        texture_atlas_builder.add_texture(Some(*id), texture);
     }
 
-    let (texture_atlas_layout, texture_atlas_sources, texture) =
-        texture_atlas_builder.build().unwrap();
+    let (layout, sources, texture) = texture_atlas_builder.build().ok()?;
+    Some(PackedAtlas {
+        layout,
+        sources,
+        texture,
+    })
+}
+
+fn finish_texture_atlas<T: GameElementId>(
+    packed: PackedAtlas,
+    atlas_handles: &AtlasHandles<T>,
+    images: &mut ResMut<Assets<Image>>,
+    texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
+    sampling: Option<ImageSampler>,
+) -> AtlasWrapper {
+    let PackedAtlas {
+        layout: texture_atlas_layout,
+        sources: texture_atlas_sources,
+        texture,
+    } = packed;
 
     let size = texture.size_f32();
     let texture = images.add(texture);
@@ -304,11 +434,38 @@ fn build_texture_atlas<T: GameElementId>(
     }
 
     // Create the atlas
-    Some(AtlasWrapper {
+    AtlasWrapper {
         texture,
         layout: texture_atlases.add(texture_atlas_layout),
         sources: texture_atlas_sources,
         handles,
         uvs,
-    })
+    }
+}
+
+/// Warns about the blocks of the server without a texture in the texture pack,
+/// drawn with the missing texture instead
+pub fn check_block_textures(
+    material_resource: Res<MaterialResource>,
+    server_blocks: Res<ServerBlocks>,
+) {
+    let Some(blocks) = material_resource.blocks.as_ref() else {
+        return;
+    };
+
+    let missing: BTreeSet<String> = server_blocks
+        .0
+        .iter()
+        .flat_map(|block| {
+            VoxelShape::create_from_block(&BlockData::new(*block, BlockDirection::Front)).faces
+        })
+        .map(|face| face.texture)
+        .filter(|texture| !blocks.uvs.contains_key(texture))
+        .collect();
+    if !missing.is_empty() {
+        warn!(
+            "No texture for {}, these blocks are drawn with the missing texture",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
 }
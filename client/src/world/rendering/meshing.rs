@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::f32::consts::PI;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::world::{ClientChunk, ClientWorldMap};
 use bevy::{
@@ -9,11 +9,16 @@ use bevy::{
     render::mesh::{Indices, PrimitiveTopology},
 };
 use shared::world::{
-    to_global_pos, BlockDirection, BlockId, BlockTransparency, LodLevel, WorldMap,
+    global_block_to_chunk_pos, to_global_pos, BlockDirection, BlockId, BlockTransparency, LodLevel,
+    WorldMap,
 };
 use shared::CHUNK_SIZE;
 
 use super::voxel::{Face, FaceDirection, VoxelShape};
+use super::MISSING_TEXTURE;
+
+/// Opacity of ice blocks, so that frozen water stays visible underneath
+const ICE_ALPHA: f32 = 0.8;
 
 #[derive(Copy, Clone, Debug)]
 pub struct UvCoords {
@@ -49,11 +54,35 @@ fn build_mesh(creator: &MeshCreator) -> Mesh {
     mesh
 }
 
+/// How the faces against chunks that aren't loaded are meshed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChunkEdgePolicy {
+    /// Chunk of the player
+    pub center: IVec3,
+    /// Distance up to which the server sends chunks, 0 if unknown
+    pub loaded_distance: i32,
+}
+
+impl ChunkEdgePolicy {
+    /// Whether `position` is in a chunk that isn't loaded yet but will be. Faces
+    /// against it are hidden until it arrives and its neighbors are meshed again:
+    /// only the outer ring of the loaded chunks is open to the air, instead of
+    /// walls at every gap of the loaded data.
+    fn is_pending(&self, world_map: &ClientWorldMap, position: &IVec3) -> bool {
+        let chunk_pos = global_block_to_chunk_pos(position);
+        self.loaded_distance > 0
+            && !world_map.map.contains_key(&chunk_pos)
+            && chunk_pos.distance_squared(self.center) <= self.loaded_distance.pow(2)
+    }
+}
+
 /// Chunk mesh response containing only solid block meshes.
 /// Water rendering is handled by the separate water system (see `rendering/water.rs`).
 #[derive(Debug, Default, Clone)]
 pub struct ChunkMeshResponse {
     pub solid_mesh: Option<Mesh>,
+    /// Time spent generating the mesh, reported in the inspector
+    pub mesh_duration: Duration,
 }
 
 pub(crate) fn generate_chunk_mesh(
@@ -61,6 +90,7 @@ pub(crate) fn generate_chunk_mesh(
     chunk: &ClientChunk,
     chunk_pos: &IVec3,
     uv_map: &HashMap<String, UvCoords>,
+    policy: &ChunkEdgePolicy,
 ) -> ChunkMeshResponse {
     let start = Instant::now();
 
@@ -80,7 +110,7 @@ pub(crate) fn generate_chunk_mesh(
         let global_block_pos = &to_global_pos(chunk_pos, local_block_pos);
         let visibility = block.id.get_visibility();
 
-        if is_block_surrounded(world_map, global_block_pos, &visibility, &block.id) {
+        if is_block_surrounded(world_map, global_block_pos, &visibility, &block.id, policy) {
             continue;
         }
 
@@ -98,16 +128,24 @@ pub(crate) fn generate_chunk_mesh(
             if let Some(uvs) = uv_map.get(&face.texture) {
                 uv_coords = uvs;
             } else {
-                uv_coords = uv_map.get("_Default").unwrap();
+                uv_coords = uv_map.get(MISSING_TEXTURE).unwrap();
             }
 
             let alpha = match visibility {
+                // unlike the water, nothing can be seen through lava
+                _ if block.id == BlockId::Lava => 1.0,
                 BlockTransparency::Liquid => 0.7,
+                _ if block.id == BlockId::Ice => ICE_ALPHA,
                 _ => 1.0,
             };
 
-            let should_render =
-                should_render_face(world_map, global_block_pos, &face.direction, &visibility);
+            let should_render = should_render_face(
+                world_map,
+                global_block_pos,
+                &face.direction,
+                &visibility,
+                policy,
+            );
 
             if should_render {
                 render_face(
@@ -184,6 +222,7 @@ pub(crate) fn generate_chunk_mesh(
         } else {
             None
         },
+        mesh_duration: start.elapsed(),
     }
 }
 
@@ -192,9 +231,13 @@ pub(crate) fn is_block_surrounded(
     global_block_pos: &IVec3,
     block_visibility: &BlockTransparency,
     block_id: &BlockId,
+    policy: &ChunkEdgePolicy,
 ) -> bool {
     for offset in &shared::world::SIX_OFFSETS {
         let neighbor_pos = *global_block_pos + *offset;
+        if policy.is_pending(world_map, &neighbor_pos) {
+            continue;
+        }
 
         // Check if the block exists at the neighboring position
         if let Some(block) = world_map.get_block_by_coordinates(&neighbor_pos) {
@@ -221,6 +264,7 @@ pub(crate) fn is_block_surrounded(
     true
 }
 
+/// Turns a vertex of a block around the vertical axis through the center of the block
 pub fn rotate_vertices(v: &[f32; 3], direction: &BlockDirection) -> [f32; 3] {
     let angle = match *direction {
         BlockDirection::Front => 0.,
@@ -229,10 +273,11 @@ pub fn rotate_vertices(v: &[f32; 3], direction: &BlockDirection) -> [f32; 3] {
         BlockDirection::Back => PI,
     };
 
+    let (x, z) = (v[0] - 0.5, v[2] - 0.5);
     [
-        angle.cos() * v[0] + angle.sin() * v[2],
+        angle.cos() * x + angle.sin() * z + 0.5,
         v[1],
-        (-angle).sin() * v[0] + angle.cos() * v[2],
+        (-angle).sin() * x + angle.cos() * z + 0.5,
     ]
 }
 
@@ -283,6 +328,7 @@ fn should_render_face(
     global_block_pos: &IVec3,
     direction: &FaceDirection,
     block_visibility: &BlockTransparency,
+    policy: &ChunkEdgePolicy,
 ) -> bool {
     let offset = match *direction {
         FaceDirection::Front => IVec3::new(0, 0, -1),
@@ -294,7 +340,11 @@ fn should_render_face(
         FaceDirection::Inset => return true,
     };
 
-    if let Some(block) = world_map.get_block_by_coordinates(&(*global_block_pos + offset)) {
+    let neighbor_pos = *global_block_pos + offset;
+    if policy.is_pending(world_map, &neighbor_pos) {
+        return false;
+    }
+    if let Some(block) = world_map.get_block_by_coordinates(&neighbor_pos) {
         let vis = block.id.get_visibility();
         match vis {
             BlockTransparency::Solid => false,
@@ -320,9 +370,10 @@ pub(crate) fn generate_chunk_mesh_lod(
     chunk_pos: &IVec3,
     uv_map: &HashMap<String, UvCoords>,
     lod_level: LodLevel,
+    policy: &ChunkEdgePolicy,
 ) -> ChunkMeshResponse {
     if lod_level == LodLevel::Lod0 {
-        return generate_chunk_mesh(world_map, chunk, chunk_pos, uv_map);
+        return generate_chunk_mesh(world_map, chunk, chunk_pos, uv_map, policy);
     }
 
     let start = Instant::now();
@@ -358,7 +409,7 @@ pub(crate) fn generate_chunk_mesh_lod(
                 let global_block_pos = to_global_pos(chunk_pos, &local_pos);
 
                 // Check if block is fully surrounded (skip rendering)
-                if is_lod_block_surrounded(world_map, &global_block_pos, &visibility, scale) {
+                if is_lod_block_surrounded(world_map, &global_block_pos, scale, policy) {
                     continue;
                 }
 
@@ -378,7 +429,7 @@ pub(crate) fn generate_chunk_mesh_lod(
                 for face in voxel.faces.iter() {
                     let uv_coords = uv_map
                         .get(&face.texture)
-                        .unwrap_or_else(|| uv_map.get("_Default").unwrap());
+                        .unwrap_or_else(|| uv_map.get(MISSING_TEXTURE).unwrap());
 
                     // Check if face should be rendered at LOD scale
                     if should_render_lod_face(
@@ -389,6 +440,7 @@ pub(crate) fn generate_chunk_mesh_lod(
                         &face.direction,
                         &visibility,
                         scale,
+                        policy,
                     ) {
                         render_face_scaled(
                             &mut local_vertices,
@@ -438,6 +490,7 @@ pub(crate) fn generate_chunk_mesh_lod(
         } else {
             None
         },
+        mesh_duration: start.elapsed(),
     }
 }
 
@@ -447,8 +500,8 @@ pub(crate) fn generate_chunk_mesh_lod(
 fn is_lod_block_surrounded(
     world_map: &ClientWorldMap,
     global_block_pos: &IVec3,
-    _block_visibility: &BlockTransparency,
     scale: i32,
+    policy: &ChunkEdgePolicy,
 ) -> bool {
     // For LOD, we need to check if all 6 faces would be fully occluded.
     // To be conservative and avoid holes, we check if the LOD sample point
@@ -464,6 +517,9 @@ fn is_lod_block_surrounded(
 
     for offset in &offsets {
         let neighbor_pos = *global_block_pos + *offset;
+        if policy.is_pending(world_map, &neighbor_pos) {
+            continue;
+        }
 
         match world_map.get_block_by_coordinates(&neighbor_pos) {
             Some(block) if block.id.get_visibility() == BlockTransparency::Solid => {}
@@ -475,7 +531,8 @@ fn is_lod_block_surrounded(
 }
 
 /// Determine if a face should be rendered at LOD scale.
-/// Conservative at chunk boundaries: always render faces at edges.
+/// Conservative at chunk boundaries: faces at edges are rendered unless the
+/// neighbor chunk is pending, see [`ChunkEdgePolicy`].
 fn should_render_lod_face(
     world_map: &ClientWorldMap,
     chunk: &ClientChunk,
@@ -484,6 +541,7 @@ fn should_render_lod_face(
     direction: &FaceDirection,
     block_visibility: &BlockTransparency,
     scale: i32,
+    policy: &ChunkEdgePolicy,
 ) -> bool {
     let (offset, is_chunk_edge) = match *direction {
         FaceDirection::Front => (
@@ -519,6 +577,9 @@ fn should_render_lod_face(
 
     if is_chunk_edge {
         // Cross-chunk boundary: use world map lookup, render face if neighbor is unknown
+        if policy.is_pending(world_map, &neighbor_pos) {
+            return false;
+        }
         if let Some(block) = world_map.get_block_by_coordinates(&neighbor_pos) {
             let vis = block.id.get_visibility();
             match vis {
@@ -1,12 +1,18 @@
+pub mod bubbles;
+pub mod far_terrain;
+pub mod fluid_particles;
+pub mod icons;
 pub mod materials;
 pub mod meshing;
 pub mod render;
 pub mod render_distance;
+pub mod stats;
 pub mod voxel;
 pub mod water;
 
 pub use materials::*;
 pub use render::*;
 pub use render_distance::*;
+pub use stats::*;
 // Note: water module types are imported directly where needed (game.rs)
 // to avoid polluting the rendering namespace
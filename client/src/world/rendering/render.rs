@@ -21,8 +21,9 @@ use crate::{
 
 use crate::world::{ClientChunk, ClientWorldMap};
 
-use super::meshing::ChunkMeshResponse;
+use super::meshing::{ChunkEdgePolicy, ChunkMeshResponse};
 use super::render_distance::RenderDistance;
+use super::stats::{mesh_counts, ChunkMeshStats};
 
 #[derive(Debug)]
 pub struct MeshingTask {
@@ -57,7 +58,7 @@ fn update_chunk(
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
     new_meshes: ChunkMeshResponse,
-    _lod_level: LodLevel, // Kept for potential future use; LOD is set when task is queued
+    lod_level: LodLevel,
 ) {
     let solid_texture = material_resource
         .global_materials
@@ -77,8 +78,30 @@ fn update_chunk(
             (chunk_pos.z * CHUNK_SIZE) as f32,
         );
 
+        let (solid_vertex_count, solid_index_count) = new_meshes
+            .solid_mesh
+            .as_ref()
+            .map(mesh_counts)
+            .unwrap_or_default();
+
+        // Water fields are filled in by `chunk_water_stats_system`
+        let stats = ChunkMeshStats {
+            chunk_pos: *chunk_pos,
+            block_count: chunk.map.len(),
+            lod_level: format!("{lod_level:?}"),
+            solid_vertex_count,
+            solid_index_count,
+            last_remesh_duration: new_meshes.mesh_duration,
+            ..default()
+        };
+
         let new_entity = commands
-            .spawn((chunk_t, Visibility::Visible))
+            .spawn((
+                Name::new(format!("Chunk {chunk_pos}")),
+                chunk_t,
+                Visibility::Visible,
+                stats,
+            ))
             .with_children(|root| {
                 // Spawn solid mesh
                 if let Some(new_solid_mesh) = new_meshes.solid_mesh {
@@ -133,8 +156,9 @@ pub fn world_render_system(
 
     if !events.is_empty() {
         // Clone map only when it changed, then share it as read-only across all meshing threads
-        let map_ptr =
-            if world_map.dirty || world_map_cache.cached.is_none() {
+        let map_ptr = match &world_map_cache.cached {
+            Some(cached) if !world_map.dirty => Arc::clone(cached),
+            _ => {
                 let start = std::time::Instant::now();
                 let new_clone = Arc::new(world_map.clone());
                 world_map.dirty = false;
@@ -142,19 +166,17 @@ pub fn world_render_system(
                 let delta = start.elapsed();
                 info!("cloning map for render, took {:?}", delta);
                 new_clone
-            } else {
-                Arc::clone(world_map_cache.cached.as_ref().expect(
-                    "World map cache should be populated after first clone; caching logic bug",
-                ))
-            };
+            }
+        };
 
         // Cache UV map to avoid cloning every frame
-        let uvs = if uv_map_cache.cached.is_none() {
-            let new_uvs = Arc::new(material_resource.blocks.as_ref().unwrap().uvs.clone());
-            uv_map_cache.cached = Some(Arc::clone(&new_uvs));
-            new_uvs
-        } else {
-            Arc::clone(uv_map_cache.cached.as_ref().unwrap())
+        let uvs = match &uv_map_cache.cached {
+            Some(cached) => Arc::clone(cached),
+            None => {
+                let new_uvs = Arc::new(material_resource.blocks.as_ref().unwrap().uvs.clone());
+                uv_map_cache.cached = Some(Arc::clone(&new_uvs));
+                new_uvs
+            }
         };
 
         let player_pos = player_pos
@@ -168,6 +190,9 @@ pub fn world_render_system(
         ));
 
         let mut chunks_to_reload: HashSet<IVec3> = HashSet::new();
+        // Chunks meshed while a neighbor that just arrived was missing, meshed again
+        // even if their LOD didn't change
+        let mut outdated_chunks: HashSet<IVec3> = HashSet::new();
 
         // Using a set so same chunks are not reloaded multiple times
         // Accumulate chunks to render
@@ -176,6 +201,18 @@ pub fn world_render_system(
 
             chunks_to_reload.insert(*target_chunk_pos);
 
+            for offset in &SIX_OFFSETS {
+                let neighbor_pos = *target_chunk_pos + *offset;
+                if world_map
+                    .map
+                    .get(&neighbor_pos)
+                    .is_some_and(|neighbor| neighbor.missing_neighbors.contains(target_chunk_pos))
+                {
+                    chunks_to_reload.insert(neighbor_pos);
+                    outdated_chunks.insert(neighbor_pos);
+                }
+            }
+
             // Only add neighbor chunks for LOD0 chunks.
             // LOD1 chunks use simplified meshes that don't need neighbor precision,
             // so we skip the neighbor cascade to reduce mesh generation overhead.
@@ -189,6 +226,10 @@ pub fn world_render_system(
         }
 
         let mut chunks_to_reload = Vec::from_iter(chunks_to_reload);
+        let policy = ChunkEdgePolicy {
+            center: player_chunk_pos,
+            loaded_distance: render_distance.server_distance,
+        };
 
         chunks_to_reload.sort_by_key(|pos| pos.distance_squared(player_chunk_pos));
 
@@ -206,7 +247,10 @@ pub fn world_render_system(
 
                 // Skip if this chunk is already at the correct LOD level
                 // This prevents redundant mesh regeneration when events fire multiple times
-                if chunk_arc.current_lod == lod_level && chunk_arc.entity.is_some() {
+                if chunk_arc.current_lod == lod_level
+                    && chunk_arc.entity.is_some()
+                    && !outdated_chunks.contains(&pos)
+                {
                     continue;
                 }
 
@@ -214,6 +258,11 @@ pub fn world_render_system(
                 // from queuing duplicate events while the mesh task is in progress
                 let chunk = Arc::make_mut(chunk_arc);
                 chunk.current_lod = lod_level;
+                chunk.missing_neighbors = SIX_OFFSETS
+                    .iter()
+                    .map(|offset| pos + *offset)
+                    .filter(|neighbor_pos| !map_ptr.map.contains_key(neighbor_pos))
+                    .collect();
 
                 // Define variables to move to the thread
                 let map_clone = Arc::clone(&map_ptr);
@@ -221,7 +270,7 @@ pub fn world_render_system(
                 let ch = chunk_arc.clone();
                 let t = pool.spawn(async move {
                     world::meshing::generate_chunk_mesh_lod(
-                        &map_clone, &ch, &pos, &uvs_clone, lod_level,
+                        &map_clone, &ch, &pos, &uvs_clone, lod_level, &policy,
                     )
                 });
 
@@ -10,9 +10,11 @@ use shared::{
     DEFAULT_RENDER_DISTANCE,
 };
 
-#[derive(Resource, Default, Reflect)]
+#[derive(Resource, Default, Reflect, Debug)]
 pub struct RenderDistance {
     pub distance: i32,
+    /// Distance up to which the server sends chunks, told when connecting
+    pub server_distance: i32,
 }
 
 impl RenderDistance {
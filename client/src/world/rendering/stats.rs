@@ -0,0 +1,87 @@
+//! Per-chunk mesh statistics.
+//!
+//! A `ChunkMeshStats` component is attached to every chunk entity so that selecting
+//! a chunk in the inspector UI shows how expensive its meshes are. This is purely
+//! a debugging aid for meshing performance work and is not read by gameplay code.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::water::WaterEntities;
+
+/// Rough classification of the water contained in a chunk, based on its water block count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum WaterBodySize {
+    #[default]
+    None,
+    Puddle,
+    Pond,
+    Lake,
+    Ocean,
+}
+
+impl WaterBodySize {
+    /// Classifies a chunk's water from the number of water blocks it contains.
+    /// A full chunk holds `CHUNK_SIZE^3` = 4096 blocks.
+    pub fn from_block_count(count: usize) -> Self {
+        match count {
+            0 => WaterBodySize::None,
+            1..=8 => WaterBodySize::Puddle,
+            9..=128 => WaterBodySize::Pond,
+            129..=1024 => WaterBodySize::Lake,
+            _ => WaterBodySize::Ocean,
+        }
+    }
+}
+
+/// Mesh statistics for a chunk, displayed by the inspector when the chunk entity is selected.
+#[derive(Component, Debug, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct ChunkMeshStats {
+    pub chunk_pos: IVec3,
+    pub block_count: usize,
+    pub lod_level: String,
+    pub solid_vertex_count: usize,
+    pub solid_index_count: usize,
+    pub water_block_count: usize,
+    pub water_vertex_count: usize,
+    pub water_index_count: usize,
+    pub water_body_size: WaterBodySize,
+    /// Time spent generating the solid mesh on the meshing thread
+    pub last_remesh_duration: Duration,
+}
+
+/// Returns the (vertex, index) counts of a mesh.
+pub fn mesh_counts(mesh: &Mesh) -> (usize, usize) {
+    (
+        mesh.count_vertices(),
+        mesh.indices().map(|indices| indices.len()).unwrap_or(0),
+    )
+}
+
+/// Copies water mesh statistics from `WaterEntities` into the chunk stats components.
+/// Water meshes live on separate entities (see `rendering/water.rs`), so they are
+/// joined back to their chunk here using the chunk position.
+pub fn chunk_water_stats_system(
+    water_entities: Res<WaterEntities>,
+    mut stats_query: Query<&mut ChunkMeshStats>,
+) {
+    let water_changed = water_entities.is_changed();
+
+    for mut stats in stats_query.iter_mut() {
+        if !water_changed && !stats.is_added() {
+            continue;
+        }
+
+        let (block_count, vertex_count, index_count) = water_entities
+            .entities
+            .get(&stats.chunk_pos)
+            .map(|data| (data.water_block_count, data.vertex_count, data.index_count))
+            .unwrap_or_default();
+
+        stats.water_block_count = block_count;
+        stats.water_vertex_count = vertex_count;
+        stats.water_index_count = index_count;
+        stats.water_body_size = WaterBodySize::from_block_count(block_count);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::constants::GRASS_COLOR;
-use shared::world::{BlockData, BlockId};
+use crate::constants::{GRASS_COLOR, HYDRATED_FARMLAND_COLOR, SWAMP_GRASS_COLOR};
+use shared::world::{BlockData, BlockId, FENCE_POST_HALF_WIDTH, ITEM_FRAME_THICKNESS};
 
 /// Specifies which position in the voxel this face occupies
 ///
@@ -57,13 +57,50 @@ impl VoxelShape {
 
                 shape
             }
-            BlockId::OakLog | BlockId::SpruceLog | BlockId::Cactus => {
+            BlockId::SwampGrass => {
+                let mut shape = Self::full_cube(block);
+
+                // Same top as the grass, darker
+                shape.faces[0].texture = "GrassTop".into();
+                for col in shape.faces[0].colors.iter_mut() {
+                    *col = SWAMP_GRASS_COLOR;
+                }
+
+                shape
+            }
+            BlockId::Mycelium => {
+                let mut shape = Self::full_cube(block);
+                shape.faces[0].texture += "Top";
+                shape.faces[1].texture = "Dirt".into();
+                shape
+            }
+            BlockId::Farmland => {
+                let mut shape = Self::full_cube(block);
+                shape.faces[0].texture += "Top";
+
+                // Wet soil is darker
+                if block.level > 0 {
+                    for col in shape.faces[0].colors.iter_mut() {
+                        *col = HYDRATED_FARMLAND_COLOR;
+                    }
+                }
+
+                shape
+            }
+            BlockId::OakLog
+            | BlockId::SpruceLog
+            | BlockId::BirchLog
+            | BlockId::JungleLog
+            | BlockId::Cactus => {
                 let mut shape = Self::full_cube(block);
                 shape.faces[0].texture += "Top";
                 shape.faces[1].texture += "Top";
                 shape
             }
-            BlockId::OakLeaves | BlockId::SpruceLeaves => {
+            BlockId::OakLeaves
+            | BlockId::SpruceLeaves
+            | BlockId::BirchLeaves
+            | BlockId::JungleLeaves => {
                 let mut shape = Self::full_cube(block);
 
                 // Apply leaves color
@@ -85,7 +122,93 @@ impl VoxelShape {
                 shape.faces[5].texture = "Right".into();
                 shape
             }
-            BlockId::Poppy | BlockId::Dandelion => Self::flora(block),
+            BlockId::SnowLayer => {
+                let mut shape = Self::full_cube(block);
+                let height = block.height();
+
+                // Flattened snow block
+                for face in shape.faces.iter_mut() {
+                    face.texture = "Snow".into();
+                    for vertex in face.vertices.iter_mut() {
+                        vertex[1] *= height;
+                    }
+                }
+
+                shape
+            }
+            BlockId::Puddle => {
+                let mut shape = Self::full_cube(block);
+                let height = block.height();
+
+                // Only the surface of the water shows, just above the ground
+                shape.faces.truncate(1);
+                for vertex in shape.faces[0].vertices.iter_mut() {
+                    vertex[1] *= height;
+                }
+
+                shape
+            }
+            BlockId::Bed => {
+                let mut shape = Self::full_cube(block);
+                let height = block.height();
+
+                shape.faces[0].texture += "Top";
+                for face in shape.faces.iter_mut() {
+                    for vertex in face.vertices.iter_mut() {
+                        vertex[1] *= height;
+                    }
+                }
+
+                shape
+            }
+            BlockId::OakFence => {
+                let mut shape = Self::full_cube(block);
+
+                // Thin post in the middle of the block, its sides are never hidden
+                for face in shape.faces.iter_mut() {
+                    face.texture = "OakPlanks".into();
+                    if !matches!(face.direction, FaceDirection::Top | FaceDirection::Bottom) {
+                        face.direction = FaceDirection::Inset;
+                    }
+                    for vertex in face.vertices.iter_mut() {
+                        vertex[0] = 0.5 + (vertex[0] - 0.5) * FENCE_POST_HALF_WIDTH * 2.0;
+                        vertex[2] = 0.5 + (vertex[2] - 0.5) * FENCE_POST_HALF_WIDTH * 2.0;
+                    }
+                }
+
+                shape
+            }
+            BlockId::ItemFrame => {
+                let mut shape = Self::full_cube(block);
+
+                // Thin board against the wall behind it, the block direction turns it
+                // to face away from its wall
+                for face in shape.faces.iter_mut() {
+                    face.texture = if face.direction == FaceDirection::Front {
+                        "ItemFrame".into()
+                    } else {
+                        "OakPlanks".into()
+                    };
+                    face.direction = FaceDirection::Inset;
+                    for vertex in face.vertices.iter_mut() {
+                        vertex[2] = 1.0 - (1.0 - vertex[2]) * ITEM_FRAME_THICKNESS;
+                    }
+                }
+
+                shape
+            }
+            BlockId::Poppy | BlockId::Dandelion | BlockId::Kelp => Self::flora(block),
+            BlockId::Vine => {
+                let mut shape = Self::flora(block);
+
+                for face in shape.faces.iter_mut() {
+                    for col in face.colors.iter_mut() {
+                        *col = SWAMP_GRASS_COLOR;
+                    }
+                }
+
+                shape
+            }
             BlockId::TallGrass => {
                 let mut shape = Self::flora(block);
 
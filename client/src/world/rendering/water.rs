@@ -21,7 +21,9 @@ use bevy::{
 };
 use std::collections::{hash_map::Entry, HashMap, HashSet};
 
-use crate::shaders::water::{StandardWaterMaterial, WaterMaterial, WaterMesh};
+use super::stats::mesh_counts;
+use crate::shaders::water::{StandardWaterMaterial, WaterMaterial, WaterMesh, WaterSettings};
+use crate::ui::menus::settings::graphics::GraphicsSettings;
 use crate::world::{ClientWorldMap, WorldRenderRequestUpdateEvent};
 use crate::GameState;
 use bevy::pbr::{ExtendedMaterial, NotShadowCaster, NotShadowReceiver};
@@ -51,13 +53,19 @@ pub struct WaterEntities {
 pub struct WaterEntityData {
     pub entity: Entity,
     pub mesh_handle: Handle<Mesh>,
+    /// Mesh statistics, consumed by the inspector (see `rendering/stats.rs`)
+    pub water_block_count: usize,
+    pub vertex_count: usize,
+    pub index_count: usize,
 }
 
-/// Resource to store water material handle.
+/// Resource to store water material handles.
 /// Using a single material with ocean amplitude for seamless cross-chunk rendering.
 #[derive(Resource, Default)]
 pub struct WaterMaterialHandle {
     pub handle: Option<Handle<StandardWaterMaterial>>,
+    /// Flat tinted material used when the water quality preset is `Off`
+    pub flat_handle: Option<Handle<StandardMaterial>>,
 }
 
 impl WaterMaterialHandle {
@@ -67,8 +75,27 @@ impl WaterMaterialHandle {
             .expect("Water material should be initialized before use")
     }
 
+    pub fn get_flat(&self) -> Handle<StandardMaterial> {
+        self.flat_handle
+            .clone()
+            .expect("Flat water material should be initialized before use")
+    }
+
     pub fn is_initialized(&self) -> bool {
-        self.handle.is_some()
+        self.handle.is_some() && self.flat_handle.is_some()
+    }
+
+    fn init(
+        &mut self,
+        water_materials: &mut Assets<StandardWaterMaterial>,
+        flat_materials: &mut Assets<StandardMaterial>,
+    ) {
+        if self.handle.is_none() {
+            self.handle = Some(create_water_material(water_materials));
+        }
+        if self.flat_handle.is_none() {
+            self.flat_handle = Some(create_flat_water_material(flat_materials));
+        }
     }
 }
 
@@ -97,6 +124,19 @@ fn create_water_material(
     })
 }
 
+/// Create the fallback water material, used when the water shader is disabled.
+fn create_flat_water_material(
+    materials: &mut Assets<StandardMaterial>,
+) -> Handle<StandardMaterial> {
+    materials.add(StandardMaterial {
+        base_color: Color::srgba(0.1, 0.3, 0.5, 0.8),
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 1.0,
+        reflectance: 0.0,
+        ..default()
+    })
+}
+
 /// Pooled allocations for water mesh generation to avoid per-frame heap allocations.
 #[derive(Default)]
 pub struct WaterMeshGenPool {
@@ -208,6 +248,17 @@ fn generate_water_mesh_for_chunk(
     Some(mesh)
 }
 
+/// Counts every water block in a chunk, including submerged ones.
+fn count_water_blocks(world_map: &ClientWorldMap, chunk_pos: &IVec3) -> usize {
+    world_map.map.get(chunk_pos).map_or(0, |chunk| {
+        chunk
+            .map
+            .values()
+            .filter(|block| block.id == BlockId::Water)
+            .count()
+    })
+}
+
 /// System that listens for chunk updates and regenerates water meshes.
 /// Water entities are independent from chunk entities.
 ///
@@ -221,6 +272,8 @@ pub fn water_render_system(
     mut water_entities: ResMut<WaterEntities>,
     mut water_material: ResMut<WaterMaterialHandle>,
     mut materials: ResMut<Assets<StandardWaterMaterial>>,
+    mut flat_materials: ResMut<Assets<StandardMaterial>>,
+    graphics_settings: Res<GraphicsSettings>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut ev_chunk_update: EventReader<WorldRenderRequestUpdateEvent>,
     mut mesh_pool: Local<WaterMeshGenPool>,
@@ -228,7 +281,7 @@ pub fn water_render_system(
 ) {
     // Initialize water material if needed
     if !water_material.is_initialized() {
-        water_material.handle = Some(create_water_material(&mut materials));
+        water_material.init(&mut materials, &mut flat_materials);
     }
 
     // Early return if no events - avoid any allocations
@@ -248,13 +301,16 @@ pub fn water_render_system(
 
     for chunk_pos in chunks_to_update.iter().copied() {
         // Check if we have an existing entity for this chunk
-        if let Some(existing_data) = water_entities.entities.get(&chunk_pos) {
+        if let Some(existing_data) = water_entities.entities.get_mut(&chunk_pos) {
             // Try to update existing mesh in-place
             if let Some(water_mesh) =
                 generate_water_mesh_for_chunk(&world_map, &chunk_pos, &mut mesh_pool)
             {
                 // Update existing mesh asset in-place (avoids GPU resource churn)
                 if let Some(mesh_asset) = meshes.get_mut(&existing_data.mesh_handle) {
+                    (existing_data.vertex_count, existing_data.index_count) =
+                        mesh_counts(&water_mesh);
+                    existing_data.water_block_count = count_water_blocks(&world_map, &chunk_pos);
                     *mesh_asset = water_mesh;
                     continue;
                 }
@@ -281,28 +337,36 @@ pub fn water_render_system(
                 (chunk_pos.z * CHUNK_SIZE) as f32,
             );
 
+            let (vertex_count, index_count) = mesh_counts(&water_mesh);
+
             // Create a new mesh handle that we can track for future updates
             let mesh_handle = meshes.add(water_mesh);
 
-            let entity = commands
-                .spawn((
-                    StateScoped(GameState::Game),
-                    transform,
-                    Visibility::Visible,
-                    Mesh3d(mesh_handle.clone()),
-                    MeshMaterial3d(water_material.get()),
-                    WaterMesh,
-                    WaterSurface,
-                    NotShadowCaster,
-                    NotShadowReceiver,
-                ))
-                .id();
+            let mut entity_commands = commands.spawn((
+                StateScoped(GameState::Game),
+                transform,
+                Visibility::Visible,
+                Mesh3d(mesh_handle.clone()),
+                WaterMesh,
+                WaterSurface,
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+            if graphics_settings.water_quality.uses_water_shader() {
+                entity_commands.insert(MeshMaterial3d(water_material.get()));
+            } else {
+                entity_commands.insert(MeshMaterial3d(water_material.get_flat()));
+            }
+            let entity = entity_commands.id();
 
             water_entities.entities.insert(
                 chunk_pos,
                 WaterEntityData {
                     entity,
                     mesh_handle,
+                    water_block_count: count_water_blocks(&world_map, &chunk_pos),
+                    vertex_count,
+                    index_count,
                 },
             );
         }
@@ -340,3 +404,45 @@ pub fn water_cleanup_system(
         }
     }
 }
+
+/// Applies the water quality preset from the graphics settings.
+/// Runs whenever the settings change, which also covers entering the game.
+pub fn apply_water_quality_system(
+    mut commands: Commands,
+    graphics_settings: Res<GraphicsSettings>,
+    mut water_settings: ResMut<WaterSettings>,
+    mut water_material: ResMut<WaterMaterialHandle>,
+    mut materials: ResMut<Assets<StandardWaterMaterial>>,
+    mut flat_materials: ResMut<Assets<StandardMaterial>>,
+    surfaces: Query<Entity, With<WaterSurface>>,
+) {
+    let preset = graphics_settings.water_quality;
+
+    if !water_material.is_initialized() {
+        water_material.init(&mut materials, &mut flat_materials);
+    }
+
+    // bevy_water pushes these into every water material when `WaterSettings` changes
+    water_settings.water_quality = preset.shader_quality();
+    water_settings.amplitude = preset.amplitude();
+
+    if let Some(material) = materials.get_mut(&water_material.get()) {
+        material.base.reflectance = preset.reflectance();
+    }
+
+    // Swap the material of the water surfaces that are already spawned
+    for entity in surfaces.iter() {
+        let mut entity_commands = commands.entity(entity);
+        if preset.uses_water_shader() {
+            entity_commands
+                .remove::<MeshMaterial3d<StandardMaterial>>()
+                .insert(MeshMaterial3d(water_material.get()));
+        } else {
+            entity_commands
+                .remove::<MeshMaterial3d<StandardWaterMaterial>>()
+                .insert(MeshMaterial3d(water_material.get_flat()));
+        }
+    }
+
+    info!("Water quality set to {}", preset.name());
+}
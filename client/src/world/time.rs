@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use shared::messages::WorldTimeSkip;
+use shared::TICKS_PER_SECOND;
+
+/// Ticks the sky takes to move to the morning when the night is skipped
+const TIME_SKIP_DURATION_TICKS: u64 = 3 * TICKS_PER_SECOND;
 
 #[derive(Resource, Serialize, Deserialize, Debug, Clone)]
 pub struct ClientTime(pub u64);
@@ -8,3 +13,44 @@ pub fn time_update_system(mut time: ResMut<ClientTime>) {
     time.0 += 1;
     // NOTE: time should eventually be periodically synced with the server to avoid drift using a NTP-like protocol
 }
+
+/// Night skip being eased into the client time
+#[derive(Debug, Default)]
+pub struct TimeSkipProgress {
+    ticks: u64,
+    applied: u64,
+    elapsed: u64,
+}
+
+/// Applies skipped ticks progressively so the sky moves to the morning instead of
+/// snapping to it
+pub fn time_skip_system(
+    mut events: EventReader<WorldTimeSkip>,
+    mut time: ResMut<ClientTime>,
+    mut progress: Local<TimeSkipProgress>,
+) {
+    for skip in events.read() {
+        // Finish any ongoing skip before starting the new one
+        time.0 += progress.ticks - progress.applied;
+        *progress = TimeSkipProgress {
+            ticks: skip.tick.saturating_sub(time.0),
+            ..default()
+        };
+    }
+
+    if progress.applied == progress.ticks {
+        return;
+    }
+
+    progress.elapsed += 1;
+    let t = (progress.elapsed as f32 / TIME_SKIP_DURATION_TICKS as f32).min(1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    let target = if t >= 1.0 {
+        progress.ticks
+    } else {
+        (progress.ticks as f32 * eased) as u64
+    };
+
+    time.0 += target.saturating_sub(progress.applied);
+    progress.applied = progress.applied.max(target);
+}
@@ -0,0 +1,218 @@
+//! Water flowing into the blocks the player breaks, shown before the server
+//! moves it.
+//!
+//! The server moves water on its next flow step, and the chunk then has to come
+//! back over the network, so a block broken next to water stays dry for a
+//! moment. Right after a local break, the client takes that first step itself,
+//! the way the fluid mode of the server would. Predicted blocks are put back
+//! over the chunks received as long as the server still has the blocks from
+//! before the prediction. Once the server shows anything else there, it has the
+//! last word, and predictions it never answers are undone after a while.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use shared::fluid::{FluidKind, FluidMode};
+use shared::players::blocks::BlockInteractionOutcome;
+use shared::world::{global_to_chunk_local, BlockData, BlockId, WorldMap};
+
+use crate::world::{ClientWorldMap, WorldRenderRequestUpdateEvent};
+
+/// Time left to the server to move the water as predicted
+const PREDICTION_LIFETIME: Duration = Duration::from_millis(1500);
+
+const HORIZONTAL_NEIGHBOURS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// Block broken or placed by the local player, before the server confirms it
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LocalBlockEditEvent(pub BlockInteractionOutcome);
+
+struct Prediction {
+    /// Block before the prediction, still there on the server until it moves
+    /// the water
+    before: Option<BlockData>,
+    predicted: Option<BlockData>,
+    expires_at: Instant,
+}
+
+#[derive(Resource, Default)]
+pub struct WaterPrediction {
+    /// Fluid mode of the server, sent when joining
+    pub fluid_mode: FluidMode,
+    predictions: HashMap<IVec3, Prediction>,
+}
+
+impl WaterPrediction {
+    fn predict(
+        &mut self,
+        world_map: &mut ClientWorldMap,
+        position: IVec3,
+        predicted: Option<BlockData>,
+        now: Instant,
+    ) {
+        let before = world_map.get_block_by_coordinates(&position).copied();
+        match predicted {
+            Some(block) => world_map.set_block(&position, block),
+            None => {
+                world_map.remove_block_by_coordinates(&position);
+            }
+        }
+        self.predictions.insert(
+            position,
+            Prediction {
+                before,
+                predicted,
+                expires_at: now + PREDICTION_LIFETIME,
+            },
+        );
+    }
+
+    /// Puts the predicted blocks back into a chunk received from the server,
+    /// unless the server already moved on
+    pub fn reconcile(&mut self, chunk_pos: IVec3, blocks: &mut HashMap<IVec3, BlockData>) {
+        let now = Instant::now();
+        self.predictions.retain(|position, prediction| {
+            let (prediction_chunk, local_pos) = global_to_chunk_local(position);
+            if prediction_chunk != chunk_pos {
+                return true;
+            }
+            if blocks.get(&local_pos).copied() != prediction.before || now >= prediction.expires_at
+            {
+                return false;
+            }
+            match prediction.predicted {
+                Some(block) => blocks.insert(local_pos, block),
+                None => blocks.remove(&local_pos),
+            };
+            true
+        });
+    }
+}
+
+fn water_at(world_map: &ClientWorldMap, position: IVec3) -> Option<BlockData> {
+    world_map
+        .get_block_by_coordinates(&position)
+        .filter(|block| block.id == BlockId::Water)
+        .copied()
+}
+
+fn is_open(world_map: &ClientWorldMap, position: IVec3) -> bool {
+    world_map
+        .get_block_by_coordinates(&position)
+        .is_none_or(|block| block.id.is_replaceable())
+}
+
+/// Whether water resting at `position` spreads sideways in the classic mode,
+/// rather than falling or lying on more water
+fn rests_on_ground(world_map: &ClientWorldMap, position: IVec3) -> bool {
+    !is_open(world_map, position - IVec3::Y) && water_at(world_map, position - IVec3::Y).is_none()
+}
+
+/// Water flowing into the emptied block at `position`: its new block, and the
+/// block the water moves from in the finite mode
+fn inflow(
+    world_map: &ClientWorldMap,
+    fluid_mode: FluidMode,
+    position: IVec3,
+) -> Option<(BlockData, Option<IVec3>)> {
+    let above = position + IVec3::Y;
+    match fluid_mode {
+        FluidMode::Finite => {
+            if let Some(water) = water_at(world_map, above) {
+                return Some((water, Some(above)));
+            }
+            // Water only moves sideways off a ledge
+            if !is_open(world_map, position - IVec3::Y) {
+                return None;
+            }
+            HORIZONTAL_NEIGHBOURS.iter().find_map(|offset| {
+                let neighbour = position + *offset;
+                water_at(world_map, neighbour).map(|water| (water, Some(neighbour)))
+            })
+        }
+        FluidMode::Classic => {
+            if let Some(water) = water_at(world_map, above) {
+                return Some((BlockData { level: 1, ..water }, None));
+            }
+            HORIZONTAL_NEIGHBOURS
+                .iter()
+                .map(|offset| position + *offset)
+                .filter_map(|neighbour| {
+                    let water = water_at(world_map, neighbour)?;
+                    (water.level < FluidKind::Water.max_spread()
+                        && rests_on_ground(world_map, neighbour))
+                    .then_some(water)
+                })
+                .min_by_key(|water| water.level)
+                .map(|water| {
+                    (
+                        BlockData {
+                            level: water.level + 1,
+                            ..water
+                        },
+                        None,
+                    )
+                })
+        }
+    }
+}
+
+/// Lets the water next to the blocks broken by the player flow into them
+pub fn predict_water_inflow_system(
+    mut events: EventReader<LocalBlockEditEvent>,
+    mut world_map: ResMut<ClientWorldMap>,
+    mut prediction: ResMut<WaterPrediction>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+) {
+    let now = Instant::now();
+    for LocalBlockEditEvent(outcome) in events.read() {
+        let BlockInteractionOutcome::Broken { position, .. } = *outcome else {
+            continue;
+        };
+        if !is_open(&world_map, position) {
+            continue;
+        }
+        let fluid_mode = prediction.fluid_mode;
+        let Some((water, source)) = inflow(&world_map, fluid_mode, position) else {
+            continue;
+        };
+
+        prediction.predict(&mut world_map, position, Some(water), now);
+        ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(
+            global_to_chunk_local(&position).0,
+        ));
+        if let Some(source) = source {
+            prediction.predict(&mut world_map, source, None, now);
+            ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(
+                global_to_chunk_local(&source).0,
+            ));
+        }
+    }
+}
+
+/// Undoes the predictions the server never answered
+pub fn expire_water_predictions_system(
+    mut world_map: ResMut<ClientWorldMap>,
+    mut prediction: ResMut<WaterPrediction>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+) {
+    let now = Instant::now();
+    prediction.predictions.retain(|position, prediction| {
+        if now < prediction.expires_at {
+            return true;
+        }
+        if world_map.get_block_by_coordinates(position).copied() == prediction.predicted {
+            match prediction.before {
+                Some(block) => world_map.set_block(position, block),
+                None => {
+                    world_map.remove_block_by_coordinates(position);
+                }
+            }
+            ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(
+                global_to_chunk_local(position).0,
+            ));
+        }
+        false
+    });
+}
@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use shared::messages::WaystoneUpdate;
+use shared::world::SoundGroup;
+
+use crate::audio::{BlockSoundEvent, BlockSoundKind};
+use crate::GameState;
+
+/// Seconds a waystone effect lasts
+const EFFECT_DURATION: f32 = 1.5;
+/// Height the effect column rises to above the waystone
+const EFFECT_HEIGHT: f32 = 3.0;
+const EFFECT_COLOR: Color = Color::srgb(0.4, 0.7, 1.0);
+
+/// Column of light rising from a waystone when it is activated or used
+#[derive(Component)]
+pub struct WaystoneEffect {
+    elapsed: f32,
+    base: Vec3,
+}
+
+fn spawn_effect(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    position: IVec3,
+) {
+    let base = position.as_vec3() + Vec3::new(0.5, 1.0, 0.5);
+    commands.spawn((
+        WaystoneEffect { elapsed: 0.0, base },
+        Mesh3d(meshes.add(Cylinder::new(0.4, 1.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: EFFECT_COLOR.with_alpha(0.6),
+            emissive: EFFECT_COLOR.to_linear() * 4.0,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_translation(base),
+        PointLight {
+            color: EFFECT_COLOR,
+            intensity: 100_000.0,
+            range: 8.0,
+            ..default()
+        },
+        StateScoped(GameState::Game),
+    ));
+}
+
+pub fn waystone_effects_event_system(
+    mut commands: Commands,
+    mut events: EventReader<WaystoneUpdate>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ev_block_sound: EventWriter<BlockSoundEvent>,
+) {
+    for event in events.read() {
+        let positions = match event {
+            WaystoneUpdate::Activated { position, .. } => vec![*position],
+            WaystoneUpdate::Teleported { from, to, .. } => vec![*from, *to],
+            WaystoneUpdate::Menu { .. } => continue,
+        };
+
+        for position in positions {
+            spawn_effect(&mut commands, &mut meshes, &mut materials, position);
+            ev_block_sound.write(BlockSoundEvent {
+                group: SoundGroup::Glass,
+                kind: BlockSoundKind::Place,
+                position: position.as_vec3() + Vec3::splat(0.5),
+            });
+        }
+    }
+}
+
+/// Grows the effect columns, fading them out until they disappear
+pub fn waystone_effects_system(
+    mut commands: Commands,
+    mut effects: Query<(
+        Entity,
+        &mut WaystoneEffect,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+        &mut PointLight,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut effect, mut transform, material, mut light) in effects.iter_mut() {
+        effect.elapsed += time.delta_secs();
+        let progress = effect.elapsed / EFFECT_DURATION;
+        if progress >= 1.0 {
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let height = EFFECT_HEIGHT * progress.sqrt();
+        transform.translation = effect.base + Vec3::Y * height / 2.0;
+        transform.scale = Vec3::new(1.0 - progress * 0.5, height.max(0.01), 1.0 - progress * 0.5);
+
+        let fade = 1.0 - progress;
+        light.intensity = 100_000.0 * fade;
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color.set_alpha(0.6 * fade);
+        }
+    }
+}
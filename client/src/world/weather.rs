@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+use shared::{messages::WeatherUpdate, world::WeatherKind};
+
+/// Weather of the server we're connected to
+#[derive(Resource, Default, Debug)]
+pub struct ClientWeather {
+    pub kind: WeatherKind,
+}
+
+impl ClientWeather {
+    pub fn is_precipitating(&self) -> bool {
+        self.kind == WeatherKind::Precipitation
+    }
+}
+
+pub fn weather_update_system(
+    mut updates: EventReader<WeatherUpdate>,
+    mut weather: ResMut<ClientWeather>,
+) {
+    if let Some(update) = updates.read().last() {
+        weather.kind = update.kind;
+    }
+}
+
+/// The weather belongs to the server we were connected to
+pub fn reset_client_weather_system(mut weather: ResMut<ClientWeather>) {
+    *weather = ClientWeather::default();
+}
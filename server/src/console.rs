@@ -0,0 +1,166 @@
+//! Commands typed in the terminal of a dedicated server, one per line:
+//!
+//! - `backup now`: saves the world and makes a backup of it
+//! - `corrupt`: lists the chunks that were damaged on load
+//! - `regenerate <x> <y> <z> [dimension]` or `regenerate all`: generates damaged
+//!   chunks again from the seed, in the overworld unless a dimension is given
+//! - `heatmap <mined|placed|grief>`: lists the chunks where players mined, placed
+//!   or griefed the most blocks
+//! - `rollback <player> <duration>`: undoes the blocks a player placed and broke
+//!   during the last `30m`, `2h`...
+//! - `render-map <radius> <out.png>`: draws the explored overworld within
+//!   `radius` blocks of the world spawn, seen from above
+
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use shared::world::{parse_duration, DimensionId, HeatmapKind, ServerWorldMap, WorldSpawn};
+
+use crate::world::backup::request_backup;
+use crate::world::cartography::{render_world_map, MAX_MAP_RADIUS};
+use crate::world::chunk_store::CorruptChunks;
+use crate::world::heatmap::BlockHeatmap;
+use crate::world::rollback::{BlockChangeLog, RollbackFilter};
+
+/// Number of chunks listed by the `heatmap` command
+const CONSOLE_HEATMAP_CHUNKS: usize = 10;
+
+/// Lines read from the standard input by a background thread
+#[derive(Resource)]
+pub struct ConsoleInput(Mutex<Receiver<String>>);
+
+impl ConsoleInput {
+    pub fn spawn_reader() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self(Mutex::new(receiver))
+    }
+}
+
+pub fn console_commands_system(world: &mut World) {
+    let lines: Vec<String> = {
+        let input = world.resource::<ConsoleInput>();
+        let Ok(receiver) = input.0.lock() else {
+            return;
+        };
+        receiver.try_iter().collect()
+    };
+
+    for line in lines {
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => {}
+            ["backup", "now"] => {
+                info!("Backup requested from the console");
+                request_backup(world);
+            }
+            ["corrupt"] => {
+                let corrupt = world.resource::<CorruptChunks>();
+                info!("{} corrupt chunks: {:?}", corrupt.0.len(), corrupt.0);
+            }
+            ["regenerate", "all"] => {
+                let mut corrupt = world.resource_mut::<CorruptChunks>();
+                info!("Regenerating {} corrupt chunks", corrupt.0.len());
+                corrupt.0.clear();
+            }
+            ["regenerate", x, y, z, dimension @ ..] if dimension.len() <= 1 => {
+                let (Ok(x), Ok(y), Ok(z)) = (x.parse(), y.parse(), z.parse()) else {
+                    warn!("Invalid chunk position: {}", line.trim());
+                    continue;
+                };
+                let dimension = match dimension.first() {
+                    Some(name) => match DimensionId::from_name(name) {
+                        Some(dimension) => dimension,
+                        None => {
+                            warn!("Unknown dimension: {}", name);
+                            continue;
+                        }
+                    },
+                    None => DimensionId::Overworld,
+                };
+                let position = IVec3::new(x, y, z);
+                if world
+                    .resource_mut::<CorruptChunks>()
+                    .0
+                    .remove(&(dimension, position))
+                {
+                    info!(
+                        "Regenerating corrupt chunk {:?} of the {}",
+                        position,
+                        dimension.name()
+                    );
+                } else {
+                    warn!(
+                        "Chunk {:?} of the {} is not corrupt",
+                        position,
+                        dimension.name()
+                    );
+                }
+            }
+            ["heatmap", name] => {
+                let Some(kind) = HeatmapKind::from_name(name) else {
+                    warn!("Unknown heatmap: {}, expected mined, placed or grief", name);
+                    continue;
+                };
+                let busiest = world
+                    .resource::<BlockHeatmap>()
+                    .busiest(kind, CONSOLE_HEATMAP_CHUNKS);
+                info!("Chunks with the most {} blocks:", kind.name());
+                for (position, count) in busiest {
+                    info!("  {:?}: {}", position, count);
+                }
+            }
+            ["rollback", name, duration] => {
+                let Some(since_secs) = parse_duration(duration) else {
+                    warn!("Invalid duration: {}, expected 30m, 2h...", duration);
+                    continue;
+                };
+                let filter = RollbackFilter::Player(name.to_string());
+                let restored = world.resource_scope(|world, block_log: Mut<BlockChangeLog>| {
+                    let mut world_map = world.resource_mut::<ServerWorldMap>();
+                    block_log.rollback(&mut world_map, &filter, since_secs)
+                });
+                info!("Rolled back {} blocks of {}", restored, name);
+            }
+            ["render-map", radius, path] => {
+                let Some(radius) = radius
+                    .parse()
+                    .ok()
+                    .filter(|radius| (0..=MAX_MAP_RADIUS).contains(radius))
+                else {
+                    warn!(
+                        "Invalid radius: {}, expected 0 to {} blocks",
+                        radius, MAX_MAP_RADIUS
+                    );
+                    continue;
+                };
+                let spawn = world.resource::<WorldSpawn>().0;
+                let render = render_world_map(
+                    &world.resource::<ServerWorldMap>().chunks,
+                    spawn.xz(),
+                    radius,
+                );
+                match render.save_png(Path::new(path)) {
+                    Ok(()) => info!(
+                        "Rendered {} explored columns around {:?} to {}",
+                        render.explored, spawn, path
+                    ),
+                    Err(err) => warn!("Could not write the map to {}: {}", path, err),
+                }
+            }
+            _ => warn!("Unknown console command: {}", line.trim()),
+        }
+    }
+}
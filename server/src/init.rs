@@ -1,35 +1,58 @@
 use crate::{
+    console::ConsoleInput,
     network::{
         cleanup::cleanup_all_players_from_world,
         dispatcher::{self, setup_resources_and_events},
     },
-    world::{data::SAVE_PATH, load_from_file::load_world_data},
+    scheduler::{load_server_file_config, ServerScheduler},
+    world::{
+        backup::request_backup,
+        chunk_store::{load_chunks, saved_timestamps},
+        data::SAVE_PATH,
+        load_from_file::{load_biome_definitions, load_world_data},
+        rollback::BlockChangeLog,
+    },
 };
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
 };
 use bevy_app::ScheduleRunnerPlugin;
-use bevy_log::{error, info, LogPlugin};
-use bevy_renet::{netcode::NetcodeServerTransport, RenetServerPlugin};
+use bevy_log::{error, info, warn, LogPlugin};
+use bevy_renet::{
+    netcode::{generate_random_bytes, NetcodeServerTransport, NETCODE_KEY_BYTES},
+    RenetServerPlugin,
+};
 use bevy_renet::{
-    netcode::{NetcodeServerPlugin, ServerAuthentication, ServerConfig},
+    netcode::{ServerAuthentication, ServerConfig},
     renet::RenetServer,
 };
 use serde::{Deserialize, Serialize};
 use shared::{
-    constants::{NETCODE_SERVER_TRANSPORT_ERROR, SOCKET_LOCAL_ADDR_ERROR, UNIX_EPOCH_TIME_ERROR},
+    constants::{
+        NETCODE_SERVER_TRANSPORT_ERROR, SOCKET_LOCAL_ADDR_ERROR, UNIX_EPOCH_TIME_ERROR,
+        WEBSOCKET_LISTENER_ERROR,
+    },
     get_shared_renet_config,
     messages::PlayerId,
     physics::RustcraftPhysicsPlugin,
-    world::{ServerChunkWorldMap, ServerWorldMap},
+    transport::{
+        memory::MemoryServerTransport,
+        secure::{spawn_handshake_server, ServerIdentity, IDENTITY_KEY_FILE},
+        websocket::WebSocketServerTransport,
+        ServerTransportPlugin,
+    },
+    world::{
+        find_world_spawn, DimensionId, ServerChunkWorldMap, ServerWorldMap, WorldGenPreset,
+        WorldSpawn,
+    },
     GameFolderPaths, GameServerConfig, TICKS_PER_SECOND,
 };
 use std::fmt::{Debug, Display, Formatter};
 use std::time::{Duration, SystemTime, SystemTimeError};
 use std::{collections::HashMap, net::IpAddr};
 
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, UdpSocket};
 
 #[derive(Resource, Serialize, Deserialize, Debug, Clone)]
 pub struct ServerTime(pub u64);
@@ -71,7 +94,6 @@ impl Display for NetcodeSetupError {
     }
 }
 
-#[allow(dead_code)]
 pub fn acquire_local_ephemeral_udp_socket(ip: IpAddr) -> std::io::Result<UdpSocket> {
     acquire_socket_by_port(ip, 0)
 }
@@ -81,9 +103,97 @@ pub fn acquire_socket_by_port(ip: IpAddr, port: u16) -> std::io::Result<UdpSocke
     UdpSocket::bind(addr)
 }
 
+/// Binds the UDP socket of a dedicated server, on the interface of `bind` or on
+/// every interface of both IP versions when none is given
+pub fn acquire_server_socket(bind: Option<IpAddr>, port: u16) -> std::io::Result<UdpSocket> {
+    if let Some(ip) = bind {
+        return acquire_socket_by_port(ip, port);
+    }
+
+    let any_v4 = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+    let socket = match acquire_socket_by_port(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port) {
+        Ok(socket) => socket,
+        // IPv6 is disabled on this machine
+        Err(_) => return acquire_socket_by_port(any_v4, port),
+    };
+    let port = socket.local_addr()?.port();
+
+    // A dual-stack socket also holds the IPv4 port, otherwise the system only
+    // gives IPv6 sockets and the IPv4 clients are the ones to keep
+    match acquire_socket_by_port(any_v4, port) {
+        Ok(socket) => {
+            // the logger only starts with the server
+            eprintln!("Warning: this system has no dual-stack sockets, use --bind :: to accept IPv6 clients instead of IPv4 ones");
+            Ok(socket)
+        }
+        Err(_) => Ok(socket),
+    }
+}
+
+/// Addresses to share with the players, or to forward the port of the server to
+fn address_candidates(addr: SocketAddr) -> Vec<SocketAddr> {
+    if !addr.ip().is_unspecified() {
+        return vec![addr];
+    }
+
+    let mut ips = vec![IpAddr::V4(Ipv4Addr::LOCALHOST)];
+    // Connecting a UDP socket sends nothing, but picks the interface of the route
+    // to the internet, which is the one of the local network
+    let mut routes = vec![(
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::from((Ipv4Addr::new(192, 0, 2, 1), 9)),
+    )];
+    if addr.is_ipv6() {
+        routes.push((
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            SocketAddr::from((Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 9)),
+        ));
+    }
+    for (unspecified, route) in routes {
+        let local = acquire_local_ephemeral_udp_socket(unspecified)
+            .and_then(|socket| socket.connect(route).and(socket.local_addr()));
+        if let Ok(local) = local {
+            ips.push(local.ip());
+        }
+    }
+
+    ips.into_iter()
+        .map(|ip| SocketAddr::new(ip, addr.port()))
+        .collect()
+}
+
+fn log_address_candidates(addr: SocketAddr) {
+    for candidate in address_candidates(addr) {
+        let ip = candidate.ip();
+        if ip.is_loopback() {
+            info!("Players on this machine can join at {}", candidate);
+        } else if is_private(ip) {
+            info!(
+                "Players of the local network can join at {}, forward UDP and TCP port {} to it for the others",
+                candidate,
+                candidate.port()
+            );
+        } else {
+            info!("Players can join at {}", candidate);
+        }
+    }
+}
+
+/// Whether `ip` can only be reached from the local network
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local(),
+        // unique local and link local addresses
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xfe00) == 0xfc00 || ip.is_unicast_link_local(),
+    }
+}
+
+/// Netcode in secure mode: clients connect with the tokens of the handshake
+/// server, sealed with `private_key`
 pub fn add_netcode_network(
     socket: UdpSocket,
-) -> Result<(RenetServer, NetcodeServerTransport, SocketAddr), NetcodeSetupError> {
+    private_key: [u8; NETCODE_KEY_BYTES],
+) -> Result<(NetcodeServerTransport, SocketAddr), NetcodeSetupError> {
     let granted_addr: SocketAddr = socket.local_addr().map_err(NetcodeSetupError::SocketAddr)?;
 
     let current_time: Duration = SystemTime::now()
@@ -94,26 +204,59 @@ pub fn add_netcode_network(
         max_clients: 64,
         protocol_id: shared::PROTOCOL_ID,
         public_addresses: vec![granted_addr],
-        authentication: ServerAuthentication::Unsecure,
+        authentication: ServerAuthentication::Secure { private_key },
     };
 
     let transport: NetcodeServerTransport = NetcodeServerTransport::new(server_config, socket)
         .map_err(|err| NetcodeSetupError::Transport(err.to_string()))?;
 
-    let server = RenetServer::new(get_shared_renet_config());
+    Ok((transport, granted_addr))
+}
 
-    Ok((server, transport, granted_addr))
+/// How clients reach the server, which can listen on several transports at once
+pub enum ServerTransportKind {
+    /// Netcode over a UDP socket, for dedicated servers, along with the listener
+    /// of the handshakes handing out the connect tokens
+    Udp {
+        socket: UdpSocket,
+        handshake: TcpListener,
+    },
+    /// WebSockets, for clients that can't use UDP. Only opened on request, as
+    /// their packets travel unencrypted, unlike the ones of netcode
+    WebSocket(TcpListener),
+    /// Channels to the game the server is integrated in, for solo games
+    #[allow(dead_code)]
+    Memory(MemoryServerTransport),
 }
 
-pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: GameFolderPaths) {
-    let (server, transport, addr) = match add_netcode_network(socket) {
-        Ok(data) => data,
-        Err(err) => {
-            error!("{err}");
-            return;
+/// Installs the biome definitions of the assets folder, logging why they can't be used
+pub(crate) fn install_biome_definitions(game_folder_paths: &GameFolderPaths) -> bool {
+    match load_biome_definitions(game_folder_paths) {
+        Ok(biomes) => {
+            let (replaced, added) = biomes.counts();
+            if replaced + added > 0 {
+                info!(
+                    "Loaded the biome definitions: {} built-in biomes replaced, {} added",
+                    replaced, added
+                );
+            }
+            biomes.install();
+            true
         }
-    };
+        Err(errors) => {
+            for err in errors {
+                error!("{err}");
+            }
+            false
+        }
+    }
+}
 
+pub fn init(
+    transports: Vec<ServerTransportKind>,
+    config: GameServerConfig,
+    game_folder_paths: GameFolderPaths,
+) {
     let mut app = App::new();
     app.add_plugins(
         MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
@@ -130,20 +273,114 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
     app.insert_resource(ServerLobby::default());
     app.insert_resource(game_folder_paths.clone());
 
+    // A world generated with broken biomes could not be fixed afterwards
+    if !install_biome_definitions(&game_folder_paths) {
+        error!("Fix or remove the biome definitions to start the server");
+        return;
+    }
+
     let world_name = &config.world_name.clone();
+    let world_preset = config.world_preset;
+    let world_seed = config.world_seed;
+
+    let file_config = load_server_file_config(&game_folder_paths);
+    let mut scheduler = ServerScheduler::from_config(&file_config.scheduler, !config.is_solo);
+    if let Some(interval_secs) = file_config.backup.interval_secs.filter(|secs| *secs > 0) {
+        let interval = Duration::from_secs(interval_secs);
+        scheduler.register_hook("backup", interval, request_backup);
+    }
+    app.insert_resource(scheduler);
+    app.insert_resource(file_config.backup);
+
+    // The terminal of an integrated server belongs to the game
+    if !config.is_solo {
+        app.insert_resource(ConsoleInput::spawn_reader());
+    }
+    app.insert_resource(file_config.sleep);
+    app.insert_resource(file_config.anti_xray);
+    app.insert_resource(file_config.far_terrain);
+    app.insert_resource(file_config.idle);
+    app.insert_resource(file_config.limits);
+    app.insert_resource(file_config.operators);
 
     app.insert_resource(config);
 
-    info!("Starting server on {}", addr);
+    app.insert_resource(RenetServer::new(get_shared_renet_config()));
+    // WebSocket clients get their token from the handshake server of the UDP transport
+    let mut handshakes = None;
+    for transport in transports {
+        match transport {
+            ServerTransportKind::Udp { socket, handshake } => {
+                let identity_path = game_folder_paths.game_folder_path.join(IDENTITY_KEY_FILE);
+                let identity = match ServerIdentity::load_or_create(&identity_path) {
+                    Ok(identity) => identity,
+                    Err(err) => {
+                        error!(
+                            "Could not load the server identity {} : {}",
+                            identity_path.display(),
+                            err
+                        );
+                        return;
+                    }
+                };
+                // Only ever known by this server, the tokens of a previous run expire
+                let private_key = generate_random_bytes();
+                let (transport, addr) = match add_netcode_network(socket, private_key) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        error!("{err}");
+                        return;
+                    }
+                };
+                info!("Starting server on {}", addr);
+                info!("Server identity fingerprint: {}", identity.fingerprint());
+                log_address_candidates(addr);
+                handshakes = Some(spawn_handshake_server(
+                    handshake,
+                    identity,
+                    private_key,
+                    vec![addr],
+                ));
 
-    app.add_plugins(NetcodeServerPlugin);
-    app.insert_resource(server);
-    app.insert_resource(transport);
+                app.add_plugins(ServerTransportPlugin::<NetcodeServerTransport>::default());
+                app.insert_resource(transport);
+            }
+            ServerTransportKind::WebSocket(listener) => {
+                let Some(handshakes) = handshakes.clone() else {
+                    error!("WebSockets need the UDP transport, whose handshakes they share");
+                    return;
+                };
+                let transport = match WebSocketServerTransport::new(listener, handshakes) {
+                    Ok(transport) => transport,
+                    Err(err) => {
+                        error!("{}: {err}", WEBSOCKET_LISTENER_ERROR);
+                        return;
+                    }
+                };
+                if let Ok(addr) = transport.local_addr() {
+                    warn!(
+                        "Accepting unencrypted WebSockets on ws://{}, their traffic can be read on the way",
+                        addr
+                    );
+                }
+
+                app.add_plugins(ServerTransportPlugin::<WebSocketServerTransport>::default());
+                app.insert_resource(transport);
+            }
+            ServerTransportKind::Memory(transport) => {
+                info!("Starting integrated server");
+
+                app.add_plugins(ServerTransportPlugin::<MemoryServerTransport>::default());
+                app.insert_resource(transport);
+            }
+        }
+    }
 
     setup_resources_and_events(&mut app);
 
     // Load world from files
-    let world_data = match load_world_data(world_name, &game_folder_paths) {
+    let world_data = match load_world_data(world_name, world_preset, world_seed, &game_folder_paths)
+    {
         Ok(data) => data,
         Err(err) => {
             error!(
@@ -153,18 +390,66 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
             panic!()
         }
     };
+    if world_preset != WorldGenPreset::Default && world_data.generation.preset != world_preset {
+        warn!(
+            "World {} was created as a {} world, its terrain stays the same",
+            world_name,
+            world_data.generation.preset.name()
+        );
+    }
+
+    let world_folder = game_folder_paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(world_name);
+    // Chunks saved in their own files replace the ones of older saves
+    let (chunks, mut corrupt_chunks) = load_chunks(&world_folder, DimensionId::Overworld);
+    let saved_ts = saved_timestamps(&chunks);
+    let mut map = world_data.map;
+    map.extend(chunks);
+    let mut pending_flora = world_data.pending_flora;
+    for (_, position) in corrupt_chunks.0.iter() {
+        map.remove(position);
+        pending_flora.remove(position);
+    }
+    app.insert_resource(BlockChangeLog::open(&world_folder, file_config.block_log));
+
+    let mut dimensions = HashMap::new();
+    for dimension in DimensionId::others() {
+        let (map, corrupt) = load_chunks(&world_folder, dimension);
+        corrupt_chunks.0.extend(corrupt.0);
+        let saved_ts = saved_timestamps(&map);
+        dimensions.insert(
+            dimension,
+            ServerChunkWorldMap {
+                map,
+                saved_ts,
+                ..default()
+            },
+        );
+    }
+    app.insert_resource(corrupt_chunks);
 
     let mut world_map = ServerWorldMap {
         name: world_data.name,
         chunks: ServerChunkWorldMap {
-            map: world_data.map,
+            map,
             chunks_to_update: Vec::new(),
-            generation_requests: HashMap::new(),
+            pending_flora,
+            structure_requests: HashMap::new(),
+            fluid_edits: Vec::new(),
+            block_edits: Vec::new(),
+            saved_ts,
         },
+        dimensions,
         players: HashMap::new(),
         mobs: world_data.mobs,
         item_stacks: world_data.item_stacks,
         time: world_data.time,
+        waystones: world_data.waystones,
+        item_frames: world_data.item_frames,
+        difficulty: world_data.difficulty,
+        boats: world_data.boats,
     };
 
     cleanup_all_players_from_world(&mut world_map);
@@ -172,6 +457,14 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
     // Insert world_map and seed into ressources
     app.insert_resource(world_map);
     app.insert_resource(world_data.seed);
+    app.insert_resource(world_data.generation);
+
+    let world_spawn = world_data.spawn.unwrap_or_else(|| {
+        let spawn = find_world_spawn(world_data.seed.0, world_data.generation);
+        info!("Chose the world spawn at {:?}", spawn);
+        spawn
+    });
+    app.insert_resource(WorldSpawn(world_spawn));
     app.insert_resource(ServerTime(world_data.time));
 
     // Create save folder if does not already exist
@@ -1,6 +1,12 @@
+mod console;
 mod init;
 mod mob;
 mod network;
+mod scheduler;
 mod world;
 
-pub use init::{acquire_local_ephemeral_udp_socket, init};
+pub use init::{
+    acquire_local_ephemeral_udp_socket, acquire_server_socket, init, ServerTransportKind,
+};
+pub use scheduler::{ServerScheduler, TaskAction};
+pub use world::pregen::pregenerate;
@@ -1,13 +1,20 @@
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
 
-use crate::init::acquire_socket_by_port;
+use crate::init::acquire_server_socket;
 use clap::Parser;
-use shared::constants::{DEFAULT_RENDER_DISTANCE, SOCKET_BIND_ERROR};
+use shared::constants::{
+    DEFAULT_RENDER_DISTANCE, HANDSHAKE_LISTENER_ERROR, SOCKET_BIND_ERROR, WEBSOCKET_LISTENER_ERROR,
+};
+use shared::fluid::{FluidMode, DEFAULT_EVAPORATION_VOLUME};
+use shared::players::GameMode;
+use shared::world::{WorldGenPreset, WorldSeed};
 use shared::{get_game_folder_paths, GameServerConfig};
 
+mod console;
 mod init;
 mod mob;
 mod network;
+mod scheduler;
 mod world;
 
 #[derive(Parser, Debug)]
@@ -16,6 +23,18 @@ struct Args {
     #[arg(short, long, default_value_t = 8000)]
     port: u16,
 
+    #[arg(
+        long,
+        help = "Address of the interface to listen on, IPv4 or IPv6, all of them if not given"
+    )]
+    bind: Option<IpAddr>,
+
+    #[arg(
+        long,
+        help = "Also accept clients over WebSockets on this TCP port, e.g. browsers. Unlike UDP, this traffic is not encrypted"
+    )]
+    unencrypted_websocket_port: Option<u16>,
+
     #[arg(short, long, default_value = "default")]
     world: String,
 
@@ -24,6 +43,49 @@ struct Args {
 
     #[arg(short, long, default_value_t = DEFAULT_RENDER_DISTANCE)]
     render_distance: i32,
+
+    #[arg(
+        long,
+        help = "Simulate fluid particles for waterfalls and pours (experimental)"
+    )]
+    fluid_particles: bool,
+
+    #[arg(
+        long,
+        default_value = "finite",
+        help = "How water and lava flow: finite, conserving their volume, or classic, spreading from source blocks"
+    )]
+    fluid_mode: FluidMode,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_EVAPORATION_VOLUME,
+        help = "Settled bodies of water holding this volume or less evaporate in the finite fluid mode, 0 to keep all water"
+    )]
+    evaporation_volume: f32,
+
+    #[arg(long, help = "Players join in creative mode")]
+    creative: bool,
+
+    #[arg(
+        long,
+        default_value = "default",
+        help = "Terrain of a new world: default, superflat, amplified, islands or overhangs"
+    )]
+    world_type: WorldGenPreset,
+
+    #[arg(
+        long,
+        help = "Seed of a new world, a number or any text, random if not given"
+    )]
+    seed: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "RADIUS",
+        help = "Generate and save the chunks within this radius of the world spawn, in chunks, then exit without accepting players"
+    )]
+    pregen: Option<u32>,
 }
 
 fn main() {
@@ -37,21 +99,68 @@ fn main() {
         std::process::exit(1);
     }
 
-    let socket =
-        match acquire_socket_by_port(std::net::IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), args.port) {
-            Ok(socket) => socket,
+    if let Some(radius) = args.pregen {
+        world::pregen::pregenerate(
+            &args.world,
+            args.world_type,
+            args.seed.as_deref().and_then(WorldSeed::from_input),
+            radius,
+            get_game_folder_paths(args.game_folder_path, None),
+        );
+        return;
+    }
+
+    let socket = match acquire_server_socket(args.bind, args.port) {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("{}: {err}", SOCKET_BIND_ERROR);
+            std::process::exit(1);
+        }
+    };
+
+    // Handshakes and WebSockets are accepted on the interfaces of the UDP socket
+    let listen_ip = socket
+        .local_addr()
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    // The handshakes go through the TCP port of the same number
+    let handshake_port = socket.local_addr().map_or(args.port, |addr| addr.port());
+    let handshake = match TcpListener::bind((listen_ip, handshake_port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("{}: {err}", HANDSHAKE_LISTENER_ERROR);
+            std::process::exit(1);
+        }
+    };
+
+    let mut transports = vec![init::ServerTransportKind::Udp { socket, handshake }];
+    if let Some(port) = args.unencrypted_websocket_port {
+        match TcpListener::bind((listen_ip, port)) {
+            Ok(listener) => transports.push(init::ServerTransportKind::WebSocket(listener)),
             Err(err) => {
-                eprintln!("{}: {err}", SOCKET_BIND_ERROR);
+                eprintln!("{}: {err}", WEBSOCKET_LISTENER_ERROR);
                 std::process::exit(1);
             }
-        };
+        }
+    }
 
     init::init(
-        socket,
+        transports,
         GameServerConfig {
             world_name: args.world,
             is_solo: false,
             broadcast_render_distance: args.render_distance,
+            fluid_particles: args.fluid_particles,
+            fluid_mode: args.fluid_mode,
+            evaporation_volume: args.evaporation_volume,
+            game_mode: if args.creative {
+                GameMode::Creative
+            } else {
+                GameMode::Survival
+            },
+            world_preset: args.world_type,
+            world_seed: args.seed.as_deref().and_then(WorldSeed::from_input),
         },
         get_game_folder_paths(args.game_folder_path, None),
     );
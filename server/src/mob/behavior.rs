@@ -1,18 +1,50 @@
+use std::collections::HashMap;
+
 use bevy::{
-    math::{bounding::Aabb3d, ops::atan2, Quat, Vec3},
+    math::{bounding::Aabb3d, ops::atan2, IVec3, Quat, Vec3},
     time::{Fixed, Time},
 };
-use bevy_ecs::system::{Res, ResMut};
+use bevy_ecs::system::{Local, Res, ResMut};
 use log::error as log_error;
+use rand::seq::SliceRandom;
 use shared::{
-    physics::constants::{GRAVITY, JUMP_VELOCITY, PLAYER_SPEED, TERMINAL_VELOCITY},
-    world::{MobAction, MobTarget, ServerWorldMap, WorldMap},
+    physics::{
+        constants::{GRAVITY, JUMP_VELOCITY, PLAYER_SPEED, TERMINAL_VELOCITY},
+        water::{apply_buoyancy, calculate_body_submersion, constants::SWIM_SPEED},
+    },
+    water::{find_water_path, reachable_water_blocks, water_column_depth, WAVE_AMPLITUDE},
+    world::{BlockId, MobAction, MobId, MobTarget, ServerMob, ServerWorldMap, WorldMap},
 };
 
 /// Mob movement speed as a fraction of player speed
 const MOB_WALK_SPEED_MULTIPLIER: f32 = 0.7;
 /// Mob flee speed as a fraction of player speed
 const MOB_FLEE_SPEED_MULTIPLIER: f32 = 0.5;
+/// Submersion above which a mob is considered swimming
+const MOB_SWIM_SUBMERSION: f32 = 0.1;
+/// Deepest water a land mob will swim across, in blocks up to the waves
+const MAX_SWIMMABLE_WATER_DEPTH: u32 = 2;
+/// Directions tried, relative to the wanted one, when a land mob avoids deep water
+const WATER_AVOIDANCE_ANGLES: [f32; 5] = [
+    0.0,
+    std::f32::consts::FRAC_PI_4,
+    -std::f32::consts::FRAC_PI_4,
+    std::f32::consts::FRAC_PI_2,
+    -std::f32::consts::FRAC_PI_2,
+];
+/// Fish swimming speed as a fraction of player speed
+const FISH_SWIM_SPEED_MULTIPLIER: f32 = 0.4;
+/// Water blocks considered when a fish picks a new destination
+const FISH_WANDER_MAX_BLOCKS: usize = 128;
+/// Distance at which a fish considers a waypoint reached
+const FISH_WAYPOINT_RADIUS: f32 = 0.2;
+
+fn is_water(world_map: &ServerWorldMap, position: IVec3) -> bool {
+    world_map
+        .chunks
+        .get_block_by_coordinates(&position)
+        .is_some_and(|block| block.id == BlockId::Water)
+}
 
 /// Calculates half extents from mob dimensions for AABB collision detection.
 #[inline]
@@ -29,6 +61,8 @@ fn calculate_half_extents(dimensions: Vec3) -> Vec3 {
 /// * `world_map` - World map for collision detection
 /// * `dimensions` - Mob dimensions (width, height, depth)
 /// * `delta` - Time step in seconds
+///
+/// Mobs in water float using the shared water physics instead of falling.
 fn apply_mob_physics(
     position: &mut Vec3,
     velocity: &mut Vec3,
@@ -37,8 +71,13 @@ fn apply_mob_physics(
     dimensions: Vec3,
     delta: f32,
 ) {
-    // Apply gravity if not on ground
-    if !*on_ground {
+    let submersion =
+        calculate_body_submersion(*position, dimensions.y, dimensions.x, &world_map.chunks);
+
+    if submersion > MOB_SWIM_SUBMERSION {
+        // Buoyancy replaces gravity, which keeps swimming mobs at the surface
+        apply_buoyancy(velocity, submersion, delta);
+    } else if !*on_ground {
         velocity.y += GRAVITY * delta;
     }
 
@@ -79,7 +118,7 @@ fn apply_mob_physics(
 /// * `dimensions` - Mob dimensions (width, height, depth)
 /// * `direction` - Normalized movement direction
 /// * `speed` - Movement speed
-fn apply_horizontal_movement(
+pub(super) fn apply_horizontal_movement(
     position: &mut Vec3,
     velocity: &mut Vec3,
     on_ground: bool,
@@ -125,10 +164,128 @@ fn apply_horizontal_movement(
     }
 }
 
-pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Time<Fixed>>) {
+/// Returns the direction a land mob should walk in to go towards `direction`
+/// without entering water deeper than it can swim across, or `None` if the
+/// way is blocked by deep water. Waves are sampled at `time`, so water only
+/// a bit shallower than the limit is deep under their crests.
+fn avoid_deep_water(
+    position: Vec3,
+    dimensions: Vec3,
+    world_map: &ServerWorldMap,
+    direction: Vec3,
+    time: f32,
+) -> Option<Vec3> {
+    let feet = (position - Vec3::Y * (dimensions.y / 2.0))
+        .floor()
+        .as_ivec3();
+    let is_deep = |cell: IVec3| {
+        water_column_depth(
+            cell,
+            MAX_SWIMMABLE_WATER_DEPTH + 1,
+            time,
+            WAVE_AMPLITUDE,
+            |pos| is_water(world_map, pos),
+        ) > MAX_SWIMMABLE_WATER_DEPTH as f32
+    };
+
+    // Already in deep water, any direction is better than staying
+    if is_deep(feet) {
+        return Some(direction);
+    }
+
+    let probe_distance = dimensions.x.max(dimensions.z) / 2.0 + 0.5;
+    WATER_AVOIDANCE_ANGLES.iter().find_map(|angle| {
+        let candidate = Quat::from_rotation_y(*angle) * direction;
+        let ahead = (position + candidate * probe_distance).floor().as_ivec3();
+        let ahead = IVec3::new(ahead.x, feet.y, ahead.z);
+        (!is_deep(ahead)).then_some(candidate)
+    })
+}
+
+/// Moves a fish along a path through connected water blocks.
+/// Out of water, fish fall and can't move horizontally.
+fn fish_behavior(
+    mob: &mut ServerMob,
+    path: &mut Vec<IVec3>,
+    world_map: &ServerWorldMap,
+    delta: f32,
+) {
+    let current_block = mob.position.floor().as_ivec3();
+
+    if !is_water(world_map, current_block) {
+        path.clear();
+        mob.action = MobAction::Idle;
+        let dimensions = Vec3::new(mob.width, mob.height, mob.depth);
+        apply_mob_physics(
+            &mut mob.position,
+            &mut mob.velocity,
+            &mut mob.on_ground,
+            world_map,
+            dimensions,
+            delta,
+        );
+        return;
+    }
+
+    mob.velocity = Vec3::ZERO;
+    mob.on_ground = false;
+
+    // Pick a new destination among the water blocks connected to the current one
+    if path.is_empty() {
+        let reachable = reachable_water_blocks(current_block, FISH_WANDER_MAX_BLOCKS, |pos| {
+            is_water(world_map, pos)
+        });
+        let Some(goal) = reachable.choose(&mut rand::thread_rng()) else {
+            return;
+        };
+        if let Some(new_path) =
+            find_water_path(current_block, *goal, FISH_WANDER_MAX_BLOCKS, |pos| {
+                is_water(world_map, pos)
+            })
+        {
+            *path = new_path;
+            // The path starts with the current block
+            path.remove(0);
+            mob.target = MobTarget::Position(goal.as_vec3() + Vec3::splat(0.5));
+            mob.action = MobAction::Walk;
+        }
+        return;
+    }
+
+    // The water may have changed since the path was computed
+    let waypoint = path[0];
+    if !is_water(world_map, waypoint) {
+        path.clear();
+        return;
+    }
+
+    let waypoint_center = waypoint.as_vec3() + Vec3::splat(0.5);
+    let to_waypoint = waypoint_center - mob.position;
+    let distance = to_waypoint.length();
+    if distance < FISH_WAYPOINT_RADIUS {
+        path.remove(0);
+        if path.is_empty() {
+            mob.action = MobAction::Idle;
+        }
+        return;
+    }
+
+    let dir = to_waypoint / distance;
+    let step = (PLAYER_SPEED * FISH_SWIM_SPEED_MULTIPLIER * delta).min(distance);
+    mob.position += dir * step;
+    mob.rotation = Quat::from_rotation_y(atan2(dir.x, dir.z));
+}
+
+pub fn mob_behavior_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    delta: Res<Time<Fixed>>,
+    mut fish_paths: Local<HashMap<MobId, Vec<IVec3>>>,
+) {
+    let time = delta.elapsed_secs_wrapped();
     let mut mobs = world_map.mobs.clone();
+    fish_paths.retain(|id, _| mobs.contains_key(id));
 
-    for (_mob_id, mob) in mobs.iter_mut() {
+    for (mob_id, mob) in mobs.iter_mut() {
         // Validate mob state
         if (mob.position.x.is_nan() || mob.position.y.is_nan() || mob.position.z.is_nan())
             || (mob.velocity.x.is_nan() || mob.velocity.y.is_nan() || mob.velocity.z.is_nan())
@@ -137,6 +294,15 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
             continue;
         }
 
+        if mob.kind.is_aquatic() {
+            let delta = delta.delta_secs();
+            if delta > 0.0 {
+                let path = fish_paths.entry(*mob_id).or_default();
+                fish_behavior(mob, path, &world_map, delta);
+            }
+            continue;
+        }
+
         let target = match mob.target {
             MobTarget::Position(pos) => pos,
             MobTarget::None => continue,
@@ -177,66 +343,78 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
         let direction_to_target = target - mob.position;
         let distance_to_target = direction_to_target.length();
 
+        let swimming =
+            calculate_body_submersion(mob.position, mob.height, mob.width, &world_map.chunks)
+                > MOB_SWIM_SUBMERSION;
+        let speed_factor = if swimming { SWIM_SPEED } else { 1.0 } * mob.effects.speed_multiplier();
+
         match mob.action {
-            MobAction::Walk | MobAction::Attack => {
-                // Only move if there's a meaningful distance to the target
-                if distance_to_target > 0.01 {
-                    let dir = direction_to_target / distance_to_target; // Normalize manually
-                    let speed = PLAYER_SPEED * MOB_WALK_SPEED_MULTIPLIER;
-
-                    // Apply horizontal movement with obstacle avoidance
-                    apply_horizontal_movement(
-                        &mut mob.position,
-                        &mut mob.velocity,
-                        mob.on_ground,
-                        &world_map,
-                        dimensions,
-                        dir,
-                        speed,
-                        delta,
-                    );
-
-                    mob.rotation = Quat::from_rotation_y(atan2(dir.x, dir.z));
-
-                    // If reached destination, start fleeing
-                    if distance_to_target < 0.5 {
-                        mob.action = MobAction::Flee;
-                    }
+            // Only move if there's a meaningful distance to the target
+            MobAction::Walk | MobAction::Attack if distance_to_target > 0.01 => {
+                let dir = direction_to_target / distance_to_target; // Normalize manually
+                let speed = PLAYER_SPEED * MOB_WALK_SPEED_MULTIPLIER * speed_factor;
+
+                // Stay put rather than walking into deep water
+                let Some(dir) = avoid_deep_water(mob.position, dimensions, &world_map, dir, time)
+                else {
+                    continue;
+                };
+
+                // Apply horizontal movement with obstacle avoidance
+                apply_horizontal_movement(
+                    &mut mob.position,
+                    &mut mob.velocity,
+                    mob.on_ground,
+                    &world_map,
+                    dimensions,
+                    dir,
+                    speed,
+                    delta,
+                );
+
+                mob.rotation = Quat::from_rotation_y(atan2(dir.x, dir.z));
+
+                // If reached destination, start fleeing. Attacking mobs keep chasing.
+                if matches!(mob.action, MobAction::Walk) && distance_to_target < 0.5 {
+                    mob.action = MobAction::Flee;
                 }
             }
-            MobAction::Flee => {
-                if distance_to_target < 15.0 {
-                    let flee_speed = PLAYER_SPEED * MOB_FLEE_SPEED_MULTIPLIER;
-
-                    // Calculate flee direction (away from target)
-                    // If very close to target, use the mob's current rotation to flee
-                    let flee_dir = if distance_to_target > 0.01 {
-                        -direction_to_target / distance_to_target // Normalize and invert
+            MobAction::Flee if distance_to_target < 15.0 => {
+                let flee_speed = PLAYER_SPEED * MOB_FLEE_SPEED_MULTIPLIER * speed_factor;
+
+                // Calculate flee direction (away from target)
+                // If very close to target, use the mob's current rotation to flee
+                let flee_dir = if distance_to_target > 0.01 {
+                    -direction_to_target / distance_to_target // Normalize and invert
+                } else {
+                    // Mob is exactly at target, use any direction to escape
+                    // Use current velocity direction if available, otherwise a default direction
+                    let vel_horizontal = Vec3::new(mob.velocity.x, 0.0, mob.velocity.z);
+                    if vel_horizontal.length() > 0.01 {
+                        vel_horizontal.normalize()
                     } else {
-                        // Mob is exactly at target, use any direction to escape
-                        // Use current velocity direction if available, otherwise a default direction
-                        let vel_horizontal = Vec3::new(mob.velocity.x, 0.0, mob.velocity.z);
-                        if vel_horizontal.length() > 0.01 {
-                            vel_horizontal.normalize()
-                        } else {
-                            Vec3::new(1.0, 0.0, 0.0) // Default escape direction
-                        }
-                    };
-
-                    // Apply horizontal movement while fleeing
-                    apply_horizontal_movement(
-                        &mut mob.position,
-                        &mut mob.velocity,
-                        mob.on_ground,
-                        &world_map,
-                        dimensions,
-                        flee_dir,
-                        flee_speed,
-                        delta,
-                    );
-
-                    mob.rotation = Quat::from_rotation_y(atan2(flee_dir.x, flee_dir.z));
-                }
+                        Vec3::new(1.0, 0.0, 0.0) // Default escape direction
+                    }
+                };
+                let Some(flee_dir) =
+                    avoid_deep_water(mob.position, dimensions, &world_map, flee_dir, time)
+                else {
+                    continue;
+                };
+
+                // Apply horizontal movement while fleeing
+                apply_horizontal_movement(
+                    &mut mob.position,
+                    &mut mob.velocity,
+                    mob.on_ground,
+                    &world_map,
+                    dimensions,
+                    flee_dir,
+                    flee_speed,
+                    delta,
+                );
+
+                mob.rotation = Quat::from_rotation_y(atan2(flee_dir.x, flee_dir.z));
             }
             _ => {}
         }
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use bevy::{
+    math::Vec3,
+    time::{Fixed, Time},
+};
+use bevy_ecs::{
+    resource::Resource,
+    system::{Res, ResMut},
+};
+use bevy_log::{debug, info};
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{mob::MobDespawnEvent, PlayerFrameInput, PlayerId, ServerToClientMessage},
+    players::{AnimationEvent, AnimationKind, AnimationTarget, Player},
+    world::{
+        knockback_velocity, melee_damage, raycast, EntityHistory, MobId, ServerChunkWorldMap,
+        ServerMob, ServerWorldMap, SpatialEntity, ATTACK_COOLDOWN_TICKS, MELEE_REACH,
+    },
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+use super::behavior::apply_horizontal_movement;
+use super::targeting::provoke;
+
+/// Fraction of the knockback velocity lost per second
+const KNOCKBACK_FRICTION: f32 = 0.99;
+
+/// Tick of the last attack of each player
+#[derive(Resource, Default, Debug)]
+pub struct LastAttacks(pub HashMap<PlayerId, u64>);
+
+fn broadcast_animation(server: &mut RenetServer, target: AnimationTarget, kind: AnimationKind) {
+    server.broadcast_game_message(ServerToClientMessage::Animation(AnimationEvent {
+        target,
+        kind,
+    }));
+}
+
+/// Hits the mob the player is looking at, if it is within reach and the player's
+/// attack cooldown is over.
+///
+/// The mobs are rewound to where the player saw them: the positions took half a
+/// round trip to reach the client, which shows them as they arrive, and the attack
+/// half a round trip to come back. The rewind is measured from the server clock
+/// and never exceeds the round trip time, the time of the input isn't trusted.
+#[allow(clippy::too_many_arguments)]
+pub fn attack(
+    server: &mut RenetServer,
+    player: &Player,
+    chunks: &ServerChunkWorldMap,
+    mobs: &mut HashMap<MobId, ServerMob>,
+    history: &EntityHistory,
+    input: &PlayerFrameInput,
+    last_attacks: &mut LastAttacks,
+    tick: u64,
+) {
+    if last_attacks
+        .0
+        .get(&player.id)
+        .is_some_and(|last| tick < last + ATTACK_COOLDOWN_TICKS)
+    {
+        debug!("Player {} attacked during their cooldown", player.id);
+        return;
+    }
+
+    let (origin, direction) = raycast::camera_ray(&input.camera, &player.position, input.view_mode);
+    let block_distance = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+        .map(|hit| (hit.position.as_vec3() + Vec3::splat(0.5)).distance(origin))
+        .unwrap_or(f32::MAX);
+    let rewind_ms = (server.rtt(player.id) * 1000.0) as u64;
+    let Some(seen_at) = history.rewound(rewind_ms) else {
+        return;
+    };
+    let Some(hit) = history
+        .raycast(seen_at, origin, direction, MELEE_REACH)
+        .filter(|hit| hit.distance < block_distance)
+    else {
+        return;
+    };
+    let SpatialEntity::Mob(id) = hit.entity else {
+        return;
+    };
+    // The camera is sent by the client, the hit must also be close to the player
+    if hit.position.distance(player.position) > MELEE_REACH + player.height {
+        debug!("Player {} attacked mob {} out of reach", player.id, id);
+        return;
+    }
+    let Some(mob) = mobs.get_mut(&id) else {
+        return;
+    };
+
+    last_attacks.0.insert(player.id, tick);
+    broadcast_animation(
+        server,
+        AnimationTarget::Player(player.id),
+        AnimationKind::Swing,
+    );
+    if mob.is_invulnerable(tick) {
+        return;
+    }
+
+    let damage = melee_damage(player.inventory.inner.get(&input.hotbar_slot));
+    let knockback = knockback_velocity(player.position, mob.position);
+    let killed = mob.hurt(damage, knockback, tick);
+    debug!(
+        "Player {} hit mob {} for {} damage, {} health left",
+        player.id,
+        id,
+        damage,
+        mob.health()
+    );
+    broadcast_animation(server, AnimationTarget::Mob(id), AnimationKind::Hurt);
+    provoke(mob, player.id);
+
+    if killed {
+        info!("Player {} killed mob {}", player.id, id);
+        mobs.remove(&id);
+        server.broadcast_game_message(ServerToClientMessage::MobDespawn(MobDespawnEvent { id }));
+    }
+}
+
+/// Moves mobs that were knocked back, until their knockback velocity fades out.
+/// Leashed mobs are moved by their lead instead.
+pub fn knockback_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Time<Fixed>>) {
+    let delta = delta.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+
+    let mut mobs = world_map.mobs.clone();
+    for mob in mobs.values_mut() {
+        if mob.leash.is_some() || mob.kind.is_aquatic() {
+            continue;
+        }
+
+        let horizontal_velocity = Vec3::new(mob.velocity.x, 0.0, mob.velocity.z);
+        let speed = horizontal_velocity.length();
+        if speed < 0.01 {
+            continue;
+        }
+
+        let dimensions = Vec3::new(mob.width, mob.height, mob.depth);
+        apply_horizontal_movement(
+            &mut mob.position,
+            &mut mob.velocity,
+            mob.on_ground,
+            &world_map,
+            dimensions,
+            horizontal_velocity / speed,
+            speed,
+            delta,
+        );
+
+        let remaining = horizontal_velocity * (1.0 - KNOCKBACK_FRICTION).powf(delta);
+        mob.velocity.x = remaining.x;
+        mob.velocity.z = remaining.z;
+    }
+    world_map.mobs = mobs;
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use bevy::{
+    math::{ops::atan2, Quat, Vec3},
+    time::{Fixed, Time},
+};
+use bevy_ecs::system::{Res, ResMut};
+use bevy_log::{debug, info};
+use shared::{
+    messages::{PlayerFrameInput, PlayerId},
+    physics::constants::PLAYER_SPEED,
+    players::{GameMode, Player},
+    world::{
+        fence_knot_position, leash_tension, raycast, BlockId, ItemId, ItemStack, LeashAnchor,
+        LeashTension, MobId, ServerChunkWorldMap, ServerMob, ServerWorldMap, SpatialEntity,
+        SpatialHash, WorldMap,
+    },
+};
+
+use super::behavior::apply_horizontal_movement;
+
+/// Leads can't be used on mobs further away than this
+const LEAD_MAX_DISTANCE: f32 = 6.0;
+/// Fastest a lead can drag a mob, as a fraction of player speed
+const LEASH_MAX_SPEED_MULTIPLIER: f32 = 1.5;
+/// Fraction of the horizontal velocity lost per second while the lead is slack
+const LEASH_FRICTION: f32 = 0.95;
+
+fn lead_stack() -> ItemStack {
+    ItemStack {
+        item_id: ItemId::Lead,
+        item_type: ItemId::Lead.get_default_type(),
+        nb: 1,
+    }
+}
+
+/// Handles a right click with a lead: ties the mob the player is looking at,
+/// unties an already leashed one, or ties the mobs led by the player to the fence
+/// they are looking at.
+///
+/// Returns whether the click was used by a lead.
+pub fn use_lead(
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    mobs: &mut HashMap<MobId, ServerMob>,
+    spatial: &SpatialHash,
+    input: &PlayerFrameInput,
+) -> bool {
+    let (origin, direction) = raycast::camera_ray(&input.camera, &player.position, input.view_mode);
+    let block_hit = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode);
+    let block_distance = block_hit
+        .map(|hit| (hit.position.as_vec3() + Vec3::splat(0.5)).distance(origin))
+        .unwrap_or(f32::MAX);
+
+    let targeted_mob = spatial
+        .raycast(origin, direction, LEAD_MAX_DISTANCE)
+        .filter(|hit| hit.distance < block_distance)
+        .and_then(|hit| match hit.entity {
+            SpatialEntity::Mob(id) => mobs.get_mut(&id).map(|mob| (id, mob)),
+            SpatialEntity::Player(_) => None,
+        });
+
+    let holding_lead = player
+        .inventory
+        .inner
+        .get(&input.hotbar_slot)
+        .is_some_and(|stack| stack.item_id == ItemId::Lead);
+
+    if let Some((id, mob)) = targeted_mob {
+        if mob.leash.take().is_some() {
+            info!("Player {} untied mob {}", player.id, id);
+            if player.game_mode != GameMode::Creative {
+                player.inventory.add_item_to_inventory(lead_stack());
+            }
+            return true;
+        }
+
+        if holding_lead && mob.kind.is_leashable() {
+            info!("Player {} tied mob {} with a lead", player.id, id);
+            mob.leash = Some(LeashAnchor::Player(player.id));
+            if player.game_mode != GameMode::Creative {
+                player
+                    .inventory
+                    .remove_item_from_stack(input.hotbar_slot, 1);
+            }
+            return true;
+        }
+        return false;
+    }
+
+    let Some(hit) = block_hit.filter(|hit| hit.block.id == BlockId::OakFence) else {
+        return false;
+    };
+    if fence_knot_position(hit.position).distance(player.position) > LEAD_MAX_DISTANCE {
+        return false;
+    }
+
+    let mut tied = false;
+    for mob in mobs.values_mut() {
+        if mob.leash == Some(LeashAnchor::Player(player.id)) {
+            mob.leash = Some(LeashAnchor::Fence(hit.position));
+            tied = true;
+        }
+    }
+    if tied {
+        info!(
+            "Player {} tied their mobs to the fence at {:?}",
+            player.id, hit.position
+        );
+    }
+    tied
+}
+
+/// Where the lead is tied, `None` if the anchor no longer exists
+fn anchor_position(world_map: &ServerWorldMap, anchor: LeashAnchor) -> Option<Vec3> {
+    match anchor {
+        LeashAnchor::Player(id) => world_map.players.get(&id).map(|player| player.position),
+        LeashAnchor::Fence(position) => world_map
+            .chunks
+            .get_block_by_coordinates(&position)
+            .filter(|block| block.id == BlockId::OakFence)
+            .map(|_| fence_knot_position(position)),
+    }
+}
+
+/// Pulls leashed mobs towards their anchor, and unties them when the lead snaps
+/// or the anchor disappears.
+pub fn leash_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Time<Fixed>>) {
+    let delta = delta.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+
+    let mut mobs = world_map.mobs.clone();
+    let mut returned_leads: Vec<PlayerId> = Vec::new();
+
+    for (id, mob) in mobs.iter_mut() {
+        let Some(anchor) = mob.leash else {
+            continue;
+        };
+        let Some(anchor_position) = anchor_position(&world_map, anchor) else {
+            debug!("Anchor of mob {} is gone, removing its lead", id);
+            mob.leash = None;
+            continue;
+        };
+
+        let mut horizontal_velocity = Vec3::new(mob.velocity.x, 0.0, mob.velocity.z);
+        match leash_tension(mob.position, mob.velocity, anchor_position) {
+            LeashTension::Snapped => {
+                info!("Lead of mob {} snapped", id);
+                mob.leash = None;
+                if let LeashAnchor::Player(player_id) = anchor {
+                    returned_leads.push(player_id);
+                }
+                continue;
+            }
+            LeashTension::Slack => {
+                horizontal_velocity *= (1.0 - LEASH_FRICTION).powf(delta);
+            }
+            LeashTension::Taut(pull) => {
+                horizontal_velocity += Vec3::new(pull.x, 0.0, pull.z) * delta;
+                horizontal_velocity =
+                    horizontal_velocity.clamp_length_max(PLAYER_SPEED * LEASH_MAX_SPEED_MULTIPLIER);
+            }
+        }
+        mob.velocity.x = horizontal_velocity.x;
+        mob.velocity.z = horizontal_velocity.z;
+
+        let speed = horizontal_velocity.length();
+        if speed < 0.01 {
+            continue;
+        }
+        let direction = horizontal_velocity / speed;
+        let dimensions = Vec3::new(mob.width, mob.height, mob.depth);
+        apply_horizontal_movement(
+            &mut mob.position,
+            &mut mob.velocity,
+            mob.on_ground,
+            &world_map,
+            dimensions,
+            direction,
+            speed,
+            delta,
+        );
+        if matches!(
+            leash_tension(mob.position, mob.velocity, anchor_position),
+            LeashTension::Taut(_)
+        ) {
+            // Dragged mobs face their anchor
+            let to_anchor = anchor_position - mob.position;
+            mob.rotation = Quat::from_rotation_y(atan2(to_anchor.x, to_anchor.z));
+        }
+    }
+
+    world_map.mobs = mobs;
+
+    for player_id in returned_leads {
+        if let Some(player) = world_map.players.get_mut(&player_id) {
+            if player.game_mode != GameMode::Creative {
+                player.inventory.add_item_to_inventory(lead_stack());
+            }
+        }
+    }
+}
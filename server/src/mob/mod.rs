@@ -1,16 +1,74 @@
 pub mod behavior;
+pub mod combat;
+pub mod leash;
+pub mod targeting;
 
 use bevy::prelude::*;
 use bevy_log::{debug, info};
-use shared::world::{MobAction, MobKind, MobTarget, ServerMob, ServerWorldMap, WorldMap};
+use rand::seq::IteratorRandom;
+use shared::messages::PlayerFrameInput;
+use shared::players::{GameMode, Player};
+use shared::water::water_depth;
+use shared::world::{
+    raycast, to_global_pos, world_position_to_chunk_position, BlockId, DimensionId,
+    FaceDirectionExt, ItemType, MobId, MobKind, ServerChunkWorldMap, ServerMob, ServerWorldMap,
+    WorldMap,
+};
+use std::collections::HashMap;
 use ulid::Ulid;
 
 use crate::init::ServerTime;
 
+/// Spawn eggs can't be used on blocks further away than this
+const SPAWN_EGG_MAX_DISTANCE: f32 = 6.0;
+/// Fish spawned in the water around the first player
+const FISH_SPAWN_COUNT: usize = 3;
+/// Fish only spawn in water at least this deep
+const FISH_SPAWN_MIN_DEPTH: u32 = 2;
+
 fn create_new_mob_id() -> u128 {
     Ulid::new().0
 }
 
+/// Picks random water blocks deep enough for fish in the chunks around `center`
+fn find_fish_spawn_positions(world_map: &ServerWorldMap, center: Vec3, count: usize) -> Vec<Vec3> {
+    let center_chunk = world_position_to_chunk_position(center);
+    let mut candidates = Vec::new();
+
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let chunk_pos = center_chunk + IVec3::new(dx, dy, dz);
+                let Some(chunk) = world_map.chunks.map.get(&chunk_pos) else {
+                    continue;
+                };
+                for (local_pos, block) in chunk.map.iter() {
+                    if block.id != BlockId::Water {
+                        continue;
+                    }
+                    let global_pos = to_global_pos(&chunk_pos, local_pos);
+                    let depth = water_depth(global_pos, FISH_SPAWN_MIN_DEPTH, |pos| {
+                        world_map
+                            .chunks
+                            .get_block_by_coordinates(&pos)
+                            .is_some_and(|block| block.id == BlockId::Water)
+                    });
+                    if depth >= FISH_SPAWN_MIN_DEPTH {
+                        candidates.push(global_pos);
+                    }
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .choose_multiple(&mut rand::thread_rng(), count)
+        .into_iter()
+        .map(|pos| pos.as_vec3() + Vec3::splat(0.5))
+        .collect()
+}
+
 pub fn manage_mob_spawning_system(mut world_map: ResMut<ServerWorldMap>, time: Res<ServerTime>) {
     if time.0 == 100 && !world_map.players.is_empty() {
         debug!("Should spawn mob");
@@ -29,21 +87,69 @@ pub fn manage_mob_spawning_system(mut world_map: ResMut<ServerWorldMap>, time: R
             0.0,
         );
 
+        // Mobs pick their targets on their own, see `targeting.rs`
         let mob = ServerMob {
-            kind: MobKind::Fox,
-            position,
-            target: MobTarget::Player(*world_map.players.keys().next().unwrap()),
-            action: MobAction::Walk,
-            rotation: Quat::IDENTITY,
-            height: 1.0,
-            width: 1.0,
-            depth: 1.5,
             on_ground: true,
-            velocity: Vec3::ZERO,
+            ..ServerMob::new(MobKind::Fox, position)
         };
 
         info!("Spawning new mob on server: {:?}", mob);
 
         world_map.mobs.insert(id, mob);
+
+        // Fish only swim in the overworld
+        let Some(player_position) = world_map
+            .players_in(DimensionId::Overworld)
+            .next()
+            .map(|player| player.position)
+        else {
+            return;
+        };
+        for position in find_fish_spawn_positions(&world_map, player_position, FISH_SPAWN_COUNT) {
+            let fish = ServerMob::new(MobKind::Fish, position);
+
+            info!("Spawning new fish on server: {:?}", fish);
+
+            world_map.mobs.insert(create_new_mob_id(), fish);
+        }
     }
 }
+
+/// Spawns the mob of the spawn egg held by the player against the block they
+/// are looking at. Spawn eggs are used up, except by creative players.
+pub fn use_spawn_egg(
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    mobs: &mut HashMap<MobId, ServerMob>,
+    input: &PlayerFrameInput,
+) {
+    let Some(stack) = player.inventory.inner.get(&input.hotbar_slot) else {
+        return;
+    };
+    let ItemType::SpawnEgg(kind) = stack.item_type else {
+        return;
+    };
+
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return;
+    };
+
+    let spawn_block = hit.position + hit.face.to_ivec3();
+    let position = spawn_block.as_vec3() + Vec3::splat(0.5);
+    if position.distance(player.position) > SPAWN_EGG_MAX_DISTANCE {
+        return;
+    }
+
+    if player.game_mode != GameMode::Creative {
+        player
+            .inventory
+            .remove_item_from_stack(input.hotbar_slot, 1);
+    }
+
+    info!(
+        "Player {} spawned a {:?} at {:?} with a spawn egg",
+        player.id, kind, position
+    );
+    mobs.insert(create_new_mob_id(), ServerMob::new(kind, position));
+}
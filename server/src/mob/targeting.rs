@@ -0,0 +1,102 @@
+use bevy::math::Vec3;
+use bevy_ecs::system::{Res, ResMut};
+use bevy_log::debug;
+use shared::{
+    messages::PlayerId,
+    players::GameMode,
+    world::{
+        raycast, DimensionId, MobAction, MobTarget, ServerChunkWorldMap, ServerMob, ServerWorldMap,
+        SpatialEntity, SpatialHash,
+    },
+};
+
+/// Hostile mobs notice players closer than this
+const AGGRO_RANGE: f32 = 16.0;
+/// Mobs forget about the player they target once further away than this
+const AGGRO_DROP_RANGE: f32 = 24.0;
+
+fn eye_position(mob: &ServerMob) -> Vec3 {
+    mob.position + Vec3::Y * mob.height * 0.4
+}
+
+/// Whether no block stands between `from` and `to`
+fn has_line_of_sight(chunks: &ServerChunkWorldMap, from: Vec3, to: Vec3) -> bool {
+    let distance = from.distance(to);
+    if distance < 0.01 {
+        return true;
+    }
+    match raycast::raycast_from_source_position_and_direction(chunks, from, (to - from) / distance)
+    {
+        Some(hit) => (hit.position.as_vec3() + Vec3::splat(0.5)).distance(from) > distance,
+        None => true,
+    }
+}
+
+fn forget_target(mob: &mut ServerMob) {
+    mob.target = MobTarget::Position(mob.position);
+    mob.action = MobAction::Idle;
+}
+
+/// Reacts to the mob being hit by `attacker`: hostile mobs go after them, the others
+/// run away
+pub fn provoke(mob: &mut ServerMob, attacker: PlayerId) {
+    mob.target = MobTarget::Player(attacker);
+    mob.action = if mob.kind.is_hostile() {
+        MobAction::Attack
+    } else {
+        MobAction::Flee
+    };
+}
+
+/// Makes hostile mobs go after the nearest player they can see, and mobs forget
+/// about players that left or got too far away
+pub fn mob_targeting_system(mut world_map: ResMut<ServerWorldMap>, spatial: Res<SpatialHash>) {
+    let world_map = world_map.as_mut();
+    let players = &world_map.players;
+    let chunks = &world_map.chunks;
+
+    for (id, mob) in world_map.mobs.iter_mut() {
+        if mob.kind.is_aquatic() {
+            continue;
+        }
+
+        if let MobTarget::Player(target) = mob.target {
+            // Players who went to another dimension are out of reach
+            let in_range = players.get(&target).is_some_and(|player| {
+                player.dimension == DimensionId::Overworld
+                    && player.position.distance(mob.position) <= AGGRO_DROP_RANGE
+            });
+            if in_range {
+                continue;
+            }
+            debug!("Mob {} lost track of player {}", id, target);
+            forget_target(mob);
+        }
+
+        if !mob.kind.is_hostile() {
+            continue;
+        }
+
+        let eyes = eye_position(mob);
+        let nearest = spatial
+            .query_radius(mob.position, AGGRO_RANGE)
+            .into_iter()
+            .filter_map(|entity| match entity {
+                SpatialEntity::Player(id) => players.get(&id),
+                SpatialEntity::Mob(_) => None,
+            })
+            .filter(|player| player.game_mode != GameMode::Creative)
+            .filter(|player| has_line_of_sight(chunks, eyes, player.position))
+            .min_by(|a, b| {
+                a.position
+                    .distance_squared(mob.position)
+                    .total_cmp(&b.position.distance_squared(mob.position))
+            });
+
+        if let Some(player) = nearest {
+            debug!("Mob {} noticed player {}", id, player.id);
+            mob.target = MobTarget::Player(player.id);
+            mob.action = MobAction::Attack;
+        }
+    }
+}
@@ -7,8 +7,10 @@ pub fn cleanup_all_players_from_world(world_map: &mut ServerWorldMap) {
     for p in world_map.players.values_mut() {
         p.last_input_processed = 0;
     }
-    for (_, chunk) in world_map.chunks.map.iter_mut() {
-        chunk.sent_to_clients.clear();
+    for chunks in world_map.all_dimension_chunks_mut() {
+        for chunk in chunks.map.values_mut() {
+            chunk.sent_to_clients.clear();
+        }
     }
 }
 
@@ -21,7 +23,9 @@ pub fn cleanup_player_from_world(
         save_event_writer.write(SaveRequestEvent::Player(*player_id));
     }
 
-    for (_, chunk) in world_map.chunks.map.iter_mut() {
-        chunk.sent_to_clients.retain(|id| id != player_id);
+    for chunks in world_map.all_dimension_chunks_mut() {
+        for chunk in chunks.map.values_mut() {
+            chunk.sent_to_clients.retain(|id| id != player_id);
+        }
     }
 }
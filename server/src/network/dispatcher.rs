@@ -1,58 +1,229 @@
+use crate::console::{console_commands_system, ConsoleInput};
 use crate::init::{LobbyPlayer, ServerLobby, ServerTime};
 use crate::mob::behavior::mob_behavior_system;
+use crate::mob::combat::{knockback_system, LastAttacks};
+use crate::mob::leash::leash_system;
+use crate::mob::targeting::mob_targeting_system;
 use crate::network::broadcast_chat::*;
 use crate::network::cleanup::cleanup_player_from_world;
+use crate::network::operators::Operators;
+use crate::network::overlays::{send_ui_overlays_system, UiOverlayEvent};
+use crate::network::voice::relay_voice_frame;
+use crate::scheduler::run_scheduled_tasks_system;
 use crate::world;
 use crate::world::background_generation::{
     background_chunk_generation_system, ChunkGenerationTasks,
 };
-use crate::world::broadcast_world::broadcast_world_state;
+use crate::world::backup::BackupRequestEvent;
+use crate::world::boats::leave_boat;
+use crate::world::broadcast_world::{broadcast_world_state, ChunkResendRequests};
+use crate::world::buckets::use_bucket;
+use crate::world::chunk_store::CorruptChunks;
+use crate::world::data::SAVE_PATH;
+use crate::world::dimensions::{change_dimension, PortalCooldowns};
+use crate::world::fluid::{
+    broadcast_fluid_particles_system, fluid_particles_enabled, simulate_fluid_particles_system,
+    spawn_waterfall_particles_system,
+};
+use crate::world::fluid_flow::{fluid_flow_system, FluidFlow};
+use crate::world::heatmap::{heatmap_command, BlockHeatmap};
+use crate::world::idle::{
+    idle_state_system, idle_throttle_system, server_is_active, ServerIdle, SoloPause,
+};
+use crate::world::limits::{chunk_cap_system, item_stack_cap_system, mob_cap_system, CapWarnings};
 use crate::world::load_from_file::load_player_data;
+use crate::world::locate::locate_command;
+use crate::world::moisture::{invalidate_moisture_system, MoistureField};
+use crate::world::prefabs::place_prefab_command;
+use crate::world::rollback::{rollback_command, BlockChangeLog};
 use crate::world::save::SaveRequestEvent;
 use crate::world::simulation::{handle_player_inputs_system, PlayerInputsEvent};
+use crate::world::sleep::{sleep_system, SleepingPlayers};
+use crate::world::spatial::{rebuild_spatial_hash_system, record_entity_history_system};
+use crate::world::spawn::{
+    respawn_system, set_spawn_point_command, teleport_to_spawn, world_spawn_position,
+};
+use crate::world::water_audit::{water_audit_system, WaterAudit, WaterAuditToggleEvent};
+use crate::world::waystones::teleport_to_waystone;
+use crate::world::weather::Weather;
 use crate::world::BlockInteractionEvent;
 use bevy::prelude::*;
-use bevy_log::{debug, info};
+use bevy_log::{debug, info, warn};
 use bevy_renet::renet::{RenetServer, ServerEvent};
+use shared::fluid::FluidParticles;
 use shared::messages::{
-    AuthRegisterResponse, ChatConversation, ClientToServerMessage, FullChatMessage, PlayerSave,
-    PlayerSpawnEvent, ServerToClientMessage,
+    AuthRegisterResponse, ChatConversation, ClientToServerMessage, FullChatMessage, PlayerId,
+    PlayerSave, PlayerSpawnEvent, ServerAnnouncement, ServerToClientMessage,
+};
+use shared::players::{
+    AnimationEvent, AnimationKind, AnimationTarget, GameMode, Player, PlayerRosterEntry,
+    PlayerRosterUpdate,
+};
+use shared::sets::{configure_server_sets, ServerSet};
+use shared::world::{
+    block_registry, block_registry_hash, EntityHistory, ItemStack, ServerWorldMap, SpatialHash,
+    WorldSpawn,
 };
-use shared::players::Player;
-use shared::world::ServerWorldMap;
 use shared::{GameFolderPaths, GameServerConfig, TICKS_PER_SECOND};
 
 use super::extensions::SendGameMessageExtension;
 
 pub fn setup_resources_and_events(app: &mut App) {
     app.add_event::<SaveRequestEvent>()
+        .add_event::<BackupRequestEvent>()
         .add_event::<BlockInteractionEvent>()
         .add_event::<PlayerInputsEvent>()
-        .init_resource::<ChunkGenerationTasks>();
+        .add_event::<WaterAuditToggleEvent>()
+        .add_event::<UiOverlayEvent>()
+        .init_resource::<ChunkGenerationTasks>()
+        .init_resource::<ChunkResendRequests>()
+        .init_resource::<FluidParticles>()
+        .init_resource::<WaterAudit>()
+        .init_resource::<FluidFlow>()
+        .init_resource::<Weather>()
+        .init_resource::<MoistureField>()
+        .init_resource::<SleepingPlayers>()
+        .init_resource::<SpatialHash>()
+        .init_resource::<LastAttacks>()
+        .init_resource::<EntityHistory>()
+        .init_resource::<ServerIdle>()
+        .init_resource::<SoloPause>()
+        .init_resource::<BlockHeatmap>()
+        .init_resource::<PortalCooldowns>()
+        .init_resource::<CapWarnings>()
+        .init_resource::<world::fishing::FishingLines>();
 
     setup_chat_resources(app);
 }
 
+/// Every system goes in a `ServerSet`, see `shared::sets` for the order between them
 pub fn register_systems(app: &mut App) {
-    // Chaining the two so that saves are always done on the same frame as the request
+    configure_server_sets(app);
+
+    // Most systems of a set share the world map or the server, they are chained
+    // so that their order is the same on every frame
     app.add_systems(
         Update,
-        (server_update_system, world::save::save_world_system).chain(),
+        (
+            run_scheduled_tasks_system.run_if(server_is_active),
+            console_commands_system.run_if(resource_exists::<ConsoleInput>),
+            // Saves are always done on the same frame as the request
+            server_update_system,
+            world::save::save_world_system,
+            world::backup::backup_system,
+        )
+            .chain()
+            .in_set(ServerSet::Input),
     );
 
-    app.add_systems(Update, broadcast_world_state);
-
-    app.add_systems(Update, world::handle_block_interactions);
+    app.add_systems(
+        Update,
+        (
+            world::effects::status_effects_system,
+            world::drowning::breathing_system,
+            respawn_system,
+            world::item_frames::send_item_frames_system,
+            handle_player_inputs_system,
+            sleep_system,
+            world::fishing::fishing_system.run_if(server_is_active),
+            world::boats::boats_system.run_if(server_is_active),
+            world::handle_block_interactions,
+            item_stack_cap_system,
+            (crate::mob::manage_mob_spawning_system, mob_cap_system)
+                .chain()
+                .run_if(server_is_active),
+            chunk_cap_system,
+            background_chunk_generation_system,
+            (
+                world::weather::weather_update_system,
+                world::weather::broadcast_weather_system,
+                world::random_tick::random_tick_system,
+            )
+                .chain()
+                .run_if(server_is_active),
+        )
+            .chain()
+            .in_set(ServerSet::Simulation),
+    );
 
-    app.add_systems(Update, crate::mob::manage_mob_spawning_system);
+    app.add_systems(
+        Update,
+        (
+            fluid_flow_system.run_if(server_is_active),
+            (
+                spawn_waterfall_particles_system,
+                simulate_fluid_particles_system,
+            )
+                .chain()
+                .run_if(fluid_particles_enabled)
+                .run_if(server_is_active),
+            water_audit_system,
+            invalidate_moisture_system,
+        )
+            .chain()
+            .in_set(ServerSet::Water),
+    );
 
-    app.add_systems(Update, handle_player_inputs_system);
+    app.add_systems(
+        Update,
+        (
+            (
+                broadcast_world_state,
+                world::far_terrain::send_far_terrain_system,
+            )
+                .chain()
+                .run_if(server_is_active),
+            broadcast_fluid_particles_system
+                .run_if(fluid_particles_enabled)
+                .run_if(server_is_active),
+            send_ui_overlays_system,
+        )
+            .chain()
+            .in_set(ServerSet::Broadcast),
+    );
 
-    app.add_systems(Update, background_chunk_generation_system);
+    app.add_systems(
+        PostUpdate,
+        update_server_time
+            .run_if(server_is_active)
+            .in_set(ServerSet::Simulation),
+    );
 
-    app.add_systems(PostUpdate, update_server_time);
+    // Decided right after the network update, so that a joining player wakes the
+    // whole tick up. Mobs only move in FixedUpdate, which doesn't run while idle as
+    // virtual time is paused.
+    app.add_systems(
+        PreUpdate,
+        idle_state_system
+            .after(bevy_renet::RenetReceive)
+            .in_set(ServerSet::Input),
+    );
+    app.add_systems(Last, idle_throttle_system.in_set(ServerSet::Simulation));
 
-    app.add_systems(FixedUpdate, mob_behavior_system);
+    // The broadphase is rebuilt every frame, and again once mobs have moved
+    app.add_systems(
+        PreUpdate,
+        rebuild_spatial_hash_system
+            .run_if(server_is_active)
+            .in_set(ServerSet::Broadcast),
+    );
+    app.add_systems(
+        FixedUpdate,
+        (
+            (
+                mob_targeting_system,
+                mob_behavior_system,
+                knockback_system,
+                leash_system,
+            )
+                .chain()
+                .in_set(ServerSet::Simulation),
+            world::currents::water_currents_system.in_set(ServerSet::Water),
+            (rebuild_spatial_hash_system, record_entity_history_system)
+                .chain()
+                .in_set(ServerSet::Broadcast),
+        ),
+    );
 }
 
 fn server_update_system(
@@ -62,17 +233,31 @@ fn server_update_system(
         ResMut<ChatConversation>,
         ResMut<ServerLobby>,
     ),
-    (mut ev_chat, mut ev_app_exit, mut ev_save_request, mut ev_player_inputs): (
+    (mut ev_chat, mut ev_app_exit, mut ev_save_request, mut ev_player_inputs, mut ev_water_audit): (
         EventWriter<ChatMessageEvent>,
         EventWriter<AppExit>,
         EventWriter<SaveRequestEvent>,
         EventWriter<PlayerInputsEvent>,
+        EventWriter<WaterAuditToggleEvent>,
     ),
     config: Res<GameServerConfig>,
     mut world_map: ResMut<ServerWorldMap>,
     time: Res<ServerTime>,
     game_folder_paths: Res<GameFolderPaths>,
     world_seed: Res<shared::world::WorldSeed>,
+    generation_config: Res<shared::world::GenerationConfig>,
+    world_spawn: Res<WorldSpawn>,
+    spatial: Res<SpatialHash>,
+    operators: Res<Operators>,
+    mut corrupt_chunks: ResMut<CorruptChunks>,
+    mut solo_pause: ResMut<SoloPause>,
+    (mut heatmap, mut block_log): (ResMut<BlockHeatmap>, ResMut<BlockChangeLog>),
+    (mut generation_tasks, mut portal_cooldowns, mut chunk_resends, mut fluid_particles): (
+        ResMut<ChunkGenerationTasks>,
+        ResMut<PortalCooldowns>,
+        ResMut<ChunkResendRequests>,
+        ResMut<FluidParticles>,
+    ),
 ) {
     for event in server_events.read() {
         debug!("event received");
@@ -82,8 +267,14 @@ fn server_update_system(
             }
             ServerEvent::ClientDisconnected { client_id, reason } => {
                 info!("Player {} disconnected: {}", client_id, reason);
-                lobby.players.remove(client_id);
+                if let Some(player) = lobby.players.remove(client_id) {
+                    broadcast_player_left(&mut server, *client_id, player.name);
+                }
                 cleanup_player_from_world(&mut world_map, client_id, &mut ev_save_request);
+                ev_water_audit.write(WaterAuditToggleEvent {
+                    client_id: *client_id,
+                    enabled: false,
+                });
             }
         }
     }
@@ -109,8 +300,16 @@ fn server_update_system(
                     {
                         player
                     } else {
-                        let data =
-                            load_player_data(&world_map.name, &client_id, &game_folder_paths);
+                        let data = load_player_data(
+                            &world_map.name,
+                            &client_id,
+                            &game_folder_paths,
+                            world_spawn_position(
+                                &world_map.chunks,
+                                &world_spawn,
+                                Player::default().height,
+                            ),
+                        );
 
                         world_map.players.insert(
                             client_id,
@@ -119,7 +318,10 @@ fn server_update_system(
                                 is_flying: data.is_flying,
                                 position: data.position,
                                 camera_transform: data.camera_transform,
+                                spawn_point: data.spawn_point,
+                                dimension: data.dimension,
                                 name: auth_req.username.clone(),
+                                game_mode: config.game_mode,
                                 ..default()
                             },
                         );
@@ -142,6 +344,8 @@ fn server_update_system(
                                 position: player.position,
                                 camera_transform: player.camera_transform,
                                 is_flying: player.is_flying,
+                                spawn_point: None,
+                                dimension: player.dimension,
                             },
                         })
                         .collect();
@@ -154,10 +358,36 @@ fn server_update_system(
                         timestamp_ms,
                         players: all_player_spawn_events,
                         world_seed: world_seed.0,
+                        world_spawn: world_spawn.0,
+                        game_mode: registered_player.game_mode,
+                        render_distance: config.broadcast_render_distance,
+                        fluid_mode: config.fluid_mode,
+                        block_registry: (auth_req.block_registry_hash != block_registry_hash())
+                            .then(block_registry),
                     };
 
                     server.send_game_message(client_id, auth_res.into());
 
+                    // The new player gets the full roster, everyone else only hears about them
+                    let roster = lobby
+                        .players
+                        .iter()
+                        .map(|(id, player)| PlayerRosterEntry {
+                            id: *id,
+                            name: player.name.clone(),
+                        })
+                        .collect();
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::PlayerRoster(PlayerRosterUpdate::Snapshot(roster)),
+                    );
+                    server.broadcast_game_message(ServerToClientMessage::PlayerRoster(
+                        PlayerRosterUpdate::Joined(PlayerRosterEntry {
+                            id: client_id,
+                            name: registered_player.name.clone(),
+                        }),
+                    ));
+
                     // Send message to all players that a new one spawned
                     for (id, player) in lobby.players.iter() {
                         let spawn_message = PlayerSpawnEvent {
@@ -167,6 +397,8 @@ fn server_update_system(
                                 position: registered_player.position,
                                 camera_transform: registered_player.camera_transform,
                                 is_flying: registered_player.is_flying,
+                                spawn_point: None,
+                                dimension: registered_player.dimension,
                             },
                         };
 
@@ -205,7 +437,9 @@ fn server_update_system(
                         ev_app_exit.write(AppExit::Success);
                     } else {
                         server.disconnect(client_id);
-                        lobby.players.remove(&client_id);
+                        if let Some(player) = lobby.players.remove(&client_id) {
+                            broadcast_player_left(&mut server, client_id, player.name);
+                        }
                         info!("Player {:?} disconnected", client_id);
                     }
                 }
@@ -228,14 +462,289 @@ fn server_update_system(
                         ev_save_request.write(SaveRequestEvent::Player(client_id));
                     }
                 }
+                ClientToServerMessage::SetWaterAudit(enabled) => {
+                    let Some(player) = world_map.players.get(&client_id) else {
+                        continue;
+                    };
+                    if enabled && !operators.is_operator(&player.name, config.is_solo) {
+                        warn!(
+                            "Player {} tried to enable the water audit without being an operator",
+                            player.name
+                        );
+                        server.send_game_message(
+                            client_id,
+                            ServerToClientMessage::Announcement(ServerAnnouncement {
+                                content: "Only operators can enable the water audit".to_string(),
+                            }),
+                        );
+                        continue;
+                    }
+                    ev_water_audit.write(WaterAuditToggleEvent { client_id, enabled });
+                }
+                ClientToServerMessage::SetPause(paused) => {
+                    // Nobody can stop the world of a multiplayer server
+                    if config.is_solo {
+                        solo_pause.0 = paused;
+                    }
+                }
+                ClientToServerMessage::CreativeTakeItem(item_id) => {
+                    let Some(player) = world_map.players.get_mut(&client_id) else {
+                        continue;
+                    };
+
+                    // Items can only be pulled from thin air in creative mode
+                    if player.game_mode != GameMode::Creative {
+                        warn!(
+                            "Player {} tried to take {:?} from the creative catalog outside of creative mode",
+                            client_id, item_id
+                        );
+                        continue;
+                    }
+
+                    debug!(
+                        "Player {} took {:?} from the creative catalog",
+                        client_id, item_id
+                    );
+                    player.inventory.add_item_to_inventory(ItemStack {
+                        item_id,
+                        item_type: item_id.get_default_type(),
+                        nb: item_id.get_max_stack(),
+                    });
+                }
+                ClientToServerMessage::Voice(frame) => {
+                    relay_voice_frame(&mut server, &world_map, &spatial, client_id, frame);
+                }
+                ClientToServerMessage::Emote(emote) => {
+                    if !world_map.players.contains_key(&client_id) {
+                        continue;
+                    }
+                    debug!("Player {} plays emote {:?}", client_id, emote);
+                    server.broadcast_game_message(ServerToClientMessage::Animation(
+                        AnimationEvent {
+                            target: AnimationTarget::Player(client_id),
+                            kind: AnimationKind::Emote(emote),
+                        },
+                    ));
+                }
+                ClientToServerMessage::SetSpawnPoint(command) => {
+                    set_spawn_point_command(
+                        &mut server,
+                        &mut world_map,
+                        &operators,
+                        &config,
+                        client_id,
+                        command,
+                    );
+                }
+                ClientToServerMessage::TeleportToSpawn => {
+                    teleport_to_spawn(&mut server, &mut world_map, &world_spawn, client_id);
+                }
+                ClientToServerMessage::RegenerateCorruptChunks => {
+                    let Some(player) = world_map.players.get(&client_id) else {
+                        continue;
+                    };
+                    let content = if !operators.is_operator(&player.name, config.is_solo) {
+                        warn!(
+                            "Player {} tried to regenerate chunks without being an operator",
+                            player.name
+                        );
+                        "Only operators can regenerate chunks".to_string()
+                    } else {
+                        info!(
+                            "Player {} regenerates {} corrupt chunks",
+                            player.name,
+                            corrupt_chunks.0.len()
+                        );
+                        let content =
+                            format!("Regenerating {} corrupt chunks", corrupt_chunks.0.len());
+                        corrupt_chunks.0.clear();
+                        content
+                    };
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+                    );
+                }
+                ClientToServerMessage::RequestSeed => {
+                    let Some(player) = world_map.players.get(&client_id) else {
+                        continue;
+                    };
+                    let content = if operators.is_operator(&player.name, config.is_solo) {
+                        format!("Seed: {}", world_seed.0)
+                    } else {
+                        "Only operators can see the seed".to_string()
+                    };
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+                    );
+                }
+                ClientToServerMessage::Locate(name) => {
+                    locate_command(
+                        &mut server,
+                        &world_map,
+                        world_seed.0,
+                        *generation_config,
+                        client_id,
+                        &name,
+                    );
+                }
+                ClientToServerMessage::RequestHeatmap(kind) => {
+                    heatmap_command(
+                        &mut server,
+                        &world_map,
+                        &heatmap,
+                        &operators,
+                        &config,
+                        client_id,
+                        kind,
+                    );
+                }
+                ClientToServerMessage::Rollback(request) => {
+                    rollback_command(
+                        &mut server,
+                        &mut world_map,
+                        &block_log,
+                        &operators,
+                        &config,
+                        client_id,
+                        request,
+                    );
+                }
+                ClientToServerMessage::PlacePrefab(placement) => {
+                    place_prefab_command(
+                        &mut server,
+                        &mut world_map,
+                        &mut block_log,
+                        &mut heatmap,
+                        client_id,
+                        placement,
+                    );
+                }
+                ClientToServerMessage::WaystoneTeleport { from, to } => {
+                    teleport_to_waystone(&mut server, &mut world_map, client_id, from, to, time.0);
+                }
+                ClientToServerMessage::ChangeDimension => {
+                    let world_folder = game_folder_paths
+                        .game_folder_path
+                        .join(SAVE_PATH)
+                        .join(&world_map.name);
+                    change_dimension(
+                        &mut server,
+                        &mut world_map,
+                        &world_folder,
+                        &mut generation_tasks,
+                        &mut corrupt_chunks,
+                        &mut portal_cooldowns,
+                        world_seed.0,
+                        *generation_config,
+                        client_id,
+                        time.0,
+                    );
+                }
+                ClientToServerMessage::SetDifficulty(difficulty) => {
+                    let Some(player) = world_map.players.get(&client_id) else {
+                        continue;
+                    };
+                    if !operators.is_operator(&player.name, config.is_solo) {
+                        warn!(
+                            "Player {} tried to set the difficulty without being an operator",
+                            player.name
+                        );
+                        server.send_game_message(
+                            client_id,
+                            ServerToClientMessage::Announcement(ServerAnnouncement {
+                                content: "Only operators can set the difficulty".to_string(),
+                            }),
+                        );
+                        continue;
+                    }
+                    info!(
+                        "Player {} set the difficulty to {}",
+                        player.name,
+                        difficulty.name()
+                    );
+                    world_map.difficulty = difficulty;
+                    server.broadcast_game_message(ServerToClientMessage::Announcement(
+                        ServerAnnouncement {
+                            content: format!("Difficulty set to {}", difficulty.name()),
+                        },
+                    ));
+                }
+                ClientToServerMessage::RequestChunks(chunks) => {
+                    debug!(
+                        "Player {} asked again for {} chunks",
+                        client_id,
+                        chunks.len()
+                    );
+                    chunk_resends.request(client_id, chunks);
+                }
+                ClientToServerMessage::UseBucket(bucket) => {
+                    use_bucket(
+                        &mut world_map,
+                        &mut block_log,
+                        &mut heatmap,
+                        config.fluid_particles.then_some(&mut *fluid_particles),
+                        config.fluid_mode,
+                        client_id,
+                        bucket,
+                    );
+                }
+                ClientToServerMessage::LeaveBoat => {
+                    leave_boat(&mut world_map, client_id);
+                }
             }
         }
     }
 }
 
+fn broadcast_player_left(server: &mut RenetServer, id: PlayerId, name: String) {
+    server.broadcast_game_message(ServerToClientMessage::PlayerRoster(
+        PlayerRosterUpdate::Left(PlayerRosterEntry { id, name }),
+    ));
+}
+
 fn update_server_time(mut time: ResMut<ServerTime>) {
     if time.0.is_multiple_of(5 * TICKS_PER_SECOND) {
         debug!("Server time: {}", time.0);
     }
     time.0 += 1;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::schedule::Schedules;
+    use shared::sets::{ordered_schedules, systems_outside_sets};
+
+    #[test]
+    fn every_system_is_in_a_server_set_without_ambiguities() {
+        let mut app = App::new();
+        register_systems(&mut app);
+
+        for label in ordered_schedules() {
+            if !app.world().resource::<Schedules>().contains(label) {
+                continue;
+            }
+            app.world_mut().schedule_scope(label, |world, schedule| {
+                schedule.initialize(world).unwrap();
+                let outside = systems_outside_sets(
+                    schedule,
+                    &[
+                        &ServerSet::Input,
+                        &ServerSet::Simulation,
+                        &ServerSet::Water,
+                        &ServerSet::Broadcast,
+                    ],
+                );
+                assert!(outside.is_empty(), "{label:?}: {outside:?}");
+                let conflicts = schedule.graph().conflicting_systems();
+                assert!(
+                    conflicts.is_empty(),
+                    "{label:?}: {} ambiguities",
+                    conflicts.len()
+                );
+            });
+        }
+    }
+}
@@ -2,3 +2,6 @@ pub mod broadcast_chat;
 pub mod cleanup;
 pub mod dispatcher;
 pub mod extensions;
+pub mod operators;
+pub mod overlays;
+pub mod voice;
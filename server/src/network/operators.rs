@@ -0,0 +1,21 @@
+//! Players allowed to run administration commands, listed by name in
+//! `server.toml`:
+//!
+//! ```toml
+//! operators = ["alice", "bob"]
+//! ```
+//!
+//! The host of a solo game is always an operator.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Resource, Deserialize, Default, Debug, Clone)]
+#[serde(transparent)]
+pub struct Operators(pub Vec<String>);
+
+impl Operators {
+    pub fn is_operator(&self, name: &str, is_solo: bool) -> bool {
+        is_solo || self.0.iter().any(|operator| operator == name)
+    }
+}
@@ -0,0 +1,40 @@
+//! Server-driven HUD overlays: boss bars, titles and action bar messages.
+//!
+//! Game systems and plugins write a `UiOverlayEvent`, sent to the clients at the
+//! end of the frame. Functions that already hold the `RenetServer` can send a
+//! `ServerToClientMessage::UiOverlay` themselves.
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerId, ServerToClientMessage, UiOverlay};
+
+use super::extensions::SendGameMessageExtension;
+
+#[derive(Event, Debug, Clone)]
+pub struct UiOverlayEvent {
+    /// Player to show the overlay to, everyone if `None`
+    pub player: Option<PlayerId>,
+    pub overlay: UiOverlay,
+}
+
+impl UiOverlayEvent {
+    pub fn everyone(overlay: UiOverlay) -> Self {
+        Self {
+            player: None,
+            overlay,
+        }
+    }
+}
+
+pub fn send_ui_overlays_system(
+    mut events: EventReader<UiOverlayEvent>,
+    mut server: ResMut<RenetServer>,
+) {
+    for event in events.read() {
+        let message = ServerToClientMessage::UiOverlay(event.overlay.clone());
+        match event.player {
+            Some(player) => server.send_game_message(player, message),
+            None => server.broadcast_game_message(message),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use bevy_log::warn;
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerId, ServerToClientMessage};
+use shared::voice::{VoiceFrame, VoicePacket, MAX_VOICE_FRAME_BYTES, VOICE_RANGE};
+use shared::world::{ServerWorldMap, SpatialEntity, SpatialHash};
+
+use super::extensions::SendGameMessageExtension;
+
+/// Forwards a voice frame to the players within hearing range of the speaker
+pub fn relay_voice_frame(
+    server: &mut RenetServer,
+    world_map: &ServerWorldMap,
+    spatial: &SpatialHash,
+    speaker: PlayerId,
+    frame: VoiceFrame,
+) {
+    if frame.data.len() > MAX_VOICE_FRAME_BYTES {
+        warn!(
+            "Player {} sent an oversized voice frame ({} bytes)",
+            speaker,
+            frame.data.len()
+        );
+        return;
+    }
+
+    let Some(position) = world_map.players.get(&speaker).map(|p| p.position) else {
+        return;
+    };
+
+    for listener in spatial.query_radius(position, VOICE_RANGE) {
+        let SpatialEntity::Player(id) = listener else {
+            continue;
+        };
+        if id == speaker || !world_map.players.contains_key(&id) {
+            continue;
+        }
+        server.send_game_message(
+            id,
+            ServerToClientMessage::Voice(VoicePacket {
+                speaker,
+                position,
+                frame: frame.clone(),
+            }),
+        );
+    }
+}
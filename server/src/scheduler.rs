@@ -0,0 +1,279 @@
+//! Recurring server actions.
+//!
+//! Tasks are run at a given server tick, and optionally repeated. Announcements
+//! and timed restarts are configured in the `[scheduler]` table of `server.toml`,
+//! in the game folder:
+//!
+//! ```toml
+//! [scheduler]
+//! announcements = [
+//!     { message = "Welcome to the server!", interval_secs = 900 },
+//! ]
+//! restart = { interval_secs = 21600, warnings_secs = [600, 60, 10] }
+//! ```
+//!
+//! Other parts of the server can register their own hooks with
+//! [`ServerScheduler::register_hook`], like the periodic backups.
+
+use std::fs;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_log::{debug, error, info, warn};
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::messages::{ServerAnnouncement, ServerToClientMessage};
+use shared::world::ServerWorldMap;
+use shared::{GameFolderPaths, TICKS_PER_SECOND};
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::operators::Operators;
+use crate::world::anti_xray::AntiXrayConfig;
+use crate::world::backup::BackupConfig;
+use crate::world::far_terrain::FarTerrainConfig;
+use crate::world::idle::IdleConfig;
+use crate::world::limits::LimitsConfig;
+use crate::world::rollback::BlockLogConfig;
+use crate::world::save::SaveRequestEvent;
+use crate::world::sleep::SleepConfig;
+
+pub const SERVER_CONFIG_FILE: &str = "server.toml";
+
+/// Contents of `server.toml`
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct ServerFileConfig {
+    pub scheduler: SchedulerConfig,
+    pub sleep: SleepConfig,
+    pub operators: Operators,
+    pub backup: BackupConfig,
+    pub anti_xray: AntiXrayConfig,
+    pub far_terrain: FarTerrainConfig,
+    pub idle: IdleConfig,
+    pub limits: LimitsConfig,
+    pub block_log: BlockLogConfig,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub announcements: Vec<AnnouncementConfig>,
+    pub restart: Option<RestartConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnnouncementConfig {
+    pub message: String,
+    pub interval_secs: u64,
+    /// Delay before the first broadcast, defaults to the interval
+    pub delay_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestartConfig {
+    pub interval_secs: u64,
+    /// Seconds before the restart at which players are warned
+    #[serde(default = "default_restart_warnings")]
+    pub warnings_secs: Vec<u64>,
+}
+
+fn default_restart_warnings() -> Vec<u64> {
+    vec![300, 60, 10]
+}
+
+pub fn load_server_file_config(game_folder_paths: &GameFolderPaths) -> ServerFileConfig {
+    let path = game_folder_paths.game_folder_path.join(SERVER_CONFIG_FILE);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return ServerFileConfig::default();
+    };
+
+    match toml::from_str(&content) {
+        Ok(config) => {
+            info!("Loaded server configuration from {}", path.display());
+            config
+        }
+        Err(err) => {
+            error!("Invalid server configuration {} : {}", path.display(), err);
+            ServerFileConfig::default()
+        }
+    }
+}
+
+pub type TaskHook = Box<dyn FnMut(&mut World) + Send + Sync>;
+
+pub enum TaskAction {
+    /// Broadcasts a message to every player's chat
+    Announce(String),
+    /// Saves the world and stops the server, which is expected to be restarted
+    /// by whatever supervises it
+    Restart,
+    Hook(TaskHook),
+}
+
+struct ScheduledTask {
+    name: String,
+    next_tick: u64,
+    interval_ticks: Option<u64>,
+    action: TaskAction,
+}
+
+#[derive(Resource, Default)]
+pub struct ServerScheduler {
+    /// Ticks elapsed since the server started
+    tick: u64,
+    tasks: Vec<ScheduledTask>,
+}
+
+fn duration_to_ticks(duration: Duration) -> u64 {
+    ((duration.as_secs_f64() * TICKS_PER_SECOND as f64).round() as u64).max(1)
+}
+
+fn format_delay(secs: u64) -> String {
+    match secs {
+        s if s >= 3600 && s % 3600 == 0 => format!("{} hour(s)", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{} minute(s)", s / 60),
+        s => format!("{} second(s)", s),
+    }
+}
+
+impl ServerScheduler {
+    /// Builds the scheduler from `server.toml`. Restarts would close the game of
+    /// a solo player, so they are ignored by integrated servers.
+    pub fn from_config(config: &SchedulerConfig, allow_restarts: bool) -> Self {
+        let mut scheduler = Self::default();
+
+        for announcement in config.announcements.iter() {
+            if announcement.interval_secs == 0 {
+                warn!(
+                    "Ignoring announcement with no interval: {}",
+                    announcement.message
+                );
+                continue;
+            }
+            let interval = Duration::from_secs(announcement.interval_secs);
+            let delay = announcement
+                .delay_secs
+                .map(Duration::from_secs)
+                .unwrap_or(interval);
+            scheduler.schedule(
+                "announcement",
+                delay,
+                Some(interval),
+                TaskAction::Announce(announcement.message.clone()),
+            );
+        }
+
+        if let Some(restart) = config.restart.as_ref().filter(|_| allow_restarts) {
+            let interval = Duration::from_secs(restart.interval_secs);
+            for warning in restart.warnings_secs.iter() {
+                if *warning == 0 || *warning >= restart.interval_secs {
+                    continue;
+                }
+                scheduler.schedule(
+                    "restart warning",
+                    interval - Duration::from_secs(*warning),
+                    None,
+                    TaskAction::Announce(format!(
+                        "The server will restart in {}",
+                        format_delay(*warning)
+                    )),
+                );
+            }
+            scheduler.schedule("restart", interval, None, TaskAction::Restart);
+            info!(
+                "Server restart scheduled in {}",
+                format_delay(restart.interval_secs)
+            );
+        }
+
+        scheduler
+    }
+
+    /// Runs `action` after `delay`, then every `interval` if given
+    pub fn schedule(
+        &mut self,
+        name: impl Into<String>,
+        delay: Duration,
+        interval: Option<Duration>,
+        action: TaskAction,
+    ) {
+        self.tasks.push(ScheduledTask {
+            name: name.into(),
+            next_tick: self.tick + duration_to_ticks(delay),
+            interval_ticks: interval.map(duration_to_ticks),
+            action,
+        });
+    }
+
+    /// Runs `hook` every `interval`, with full access to the server world
+    pub fn register_hook(
+        &mut self,
+        name: impl Into<String>,
+        interval: Duration,
+        hook: impl FnMut(&mut World) + Send + Sync + 'static,
+    ) {
+        self.schedule(
+            name,
+            interval,
+            Some(interval),
+            TaskAction::Hook(Box::new(hook)),
+        );
+    }
+}
+
+fn announce(world: &mut World, content: String) {
+    info!("[ANNOUNCEMENT] {}", content);
+    world.resource_mut::<RenetServer>().broadcast_game_message(
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+}
+
+fn restart(world: &mut World) {
+    announce(world, "The server is restarting now".to_string());
+
+    let players: Vec<_> = world
+        .resource::<ServerWorldMap>()
+        .players
+        .keys()
+        .copied()
+        .collect();
+    world.send_event(SaveRequestEvent::World);
+    for player in players {
+        world.send_event(SaveRequestEvent::Player(player));
+    }
+    // Saves happen later in this frame, the app only exits once it's over
+    world.send_event(AppExit::Success);
+}
+
+pub fn run_scheduled_tasks_system(world: &mut World) {
+    world.resource_scope(|world, mut scheduler: Mut<ServerScheduler>| {
+        scheduler.tick += 1;
+        let tick = scheduler.tick;
+
+        let mut index = 0;
+        while index < scheduler.tasks.len() {
+            let task = &mut scheduler.tasks[index];
+            if task.next_tick > tick {
+                index += 1;
+                continue;
+            }
+
+            debug!("Running scheduled task {}", task.name);
+            match &mut task.action {
+                TaskAction::Announce(message) => announce(world, message.clone()),
+                TaskAction::Restart => restart(world),
+                TaskAction::Hook(hook) => hook(world),
+            }
+
+            match task.interval_ticks {
+                Some(interval) => {
+                    task.next_tick = tick + interval;
+                    index += 1;
+                }
+                None => {
+                    scheduler.tasks.remove(index);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,82 @@
+//! Hiding ores and caves from x-ray cheats.
+//!
+//! When enabled, the chunks sent to the players replace every solid block that
+//! touches no air, water or decoration with stone, so a modified client can't
+//! see through the ground. The server keeps the real blocks, and a chunk is sent
+//! again with its neighbours whenever it changes, which reveals the blocks a
+//! player just uncovered. It is off by default and set in the `[anti_xray]`
+//! table of `server.toml`:
+//!
+//! ```toml
+//! [anti_xray]
+//! enabled = true
+//! ```
+
+use bevy::math::IVec3;
+use bevy::prelude::*;
+use serde::Deserialize;
+use shared::world::{
+    to_global_pos, BlockData, BlockId, BlockTransparency, ServerChunk, ServerChunkWorldMap,
+    WorldMap,
+};
+
+/// Block sent in place of the enclosed ones
+const PLACEHOLDER: BlockId = BlockId::Stone;
+
+const FACE_OFFSETS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct AntiXrayConfig {
+    pub enabled: bool,
+}
+
+/// Whether a player could see the block at `position`. Blocks next to an unloaded
+/// chunk count as exposed, so that nothing stays hidden once it is generated.
+fn is_exposed(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    FACE_OFFSETS.iter().any(|offset| {
+        chunks
+            .get_block_by_coordinates(&(position + *offset))
+            .is_none_or(|block| block.id.get_visibility() != BlockTransparency::Solid)
+    })
+}
+
+/// Copy of the chunk at `chunk_pos` as players are allowed to see it
+pub fn obfuscate_chunk(
+    chunks: &ServerChunkWorldMap,
+    chunk_pos: IVec3,
+    chunk: &ServerChunk,
+) -> ServerChunk {
+    let mut hidden = chunk.clone();
+    for (local_pos, block) in hidden.map.iter_mut() {
+        if block.id == PLACEHOLDER || block.id.get_visibility() != BlockTransparency::Solid {
+            continue;
+        }
+        if !is_exposed(chunks, to_global_pos(&chunk_pos, local_pos)) {
+            *block = BlockData::new(PLACEHOLDER, block.direction);
+        }
+    }
+    hidden
+}
+
+/// Modified chunks and their face neighbours, whose border blocks may have been
+/// uncovered by the change
+pub fn chunks_to_reveal(chunks_to_update: &[IVec3]) -> Vec<IVec3> {
+    let mut revealed = chunks_to_update.to_vec();
+    for chunk_pos in chunks_to_update {
+        for offset in FACE_OFFSETS {
+            let neighbour = *chunk_pos + offset;
+            if !revealed.contains(&neighbour) {
+                revealed.push(neighbour);
+            }
+        }
+    }
+    revealed
+}
@@ -1,110 +1,319 @@
 use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task};
 use futures_lite::future;
-use log::info;
-use shared::world::{FloraRequest, ServerWorldMap, WorldSeed};
-use shared::LOD1_MULTIPLIER;
-use std::collections::HashSet;
+use log::{debug, info};
+use shared::world::{
+    dimension_chunks_mut, world_position_to_chunk_position, DimensionId, GenerationConfig,
+    ServerChunk, ServerChunkWorldMap, ServerWorldMap, WorldSeed,
+};
+use shared::{GameFolderPaths, LOD1_MULTIPLIER};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 
-use crate::world::generation::{generate_chunk, ChunkGenerationResult};
+use crate::world::chunk_store::{dimension_folder, load_chunk, CorruptChunks};
+use crate::world::data::SAVE_PATH;
+use crate::world::dimensions::generate_dimension_chunk;
+use crate::world::generation::{decorate_ready_chunks, ChunkGenerationResult};
+use crate::world::limits::{loaded_chunk_count, Cap, CapWarnings, LimitsConfig};
+use crate::world::structures::{apply_queued_structure_requests, dispatch_structure_requests};
 
-use super::broadcast_world::get_all_active_chunks;
+use super::broadcast_world::get_player_nearby_chunks_coords;
 use shared::GameServerConfig;
 
-const MAX_CONCURRENT_GENERATION_TASKS: usize = 4;
+/// Chunk waiting for its generation, the nearest to a player coming out first
+#[derive(PartialEq, Eq)]
+struct QueuedChunk {
+    /// Squared distance to the nearest player, in chunks
+    distance: i32,
+    dimension: DimensionId,
+    chunk_pos: IVec3,
+}
+
+impl Ord for QueuedChunk {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.cmp(&self.distance)
+    }
+}
+
+impl PartialOrd for QueuedChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Chunk coming out of a background task, which reads back the chunks unloaded
+/// by the cap of loaded chunks and generates the others
+pub enum ChunkTaskResult {
+    Generated(ChunkGenerationResult),
+    Loaded(ServerChunk),
+    /// The file of an unloaded chunk of the overworld was damaged, the chunk is
+    /// left out like the corrupt chunks found when the world is loaded
+    Corrupt,
+}
 
 /// Resource to track in-progress chunk generation tasks.
 ///
-/// The `in_progress` HashSet duplicates position information from `tasks`, but provides
-/// O(1) lookup vs O(n) linear scan of the tasks vec. With a small MAX_CONCURRENT_GENERATION_TASKS
-/// (currently 4), the memory overhead is negligible and the O(1) lookup is worthwhile since we
-/// check membership for every candidate chunk each frame.
+/// Chunks are generated on the `AsyncComputeTaskPool`, at most one task per thread
+/// of the pool so that the generation never competes with the tick for the
+/// main thread. The `in_progress` HashSet duplicates position information from
+/// `tasks`, but provides O(1) lookup vs O(n) linear scan of the tasks vec.
 #[derive(Resource, Default)]
 pub struct ChunkGenerationTasks {
-    /// Active generation tasks with their chunk positions
-    pub tasks: Vec<(IVec3, Task<ChunkGenerationResult>)>,
+    /// Active generation tasks with the dimension and position of their chunk
+    pub tasks: Vec<((DimensionId, IVec3), Task<ChunkTaskResult>)>,
     /// Chunk positions currently being generated (for O(1) duplicate checking)
-    pub in_progress: HashSet<IVec3>,
+    pub in_progress: HashSet<(DimensionId, IVec3)>,
+    /// Missing chunks around the players, by distance to the nearest one
+    queue: BinaryHeap<QueuedChunk>,
+    /// Chunks of the players when the queue was built
+    queued_around: HashSet<(DimensionId, IVec3)>,
+}
+
+impl ChunkGenerationTasks {
+    /// Drops the generation of a chunk, generated by other means in the meantime
+    pub fn cancel(&mut self, dimension: DimensionId, chunk_pos: IVec3) {
+        if self.in_progress.remove(&(dimension, chunk_pos)) {
+            self.tasks.retain(|(key, _)| *key != (dimension, chunk_pos));
+        }
+    }
+
+    /// Whether missing chunks are waiting for a free worker
+    pub fn has_queued_chunks(&self) -> bool {
+        !self.queue.is_empty()
+    }
+}
+
+/// Inserts a freshly generated chunk in the world, with the structures it shares
+/// with its neighbours, and grows the flora of the chunks around it that were
+/// only waiting for it
+pub fn integrate_generated_chunk(
+    chunks: &mut ServerChunkWorldMap,
+    chunk_pos: IVec3,
+    result: ChunkGenerationResult,
+) {
+    chunks.map.insert(chunk_pos, result.chunk);
+    apply_queued_structure_requests(chunks, chunk_pos);
+    dispatch_structure_requests(chunks, result.structure_requests);
+    if !result.flora.is_empty() {
+        chunks.pending_flora.insert(chunk_pos, result.flora);
+    }
+    decorate_ready_chunks(chunks, chunk_pos);
+}
+
+/// Puts back a chunk read from the file it was unloaded to, with the parts of
+/// structures its neighbours queued for it in the meantime
+pub fn integrate_loaded_chunk(
+    chunks: &mut ServerChunkWorldMap,
+    chunk_pos: IVec3,
+    mut chunk: ServerChunk,
+) {
+    // The players it was sent to may have dropped it since
+    chunk.sent_to_clients.clear();
+    chunks.saved_ts.insert(chunk_pos, chunk.ts);
+    chunks.map.insert(chunk_pos, chunk);
+    if chunks.structure_requests.contains_key(&chunk_pos) {
+        apply_queued_structure_requests(chunks, chunk_pos);
+        chunks.chunks_to_update.push(chunk_pos);
+    }
+    decorate_ready_chunks(chunks, chunk_pos);
+}
+
+/// Missing chunks within `radius` of the players, ordered by their distance to
+/// the nearest player of their dimension
+fn queue_missing_chunks(
+    world_map: &ServerWorldMap,
+    player_chunks: &HashSet<(DimensionId, IVec3)>,
+    radius: i32,
+    corrupt_chunks: &CorruptChunks,
+) -> BinaryHeap<QueuedChunk> {
+    let mut nearest: HashMap<(DimensionId, IVec3), i32> = HashMap::new();
+    for (dimension, player_chunk) in player_chunks.iter() {
+        let Some(chunks) = world_map.dimension_chunks(*dimension) else {
+            continue;
+        };
+        for chunk_pos in get_player_nearby_chunks_coords(*player_chunk, radius) {
+            // Corrupt chunks are only generated again once an operator confirms it
+            if chunks.map.contains_key(&chunk_pos)
+                || corrupt_chunks.0.contains(&(*dimension, chunk_pos))
+            {
+                continue;
+            }
+            let distance = (chunk_pos - *player_chunk).length_squared();
+            nearest
+                .entry((*dimension, chunk_pos))
+                .and_modify(|nearest| *nearest = (*nearest).min(distance))
+                .or_insert(distance);
+        }
+    }
+
+    nearest
+        .into_iter()
+        .map(|((dimension, chunk_pos), distance)| QueuedChunk {
+            distance,
+            dimension,
+            chunk_pos,
+        })
+        .collect()
 }
 
 /// System to spawn async chunk generation tasks and collect completed results.
 ///
-/// Spawns up to MAX_CONCURRENT_GENERATION_TASKS parallel chunk generation tasks
-/// using Bevy's AsyncComputeTaskPool, then polls for completed tasks and integrates
-/// the results into the world map.
+/// Polls the generation tasks and integrates the finished chunks into the world
+/// map, then hands the chunks nearest to the players to the free workers. The
+/// queue of missing chunks is only built again when a player moves to another
+/// chunk, or when corrupt chunks may be regenerated. The chunks unloaded by the
+/// cap of loaded chunks are read back from their file rather than generated, and
+/// no chunk is loaded past the cap.
 pub fn background_chunk_generation_system(
     mut world_map: ResMut<ServerWorldMap>,
     seed: Res<WorldSeed>,
+    generation_config: Res<GenerationConfig>,
     config: Res<GameServerConfig>,
     mut generation_tasks: ResMut<ChunkGenerationTasks>,
+    mut corrupt_chunks: ResMut<CorruptChunks>,
+    game_folder_paths: Res<GameFolderPaths>,
+    limits: Res<LimitsConfig>,
+    mut warnings: ResMut<CapWarnings>,
+    time: Res<Time<Real>>,
 ) {
     // === Phase 1: Collect completed tasks ===
-    let mut completed: Vec<(usize, IVec3, ChunkGenerationResult)> = Vec::new();
+    let mut completed: Vec<(usize, (DimensionId, IVec3), ChunkTaskResult)> = Vec::new();
 
-    for (index, (chunk_pos, task)) in generation_tasks.tasks.iter_mut().enumerate() {
+    for (index, (key, task)) in generation_tasks.tasks.iter_mut().enumerate() {
         if let Some(result) = future::block_on(future::poll_once(task)) {
-            completed.push((index, *chunk_pos, result));
+            completed.push((index, *key, result));
         }
     }
 
     // Sort completed indices in descending order to safely remove without invalidating indices
-    completed.sort_by(|a, b| b.0.cmp(&a.0));
+    completed.sort_by_key(|(index, _, _)| std::cmp::Reverse(*index));
+
+    let world_map = world_map.as_mut();
 
     // Process completed results
-    for (index, chunk_pos, result) in completed {
-        info!("Generated chunk: {:?}", chunk_pos);
-
-        world_map.chunks.map.insert(chunk_pos, result.chunk);
-
-        if !result.requests_for_chunk_above.is_empty() {
-            let chunk_above = IVec3::new(chunk_pos.x, chunk_pos.y + 1, chunk_pos.z);
-            world_map
-                .chunks
-                .generation_requests
-                .entry(chunk_above)
-                .or_default()
-                .extend(result.requests_for_chunk_above);
+    for (index, (dimension, chunk_pos), result) in completed {
+        let chunks =
+            dimension_chunks_mut(&mut world_map.chunks, &mut world_map.dimensions, dimension);
+        match result {
+            ChunkTaskResult::Generated(result) => {
+                info!(
+                    "Generated chunk: {:?} of the {}",
+                    chunk_pos,
+                    dimension.name()
+                );
+                integrate_generated_chunk(chunks, chunk_pos, result);
+            }
+            ChunkTaskResult::Loaded(chunk) => {
+                debug!(
+                    "Loaded chunk: {:?} of the {} back",
+                    chunk_pos,
+                    dimension.name()
+                );
+                integrate_loaded_chunk(chunks, chunk_pos, chunk);
+            }
+            ChunkTaskResult::Corrupt => {
+                chunks.saved_ts.remove(&chunk_pos);
+                corrupt_chunks.0.insert((dimension, chunk_pos));
+            }
         }
 
         // Remove from tasks first, then from in_progress to keep structures in sync
         let _ = generation_tasks.tasks.swap_remove(index);
-        generation_tasks.in_progress.remove(&chunk_pos);
+        generation_tasks.in_progress.remove(&(dimension, chunk_pos));
     }
 
-    // === Phase 2: Spawn new tasks ===
-    let first_player = match world_map.players.values().next() {
-        Some(player) => player,
-        None => return, // No players, no need to generate chunks
-    };
-
+    // === Phase 2: Queue the missing chunks ===
     // Use extended render distance to generate chunks for LOD 1 rendering
     let effective_render_distance =
         (config.broadcast_render_distance as f32 * LOD1_MULTIPLIER) as i32;
 
-    let all_chunks =
-        get_all_active_chunks(&world_map.players, effective_render_distance, first_player);
+    let player_chunks: HashSet<(DimensionId, IVec3)> = world_map
+        .players
+        .values()
+        .map(|player| {
+            (
+                player.dimension,
+                world_position_to_chunk_position(player.position),
+            )
+        })
+        .collect();
+    if player_chunks != generation_tasks.queued_around || corrupt_chunks.is_changed() {
+        generation_tasks.queue = queue_missing_chunks(
+            world_map,
+            &player_chunks,
+            effective_render_distance,
+            &corrupt_chunks,
+        );
+        generation_tasks.queued_around = player_chunks;
+    }
 
+    // === Phase 3: Spawn new tasks ===
     let task_pool = AsyncComputeTaskPool::get();
+    let workers = task_pool.thread_num().max(1);
     let seed_value = seed.0;
+    let generation_config = *generation_config;
 
-    for chunk_pos in all_chunks {
-        if generation_tasks.tasks.len() >= MAX_CONCURRENT_GENERATION_TASKS {
+    let mut loaded_chunks = loaded_chunk_count(world_map) + generation_tasks.tasks.len();
+    let world_folder = game_folder_paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(&world_map.name);
+
+    while generation_tasks.tasks.len() < workers {
+        if loaded_chunks >= limits.max_loaded_chunks {
+            if !generation_tasks.queue.is_empty() {
+                warnings.warn(Cap::LoadedChunks, time.elapsed(), || {
+                    format!(
+                        "Loaded chunk cap of {} reached with no chunk left to unload, no more chunks are generated",
+                        limits.max_loaded_chunks
+                    )
+                });
+            }
             break;
         }
 
-        if world_map.chunks.map.contains_key(&chunk_pos)
-            || generation_tasks.in_progress.contains(&chunk_pos)
+        let Some(QueuedChunk {
+            dimension,
+            chunk_pos,
+            ..
+        }) = generation_tasks.queue.pop()
+        else {
+            break;
+        };
+
+        // Chunks may have been generated right away since the queue was built
+        let chunks =
+            dimension_chunks_mut(&mut world_map.chunks, &mut world_map.dimensions, dimension);
+        if chunks.map.contains_key(&chunk_pos)
+            || generation_tasks
+                .in_progress
+                .contains(&(dimension, chunk_pos))
         {
             continue;
         }
 
-        let pending_requests: Option<Vec<FloraRequest>> =
-            world_map.chunks.generation_requests.remove(&chunk_pos);
-
-        let task =
-            task_pool.spawn(async move { generate_chunk(chunk_pos, seed_value, pending_requests) });
+        // Chunks unloaded by the cap are read back from their file
+        let folder = chunks
+            .saved_ts
+            .contains_key(&chunk_pos)
+            .then(|| dimension_folder(&world_folder, dimension));
+        let task = task_pool.spawn(async move {
+            let Some(folder) = folder else {
+                return ChunkTaskResult::Generated(generate_dimension_chunk(
+                    dimension,
+                    chunk_pos,
+                    seed_value,
+                    generation_config,
+                ));
+            };
+            match load_chunk(&folder, chunk_pos) {
+                Some(chunk) => ChunkTaskResult::Loaded(chunk),
+                None => ChunkTaskResult::Corrupt,
+            }
+        });
 
-        generation_tasks.tasks.push((chunk_pos, task));
-        generation_tasks.in_progress.insert(chunk_pos);
+        generation_tasks.tasks.push(((dimension, chunk_pos), task));
+        generation_tasks.in_progress.insert((dimension, chunk_pos));
+        loaded_chunks += 1;
     }
 }
@@ -0,0 +1,179 @@
+//! World backups, made with `backup now` on the server console or on a schedule.
+//!
+//! The world and players are saved first, then the world folder is copied to
+//! `backups/<world>/<unix time in ms>/` in the game folder. Only the most recent
+//! backups are kept. Both are set in the `[backup]` table of `server.toml`:
+//!
+//! ```toml
+//! [backup]
+//! interval_secs = 3600
+//! keep = 5
+//! ```
+//!
+//! Restoring a backup is done by copying its content back to the world folder
+//! while the server is stopped.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy_log::{error, info};
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::messages::{ServerAnnouncement, ServerToClientMessage};
+use shared::world::ServerWorldMap;
+use shared::{GameFolderPaths, GameServerConfig};
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::operators::Operators;
+use crate::world::data::SAVE_PATH;
+use crate::world::save::{SaveRequestEvent, TEMPORARY_SAVE_EXTENSION};
+
+pub const BACKUP_PATH: &str = "backups/";
+
+#[derive(Resource, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Time between two scheduled backups, none if unset
+    pub interval_secs: Option<u64>,
+    /// Number of backups kept for each world, older ones are deleted
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: None,
+            keep: 5,
+        }
+    }
+}
+
+/// Backs up the world once this frame's saves are written
+#[derive(Event, Debug)]
+pub struct BackupRequestEvent;
+
+/// Saves the world and every player, and backs the world up right after
+pub fn request_backup(world: &mut World) {
+    let players: Vec<_> = world
+        .resource::<ServerWorldMap>()
+        .players
+        .keys()
+        .copied()
+        .collect();
+    world.send_event(SaveRequestEvent::World);
+    for player in players {
+        world.send_event(SaveRequestEvent::Player(player));
+    }
+    world.send_event(BackupRequestEvent);
+}
+
+/// Copies the content of `from` to `to`, except for saves being written.
+/// Returns the number of files copied.
+fn copy_folder(from: &Path, to: &Path) -> io::Result<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_folder(&path, &destination)?;
+        } else if path
+            .extension()
+            .is_none_or(|extension| extension != TEMPORARY_SAVE_EXTENSION)
+        {
+            fs::copy(&path, &destination)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Deletes the oldest backups of the folder, keeping the `keep` most recent ones
+fn prune_backups(backups_folder: &Path, keep: usize) -> io::Result<()> {
+    let mut backups: Vec<(u128, PathBuf)> = fs::read_dir(backups_folder)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let timestamp = entry.file_name().to_str()?.parse().ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for (_, path) in backups.into_iter().take(excess) {
+        info!("Deleting old backup {}", path.display());
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+fn create_backup(world_folder: &Path, backups_folder: &Path, keep: usize) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let destination = backups_folder.join(timestamp.to_string());
+
+    let copied = copy_folder(world_folder, &destination)?;
+    info!(
+        "Copied {} files from {} to {}",
+        copied,
+        world_folder.display(),
+        destination.display()
+    );
+    prune_backups(backups_folder, keep)?;
+    Ok(destination)
+}
+
+/// Makes the requested backups, after the saves of the frame, and tells the
+/// online operators how it went
+pub fn backup_system(
+    mut events: EventReader<BackupRequestEvent>,
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+    game_folder_paths: Res<GameFolderPaths>,
+    backup_config: Res<BackupConfig>,
+    operators: Res<Operators>,
+    config: Res<GameServerConfig>,
+) {
+    // Several requests in a frame make a single backup
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let game_folder = &game_folder_paths.game_folder_path;
+    let world_folder = game_folder.join(SAVE_PATH).join(&world_map.name);
+    let backups_folder = game_folder.join(BACKUP_PATH).join(&world_map.name);
+
+    let content = match create_backup(&world_folder, &backups_folder, backup_config.keep) {
+        Ok(destination) => {
+            info!(
+                "Backup of world {} written to {}",
+                world_map.name,
+                destination.display()
+            );
+            format!("Backup of world {} done", world_map.name)
+        }
+        Err(err) => {
+            error!("Backup of world {} failed : {}", world_map.name, err);
+            format!("Backup of world {} failed : {}", world_map.name, err)
+        }
+    };
+
+    for player in world_map
+        .players
+        .values()
+        .filter(|player| operators.is_operator(&player.name, config.is_solo))
+    {
+        server.send_game_message(
+            player.id,
+            ServerToClientMessage::Announcement(ServerAnnouncement {
+                content: content.clone(),
+            }),
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Boats put on the water by players, see `shared::world::boats`.
+//!
+//! The boats nobody rides live in `ServerWorldMap::boats` and drift here, while
+//! the boat of a player moves with their inputs in the player simulation.
+//! Boarding takes the boat out of the map and leaving puts it back, under a new
+//! id.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use shared::fluid::water_to_fill_from;
+use shared::messages::{BoatUpdate, PlayerFrameInput, PlayerId, ServerToClientMessage};
+use shared::players::{GameMode, Player};
+use shared::world::{
+    raycast, Boat, BoatId, DimensionId, ItemId, ItemStack, ServerBoat, ServerChunkWorldMap,
+    ServerWorldMap, BOAT_REACH,
+};
+use shared::TICKS_PER_SECOND;
+use ulid::Ulid;
+
+use crate::network::extensions::SendGameMessageExtension;
+
+/// Boats are sent again once they moved or turned this much, so that boats
+/// settling on the water aren't sent every tick
+const BOAT_RESEND_DISTANCE: f32 = 0.01;
+const BOAT_RESEND_ANGLE: f32 = 0.01;
+
+fn boat_stack() -> ItemStack {
+    ItemStack {
+        item_id: ItemId::Boat,
+        item_type: ItemId::Boat.get_default_type(),
+        nb: 1,
+    }
+}
+
+/// Boat the player is looking at, unless a block is in the way
+fn targeted_boat(
+    player: &Player,
+    chunks: &ServerChunkWorldMap,
+    boats: &HashMap<BoatId, ServerBoat>,
+    input: &PlayerFrameInput,
+) -> Option<BoatId> {
+    let (origin, direction) = raycast::camera_ray(&input.camera, &player.position, input.view_mode);
+    let block_distance = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+        .map(|hit| (hit.position.as_vec3() + Vec3::splat(0.5)).distance(origin))
+        .unwrap_or(f32::MAX);
+    raycast::raycast_entities(
+        boats.iter().map(|(id, boat)| (*id, boat.boat.bounds())),
+        origin,
+        direction,
+        BOAT_REACH,
+    )
+    .filter(|hit| hit.distance < block_distance)
+    .map(|hit| hit.entity)
+}
+
+/// Handles a right click for boats: boards the boat the player is looking at, or
+/// puts the held boat on the water they are looking at.
+///
+/// Returns whether the click was used by a boat.
+pub fn use_boat(
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    boats: &mut HashMap<BoatId, ServerBoat>,
+    input: &PlayerFrameInput,
+) -> bool {
+    if player.boat.is_some() {
+        return false;
+    }
+
+    if let Some(id) = targeted_boat(player, chunks, boats, input) {
+        let Some(ServerBoat { boat }) = boats.remove(&id) else {
+            return false;
+        };
+        info!("Player {} boarded boat {}", player.id, id);
+        player.boat = Some(boat);
+        player.position = boat.seat(player.height);
+        player.velocity = Vec3::ZERO;
+        return true;
+    }
+
+    let holding_boat = player
+        .inventory
+        .inner
+        .get(&input.hotbar_slot)
+        .is_some_and(|stack| stack.item_id == ItemId::Boat);
+    if !holding_boat {
+        return false;
+    }
+    let (origin, direction) = raycast::camera_ray(&input.camera, &player.position, input.view_mode);
+    let Some(water) = water_to_fill_from(chunks, origin, direction)
+        .filter(|water| (water.as_vec3() + Vec3::splat(0.5)).distance(origin) <= BOAT_REACH)
+    else {
+        return false;
+    };
+
+    let yaw = input.camera.rotation.to_euler(EulerRot::YXZ).0;
+    let id = Ulid::new().0;
+    info!(
+        "Player {} put boat {} on the water at {:?}",
+        player.id, id, water
+    );
+    boats.insert(
+        id,
+        ServerBoat {
+            boat: Boat::on_water(water, yaw),
+        },
+    );
+    if player.game_mode != GameMode::Creative {
+        player
+            .inventory
+            .remove_item_from_stack(input.hotbar_slot, 1);
+    }
+    true
+}
+
+/// Picks up the boat the player hits, which goes back to their inventory.
+///
+/// Returns whether a boat was hit.
+pub fn attack_boat(
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    boats: &mut HashMap<BoatId, ServerBoat>,
+    input: &PlayerFrameInput,
+) -> bool {
+    let Some(id) = targeted_boat(player, chunks, boats, input) else {
+        return false;
+    };
+    boats.remove(&id);
+    info!("Player {} picked boat {} up", player.id, id);
+    if player.game_mode != GameMode::Creative {
+        player.inventory.add_item_to_inventory(boat_stack());
+    }
+    true
+}
+
+/// Takes the player off their boat before they are teleported, the boat going
+/// back to their inventory
+pub fn stow_boat(player: &mut Player) {
+    if player.boat.take().is_none() {
+        return;
+    }
+    info!("Player {} got off their boat, teleported", player.id);
+    if player.game_mode != GameMode::Creative {
+        player.inventory.add_item_to_inventory(boat_stack());
+    }
+}
+
+/// Gets the player off their boat, which stays on the water. Boats only float
+/// in the overworld, elsewhere the boat goes back to the inventory.
+pub fn leave_boat(world_map: &mut ServerWorldMap, player_id: PlayerId) {
+    let Some(player) = world_map.players.get_mut(&player_id) else {
+        return;
+    };
+    let Some(boat) = player.boat else {
+        return;
+    };
+    player.position = boat.seat(player.height);
+    if player.dimension != DimensionId::Overworld {
+        stow_boat(player);
+        return;
+    }
+    info!("Player {} left their boat", player.id);
+    player.boat = None;
+    world_map.boats.insert(
+        Ulid::new().0,
+        ServerBoat {
+            boat: Boat {
+                velocity: Vec3::ZERO,
+                ..boat
+            },
+        },
+    );
+}
+
+/// Lets the boats nobody rides drift and settle on the water, and sends the ones
+/// that changed to every player. Players who just joined get all the boats.
+pub fn boats_system(
+    mut server: ResMut<RenetServer>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut sent: Local<HashMap<BoatId, Boat>>,
+    mut informed: Local<HashSet<PlayerId>>,
+) {
+    let delta = 1.0 / TICKS_PER_SECOND as f32;
+    let world_map = world_map.as_mut();
+    for boat in world_map.boats.values_mut() {
+        boat.boat.step(&world_map.chunks, 0.0, 0.0, delta);
+    }
+
+    let mut changes: Vec<(BoatId, Option<Boat>)> = Vec::new();
+    for (id, boat) in world_map.boats.iter() {
+        let moved = sent.get(id).is_none_or(|last| {
+            last.position.distance(boat.boat.position) > BOAT_RESEND_DISTANCE
+                || (last.yaw - boat.boat.yaw).abs() > BOAT_RESEND_ANGLE
+        });
+        if moved {
+            sent.insert(*id, boat.boat);
+            changes.push((*id, Some(boat.boat)));
+        }
+    }
+    sent.retain(|id, _| {
+        let kept = world_map.boats.contains_key(id);
+        if !kept {
+            changes.push((*id, None));
+        }
+        kept
+    });
+    if !changes.is_empty() {
+        server.broadcast_game_message(ServerToClientMessage::Boats(BoatUpdate { boats: changes }));
+    }
+
+    informed.retain(|id| world_map.players.contains_key(id));
+    for id in world_map.players.keys() {
+        if !informed.insert(*id) {
+            continue;
+        }
+        let boats = world_map
+            .boats
+            .iter()
+            .map(|(id, boat)| (*id, Some(boat.boat)))
+            .collect();
+        server.send_game_message(*id, ServerToClientMessage::Boats(BoatUpdate { boats }));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::init::ServerTime;
 use crate::network::extensions::SendGameMessageExtension;
+use crate::world::anti_xray::{chunks_to_reveal, obfuscate_chunk, AntiXrayConfig};
 use bevy::math::IVec3;
 use bevy::prelude::*;
 use bevy_ecs::system::ResMut;
@@ -8,20 +9,28 @@ use shared::messages::mob::MobUpdateEvent;
 use shared::messages::{ItemStackUpdateEvent, PlayerId, ServerToClientMessage, WorldUpdate};
 use shared::players::Player;
 use shared::world::{
-    world_position_to_chunk_position, ServerChunk, ServerChunkWorldMap, ServerWorldMap,
+    dimension_chunks_mut, spawn_ground_rank, world_position_to_chunk_position, DimensionId,
+    ServerChunk, ServerChunkWorldMap, ServerWorldMap, SpatialEntity, SpatialHash,
 };
 use shared::{GameServerConfig, CHUNK_SIZE, LOD1_MULTIPLIER};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 /// Maximum number of chunks to send to a client per update
 const MAX_CHUNKS_PER_UPDATE: usize = 50;
 
+/// Most chunks a client can have waiting to be sent again
+const MAX_CHUNK_RESEND_REQUESTS: usize = 64;
+
+/// Maximum number of chunks serialized and sent per tick, all players included,
+/// so that a burst of joining players doesn't stall the tick
+const MAX_CHUNKS_PER_TICK: usize = 96;
+
 // Scaling factor for chunk limit based on render distance
 // With the default render distance of 8, this gives 48 chunks per tick
 // The factor of 6 provides a good balance between initial load speed and bandwidth usage
 const CHUNKS_PER_RENDER_DISTANCE: i32 = 6;
 
-// Chunk prioritization constants for get_all_active_chunks
+// Chunk prioritization constants for order_chunks_by_render_score
 /// Dot product threshold for considering a chunk as "in front" of the player.
 /// -0.3 allows a wider viewing angle (~108° from center vs 90° for 0.0).
 /// This ensures chunks slightly behind the player are still prioritized.
@@ -51,12 +60,21 @@ const BEHIND_PLAYER_PENALTY: f32 = 5000.0;
 /// relative to the player's vertical position.
 const VERTICAL_DISTANCE_MULTIPLIER: f32 = 100.0;
 
+/// Score of the ground under the player, lower than any other chunk can get, so
+/// that joining players can leave the loading screen as soon as possible
+const SPAWN_GROUND_SCORE: f32 = -10_000.0;
+
 /// Calculate a score for chunk prioritization based on distance and view direction.
 /// # Arguments
 /// * `chunk_pos` - Position of the chunk being evaluated.
 /// * `player_chunk_pos` - Chunk the player is currently in.
 /// * `forward` - Player's forward view direction.
 fn get_chunk_render_score(chunk_pos: IVec3, player_chunk_pos: IVec3, forward: Vec3) -> f32 {
+    // The column under the player comes first, then the ring around it
+    if let Some(rank) = spawn_ground_rank(chunk_pos, player_chunk_pos) {
+        return SPAWN_GROUND_SCORE + rank as f32;
+    }
+
     let direction_from_player = (chunk_pos - player_chunk_pos).as_vec3().normalize_or_zero();
     let direction_dot_product = forward.dot(direction_from_player);
     let distance_from_player = (chunk_pos - player_chunk_pos).length_squared();
@@ -87,11 +105,34 @@ fn order_chunks_by_render_score(
         .unwrap_or(std::cmp::Ordering::Equal)
 }
 
+/// Chunks the clients never received and asked for again. They are sent after
+/// the chunks the clients are due, with what is left of the budget.
+#[derive(Resource, Default, Debug)]
+pub struct ChunkResendRequests(pub HashMap<PlayerId, VecDeque<IVec3>>);
+
+impl ChunkResendRequests {
+    pub fn request(&mut self, player: PlayerId, chunks: Vec<IVec3>) {
+        let queue = self.0.entry(player).or_default();
+        for chunk_pos in chunks {
+            if queue.len() >= MAX_CHUNK_RESEND_REQUESTS {
+                break;
+            }
+            if !queue.contains(&chunk_pos) {
+                queue.push_back(chunk_pos);
+            }
+        }
+    }
+}
+
 pub fn broadcast_world_state(
     mut server: ResMut<RenetServer>,
     time: Res<ServerTime>,
     mut world_map: ResMut<ServerWorldMap>,
     config: Res<GameServerConfig>,
+    spatial: Res<SpatialHash>,
+    anti_xray: Res<AntiXrayConfig>,
+    mut resend_requests: ResMut<ChunkResendRequests>,
+    mut round_robin: Local<usize>,
 ) {
     let ts = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -101,28 +142,63 @@ pub fn broadcast_world_state(
     let world_map = world_map.as_mut();
 
     let mobs = world_map.mobs.clone();
-    let players = &mut world_map.players;
-    let chunks = &mut world_map.chunks;
+    let ServerWorldMap {
+        players,
+        chunks,
+        dimensions,
+        ..
+    } = world_map;
+
+    if anti_xray.enabled {
+        // Digging may uncover hidden blocks at the border of the next chunk
+        for chunks in std::iter::once(&mut *chunks).chain(dimensions.values_mut()) {
+            chunks.chunks_to_update = chunks_to_reveal(&chunks.chunks_to_update);
+        }
+    }
+
+    // Players take turns being served first, so that the ones at the end of the
+    // list still get chunks when the budget of the tick runs out
+    let mut clients = server.clients_id();
+    clients.sort_unstable();
+    if !clients.is_empty() {
+        let first = *round_robin % clients.len();
+        clients.rotate_left(first);
+        *round_robin = round_robin.wrapping_add(1);
+    }
+    let mut chunk_budget = MAX_CHUNKS_PER_TICK;
+    resend_requests
+        .0
+        .retain(|player, _| players.contains_key(player));
 
-    for client in server.clients_id().iter_mut() {
+    for client in clients.iter() {
         let player = players.get_mut(client);
         let player = match player {
             Some(p) => p.clone(),
             None => continue,
         };
-
-        for (id, mob) in mobs.iter() {
-            if mob.position.distance(player.position)
-                < (config.broadcast_render_distance * CHUNK_SIZE) as f32
-            {
-                server.send_game_message(
-                    *client,
-                    ServerToClientMessage::MobUpdate(MobUpdateEvent {
-                        id: *id,
-                        mob: mob.clone(),
-                    }),
-                );
-            }
+        // Mobs only live in the overworld
+        let in_overworld = player.dimension == DimensionId::Overworld;
+
+        let range = (config.broadcast_render_distance * CHUNK_SIZE) as f32;
+        let nearby = if in_overworld {
+            spatial.query_radius(player.position, range)
+        } else {
+            Vec::new()
+        };
+        for entity in nearby {
+            let SpatialEntity::Mob(id) = entity else {
+                continue;
+            };
+            let Some(mob) = mobs.get(&id) else {
+                continue;
+            };
+            server.send_game_message(
+                *client,
+                ServerToClientMessage::MobUpdate(MobUpdateEvent {
+                    id,
+                    mob: mob.clone(),
+                }),
+            );
         }
 
         // Use extended render distance to support LOD 1 chunks on the client
@@ -132,28 +208,50 @@ pub fn broadcast_world_state(
         let msg = WorldUpdate {
             tick: time.0,
             time: ts,
-            new_map: get_world_map_chunks_to_send(chunks, &player, effective_render_distance),
-            mobs: mobs.clone(),
+            new_map: get_world_map_chunks_to_send(
+                dimension_chunks_mut(chunks, dimensions, player.dimension),
+                &player,
+                effective_render_distance,
+                chunk_budget,
+                anti_xray.enabled,
+                resend_requests.0.get_mut(client),
+            ),
+            mobs: if in_overworld {
+                mobs.clone()
+            } else {
+                HashMap::new()
+            },
             item_stacks: get_items_stacks(),
         };
 
         if msg.new_map.is_empty() {
             continue;
         }
+        chunk_budget = chunk_budget.saturating_sub(msg.new_map.len());
 
         let message = ServerToClientMessage::WorldUpdate(msg);
 
         server.send_game_message(*client, message);
     }
 
-    // Clear the list of chunks that needed updates after broadcasting to all clients
-    chunks.chunks_to_update.clear();
+    // Clear the list of chunks that needed updates after broadcasting to all clients,
+    // stamping them so that the next save writes them
+    for chunks in std::iter::once(chunks).chain(dimensions.values_mut()) {
+        for chunk_pos in chunks.chunks_to_update.drain(..) {
+            if let Some(chunk) = chunks.map.get_mut(&chunk_pos) {
+                chunk.ts = ts;
+            }
+        }
+    }
 }
 
 fn get_world_map_chunks_to_send(
     chunks: &mut ServerChunkWorldMap,
     player: &Player,
     broadcast_render_distance: i32,
+    chunk_budget: usize,
+    anti_xray: bool,
+    resend_requests: Option<&mut VecDeque<IVec3>>,
 ) -> HashMap<IVec3, ServerChunk> {
     // Send only chunks in render distance
     let mut map: HashMap<IVec3, ServerChunk> = HashMap::new();
@@ -166,7 +264,8 @@ fn get_world_map_chunks_to_send(
         .min(MAX_CHUNKS_PER_UPDATE as i32) as usize;
 
     let active_chunks =
-        get_player_chunks_prioritized(player, broadcast_render_distance, chunk_limit);
+        get_player_chunks_prioritized(player, broadcast_render_distance, chunk_limit.max(1));
+    let chunk_limit = chunk_limit.min(chunk_budget);
 
     // First, handle chunks that need to be updated (re-sent due to modifications)
     for &chunk_pos in &chunks.chunks_to_update {
@@ -184,19 +283,53 @@ fn get_world_map_chunks_to_send(
             break;
         }
 
-        let chunk = chunks.map.get_mut(&c);
-
         // If chunk already exists, transmit it to client
-        if let Some(chunk) = chunk {
-            if chunk.sent_to_clients.contains(&player.id) {
-                continue;
-            }
+        let Some(chunk) = chunks.map.get(&c) else {
+            continue;
+        };
+        if chunk.sent_to_clients.contains(&player.id) {
+            continue;
+        }
 
-            map.insert(c, chunk.clone());
+        let sent = if anti_xray {
+            obfuscate_chunk(chunks, c, chunk)
+        } else {
+            chunk.clone()
+        };
+        map.insert(c, sent);
+        if let Some(chunk) = chunks.map.get_mut(&c) {
             chunk.sent_to_clients.insert(player.id);
         }
     }
 
+    // Chunks asked again come last. The ones that aren't generated yet are sent
+    // as usual once they are.
+    if let Some(requests) = resend_requests {
+        let player_chunk_pos = world_position_to_chunk_position(player.position);
+        let radius_squared = broadcast_render_distance * broadcast_render_distance;
+        while map.len() < chunk_limit {
+            let Some(c) = requests.pop_front() else {
+                break;
+            };
+            if map.contains_key(&c) || (c - player_chunk_pos).length_squared() > radius_squared {
+                continue;
+            }
+            let Some(chunk) = chunks.map.get(&c) else {
+                continue;
+            };
+
+            let sent = if anti_xray {
+                obfuscate_chunk(chunks, c, chunk)
+            } else {
+                chunk.clone()
+            };
+            map.insert(c, sent);
+            if let Some(chunk) = chunks.map.get_mut(&c) {
+                chunk.sent_to_clients.insert(player.id);
+            }
+        }
+    }
+
     map
 }
 
@@ -249,46 +382,10 @@ fn get_player_chunks_prioritized(player: &Player, radius: i32, max_chunks: usize
     chunks
 }
 
-pub fn get_all_active_chunks(
-    players: &HashMap<PlayerId, Player>,
-    radius: i32,
-    requesting_player: &Player,
-) -> Vec<IVec3> {
-    let player_chunks: Vec<IVec3> = players
-        .values()
-        .map(|v| world_position_to_chunk_position(v.position))
-        .flat_map(|v| get_player_nearby_chunks_coords(v, radius))
-        .collect();
-
-    let mut chunks: Vec<IVec3> = Vec::new();
-
-    for c in player_chunks {
-        if !chunks.contains(&c) {
-            chunks.push(c);
-        }
-    }
-
-    // Prioritize chunks based on requesting player's view direction
-    let player_chunk_pos = world_position_to_chunk_position(requesting_player.position);
-    let forward = requesting_player.camera_transform.forward();
-
-    // Only partially sort the chunks we'll actually use
-    // This significantly improves performance when there are many chunks
-    let sort_count = chunks.len().min(MAX_CHUNKS_PER_UPDATE);
-
-    if chunks.len() > 1 {
-        chunks.select_nth_unstable_by(sort_count - 1, |a, b| {
-            order_chunks_by_render_score(a, b, player_chunk_pos, *forward)
-        });
-    }
-
-    chunks
-}
-
 /// Get all chunk coordinates within a spherical radius around the player's chunk position
 ///
 /// Resulting vector is not sorted in any way.
-fn get_player_nearby_chunks_coords(
+pub fn get_player_nearby_chunks_coords(
     player_chunk_position: IVec3,
     render_distance: i32,
 ) -> Vec<IVec3> {
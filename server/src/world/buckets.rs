@@ -0,0 +1,126 @@
+//! Buckets used by players, see `shared::fluid::bucket`.
+
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use shared::fluid::{is_within_bucket_reach, BucketUse, FluidMode, FluidParticles};
+use shared::messages::PlayerId;
+use shared::players::blocks::BlockInteractionOutcome;
+use shared::players::Player;
+use shared::world::{
+    dimension_chunks_mut, global_to_chunk_local, BlockData, BlockDirection, BlockId, DimensionId,
+    ItemId, ItemStack, ServerWorldMap, WorldMap,
+};
+
+use crate::world::heatmap::BlockHeatmap;
+use crate::world::rollback::BlockChangeLog;
+
+/// Particles splashing out of a poured water bucket, in the fluid particle mode
+const POUR_PARTICLES: usize = 8;
+
+/// Swaps one item of the hotbar slot for `item`, which goes to the same slot if
+/// it was the last one
+fn exchange_held_item(player: &mut Player, hotbar_slot: u32, item: ItemId) {
+    player.inventory.remove_item_from_stack(hotbar_slot, 1);
+    let stack = ItemStack {
+        item_id: item,
+        item_type: item.get_default_type(),
+        nb: 1,
+    };
+    if player.inventory.inner.contains_key(&hotbar_slot) {
+        player.inventory.add_item_to_inventory(stack);
+    } else {
+        player.inventory.inner.insert(hotbar_slot, stack);
+    }
+}
+
+/// Fills the held bucket from a water block, or pours the held water bucket
+/// out. A water block is taken or added whole, logged like blocks broken or
+/// placed by hand.
+///
+/// Pouring water in the overworld splashes `particles`, given when the fluid
+/// particle mode is on.
+pub fn use_bucket(
+    world_map: &mut ServerWorldMap,
+    block_log: &mut BlockChangeLog,
+    heatmap: &mut BlockHeatmap,
+    particles: Option<&mut FluidParticles>,
+    fluid_mode: FluidMode,
+    player_id: PlayerId,
+    bucket: BucketUse,
+) {
+    let ServerWorldMap {
+        chunks: overworld,
+        dimensions,
+        players,
+        ..
+    } = world_map;
+    let Some(player) = players.get_mut(&player_id) else {
+        return;
+    };
+    let held = player
+        .inventory
+        .inner
+        .get(&bucket.hotbar_slot)
+        .map(|stack| stack.item_id);
+    if !is_within_bucket_reach(player.position, bucket.target) {
+        warn!(
+            "Player {} tried to use a bucket on {:?}, out of reach",
+            player.name, bucket.target
+        );
+        return;
+    }
+
+    let dimension = player.dimension;
+    let chunks = dimension_chunks_mut(overworld, dimensions, dimension);
+    let target = chunks.get_block_by_coordinates(&bucket.target).copied();
+    let outcome = match held {
+        Some(ItemId::Bucket) => {
+            // Classic flowing water comes back on its own, only sources are taken
+            let Some(water) = target.filter(|block| {
+                block.id == BlockId::Water && (fluid_mode == FluidMode::Finite || block.level == 0)
+            }) else {
+                return;
+            };
+            chunks.remove_block_by_coordinates(&bucket.target);
+            exchange_held_item(player, bucket.hotbar_slot, ItemId::WaterBucket);
+            BlockInteractionOutcome::Broken {
+                block: water,
+                position: bucket.target,
+            }
+        }
+        Some(ItemId::WaterBucket) => {
+            let loaded = chunks.has_chunk(&global_to_chunk_local(&bucket.target).0);
+            if !loaded || target.is_some_and(|block| !block.id.is_replaceable()) {
+                return;
+            }
+            let water = BlockData::new(BlockId::Water, BlockDirection::Front);
+            chunks.set_block(&bucket.target, water);
+            exchange_held_item(player, bucket.hotbar_slot, ItemId::Bucket);
+            // Particles only move through the blocks of the overworld
+            if let Some(particles) = particles.filter(|_| dimension == DimensionId::Overworld) {
+                particles.spawn_burst(
+                    bucket.target.as_vec3() + Vec3::splat(0.5),
+                    Vec3::ZERO,
+                    POUR_PARTICLES,
+                );
+            }
+            BlockInteractionOutcome::Placed {
+                block: water,
+                position: bucket.target,
+            }
+        }
+        _ => {
+            warn!(
+                "Player {} tried to use a bucket without holding one",
+                player.name
+            );
+            return;
+        }
+    };
+
+    info!("Player {} used a bucket: {:?}", player.name, outcome);
+    if dimension == DimensionId::Overworld {
+        block_log.record(&player.name, &[outcome]);
+        heatmap.record(player_id, &[outcome]);
+    }
+}
@@ -0,0 +1,173 @@
+//! Top-down renders of the explored overworld, behind the `render-map` console
+//! command.
+//!
+//! Each pixel is the topmost block of a column, colored from `map_color`. Slopes
+//! facing north are lighter and the ones facing south darker, and water gets
+//! darker with depth. Columns without any generated chunk are left transparent.
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use bevy::prelude::*;
+use shared::world::{BlockId, ServerChunkWorldMap, WorldMap};
+use shared::CHUNK_SIZE;
+
+/// Largest radius of a render, in blocks, so that a typo doesn't fill the disk
+pub const MAX_MAP_RADIUS: i32 = 2048;
+
+/// Water deeper than this is drawn as dark as it gets
+const MAX_SHADED_DEPTH: i32 = 8;
+/// Brightness lost by water for each block of depth
+const DEPTH_SHADE: f32 = 0.06;
+/// Brightness of a column higher than the one north of it, and of a lower one
+const SLOPE_SHADES: (f32, f32) = (1.12, 0.85);
+
+/// Render of the world as RGBA pixels, `x` growing to the right and `z` downwards
+pub struct WorldMapRender {
+    pub size: u32,
+    pub pixels: Vec<u8>,
+    /// Columns with a generated chunk
+    pub explored: usize,
+}
+
+/// Color of a block seen from above
+fn map_color(block: BlockId) -> [u8; 3] {
+    match block {
+        BlockId::Grass | BlockId::TallGrass => [96, 160, 56],
+        BlockId::SwampGrass | BlockId::Vine | BlockId::Kelp => [76, 104, 52],
+        BlockId::OakLeaves | BlockId::BirchLeaves | BlockId::JungleLeaves => [52, 120, 36],
+        BlockId::SpruceLeaves => [48, 92, 52],
+        BlockId::Dirt | BlockId::Farmland => [134, 96, 67],
+        BlockId::Mud => [84, 66, 54],
+        BlockId::Mycelium => [112, 96, 112],
+        BlockId::Sand => [218, 206, 160],
+        BlockId::SoulSand => [84, 64, 52],
+        BlockId::Gravel => [136, 126, 124],
+        BlockId::Clay => [160, 166, 178],
+        BlockId::Stone | BlockId::Cobblestone | BlockId::Waystone | BlockId::Debug => {
+            [124, 124, 124]
+        }
+        BlockId::MossyCobblestone => [100, 124, 92],
+        BlockId::Deepslate => [80, 80, 86],
+        BlockId::Bedrock => [60, 60, 60],
+        BlockId::Obsidian => [36, 24, 52],
+        BlockId::CoalOre => [104, 104, 104],
+        BlockId::IronOre => [150, 130, 118],
+        BlockId::GoldOre => [176, 160, 92],
+        BlockId::DiamondOre => [110, 170, 168],
+        BlockId::Snow | BlockId::SnowLayer => [248, 250, 252],
+        BlockId::Ice => [160, 190, 250],
+        BlockId::Water | BlockId::Puddle => [60, 90, 220],
+        BlockId::Lava | BlockId::Magma => [220, 100, 20],
+        BlockId::Netherrack => [112, 44, 44],
+        BlockId::Portal => [120, 40, 200],
+        BlockId::Cactus => [40, 120, 40],
+        BlockId::OakLog
+        | BlockId::OakPlanks
+        | BlockId::OakFence
+        | BlockId::ItemFrame
+        | BlockId::JungleLog => [144, 112, 70],
+        BlockId::SpruceLog => [96, 72, 48],
+        BlockId::BirchLog => [210, 206, 190],
+        BlockId::MushroomStem => [200, 190, 170],
+        BlockId::RedMushroomBlock | BlockId::Poppy | BlockId::Bed => [176, 40, 36],
+        BlockId::Dandelion => [230, 210, 50],
+        BlockId::Coral => [220, 90, 140],
+        BlockId::Glass => [200, 220, 230],
+    }
+}
+
+/// Renders the square of columns within `radius` blocks of `center`
+pub fn render_world_map(
+    chunks: &ServerChunkWorldMap,
+    center: IVec2,
+    radius: i32,
+) -> WorldMapRender {
+    let radius = radius.clamp(0, MAX_MAP_RADIUS);
+    let size = 2 * radius + 1;
+    let min = center - IVec2::splat(radius);
+    let max = center + IVec2::splat(radius);
+
+    // Topmost block of each column, found chunk by chunk
+    let mut tops: Vec<Option<(i32, BlockId)>> = vec![None; (size * size) as usize];
+    let mut explored = vec![false; (size * size) as usize];
+    for (chunk_pos, chunk) in chunks.map.iter() {
+        let base = *chunk_pos * CHUNK_SIZE;
+        let chunk_min = IVec2::new(base.x, base.z);
+        let chunk_max = chunk_min + IVec2::splat(CHUNK_SIZE - 1);
+        if chunk_max.cmplt(min).any() || chunk_min.cmpgt(max).any() {
+            continue;
+        }
+        for x in chunk_min.x.max(min.x)..=chunk_max.x.min(max.x) {
+            for z in chunk_min.y.max(min.y)..=chunk_max.y.min(max.y) {
+                explored[((x - min.x) * size + (z - min.y)) as usize] = true;
+            }
+        }
+        for (local_pos, block) in chunk.map.iter() {
+            let (x, z) = (base.x + local_pos.x, base.z + local_pos.z);
+            if x < min.x || x > max.x || z < min.y || z > max.y {
+                continue;
+            }
+            let y = base.y + local_pos.y;
+            let top = &mut tops[((x - min.x) * size + (z - min.y)) as usize];
+            if top.is_none_or(|(top_y, _)| y > top_y) {
+                *top = Some((y, block.id));
+            }
+        }
+    }
+
+    let mut pixels = vec![0; (size * size * 4) as usize];
+    for x in 0..size {
+        for z in 0..size {
+            let index = (x * size + z) as usize;
+            let Some((y, block)) = tops[index] else {
+                continue;
+            };
+
+            let mut shade = 1.0;
+            if block == BlockId::Water {
+                let depth = (1..=MAX_SHADED_DEPTH)
+                    .take_while(|dy| {
+                        chunks
+                            .get_block_by_coordinates(&IVec3::new(min.x + x, y - dy, min.y + z))
+                            .is_some_and(|below| below.id == BlockId::Water)
+                    })
+                    .count();
+                shade -= depth as f32 * DEPTH_SHADE;
+            } else if let Some(Some((north_y, _))) = (z > 0).then(|| tops[index - 1]) {
+                if y > north_y {
+                    shade = SLOPE_SHADES.0;
+                } else if y < north_y {
+                    shade = SLOPE_SHADES.1;
+                }
+            }
+
+            let pixel = ((z * size + x) * 4) as usize;
+            for (channel, value) in map_color(block).into_iter().enumerate() {
+                pixels[pixel + channel] = (value as f32 * shade).clamp(0.0, 255.0) as u8;
+            }
+            pixels[pixel + 3] = 255;
+        }
+    }
+
+    WorldMapRender {
+        size: size as u32,
+        pixels,
+        explored: explored.iter().filter(|explored| **explored).count(),
+    }
+}
+
+impl WorldMapRender {
+    /// Writes the render to a PNG file
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.size, self.size);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(io::Error::other)
+    }
+}
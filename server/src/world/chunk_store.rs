@@ -0,0 +1,219 @@
+//! Chunks are saved one per file in the `chunks` folder of the world, so that a
+//! damaged file only loses its own chunk.
+//!
+//! Each file starts with a checksum of the rest of its content. Files that fail
+//! the check or don't deserialize are moved to the `quarantine` folder of their
+//! dimension, and their chunk is left empty rather than generated again, in every
+//! dimension: an operator has to confirm the regeneration with
+//! `regenerate <x> <y> <z> [dimension]` or `regenerate all` on the server
+//! console, or `/regenerate` in the chat.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy_log::{error, info, warn};
+use shared::world::{DimensionId, ServerChunk, ServerChunkWorldMap};
+
+use crate::world::save::write_atomically;
+
+pub const CHUNKS_FOLDER: &str = "chunks";
+pub const QUARANTINE_FOLDER: &str = "quarantine";
+
+const CHUNK_FILE_EXTENSION: &str = "bin";
+const CHECKSUM_SIZE: usize = std::mem::size_of::<u64>();
+
+/// Chunks whose save was damaged, which won't be generated again until an
+/// operator asks for it
+#[derive(Resource, Default, Debug)]
+pub struct CorruptChunks(pub HashSet<(DimensionId, IVec3)>);
+
+/// FNV-1a hash of the chunk data
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn chunk_file_name(position: IVec3) -> String {
+    format!(
+        "{}_{}_{}.{}",
+        position.x, position.y, position.z, CHUNK_FILE_EXTENSION
+    )
+}
+
+fn parse_chunk_file_name(name: &str) -> Option<IVec3> {
+    let stem = name.strip_suffix(CHUNK_FILE_EXTENSION)?.strip_suffix('.')?;
+    let mut coordinates = stem.split('_').map(|coordinate| coordinate.parse().ok());
+    let position = IVec3::new(
+        coordinates.next()??,
+        coordinates.next()??,
+        coordinates.next()??,
+    );
+    coordinates.next().is_none().then_some(position)
+}
+
+fn encode_chunk(chunk: &ServerChunk) -> Result<Vec<u8>, bincode::Error> {
+    let data = bincode::serialize(chunk)?;
+    let mut bytes = checksum(&data).to_le_bytes().to_vec();
+    bytes.extend(data);
+    Ok(bytes)
+}
+
+fn decode_chunk(bytes: &[u8]) -> Result<ServerChunk, String> {
+    if bytes.len() < CHECKSUM_SIZE {
+        return Err("file is truncated".into());
+    }
+    let (expected, data) = bytes.split_at(CHECKSUM_SIZE);
+    let expected = u64::from_le_bytes(expected.try_into().unwrap());
+    if checksum(data) != expected {
+        return Err("checksum mismatch".into());
+    }
+    bincode::deserialize(data).map_err(|err| err.to_string())
+}
+
+/// Timestamps of chunks just read from disk, which don't need to be written
+/// again until they are updated
+pub fn saved_timestamps(chunks: &HashMap<IVec3, ServerChunk>) -> HashMap<IVec3, u64> {
+    chunks
+        .iter()
+        .map(|(position, chunk)| (*position, chunk.ts))
+        .collect()
+}
+
+/// Writes the chunks updated since their last save to their own file, and
+/// returns how many were written.
+///
+/// Chunks are stamped with their last update when they are sent to the
+/// players again, the ones still waiting to be sent count as updated too.
+pub fn save_chunks(world_folder: &Path, chunks: &mut ServerChunkWorldMap) -> io::Result<usize> {
+    let folder = world_folder.join(CHUNKS_FOLDER);
+    fs::create_dir_all(&folder)?;
+    let waiting: HashSet<IVec3> = chunks.chunks_to_update.iter().copied().collect();
+    let mut written = 0;
+    for (position, chunk) in chunks.map.iter() {
+        if chunks.saved_ts.get(position) == Some(&chunk.ts) && !waiting.contains(position) {
+            continue;
+        }
+        let bytes = encode_chunk(chunk).map_err(io::Error::other)?;
+        write_atomically(&folder.join(chunk_file_name(*position)), &bytes)?;
+        chunks.saved_ts.insert(*position, chunk.ts);
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Takes a chunk out of memory, writing it first if it was updated since its
+/// last save. Its timestamp stays in `saved_ts`, telling that the chunk is to
+/// be read back from its file rather than generated.
+pub fn unload_chunk(
+    world_folder: &Path,
+    chunks: &mut ServerChunkWorldMap,
+    position: IVec3,
+) -> io::Result<()> {
+    let Some(chunk) = chunks.map.get(&position) else {
+        return Ok(());
+    };
+    if chunks.saved_ts.get(&position) != Some(&chunk.ts) {
+        let folder = world_folder.join(CHUNKS_FOLDER);
+        fs::create_dir_all(&folder)?;
+        let bytes = encode_chunk(chunk).map_err(io::Error::other)?;
+        write_atomically(&folder.join(chunk_file_name(position)), &bytes)?;
+        chunks.saved_ts.insert(position, chunk.ts);
+    }
+    chunks.map.remove(&position);
+    Ok(())
+}
+
+/// Folder of the world holding the chunks of a dimension
+pub fn dimension_folder(world_folder: &Path, dimension: DimensionId) -> PathBuf {
+    match dimension.save_folder() {
+        Some(folder) => world_folder.join(folder),
+        None => world_folder.to_path_buf(),
+    }
+}
+
+/// Moves a damaged chunk file out of the way, keeping it for inspection
+fn quarantine(world_folder: &Path, file: &Path) -> io::Result<()> {
+    let folder = world_folder.join(QUARANTINE_FOLDER);
+    fs::create_dir_all(&folder)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    fs::rename(file, folder.join(format!("{name}.{timestamp}")))
+}
+
+/// Reads a chunk file, quarantining it if it is damaged
+fn read_chunk_file(world_folder: &Path, path: &Path, position: IVec3) -> Option<ServerChunk> {
+    let result = fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| decode_chunk(&bytes));
+    match result {
+        Ok(chunk) => Some(chunk),
+        Err(err) => {
+            error!("Chunk {:?} is corrupt ({}), quarantining it", position, err);
+            if let Err(err) = quarantine(world_folder, path) {
+                error!("Could not quarantine {} : {}", path.display(), err);
+            }
+            None
+        }
+    }
+}
+
+/// Reads back a chunk unloaded by the cap of loaded chunks, see `unload_chunk`.
+/// A damaged file is quarantined and gives no chunk.
+pub fn load_chunk(world_folder: &Path, position: IVec3) -> Option<ServerChunk> {
+    let path = world_folder
+        .join(CHUNKS_FOLDER)
+        .join(chunk_file_name(position));
+    read_chunk_file(world_folder, &path, position)
+}
+
+/// Reads the chunks of a dimension saved in the world folder. Damaged chunks are
+/// quarantined and returned apart.
+pub fn load_chunks(
+    world_folder: &Path,
+    dimension: DimensionId,
+) -> (HashMap<IVec3, ServerChunk>, CorruptChunks) {
+    let world_folder = &dimension_folder(world_folder, dimension);
+    let mut chunks = HashMap::new();
+    let mut corrupt = CorruptChunks::default();
+
+    let Ok(entries) = fs::read_dir(world_folder.join(CHUNKS_FOLDER)) else {
+        return (chunks, corrupt);
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Some(position) = entry.file_name().to_str().and_then(parse_chunk_file_name) else {
+            continue;
+        };
+
+        match read_chunk_file(world_folder, &path, position) {
+            Some(chunk) => {
+                chunks.insert(position, chunk);
+            }
+            None => {
+                corrupt.0.insert((dimension, position));
+            }
+        }
+    }
+
+    info!(
+        "Loaded {} chunks from {}",
+        chunks.len(),
+        world_folder.display()
+    );
+    if !corrupt.0.is_empty() {
+        warn!(
+            "{} corrupt chunks were left empty, use `regenerate all` on the console to generate them again",
+            corrupt.0.len()
+        );
+    }
+    (chunks, corrupt)
+}
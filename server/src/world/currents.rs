@@ -0,0 +1,85 @@
+//! Currents of flowing water pushing mobs and dropped items downstream.
+//!
+//! The current is added to the velocity of the mobs, which then drift and slow
+//! down like after a knockback. Swimming mobs fight their way against it, and
+//! leashed mobs follow their lead. Dropped items are carried at the speed of the
+//! current, as long as nothing solid is in their way. Bubble columns carry them
+//! up or down in the same way, elsewhere they float up to the waves and ride
+//! them. Players drift in their own movement simulation, which clients predict.
+
+use bevy::prelude::*;
+use shared::physics::water::apply_bubble_column;
+use shared::water::{
+    bubble_column, water_cell_volume, water_current, water_surface_height, WATER_CURRENT_SPEED,
+    WATER_SURFACE_OFFSET, WAVE_AMPLITUDE,
+};
+use shared::world::{ServerWorldMap, WorldMap};
+
+/// Velocity the strongest current adds to a mob every second
+const MOB_CURRENT_ACCELERATION: f32 = 4.0;
+/// Speed at which dropped items rise to the surface of the water
+const ITEM_FLOAT_SPEED: f32 = 1.0;
+
+/// Displaced surface of the water `pos` is in, or is lifted above by a wave.
+/// Water under a ceiling has no waves, so that items don't float into it.
+fn floating_surface(world_map: &impl WorldMap, pos: Vec3, time: f32) -> Option<f32> {
+    let is_water =
+        |cell: IVec3| water_cell_volume(world_map, cell).is_some_and(|volume| volume > 0.0);
+    let mut cell = pos.floor().as_ivec3();
+    if !is_water(cell) {
+        cell -= IVec3::Y;
+        if !is_water(cell) {
+            return None;
+        }
+    }
+    let top = (cell.y..).find(|y| !is_water(IVec3::new(cell.x, y + 1, cell.z)))?;
+    if water_cell_volume(world_map, IVec3::new(cell.x, top + 1, cell.z)).is_none() {
+        return Some(top as f32 + WATER_SURFACE_OFFSET);
+    }
+    Some(water_surface_height(top, pos.xz(), time, WAVE_AMPLITUDE))
+}
+
+pub fn water_currents_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Time<Fixed>>) {
+    let time = delta.elapsed_secs_wrapped();
+    let delta = delta.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+
+    let ServerWorldMap {
+        chunks,
+        mobs,
+        item_stacks,
+        ..
+    } = &mut *world_map;
+
+    for mob in mobs.values_mut() {
+        if mob.leash.is_some() || mob.kind.is_aquatic() {
+            continue;
+        }
+        let flow = water_current(chunks, mob.position);
+        mob.velocity += flow * MOB_CURRENT_ACCELERATION * delta;
+        apply_bubble_column(&mut mob.velocity, mob.position, chunks, delta);
+    }
+
+    for stack in item_stacks.iter_mut().filter(|stack| !stack.despawned) {
+        let mut flow = water_current(chunks, stack.pos);
+        let column = bubble_column(stack.pos.floor().as_ivec3(), |cell| {
+            chunks.get_block_by_coordinates(&cell).map(|block| block.id)
+        });
+        if let Some(column) = column {
+            flow.y = column.direction();
+        }
+        if flow != Vec3::ZERO {
+            let next = stack.pos + flow * WATER_CURRENT_SPEED * delta;
+            if water_cell_volume(chunks, next.floor().as_ivec3()).is_some() {
+                stack.pos = next;
+            }
+        }
+        if column.is_none() {
+            if let Some(surface) = floating_surface(chunks, stack.pos, time) {
+                stack.pos.y = (stack.pos.y + ITEM_FLOAT_SPEED * delta).min(surface);
+            }
+        }
+    }
+}
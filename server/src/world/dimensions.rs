@@ -0,0 +1,367 @@
+//! Travel between dimensions through portals, see `shared::world::dimensions`.
+//!
+//! Clients ask to go through a portal when their player walks into one, and the
+//! server checks that the player is really in one and that their cooldown is over.
+//! The chunks around the arrival are generated right away so that the player can
+//! be put on solid ground, or in a small room carved for them, and a portal is
+//! built next to the arrival when there is none around to go back with.
+
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::RenetServer;
+use shared::messages::{DimensionChange, PlayerId, ServerToClientMessage, UiOverlay};
+use shared::players::Player;
+use shared::world::{
+    block_to_chunk_coord, dimension_chunks_mut, is_in_portal, BlockData, BlockDirection,
+    BlockHitbox, BlockId, BlockTransparency, DimensionId, GenerationConfig, NetherNoise,
+    ServerChunk, ServerChunkWorldMap, ServerWorldMap, TerrainNoise, WorldMap, NETHER_CEILING,
+    PORTAL_COOLDOWN_TICKS,
+};
+use shared::{CHUNK_SIZE, SEA_LEVEL};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::background_generation::{
+    integrate_generated_chunk, integrate_loaded_chunk, ChunkGenerationTasks,
+};
+use crate::world::boats::stow_boat;
+use crate::world::chunk_store::{dimension_folder, load_chunk, CorruptChunks};
+use crate::world::generation::{generate_chunk, ChunkGenerationResult};
+
+/// Players may be a little ahead of the server when they ask to go through a portal
+const PORTAL_MARGIN: f32 = 0.5;
+/// Blocks above and below the arrival searched for ground to stand on
+const ARRIVAL_SEARCH_HEIGHT: i32 = 24;
+/// No portal is built at the arrival when there is already one this close
+const PORTAL_SEARCH_RADIUS: i32 = 8;
+/// Blocks between the arrival and the portal built next to it, so that arriving
+/// players don't walk right back into it
+const RETURN_PORTAL_OFFSET: i32 = 2;
+/// Time the name of the dimension stays on screen after arriving
+const DIMENSION_TITLE_SECS: f32 = 3.0;
+
+/// Tick of the last trip of each player through a portal
+#[derive(Resource, Default, Debug)]
+pub struct PortalCooldowns(pub HashMap<PlayerId, u64>);
+
+/// Generates a chunk of the nether, which has no flora nor structures
+pub fn generate_nether_chunk(chunk_pos: IVec3, seed: u32) -> ChunkGenerationResult {
+    let nether = NetherNoise::new(seed);
+    let mut chunk = ServerChunk {
+        map: HashMap::new(),
+        ts: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        sent_to_clients: Default::default(),
+    };
+
+    let origin = chunk_pos * CHUNK_SIZE;
+    if origin.y <= NETHER_CEILING && origin.y + CHUNK_SIZE > 0 {
+        for dx in 0..CHUNK_SIZE {
+            for dy in 0..CHUNK_SIZE {
+                for dz in 0..CHUNK_SIZE {
+                    let position = origin + IVec3::new(dx, dy, dz);
+                    if let Some(block) = nether.block_at(position.x, position.y, position.z) {
+                        chunk.map.insert(
+                            IVec3::new(dx, dy, dz),
+                            BlockData::new(block, BlockDirection::Front),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    ChunkGenerationResult {
+        chunk,
+        flora: Vec::new(),
+        structure_requests: Vec::new(),
+    }
+}
+
+/// Generates a chunk of the given dimension
+pub fn generate_dimension_chunk(
+    dimension: DimensionId,
+    chunk_pos: IVec3,
+    seed: u32,
+    config: GenerationConfig,
+) -> ChunkGenerationResult {
+    match dimension {
+        DimensionId::Overworld => generate_chunk(chunk_pos, seed, config),
+        DimensionId::Nether => generate_nether_chunk(chunk_pos, seed),
+    }
+}
+
+/// Generates the missing chunks between two corners right away, in place of their
+/// background generation if it was already started. Chunks unloaded by the cap
+/// of loaded chunks are read back from their file instead, and corrupt chunks
+/// are left empty.
+fn generate_now(
+    chunks: &mut ServerChunkWorldMap,
+    tasks: &mut ChunkGenerationTasks,
+    corrupt_chunks: &mut CorruptChunks,
+    world_folder: &Path,
+    dimension: DimensionId,
+    (min, max): (IVec3, IVec3),
+    seed: u32,
+    config: GenerationConfig,
+) {
+    let min = min.map(block_to_chunk_coord);
+    let max = max.map(block_to_chunk_coord);
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let chunk_pos = IVec3::new(x, y, z);
+                if chunks.map.contains_key(&chunk_pos)
+                    || corrupt_chunks.0.contains(&(dimension, chunk_pos))
+                {
+                    continue;
+                }
+                tasks.cancel(dimension, chunk_pos);
+                if chunks.saved_ts.contains_key(&chunk_pos) {
+                    let folder = dimension_folder(world_folder, dimension);
+                    match load_chunk(&folder, chunk_pos) {
+                        Some(chunk) => integrate_loaded_chunk(chunks, chunk_pos, chunk),
+                        None => {
+                            chunks.saved_ts.remove(&chunk_pos);
+                            corrupt_chunks.0.insert((dimension, chunk_pos));
+                        }
+                    }
+                    continue;
+                }
+                let result = generate_dimension_chunk(dimension, chunk_pos, seed, config);
+                integrate_generated_chunk(chunks, chunk_pos, result);
+            }
+        }
+    }
+}
+
+fn is_solid(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    chunks
+        .get_block_by_coordinates(&position)
+        .is_some_and(|block| matches!(block.get_collision_hitbox(), BlockHitbox::FullBlock))
+}
+
+/// Whether a player can stand in a block without colliding nor drowning
+fn is_clear(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    chunks
+        .get_block_by_coordinates(&position)
+        .is_none_or(|block| {
+            matches!(block.get_collision_hitbox(), BlockHitbox::None)
+                && block.id.get_visibility() != BlockTransparency::Liquid
+                && block.id != BlockId::Portal
+        })
+}
+
+fn can_stand(chunks: &ServerChunkWorldMap, feet: IVec3) -> bool {
+    is_solid(chunks, feet - IVec3::Y) && is_clear(chunks, feet) && is_clear(chunks, feet + IVec3::Y)
+}
+
+fn has_portal_near(chunks: &ServerChunkWorldMap, center: IVec3) -> bool {
+    let radius = PORTAL_SEARCH_RADIUS;
+    (-radius..=radius).any(|x| {
+        (-radius..=radius).any(|y| {
+            (-radius..=radius).any(|z| {
+                chunks
+                    .get_block_by_coordinates(&(center + IVec3::new(x, y, z)))
+                    .is_some_and(|block| block.id == BlockId::Portal)
+            })
+        })
+    })
+}
+
+/// Ground put under the carved arrivals and the portals built next to them
+fn ground_block(dimension: DimensionId) -> BlockId {
+    match dimension {
+        DimensionId::Overworld => BlockId::Cobblestone,
+        DimensionId::Nether => BlockId::Netherrack,
+    }
+}
+
+/// Makes room for a player at `feet` and a way to the portal built next to them
+fn carve_arrival(chunks: &mut ServerChunkWorldMap, dimension: DimensionId, feet: IVec3) {
+    for offset in 0..=RETURN_PORTAL_OFFSET {
+        let column = feet + IVec3::X * offset;
+        if !is_solid(chunks, column - IVec3::Y) {
+            chunks.set_block(
+                &(column - IVec3::Y),
+                BlockData::new(ground_block(dimension), BlockDirection::Front),
+            );
+        }
+        for height in 0..2 {
+            let position = column + IVec3::Y * height;
+            if !is_clear(chunks, position) {
+                chunks.remove_block_by_coordinates(&position);
+            }
+        }
+    }
+}
+
+/// Where a player going from `from` to `to` arrives, with the chunks around it
+/// generated and a portal next to it
+fn prepare_arrival(
+    chunks: &mut ServerChunkWorldMap,
+    tasks: &mut ChunkGenerationTasks,
+    corrupt_chunks: &mut CorruptChunks,
+    world_folder: &Path,
+    from: DimensionId,
+    to: DimensionId,
+    position: Vec3,
+    seed: u32,
+    config: GenerationConfig,
+) -> IVec3 {
+    let target = from.portal_arrival(position).floor().as_ivec3();
+    // The ground of the overworld is found from the terrain, the nether keeps the height
+    let center_y = match to {
+        DimensionId::Overworld => {
+            TerrainNoise::new(seed, config)
+                .height(target.x, target.z)
+                .max(SEA_LEVEL)
+                + 1
+        }
+        _ => target.y,
+    };
+    let center = IVec3::new(target.x, center_y, target.z);
+
+    let reach = IVec3::new(RETURN_PORTAL_OFFSET, 0, 0);
+    let search = IVec3::new(1, ARRIVAL_SEARCH_HEIGHT, 1);
+    generate_now(
+        chunks,
+        tasks,
+        corrupt_chunks,
+        world_folder,
+        to,
+        (center - search, center + search + reach),
+        seed,
+        config,
+    );
+
+    let feet = (0..=ARRIVAL_SEARCH_HEIGHT)
+        .flat_map(|dy| [center + IVec3::Y * dy, center - IVec3::Y * dy])
+        .find(|feet| can_stand(chunks, *feet))
+        .unwrap_or(center);
+
+    if !has_portal_near(chunks, feet) {
+        carve_arrival(chunks, to, feet);
+        let portal = feet + reach;
+        for height in 0..2 {
+            chunks.set_block(
+                &(portal + IVec3::Y * height),
+                BlockData::new(BlockId::Portal, BlockDirection::Front),
+            );
+        }
+    } else if !can_stand(chunks, feet) {
+        carve_arrival(chunks, to, feet);
+    }
+    feet
+}
+
+/// Moves a player to another dimension, `chunks` being the ones of the
+/// destination. They are all sent to the player again, who dropped them when
+/// leaving it.
+pub fn send_to_dimension(
+    server: &mut RenetServer,
+    player: &mut Player,
+    chunks: &mut ServerChunkWorldMap,
+    dimension: DimensionId,
+    position: Vec3,
+) {
+    for chunk in chunks.map.values_mut() {
+        chunk.sent_to_clients.remove(&player.id);
+    }
+    stow_boat(player);
+    player.dimension = dimension;
+    player.position = position;
+    player.velocity = Vec3::ZERO;
+    server.send_game_message(
+        player.id,
+        ServerToClientMessage::DimensionChange(DimensionChange {
+            dimension,
+            position,
+        }),
+    );
+    server.send_game_message(
+        player.id,
+        ServerToClientMessage::UiOverlay(UiOverlay::Title {
+            title: match dimension {
+                DimensionId::Overworld => "The Overworld".into(),
+                DimensionId::Nether => "The Nether".into(),
+            },
+            subtitle: String::new(),
+            duration_secs: DIMENSION_TITLE_SECS,
+        }),
+    );
+}
+
+/// Takes a player through the portal they are standing in, if their cooldown is over
+#[allow(clippy::too_many_arguments)]
+pub fn change_dimension(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    world_folder: &Path,
+    tasks: &mut ChunkGenerationTasks,
+    corrupt_chunks: &mut CorruptChunks,
+    cooldowns: &mut PortalCooldowns,
+    seed: u32,
+    config: GenerationConfig,
+    player_id: PlayerId,
+    tick: u64,
+) {
+    let ServerWorldMap {
+        players,
+        chunks,
+        dimensions,
+        ..
+    } = world_map;
+    let Some(player) = players.get_mut(&player_id) else {
+        return;
+    };
+
+    let from = player.dimension;
+    let in_portal = is_in_portal(
+        dimension_chunks_mut(chunks, dimensions, from),
+        player,
+        PORTAL_MARGIN,
+    );
+    if !in_portal {
+        warn!(
+            "Player {} tried to change dimension outside of a portal",
+            player.name
+        );
+        return;
+    }
+    if cooldowns
+        .0
+        .get(&player_id)
+        .is_some_and(|last| tick < last + PORTAL_COOLDOWN_TICKS)
+    {
+        return;
+    }
+
+    let to = from.portal_destination();
+    let destination = dimension_chunks_mut(chunks, dimensions, to);
+    let feet = prepare_arrival(
+        destination,
+        tasks,
+        corrupt_chunks,
+        world_folder,
+        from,
+        to,
+        player.position,
+        seed,
+        config,
+    );
+    let position = feet.as_vec3() + Vec3::new(0.5, player.height / 2.0, 0.5);
+
+    info!(
+        "Player {} went from the {} to the {} at {:?}",
+        player.name,
+        from.name(),
+        to.name(),
+        position
+    );
+    cooldowns.0.insert(player_id, tick);
+    send_to_dimension(server, player, destination, to, position);
+}
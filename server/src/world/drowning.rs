@@ -0,0 +1,38 @@
+//! Air and drowning of the players under water, see `shared::players::Breath`.
+//!
+//! The waves are sampled on the server clock: they have the same shape as the
+//! ones clients draw, but not the same phase.
+
+use bevy::prelude::*;
+use bevy_log::debug;
+use shared::physics::water::eyes_under_water;
+use shared::players::GameMode;
+use shared::water::WAVE_AMPLITUDE;
+use shared::world::ServerWorldMap;
+
+/// Spends the air of the players with their eyes under the waves, once per
+/// server tick. Creative players never run out of air.
+pub fn breathing_system(mut world_map: ResMut<ServerWorldMap>, time: Res<Time>) {
+    let time = time.elapsed_secs_wrapped();
+    let under_water: Vec<bool> = world_map
+        .players
+        .values()
+        .map(|player| {
+            player.game_mode != GameMode::Creative
+                && world_map
+                    .dimension_chunks(player.dimension)
+                    .is_some_and(|chunks| eyes_under_water(player, chunks, time, WAVE_AMPLITUDE))
+        })
+        .collect();
+
+    for (player, head_under_water) in world_map.players.values_mut().zip(under_water) {
+        let damage = player.breath.tick(head_under_water);
+        if damage > 0.0 {
+            player.health = (player.health - damage).max(0.0);
+            debug!(
+                "Player {} is drowning, {} health left",
+                player.id, player.health
+            );
+        }
+    }
+}
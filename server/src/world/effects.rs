@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy_log::{debug, info};
+use shared::messages::PlayerFrameInput;
+use shared::players::{GameMode, Player, MAX_PLAYER_HEALTH};
+use shared::world::{apply_effect_health_change, ItemType, ServerWorldMap, StatusEffect};
+
+/// Drinks the potion held by the player, applying its effect to them. Potions are
+/// used up, except by creative players.
+///
+/// Returns whether the click was used by a potion.
+pub fn use_potion(player: &mut Player, input: &PlayerFrameInput) -> bool {
+    let Some(stack) = player.inventory.inner.get(&input.hotbar_slot) else {
+        return false;
+    };
+    let ItemType::Potion(kind) = stack.item_type else {
+        return false;
+    };
+
+    if player.game_mode != GameMode::Creative {
+        player
+            .inventory
+            .remove_item_from_stack(input.hotbar_slot, 1);
+    }
+
+    info!("Player {} drank a potion of {}", player.id, kind.name());
+    player
+        .effects
+        .add(StatusEffect::new(kind, 0, kind.potion_duration_ticks()));
+    true
+}
+
+/// Ticks the status effects of every player and mob, once per server tick
+pub fn status_effects_system(mut world_map: ResMut<ServerWorldMap>) {
+    let world_map = world_map.as_mut();
+
+    for player in world_map.players.values_mut() {
+        if player.effects.is_empty() {
+            continue;
+        }
+        let change = player.effects.tick();
+        player.health = apply_effect_health_change(player.health, change, MAX_PLAYER_HEALTH);
+        if player.effects.is_empty() {
+            debug!("Effects of player {} wore off", player.id);
+        }
+    }
+
+    for mob in world_map.mobs.values_mut() {
+        if mob.effects.is_empty() {
+            continue;
+        }
+        let change = mob.effects.tick();
+        let max_health = mob.kind.max_health();
+        mob.damage_taken =
+            max_health - apply_effect_health_change(mob.health(), change, max_health);
+    }
+}
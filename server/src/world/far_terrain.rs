@@ -0,0 +1,110 @@
+//! Streaming of the far terrain summaries, see `shared::world::far_terrain`.
+//!
+//! Every player gets the tiles of the ring between the full chunks and the far
+//! terrain distance, nearest first and a few per tick. Tiles are read from the
+//! generation noise, so changes made by players far away don't show until their
+//! chunks are sent in full. The distance is set in the `[far_terrain]` table of
+//! `server.toml`, 0 disables the far terrain:
+//!
+//! ```toml
+//! [far_terrain]
+//! distance = 32
+//! ```
+
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::messages::{FarTerrainUpdate, PlayerId, ServerToClientMessage};
+use shared::world::{
+    is_far_terrain_column, world_position_to_chunk_position, ClimateNoises, DimensionId,
+    FarTerrainTile, GenerationConfig, ServerWorldMap, TerrainNoise, WorldSeed,
+};
+use shared::{GameServerConfig, LOD1_MULTIPLIER};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+/// Tiles sent to a player per tick
+const TILES_PER_UPDATE: usize = 16;
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct FarTerrainConfig {
+    /// Chunks up to which the far terrain reaches
+    pub distance: i32,
+}
+
+impl Default for FarTerrainConfig {
+    fn default() -> Self {
+        Self { distance: 32 }
+    }
+}
+
+pub fn send_far_terrain_system(
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+    server_config: Res<GameServerConfig>,
+    far_terrain: Res<FarTerrainConfig>,
+    seed: Res<WorldSeed>,
+    generation_config: Res<GenerationConfig>,
+    mut sent: Local<HashMap<PlayerId, HashSet<IVec2>>>,
+) {
+    // The far terrain only shows the overworld, players coming back get it again
+    sent.retain(|id, _| {
+        world_map
+            .players
+            .get(id)
+            .is_some_and(|player| player.dimension == DimensionId::Overworld)
+    });
+    // Same distance as the chunk broadcast, which includes the LOD 1 chunks
+    let inner_radius = (server_config.broadcast_render_distance as f32 * LOD1_MULTIPLIER) as i32;
+    let outer_radius = far_terrain.distance;
+    if outer_radius <= inner_radius {
+        return;
+    }
+
+    let mut noises: Option<(TerrainNoise, ClimateNoises)> = None;
+    for player in world_map.players_in(DimensionId::Overworld) {
+        let id = &player.id;
+        let chunk = world_position_to_chunk_position(player.position);
+        let center = IVec2::new(chunk.x, chunk.z);
+        let sent = sent.entry(*id).or_default();
+        // Tiles that left the ring are forgotten by the client too
+        sent.retain(|column| is_far_terrain_column(center, *column, inner_radius, outer_radius));
+
+        let mut missing: Vec<IVec2> = (-outer_radius..=outer_radius)
+            .flat_map(|x| (-outer_radius..=outer_radius).map(move |z| center + IVec2::new(x, z)))
+            .filter(|column| {
+                is_far_terrain_column(center, *column, inner_radius, outer_radius)
+                    && !sent.contains(column)
+            })
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        missing.sort_unstable_by_key(|column| (*column - center).length_squared());
+
+        let (terrain, climate) = noises.get_or_insert_with(|| {
+            (
+                TerrainNoise::new(seed.0, *generation_config),
+                ClimateNoises::new(seed.0),
+            )
+        });
+        let tiles = missing
+            .into_iter()
+            .take(TILES_PER_UPDATE)
+            .map(|column| {
+                sent.insert(column);
+                FarTerrainTile::generate(column, terrain, climate)
+            })
+            .collect();
+        server.send_game_message(
+            *id,
+            ServerToClientMessage::FarTerrain(FarTerrainUpdate {
+                inner_radius,
+                outer_radius,
+                tiles,
+            }),
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Fishing rods and their bobbers.
+//!
+//! The bobbers are simulated here, one per player at most, and sent to every
+//! client each tick so they can draw the line. A fish bites a random time after
+//! the bobber starts floating, and gets away if the player doesn't reel in within
+//! [`FISHING_BITE_WINDOW_SECS`].
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use rand::Rng;
+use shared::messages::{
+    FishingBobber, FishingUpdate, PlayerFrameInput, PlayerId, ServerToClientMessage,
+};
+use shared::players::Player;
+use shared::world::{
+    raycast, roll_fishing_loot, Bobber, BobberState, ItemId, ServerWorldMap,
+    FISHING_BITE_WAIT_SECS, FISHING_BITE_WINDOW_SECS, FISHING_LINE_MAX_LENGTH,
+};
+use shared::TICKS_PER_SECOND;
+
+use crate::init::ServerTime;
+use crate::network::extensions::SendGameMessageExtension;
+
+struct Line {
+    bobber: Bobber,
+    /// Hotbar slot of the rod, the line breaks when the player holds something else
+    hotbar_slot: u32,
+    /// Tick at which the next fish bites, once the bobber floats
+    bite_at: Option<u64>,
+}
+
+impl Line {
+    fn biting(&self, tick: u64) -> bool {
+        let window = (FISHING_BITE_WINDOW_SECS * TICKS_PER_SECOND as f32) as u64;
+        self.bite_at
+            .is_some_and(|bite_at| (bite_at..bite_at + window).contains(&tick))
+    }
+}
+
+/// Bobbers out on the water, by the player holding the rod
+#[derive(Resource, Default)]
+pub struct FishingLines(HashMap<PlayerId, Line>);
+
+impl FishingLines {
+    /// Breaks the line of a player who selected another hotbar slot
+    pub fn follow_hotbar(&mut self, player: PlayerId, hotbar_slot: u32) {
+        self.0
+            .retain(|id, line| *id != player || line.hotbar_slot == hotbar_slot);
+    }
+}
+
+fn holds_fishing_rod(player: &Player, hotbar_slot: u32) -> bool {
+    player
+        .inventory
+        .inner
+        .get(&hotbar_slot)
+        .is_some_and(|stack| stack.item_id == ItemId::FishingRod)
+}
+
+fn random_bite_tick(tick: u64) -> u64 {
+    let (min, max) = FISHING_BITE_WAIT_SECS;
+    let wait = rand::thread_rng().gen_range(min..max);
+    tick + (wait * TICKS_PER_SECOND as f32) as u64
+}
+
+/// Casts a bobber with the held fishing rod, or reels in the one already out,
+/// catching something if a fish is biting.
+///
+/// Returns whether the click was used by a fishing rod.
+pub fn use_fishing_rod(
+    server: &mut RenetServer,
+    player: &mut Player,
+    lines: &mut FishingLines,
+    input: &PlayerFrameInput,
+    tick: u64,
+) -> bool {
+    if !holds_fishing_rod(player, input.hotbar_slot) {
+        return false;
+    }
+
+    if let Some(line) = lines.0.remove(&player.id) {
+        if line.biting(tick) {
+            let loot = roll_fishing_loot(rand::random());
+            info!("Player {} caught {:?}", player.id, loot);
+            player.inventory.add_item_to_inventory(loot);
+        }
+        server.broadcast_game_message(ServerToClientMessage::Fishing(FishingUpdate {
+            player: player.id,
+            bobber: None,
+        }));
+        return true;
+    }
+
+    let (origin, direction) = raycast::camera_ray(&input.camera, &player.position, input.view_mode);
+    lines.0.insert(
+        player.id,
+        Line {
+            bobber: Bobber::cast(origin + direction * 0.5, direction),
+            hotbar_slot: input.hotbar_slot,
+            bite_at: None,
+        },
+    );
+    true
+}
+
+/// Moves the bobbers, makes the fish bite, breaks the lines of the players who
+/// went too far or put their rod away, and sends all the bobbers
+pub fn fishing_system(
+    mut lines: ResMut<FishingLines>,
+    world_map: Res<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    time: Res<ServerTime>,
+) {
+    let tick = time.0;
+    let delta = 1.0 / TICKS_PER_SECOND as f32;
+
+    lines.0.retain(|id, line| {
+        let kept = world_map.players.get(id).is_some_and(|player| {
+            holds_fishing_rod(player, line.hotbar_slot)
+                && line.bobber.position.distance(player.position) <= FISHING_LINE_MAX_LENGTH
+        });
+        if !kept {
+            server.broadcast_game_message(ServerToClientMessage::Fishing(FishingUpdate {
+                player: *id,
+                bobber: None,
+            }));
+            return false;
+        }
+
+        line.bobber.step(&world_map.chunks, delta);
+        match line.bobber.state {
+            BobberState::Floating { .. } => {
+                let bite_at = *line.bite_at.get_or_insert_with(|| random_bite_tick(tick));
+                if !line.biting(tick) && tick >= bite_at {
+                    // The fish got away, another one comes later
+                    line.bite_at = Some(random_bite_tick(tick));
+                }
+            }
+            BobberState::Flying | BobberState::Landed => line.bite_at = None,
+        }
+
+        server.broadcast_game_message(ServerToClientMessage::Fishing(FishingUpdate {
+            player: *id,
+            bobber: Some(FishingBobber {
+                position: line.bobber.position,
+                state: line.bobber.state,
+                biting: line.biting(tick),
+            }),
+        }));
+        true
+    });
+}
@@ -0,0 +1,122 @@
+//! Server side of the opt-in fluid particle mode (`--fluid-particles`).
+//!
+//! Bulk water stays on the block grid. Particles are spawned under waterfalls
+//! (water blocks with nothing below them, in a loaded chunk) near players and
+//! where water buckets are poured, simulated every tick, and their positions are
+//! sent to nearby clients.
+
+use crate::network::extensions::SendGameMessageExtension;
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::fluid::{FluidParticles, FluidParticlesUpdate};
+use shared::messages::{PlayerId, ServerToClientMessage};
+use shared::world::{
+    global_to_chunk_local, to_global_pos, world_position_to_chunk_position, BlockId, DimensionId,
+    ServerWorldMap, WorldMap,
+};
+use shared::{GameServerConfig, CHUNK_SIZE};
+use std::collections::HashSet;
+
+/// Waterfalls emit particles every this many ticks
+const WATERFALL_SPAWN_INTERVAL_TICKS: u32 = 5;
+/// Particles emitted by a single waterfall block each time
+const WATERFALL_PARTICLES_PER_BLOCK: usize = 2;
+/// Chunks around a player that are scanned for waterfalls
+const WATERFALL_SCAN_RADIUS_CHUNKS: i32 = 1;
+/// Particles further than this from a player are not sent to them
+const PARTICLE_BROADCAST_RADIUS: f32 = (CHUNK_SIZE * 3) as f32;
+
+pub fn fluid_particles_enabled(config: Res<GameServerConfig>) -> bool {
+    config.fluid_particles
+}
+
+pub fn spawn_waterfall_particles_system(
+    world_map: Res<ServerWorldMap>,
+    mut particles: ResMut<FluidParticles>,
+    mut tick_counter: Local<u32>,
+) {
+    *tick_counter += 1;
+    if *tick_counter < WATERFALL_SPAWN_INTERVAL_TICKS {
+        return;
+    }
+    *tick_counter = 0;
+
+    let mut scanned_chunks = HashSet::new();
+
+    for player in world_map.players_in(DimensionId::Overworld) {
+        let player_chunk = world_position_to_chunk_position(player.position);
+
+        for dx in -WATERFALL_SCAN_RADIUS_CHUNKS..=WATERFALL_SCAN_RADIUS_CHUNKS {
+            for dy in -WATERFALL_SCAN_RADIUS_CHUNKS..=WATERFALL_SCAN_RADIUS_CHUNKS {
+                for dz in -WATERFALL_SCAN_RADIUS_CHUNKS..=WATERFALL_SCAN_RADIUS_CHUNKS {
+                    let chunk_pos = player_chunk + IVec3::new(dx, dy, dz);
+                    if !scanned_chunks.insert(chunk_pos) {
+                        continue;
+                    }
+
+                    let Some(chunk) = world_map.chunks.map.get(&chunk_pos) else {
+                        continue;
+                    };
+
+                    for (local_pos, block) in chunk.map.iter() {
+                        if block.id != BlockId::Water {
+                            continue;
+                        }
+                        let global_pos = to_global_pos(&chunk_pos, local_pos);
+                        let below = global_pos - IVec3::Y;
+                        // Water at the bottom of a chunk may rest on one that isn't loaded yet
+                        if !world_map.chunks.has_chunk(&global_to_chunk_local(&below).0)
+                            || world_map.chunks.get_block_by_coordinates(&below).is_some()
+                        {
+                            continue;
+                        }
+                        particles.spawn_burst(
+                            global_pos.as_vec3() + Vec3::new(0.5, 0.0, 0.5),
+                            Vec3::ZERO,
+                            WATERFALL_PARTICLES_PER_BLOCK,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn simulate_fluid_particles_system(
+    world_map: Res<ServerWorldMap>,
+    mut particles: ResMut<FluidParticles>,
+    time: Res<Time>,
+) {
+    if particles.particles.is_empty() {
+        return;
+    }
+    particles.step(&world_map.chunks, time.delta_secs());
+}
+
+pub fn broadcast_fluid_particles_system(
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+    particles: Res<FluidParticles>,
+    mut players_with_particles: Local<HashSet<PlayerId>>,
+) {
+    for client in server.clients_id() {
+        let Some(player) = world_map.players.get(&client) else {
+            continue;
+        };
+
+        let positions = particles.positions_near(player.position, PARTICLE_BROADCAST_RADIUS);
+
+        // Send one last empty update so the client clears its particles
+        if positions.is_empty() && !players_with_particles.remove(&client) {
+            continue;
+        }
+        if !positions.is_empty() {
+            players_with_particles.insert(client);
+        }
+
+        server.send_game_message(
+            client,
+            ServerToClientMessage::FluidParticles(FluidParticlesUpdate { positions }),
+        );
+    }
+}
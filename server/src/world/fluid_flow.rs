@@ -0,0 +1,314 @@
+//! Flow of water and lava over the block grid.
+//!
+//! Fluids only move when a block next to them changes: every block set or
+//! removed through `ServerChunkWorldMap` wakes the fluids around it up, and they
+//! then move one block per step, a step every `FluidKind::flow_interval_ticks`.
+//! How they move depends on the `FluidMode` of the server:
+//! - **Finite**: a fluid block falls into the open block below it, or moves
+//!   sideways off a ledge. The flow never creates fluid, so a lake drained into
+//!   a pit is gone, and spilled water ends up in a thin layer. Small bodies of
+//!   water left once it settles, holding `GameServerConfig::evaporation_volume`
+//!   or less, evaporate after `EVAPORATION_DELAY_TICKS`.
+//! - **Classic**: a fluid falls into the open block below it, and spreads sideways
+//!   over ground up to `FluidKind::max_spread` blocks from its source or from
+//!   where it fell. The `level` of a flowing block is that distance, 0 being a
+//!   source, and a flowing block that lost what fed it dries up.
+//!
+//! In both modes, where water meets lava, the lava hardens: a lava source into
+//! obsidian, and flowing lava into stone.
+//!
+//! Generated water and lava are all sources, and stay still until something next
+//! to them changes. Fluids still waiting for their next step when the server
+//! stops stay where they are.
+
+use bevy::prelude::*;
+use shared::fluid::{FluidKind, FluidMode};
+use shared::world::{
+    global_to_chunk_local, BlockData, BlockDirection, BlockId, DimensionId, ServerChunkWorldMap,
+    ServerWorldMap, WorldMap,
+};
+use shared::{GameServerConfig, TICKS_PER_SECOND};
+use std::collections::{HashMap, HashSet};
+
+/// Fluid blocks moved in a tick at most, the others waiting for the next one
+const MAX_FLOW_UPDATES_PER_TICK: usize = 4096;
+/// Time a small body of water stays still before it evaporates
+const EVAPORATION_DELAY_TICKS: u64 = 60 * TICKS_PER_SECOND;
+
+const HORIZONTAL_NEIGHBOURS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+#[derive(Resource, Default)]
+pub struct FluidFlow {
+    tick: u64,
+    /// Blocks woken up by an edit next to them, per dimension, which move on the
+    /// next step of their fluid
+    pending: HashMap<DimensionId, HashSet<IVec3>>,
+    /// Water blocks of small settled bodies, per dimension, and the tick they
+    /// evaporate at
+    evaporating: HashMap<DimensionId, HashMap<IVec3, u64>>,
+}
+
+pub fn fluid_flow_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut flow: ResMut<FluidFlow>,
+    config: Res<GameServerConfig>,
+) {
+    flow.tick += 1;
+    let FluidFlow {
+        tick,
+        pending,
+        evaporating,
+    } = &mut *flow;
+    let tick = *tick;
+    let mut budget = MAX_FLOW_UPDATES_PER_TICK;
+
+    let dimensions: Vec<DimensionId> = std::iter::once(DimensionId::Overworld)
+        .chain(world_map.dimensions.keys().copied())
+        .collect();
+    for dimension in dimensions {
+        let chunks = world_map.dimension_chunks_mut(dimension);
+        let pending = pending.entry(dimension).or_default();
+        let evaporating = evaporating.entry(dimension).or_default();
+        for position in chunks.block_edits.drain(..) {
+            pending.insert(position);
+            pending.extend(NEIGHBOURS.map(|offset| position + offset));
+        }
+
+        let mut due = Vec::new();
+        pending.retain(|position| {
+            let Some((kind, _)) = fluid_at(chunks, *position) else {
+                return false;
+            };
+            if !tick.is_multiple_of(kind.flow_interval_ticks()) || due.len() >= budget {
+                return true;
+            }
+            due.push(*position);
+            false
+        });
+        budget -= due.len();
+
+        for position in due {
+            // An earlier step of this tick may have changed the block
+            if let Some((kind, level)) = fluid_at(chunks, position) {
+                if harden_where_fluids_meet(chunks, position, kind, level) {
+                    continue;
+                }
+                match config.fluid_mode {
+                    FluidMode::Finite => {
+                        let moved = finite_step(chunks, position, kind, level);
+                        if !moved && kind == FluidKind::Water {
+                            let body =
+                                small_water_body(chunks, position, config.evaporation_volume);
+                            for cell in body.into_iter().flatten() {
+                                evaporating
+                                    .entry(cell)
+                                    .or_insert(tick + EVAPORATION_DELAY_TICKS);
+                            }
+                        }
+                    }
+                    FluidMode::Classic => classic_step(chunks, position, kind, level),
+                }
+            }
+        }
+
+        evaporating.retain(|position, evaporates_at| {
+            if *evaporates_at > tick {
+                return true;
+            }
+            // Water poured next to it since may have made the body too big
+            if small_water_body(chunks, *position, config.evaporation_volume).is_some() {
+                chunks.remove_block_by_coordinates(position);
+            }
+            false
+        });
+    }
+}
+
+/// Fluid flowing at `position`, and its level
+fn fluid_at(chunks: &ServerChunkWorldMap, position: IVec3) -> Option<(FluidKind, u8)> {
+    let block = chunks.get_block_by_coordinates(&position)?;
+    FluidKind::flowing_as(block.id).map(|kind| (kind, block.level))
+}
+
+/// Whether a fluid can flow into `position`. Fluids never flow into chunks that
+/// aren't loaded.
+fn is_open(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    let (chunk_pos, _) = global_to_chunk_local(&position);
+    chunks.map.contains_key(&chunk_pos)
+        && chunks
+            .get_block_by_coordinates(&position)
+            .is_none_or(|block| block.id.is_replaceable())
+}
+
+fn fluid_block(kind: FluidKind, level: u8) -> BlockData {
+    BlockData {
+        level,
+        ..BlockData::new(kind.block(), BlockDirection::Front)
+    }
+}
+
+/// Hardens the lava at `position`, touched by water
+fn harden_lava(chunks: &mut ServerChunkWorldMap, position: IVec3, level: u8) {
+    let hardened = if level == 0 {
+        BlockId::Obsidian
+    } else {
+        BlockId::Stone
+    };
+    chunks.set_block(&position, BlockData::new(hardened, BlockDirection::Front));
+}
+
+/// Level the block at `position` gets from the fluid feeding it: 1 below a
+/// falling fluid, or one more than its lowest neighbour. Only neighbours with a
+/// lower level feed a block, so that flowing blocks can't keep each other alive.
+fn fed_level(
+    chunks: &ServerChunkWorldMap,
+    position: IVec3,
+    kind: FluidKind,
+    level: u8,
+) -> Option<u8> {
+    if fluid_at(chunks, position + IVec3::Y).is_some_and(|(above, _)| above == kind) {
+        return Some(1);
+    }
+    HORIZONTAL_NEIGHBOURS
+        .iter()
+        .filter_map(|offset| match fluid_at(chunks, position + *offset) {
+            Some((neighbour, neighbour_level)) if neighbour == kind && neighbour_level < level => {
+                Some(neighbour_level + 1)
+            }
+            _ => None,
+        })
+        .min()
+        .filter(|fed| *fed <= kind.max_spread())
+}
+
+/// Hardens the lava touching water, returns whether it was the block at
+/// `position`
+fn harden_where_fluids_meet(
+    chunks: &mut ServerChunkWorldMap,
+    position: IVec3,
+    kind: FluidKind,
+    level: u8,
+) -> bool {
+    match kind {
+        FluidKind::Water => {
+            for offset in NEIGHBOURS {
+                if let Some((FluidKind::Lava, lava_level)) = fluid_at(chunks, position + offset) {
+                    harden_lava(chunks, position + offset, lava_level);
+                }
+            }
+        }
+        FluidKind::Lava => {
+            let touches_water = NEIGHBOURS.iter().any(|offset| {
+                matches!(
+                    fluid_at(chunks, position + *offset),
+                    Some((FluidKind::Water, _))
+                )
+            });
+            if touches_water {
+                harden_lava(chunks, position, level);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Water blocks connected to the one at `position`, if they hold `max_volume`
+/// or less
+fn small_water_body(
+    chunks: &ServerChunkWorldMap,
+    position: IVec3,
+    max_volume: f32,
+) -> Option<Vec<IVec3>> {
+    let is_water = |cell: IVec3| matches!(fluid_at(chunks, cell), Some((FluidKind::Water, _)));
+    let volume = |cells: usize| cells as f32 * FluidKind::Water.block_volume();
+    if !is_water(position) || volume(1) > max_volume {
+        return None;
+    }
+    let mut body = vec![position];
+    let mut seen = HashSet::from([position]);
+    let mut next = 0;
+    while let Some(cell) = body.get(next).copied() {
+        next += 1;
+        for offset in NEIGHBOURS {
+            let neighbour = cell + offset;
+            if is_water(neighbour) && seen.insert(neighbour) {
+                body.push(neighbour);
+                if volume(body.len()) > max_volume {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(body)
+}
+
+/// Moves the fluid block down, or sideways onto an open block above another
+/// one, returns whether it moved
+fn finite_step(
+    chunks: &mut ServerChunkWorldMap,
+    position: IVec3,
+    kind: FluidKind,
+    level: u8,
+) -> bool {
+    let below = position - IVec3::Y;
+    let target = if is_open(chunks, below) {
+        Some(below)
+    } else {
+        HORIZONTAL_NEIGHBOURS
+            .iter()
+            .map(|offset| position + *offset)
+            .find(|side| is_open(chunks, *side) && is_open(chunks, *side - IVec3::Y))
+    };
+    let Some(target) = target else {
+        return false;
+    };
+    chunks.remove_block_by_coordinates(&position);
+    chunks.set_block(&target, fluid_block(kind, level));
+    true
+}
+
+/// Spreads the fluid from its source, or dries it up when it lost its source
+fn classic_step(chunks: &mut ServerChunkWorldMap, position: IVec3, kind: FluidKind, level: u8) {
+    let mut level = level;
+    if level > 0 {
+        match fed_level(chunks, position, kind, level) {
+            None => {
+                chunks.remove_block_by_coordinates(&position);
+                return;
+            }
+            Some(fed) if fed != level => {
+                level = fed;
+                chunks.set_block(&position, fluid_block(kind, level));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let below = position - IVec3::Y;
+    if is_open(chunks, below) {
+        chunks.set_block(&below, fluid_block(kind, 1));
+        return;
+    }
+    if fluid_at(chunks, below).is_some_and(|(below_kind, _)| below_kind == kind) {
+        return;
+    }
+    if level >= kind.max_spread() {
+        return;
+    }
+
+    for offset in HORIZONTAL_NEIGHBOURS {
+        let target = position + offset;
+        if is_open(chunks, target) {
+            chunks.set_block(&target, fluid_block(kind, level + 1));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Freezing of surface water in cold climates.
+//!
+//! Surface water in a freezing climate turns into ice, and ice in a warmer climate
+//! melts back, as does ice within `HEAT_RADIUS` of a heat source such as lava. The
+//! block is converted in place, so the change is not recorded as a water edit: ice
+//! stores the volume of the water it froze from (see `BlockId::stored_fluid`).
+//! Ice doesn't flow, so frozen water stays out of the fluid flow until it melts.
+
+use bevy::math::IVec3;
+use rand::Rng;
+use shared::world::{BlockData, BlockId, ServerChunkWorldMap, WorldMap};
+
+use super::random_tick::{BlockChange, SurfaceBlock};
+
+/// Chance for a ticked surface block to freeze or melt
+const FREEZE_CHANCE: f64 = 0.1;
+/// Distance in blocks up to which heat sources melt ice
+const HEAT_RADIUS: i32 = 2;
+
+fn is_near_heat(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    let range = -HEAT_RADIUS..=HEAT_RADIUS;
+    range.clone().any(|dx| {
+        range.clone().any(|dy| {
+            range.clone().any(|dz| {
+                chunks
+                    .get_block_by_coordinates(&(position + IVec3::new(dx, dy, dz)))
+                    .is_some_and(|block| block.id.is_heat_source())
+            })
+        })
+    })
+}
+
+pub fn freeze_or_melt(
+    surface: &SurfaceBlock,
+    chunks: &ServerChunkWorldMap,
+    rng: &mut impl Rng,
+) -> Option<BlockChange> {
+    if !matches!(surface.block.id, BlockId::Water | BlockId::Ice) {
+        return None;
+    }
+    let freezing = surface.climate.is_freezing_at(surface.position.y)
+        && !is_near_heat(chunks, surface.position);
+    let id = match surface.block.id {
+        BlockId::Water if freezing => BlockId::Ice,
+        BlockId::Ice if !freezing => BlockId::Water,
+        _ => return None,
+    };
+
+    if !rng.gen_bool(FREEZE_CHANCE) {
+        return None;
+    }
+
+    Some(BlockChange::Set(
+        surface.position,
+        BlockData::new(id, surface.block.direction),
+    ))
+}
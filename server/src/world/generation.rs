@@ -1,32 +1,132 @@
 use bevy::prelude::*;
-use noiz::prelude::*;
 use shared::{world::*, CHUNK_SIZE, SEA_LEVEL};
 use std::collections::{HashMap, HashSet};
 
-fn try_place_block(
-    chunk: &mut ServerChunk,
-    x: i32,
-    y: i32,
-    z: i32,
-    block: BlockId,
-    direction: BlockDirection,
-) {
-    if x >= 0 && x < CHUNK_SIZE && y >= 0 && y < CHUNK_SIZE && z >= 0 && z < CHUNK_SIZE {
-        chunk
-            .map
-            .insert(IVec3::new(x, y, z), BlockData::new(block, direction));
+use crate::world::structures::build_structures;
+
+/// Grows the flora of a chunk into the world, once the neighbouring chunks it
+/// may reach into are all generated. Positions are local to the decorated chunk.
+struct FloraPlacer<'a> {
+    chunks: &'a mut ServerChunkWorldMap,
+    /// Global position of the first block of the decorated chunk
+    origin: IVec3,
+    /// Blocks placed by the flora of the chunk so far, which other flora of the
+    /// chunk can grow over, unlike the ground and the flora of other chunks
+    placed: HashSet<IVec3>,
+    /// Chunks the flora reached, to send again to the players
+    touched: HashSet<IVec3>,
+}
+
+impl<'a> FloraPlacer<'a> {
+    fn new(chunks: &'a mut ServerChunkWorldMap, chunk_pos: IVec3) -> Self {
+        Self {
+            chunks,
+            origin: chunk_pos * CHUNK_SIZE,
+            placed: HashSet::new(),
+            touched: HashSet::new(),
+        }
+    }
+
+    fn get(&self, position: IVec3) -> Option<&BlockData> {
+        self.chunks
+            .get_block_by_coordinates(&(self.origin + position))
+    }
+
+    fn place(&mut self, x: i32, y: i32, z: i32, block: BlockId, direction: BlockDirection) {
+        self.place_block(IVec3::new(x, y, z), BlockData::new(block, direction));
+    }
+
+    /// Places a block of flora, unless the ground or the flora of another chunk
+    /// is already there. Water gives way, so that trees grow in swamps.
+    fn place_block(&mut self, position: IVec3, block: BlockData) {
+        let position = self.origin + position;
+        let chunk_pos = position.div_euclid(IVec3::splat(CHUNK_SIZE));
+        let Some(chunk) = self.chunks.map.get_mut(&chunk_pos) else {
+            return;
+        };
+        let local_pos = position.rem_euclid(IVec3::splat(CHUNK_SIZE));
+        let free = chunk.map.get(&local_pos).is_none_or(|existing| {
+            existing.id == BlockId::Water || self.placed.contains(&position)
+        });
+        if free {
+            chunk.map.insert(local_pos, block);
+            self.placed.insert(position);
+            self.touched.insert(chunk_pos);
+        }
     }
 }
 
 // Import shared biome functions
 use shared::world::{calculate_temperature_humidity_with_noises, ClimateNoises};
 
-fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId, leaves: BlockId) {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TreeSpecies {
+    Oak,
+    Birch,
+    Spruce,
+    Jungle,
+}
+
+impl TreeSpecies {
+    /// Species of a tree growing in this biome, some biomes mix several of them
+    fn for_biome(biome_type: BiomeType) -> Self {
+        match biome_type {
+            BiomeType::Forest => match rand::random::<f32>() {
+                roll if roll < 0.2 => TreeSpecies::Jungle,
+                roll if roll < 0.5 => TreeSpecies::Birch,
+                _ => TreeSpecies::Oak,
+            },
+            BiomeType::FlowerPlains => TreeSpecies::Birch,
+            BiomeType::MediumMountain | BiomeType::HighMountainGrass | BiomeType::IcePlain => {
+                TreeSpecies::Spruce
+            }
+            _ => TreeSpecies::Oak,
+        }
+    }
+
+    fn log(&self) -> BlockId {
+        match self {
+            TreeSpecies::Oak => BlockId::OakLog,
+            TreeSpecies::Birch => BlockId::BirchLog,
+            TreeSpecies::Spruce => BlockId::SpruceLog,
+            TreeSpecies::Jungle => BlockId::JungleLog,
+        }
+    }
+
+    fn leaves(&self) -> BlockId {
+        match self {
+            TreeSpecies::Oak => BlockId::OakLeaves,
+            TreeSpecies::Birch => BlockId::BirchLeaves,
+            TreeSpecies::Spruce => BlockId::SpruceLeaves,
+            TreeSpecies::Jungle => BlockId::JungleLeaves,
+        }
+    }
+}
+
+fn generate_tree(placer: &mut FloraPlacer, x: i32, y: i32, z: i32, species: TreeSpecies) {
+    match species {
+        TreeSpecies::Oak => generate_round_tree(placer, x, y, z, 3, species),
+        // same shape, on a taller trunk
+        TreeSpecies::Birch => generate_round_tree(placer, x, y, z, 5, species),
+        TreeSpecies::Spruce => generate_spruce_tree(placer, x, y, z),
+        TreeSpecies::Jungle => generate_jungle_tree(placer, x, y, z),
+    }
+}
+
+fn generate_round_tree(
+    placer: &mut FloraPlacer,
+    x: i32,
+    y: i32,
+    z: i32,
+    min_trunk_height: u8,
+    species: TreeSpecies,
+) {
+    let (trunk, leaves) = (species.log(), species.leaves());
     // create trunk
-    let trunk_height = 3 + rand::random::<u8>() % 3; // random height between 3 and 5
+    let trunk_height = min_trunk_height + rand::random::<u8>() % 3; // random height, 3 more at most
     for dy in 0..trunk_height {
         let trunk_y = y + dy as i32;
-        try_place_block(chunk, x, trunk_y, z, trunk, BlockDirection::Front);
+        placer.place(x, trunk_y, z, trunk, BlockDirection::Front);
     }
 
     // place the leaves
@@ -42,26 +142,153 @@ fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId
                 if cond1 || cond2 {
                     let leaf_x = x + offset_x;
                     let leaf_z = z + offset_z;
-                    try_place_block(
-                        chunk,
-                        leaf_x,
-                        current_y,
-                        leaf_z,
-                        leaves,
-                        BlockDirection::Front,
-                    );
+                    placer.place(leaf_x, current_y, leaf_z, leaves, BlockDirection::Front);
                 }
             }
         }
     }
     let top_trunk_y = y + trunk_height as i32 - 1;
-    try_place_block(chunk, x, top_trunk_y, z, trunk, BlockDirection::Front);
+    placer.place(x, top_trunk_y, z, trunk, BlockDirection::Front);
 
     // add one leaf block at the top of the trunk
 }
 
+/// Narrow cone of leaves, wider at the bottom
+fn generate_spruce_tree(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let (trunk, leaves) = (BlockId::SpruceLog, BlockId::SpruceLeaves);
+    let trunk_height = 5 + rand::random::<u8>() % 3; // random height between 5 and 7
+    for dy in 0..trunk_height {
+        placer.place(x, y + dy as i32, z, trunk, BlockDirection::Front);
+    }
+
+    // rings of radius 2 and 1 in turns, up to a single leaf at the top
+    let top_y = y + trunk_height as i32;
+    for layer in 0..trunk_height as i32 - 1 {
+        let current_y = top_y - layer;
+        let radius: i32 = match layer {
+            0 => 0,
+            _ if layer % 2 == 1 => 1,
+            _ => 2,
+        };
+        for offset_x in -radius..=radius {
+            for offset_z in -radius..=radius {
+                if offset_x.abs() + offset_z.abs() > radius + radius / 2 {
+                    continue;
+                }
+                // the trunk keeps its own block
+                if current_y < top_y && offset_x == 0 && offset_z == 0 {
+                    continue;
+                }
+                placer.place(
+                    x + offset_x,
+                    current_y,
+                    z + offset_z,
+                    leaves,
+                    BlockDirection::Front,
+                );
+            }
+        }
+    }
+}
+
+/// Tall trunk under a large, flat canopy
+fn generate_jungle_tree(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let (trunk, leaves) = (BlockId::JungleLog, BlockId::JungleLeaves);
+    let trunk_height = 8 + rand::random::<u8>() % 4; // random height between 8 and 11
+    for dy in 0..trunk_height {
+        placer.place(x, y + dy as i32, z, trunk, BlockDirection::Front);
+    }
+
+    // the canopy narrows from a radius of 4 to 2 over three layers
+    let canopy_y = y + trunk_height as i32 - 1;
+    for layer in 0..3 {
+        let current_y = canopy_y + layer;
+        let radius = 4 - layer;
+        for offset_x in -radius..=radius {
+            for offset_z in -radius..=radius {
+                let distance = offset_x * offset_x + offset_z * offset_z;
+                if distance > radius * radius
+                    || distance == radius * radius && rand::random::<f32>() < 0.5
+                {
+                    continue;
+                }
+                if layer == 0 && offset_x == 0 && offset_z == 0 {
+                    continue;
+                }
+                placer.place(
+                    x + offset_x,
+                    current_y,
+                    z + offset_z,
+                    leaves,
+                    BlockDirection::Front,
+                );
+            }
+        }
+    }
+}
+
+/// Hangs vines of 1 to 3 blocks under some of the lowest leaves around the
+/// trunk at `x`, `z`
+fn hang_vines(placer: &mut FloraPlacer, x: i32, y: i32, z: i32, leaves: BlockId) {
+    for offset_x in -2i32..=2i32 {
+        for offset_z in -2i32..=2i32 {
+            if offset_x.abs() + offset_z.abs() != 2 || rand::random::<f32>() < 0.5 {
+                continue;
+            }
+            let (vine_x, vine_z) = (x + offset_x, z + offset_z);
+            let Some(lowest_leaf_y) = (y..y + 8).find(|leaf_y| {
+                placer
+                    .get(IVec3::new(vine_x, *leaf_y, vine_z))
+                    .is_some_and(|block| block.id == leaves)
+            }) else {
+                continue;
+            };
+
+            let length = 1 + rand::random::<u8>() % 3;
+            for dy in 1..=length as i32 {
+                let vine_pos = IVec3::new(vine_x, lowest_leaf_y - dy, vine_z);
+                if placer.get(vine_pos).is_some() {
+                    break;
+                }
+                placer.place(
+                    vine_pos.x,
+                    vine_pos.y,
+                    vine_pos.z,
+                    BlockId::Vine,
+                    BlockDirection::Front,
+                );
+            }
+        }
+    }
+}
+
+fn generate_giant_mushroom(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let stem_height = 4 + rand::random::<u8>() % 3; // random height between 4 and 6
+    for dy in 0..stem_height {
+        let stem_y = y + dy as i32;
+        placer.place(x, stem_y, z, BlockId::MushroomStem, BlockDirection::Front);
+    }
+
+    // flat cap, without its corners
+    let cap_y = y + stem_height as i32;
+    for offset_x in -2i32..=2i32 {
+        for offset_z in -2i32..=2i32 {
+            if offset_x.abs() == 2 && offset_z.abs() == 2 {
+                continue;
+            }
+            placer.place(
+                x + offset_x,
+                cap_y,
+                z + offset_z,
+                BlockId::RedMushroomBlock,
+                BlockDirection::Front,
+            );
+        }
+    }
+}
+
 fn generate_big_tree(
-    chunk: &mut ServerChunk,
+    placer: &mut FloraPlacer,
     x: i32,
     y: i32,
     z: i32,
@@ -78,46 +305,18 @@ fn generate_big_tree(
         let prof = rand::random::<u8>() % 2 + 1;
         for dx in 0..prof {
             let bx = branch_x + dx as i32;
-            try_place_block(
-                chunk,
-                bx,
-                branch_y,
-                branch_z + 1,
-                leaves,
-                BlockDirection::Front,
-            );
-            try_place_block(
-                chunk,
-                bx,
-                branch_y,
-                branch_z - 1,
-                leaves,
-                BlockDirection::Front,
-            );
-            try_place_block(
-                chunk,
-                bx,
-                branch_y + 1,
-                branch_z,
-                leaves,
-                BlockDirection::Front,
-            );
-            try_place_block(chunk, bx, branch_y, branch_z, trunk, BlockDirection::Front);
+            placer.place(bx, branch_y, branch_z + 1, leaves, BlockDirection::Front);
+            placer.place(bx, branch_y, branch_z - 1, leaves, BlockDirection::Front);
+            placer.place(bx, branch_y + 1, branch_z, leaves, BlockDirection::Front);
+            placer.place(bx, branch_y, branch_z, trunk, BlockDirection::Front);
         }
         let final_bx = branch_x + prof as i32;
-        try_place_block(
-            chunk,
-            final_bx,
-            branch_y,
-            branch_z,
-            leaves,
-            BlockDirection::Front,
-        );
+        placer.place(final_bx, branch_y, branch_z, leaves, BlockDirection::Front);
     }
     // create trunk
     for dy in 0..trunk_height {
         let trunk_y = y + dy as i32;
-        try_place_block(chunk, x, trunk_y, z, trunk, BlockDirection::Front);
+        placer.place(x, trunk_y, z, trunk, BlockDirection::Front);
     }
 
     // place the leaves
@@ -129,14 +328,7 @@ fn generate_big_tree(
                 if !(offset_x == 0 && offset_z == 0 || offset_x.abs() == 2 && offset_z.abs() == 2) {
                     let leaf_x = x + offset_x;
                     let leaf_z = z + offset_z;
-                    try_place_block(
-                        chunk,
-                        leaf_x,
-                        current_y,
-                        leaf_z,
-                        leaves,
-                        BlockDirection::Front,
-                    );
+                    placer.place(leaf_x, current_y, leaf_z, leaves, BlockDirection::Front);
                 }
             }
         }
@@ -144,7 +336,7 @@ fn generate_big_tree(
 
     // add one leaf block at the top of the trunk
     let top_y = leaf_start_y + 2;
-    try_place_block(chunk, x, top_y, z, leaves, BlockDirection::Front);
+    placer.place(x, top_y, z, leaves, BlockDirection::Front);
 
     // Add random leaves above the top leaf
     for layer in 0..3 {
@@ -158,123 +350,174 @@ fn generate_big_tree(
                 if cond1 || cond2 {
                     let leaf_x = x + offset_x;
                     let leaf_z = z + offset_z;
-                    try_place_block(
-                        chunk,
-                        leaf_x,
-                        current_y,
-                        leaf_z,
-                        leaves,
-                        BlockDirection::Front,
-                    );
+                    placer.place(leaf_x, current_y, leaf_z, leaves, BlockDirection::Front);
                 }
             }
         }
     }
 }
 
-fn generate_cactus(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, cactus: BlockId) {
+fn generate_cactus(placer: &mut FloraPlacer, x: i32, y: i32, z: i32, cactus: BlockId) {
     let cactus_height = 2 + rand::random::<u8>() % 2;
     for dy in 0..cactus_height {
         let cactus_y = y + dy as i32;
-        // Only place cactus blocks within chunk boundaries
-        if x >= 0
-            && x < CHUNK_SIZE
-            && z >= 0
-            && z < CHUNK_SIZE
-            && cactus_y >= 0
-            && cactus_y < CHUNK_SIZE
-        {
-            chunk.map.insert(
-                IVec3::new(x, cactus_y, z),
-                BlockData::new(cactus, BlockDirection::Front),
-            );
-        }
+        placer.place(x, cactus_y, z, cactus, BlockDirection::Front);
     }
 }
 
-fn interpolated_height(
-    x: i32,
-    z: i32,
-    perlin: &Noise<common_noise::Perlin>,
-    scale: f32,
-    seed: u32,
-) -> i32 {
-    // get the properties of the main biome at (x, z)
-    let climate = calculate_temperature_humidity(x, z, seed);
-    let biome_type = BiomeType::from_climate(climate);
-    let biome = get_biome_data(biome_type);
-
-    // initialize weighted values
-    let mut weighted_base_height = biome.base_height as f64;
-    let mut weighted_variation = biome.height_variation as f64;
-    let mut total_weight = 1.0;
-
-    // loop through neighboring blocks to get influences
-    for &offset_x in &[-4, 0, 4] {
-        for &offset_z in &[-4, 0, 4] {
-            if offset_x == 0 && offset_z == 0 {
-                continue; // ignore the central position
-            }
+/// Kelp columns are at most this tall
+const MAX_KELP_HEIGHT: i32 = 10;
 
-            let neighbor_x = x + offset_x;
-            let neighbor_z = z + offset_z;
-
-            // calculate the temperature and humidity of the neighboring block
-            let neighbor_climate = calculate_temperature_humidity(neighbor_x, neighbor_z, seed);
-
-            // determine the biome of the neighboring block
-            let neighbor_biome_type = BiomeType::from_climate(neighbor_climate);
-            let neighbor_biome = get_biome_data(neighbor_biome_type);
-
-            // weight by distance (the farther a neighbor is, the less influence it has)
-            let distance = ((offset_x.pow(2) + offset_z.pow(2)) as f64).sqrt();
-            let weight = 1.0 / (distance + 1.0); // distance +1 to avoid division by zero
+/// Grows a column of kelp of up to `MAX_KELP_HEIGHT` blocks, which stays under
+/// the surface of the water
+fn generate_kelp(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let is_water = |placer: &FloraPlacer, kelp_y: i32| {
+        placer
+            .get(IVec3::new(x, kelp_y, z))
+            .is_some_and(|block| block.id == BlockId::Water)
+    };
 
-            // update weighted values
-            weighted_base_height += neighbor_biome.base_height as f64 * weight;
-            weighted_variation += neighbor_biome.height_variation as f64 * weight;
-            total_weight += weight;
+    let kelp_height = 2 + rand::random::<u8>() as i32 % (MAX_KELP_HEIGHT - 1);
+    for kelp_y in y..y + kelp_height {
+        if !is_water(placer, kelp_y) || !is_water(placer, kelp_y + 1) {
+            break;
         }
+        placer.place(x, kelp_y, z, BlockId::Kelp, BlockDirection::Front);
     }
+}
 
-    // normalize weighted values
-    weighted_base_height /= total_weight;
-    weighted_variation /= total_weight;
+/// Grows a mound of coral: a pillar of 1 to 3 blocks, with some blocks around
+/// its foot
+fn generate_coral(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let mut blocks = vec![];
+    let pillar_height = 1 + rand::random::<u8>() % 3;
+    for dy in 0..pillar_height as i32 {
+        blocks.push(IVec3::new(x, y + dy, z));
+    }
+    for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        if rand::random::<f32>() < 0.5 {
+            blocks.push(IVec3::new(x + dx, y, z + dz));
+        }
+    }
 
-    // final calculation of height with perlin noise
-    let sample_pos = Vec2::new(x as f32 * scale, z as f32 * scale);
-    let terrain_noise = perlin.sample_for::<f32>(sample_pos) as f64;
-    let interpolated_height = weighted_base_height + (weighted_variation * terrain_noise);
+    let is_water = |placer: &FloraPlacer, position: IVec3| {
+        placer
+            .get(position)
+            .is_some_and(|block| block.id == BlockId::Water)
+    };
+    for position in blocks {
+        // Reefs stay under the surface
+        if is_water(placer, position) && is_water(placer, position + IVec3::Y) {
+            placer.place_block(
+                position,
+                BlockData::new(BlockId::Coral, BlockDirection::Front),
+            );
+        }
+    }
+}
 
-    interpolated_height.round() as i32
+/// Height of the ground near `x`, `z`, close to `y`: the position right above
+/// the first solid block found going down
+fn ground_near(placer: &FloraPlacer, x: i32, y: i32, z: i32) -> Option<i32> {
+    (y - 3..=y + 3).rev().find_map(|ground_y| {
+        placer
+            .get(IVec3::new(x, ground_y, z))
+            .is_some_and(|block| block.id.get_visibility() == BlockTransparency::Solid)
+            .then_some(ground_y + 1)
+    })
 }
 
-/// Helper function to attempt flora placement based on biome-specific thresholds
-/// Returns true if flora was placed, false otherwise
-fn try_place_flora<F>(
-    threshold: f32,
-    current_block: BlockId,
-    valid_surface_blocks: &[BlockId],
-    placement_fn: F,
-) -> bool
-where
-    F: FnOnce(),
-{
-    if threshold <= 0.0 {
-        return false;
+/// Drops 1 to 3 boulders around `x`, `z`, half buried in the ground. Moss
+/// grows on their top.
+fn generate_boulders(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let boulder_count = 1 + rand::random::<u8>() % 3;
+    for index in 0..boulder_count {
+        let (center_x, center_z) = if index == 0 {
+            (x, z)
+        } else {
+            (
+                x + rand::random::<i32>().rem_euclid(7) - 3,
+                z + rand::random::<i32>().rem_euclid(7) - 3,
+            )
+        };
+        let Some(center_y) = ground_near(placer, center_x, y, center_z) else {
+            continue;
+        };
+
+        let radius = 1.0 + rand::random::<f32>() * 1.2;
+        let reach = radius.ceil() as i32;
+        for dx in -reach..=reach {
+            for dy in -reach..=reach {
+                for dz in -reach..=reach {
+                    let offset = Vec3::new(dx as f32, dy as f32, dz as f32);
+                    if offset.length() > radius {
+                        continue;
+                    }
+                    let mossy = rand::random::<f32>() < 0.3 + 0.3 * dy as f32;
+                    let block = if mossy {
+                        BlockId::MossyCobblestone
+                    } else {
+                        BlockId::Cobblestone
+                    };
+                    placer.place(
+                        center_x + dx,
+                        center_y + dy,
+                        center_z + dz,
+                        block,
+                        BlockDirection::Front,
+                    );
+                }
+            }
+        }
     }
+}
 
-    if !valid_surface_blocks.contains(&current_block) {
-        return false;
+/// Rock spires are at most this tall
+const MAX_ROCK_SPIRE_HEIGHT: i32 = 12;
+
+/// Raises a pillar of stone, wide at its foot and narrowing up to its tip
+fn generate_rock_spire(placer: &mut FloraPlacer, x: i32, y: i32, z: i32) {
+    let height = 6 + rand::random::<u8>() as i32 % (MAX_ROCK_SPIRE_HEIGHT - 5);
+    let base_radius = 1.5 + rand::random::<f32>();
+    for dy in 0..height {
+        let radius = base_radius * (1.0 - dy as f32 / height as f32);
+        let reach = radius.ceil() as i32;
+        for dx in -reach..=reach {
+            for dz in -reach..=reach {
+                if dx != 0 || dz != 0 {
+                    // ragged sides
+                    let distance = Vec2::new(dx as f32, dz as f32).length();
+                    if distance > radius || rand::random::<f32>() < 0.15 {
+                        continue;
+                    }
+                }
+                let block = if dy == 0 {
+                    BlockId::Cobblestone
+                } else {
+                    BlockId::Stone
+                };
+                placer.place(x + dx, y + dy, z + dz, block, BlockDirection::Front);
+            }
+        }
     }
+}
 
-    let chance = rand::random::<f32>();
-    if chance < threshold {
-        placement_fn();
-        true
-    } else {
-        false
-    }
+/// Surfaces where tall grass and trees grow
+const GRASSY_SURFACES: &[BlockId] = &[BlockId::Grass, BlockId::SwampGrass];
+/// Surfaces where boulders and rock spires stand
+const ROCKY_SURFACES: &[BlockId] = &[
+    BlockId::Grass,
+    BlockId::SwampGrass,
+    BlockId::Dirt,
+    BlockId::Stone,
+    BlockId::Snow,
+];
+/// Ocean floors where kelp and coral grow
+const SEABED_SURFACES: &[BlockId] = &[BlockId::Sand, BlockId::Gravel, BlockId::Clay];
+
+/// Height of the ground at `x`, `z` once generated, without generating the chunk
+pub fn surface_height(x: i32, z: i32, seed: u32, config: GenerationConfig) -> i32 {
+    TerrainNoise::new(seed, config).height(x, z)
 }
 
 /// Helper function to check if flora should be placed based on threshold and surface block.
@@ -295,83 +538,124 @@ fn should_place_flora(
     rand::random::<f32>() < threshold
 }
 
-/// Fulfills a flora generation request by placing the appropriate flora type at the given position.
-fn fulfill_flora_request(chunk: &mut ServerChunk, request: &FloraRequest) {
-    let local_pos = IVec3::new(request.local_x, 0, request.local_z);
+/// Grows the flora of a site, from the air or the water right above the ground
+fn grow_flora(placer: &mut FloraPlacer, site: &FloraSite) {
+    let IVec3 { x, y, z } = site.position;
 
-    match request.flora_type {
+    match site.flora_type {
         FloraType::Flower => {
             let flower_type = if rand::random::<f32>() < 0.5 {
                 BlockId::Dandelion
             } else {
                 BlockId::Poppy
             };
-            chunk.map.insert(
-                local_pos,
-                BlockData::new(flower_type, BlockDirection::Front),
-            );
+            placer.place(x, y, z, flower_type, BlockDirection::Front);
         }
         FloraType::TallGrass => {
-            chunk.map.insert(
-                local_pos,
-                BlockData::new(BlockId::TallGrass, BlockDirection::Front),
-            );
+            placer.place(x, y, z, BlockId::TallGrass, BlockDirection::Front);
         }
         FloraType::Tree => {
-            generate_tree(
-                chunk,
-                request.local_x,
-                0,
-                request.local_z,
-                BlockId::OakLog,
-                BlockId::OakLeaves,
-            );
+            let species = TreeSpecies::for_biome(site.biome_type);
+            generate_tree(placer, x, y, z, species);
+            if site.biome_type == BiomeType::Swamp {
+                hang_vines(placer, x, y, z, species.leaves());
+            }
         }
         FloraType::BigTree => {
-            generate_big_tree(
-                chunk,
-                request.local_x,
-                0,
-                request.local_z,
-                BlockId::OakLog,
-                BlockId::OakLeaves,
-            );
+            generate_big_tree(placer, x, y, z, BlockId::OakLog, BlockId::OakLeaves);
         }
         FloraType::Cactus => {
-            generate_cactus(chunk, request.local_x, 0, request.local_z, BlockId::Cactus);
+            generate_cactus(placer, x, y, z, BlockId::Cactus);
+        }
+        FloraType::SnowLayer => {
+            placer.place_block(site.position, BlockData::snow_layer(1));
+        }
+        FloraType::GiantMushroom => {
+            generate_giant_mushroom(placer, x, y, z);
+        }
+        FloraType::Kelp => {
+            generate_kelp(placer, x, y, z);
+        }
+        FloraType::Coral => {
+            generate_coral(placer, x, y, z);
+        }
+        FloraType::Boulder => {
+            generate_boulders(placer, x, y, z);
+        }
+        FloraType::RockSpire => {
+            generate_rock_spire(placer, x, y, z);
+        }
+    }
+}
+
+/// Whether the chunk and all the chunks around it are generated, the flora of a
+/// chunk reaching at most one chunk away
+fn neighbours_generated(chunks: &ServerChunkWorldMap, chunk_pos: IVec3) -> bool {
+    (-1..=1).all(|x| {
+        (-1..=1)
+            .all(|y| (-1..=1).all(|z| chunks.map.contains_key(&(chunk_pos + IVec3::new(x, y, z)))))
+    })
+}
+
+/// Decoration pass: grows the flora of the chunks around a newly generated one
+/// which were only waiting for it, or of the new chunk itself if its
+/// neighbours are all there already
+pub fn decorate_ready_chunks(chunks: &mut ServerChunkWorldMap, chunk_pos: IVec3) {
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let decorated_pos = chunk_pos + IVec3::new(x, y, z);
+                if !chunks.pending_flora.contains_key(&decorated_pos)
+                    || !neighbours_generated(chunks, decorated_pos)
+                {
+                    continue;
+                }
+                let sites = chunks
+                    .pending_flora
+                    .remove(&decorated_pos)
+                    .unwrap_or_default();
+
+                let mut placer = FloraPlacer::new(chunks, decorated_pos);
+                for site in sites.iter() {
+                    grow_flora(&mut placer, site);
+                }
+                let touched = placer.touched;
+                chunks.chunks_to_update.extend(touched);
+            }
         }
     }
 }
 
-/// Result of chunk generation containing the generated chunk and any pending
-/// generation requests for the chunk above.
+/// Result of the terrain generation of a chunk, its flora waiting for the
+/// decoration pass
 pub struct ChunkGenerationResult {
     /// The generated chunk
     pub chunk: ServerChunk,
-    /// Generation requests to be fulfilled by the chunk above (y + 1)
-    pub requests_for_chunk_above: Vec<FloraRequest>,
+    /// Flora growing on the ground of this chunk
+    pub flora: Vec<FloraSite>,
+    /// Parts of the structure anchored in this chunk that belong to other chunks
+    pub structure_requests: Vec<(IVec3, StructureRequest)>,
 }
 
-/// Generates a chunk at the given position.
+/// Generates the terrain of a chunk at the given position.
 ///
 /// # Arguments
 /// * `chunk_pos` - The chunk position in chunk coordinates
 /// * `seed` - The world seed for procedural generation
-/// * `pending_requests` - Optional list of pending flora generation requests from the chunk below.
-///   These are processed first before generating new flora.
+/// * `config` - The terrain parameters the world was created with
 ///
 /// # Returns
-/// A `ChunkGenerationResult` containing the generated chunk and any requests for the chunk above.
+/// A `ChunkGenerationResult` containing the generated chunk and the sites of its
+/// flora, grown by [`decorate_ready_chunks`] once its neighbours are generated.
 pub fn generate_chunk(
     chunk_pos: IVec3,
     seed: u32,
-    pending_requests: Option<Vec<FloraRequest>>,
+    config: GenerationConfig,
 ) -> ChunkGenerationResult {
-    let mut perlin = Noise::<common_noise::Perlin>::default();
-    perlin.set_seed(seed);
+    let mut terrain = TerrainNoise::new(seed, config);
     let mut climate_noises = ClimateNoises::new(seed);
+    let superflat = config.preset == WorldGenPreset::Superflat;
 
-    let scale: f32 = 0.1;
     let cx = chunk_pos.x;
     let cy = chunk_pos.y;
     let cz = chunk_pos.z;
@@ -385,15 +669,7 @@ pub fn generate_chunk(
         sent_to_clients: HashSet::new(),
     };
 
-    // Collection of generation requests for the chunk above
-    let mut requests_for_chunk_above: Vec<FloraRequest> = Vec::new();
-
-    // First, process any pending generation requests from the chunk below
-    if let Some(requests) = pending_requests {
-        for request in requests {
-            fulfill_flora_request(&mut chunk, &request);
-        }
-    }
+    let mut flora: Vec<FloraSite> = Vec::new();
 
     for dx in 0..CHUNK_SIZE {
         for dz in 0..CHUNK_SIZE {
@@ -403,33 +679,85 @@ pub fn generate_chunk(
             // calculate temperature and humidity using shared function
             let climate = calculate_temperature_humidity_with_noises(x, z, &mut climate_noises);
 
-            // get biome regarding the two values
-            let biome_type = BiomeType::from_climate(climate);
-            let biome = get_biome_data(biome_type);
-
             // get terrain height
-            let terrain_height = interpolated_height(x, z, &perlin, scale, seed);
+            let terrain_height = terrain.height(x, z);
+
+            // get biome regarding the two values, its ground changing with altitude
+            let mut biome = biome_for_climate(climate).at_altitude(terrain_height);
+            let biome_type = biome.biome_type;
+            if superflat {
+                // the same grass everywhere
+                biome.surface_block = BlockId::Grass;
+                biome.sub_surface_block = BlockId::Dirt;
+            }
+            let ravine_floor = terrain.ravine_floor(x, z, terrain_height);
+            let lava_spring = terrain.is_lava_spring(x, z, terrain_height);
+            // Cold biomes and peaks above the snow line
+            let snowy = !superflat && climate.is_freezing_at(terrain_height);
+            // swamps are flooded right to their edges
+            let beach = if biome_type == BiomeType::Swamp {
+                None
+            } else {
+                terrain.beach(x, z, terrain_height)
+            };
+            // patches of gravel and clay on the sandy ocean floors
+            let is_ocean = matches!(
+                biome_type,
+                BiomeType::ShallowOcean | BiomeType::Ocean | BiomeType::DeepOcean
+            );
+            // flowers grow in meadows rather than scattered everywhere
+            let flower_density = terrain.flower_density(x, z);
+            let seabed_patch = if is_ocean && !superflat {
+                terrain.seabed_patch(x, z)
+            } else {
+                None
+            };
 
             // generate blocs
             for dy in 0..CHUNK_SIZE {
                 let y = CHUNK_SIZE * cy + dy;
 
-                if y > terrain_height && y > SEA_LEVEL {
+                if y > terrain.top(terrain_height) && y > SEA_LEVEL {
                     break;
                 }
 
+                // solid blocks right above, the surface block being the top one
+                let depth = terrain.depth(x, y, z, terrain_height, 5);
                 let block = if y == 0 {
                     BlockId::Bedrock
-                } else if y < terrain_height - 4 {
-                    BlockId::Stone
-                } else if y < terrain_height {
-                    biome.sub_surface_block
-                } else if y == terrain_height {
-                    biome.surface_block
+                } else if ravine_floor.is_some_and(|floor| y >= floor && y <= terrain_height) {
+                    // dry all the way down, even under the sea level
+                    continue;
+                } else if depth.is_some_and(|depth| depth > 4) && terrain.is_lava_pool(x, y, z) {
+                    if y > config.lava_level {
+                        // the cave above the lava
+                        continue;
+                    }
+                    BlockId::Lava
+                } else if depth.is_some_and(|depth| depth > 4) && terrain.is_lava_pocket(x, y, z) {
+                    BlockId::Lava
+                } else if let Some(depth) = depth {
+                    match (depth, beach) {
+                        // under the surface, so that the spring pours out of the slope
+                        (1, _) if lava_spring && y == terrain_height - 1 => BlockId::Lava,
+                        // sandy river beds, instead of grass under water
+                        (0, _) if y < SEA_LEVEL && terrain.river(x, z) > 0.0 => BlockId::Sand,
+                        // the top of the column only, not the ground under an overhang
+                        (0..=2, Some(beach)) if y > terrain_height - 3 => beach,
+                        (0, _) if y < SEA_LEVEL => seabed_patch.unwrap_or(biome.surface_block),
+                        (0, _) => biome.surface_block,
+                        (1..=4, _) => biome.sub_surface_block,
+                        _ if terrain.is_deepslate(y) => BlockId::Deepslate,
+                        _ => BlockId::Stone,
+                    }
+                } else if y == SEA_LEVEL && snowy {
+                    // only the water exposed to the air freezes
+                    BlockId::Ice
                 } else if y <= SEA_LEVEL {
                     BlockId::Water
                 } else {
-                    panic!();
+                    // air under an overhang or between the ground and the islands
+                    continue;
                 };
 
                 let block_pos = IVec3::new(dx, dy, dz);
@@ -438,144 +766,95 @@ pub fn generate_chunk(
                     .map
                     .insert(block_pos, BlockData::new(block, BlockDirection::Front));
 
-                // Determine flora placement thresholds based on biome
-                let flower_threshold = match biome_type {
-                    BiomeType::FlowerPlains => 0.1,
-                    BiomeType::Plains | BiomeType::Forest | BiomeType::MediumMountain => 0.02,
-                    _ => 0.0,
-                };
-
-                let tall_grass_threshold = match biome_type {
-                    BiomeType::HighMountainGrass | BiomeType::Desert | BiomeType::IcePlain => 0.0,
-                    _ => 0.1,
-                };
-
-                let tree_threshold = match biome_type {
-                    BiomeType::Forest => 0.06,
-                    BiomeType::FlowerPlains | BiomeType::MediumMountain => 0.02,
-                    _ => 0.0,
+                // Flora placement thresholds of the biome
+                let flower_threshold = biome.flora.flower * flower_density;
+                let tall_grass_threshold = biome.flora.tall_grass;
+                let tree_threshold = if superflat { 0.0 } else { biome.flora.tree };
+                let cactus_threshold = biome.flora.cactus;
+                let giant_mushroom_threshold = biome.flora.giant_mushroom;
+                let kelp_threshold = biome.flora.kelp;
+                let coral_threshold = if climate.temperature > WARM_WATER_TEMPERATURE {
+                    biome.flora.coral
+                } else {
+                    0.0
                 };
-
-                let cactus_threshold = match biome_type {
-                    BiomeType::Desert => 0.01,
-                    _ => 0.0,
+                // The ocean floor, with at least two blocks of water above it
+                let seabed = depth == Some(0) && y < SEA_LEVEL - 1;
+                let (boulder_threshold, rock_spire_threshold) = if superflat {
+                    (0.0, 0.0)
+                } else {
+                    (biome.flora.boulder, biome.flora.rock_spire)
                 };
+                // Rocks stand on the open ground, snowy or not
+                let open_ground = depth == Some(0) && y >= SEA_LEVEL;
 
-                let valid_tree_position =
-                    dx >= 1 && dx < CHUNK_SIZE - 1 && dz >= 1 && dz < CHUNK_SIZE - 1;
-
-                // If we're at the top of the chunk (dy == CHUNK_SIZE - 1), create a generation
-                // request for the chunk above instead of placing flora directly
-                if block_pos.y + 1 >= CHUNK_SIZE {
-                    // Try to create generation requests for the chunk above
-                    if should_place_flora(flower_threshold, block, &[BlockId::Grass]) {
-                        requests_for_chunk_above.push(FloraRequest {
-                            local_x: dx,
-                            local_z: dz,
-                            flora_type: FloraType::Flower,
-                            biome_type,
-                        });
-                    } else if should_place_flora(tall_grass_threshold, block, &[BlockId::Grass]) {
-                        requests_for_chunk_above.push(FloraRequest {
-                            local_x: dx,
-                            local_z: dz,
-                            flora_type: FloraType::TallGrass,
-                            biome_type,
-                        });
-                    } else if valid_tree_position
-                        && should_place_flora(tree_threshold, block, &[BlockId::Grass])
+                // Snow covers the exposed ground of cold places, instead of flora
+                let snow_covered = snowy && depth == Some(0) && y >= SEA_LEVEL;
+                let flora_type = if open_ground
+                    && should_place_flora(rock_spire_threshold, block, ROCKY_SURFACES)
+                {
+                    Some(FloraType::RockSpire)
+                } else if open_ground
+                    && should_place_flora(boulder_threshold, block, ROCKY_SURFACES)
+                {
+                    Some(FloraType::Boulder)
+                } else if snow_covered {
+                    Some(FloraType::SnowLayer)
+                } else if should_place_flora(flower_threshold, block, &[BlockId::Grass]) {
+                    Some(FloraType::Flower)
+                } else if should_place_flora(tall_grass_threshold, block, GRASSY_SURFACES) {
+                    Some(FloraType::TallGrass)
+                } else if should_place_flora(tree_threshold, block, GRASSY_SURFACES) {
+                    // Determine if this should be a big tree based on biome and threshold
+                    // Note: tree_threshold > 0.0 is guaranteed by should_place_flora returning true
+                    if biome_type == BiomeType::Forest
+                        && tree_threshold > 0.0
+                        && rand::random::<f32>() < 0.01 / tree_threshold
                     {
-                        // Determine if this should be a big tree based on biome and threshold
-                        // Note: tree_threshold > 0.0 is guaranteed by should_place_flora returning true
-                        let flora_type = if biome_type == BiomeType::Forest
-                            && tree_threshold > 0.0
-                            && rand::random::<f32>() < 0.01 / tree_threshold
-                        {
-                            FloraType::BigTree
-                        } else {
-                            FloraType::Tree
-                        };
-                        requests_for_chunk_above.push(FloraRequest {
-                            local_x: dx,
-                            local_z: dz,
-                            flora_type,
-                            biome_type,
-                        });
-                    } else if should_place_flora(cactus_threshold, block, &[BlockId::Sand]) {
-                        requests_for_chunk_above.push(FloraRequest {
-                            local_x: dx,
-                            local_z: dz,
-                            flora_type: FloraType::Cactus,
-                            biome_type,
-                        });
-                    }
-                    continue;
-                }
-
-                // Normal flora placement for blocks not at chunk top
-                // Try placing flora in priority order
-                if try_place_flora(flower_threshold, block, &[BlockId::Grass], || {
-                    let flower_type = if rand::random::<f32>() < 0.5 {
-                        BlockId::Dandelion
+                        Some(FloraType::BigTree)
                     } else {
-                        BlockId::Poppy
-                    };
-                    chunk.map.insert(
-                        block_pos.with_y(block_pos.y + 1),
-                        BlockData::new(flower_type, BlockDirection::Front),
-                    );
-                }) {
-                    continue;
-                }
-
-                if try_place_flora(tall_grass_threshold, block, &[BlockId::Grass], || {
-                    chunk.map.insert(
-                        block_pos.with_y(block_pos.y + 1),
-                        BlockData::new(BlockId::TallGrass, BlockDirection::Front),
-                    );
-                }) {
-                    continue;
-                }
-
-                if valid_tree_position
-                    && try_place_flora(tree_threshold, block, &[BlockId::Grass], || {
-                        // Determine if this should be a big tree based on biome and threshold
-                        // Note: tree_threshold > 0.0 is guaranteed by try_place_flora calling this closure
-                        if biome_type == BiomeType::Forest
-                            && tree_threshold > 0.0
-                            && rand::random::<f32>() < 0.01 / tree_threshold
-                        {
-                            generate_big_tree(
-                                &mut chunk,
-                                dx,
-                                dy + 1,
-                                dz,
-                                BlockId::OakLog,
-                                BlockId::OakLeaves,
-                            );
-                        } else {
-                            generate_tree(
-                                &mut chunk,
-                                dx,
-                                dy + 1,
-                                dz,
-                                BlockId::OakLog,
-                                BlockId::OakLeaves,
-                            );
-                        }
-                    })
+                        Some(FloraType::Tree)
+                    }
+                } else if should_place_flora(cactus_threshold, block, &[BlockId::Sand]) {
+                    Some(FloraType::Cactus)
+                } else if should_place_flora(giant_mushroom_threshold, block, &[BlockId::Mycelium])
                 {
-                    continue;
-                }
+                    Some(FloraType::GiantMushroom)
+                } else if seabed && should_place_flora(coral_threshold, block, SEABED_SURFACES) {
+                    Some(FloraType::Coral)
+                } else if seabed && should_place_flora(kelp_threshold, block, SEABED_SURFACES) {
+                    Some(FloraType::Kelp)
+                } else {
+                    None
+                };
 
-                try_place_flora(cactus_threshold, block, &[BlockId::Sand], || {
-                    generate_cactus(&mut chunk, dx, dy + 1, dz, BlockId::Cactus);
-                });
+                if let Some(flora_type) = flora_type {
+                    flora.push(FloraSite {
+                        position: block_pos.with_y(block_pos.y + 1),
+                        flora_type,
+                        biome_type,
+                    });
+                }
             }
         }
     }
+
+    // Ores only replace stone, so veins stop at caves and the surface
+    for (local_pos, ore) in ore_veins(seed, chunk_pos, &config.ores) {
+        if let Some(block) = chunk
+            .map
+            .get_mut(&local_pos)
+            .filter(|block| matches!(block.id, BlockId::Stone | BlockId::Deepslate))
+        {
+            *block = BlockData::new(ore, BlockDirection::Front);
+        }
+    }
+
+    let structure_requests = build_structures(seed, config, chunk_pos, &mut chunk);
+
     ChunkGenerationResult {
         chunk,
-        requests_for_chunk_above,
+        flora,
+        structure_requests,
     }
 }
@@ -0,0 +1,126 @@
+//! Per-chunk counts of the blocks players mine and place, behind `/heatmap` and
+//! the `heatmap` console command.
+//!
+//! Counts start over when the server starts. To tell grief apart, the server
+//! remembers who placed every block placed since then.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::RenetServer;
+use shared::messages::{HeatmapUpdate, PlayerId, ServerAnnouncement, ServerToClientMessage};
+use shared::players::blocks::BlockInteractionOutcome;
+use shared::world::{
+    block_to_chunk_coord, ChunkActivity, HeatmapKind, ServerWorldMap, HEATMAP_MAX_CHUNKS,
+};
+use shared::GameServerConfig;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::operators::Operators;
+
+#[derive(Resource, Default, Debug)]
+pub struct BlockHeatmap {
+    chunks: HashMap<IVec3, ChunkActivity>,
+    placed_by: HashMap<IVec3, PlayerId>,
+}
+
+fn chunk_of(position: IVec3) -> IVec3 {
+    IVec3::new(
+        block_to_chunk_coord(position.x),
+        block_to_chunk_coord(position.y),
+        block_to_chunk_coord(position.z),
+    )
+}
+
+impl BlockHeatmap {
+    /// Counts the blocks `player` just mined or placed
+    pub fn record(&mut self, player: PlayerId, outcomes: &[BlockInteractionOutcome]) {
+        for outcome in outcomes {
+            match *outcome {
+                BlockInteractionOutcome::Placed { position, .. } => {
+                    self.chunks.entry(chunk_of(position)).or_default().placed += 1;
+                    self.placed_by.insert(position, player);
+                }
+                BlockInteractionOutcome::Broken { position, .. } => {
+                    let activity = self.chunks.entry(chunk_of(position)).or_default();
+                    activity.mined += 1;
+                    if self
+                        .placed_by
+                        .remove(&position)
+                        .is_some_and(|owner| owner != player)
+                    {
+                        activity.grief += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Chunks with some activity of this kind, the busiest first
+    pub fn busiest(&self, kind: HeatmapKind, limit: usize) -> Vec<(IVec3, u32)> {
+        let mut chunks: Vec<(IVec3, u32)> = self
+            .chunks
+            .iter()
+            .map(|(position, activity)| (*position, activity.count(kind)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        chunks.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.to_array().cmp(&b.0.to_array())));
+        chunks.truncate(limit);
+        chunks
+    }
+}
+
+/// Answers `/heatmap <kind>` with the busiest chunks, for operators only
+pub fn heatmap_command(
+    server: &mut RenetServer,
+    world_map: &ServerWorldMap,
+    heatmap: &BlockHeatmap,
+    operators: &Operators,
+    config: &GameServerConfig,
+    player_id: PlayerId,
+    kind: HeatmapKind,
+) {
+    let Some(player) = world_map.players.get(&player_id) else {
+        return;
+    };
+
+    if !operators.is_operator(&player.name, config.is_solo) {
+        warn!(
+            "Player {} asked for a heatmap without being an operator",
+            player.name
+        );
+        server.send_game_message(
+            player_id,
+            ServerToClientMessage::Announcement(ServerAnnouncement {
+                content: "Only operators can see heatmaps".to_string(),
+            }),
+        );
+        return;
+    }
+
+    let chunks = heatmap.busiest(kind, HEATMAP_MAX_CHUNKS);
+    info!(
+        "Player {} shows the {} heatmap, {} chunks",
+        player.name,
+        kind.name(),
+        chunks.len()
+    );
+    let content = match chunks.first() {
+        None => format!("No {} blocks yet", kind.name()),
+        Some((position, count)) => format!(
+            "{} heatmap: {} chunks, the busiest is {:?} with {} blocks",
+            kind.name(),
+            chunks.len(),
+            position,
+            count
+        ),
+    };
+    server.send_game_message(
+        player_id,
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+    server.send_game_message(
+        player_id,
+        ServerToClientMessage::Heatmap(HeatmapUpdate { kind, chunks }),
+    );
+}
@@ -0,0 +1,102 @@
+//! Power-save mode of dedicated servers.
+//!
+//! When nobody is connected, the server slows down to a few ticks per second and
+//! the world stands still: virtual time is paused, so mobs, fluids, weather, random
+//! ticks and the day cycle don't move, and nothing is broadcast. The first
+//! connection wakes everything up on the same tick, with no catching up to do.
+//! Scheduled tasks wait for the server to wake up too. Integrated servers are
+//! never idle, but they stand still the same way while the solo player has the
+//! pause menu open (see [`SoloPause`]). The mode is set in the `[idle]` table of
+//! `server.toml`:
+//!
+//! ```toml
+//! [idle]
+//! enabled = true
+//! ticks_per_second = 2
+//! ```
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::{GameServerConfig, TICKS_PER_SECOND};
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct IdleConfig {
+    pub enabled: bool,
+    /// Tick rate of the server while nobody is connected
+    pub ticks_per_second: u32,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ticks_per_second: 2,
+        }
+    }
+}
+
+/// Whether the server is currently in power-save mode
+#[derive(Resource, Default, Debug)]
+pub struct ServerIdle(pub bool);
+
+/// Whether the player of a solo game has the pause menu open
+#[derive(Resource, Default, Debug)]
+pub struct SoloPause(pub bool);
+
+/// Run condition of the systems that only matter while players are connected
+pub fn server_is_active(idle: Res<ServerIdle>) -> bool {
+    !idle.0
+}
+
+/// Enters power-save mode once the last player left, and leaves it as soon as
+/// someone connects. Solo games are paused and resumed the same way.
+pub fn idle_state_system(
+    server: Res<RenetServer>,
+    config: Res<IdleConfig>,
+    server_config: Res<GameServerConfig>,
+    solo_pause: Res<SoloPause>,
+    mut idle: ResMut<ServerIdle>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let should_idle = if server_config.is_solo {
+        solo_pause.0
+    } else {
+        config.enabled && server.connected_clients() == 0
+    };
+    if should_idle == idle.0 {
+        return;
+    }
+
+    idle.0 = should_idle;
+    match (should_idle, server_config.is_solo) {
+        (true, true) => info!("The game is paused"),
+        (false, true) => info!("The game is resumed"),
+        (true, false) => info!("No players connected, the server goes idle"),
+        (false, false) => info!("A player connected, the server wakes up"),
+    }
+    if should_idle {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+/// Stretches idle ticks to the idle tick rate, on top of the wait of the run loop.
+/// Paused solo games keep their tick rate, to resume as soon as the menu closes.
+pub fn idle_throttle_system(
+    idle: Res<ServerIdle>,
+    config: Res<IdleConfig>,
+    server_config: Res<GameServerConfig>,
+) {
+    if !idle.0 || server_config.is_solo {
+        return;
+    }
+    let idle_tick = Duration::from_secs_f64(1.0 / config.ticks_per_second.max(1) as f64);
+    let tick = Duration::from_secs_f64(1.0 / TICKS_PER_SECOND as f64);
+    std::thread::sleep(idle_tick.saturating_sub(tick));
+}
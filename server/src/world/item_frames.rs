@@ -0,0 +1,121 @@
+use bevy::math::{IVec3, Vec3};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use shared::messages::{ItemFrameUpdate, PlayerFrameInput, PlayerId, ServerToClientMessage};
+use shared::players::{blocks::BlockInteractionOutcome, Player};
+use shared::world::{
+    raycast, BlockData, BlockId, ItemFrame, ItemFrameRegistry, ItemStack, ServerChunkWorldMap,
+    ServerWorldMap, ITEM_FRAME_MAX_DISTANCE,
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+fn broadcast_frame(server: &mut RenetServer, position: IVec3, frame: ItemFrame) {
+    server.broadcast_game_message(ServerToClientMessage::ItemFrame(ItemFrameUpdate {
+        frames: vec![(position, frame)],
+    }));
+}
+
+/// Puts the held item in the frame the player is looking at, or turns the item
+/// already in it.
+///
+/// Returns whether the click was used by an item frame.
+pub fn use_item_frame(
+    server: &mut RenetServer,
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    frames: &mut ItemFrameRegistry,
+    input: &PlayerFrameInput,
+) -> bool {
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return false;
+    };
+    if hit.block.id != BlockId::ItemFrame {
+        return false;
+    }
+    let position = hit.position;
+    if (position.as_vec3() + Vec3::splat(0.5)).distance(player.position) > ITEM_FRAME_MAX_DISTANCE {
+        return false;
+    }
+
+    let held = player
+        .inventory
+        .inner
+        .get(&input.hotbar_slot)
+        .map(|stack| stack.item_id);
+    let mut frame = frames.get(position);
+    if frame.interact(held) {
+        player
+            .inventory
+            .remove_item_from_stack(input.hotbar_slot, 1);
+        info!(
+            "Player {} put {:?} in the item frame at {:?}",
+            player.id, frame.item, position
+        );
+    }
+
+    if frame.item.is_some() {
+        frames.frames.insert(position, frame);
+        broadcast_frame(server, position, frame);
+    }
+    true
+}
+
+/// Gives the content of the item frames the player broke back to them
+pub fn drop_broken_item_frames(
+    server: &mut RenetServer,
+    player: &mut Player,
+    frames: &mut ItemFrameRegistry,
+    outcomes: &[BlockInteractionOutcome],
+) {
+    for outcome in outcomes {
+        let BlockInteractionOutcome::Broken {
+            block:
+                BlockData {
+                    id: BlockId::ItemFrame,
+                    ..
+                },
+            position,
+        } = *outcome
+        else {
+            continue;
+        };
+        let Some(item_id) = frames.take(position) else {
+            continue;
+        };
+
+        player.inventory.add_item_to_inventory(ItemStack {
+            item_id,
+            item_type: item_id.get_default_type(),
+            nb: 1,
+        });
+        broadcast_frame(server, position, ItemFrame::default());
+    }
+}
+
+/// Sends the content of every item frame to the players who just joined
+pub fn send_item_frames_system(
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+    mut informed: Local<HashSet<PlayerId>>,
+) {
+    informed.retain(|id| world_map.players.contains_key(id));
+    for id in world_map.players.keys() {
+        if !informed.insert(*id) {
+            continue;
+        }
+        let frames = world_map
+            .item_frames
+            .frames
+            .iter()
+            .map(|(position, frame)| (*position, *frame))
+            .collect();
+        server.send_game_message(
+            *id,
+            ServerToClientMessage::ItemFrame(ItemFrameUpdate { frames }),
+        );
+    }
+}
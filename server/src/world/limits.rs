@@ -0,0 +1,253 @@
+//! Hard caps on what a long-running server keeps in memory.
+//!
+//! Once the loaded chunks reach their cap, the chunks farthest from the players
+//! are saved and unloaded, to be read back from their file when a player comes
+//! near again. Chunks within the generation distance of a player, holding a mob,
+//! an item stack or a boat, or with updates not yet sent are kept: when none is
+//! left to unload, no new chunk is generated and players wandering further only
+//! find the edge of the world. Mobs beyond their cap
+//! despawn, the farthest from the players first, and item stacks beyond theirs
+//! despawn, the oldest first. Hitting a cap is logged, at most once a minute per
+//! cap. The caps are set in the `[limits]` table of `server.toml`:
+//!
+//! ```toml
+//! [limits]
+//! max_loaded_chunks = 40000
+//! max_mobs = 256
+//! max_item_stacks = 1024
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_log::{error, warn};
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::messages::{mob::MobDespawnEvent, ServerToClientMessage};
+use shared::world::{world_position_to_chunk_position, DimensionId, MobId, ServerWorldMap};
+use shared::{GameFolderPaths, GameServerConfig, LOD1_MULTIPLIER};
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::background_generation::ChunkGenerationTasks;
+use crate::world::chunk_store::{dimension_folder, unload_chunk};
+use crate::world::data::SAVE_PATH;
+
+/// Delay between two warnings about the same cap
+const CAP_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+/// Chunks unloaded under the cap of loaded chunks once it is reached
+const CHUNK_UNLOAD_ROOM: usize = 64;
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Chunks of all the dimensions held in memory
+    pub max_loaded_chunks: usize,
+    pub max_mobs: usize,
+    pub max_item_stacks: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_loaded_chunks: 40_000,
+            max_mobs: 256,
+            max_item_stacks: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cap {
+    LoadedChunks,
+    Mobs,
+    ItemStacks,
+}
+
+/// When each cap was last reported as hit
+#[derive(Resource, Default, Debug)]
+pub struct CapWarnings(HashMap<Cap, Duration>);
+
+impl CapWarnings {
+    /// Logs that `cap` was hit, unless it was already logged recently
+    pub fn warn(&mut self, cap: Cap, now: Duration, message: impl FnOnce() -> String) {
+        if self
+            .0
+            .get(&cap)
+            .is_some_and(|last| now < *last + CAP_WARNING_INTERVAL)
+        {
+            return;
+        }
+        self.0.insert(cap, now);
+        warn!("{}", message());
+    }
+}
+
+/// Chunks held in memory, in every dimension
+pub fn loaded_chunk_count(world_map: &ServerWorldMap) -> usize {
+    world_map.chunks.map.len()
+        + world_map
+            .dimensions
+            .values()
+            .map(|chunks| chunks.map.len())
+            .sum::<usize>()
+}
+
+/// Saves and unloads the chunks beyond the cap, the farthest from the players of
+/// their dimension first, while chunks are waiting to be generated. Some room is
+/// made under the cap so that the generation goes on for a while before the next
+/// unload.
+pub fn chunk_cap_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    generation_tasks: Res<ChunkGenerationTasks>,
+    config: Res<GameServerConfig>,
+    game_folder_paths: Res<GameFolderPaths>,
+    limits: Res<LimitsConfig>,
+    mut warnings: ResMut<CapWarnings>,
+    time: Res<Time<Real>>,
+) {
+    let loaded = loaded_chunk_count(&world_map) + generation_tasks.tasks.len();
+    if loaded < limits.max_loaded_chunks || !generation_tasks.has_queued_chunks() {
+        return;
+    }
+    let excess = loaded + CHUNK_UNLOAD_ROOM - limits.max_loaded_chunks;
+
+    let radius = (config.broadcast_render_distance as f32 * LOD1_MULTIPLIER) as i32;
+    let world_map = world_map.as_mut();
+    // Mobs, item stacks and boats would fall through the ground of an unloaded chunk
+    let occupied: HashSet<IVec3> = world_map
+        .mobs
+        .values()
+        .map(|mob| mob.position)
+        .chain(world_map.item_stacks.iter().map(|stack| stack.pos))
+        .chain(world_map.boats.values().map(|boat| boat.boat.position))
+        .map(world_position_to_chunk_position)
+        .flat_map(|chunk_pos| [chunk_pos, chunk_pos - IVec3::Y])
+        .collect();
+
+    let mut by_distance: Vec<(i32, DimensionId, IVec3)> = Vec::new();
+    for dimension in std::iter::once(DimensionId::Overworld).chain(DimensionId::others()) {
+        let Some(chunks) = world_map.dimension_chunks(dimension) else {
+            continue;
+        };
+        let players: Vec<IVec3> = world_map
+            .players_in(dimension)
+            .map(|player| world_position_to_chunk_position(player.position))
+            .collect();
+        let waiting: HashSet<IVec3> = chunks.chunks_to_update.iter().copied().collect();
+        for chunk_pos in chunks.map.keys() {
+            if waiting.contains(chunk_pos)
+                || (dimension == DimensionId::Overworld && occupied.contains(chunk_pos))
+            {
+                continue;
+            }
+            let distance = players
+                .iter()
+                .map(|player| (*chunk_pos - *player).length_squared())
+                .min()
+                .unwrap_or(i32::MAX);
+            if distance > radius * radius {
+                by_distance.push((distance, dimension, *chunk_pos));
+            }
+        }
+    }
+    by_distance.sort_by_key(|(distance, _, _)| std::cmp::Reverse(*distance));
+
+    let world_folder = game_folder_paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(&world_map.name);
+    let mut unloaded = 0;
+    for (_, dimension, chunk_pos) in by_distance.into_iter().take(excess) {
+        let chunks = world_map.dimension_chunks_mut(dimension);
+        match unload_chunk(
+            &dimension_folder(&world_folder, dimension),
+            chunks,
+            chunk_pos,
+        ) {
+            Ok(()) => unloaded += 1,
+            Err(err) => error!(
+                "Could not save chunk {:?} of the {} to unload it : {}",
+                chunk_pos,
+                dimension.name(),
+                err
+            ),
+        }
+    }
+    if unloaded > 0 {
+        warnings.warn(Cap::LoadedChunks, time.elapsed(), || {
+            format!(
+                "Loaded chunk cap of {} reached, unloaded the {} farthest chunks",
+                limits.max_loaded_chunks, unloaded
+            )
+        });
+    }
+}
+
+/// Despawns the mobs beyond the cap, the farthest from the players of the
+/// overworld first
+pub fn mob_cap_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    limits: Res<LimitsConfig>,
+    mut warnings: ResMut<CapWarnings>,
+    time: Res<Time<Real>>,
+) {
+    let excess = world_map.mobs.len().saturating_sub(limits.max_mobs);
+    if excess == 0 {
+        return;
+    }
+
+    let players: Vec<Vec3> = world_map
+        .players_in(DimensionId::Overworld)
+        .map(|player| player.position)
+        .collect();
+    let mut by_distance: Vec<(f32, MobId)> = world_map
+        .mobs
+        .iter()
+        .map(|(id, mob)| {
+            let distance = players
+                .iter()
+                .map(|player| player.distance_squared(mob.position))
+                .fold(f32::INFINITY, f32::min);
+            (distance, *id)
+        })
+        .collect();
+    by_distance.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (_, id) in by_distance.into_iter().take(excess) {
+        world_map.mobs.remove(&id);
+        server.broadcast_game_message(ServerToClientMessage::MobDespawn(MobDespawnEvent { id }));
+    }
+    warnings.warn(Cap::Mobs, time.elapsed(), || {
+        format!(
+            "Mob cap of {} reached, despawned the {} farthest mobs",
+            limits.max_mobs, excess
+        )
+    });
+}
+
+/// Despawns the item stacks beyond the cap, the oldest first
+pub fn item_stack_cap_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    limits: Res<LimitsConfig>,
+    mut warnings: ResMut<CapWarnings>,
+    time: Res<Time<Real>>,
+) {
+    // Stacks are pushed as they are dropped, so the oldest come first
+    let excess = world_map
+        .item_stacks
+        .len()
+        .saturating_sub(limits.max_item_stacks);
+    if excess == 0 {
+        return;
+    }
+
+    world_map.item_stacks.drain(..excess);
+    warnings.warn(Cap::ItemStacks, time.elapsed(), || {
+        format!(
+            "Item stack cap of {} reached, despawned the {} oldest stacks",
+            limits.max_item_stacks, excess
+        )
+    });
+}
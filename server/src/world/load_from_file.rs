@@ -1,8 +1,12 @@
 use bevy::prelude::*;
-use bevy_log::info;
+use bevy_log::{error, info};
 use ron::de::from_str;
 use shared::messages::{PlayerId, PlayerSave};
 use shared::world::data::WorldSeed;
+use shared::world::{
+    BiomeDefinition, BiomeRegistry, DimensionId, GenerationConfig, WorldGenPreset, BIOMES_FOLDER,
+    GENERATION_CONFIG_FILE,
+};
 use shared::GameFolderPaths;
 use std::fs;
 use std::path::Path;
@@ -13,6 +17,8 @@ use std::path::PathBuf;
 
 pub fn load_world_data(
     file_name: &str,
+    preset: WorldGenPreset,
+    seed: Option<WorldSeed>,
     game_folder_paths: &GameFolderPaths,
 ) -> Result<WorldData, Box<dyn std::error::Error>> {
     let file_path: PathBuf = game_folder_paths
@@ -23,13 +29,16 @@ pub fn load_world_data(
 
     if !path.exists() {
         info!(
-            "World data file not found: {}. Generating default world and seed.",
-            file_path.display()
+            "World data file not found: {}. Generating a {} world.",
+            file_path.display(),
+            preset.name()
         );
-        let seed = WorldSeed(rand::random::<u32>());
+        let seed = seed.unwrap_or_else(|| WorldSeed(rand::random::<u32>()));
+        info!("World seed: {}", seed.0);
         return Ok(WorldData {
             name: file_name.to_string(),
             seed,
+            generation: preset.apply(load_generation_config(game_folder_paths)),
             ..default()
         });
     }
@@ -42,10 +51,70 @@ pub fn load_world_data(
     Ok(world_data)
 }
 
+/// Terrain parameters for new worlds, tuned with the noise playground of the client
+pub fn load_generation_config(game_folder_paths: &GameFolderPaths) -> GenerationConfig {
+    let path = game_folder_paths
+        .game_folder_path
+        .join(GENERATION_CONFIG_FILE);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return GenerationConfig::default();
+    };
+
+    match from_str(&contents) {
+        Ok(config) => {
+            info!("Using the generation config from {}", path.display());
+            config
+        }
+        Err(err) => {
+            error!("Invalid generation config {} : {}", path.display(), err);
+            GenerationConfig::default()
+        }
+    }
+}
+
+/// Biome definitions of the assets folder, the files being read in the order of
+/// their names. Errors list every file or definition that can't be used.
+pub fn load_biome_definitions(
+    game_folder_paths: &GameFolderPaths,
+) -> Result<BiomeRegistry, Vec<String>> {
+    let folder = game_folder_paths.assets_folder_path.join(BIOMES_FOLDER);
+    let Ok(entries) = fs::read_dir(&folder) else {
+        return Ok(BiomeRegistry::default());
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect();
+    paths.sort();
+
+    let mut definitions = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        match fs::read_to_string(&path) {
+            Ok(contents) => match from_str::<BiomeDefinition>(&contents) {
+                Ok(definition) => definitions.push(definition),
+                Err(err) => errors.push(format!("Invalid biome {} : {}", path.display(), err)),
+            },
+            Err(err) => errors.push(format!("Could not read {} : {}", path.display(), err)),
+        }
+    }
+
+    let registry = BiomeRegistry::from_definitions(&definitions);
+    match registry {
+        Ok(registry) if errors.is_empty() => Ok(registry),
+        Ok(_) => Err(errors),
+        Err(invalid) => {
+            errors.extend(invalid);
+            Err(errors)
+        }
+    }
+}
+
 pub fn load_player_data(
     world_name: &str,
     player_id: &PlayerId,
     game_folder_paths: &GameFolderPaths,
+    spawn_position: Vec3,
 ) -> PlayerSave {
     let file_path: PathBuf = game_folder_paths
         .game_folder_path
@@ -69,8 +138,10 @@ pub fn load_player_data(
     }
 
     PlayerSave {
-        position: Vec3::new(0., 80., 0.),
+        position: spawn_position,
         camera_transform: Transform::default(),
         is_flying: false,
+        spawn_point: None,
+        dimension: DimensionId::Overworld,
     }
 }
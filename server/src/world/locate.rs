@@ -0,0 +1,96 @@
+use bevy::math::IVec2;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerId, ServerAnnouncement, ServerToClientMessage};
+use shared::world::{
+    locate_biome, BiomeType, GenerationConfig, ServerWorldMap, LOCATE_MAX_DISTANCE,
+};
+use shared::SEA_LEVEL;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::generation::surface_height;
+use crate::world::structures::{locate_structure, StructureKind, StructureNoises};
+
+/// Answers `/locate <name>` with the coordinates of the nearest such structure or
+/// biome, at ground level so that players can go there directly
+pub fn locate_command(
+    server: &mut RenetServer,
+    world_map: &ServerWorldMap,
+    seed: u32,
+    generation_config: GenerationConfig,
+    player_id: PlayerId,
+    name: &str,
+) {
+    let Some(player) = world_map.players.get(&player_id) else {
+        return;
+    };
+
+    let origin = IVec2::new(player.position.x as i32, player.position.z as i32);
+    let content = if let Some(kind) = StructureKind::from_name(name) {
+        let mut noises = StructureNoises::new(seed, generation_config);
+        match locate_structure(&mut noises, origin, kind, LOCATE_MAX_DISTANCE) {
+            None => format!("No {} within {} blocks", kind.name(), LOCATE_MAX_DISTANCE),
+            Some(found) => {
+                info!(
+                    "Player {} located {} at {:?}",
+                    player.name,
+                    kind.name(),
+                    found
+                );
+                format!(
+                    "Nearest {} is at {} {} {} ({} blocks away)",
+                    kind.name(),
+                    found.x,
+                    found.y + 1,
+                    found.z,
+                    IVec2::new(found.x, found.z)
+                        .as_vec2()
+                        .distance(origin.as_vec2())
+                        .round()
+                )
+            }
+        }
+    } else {
+        match BiomeType::from_name(name) {
+            None => {
+                let names: Vec<&str> = StructureKind::ALL
+                    .iter()
+                    .map(|kind| kind.name())
+                    .chain(BiomeType::ALL.iter().map(|biome| biome.name()))
+                    .collect();
+                format!(
+                    "Unknown structure or biome {}, try one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            }
+            Some(biome) => match locate_biome(seed, origin, biome) {
+                None => format!("No {} within {} blocks", biome.name(), LOCATE_MAX_DISTANCE),
+                Some(found) => {
+                    let y = surface_height(found.x, found.y, seed, generation_config)
+                        .max(SEA_LEVEL)
+                        + 1;
+                    info!(
+                        "Player {} located {} at {:?}",
+                        player.name,
+                        biome.name(),
+                        found
+                    );
+                    format!(
+                        "Nearest {} is at {} {} {} ({} blocks away)",
+                        biome.name(),
+                        found.x,
+                        y,
+                        found.y,
+                        found.as_vec2().distance(origin.as_vec2()).round()
+                    )
+                }
+            },
+        }
+    };
+
+    server.send_game_message(
+        player_id,
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+}
@@ -1,11 +1,45 @@
+pub mod anti_xray;
 pub mod background_generation;
+pub mod backup;
+pub mod boats;
 pub mod broadcast_world;
+pub mod buckets;
+pub mod cartography;
+pub mod chunk_store;
+pub mod currents;
 pub(crate) mod data;
+pub mod dimensions;
+pub mod drowning;
+pub mod effects;
+pub mod far_terrain;
+pub mod fishing;
+pub mod fluid;
+pub mod fluid_flow;
+pub mod freezing;
 pub mod generation;
+pub mod heatmap;
+pub mod idle;
+pub mod item_frames;
+pub mod limits;
 pub mod load_from_file;
+pub mod locate;
+pub mod moisture;
+pub mod prefabs;
+pub mod pregen;
+pub mod random_tick;
+pub mod rollback;
 pub mod save;
 pub mod simulation;
+pub mod sleep;
+pub mod snow;
+pub mod spatial;
+pub mod spawn;
 pub mod stacks;
+pub mod structures;
+pub mod water_audit;
+pub mod waystones;
+pub mod weather;
+pub mod wetness;
 
 use bevy::prelude::Event;
 use bevy::prelude::EventReader;
@@ -0,0 +1,161 @@
+//! Proximity of water, for the blocks that get wet next to it.
+//!
+//! Each chunk of the overworld gets a moisture field: for every block, the
+//! distance to the nearest water block (the largest of the distances along the
+//! three axes), up to `MOISTURE_RADIUS`. Fields are computed on the first query
+//! and thrown away whenever the chunk or one of its neighbours changes, so asking
+//! whether there is water nearby costs a lookup most of the time.
+
+use bevy::prelude::*;
+use shared::world::{global_to_chunk_local, BlockId, ServerChunkWorldMap, ServerWorldMap};
+use shared::CHUNK_SIZE;
+use std::collections::HashMap;
+
+/// Furthest water the moisture fields know of, in blocks
+pub const MOISTURE_RADIUS: i32 = 4;
+
+/// Distance stored for blocks with no water within `MOISTURE_RADIUS`
+const DRY: u8 = MOISTURE_RADIUS as u8 + 1;
+
+/// Width of the region scanned for water around a chunk
+const REGION_SIZE: i32 = CHUNK_SIZE + 2 * MOISTURE_RADIUS;
+
+#[derive(Resource, Default)]
+pub struct MoistureField {
+    /// Distance to the nearest water of each block of a chunk, indexed like
+    /// `field_index`
+    fields: HashMap<IVec3, Box<[u8]>>,
+}
+
+impl MoistureField {
+    /// Whether a water block lies within `radius` blocks of `position`, on every
+    /// axis. `radius` is capped to `MOISTURE_RADIUS`.
+    pub fn is_water_within(
+        &mut self,
+        chunks: &ServerChunkWorldMap,
+        position: IVec3,
+        radius: i32,
+    ) -> bool {
+        let (chunk_pos, local_pos) = global_to_chunk_local(&position);
+        let index = field_index(local_pos, CHUNK_SIZE);
+        let radius = radius.min(MOISTURE_RADIUS) as u8;
+
+        if let Some(field) = self.fields.get(&chunk_pos) {
+            return field[index] <= radius;
+        }
+
+        let (field, complete) = compute_field(chunks, chunk_pos);
+        let is_wet = field[index] <= radius;
+        // Water may still come with the missing neighbours
+        if complete {
+            self.fields.insert(chunk_pos, field);
+        }
+        is_wet
+    }
+
+    /// Forgets the fields that may see the blocks of `chunk_pos`
+    fn invalidate(&mut self, chunk_pos: IVec3) {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    self.fields.remove(&(chunk_pos + IVec3::new(dx, dy, dz)));
+                }
+            }
+        }
+    }
+}
+
+/// Index of a position within a cube of `size` blocks
+fn field_index(local_pos: IVec3, size: i32) -> usize {
+    ((local_pos.x * size + local_pos.y) * size + local_pos.z) as usize
+}
+
+/// Computes the field of a chunk, and whether all its neighbours were there to
+/// be scanned
+fn compute_field(chunks: &ServerChunkWorldMap, chunk_pos: IVec3) -> (Box<[u8]>, bool) {
+    let origin = chunk_pos * CHUNK_SIZE - IVec3::splat(MOISTURE_RADIUS);
+    let mut region = vec![DRY; (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize];
+    let mut complete = true;
+
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let neighbour = chunk_pos + IVec3::new(dx, dy, dz);
+                let Some(chunk) = chunks.map.get(&neighbour) else {
+                    complete = false;
+                    continue;
+                };
+                let base = neighbour * CHUNK_SIZE - origin;
+                for (local_pos, block) in chunk.map.iter() {
+                    let pos = base + *local_pos;
+                    let in_region =
+                        pos.cmpge(IVec3::ZERO).all() && pos.cmplt(IVec3::splat(REGION_SIZE)).all();
+                    if block.id == BlockId::Water && in_region {
+                        region[field_index(pos, REGION_SIZE)] = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    // The distance along the three axes is the largest of the three, so it can
+    // be spread one axis after the other
+    for axis in [IVec3::X, IVec3::Y, IVec3::Z] {
+        region = spread_along(&region, axis);
+    }
+
+    let mut field = vec![DRY; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize];
+    for x in 0..CHUNK_SIZE {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let local_pos = IVec3::new(x, y, z);
+                field[field_index(local_pos, CHUNK_SIZE)] =
+                    region[field_index(local_pos + IVec3::splat(MOISTURE_RADIUS), REGION_SIZE)];
+            }
+        }
+    }
+
+    (field.into_boxed_slice(), complete)
+}
+
+/// Lowers the distance of each block of the region to the distance of its
+/// neighbours along `axis`, plus the offset to them
+fn spread_along(region: &[u8], axis: IVec3) -> Vec<u8> {
+    let mut spread = region.to_vec();
+    for x in 0..REGION_SIZE {
+        for y in 0..REGION_SIZE {
+            for z in 0..REGION_SIZE {
+                let pos = IVec3::new(x, y, z);
+                let index = field_index(pos, REGION_SIZE);
+                for offset in 1..=MOISTURE_RADIUS {
+                    for neighbour in [pos + axis * offset, pos - axis * offset] {
+                        if neighbour.cmplt(IVec3::ZERO).any()
+                            || neighbour.cmpge(IVec3::splat(REGION_SIZE)).any()
+                        {
+                            continue;
+                        }
+                        let distance =
+                            region[field_index(neighbour, REGION_SIZE)].max(offset as u8);
+                        spread[index] = spread[index].min(distance);
+                    }
+                }
+            }
+        }
+    }
+    spread
+}
+
+/// Forgets the fields around the chunks changed this tick. Runs before the
+/// changed chunks are broadcast, which clears them.
+pub fn invalidate_moisture_system(
+    world_map: Res<ServerWorldMap>,
+    mut moisture: ResMut<MoistureField>,
+) {
+    for chunk_pos in world_map.chunks.chunks_to_update.iter() {
+        moisture.invalidate(*chunk_pos);
+    }
+    // Unloaded chunks have to be computed again anyway
+    moisture
+        .fields
+        .retain(|chunk_pos, _| world_map.chunks.map.contains_key(chunk_pos));
+}
@@ -0,0 +1,110 @@
+//! Builds the prefabs sent by creative players, see `shared::world::prefabs`.
+
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerId, ServerAnnouncement, ServerToClientMessage};
+use shared::players::blocks::BlockInteractionOutcome;
+use shared::players::GameMode;
+use shared::world::{
+    global_to_chunk_local, DimensionId, PrefabPlacement, ServerWorldMap, WorldMap,
+    PREFAB_PLACE_DISTANCE,
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::heatmap::BlockHeatmap;
+use crate::world::rollback::BlockChangeLog;
+
+fn announce(server: &mut RenetServer, player_id: PlayerId, content: String) {
+    server.send_game_message(
+        player_id,
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+}
+
+/// Fills the empty blocks of the placement, logged like blocks placed by hand so
+/// that a prefab can be rolled back
+pub fn place_prefab_command(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    block_log: &mut BlockChangeLog,
+    heatmap: &mut BlockHeatmap,
+    player_id: PlayerId,
+    placement: PrefabPlacement,
+) {
+    let Some(player) = world_map.players.get(&player_id) else {
+        return;
+    };
+    let name = player.name.clone();
+    let dimension = player.dimension;
+
+    if player.game_mode != GameMode::Creative {
+        warn!(
+            "Player {} tried to place a prefab outside of creative mode",
+            name
+        );
+        announce(
+            server,
+            player_id,
+            "Prefabs can only be placed in creative mode".to_string(),
+        );
+        return;
+    }
+
+    if let Err(error) = placement.validate() {
+        warn!("Player {} sent an invalid prefab: {:?}", name, error);
+        announce(server, player_id, error.message());
+        return;
+    }
+
+    let min = placement.origin.as_vec3();
+    let max = (placement.origin + placement.size).as_vec3();
+    let distance = player.position.clamp(min, max).distance(player.position);
+    if distance > PREFAB_PLACE_DISTANCE {
+        announce(
+            server,
+            player_id,
+            format!("Prefabs are placed within {PREFAB_PLACE_DISTANCE} blocks"),
+        );
+        return;
+    }
+
+    let chunks = world_map.dimension_chunks_mut(dimension);
+    if !placement
+        .world_blocks()
+        .all(|(position, _)| chunks.has_chunk(&global_to_chunk_local(&position).0))
+    {
+        announce(
+            server,
+            player_id,
+            "This prefab reaches into unloaded chunks".to_string(),
+        );
+        return;
+    }
+
+    let mut outcomes = Vec::new();
+    for (position, block) in placement.world_blocks() {
+        if chunks.get_block_by_coordinates(&position).is_none() {
+            chunks.set_block(&position, block);
+            outcomes.push(BlockInteractionOutcome::Placed { block, position });
+        }
+    }
+
+    // Like blocks placed by hand, only the overworld keeps a log
+    if dimension == DimensionId::Overworld {
+        block_log.record(&name, &outcomes);
+        heatmap.record(player_id, &outcomes);
+    }
+
+    info!(
+        "Player {} placed a prefab of {} blocks at {:?}",
+        name,
+        outcomes.len(),
+        placement.origin
+    );
+    announce(
+        server,
+        player_id,
+        format!("Placed {} blocks", outcomes.len()),
+    );
+}
@@ -0,0 +1,209 @@
+//! Generates the chunks around the world spawn ahead of time, without accepting
+//! players, so that large worlds don't stutter on their first exploration.
+//! Started with `--pregen <radius>` while the server of the world is stopped.
+//!
+//! Only the overworld is pregenerated. Chunks that are already saved are kept,
+//! and corrupt ones stay empty until an operator regenerates them.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_app::ScheduleRunnerPlugin;
+use bevy_log::{error, info, LogPlugin};
+use shared::world::{
+    block_to_chunk_coord, find_world_spawn, DimensionId, GenerationConfig, ServerChunkWorldMap,
+    TerrainNoise, WorldGenPreset, WorldSeed,
+};
+use shared::{GameFolderPaths, CHUNK_SIZE, SEA_LEVEL};
+
+use crate::init::install_biome_definitions;
+use crate::world::background_generation::integrate_generated_chunk;
+use crate::world::chunk_store::{load_chunks, save_chunks, saved_timestamps, CorruptChunks};
+use crate::world::data::SAVE_PATH;
+use crate::world::generation::generate_chunk;
+use crate::world::load_from_file::load_world_data;
+use crate::world::save::{save_world_data, WorldData};
+
+#[derive(Resource)]
+struct Pregeneration {
+    chunks: ServerChunkWorldMap,
+    corrupt_chunks: CorruptChunks,
+    /// Columns left to generate, the nearest to the spawn last
+    columns: Vec<IVec2>,
+    total_columns: usize,
+    generated_chunks: usize,
+    reported_percent: usize,
+    seed: u32,
+    config: GenerationConfig,
+    world_folder: PathBuf,
+    /// Saved along the chunks, the world spawn being chosen if it wasn't yet
+    world_data: WorldData,
+}
+
+impl Pregeneration {
+    /// Generates the missing chunks of a column, from the bedrock to the top of
+    /// the terrain and up to the last chunk trees and structures reach
+    fn generate_column(&mut self, column: IVec2) {
+        let top = column_top_chunk(column, self.seed, self.config);
+        for y in 0.. {
+            let chunk_pos = IVec3::new(column.x, y, column.y);
+            if !self.chunks.map.contains_key(&chunk_pos)
+                && !self
+                    .corrupt_chunks
+                    .0
+                    .contains(&(DimensionId::Overworld, chunk_pos))
+            {
+                let result = generate_chunk(chunk_pos, self.seed, self.config);
+                integrate_generated_chunk(&mut self.chunks, chunk_pos, result);
+                self.generated_chunks += 1;
+            }
+
+            let empty = self
+                .chunks
+                .map
+                .get(&chunk_pos)
+                .is_none_or(|chunk| chunk.map.is_empty());
+            if y >= top && empty {
+                break;
+            }
+        }
+    }
+
+    fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        save_chunks(&self.world_folder, &mut self.chunks)?;
+        // The flora of the chunks at the edge grows once their neighbours exist
+        self.world_data.pending_flora = self.chunks.pending_flora.clone();
+        save_world_data(
+            &self.world_data,
+            &self.world_folder.join("world.ron").to_string_lossy(),
+        )
+    }
+}
+
+/// Columns of chunks within `radius` of the center, the farthest first
+fn columns_around(center: IVec2, radius: i32) -> Vec<IVec2> {
+    let mut columns: Vec<IVec2> = (-radius..=radius)
+        .flat_map(|x| (-radius..=radius).map(move |z| IVec2::new(x, z)))
+        .filter(|offset| offset.length_squared() <= radius * radius)
+        .map(|offset| center + offset)
+        .collect();
+    columns.sort_by_key(|column| std::cmp::Reverse((*column - center).length_squared()));
+    columns
+}
+
+/// Highest chunk of a column holding terrain or water
+fn column_top_chunk(column: IVec2, seed: u32, config: GenerationConfig) -> i32 {
+    let mut terrain = TerrainNoise::new(seed, config);
+    let mut top = SEA_LEVEL;
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let height = terrain.height(column.x * CHUNK_SIZE + dx, column.y * CHUNK_SIZE + dz);
+            top = top.max(terrain.top(height));
+        }
+    }
+    block_to_chunk_coord(top)
+}
+
+fn pregeneration_system(mut pregeneration: ResMut<Pregeneration>, mut exit: EventWriter<AppExit>) {
+    let Some(column) = pregeneration.columns.pop() else {
+        match pregeneration.save() {
+            Ok(()) => {
+                info!(
+                    "Pregeneration done, {} chunks generated",
+                    pregeneration.generated_chunks
+                );
+                exit.write(AppExit::Success);
+            }
+            Err(err) => {
+                error!("Could not save the pregenerated chunks : {}", err);
+                exit.write(AppExit::error());
+            }
+        }
+        return;
+    };
+
+    pregeneration.generate_column(column);
+
+    let done = pregeneration.total_columns - pregeneration.columns.len();
+    let percent = done * 100 / pregeneration.total_columns;
+    if percent > pregeneration.reported_percent {
+        pregeneration.reported_percent = percent;
+        info!(
+            "Pregenerating: {}% ({}/{} columns, {} chunks generated)",
+            percent, done, pregeneration.total_columns, pregeneration.generated_chunks
+        );
+    }
+}
+
+/// Generates and saves the chunks within `radius` chunks of the world spawn,
+/// creating the world if it doesn't exist yet
+pub fn pregenerate(
+    world_name: &str,
+    preset: WorldGenPreset,
+    seed: Option<WorldSeed>,
+    radius: u32,
+    game_folder_paths: GameFolderPaths,
+) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)));
+    app.add_plugins(LogPlugin::default());
+
+    if !install_biome_definitions(&game_folder_paths) {
+        error!("Fix or remove the biome definitions to pregenerate the world");
+        return;
+    }
+
+    let mut world_data = match load_world_data(world_name, preset, seed, &game_folder_paths) {
+        Ok(data) => data,
+        Err(err) => {
+            error!("Failed to load world {} : {}", world_name, err);
+            return;
+        }
+    };
+
+    let world_folder = game_folder_paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(world_name);
+    let (chunks, corrupt_chunks) = load_chunks(&world_folder, DimensionId::Overworld);
+    let saved_ts = saved_timestamps(&chunks);
+    let mut map = std::mem::take(&mut world_data.map);
+    map.extend(chunks);
+    let mut pending_flora = std::mem::take(&mut world_data.pending_flora);
+    for (_, position) in corrupt_chunks.0.iter() {
+        map.remove(position);
+        pending_flora.remove(position);
+    }
+
+    let spawn = *world_data
+        .spawn
+        .get_or_insert_with(|| find_world_spawn(world_data.seed.0, world_data.generation));
+    let center = IVec2::new(block_to_chunk_coord(spawn.x), block_to_chunk_coord(spawn.z));
+    let columns = columns_around(center, radius as i32);
+    info!(
+        "Pregenerating {} columns of chunks around the world spawn {:?}",
+        columns.len(),
+        spawn
+    );
+
+    app.insert_resource(Pregeneration {
+        chunks: ServerChunkWorldMap {
+            map,
+            pending_flora,
+            saved_ts,
+            ..default()
+        },
+        corrupt_chunks,
+        total_columns: columns.len(),
+        columns,
+        generated_chunks: 0,
+        reported_percent: 0,
+        seed: world_data.seed.0,
+        config: world_data.generation,
+        world_folder,
+        world_data,
+    });
+    app.add_systems(Update, pregeneration_system);
+    app.run();
+}
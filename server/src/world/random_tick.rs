@@ -0,0 +1,175 @@
+//! Random ticking of the surface around players.
+//!
+//! Every second, random columns of the chunks around players are picked and their
+//! topmost block is handed to the slow environmental processes (freezing, snow,
+//! wetness).
+//! Any given column is only ticked every few seconds, so these changes spread
+//! gradually instead of all at once.
+
+use bevy::prelude::*;
+use rand::Rng;
+use shared::fluid::FluidKind;
+use shared::world::{
+    calculate_temperature_humidity_with_noises, global_to_chunk_local,
+    world_position_to_chunk_position, BiomeClimate, BlockData, ClimateNoises, DimensionId,
+    ServerWorldMap, WorldMap, WorldSeed,
+};
+use shared::{CHUNK_SIZE, TICKS_PER_SECOND};
+use std::collections::HashSet;
+
+use super::moisture::MoistureField;
+use super::weather::Weather;
+use super::{freezing, snow, wetness};
+
+/// Chunks around a player that receive random ticks
+const RANDOM_TICK_RADIUS_CHUNKS: i32 = 2;
+/// Columns picked in each chunk every second, out of `CHUNK_SIZE^2`
+const RANDOM_TICKS_PER_CHUNK: usize = 16;
+
+/// Topmost block of a column exposed to the sky, passed to random tick handlers
+pub struct SurfaceBlock {
+    pub position: IVec3,
+    pub block: BlockData,
+    pub climate: BiomeClimate,
+}
+
+pub enum BlockChange {
+    /// Changes an existing block in place, or creates it
+    Set(IVec3, BlockData),
+    Remove(IVec3),
+}
+
+/// Finds the topmost block of a column within a chunk, if nothing sits on top of it
+/// in the chunk above.
+fn find_surface_block(
+    world_map: &ServerWorldMap,
+    chunk_pos: IVec3,
+    x: i32,
+    z: i32,
+) -> Option<(IVec3, BlockData)> {
+    let base = chunk_pos * CHUNK_SIZE;
+
+    let above_base = base + IVec3::new(x, CHUNK_SIZE, z);
+    let covered = (0..CHUNK_SIZE).any(|dy| {
+        world_map
+            .chunks
+            .get_block_by_coordinates(&(above_base + IVec3::new(0, dy, 0)))
+            .is_some()
+    });
+    if covered {
+        return None;
+    }
+
+    (0..CHUNK_SIZE).rev().find_map(|y| {
+        let position = base + IVec3::new(x, y, z);
+        world_map
+            .chunks
+            .get_block_by_coordinates(&position)
+            .map(|block| (position, *block))
+    })
+}
+
+pub fn random_tick_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+    mut moisture: ResMut<MoistureField>,
+    mut tick_counter: Local<u64>,
+) {
+    *tick_counter += 1;
+    if *tick_counter < TICKS_PER_SECOND {
+        return;
+    }
+    *tick_counter = 0;
+
+    let mut chunks = HashSet::new();
+    // Nothing grows nor melts in the other dimensions
+    for player in world_map.players_in(DimensionId::Overworld) {
+        let player_chunk = world_position_to_chunk_position(player.position);
+        for dx in -RANDOM_TICK_RADIUS_CHUNKS..=RANDOM_TICK_RADIUS_CHUNKS {
+            for dy in -RANDOM_TICK_RADIUS_CHUNKS..=RANDOM_TICK_RADIUS_CHUNKS {
+                for dz in -RANDOM_TICK_RADIUS_CHUNKS..=RANDOM_TICK_RADIUS_CHUNKS {
+                    chunks.insert(player_chunk + IVec3::new(dx, dy, dz));
+                }
+            }
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut climate_noises = ClimateNoises::new(seed.0);
+    let mut changes = Vec::new();
+
+    for chunk_pos in chunks {
+        if !world_map.chunks.has_chunk(&chunk_pos) {
+            continue;
+        }
+
+        for _ in 0..RANDOM_TICKS_PER_CHUNK {
+            let x = rng.gen_range(0..CHUNK_SIZE);
+            let z = rng.gen_range(0..CHUNK_SIZE);
+            let Some((position, block)) = find_surface_block(&world_map, chunk_pos, x, z) else {
+                continue;
+            };
+
+            let climate = calculate_temperature_humidity_with_noises(
+                position.x,
+                position.z,
+                &mut climate_noises,
+            );
+            let surface = SurfaceBlock {
+                position,
+                block,
+                climate,
+            };
+
+            changes.extend(freezing::freeze_or_melt(
+                &surface,
+                &world_map.chunks,
+                &mut rng,
+            ));
+            changes.extend(snow::accumulate_or_melt(&surface, &weather, &mut rng));
+            changes.extend(wetness::soak_or_dry(
+                &surface,
+                &world_map.chunks,
+                &mut moisture,
+                &weather,
+                &mut rng,
+            ));
+        }
+    }
+
+    apply_block_changes(&mut world_map, changes);
+}
+
+/// Applies the changes in order and sends the changed chunks to the players
+pub fn apply_block_changes(
+    world_map: &mut ServerWorldMap,
+    changes: impl IntoIterator<Item = BlockChange>,
+) {
+    for change in changes {
+        match change {
+            BlockChange::Set(position, block) => {
+                // Changed in place so that it is not recorded as a water edit
+                // Never create chunks that were not generated yet
+                if !world_map
+                    .chunks
+                    .has_chunk(&global_to_chunk_local(&position).0)
+                {
+                    continue;
+                }
+                match world_map.chunks.get_block_mut_by_coordinates(&position) {
+                    Some(existing) => *existing = block,
+                    None => world_map.chunks.set_block(&position, block),
+                }
+                world_map.chunks.mark_block_for_update(&position);
+                // Melted ice flows again
+                if FluidKind::flowing_as(block.id).is_some() {
+                    world_map.chunks.block_edits.push(position);
+                }
+            }
+            BlockChange::Remove(position) => {
+                world_map.chunks.remove_block_by_coordinates(&position);
+            }
+        }
+    }
+}
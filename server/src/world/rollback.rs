@@ -0,0 +1,362 @@
+//! Log of the blocks placed and broken by players, behind `/rollback` and the
+//! `rollback` console command.
+//!
+//! Changes are appended to the `block_log.bin` file of the world as they happen,
+//! so that griefing can still be undone after a restart. Player names are only
+//! written once, changes refer to them by their index.
+//!
+//! Changes are kept for a number of days set in the `[block_log]` table of
+//! `server.toml`. Older ones are forgotten, and the file is written again
+//! without them once they make up most of it, as well as when the world is
+//! loaded:
+//!
+//! ```toml
+//! [block_log]
+//! retention_days = 30
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy_log::{error, info, warn};
+use bevy_renet::renet::RenetServer;
+use serde::{Deserialize, Serialize};
+use shared::messages::{PlayerId, ServerAnnouncement, ServerToClientMessage};
+use shared::players::blocks::BlockInteractionOutcome;
+use shared::world::{BlockData, RollbackRequest, RollbackTarget, ServerWorldMap, WorldMap};
+use shared::GameServerConfig;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::operators::Operators;
+use crate::world::random_tick::{apply_block_changes, BlockChange};
+use crate::world::save::write_atomically;
+
+const BLOCK_LOG_FILE: &str = "block_log.bin";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct BlockLogConfig {
+    /// Days a change can still be rolled back
+    pub retention_days: u64,
+}
+
+impl Default for BlockLogConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum LoggedChange {
+    Placed(BlockData),
+    Broken(BlockData),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct LoggedBlockChange {
+    /// Seconds since the Unix epoch
+    time: u64,
+    /// Index of the player in `BlockChangeLog::players`
+    player: u32,
+    position: IVec3,
+    change: LoggedChange,
+}
+
+/// What is appended to the log file
+#[derive(Serialize, Deserialize, Debug)]
+enum LogRecord {
+    /// First change of a player, who gets the next index
+    Player(String),
+    Change(LoggedBlockChange),
+}
+
+/// Which changes a rollback undoes
+pub enum RollbackFilter {
+    Player(String),
+    /// Columns within `radius` blocks of `center`
+    Area {
+        center: IVec3,
+        radius: u32,
+    },
+}
+
+#[derive(Resource, Default)]
+pub struct BlockChangeLog {
+    players: Vec<String>,
+    /// Changes within the retention, the oldest first
+    changes: Vec<LoggedBlockChange>,
+    /// Changes forgotten since the file was last written again
+    forgotten: usize,
+    retention_secs: u64,
+    path: PathBuf,
+    file: Option<File>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl BlockChangeLog {
+    /// Reads the log of the world, written again without the changes past the
+    /// retention, and keeps its file open to append the next changes
+    pub fn open(world_folder: &Path, config: BlockLogConfig) -> Self {
+        let path = world_folder.join(BLOCK_LOG_FILE);
+        let mut log = Self {
+            retention_secs: config.retention_days * SECONDS_PER_DAY,
+            path: path.clone(),
+            ..default()
+        };
+
+        if let Ok(file) = File::open(&path) {
+            let mut reader = BufReader::new(file);
+            // A record cut short by a crash ends the log
+            while let Ok(record) = bincode::deserialize_from::<_, LogRecord>(&mut reader) {
+                match record {
+                    LogRecord::Player(name) => log.players.push(name),
+                    LogRecord::Change(change) => log.changes.push(change),
+                }
+            }
+            log.forget_expired();
+            info!(
+                "Loaded {} block changes of {} players from {:?}",
+                log.changes.len(),
+                log.players.len(),
+                path
+            );
+        }
+
+        if let Err(e) = fs::create_dir_all(world_folder).and_then(|_| log.compact()) {
+            error!(
+                "Failed to open the block log at {:?}, changes won't survive a restart: {}",
+                path, e
+            );
+        }
+        log
+    }
+
+    /// Forgets the changes past the retention
+    fn forget_expired(&mut self) {
+        let oldest = now_secs().saturating_sub(self.retention_secs);
+        let expired = self.changes.partition_point(|change| change.time < oldest);
+        self.changes.drain(..expired);
+        self.forgotten += expired;
+    }
+
+    /// Writes the log file again with the remembered changes only, the players
+    /// who made none of them being left out
+    fn compact(&mut self) -> io::Result<()> {
+        self.file = None;
+        // Changes of players whose record was lost can't be told apart anymore
+        self.changes
+            .retain(|change| (change.player as usize) < self.players.len());
+        let mut players: Vec<String> = Vec::new();
+        let mut records = Vec::new();
+        for change in self.changes.iter_mut() {
+            let name = &self.players[change.player as usize];
+            change.player = match players.iter().position(|player| player == name) {
+                Some(index) => index as u32,
+                None => {
+                    players.push(name.clone());
+                    records.push(LogRecord::Player(name.clone()));
+                    players.len() as u32 - 1
+                }
+            };
+            records.push(LogRecord::Change(*change));
+        }
+        self.players = players;
+        self.forgotten = 0;
+
+        let mut bytes = Vec::new();
+        for record in records.iter() {
+            bincode::serialize_into(&mut bytes, record).map_err(io::Error::other)?;
+        }
+        write_atomically(&self.path, &bytes)?;
+        self.file = Some(OpenOptions::new().append(true).open(&self.path)?);
+        Ok(())
+    }
+
+    fn append(&mut self, record: &LogRecord) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let written = bincode::serialize(record)
+            .map_err(io::Error::other)
+            .and_then(|bytes| file.write_all(&bytes));
+        if let Err(e) = written {
+            warn!("Failed to append to the block log: {}", e);
+        }
+    }
+
+    fn player_index(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.players.iter().position(|player| player == name) {
+            return index as u32;
+        }
+        self.players.push(name.to_string());
+        self.append(&LogRecord::Player(name.to_string()));
+        self.players.len() as u32 - 1
+    }
+
+    /// Logs the blocks `player` just mined or placed
+    pub fn record(&mut self, player: &str, outcomes: &[BlockInteractionOutcome]) {
+        if outcomes.is_empty() {
+            return;
+        }
+        self.forget_expired();
+        if self.forgotten > self.changes.len() {
+            if let Err(e) = self.compact() {
+                error!(
+                    "Failed to write the block log at {:?} again, changes won't survive a restart: {}",
+                    self.path, e
+                );
+            }
+        }
+        let player = self.player_index(player);
+        let time = now_secs();
+        for outcome in outcomes {
+            let (position, change) = match *outcome {
+                BlockInteractionOutcome::Placed { block, position } => {
+                    (position, LoggedChange::Placed(block))
+                }
+                BlockInteractionOutcome::Broken { block, position } => (
+                    position,
+                    LoggedChange::Broken(BlockData {
+                        breaking_progress: 0,
+                        ..block
+                    }),
+                ),
+            };
+            let change = LoggedBlockChange {
+                time,
+                player,
+                position,
+                change,
+            };
+            self.changes.push(change);
+            self.append(&LogRecord::Change(change));
+        }
+    }
+
+    fn matches(&self, change: &LoggedBlockChange, filter: &RollbackFilter) -> bool {
+        match filter {
+            RollbackFilter::Player(name) => self
+                .players
+                .get(change.player as usize)
+                .is_some_and(|player| player.eq_ignore_ascii_case(name)),
+            RollbackFilter::Area { center, radius } => {
+                let offset = (change.position - *center).abs();
+                offset.x <= *radius as i32 && offset.z <= *radius as i32
+            }
+        }
+    }
+
+    /// Undoes the matching changes of the last `since_secs` seconds, the most recent
+    /// first, and returns the number of blocks restored.
+    ///
+    /// Blocks changed again since then by someone else are left alone.
+    pub fn rollback(
+        &self,
+        world_map: &mut ServerWorldMap,
+        filter: &RollbackFilter,
+        since_secs: u64,
+    ) -> usize {
+        let since = now_secs().saturating_sub(since_secs);
+        // Blocks as they will be once the previous changes are applied
+        let mut pending: HashMap<IVec3, Option<BlockData>> = HashMap::new();
+        let mut changes = Vec::new();
+
+        for change in self
+            .changes
+            .iter()
+            .rev()
+            .take_while(|change| change.time >= since)
+            .filter(|change| self.matches(change, filter))
+        {
+            let current = *pending.entry(change.position).or_insert_with(|| {
+                world_map
+                    .chunks
+                    .get_block_by_coordinates(&change.position)
+                    .copied()
+            });
+            let undo = match change.change {
+                LoggedChange::Placed(block)
+                    if current.is_some_and(|current| current.id == block.id) =>
+                {
+                    BlockChange::Remove(change.position)
+                }
+                LoggedChange::Broken(block) if current.is_none() => {
+                    BlockChange::Set(change.position, block)
+                }
+                _ => continue,
+            };
+            pending.insert(
+                change.position,
+                match change.change {
+                    LoggedChange::Placed(_) => None,
+                    LoggedChange::Broken(block) => Some(block),
+                },
+            );
+            changes.push(undo);
+        }
+
+        let restored = changes.len();
+        apply_block_changes(world_map, changes);
+        restored
+    }
+}
+
+/// Answers `/rollback`, for operators only
+pub fn rollback_command(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    block_log: &BlockChangeLog,
+    operators: &Operators,
+    config: &GameServerConfig,
+    player_id: PlayerId,
+    request: RollbackRequest,
+) {
+    let Some(player) = world_map.players.get(&player_id) else {
+        return;
+    };
+    let name = player.name.clone();
+
+    if !operators.is_operator(&name, config.is_solo) {
+        warn!(
+            "Player {} tried to roll back without being an operator",
+            name
+        );
+        server.send_game_message(
+            player_id,
+            ServerToClientMessage::Announcement(ServerAnnouncement {
+                content: "Only operators can roll back changes".to_string(),
+            }),
+        );
+        return;
+    }
+
+    let filter = match request.target {
+        RollbackTarget::Player(name) => RollbackFilter::Player(name),
+        RollbackTarget::Radius(radius) => RollbackFilter::Area {
+            center: player.position.floor().as_ivec3(),
+            radius,
+        },
+    };
+    let restored = block_log.rollback(world_map, &filter, request.since_secs);
+    info!(
+        "Player {} rolled back {} blocks of the last {} seconds",
+        name, restored, request.since_secs
+    );
+    server.send_game_message(
+        player_id,
+        ServerToClientMessage::Announcement(ServerAnnouncement {
+            content: format!("Rolled back {} blocks", restored),
+        }),
+    );
+}
@@ -10,9 +10,18 @@ use shared::world::ServerItemStack;
 use shared::world::ServerMob;
 use shared::world::ServerWorldMap;
 use shared::world::WorldSeed;
+use shared::world::WorldSpawn;
+use shared::world::{
+    BoatId, Difficulty, FloraSite, GenerationConfig, ItemFrameRegistry, ServerBoat,
+    WaystoneRegistry,
+};
 use shared::GameFolderPaths;
 use std::collections::HashMap;
-use std::{fs::File, io::Write, path::Path};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
 
 #[derive(Event)]
 pub enum SaveRequestEvent {
@@ -20,21 +29,43 @@ pub enum SaveRequestEvent {
     Player(PlayerId),
 }
 
+use crate::world::chunk_store::save_chunks;
 use crate::world::data::SAVE_PATH;
 
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 pub struct WorldData {
+    /// Chunks of worlds saved before chunks got their own files, see `chunk_store.rs`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub map: HashMap<IVec3, ServerChunk>,
     pub mobs: HashMap<MobId, ServerMob>,
     pub seed: WorldSeed,
     pub name: String,
     pub time: u64,
     pub item_stacks: Vec<ServerItemStack>,
+    #[serde(default)]
+    pub waystones: WaystoneRegistry,
+    #[serde(default)]
+    pub item_frames: ItemFrameRegistry,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub boats: HashMap<BoatId, ServerBoat>,
+    /// Ground block of the world spawn, chosen on the first load of older worlds
+    #[serde(default)]
+    pub spawn: Option<IVec3>,
+    /// Worlds saved before it was configurable keep their classic terrain
+    #[serde(default = "GenerationConfig::classic")]
+    pub generation: GenerationConfig,
+    /// Flora of the overworld chunks still waiting for their neighbours
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pending_flora: HashMap<IVec3, Vec<FloraSite>>,
 }
 
 pub fn save_world_system(
-    world_map: Res<ServerWorldMap>,
+    mut world_map: ResMut<ServerWorldMap>,
     world_seed: Res<WorldSeed>,
+    generation_config: Res<GenerationConfig>,
+    world_spawn: Res<WorldSpawn>,
     game_folder_path: Res<GameFolderPaths>,
     time: Res<ServerTime>,
     mut event: EventReader<SaveRequestEvent>,
@@ -68,13 +99,38 @@ pub fn save_world_system(
 
     // If a save was requested by the user
     if save_requested {
+        let world_folder = game_folder_path
+            .game_folder_path
+            .join(SAVE_PATH)
+            .join(&world_map.name);
+        let world_map = world_map.as_mut();
+        match save_chunks(&world_folder, &mut world_map.chunks) {
+            Ok(written) => info!("Saved {} updated chunks", written),
+            Err(e) => error!("Failed to save chunks: {}", e),
+        }
+        for (dimension, chunks) in world_map.dimensions.iter_mut() {
+            let Some(folder) = dimension.save_folder() else {
+                continue;
+            };
+            if let Err(e) = save_chunks(&world_folder.join(folder), chunks) {
+                error!("Failed to save chunks of the {}: {}", dimension.name(), e);
+            }
+        }
+
         let world_data = WorldData {
-            map: world_map.chunks.map.clone(),
+            map: HashMap::new(),
             mobs: world_map.mobs.clone(),
             item_stacks: world_map.item_stacks.clone(),
             name: world_map.name.clone(),
             seed: *world_seed,
             time: time.0,
+            waystones: world_map.waystones.clone(),
+            item_frames: world_map.item_frames.clone(),
+            difficulty: world_map.difficulty,
+            boats: world_map.boats.clone(),
+            spawn: Some(world_spawn.0),
+            generation: *generation_config,
+            pending_flora: world_map.chunks.pending_flora.clone(),
         };
 
         // define save file path
@@ -93,6 +149,22 @@ pub fn save_world_system(
     }
 }
 
+/// Extension of the files saves are written to before replacing the actual save
+pub const TEMPORARY_SAVE_EXTENSION: &str = "tmp";
+
+/// Writes to a temporary file first, then moves it over the previous save, so that
+/// a crash while saving never leaves a truncated save behind
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".");
+    temporary_path.push(TEMPORARY_SAVE_EXTENSION);
+
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)
+}
+
 pub fn save_world_data(
     world_data: &WorldData,
     file_path: &str,
@@ -105,9 +177,7 @@ pub fn save_world_data(
 
     // serialize combined data (map + seed)
     let serialized = ron::ser::to_string_pretty(world_data, pretty_config)?;
-    let path = Path::new(file_path);
-    let mut file = File::create(path)?;
-    file.write_all(serialized.as_bytes())?;
+    write_atomically(Path::new(file_path), serialized.as_bytes())?;
     info!("World data saved to {}", file_path);
     Ok(())
 }
@@ -124,9 +194,7 @@ pub fn save_player_data(
 
     // Serialize Complete player data
     let serialized = ron::ser::to_string_pretty(player, pretty_config)?;
-    let path = Path::new(&file_path);
-    let mut file = File::create(path)?;
-    file.write_all(serialized.as_bytes())?;
+    write_atomically(Path::new(&file_path), serialized.as_bytes())?;
 
     Ok(())
 }
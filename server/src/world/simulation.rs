@@ -6,10 +6,21 @@ use bevy_renet::renet::{ClientId, RenetServer};
 use shared::{
     messages::{NetworkAction, PlayerFrameInput, PlayerUpdateEvent},
     players::{blocks::CallerType, simulation::simulate_player_actions},
-    world::ServerWorldMap,
+    world::{dimension_chunks_mut, DimensionId, EntityHistory, ServerWorldMap, SpatialHash},
 };
 
+use crate::init::ServerTime;
+use crate::mob::combat::{attack, LastAttacks};
+use crate::mob::{leash::use_lead, use_spawn_egg};
 use crate::network::extensions::SendGameMessageExtension;
+use crate::world::boats::{attack_boat, use_boat};
+use crate::world::effects::use_potion;
+use crate::world::fishing::{use_fishing_rod, FishingLines};
+use crate::world::heatmap::BlockHeatmap;
+use crate::world::item_frames::{drop_broken_item_frames, use_item_frame};
+use crate::world::rollback::BlockChangeLog;
+use crate::world::sleep::{use_bed, SleepingPlayers};
+use crate::world::waystones::use_waystone;
 
 #[derive(Event, Debug)]
 pub struct PlayerInputsEvent {
@@ -21,10 +32,24 @@ pub fn handle_player_inputs_system(
     mut events: EventReader<PlayerInputsEvent>,
     mut world_map: ResMut<ServerWorldMap>,
     mut server: ResMut<RenetServer>,
+    mut holding_right_click: Local<HashSet<u64>>,
+    time: Res<ServerTime>,
+    mut sleeping: ResMut<SleepingPlayers>,
+    spatial: Res<SpatialHash>,
+    history: Res<EntityHistory>,
+    mut last_attacks: ResMut<LastAttacks>,
+    mut fishing_lines: ResMut<FishingLines>,
+    mut heatmap: ResMut<BlockHeatmap>,
+    mut block_log: ResMut<BlockChangeLog>,
 ) {
     let world_map = world_map.as_mut();
     let players = &mut world_map.players;
-    let chunks = &mut world_map.chunks;
+    let overworld = &mut world_map.chunks;
+    let dimensions = &mut world_map.dimensions;
+    let mobs = &mut world_map.mobs;
+    let waystones = &mut world_map.waystones;
+    let item_frames = &mut world_map.item_frames;
+    let boats = &mut world_map.boats;
 
     let mut player_actions = HashMap::<u64, HashSet<NetworkAction>>::new();
     for client_id in players.keys() {
@@ -33,8 +58,62 @@ pub fn handle_player_inputs_system(
 
     for ev in events.read() {
         let player = players.get_mut(&ev.client_id).unwrap();
+        // Mobs, item frames, waystones and the block logs only exist in the overworld
+        let in_overworld = player.dimension == DimensionId::Overworld;
+        let chunks = dimension_chunks_mut(overworld, dimensions, player.dimension);
 
-        simulate_player_actions(player, chunks, &ev.input.clone(), CallerType::Server);
+        let outcomes =
+            simulate_player_actions(player, chunks, &ev.input.clone(), CallerType::Server);
+        if in_overworld {
+            drop_broken_item_frames(&mut server, player, item_frames, &outcomes);
+            heatmap.record(player.id, &outcomes);
+            block_log.record(&player.name, &outcomes);
+        }
+        fishing_lines.follow_hotbar(player.id, ev.input.hotbar_slot);
+
+        if in_overworld
+            && ev.input.inputs.contains(&NetworkAction::Attack)
+            && !attack_boat(player, chunks, boats, &ev.input)
+        {
+            attack(
+                &mut server,
+                player,
+                chunks,
+                mobs,
+                &history,
+                &ev.input,
+                &mut last_attacks,
+                time.0,
+            );
+        }
+
+        // Right click is sent every frame while held, beds, waystones, item frames,
+        // leads, boats, fishing rods, potions and spawn eggs are only used on press
+        if ev.input.inputs.contains(&NetworkAction::RightClick) {
+            let pressed = holding_right_click.insert(ev.client_id);
+            if pressed && !in_overworld {
+                use_potion(player, &ev.input);
+            } else if pressed
+                && !use_bed(
+                    &mut server,
+                    player,
+                    chunks,
+                    &mut sleeping,
+                    &ev.input,
+                    time.0,
+                )
+                && !use_waystone(&mut server, player, chunks, waystones, &ev.input, time.0)
+                && !use_item_frame(&mut server, player, chunks, item_frames, &ev.input)
+                && !use_lead(player, chunks, mobs, &spatial, &ev.input)
+                && !use_boat(player, chunks, boats, &ev.input)
+                && !use_fishing_rod(&mut server, player, &mut fishing_lines, &ev.input, time.0)
+                && !use_potion(player, &ev.input)
+            {
+                use_spawn_egg(player, chunks, mobs, &ev.input);
+            }
+        } else {
+            holding_right_click.remove(&ev.client_id);
+        }
 
         player.last_input_processed = ev.input.time_ms;
     }
@@ -47,6 +126,11 @@ pub fn handle_player_inputs_system(
                 orientation: player.camera_transform.rotation,
                 last_ack_time: player.last_input_processed,
                 inventory: player.inventory.clone(),
+                health: player.health,
+                air: player.breath.air,
+                boat: player.boat,
+                effects: player.effects.clone(),
+                dimension: player.dimension,
             },
         ));
     }
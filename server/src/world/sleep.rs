@@ -0,0 +1,230 @@
+//! Skipping the night by sleeping in beds.
+//!
+//! Players sleep by using a bed at night. Once enough of the online players are
+//! asleep, the world jumps to the next morning and the weather clears. Sleeping in
+//! a bed also makes it the player's spawn point, see `spawn.rs`. The share
+//! of players needed is set in the `[sleep]` table of `server.toml`:
+//!
+//! ```toml
+//! [sleep]
+//! fraction = 0.5
+//! ```
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use serde::Deserialize;
+use shared::messages::{
+    BossBar, BossBarColor, PlayerFrameInput, PlayerId, ServerAnnouncement, ServerToClientMessage,
+    UiOverlay, WorldTimeSkip,
+};
+use shared::players::{Player, SpawnPoint};
+use shared::world::{
+    is_night, next_morning, raycast, BlockId, ServerChunkWorldMap, ServerWorldMap, WorldMap,
+};
+
+use crate::init::ServerTime;
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::overlays::UiOverlayEvent;
+use crate::world::weather::Weather;
+
+/// Beds can't be used from further away than this
+const BED_MAX_DISTANCE: f32 = 4.0;
+/// Sleeping players wake up if they move further than this from where they lay down
+const SLEEP_MAX_MOVEMENT: f32 = 1.0;
+/// Time the reasons a bed can't be used stay above the hotbar
+const BED_MESSAGE_SECS: f32 = 2.0;
+/// Boss bar following the sleeping players while someone sleeps
+const SLEEP_BOSS_BAR: u32 = 0;
+
+#[derive(Resource, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct SleepConfig {
+    /// Share of the online players that must sleep to skip the night
+    pub fraction: f32,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self { fraction: 0.5 }
+    }
+}
+
+impl SleepConfig {
+    /// Number of sleeping players needed to skip the night, always at least one
+    pub fn sleepers_needed(&self, online: usize) -> usize {
+        ((online as f32 * self.fraction.clamp(0.0, 1.0)).ceil() as usize).max(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sleeper {
+    pub bed: IVec3,
+    pub position: Vec3,
+}
+
+/// Players currently sleeping in a bed
+#[derive(Resource, Default, Debug)]
+pub struct SleepingPlayers(pub HashMap<PlayerId, Sleeper>);
+
+fn announce(server: &mut RenetServer, player: PlayerId, content: String) {
+    server.send_game_message(
+        player,
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+}
+
+fn show_action_bar(server: &mut RenetServer, player: PlayerId, text: &str) {
+    server.send_game_message(
+        player,
+        ServerToClientMessage::UiOverlay(UiOverlay::ActionBar {
+            text: text.into(),
+            duration_secs: BED_MESSAGE_SECS,
+        }),
+    );
+}
+
+/// Puts the player to sleep in the bed they are looking at.
+///
+/// Returns whether the click was used by a bed.
+pub fn use_bed(
+    server: &mut RenetServer,
+    player: &mut Player,
+    chunks: &ServerChunkWorldMap,
+    sleeping: &mut SleepingPlayers,
+    input: &PlayerFrameInput,
+    tick: u64,
+) -> bool {
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return false;
+    };
+    if hit.block.id != BlockId::Bed {
+        return false;
+    }
+    let bed = hit.position;
+    if (bed.as_vec3() + Vec3::splat(0.5)).distance(player.position) > BED_MAX_DISTANCE {
+        return false;
+    }
+
+    if !is_night(tick) {
+        show_action_bar(server, player.id, "You can only sleep at night");
+        return true;
+    }
+    if sleeping.0.values().any(|sleeper| sleeper.bed == bed) {
+        show_action_bar(server, player.id, "This bed is occupied");
+        return true;
+    }
+
+    if player.spawn_point != Some(SpawnPoint::Bed(bed)) {
+        player.spawn_point = Some(SpawnPoint::Bed(bed));
+        announce(server, player.id, "Respawn point set".into());
+    }
+
+    info!("Player {} is sleeping in the bed at {:?}", player.id, bed);
+    sleeping.0.insert(
+        player.id,
+        Sleeper {
+            bed,
+            position: player.position,
+        },
+    );
+    true
+}
+
+/// Wakes up players who left their bed, and skips the night once enough players sleep
+#[allow(clippy::too_many_arguments)]
+pub fn sleep_system(
+    mut server: ResMut<RenetServer>,
+    mut overlays: EventWriter<UiOverlayEvent>,
+    mut sleeping: ResMut<SleepingPlayers>,
+    mut time: ResMut<ServerTime>,
+    mut weather: ResMut<Weather>,
+    world_map: Res<ServerWorldMap>,
+    config: Res<SleepConfig>,
+    mut announced: Local<usize>,
+) {
+    if !is_night(time.0) {
+        if *announced > 0 {
+            overlays.write(UiOverlayEvent::everyone(UiOverlay::RemoveBossBar(
+                SLEEP_BOSS_BAR,
+            )));
+        }
+        sleeping.0.clear();
+        *announced = 0;
+        return;
+    }
+
+    sleeping.0.retain(|id, sleeper| {
+        world_map.players.get(id).is_some_and(|player| {
+            player.position.distance(sleeper.position) <= SLEEP_MAX_MOVEMENT
+                && world_map
+                    .chunks
+                    .get_block_by_coordinates(&sleeper.bed)
+                    .is_some_and(|block| block.id == BlockId::Bed)
+        })
+    });
+
+    let count = sleeping.0.len();
+    let needed = config.sleepers_needed(world_map.players.len());
+
+    // Only announce when someone new lays down
+    if count > *announced {
+        let names: Vec<&str> = sleeping
+            .0
+            .keys()
+            .filter_map(|id| world_map.players.get(id))
+            .map(|player| player.name.as_str())
+            .collect();
+        let content = format!(
+            "{} sleeping ({}/{})",
+            names.join(", "),
+            count.min(needed),
+            needed
+        );
+        server.broadcast_game_message(ServerToClientMessage::Announcement(ServerAnnouncement {
+            content,
+        }));
+    }
+    if count != *announced {
+        let overlay = if count == 0 {
+            UiOverlay::RemoveBossBar(SLEEP_BOSS_BAR)
+        } else {
+            UiOverlay::BossBar(BossBar {
+                id: SLEEP_BOSS_BAR,
+                title: format!("Sleeping ({}/{})", count.min(needed), needed),
+                progress: count as f32 / needed as f32,
+                color: BossBarColor::Blue,
+            })
+        };
+        overlays.write(UiOverlayEvent::everyone(overlay));
+    }
+    *announced = count;
+
+    if count < needed {
+        return;
+    }
+
+    let morning = next_morning(time.0);
+    info!(
+        "{} of {} players are sleeping, skipping the night to tick {}",
+        count,
+        world_map.players.len(),
+        morning
+    );
+    time.0 = morning;
+    weather.clear();
+    sleeping.0.clear();
+    *announced = 0;
+    overlays.write(UiOverlayEvent::everyone(UiOverlay::RemoveBossBar(
+        SLEEP_BOSS_BAR,
+    )));
+
+    server.broadcast_game_message(ServerToClientMessage::TimeSkip(WorldTimeSkip {
+        tick: morning,
+    }));
+    server.broadcast_game_message(ServerToClientMessage::Announcement(ServerAnnouncement {
+        content: "The night was skipped, good morning!".into(),
+    }));
+}
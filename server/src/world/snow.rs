@@ -0,0 +1,58 @@
+//! Snow layers accumulating on the ground while it snows.
+//!
+//! When it snows in a freezing climate, exposed surfaces get covered by thin snow
+//! layers that slowly pile up to `MAX_ACCUMULATED_LAYERS`. Snow layers melt, one
+//! layer at a time, outside of freezing climates.
+
+use rand::Rng;
+use shared::world::{BlockData, BlockHitbox, BlockId};
+
+use super::random_tick::{BlockChange, SurfaceBlock};
+use super::weather::Weather;
+
+/// Snowfall stops piling up at this many layers, half a block
+const MAX_ACCUMULATED_LAYERS: u8 = 4;
+/// Chance for a ticked surface to receive a layer while it snows
+const SNOWFALL_CHANCE: f64 = 0.3;
+/// Chance for a ticked snow layer to lose a layer in a warm climate
+const MELT_CHANCE: f64 = 0.2;
+
+pub fn accumulate_or_melt(
+    surface: &SurfaceBlock,
+    weather: &Weather,
+    rng: &mut impl Rng,
+) -> Option<BlockChange> {
+    let freezing = surface.climate.is_freezing_at(surface.position.y);
+    let snowing = freezing && weather.is_precipitating();
+    let block = surface.block;
+
+    if block.id == BlockId::SnowLayer {
+        if !freezing && rng.gen_bool(MELT_CHANCE) {
+            if block.level <= 1 {
+                return Some(BlockChange::Remove(surface.position));
+            }
+            return Some(BlockChange::Set(
+                surface.position,
+                BlockData::snow_layer(block.level - 1),
+            ));
+        }
+        if snowing && block.level < MAX_ACCUMULATED_LAYERS && rng.gen_bool(SNOWFALL_CHANCE) {
+            return Some(BlockChange::Set(
+                surface.position,
+                BlockData::snow_layer(block.level + 1),
+            ));
+        }
+        return None;
+    }
+
+    // Snow only settles on top of full blocks, not on water, flowers...
+    let is_full_block = matches!(block.get_collision_hitbox(), BlockHitbox::FullBlock);
+    if snowing && is_full_block && rng.gen_bool(SNOWFALL_CHANCE) {
+        return Some(BlockChange::Set(
+            surface.position + bevy::math::IVec3::Y,
+            BlockData::snow_layer(1),
+        ));
+    }
+
+    None
+}
@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+use shared::world::{EntityHistory, ServerWorldMap, SpatialHash};
+
+/// Rebuilds the entity broadphase from the current positions of players and mobs
+pub fn rebuild_spatial_hash_system(
+    mut spatial: ResMut<SpatialHash>,
+    world_map: Res<ServerWorldMap>,
+) {
+    spatial.rebuild(&world_map);
+}
+
+/// Keeps the positions of this tick for lag compensated hit detection
+pub fn record_entity_history_system(mut history: ResMut<EntityHistory>, spatial: Res<SpatialHash>) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    history.record(now, &spatial);
+}
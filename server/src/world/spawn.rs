@@ -0,0 +1,233 @@
+//! Respawning players at their spawn point.
+//!
+//! Spawn points are checked when used: a bed must still be there with room to
+//! stand on it, and a position must not be inside blocks. Otherwise the player
+//! is sent back to the world spawn and told why.
+//!
+//! The world spawn is chosen on dry and flat land near the origin the first time
+//! the world is loaded, then saved with it and sent to the players when they join.
+
+use bevy::math::bounding::Aabb3d;
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerId, ServerAnnouncement, ServerToClientMessage};
+use shared::players::{Breath, Player, SetSpawnPoint, SpawnPoint, MAX_PLAYER_HEALTH};
+use shared::world::{
+    BlockId, DimensionId, ServerChunkWorldMap, ServerWorldMap, StatusEffects, WorldMap, WorldSpawn,
+};
+use shared::GameServerConfig;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::network::operators::Operators;
+use crate::world::boats::stow_boat;
+use crate::world::dimensions::send_to_dimension;
+
+fn announce(server: &mut RenetServer, player: PlayerId, content: String) {
+    server.send_game_message(
+        player,
+        ServerToClientMessage::Announcement(ServerAnnouncement { content }),
+    );
+}
+
+/// Where a player of the given height stands on the world spawn. The spawn was
+/// chosen from the terrain alone, so it is raised above the trees and builds that
+/// now cover it once its chunks are loaded.
+pub fn world_spawn_position(
+    chunks: &ServerChunkWorldMap,
+    world_spawn: &WorldSpawn,
+    player_height: f32,
+) -> Vec3 {
+    let ground = chunks
+        .get_height_ground(world_spawn.0.as_vec3())
+        .max(world_spawn.0.y);
+    WorldSpawn(world_spawn.0.with_y(ground)).standing_position(player_height)
+}
+
+fn has_room(chunks: &ServerChunkWorldMap, player: &Player, position: Vec3) -> bool {
+    let half_extents = Vec3::new(player.width, player.height, player.width) / 2.0;
+    !chunks.check_collision_box(&Aabb3d::new(position, half_extents))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvalidSpawnPoint {
+    BedMissing,
+    Obstructed,
+}
+
+impl InvalidSpawnPoint {
+    fn message(&self) -> &'static str {
+        match self {
+            InvalidSpawnPoint::BedMissing => "Your bed is missing",
+            InvalidSpawnPoint::Obstructed => "Your spawn point is obstructed",
+        }
+    }
+}
+
+/// Where the player respawns, or why their spawn point can't be used
+fn respawn_position(
+    chunks: &ServerChunkWorldMap,
+    player: &Player,
+    spawn_point: SpawnPoint,
+) -> Result<Vec3, InvalidSpawnPoint> {
+    if let SpawnPoint::Bed(bed) = spawn_point {
+        if chunks
+            .get_block_by_coordinates(&bed)
+            .is_none_or(|block| block.id != BlockId::Bed)
+        {
+            return Err(InvalidSpawnPoint::BedMissing);
+        }
+    }
+
+    let position = spawn_point.standing_position(player.height);
+    if !has_room(chunks, player, position) {
+        return Err(InvalidSpawnPoint::Obstructed);
+    }
+    Ok(position)
+}
+
+/// Moves a player to a position of the overworld, bringing them back from
+/// another dimension if needed
+fn move_to_overworld(
+    server: &mut RenetServer,
+    chunks: &mut ServerChunkWorldMap,
+    player: &mut Player,
+    position: Vec3,
+) {
+    if player.dimension == DimensionId::Overworld {
+        stow_boat(player);
+        player.position = position;
+        player.velocity = Vec3::ZERO;
+    } else {
+        send_to_dimension(server, player, chunks, DimensionId::Overworld, position);
+    }
+}
+
+/// Brings the player back to their spawn point with full health, falling back to
+/// the world spawn if the spawn point is no longer usable. Spawn points are all in
+/// the overworld, `chunks` being its chunks.
+pub fn respawn_player(
+    server: &mut RenetServer,
+    chunks: &mut ServerChunkWorldMap,
+    world_spawn: &WorldSpawn,
+    player: &mut Player,
+) {
+    let position = match player.spawn_point {
+        None => world_spawn_position(chunks, world_spawn, player.height),
+        Some(spawn_point) => match respawn_position(chunks, player, spawn_point) {
+            Ok(position) => position,
+            Err(reason) => {
+                info!(
+                    "Spawn point {:?} of player {} is invalid: {:?}",
+                    spawn_point, player.id, reason
+                );
+                announce(
+                    server,
+                    player.id,
+                    format!(
+                        "{}, you were sent back to the world spawn",
+                        reason.message()
+                    ),
+                );
+                // Broken beds are forgotten, obstructed spawn points may be cleared later
+                if reason == InvalidSpawnPoint::BedMissing {
+                    player.spawn_point = None;
+                }
+                world_spawn_position(chunks, world_spawn, player.height)
+            }
+        },
+    };
+
+    info!("Player {} respawned at {:?}", player.id, position);
+    move_to_overworld(server, chunks, player, position);
+    player.health = MAX_PLAYER_HEALTH;
+    player.effects = StatusEffects::default();
+    player.breath = Breath::default();
+}
+
+/// Respawns the players who ran out of health
+pub fn respawn_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    world_spawn: Res<WorldSpawn>,
+) {
+    let ServerWorldMap {
+        players, chunks, ..
+    } = world_map.as_mut();
+    for player in players.values_mut() {
+        if player.health <= 0.0 {
+            respawn_player(&mut server, chunks, &world_spawn, player);
+        }
+    }
+}
+
+/// Runs `/spawn`, which any player can use
+pub fn teleport_to_spawn(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    world_spawn: &WorldSpawn,
+    player_id: PlayerId,
+) {
+    let Some(player) = world_map.players.get_mut(&player_id) else {
+        return;
+    };
+
+    info!("Player {} teleported to the world spawn", player.name);
+    let position = world_spawn_position(&world_map.chunks, world_spawn, player.height);
+    move_to_overworld(server, &mut world_map.chunks, player, position);
+    announce(server, player_id, "Teleported to the world spawn".into());
+}
+
+/// Runs `/spawnpoint [player] [x y z]` sent by `sender`
+pub fn set_spawn_point_command(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    operators: &Operators,
+    config: &GameServerConfig,
+    sender: PlayerId,
+    command: SetSpawnPoint,
+) {
+    let Some(sender_name) = world_map
+        .players
+        .get(&sender)
+        .map(|player| player.name.clone())
+    else {
+        return;
+    };
+    if !operators.is_operator(&sender_name, config.is_solo) {
+        warn!(
+            "Player {} tried to set a spawn point without being an operator",
+            sender_name
+        );
+        announce(server, sender, "Only operators can set spawn points".into());
+        return;
+    }
+
+    let target = match &command.player {
+        Some(name) => world_map
+            .players
+            .values_mut()
+            .find(|player| &player.name == name),
+        None => world_map.players.get_mut(&sender),
+    };
+    let Some(target) = target else {
+        announce(server, sender, "No such player".into());
+        return;
+    };
+
+    let position = command.position.unwrap_or(target.position);
+    target.spawn_point = Some(SpawnPoint::Position(position));
+    info!(
+        "Player {} set the spawn point of {} to {:?}",
+        sender_name, target.name, position
+    );
+    let content = format!(
+        "Spawn point of {} set to {:.1} {:.1} {:.1}",
+        target.name, position.x, position.y, position.z
+    );
+    let target_id = target.id;
+    announce(server, sender, content.clone());
+    if target_id != sender {
+        announce(server, target_id, content);
+    }
+}
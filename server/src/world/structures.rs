@@ -0,0 +1,523 @@
+//! Structures spanning several chunks: villages, desert temples and ruins.
+//!
+//! The world is split in regions of [`REGION_CHUNKS`]² chunk columns, each holding at
+//! most one structure whose kind and anchor are drawn from the seed and the region.
+//! A structure is built when the chunk holding its anchor is generated. Its blocks
+//! in that chunk are placed right away, the others are handed to their chunk:
+//! directly if it is already generated, otherwise through
+//! `ServerChunkWorldMap::structure_requests` until it is.
+//!
+//! Temples and some ruins hide a cellar under their floor, holding ore blocks
+//! drawn from [`CELLAR_LOOT`] as there are no chests.
+
+use std::collections::HashMap;
+
+use bevy::math::{IVec2, IVec3};
+use shared::world::{
+    calculate_temperature_humidity_with_noises, global_to_chunk_local, BiomeType, BlockData,
+    BlockDirection, BlockId, ClimateNoises, GenerationConfig, SeededRng, ServerChunk,
+    ServerChunkWorldMap, StructureRequest, TerrainNoise,
+};
+use shared::{CHUNK_SIZE, SEA_LEVEL};
+
+/// Side of a structure region, in chunks
+const REGION_CHUNKS: i32 = 8;
+/// Chance for a region on land to hold a structure
+const STRUCTURE_CHANCE: f32 = 0.6;
+/// Salts of the generators, apart from the ore veins
+const REGION_SALT: u64 = 0x5354_5255;
+const RUINS_SALT: u64 = 0x5255_494E;
+const TEMPLE_SALT: u64 = 0x5445_4D50;
+/// Distance between the well of a village and its houses
+const VILLAGE_HOUSE_DISTANCE: i32 = 10;
+/// Chance for each of the four houses of a village to be built
+const VILLAGE_HOUSE_CHANCE: f32 = 0.75;
+/// Half side of a house, walls included
+const HOUSE_HALF_SIZE: i32 = 2;
+const HOUSE_WALL_HEIGHT: i32 = 3;
+/// Depth of the foundations under houses, hiding the slopes
+const FOUNDATION_DEPTH: i32 = 3;
+/// Half side of ruins, walls included
+const RUINS_HALF_SIZE: i32 = 3;
+/// Chance for ruins to hide a cellar
+const RUINS_CELLAR_CHANCE: f32 = 0.5;
+/// Chance for a region in a desert to hold a temple rather than ruins
+const DESERT_TEMPLE_CHANCE: f32 = 0.5;
+/// Half side of the base of a temple, which rises in steps up to a single block
+const TEMPLE_HALF_SIZE: i32 = 7;
+/// Half side of the hall inside a temple, walls excluded
+const TEMPLE_HALL_HALF_SIZE: i32 = 3;
+const TEMPLE_HALL_HEIGHT: i32 = 3;
+/// Depth of the floor of the cellars under the floor of their structure
+const CELLAR_DEPTH: i32 = 7;
+/// Half side of a cellar, walls excluded
+const CELLAR_HALF_SIZE: i32 = 2;
+/// Ore blocks lying in the corners of cellars, by weight
+pub const CELLAR_LOOT: &[(u32, BlockId)] = &[
+    (5, BlockId::IronOre),
+    (4, BlockId::GoldOre),
+    (1, BlockId::DiamondOre),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureKind {
+    /// Well surrounded by a few houses, in grassy biomes
+    Village,
+    /// Sand pyramid with a hall and a hidden cellar, in deserts
+    DesertTemple,
+    /// Broken cobblestone walls, anywhere else on land
+    Ruins,
+}
+
+impl StructureKind {
+    pub const ALL: [StructureKind; 3] = [
+        StructureKind::Village,
+        StructureKind::DesertTemple,
+        StructureKind::Ruins,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StructureKind::Village => "Village",
+            StructureKind::DesertTemple => "Desert Temple",
+            StructureKind::Ruins => "Ruins",
+        }
+    }
+
+    /// Finds a structure by its name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacedStructure {
+    pub kind: StructureKind,
+    /// Surface block the structure is built around
+    pub anchor: IVec3,
+}
+
+/// Noises used to lay structures out, to build once per chunk or search
+pub struct StructureNoises {
+    seed: u32,
+    terrain: TerrainNoise,
+    climate: ClimateNoises,
+}
+
+impl StructureNoises {
+    pub fn new(seed: u32, config: GenerationConfig) -> Self {
+        Self {
+            seed,
+            terrain: TerrainNoise::new(seed, config),
+            climate: ClimateNoises::new(seed),
+        }
+    }
+
+    fn surface(&mut self, x: i32, z: i32) -> i32 {
+        self.terrain.height(x, z)
+    }
+}
+
+fn region_of_chunk(chunk_pos: IVec3) -> IVec2 {
+    IVec2::new(
+        chunk_pos.x.div_euclid(REGION_CHUNKS),
+        chunk_pos.z.div_euclid(REGION_CHUNKS),
+    )
+}
+
+/// Structure of the region, if it has one
+pub fn region_structure(noises: &mut StructureNoises, region: IVec2) -> Option<PlacedStructure> {
+    let mut rng = SeededRng::new(noises.seed, IVec3::new(region.x, 0, region.y), REGION_SALT);
+    if rng.next_f32() >= STRUCTURE_CHANCE {
+        return None;
+    }
+
+    let size = REGION_CHUNKS * CHUNK_SIZE;
+    let x = region.x * size + rng.below(size);
+    let z = region.y * size + rng.below(size);
+    let height = noises.surface(x, z);
+    if height <= SEA_LEVEL {
+        return None;
+    }
+
+    let climate = calculate_temperature_humidity_with_noises(x, z, &mut noises.climate);
+    let kind = match BiomeType::from_climate(climate) {
+        BiomeType::ShallowOcean | BiomeType::Ocean | BiomeType::DeepOcean => return None,
+        BiomeType::Plains | BiomeType::FlowerPlains | BiomeType::Forest if rng.next_f32() < 0.5 => {
+            StructureKind::Village
+        }
+        BiomeType::Desert if rng.next_f32() < DESERT_TEMPLE_CHANCE => StructureKind::DesertTemple,
+        _ => StructureKind::Ruins,
+    };
+    Some(PlacedStructure {
+        kind,
+        anchor: IVec3::new(x, height, z),
+    })
+}
+
+type StructureBlocks = Vec<(IVec3, Option<BlockData>)>;
+
+fn block(id: BlockId) -> Option<BlockData> {
+    Some(BlockData::new(id, BlockDirection::Front))
+}
+
+fn build_well(blocks: &mut StructureBlocks, anchor: IVec3) {
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let column = anchor + IVec3::new(dx, 0, dz);
+            blocks.push((column - IVec3::Y, block(BlockId::Cobblestone)));
+            for dy in 2..=3 {
+                blocks.push((column + IVec3::Y * dy, None));
+            }
+            if dx == 0 && dz == 0 {
+                blocks.push((column, block(BlockId::Water)));
+                blocks.push((column + IVec3::Y, None));
+            } else {
+                blocks.push((column, block(BlockId::Cobblestone)));
+                blocks.push((column + IVec3::Y, block(BlockId::Cobblestone)));
+            }
+        }
+    }
+}
+
+/// House whose door faces `door`, a unit vector along `x` or `z`
+fn build_house(
+    noises: &mut StructureNoises,
+    blocks: &mut StructureBlocks,
+    center: IVec2,
+    door: IVec2,
+) {
+    let floor = noises.surface(center.x, center.y);
+    let roof = floor + HOUSE_WALL_HEIGHT + 1;
+    for dx in -HOUSE_HALF_SIZE..=HOUSE_HALF_SIZE {
+        for dz in -HOUSE_HALF_SIZE..=HOUSE_HALF_SIZE {
+            let column = IVec3::new(center.x + dx, floor, center.y + dz);
+            for dy in 1..=FOUNDATION_DEPTH {
+                blocks.push((column - IVec3::Y * dy, block(BlockId::Cobblestone)));
+            }
+            blocks.push((column, block(BlockId::OakPlanks)));
+
+            let edge = dx.abs() == HOUSE_HALF_SIZE || dz.abs() == HOUSE_HALF_SIZE;
+            let corner = dx.abs() == HOUSE_HALF_SIZE && dz.abs() == HOUSE_HALF_SIZE;
+            for dy in 1..=HOUSE_WALL_HEIGHT {
+                let wall = match (corner, edge) {
+                    (true, _) => block(BlockId::OakLog),
+                    (false, true) => block(BlockId::OakPlanks),
+                    (false, false) => None,
+                };
+                blocks.push((column + IVec3::Y * dy, wall));
+            }
+            blocks.push((column.with_y(roof), block(BlockId::OakPlanks)));
+            blocks.push((column.with_y(roof + 1), None));
+        }
+    }
+
+    // Door on the side of the well, windows in the middle of the other walls
+    for side in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+        let wall = center + side * HOUSE_HALF_SIZE;
+        let wall = IVec3::new(wall.x, floor, wall.y);
+        if side == door {
+            blocks.push((wall + IVec3::Y, None));
+            blocks.push((wall + IVec3::Y * 2, None));
+        } else {
+            blocks.push((wall + IVec3::Y * 2, block(BlockId::Glass)));
+        }
+    }
+}
+
+/// Cobblestone path along the ground, from `start` going `length` blocks towards `direction`
+fn build_path(
+    noises: &mut StructureNoises,
+    blocks: &mut StructureBlocks,
+    start: IVec2,
+    direction: IVec2,
+    length: i32,
+) {
+    for step in 0..length {
+        let position = start + direction * step;
+        let height = noises.surface(position.x, position.y);
+        let ground = IVec3::new(position.x, height, position.y);
+        blocks.push((ground, block(BlockId::Cobblestone)));
+        blocks.push((ground + IVec3::Y, None));
+        blocks.push((ground + IVec3::Y * 2, None));
+    }
+}
+
+fn build_village(noises: &mut StructureNoises, blocks: &mut StructureBlocks, anchor: IVec3) {
+    build_well(blocks, anchor);
+
+    let mut rng = SeededRng::new(noises.seed, anchor, REGION_SALT);
+    let center = IVec2::new(anchor.x, anchor.z);
+    for direction in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+        if rng.next_f32() >= VILLAGE_HOUSE_CHANCE {
+            continue;
+        }
+        build_path(
+            noises,
+            blocks,
+            center + direction * 2,
+            direction,
+            VILLAGE_HOUSE_DISTANCE - HOUSE_HALF_SIZE - 2,
+        );
+        build_house(
+            noises,
+            blocks,
+            center + direction * VILLAGE_HOUSE_DISTANCE,
+            -direction,
+        );
+    }
+}
+
+fn build_ruins(noises: &mut StructureNoises, blocks: &mut StructureBlocks, anchor: IVec3) {
+    let mut rng = SeededRng::new(noises.seed, anchor, RUINS_SALT);
+    for dx in -RUINS_HALF_SIZE..=RUINS_HALF_SIZE {
+        for dz in -RUINS_HALF_SIZE..=RUINS_HALF_SIZE {
+            let column = anchor + IVec3::new(dx, 0, dz);
+            // Parts of the floor have crumbled away
+            if rng.next_f32() < 0.8 {
+                blocks.push((column, block(BlockId::Cobblestone)));
+            }
+            let edge = dx.abs() == RUINS_HALF_SIZE || dz.abs() == RUINS_HALF_SIZE;
+            let height = if edge { rng.below(4) } else { 0 };
+            for dy in 1..=4 {
+                let wall = (dy <= height)
+                    .then(|| BlockData::new(BlockId::Cobblestone, BlockDirection::Front));
+                blocks.push((column + IVec3::Y * dy, wall));
+            }
+        }
+    }
+
+    if rng.next_f32() < RUINS_CELLAR_CHANCE {
+        blocks.push((anchor, block(BlockId::Cobblestone)));
+        build_cellar(&mut rng, blocks, anchor);
+    }
+}
+
+/// Ore block of the cellar loot table at `roll`, taken modulo the total weight
+fn roll_cellar_loot(roll: u32) -> BlockId {
+    let total: u32 = CELLAR_LOOT.iter().map(|(weight, _)| weight).sum();
+    let mut roll = roll % total;
+    for (weight, loot) in CELLAR_LOOT {
+        if roll < *weight {
+            return *loot;
+        }
+        roll -= weight;
+    }
+    CELLAR_LOOT[0].1
+}
+
+/// Cobblestone room under the floor block `hatch`, reached by digging through it
+/// into the shaft below, with loot in its corners
+fn build_cellar(rng: &mut SeededRng, blocks: &mut StructureBlocks, hatch: IVec3) {
+    let floor = hatch.y - CELLAR_DEPTH;
+    let ceiling = floor + 3;
+    for dx in -CELLAR_HALF_SIZE - 1..=CELLAR_HALF_SIZE + 1 {
+        for dz in -CELLAR_HALF_SIZE - 1..=CELLAR_HALF_SIZE + 1 {
+            let inside = dx.abs() <= CELLAR_HALF_SIZE && dz.abs() <= CELLAR_HALF_SIZE;
+            for y in floor..=ceiling {
+                let wall = (!inside || y == floor || y == ceiling)
+                    .then(|| BlockData::new(BlockId::Cobblestone, BlockDirection::Front));
+                blocks.push((IVec3::new(hatch.x + dx, y, hatch.z + dz), wall));
+            }
+
+            let corner = dx.abs() == CELLAR_HALF_SIZE && dz.abs() == CELLAR_HALF_SIZE;
+            if corner {
+                let loot = roll_cellar_loot(rng.next_u64() as u32);
+                blocks.push((
+                    IVec3::new(hatch.x + dx, floor + 1, hatch.z + dz),
+                    block(loot),
+                ));
+            }
+        }
+    }
+
+    for y in ceiling..hatch.y {
+        blocks.push((hatch.with_y(y), None));
+    }
+}
+
+/// Stepped sand pyramid over a hall, its entrance facing a random side
+fn build_desert_temple(noises: &mut StructureNoises, blocks: &mut StructureBlocks, anchor: IVec3) {
+    let mut rng = SeededRng::new(noises.seed, anchor, TEMPLE_SALT);
+    for dx in -TEMPLE_HALF_SIZE..=TEMPLE_HALF_SIZE {
+        for dz in -TEMPLE_HALF_SIZE..=TEMPLE_HALF_SIZE {
+            let column = anchor + IVec3::new(dx, 0, dz);
+            for dy in 1..=FOUNDATION_DEPTH {
+                blocks.push((column - IVec3::Y * dy, block(BlockId::Sand)));
+            }
+            // Cobblestone floor in the hall, but for the sand hiding the cellar
+            let hall_floor = dx.abs() <= TEMPLE_HALL_HALF_SIZE
+                && dz.abs() <= TEMPLE_HALL_HALF_SIZE
+                && (dx, dz) != (0, 0);
+            let floor = if hall_floor {
+                BlockId::Cobblestone
+            } else {
+                BlockId::Sand
+            };
+            blocks.push((column, block(floor)));
+
+            // Each layer is a block narrower than the one below
+            let layers = TEMPLE_HALF_SIZE + 1 - dx.abs().max(dz.abs());
+            for dy in 1..=layers {
+                let hall = dx.abs() <= TEMPLE_HALL_HALF_SIZE
+                    && dz.abs() <= TEMPLE_HALL_HALF_SIZE
+                    && dy <= TEMPLE_HALL_HEIGHT;
+                let wall = if hall { None } else { block(BlockId::Sand) };
+                blocks.push((column + IVec3::Y * dy, wall));
+            }
+        }
+    }
+
+    let sides = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+    let entrance = sides[rng.below(sides.len() as i32) as usize];
+    for step in TEMPLE_HALL_HALF_SIZE + 1..=TEMPLE_HALF_SIZE {
+        let position = entrance * step;
+        for dy in 1..=2 {
+            blocks.push((anchor + IVec3::new(position.x, dy, position.y), None));
+        }
+    }
+    build_cellar(&mut rng, blocks, anchor);
+}
+
+/// Every block of the structure in world coordinates, later ones replacing earlier ones
+pub fn structure_blocks(
+    noises: &mut StructureNoises,
+    structure: &PlacedStructure,
+) -> StructureBlocks {
+    let mut blocks = Vec::new();
+    match structure.kind {
+        StructureKind::Village => build_village(noises, &mut blocks, structure.anchor),
+        StructureKind::DesertTemple => build_desert_temple(noises, &mut blocks, structure.anchor),
+        StructureKind::Ruins => build_ruins(noises, &mut blocks, structure.anchor),
+    }
+    blocks
+}
+
+pub fn apply_structure_request(chunk: &mut ServerChunk, request: &StructureRequest) {
+    for (local_pos, block) in request.blocks.iter() {
+        match block {
+            Some(block) => {
+                chunk.map.insert(*local_pos, *block);
+            }
+            None => {
+                chunk.map.remove(local_pos);
+            }
+        }
+    }
+}
+
+/// Builds the structure anchored in the chunk being generated, if any. Its blocks
+/// in the chunk are placed, the others are returned with the chunk they belong to.
+pub fn build_structures(
+    seed: u32,
+    config: GenerationConfig,
+    chunk_pos: IVec3,
+    chunk: &mut ServerChunk,
+) -> Vec<(IVec3, StructureRequest)> {
+    let mut noises = StructureNoises::new(seed, config);
+    let Some(structure) = region_structure(&mut noises, region_of_chunk(chunk_pos)) else {
+        return Vec::new();
+    };
+    if global_to_chunk_local(&structure.anchor).0 != chunk_pos {
+        return Vec::new();
+    }
+
+    let mut requests: HashMap<IVec3, StructureRequest> = HashMap::new();
+    for (position, block) in structure_blocks(&mut noises, &structure) {
+        let (target, local_pos) = global_to_chunk_local(&position);
+        requests
+            .entry(target)
+            .or_default()
+            .blocks
+            .push((local_pos, block));
+    }
+    if let Some(own) = requests.remove(&chunk_pos) {
+        apply_structure_request(chunk, &own);
+    }
+    requests.into_iter().collect()
+}
+
+/// Hands the parts of a structure to their chunks, queuing those not generated yet
+pub fn dispatch_structure_requests(
+    chunks: &mut ServerChunkWorldMap,
+    requests: Vec<(IVec3, StructureRequest)>,
+) {
+    for (target, request) in requests {
+        match chunks.map.get_mut(&target) {
+            Some(chunk) => {
+                apply_structure_request(chunk, &request);
+                chunks.chunks_to_update.push(target);
+            }
+            None => chunks
+                .structure_requests
+                .entry(target)
+                .or_default()
+                .push(request),
+        }
+    }
+}
+
+/// Applies the parts of structures queued for a chunk that was just generated
+pub fn apply_queued_structure_requests(chunks: &mut ServerChunkWorldMap, chunk_pos: IVec3) {
+    let Some(requests) = chunks.structure_requests.remove(&chunk_pos) else {
+        return;
+    };
+    if let Some(chunk) = chunks.map.get_mut(&chunk_pos) {
+        for request in requests.iter() {
+            apply_structure_request(chunk, request);
+        }
+    }
+}
+
+/// Regions at a Chebyshev distance of `ring` from `center`
+fn region_ring(center: IVec2, ring: i32) -> Vec<IVec2> {
+    if ring == 0 {
+        return vec![center];
+    }
+    (-ring..=ring)
+        .flat_map(|a| {
+            [
+                IVec2::new(a, -ring),
+                IVec2::new(a, ring),
+                IVec2::new(-ring, a),
+                IVec2::new(ring, a),
+            ]
+        })
+        .map(|offset| center + offset)
+        .collect()
+}
+
+/// Anchor of the nearest structure of this kind around `origin`, in world x and z,
+/// looking at most `max_distance` blocks away
+pub fn locate_structure(
+    noises: &mut StructureNoises,
+    origin: IVec2,
+    kind: StructureKind,
+    max_distance: i32,
+) -> Option<IVec3> {
+    let size = REGION_CHUNKS * CHUNK_SIZE;
+    let center = IVec2::new(origin.x.div_euclid(size), origin.y.div_euclid(size));
+    let distance = |anchor: &IVec3| IVec2::new(anchor.x, anchor.z).distance_squared(origin);
+
+    let mut nearest: Option<IVec3> = None;
+    for ring in 0..=max_distance / size + 1 {
+        // A structure in the next ring can still be closer than one in this ring
+        let found_before = nearest.is_some();
+        for region in region_ring(center, ring) {
+            let Some(structure) = region_structure(noises, region) else {
+                continue;
+            };
+            if structure.kind == kind
+                && nearest.is_none_or(|nearest| distance(&structure.anchor) < distance(&nearest))
+            {
+                nearest = Some(structure.anchor);
+            }
+        }
+        if found_before {
+            break;
+        }
+    }
+    nearest.filter(|anchor| distance(anchor) <= max_distance * max_distance)
+}
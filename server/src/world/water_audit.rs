@@ -0,0 +1,158 @@
+//! Debug audit of water volume conservation, toggled by operators.
+//!
+//! While at least one client has the audit enabled, loaded water is sampled every
+//! second and compared with the previous sample (see `shared::water::audit`).
+//! Ice counts as stored water, as it freezes from and melts back to water in
+//! place (see `freezing.rs`): the climate alone decides it, apart from heat
+//! sources melting the ice around them. Explicit block edits are recorded by
+//! `ServerChunkWorldMap` and count as sources and sinks; any other net creation
+//! or destruction of water is logged and reported to the subscribed clients.
+
+use crate::network::extensions::SendGameMessageExtension;
+use bevy::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::{ClientId, RenetServer};
+use shared::fluid::FluidKind;
+use shared::messages::ServerToClientMessage;
+use shared::water::{audit_water_volume, find_water_bodies, WaterAuditReport};
+use shared::world::{to_global_pos, ServerWorldMap};
+use shared::TICKS_PER_SECOND;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Event, Debug)]
+pub struct WaterAuditToggleEvent {
+    pub client_id: ClientId,
+    pub enabled: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct WaterAudit {
+    subscribers: HashSet<ClientId>,
+    /// Water blocks of the previous sample, grouped by chunk
+    previous_sample: Option<HashMap<IVec3, Vec<IVec3>>>,
+    /// Chunks that were loaded when the previous sample was taken
+    previous_loaded_chunks: HashSet<IVec3>,
+    /// Explicit edits since the previous sample
+    pending_edits: Vec<(IVec3, f32)>,
+    ticks_since_sample: u64,
+    total_violations: usize,
+}
+
+fn sample_water(world_map: &ServerWorldMap) -> HashMap<IVec3, Vec<IVec3>> {
+    world_map
+        .chunks
+        .map
+        .iter()
+        .filter_map(|(chunk_pos, chunk)| {
+            let water: Vec<IVec3> = chunk
+                .map
+                .iter()
+                .filter(|(_, block)| block.id.stored_fluid_volume(FluidKind::Water) > 0.0)
+                .map(|(local_pos, _)| to_global_pos(chunk_pos, local_pos))
+                .collect();
+            (!water.is_empty()).then_some((*chunk_pos, water))
+        })
+        .collect()
+}
+
+pub fn water_audit_system(
+    mut server: ResMut<RenetServer>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut audit: ResMut<WaterAudit>,
+    mut toggles: EventReader<WaterAuditToggleEvent>,
+) {
+    for toggle in toggles.read() {
+        if toggle.enabled {
+            info!("Water audit enabled by client {}", toggle.client_id);
+            audit.subscribers.insert(toggle.client_id);
+        } else if audit.subscribers.remove(&toggle.client_id)
+            && server.is_connected(toggle.client_id)
+        {
+            server.send_game_message(
+                toggle.client_id,
+                ServerToClientMessage::WaterAudit(WaterAuditReport::default()),
+            );
+        }
+    }
+
+    // Always drained, otherwise edits would pile up while the audit is disabled
+    let edits = std::mem::take(&mut world_map.chunks.fluid_edits);
+
+    if audit.subscribers.is_empty() {
+        if audit.previous_sample.is_some() {
+            *audit = WaterAudit::default();
+        }
+        return;
+    }
+
+    audit.pending_edits.extend(
+        edits
+            .into_iter()
+            .filter(|(_, fluid, _)| *fluid == FluidKind::Water)
+            .map(|(position, _, volume)| (position, volume)),
+    );
+    audit.ticks_since_sample += 1;
+    if audit.previous_sample.is_some() && audit.ticks_since_sample < TICKS_PER_SECOND {
+        return;
+    }
+    audit.ticks_since_sample = 0;
+
+    let current_sample = sample_water(&world_map);
+    let loaded_chunks: HashSet<IVec3> = world_map.chunks.map.keys().copied().collect();
+    let previous_loaded_chunks =
+        std::mem::replace(&mut audit.previous_loaded_chunks, loaded_chunks);
+    let Some(previous_sample) = audit.previous_sample.replace(current_sample.clone()) else {
+        audit.pending_edits.clear();
+        return;
+    };
+
+    // Only chunks loaded in both samples are compared, so that chunks being
+    // generated or unloaded don't show up as water appearing or vanishing
+    let mut previous_blocks = HashSet::new();
+    let mut current_blocks = HashSet::new();
+    for (chunk_pos, blocks) in current_sample.iter() {
+        if !previous_loaded_chunks.contains(chunk_pos) {
+            continue;
+        }
+        current_blocks.extend(blocks.iter().copied());
+    }
+    for (chunk_pos, blocks) in previous_sample.iter() {
+        if !world_map.chunks.map.contains_key(chunk_pos) {
+            continue;
+        }
+        previous_blocks.extend(blocks.iter().copied());
+    }
+
+    let previous_bodies = find_water_bodies(&previous_blocks);
+    let current_bodies = find_water_bodies(&current_blocks);
+    let edits = std::mem::take(&mut audit.pending_edits);
+    let violations = audit_water_volume(&previous_bodies, &current_bodies, &edits);
+
+    for violation in violations.iter() {
+        warn!(
+            "Water volume not conserved near {}: {:.3} -> {:.3} ({:+.3} explained, {:+.3} unexplained)",
+            violation.anchor,
+            violation.previous_volume,
+            violation.current_volume,
+            violation.explained_change,
+            violation.unexplained_change()
+        );
+    }
+    audit.total_violations += violations.len();
+
+    let previous_volume: f32 = previous_bodies.iter().map(|body| body.volume).sum();
+    let total_volume: f32 = current_bodies.iter().map(|body| body.volume).sum();
+
+    let report = WaterAuditReport {
+        enabled: true,
+        body_count: current_bodies.len(),
+        total_volume,
+        net_change: total_volume - previous_volume,
+        violations,
+        total_violations: audit.total_violations,
+    };
+
+    for client in audit.subscribers.iter() {
+        server.send_game_message(*client, ServerToClientMessage::WaterAudit(report.clone()));
+    }
+}
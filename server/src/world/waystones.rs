@@ -0,0 +1,135 @@
+use bevy::math::{IVec3, Vec3};
+use bevy_log::{info, warn};
+use bevy_renet::renet::RenetServer;
+use shared::messages::{PlayerFrameInput, PlayerId, ServerToClientMessage, WaystoneUpdate};
+use shared::players::Player;
+use shared::world::{
+    raycast, waystone_arrival_position, BlockId, DimensionId, ServerChunkWorldMap, ServerWorldMap,
+    WaystoneRegistry, WorldMap, WAYSTONE_MAX_DISTANCE,
+};
+use shared::TICKS_PER_SECOND;
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::boats::stow_boat;
+
+fn is_waystone(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    chunks
+        .get_block_by_coordinates(&position)
+        .is_some_and(|block| block.id == BlockId::Waystone)
+}
+
+/// Activates the waystone the player is looking at, or shows them the waystones
+/// they can teleport to if they already activated it.
+///
+/// Returns whether the click was used by a waystone.
+pub fn use_waystone(
+    server: &mut RenetServer,
+    player: &Player,
+    chunks: &ServerChunkWorldMap,
+    waystones: &mut WaystoneRegistry,
+    input: &PlayerFrameInput,
+    tick: u64,
+) -> bool {
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return false;
+    };
+    if hit.block.id != BlockId::Waystone {
+        return false;
+    }
+    let position = hit.position;
+    if (position.as_vec3() + Vec3::splat(0.5)).distance(player.position) > WAYSTONE_MAX_DISTANCE {
+        return false;
+    }
+
+    if waystones.activate(position, player.id) {
+        info!(
+            "Player {} activated the waystone at {:?}",
+            player.id, position
+        );
+        server.broadcast_game_message(ServerToClientMessage::Waystone(WaystoneUpdate::Activated {
+            position,
+            player: player.id,
+        }));
+        return true;
+    }
+
+    // Server chunks are never unloaded, so waystones missing from them were broken
+    waystones
+        .waystones
+        .retain(|position, _| is_waystone(chunks, *position));
+
+    let destinations = waystones
+        .discovered(player.id)
+        .into_iter()
+        .filter(|entry| entry.position != position)
+        .collect();
+    let cooldown = waystones.cooldown_remaining(player.id, tick);
+    server.send_game_message(
+        player.id,
+        ServerToClientMessage::Waystone(WaystoneUpdate::Menu {
+            from: position,
+            destinations,
+            cooldown_secs: cooldown as f32 / TICKS_PER_SECOND as f32,
+        }),
+    );
+    true
+}
+
+/// Teleports a player between two waystones, if they discovered both and their
+/// cooldown is over
+pub fn teleport_to_waystone(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    player_id: PlayerId,
+    from: IVec3,
+    to: IVec3,
+    tick: u64,
+) {
+    let Some(player) = world_map.players.get_mut(&player_id) else {
+        return;
+    };
+    let waystones = &mut world_map.waystones;
+
+    let near_source =
+        (from.as_vec3() + Vec3::splat(0.5)).distance(player.position) <= WAYSTONE_MAX_DISTANCE;
+    // Waystones only stand in the overworld
+    if player.dimension != DimensionId::Overworld
+        || !near_source
+        || !waystones.is_discovered(from, player_id)
+        || !waystones.is_discovered(to, player_id)
+        || !is_waystone(&world_map.chunks, from)
+        || !is_waystone(&world_map.chunks, to)
+    {
+        warn!(
+            "Player {} can't teleport from the waystone at {:?} to {:?}",
+            player_id, from, to
+        );
+        return;
+    }
+
+    if waystones.cooldown_remaining(player_id, tick) > 0 {
+        warn!(
+            "Player {} tried to teleport before the end of their cooldown",
+            player_id
+        );
+        return;
+    }
+
+    info!(
+        "Player {} teleported from the waystone at {:?} to {:?}",
+        player_id, from, to
+    );
+    waystones.last_teleports.insert(player_id, tick);
+    stow_boat(player);
+    player.position = waystone_arrival_position(to);
+    player.velocity = Vec3::ZERO;
+
+    server.broadcast_game_message(ServerToClientMessage::Waystone(
+        WaystoneUpdate::Teleported {
+            player: player_id,
+            from,
+            to,
+        },
+    ));
+}
@@ -0,0 +1,87 @@
+//! Global weather, cycling between clear skies and precipitation.
+//!
+//! Precipitation falls as snow in freezing climates (see `snow.rs`) and has no
+//! effect elsewhere for now.
+
+use bevy::prelude::*;
+use bevy_log::info;
+use bevy_renet::renet::RenetServer;
+use rand::Rng;
+use shared::messages::{PlayerId, ServerToClientMessage, WeatherUpdate};
+use shared::world::{ServerWorldMap, WeatherKind};
+use shared::TICKS_PER_SECOND;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::network::extensions::SendGameMessageExtension;
+
+/// How long clear weather lasts, in seconds
+const CLEAR_DURATION_SECS: Range<u64> = 300..900;
+/// How long precipitation lasts, in seconds
+const PRECIPITATION_DURATION_SECS: Range<u64> = 120..360;
+
+#[derive(Resource, Debug)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    ticks_remaining: u64,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            kind: WeatherKind::Clear,
+            ticks_remaining: rand::thread_rng().gen_range(CLEAR_DURATION_SECS) * TICKS_PER_SECOND,
+        }
+    }
+}
+
+impl Weather {
+    pub fn is_precipitating(&self) -> bool {
+        self.kind == WeatherKind::Precipitation
+    }
+
+    /// Ends the current precipitation, starting a new clear period
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn weather_update_system(mut weather: ResMut<Weather>) {
+    if weather.ticks_remaining > 0 {
+        weather.ticks_remaining -= 1;
+        return;
+    }
+
+    let (kind, duration) = match weather.kind {
+        WeatherKind::Clear => (WeatherKind::Precipitation, PRECIPITATION_DURATION_SECS),
+        WeatherKind::Precipitation => (WeatherKind::Clear, CLEAR_DURATION_SECS),
+    };
+    weather.kind = kind;
+    weather.ticks_remaining = rand::thread_rng().gen_range(duration) * TICKS_PER_SECOND;
+    info!("Weather changed to {:?}", kind);
+}
+
+/// Sends the weather to players that don't know it yet, either because they just
+/// joined or because it changed
+pub fn broadcast_weather_system(
+    mut server: ResMut<RenetServer>,
+    weather: Res<Weather>,
+    world_map: Res<ServerWorldMap>,
+    mut informed: Local<(WeatherKind, HashSet<PlayerId>)>,
+) {
+    let (kind, players) = &mut *informed;
+    if *kind != weather.kind {
+        *kind = weather.kind;
+        players.clear();
+    }
+
+    players.retain(|id| world_map.players.contains_key(id));
+    for id in world_map.players.keys() {
+        if players.insert(*id) {
+            server.send_game_message(
+                *id,
+                ServerToClientMessage::Weather(WeatherUpdate { kind: weather.kind }),
+            );
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Ground getting wet next to water and under the rain.
+//!
+//! Farmland is hydrated while water lies within `MOISTURE_RADIUS` or while it
+//! rains, and turns back to dirt once it has dried. Dirt and grass on humid
+//! shores soak into mud, and sand and gravel into clay. While it rains, puddles
+//! form on the ground of humid biomes, and evaporate once the rain stops.
+
+use bevy::math::IVec3;
+use rand::Rng;
+use shared::world::{BlockData, BlockDirection, BlockId, ServerChunkWorldMap};
+
+use super::moisture::{MoistureField, MOISTURE_RADIUS};
+use super::random_tick::{BlockChange, SurfaceBlock};
+use super::weather::Weather;
+
+/// Chance for ticked farmland that dried out to turn back to dirt
+const FARMLAND_REVERT_CHANCE: f64 = 0.1;
+/// Chance for ticked ground next to water to soak into mud or clay, and for mud
+/// away from water to dry
+const SOAK_CHANCE: f64 = 0.05;
+/// Humidity from which dirt soaks into mud, the swamps and jungles
+const MUD_MIN_HUMIDITY: f64 = 0.6;
+/// Humidity from which sand and gravel soak into clay
+const CLAY_MIN_HUMIDITY: f64 = 0.4;
+/// Chance for a ticked surface to get a puddle while it rains, scaled by humidity
+const PUDDLE_CHANCE: f64 = 0.1;
+/// Humidity below which rain sinks into the ground without leaving puddles
+const PUDDLE_MIN_HUMIDITY: f64 = 0.3;
+/// Chance for a ticked puddle to evaporate once the rain stopped
+const EVAPORATION_CHANCE: f64 = 0.2;
+
+pub fn soak_or_dry(
+    surface: &SurfaceBlock,
+    chunks: &ServerChunkWorldMap,
+    moisture: &mut MoistureField,
+    weather: &Weather,
+    rng: &mut impl Rng,
+) -> Option<BlockChange> {
+    let freezing = surface.climate.is_freezing_at(surface.position.y);
+    let raining = weather.is_precipitating() && !freezing;
+
+    soak(surface, chunks, moisture, raining, rng)
+        .or_else(|| fill_or_evaporate_puddle(surface, raining, freezing, rng))
+}
+
+fn soak(
+    surface: &SurfaceBlock,
+    chunks: &ServerChunkWorldMap,
+    moisture: &mut MoistureField,
+    raining: bool,
+    rng: &mut impl Rng,
+) -> Option<BlockChange> {
+    let position = surface.position;
+    let humidity = surface.climate.humidity;
+    let turn_into = |id: BlockId| {
+        Some(BlockChange::Set(
+            position,
+            BlockData::new(id, BlockDirection::Front),
+        ))
+    };
+
+    match surface.block.id {
+        BlockId::Farmland => {
+            let hydrated = raining || moisture.is_water_within(chunks, position, MOISTURE_RADIUS);
+            if hydrated != (surface.block.level > 0) {
+                return Some(BlockChange::Set(
+                    position,
+                    BlockData {
+                        level: hydrated as u8,
+                        ..surface.block
+                    },
+                ));
+            }
+            if !hydrated && rng.gen_bool(FARMLAND_REVERT_CHANCE) {
+                return turn_into(BlockId::Dirt);
+            }
+            None
+        }
+        BlockId::Dirt | BlockId::Grass | BlockId::SwampGrass
+            if humidity >= MUD_MIN_HUMIDITY
+                && rng.gen_bool(SOAK_CHANCE)
+                && moisture.is_water_within(chunks, position, 1) =>
+        {
+            turn_into(BlockId::Mud)
+        }
+        BlockId::Sand | BlockId::Gravel
+            if humidity >= CLAY_MIN_HUMIDITY
+                && rng.gen_bool(SOAK_CHANCE)
+                && moisture.is_water_within(chunks, position, 1) =>
+        {
+            turn_into(BlockId::Clay)
+        }
+        BlockId::Mud
+            if !raining
+                && rng.gen_bool(SOAK_CHANCE)
+                && !moisture.is_water_within(chunks, position, 1) =>
+        {
+            turn_into(BlockId::Dirt)
+        }
+        _ => None,
+    }
+}
+
+fn fill_or_evaporate_puddle(
+    surface: &SurfaceBlock,
+    raining: bool,
+    freezing: bool,
+    rng: &mut impl Rng,
+) -> Option<BlockChange> {
+    let humidity = surface.climate.humidity;
+
+    match surface.block.id {
+        BlockId::Puddle => {
+            let evaporates = !raining && rng.gen_bool(EVAPORATION_CHANCE);
+            // The frost dries them up, the snow settles on the ground instead
+            (freezing || evaporates).then_some(BlockChange::Remove(surface.position))
+        }
+        // Rain sinks into sand and gravel, and runs off the leaves
+        BlockId::Dirt
+        | BlockId::Grass
+        | BlockId::SwampGrass
+        | BlockId::Mycelium
+        | BlockId::Farmland
+        | BlockId::Mud
+        | BlockId::Clay
+        | BlockId::Stone
+        | BlockId::Cobblestone
+            if raining
+                && humidity >= PUDDLE_MIN_HUMIDITY
+                && rng.gen_bool(PUDDLE_CHANCE * humidity) =>
+        {
+            Some(BlockChange::Set(
+                surface.position + IVec3::Y,
+                BlockData::new(BlockId::Puddle, BlockDirection::Front),
+            ))
+        }
+        _ => None,
+    }
+}
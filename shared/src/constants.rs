@@ -13,6 +13,10 @@ pub const TARGET_SERVER_ADDR_ERROR: &str =
     "Target server address missing when initializing connection";
 pub const NETCODE_CLIENT_TRANSPORT_ERROR: &str = "Failed to create Netcode client transport";
 pub const NETCODE_SERVER_TRANSPORT_ERROR: &str = "Failed to create Netcode server transport";
+pub const WEBSOCKET_LISTENER_ERROR: &str = "Failed to listen for WebSockets";
+pub const HANDSHAKE_LISTENER_ERROR: &str = "Failed to listen for handshakes";
+pub const HANDSHAKE_ERROR: &str = "Failed to get a connect token from the server";
+pub const WEBSOCKET_CONNECT_ERROR: &str = "Failed to open a WebSocket to the server";
 pub const USERNAME_MISSING_AUTHENTICATED_ERROR: &str =
     "Username missing while handling authenticated session token";
 pub const HALF_BLOCK: Vec3 = Vec3 {
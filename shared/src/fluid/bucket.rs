@@ -0,0 +1,113 @@
+//! Buckets, scooping water up and pouring it out one block at a time.
+//!
+//! The client picks the block a bucket is used on and sends it with
+//! `ClientToServerMessage::UseBucket`. The server checks it against the held
+//! bucket and the reach, then takes or adds the water block through
+//! `WorldMap::remove_block_by_coordinates` and `WorldMap::set_block`, which
+//! record the full block of volume moved.
+
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::world::{raycast, BlockHitbox, BlockId, FaceDirectionExt, ItemId, WorldMap};
+
+/// Farthest block a bucket is used on
+pub const BUCKET_REACH: f32 = 5.0;
+/// Distance between two samples of the ray looking for water
+const FLUID_RAY_STEP: f32 = 0.1;
+
+/// Bucket held in `hotbar_slot` used on `target`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BucketUse {
+    pub hotbar_slot: u32,
+    /// Water block filling an empty bucket, or block a water bucket is poured into
+    pub target: IVec3,
+}
+
+/// First water block along the ray, unless a block stops the ray before it
+pub fn water_to_fill_from(
+    world_map: &impl WorldMap,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<IVec3> {
+    let samples = (BUCKET_REACH / FLUID_RAY_STEP) as usize;
+    for sample in 0..=samples {
+        let position = (origin + direction * sample as f32 * FLUID_RAY_STEP)
+            .floor()
+            .as_ivec3();
+        match world_map.get_block_by_coordinates(&position) {
+            Some(block) if block.id == BlockId::Water => return Some(position),
+            Some(block) if !matches!(block.get_ray_hitbox(), BlockHitbox::None) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Block in front of the face looked at, where water is poured
+pub fn block_to_pour_into(
+    world_map: &impl WorldMap,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<IVec3> {
+    let hit = raycast::raycast_from_source_position_and_direction(world_map, origin, direction)?;
+    let distance = (hit.position.as_vec3() + Vec3::splat(0.5)).distance(origin);
+    (distance <= BUCKET_REACH).then(|| hit.position + hit.face.to_ivec3())
+}
+
+/// Block a bucket held as `item` would be used on, `None` if `item` isn't a
+/// bucket
+pub fn bucket_target(
+    world_map: &impl WorldMap,
+    item: ItemId,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<IVec3> {
+    match item {
+        ItemId::Bucket => water_to_fill_from(world_map, origin, direction),
+        ItemId::WaterBucket => block_to_pour_into(world_map, origin, direction),
+        _ => None,
+    }
+}
+
+/// Whether a player standing at `player_position` reaches `target` with a
+/// bucket. Rays start from the eyes, and water is poured in front of the block
+/// looked at, hence the leeway.
+pub fn is_within_bucket_reach(player_position: Vec3, target: IVec3) -> bool {
+    (target.as_vec3() + Vec3::splat(0.5)).distance(player_position) <= BUCKET_REACH + 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockData, BlockDirection, ServerChunkWorldMap};
+
+    #[test]
+    fn buckets_target_the_water_in_sight() {
+        let mut world_map = ServerChunkWorldMap::default();
+        let block = |id| BlockData::new(id, BlockDirection::Front);
+        world_map.set_block(&IVec3::new(0, 1, -3), block(BlockId::Water));
+        world_map.set_block(&IVec3::new(0, 1, -4), block(BlockId::Stone));
+
+        let (origin, forward) = (Vec3::new(0.5, 1.5, 0.5), Vec3::NEG_Z);
+        assert_eq!(
+            bucket_target(&world_map, ItemId::Bucket, origin, forward),
+            Some(IVec3::new(0, 1, -3))
+        );
+        // Water is poured through water, against the stone behind it
+        assert_eq!(
+            bucket_target(&world_map, ItemId::WaterBucket, origin, forward),
+            Some(IVec3::new(0, 1, -3))
+        );
+        assert_eq!(
+            bucket_target(&world_map, ItemId::Stone, origin, forward),
+            None
+        );
+
+        world_map.set_block(&IVec3::new(0, 1, -2), block(BlockId::Glass));
+        assert_eq!(
+            bucket_target(&world_map, ItemId::Bucket, origin, forward),
+            None
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::water::WATER_BLOCK_VOLUME;
+use crate::world::BlockId;
+
+/// Volume of a single lava block
+pub const LAVA_BLOCK_VOLUME: f32 = 1.0;
+
+/// Fluids stored on the block grid, whose volume is tracked per block
+/// (see `BlockId::stored_fluid`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FluidKind {
+    Water,
+    Lava,
+}
+
+impl FluidKind {
+    pub const ALL: [FluidKind; 2] = [FluidKind::Water, FluidKind::Lava];
+
+    /// Fluid flowing as `block`. Ice stores water but doesn't flow.
+    pub fn flowing_as(block: BlockId) -> Option<FluidKind> {
+        Self::ALL.into_iter().find(|kind| kind.block() == block)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FluidKind::Water => "water",
+            FluidKind::Lava => "lava",
+        }
+    }
+
+    /// Block of the fluid at rest
+    pub fn block(&self) -> BlockId {
+        match self {
+            FluidKind::Water => BlockId::Water,
+            FluidKind::Lava => BlockId::Lava,
+        }
+    }
+
+    /// Volume held by a full block of the fluid
+    pub fn block_volume(&self) -> f32 {
+        match self {
+            FluidKind::Water => WATER_BLOCK_VOLUME,
+            FluidKind::Lava => LAVA_BLOCK_VOLUME,
+        }
+    }
+
+    /// Ticks between two steps of the flow: the more viscous the fluid, the
+    /// slower it spreads
+    pub fn flow_interval_ticks(&self) -> u64 {
+        match self {
+            FluidKind::Water => 5,
+            FluidKind::Lava => 30,
+        }
+    }
+
+    /// Furthest a fluid flows sideways from its source or from where it fell
+    pub fn max_spread(&self) -> u8 {
+        match self {
+            FluidKind::Water => 7,
+            FluidKind::Lava => 3,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Fluids of the world, and particles for small, short-lived water effects.
+//!
+//! Bulk water and lava stay on the block grid, each block storing the volume of its
+//! `FluidKind`. Particles are only used for
+//! small dynamic events such as waterfalls and bucket pours, where a few hundred
+//! ballistic droplets look much better than blocks popping in and out. They are
+//! simulated by the server when the fluid particle mode is enabled, and replicated
+//! to clients as plain positions.
+
+pub mod bucket;
+pub mod kind;
+pub mod mode;
+pub mod particles;
+
+pub use bucket::*;
+pub use kind::*;
+pub use mode::*;
+pub use particles::*;
@@ -0,0 +1,41 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// How water and lava flow, from the `--fluid-mode` argument of the server
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FluidMode {
+    /// Fluid blocks move, down and off ledges, and are never created nor
+    /// destroyed by the flow
+    #[default]
+    Finite,
+    /// Source blocks spread endlessly, their flowing blocks losing a level with
+    /// each block away from the source
+    Classic,
+}
+
+/// Water bodies holding this volume or less evaporate once they settle in the
+/// finite mode, see the `--evaporation-volume` argument of the server
+pub const DEFAULT_EVAPORATION_VOLUME: f32 = 2.0;
+
+impl FluidMode {
+    pub const ALL: [FluidMode; 2] = [FluidMode::Finite, FluidMode::Classic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FluidMode::Finite => "Finite",
+            FluidMode::Classic => "Classic",
+        }
+    }
+}
+
+impl FromStr for FluidMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown fluid mode {s}, expected finite or classic"))
+    }
+}
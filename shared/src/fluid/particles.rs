@@ -0,0 +1,115 @@
+use bevy::math::{IVec3, Vec3};
+use bevy::prelude::{Event, Resource};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::constants::{GRAVITY, TERMINAL_VELOCITY};
+use crate::world::{BlockHitbox, BlockId, WorldMap};
+
+/// Hard cap on simulated particles, to keep simulation and replication cheap
+pub const MAX_FLUID_PARTICLES: usize = 512;
+/// Seconds before a particle evaporates if it did not land anywhere
+pub const FLUID_PARTICLE_LIFETIME: f32 = 3.0;
+/// Horizontal speed given to particles spawned by a burst, in blocks per second
+const BURST_SPREAD_SPEED: f32 = 1.5;
+/// Air drag applied to particles each second
+const AIR_DRAG: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FluidParticle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub age: f32,
+}
+
+/// Positions of the fluid particles near a player, sent by the server every tick
+/// while the fluid particle mode is enabled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Event)]
+pub struct FluidParticlesUpdate {
+    pub positions: Vec<Vec3>,
+}
+
+/// What happened to a particle during a simulation step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParticleFate {
+    Alive,
+    /// Fell back into grid water, and merged with it
+    Merged,
+    /// Hit a solid block and splashed
+    Splashed,
+    Expired,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct FluidParticles {
+    pub particles: Vec<FluidParticle>,
+}
+
+impl FluidParticles {
+    /// Spawns `count` particles around `origin` with a random horizontal spread.
+    /// Particles over `MAX_FLUID_PARTICLES` are dropped.
+    pub fn spawn_burst(&mut self, origin: Vec3, initial_velocity: Vec3, count: usize) {
+        let mut rng = rand::thread_rng();
+        let available = MAX_FLUID_PARTICLES.saturating_sub(self.particles.len());
+
+        for _ in 0..count.min(available) {
+            let spread = Vec3::new(
+                rng.gen_range(-1.0..1.0) * BURST_SPREAD_SPEED,
+                rng.gen_range(0.0..0.5),
+                rng.gen_range(-1.0..1.0) * BURST_SPREAD_SPEED,
+            );
+            self.particles.push(FluidParticle {
+                position: origin,
+                velocity: initial_velocity + spread,
+                age: 0.0,
+            });
+        }
+    }
+
+    /// Advances every particle by `delta` seconds, removing the ones that landed or expired.
+    pub fn step(&mut self, world_map: &impl WorldMap, delta: f32) {
+        self.particles.retain_mut(|particle| {
+            step_particle(particle, world_map, delta) == ParticleFate::Alive
+        });
+    }
+
+    /// Positions of the particles within `radius` of `center`
+    pub fn positions_near(&self, center: Vec3, radius: f32) -> Vec<Vec3> {
+        let radius_sq = radius * radius;
+        self.particles
+            .iter()
+            .map(|particle| particle.position)
+            .filter(|position| position.distance_squared(center) <= radius_sq)
+            .collect()
+    }
+}
+
+fn step_particle(
+    particle: &mut FluidParticle,
+    world_map: &impl WorldMap,
+    delta: f32,
+) -> ParticleFate {
+    particle.age += delta;
+    if particle.age > FLUID_PARTICLE_LIFETIME {
+        return ParticleFate::Expired;
+    }
+
+    particle.velocity.y = (particle.velocity.y + GRAVITY * delta).max(-TERMINAL_VELOCITY);
+    particle.velocity *= 1.0 - AIR_DRAG * delta;
+    particle.position += particle.velocity * delta;
+
+    let block_pos = IVec3::new(
+        particle.position.x.floor() as i32,
+        particle.position.y.floor() as i32,
+        particle.position.z.floor() as i32,
+    );
+
+    match world_map.get_block_by_coordinates(&block_pos) {
+        None => ParticleFate::Alive,
+        Some(block) if block.id == BlockId::Water => ParticleFate::Merged,
+        Some(block) => match block.get_collision_hitbox() {
+            BlockHitbox::None => ParticleFate::Alive,
+            _ => ParticleFate::Splashed,
+        },
+    }
+}
@@ -6,15 +6,23 @@ use bevy_renet::renet::{ChannelConfig, ConnectionConfig, SendType};
 use bincode::Options;
 
 pub mod constants;
+pub mod fluid;
 pub mod messages;
 pub mod physics;
 pub mod players;
+pub mod sets;
+pub mod transport;
 pub mod utils;
+pub mod voice;
+pub mod water;
 pub mod world;
 
 pub use constants::*;
+use fluid::FluidMode;
 use messages::{ClientToServerMessage, ServerToClientMessage};
+use players::GameMode;
 use utils::format_bytes;
+use world::{WorldGenPreset, WorldSeed};
 
 #[derive(Resource, Debug, Clone)]
 pub struct GameFolderPaths {
@@ -33,14 +41,29 @@ pub struct GameServerConfig {
     pub world_name: String,
     pub is_solo: bool,
     pub broadcast_render_distance: i32,
+    /// Opt-in simulation of fluid particles for waterfalls and pours (see `fluid`)
+    pub fluid_particles: bool,
+    /// How water and lava flow (see `fluid::FluidMode`)
+    pub fluid_mode: FluidMode,
+    /// Largest volume of settled water that evaporates, 0 to keep all water
+    pub evaporation_volume: f32,
+    /// Game mode of the players joining the server
+    pub game_mode: GameMode,
+    /// Preset of the world if it doesn't exist yet, existing worlds keep theirs
+    pub world_preset: WorldGenPreset,
+    /// Seed of the world if it doesn't exist yet, random if unset
+    pub world_seed: Option<WorldSeed>,
 }
 
 const MAX_MEMORY: usize = 128 * 1024 * 1024;
+/// Voice frames are dropped rather than queued when the connection can't keep up
+const VOICE_MAX_MEMORY: usize = 1024 * 1024;
 const RESEND_TIME: Duration = Duration::from_millis(300);
 const AVAILABLE_BYTES_PER_TICK: u64 = 5 * 1024 * 1024;
 
 pub const CTS_STANDARD_CHANNEL: u8 = 0;
 pub const CTS_AUTH_CHANNEL: u8 = 1;
+pub const CTS_VOICE_CHANNEL: u8 = 2;
 
 pub fn get_customized_client_to_server_channels() -> Vec<ChannelConfig> {
     vec![
@@ -58,12 +81,18 @@ pub fn get_customized_client_to_server_channels() -> Vec<ChannelConfig> {
                 resend_time: RESEND_TIME,
             },
         },
+        ChannelConfig {
+            channel_id: CTS_VOICE_CHANNEL,
+            max_memory_usage_bytes: VOICE_MAX_MEMORY,
+            send_type: SendType::Unreliable,
+        },
     ]
 }
 
 pub const STC_STANDARD_CHANNEL: u8 = 0;
 pub const STC_CHUNK_DATA_CHANNEL: u8 = 1;
 pub const STC_AUTH_CHANNEL: u8 = 2;
+pub const STC_VOICE_CHANNEL: u8 = 3;
 
 pub fn get_customized_server_to_client_channels() -> Vec<ChannelConfig> {
     vec![
@@ -88,9 +117,16 @@ pub fn get_customized_server_to_client_channels() -> Vec<ChannelConfig> {
                 resend_time: RESEND_TIME,
             },
         },
+        ChannelConfig {
+            channel_id: STC_VOICE_CHANNEL,
+            max_memory_usage_bytes: VOICE_MAX_MEMORY,
+            send_type: SendType::Unreliable,
+        },
     ]
 }
 
+/// Channels are handled by renet on both ends, so they behave the same whatever
+/// transport carries the packets, see [`transport`]
 pub fn get_shared_renet_config() -> ConnectionConfig {
     ConnectionConfig {
         client_channels_config: get_customized_client_to_server_channels(),
@@ -130,6 +166,7 @@ pub fn get_game_folder_paths(
 
     if let Some(game_data) = game_folder_path {
         paths.game_folder_path = game_data.into();
+        paths.shaders_folder_path = paths.game_folder_path.join("shaders");
     }
     if let Some(game_assets) = assets_folder_path {
         paths.assets_folder_path = game_assets.into();
@@ -173,6 +210,7 @@ impl ChannelResolvableExt for ClientToServerMessage {
     fn get_channel_id(&self) -> u8 {
         match self {
             ClientToServerMessage::AuthRegisterRequest(_) => CTS_AUTH_CHANNEL,
+            ClientToServerMessage::Voice(_) => CTS_VOICE_CHANNEL,
             _ => CTS_STANDARD_CHANNEL,
         }
     }
@@ -181,8 +219,12 @@ impl ChannelResolvableExt for ClientToServerMessage {
 impl ChannelResolvableExt for ServerToClientMessage {
     fn get_channel_id(&self) -> u8 {
         match self {
-            ServerToClientMessage::WorldUpdate(_) => STC_CHUNK_DATA_CHANNEL,
+            // Dimension changes are ordered with the chunks, which they replace
+            ServerToClientMessage::WorldUpdate(_)
+            | ServerToClientMessage::FarTerrain(_)
+            | ServerToClientMessage::DimensionChange(_) => STC_CHUNK_DATA_CHANNEL,
             ServerToClientMessage::AuthRegisterResponse(_) => STC_AUTH_CHANNEL,
+            ServerToClientMessage::Voice(_) => STC_VOICE_CHANNEL,
             _ => STC_STANDARD_CHANNEL,
         }
     }
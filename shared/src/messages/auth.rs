@@ -1,10 +1,16 @@
+use bevy::math::IVec3;
 use serde::{Deserialize, Serialize};
 
+use crate::fluid::FluidMode;
+use crate::players::GameMode;
+
 use super::{ClientToServerMessage, PlayerSpawnEvent, ServerToClientMessage};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct AuthRegisterRequest {
     pub username: String,
+    /// See `block_registry_hash`
+    pub block_registry_hash: u64,
 }
 
 impl From<AuthRegisterRequest> for ClientToServerMessage {
@@ -21,6 +27,16 @@ pub struct AuthRegisterResponse {
     pub timestamp_ms: u64,
     pub players: Vec<PlayerSpawnEvent>, // all players (including the new one)
     pub world_seed: u32,                // World seed for biome calculation
+    /// Ground block of the world spawn
+    pub world_spawn: IVec3,
+    pub game_mode: GameMode,
+    /// Distance in chunks up to which the server sends the world
+    pub render_distance: i32,
+    /// How water and lava flow on the server
+    pub fluid_mode: FluidMode,
+    /// Blocks of the server by id, only sent when its registry differs from the
+    /// one of the client
+    pub block_registry: Option<Vec<String>>,
 }
 
 impl From<AuthRegisterResponse> for ServerToClientMessage {
@@ -1,4 +1,4 @@
-use bevy::prelude::Resource;
+use bevy::prelude::{Event, Resource};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -17,3 +17,9 @@ pub struct FullChatMessage {
 pub struct ChatConversation {
     pub messages: Vec<FullChatMessage>,
 }
+
+/// Message from the server itself, shown in every player's chat
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ServerAnnouncement {
+    pub content: String,
+}
@@ -8,3 +8,9 @@ pub struct MobUpdateEvent {
     pub id: MobId,
     pub mob: ServerMob,
 }
+
+/// Sent to everyone when a mob dies
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct MobDespawnEvent {
+    pub id: MobId,
+}
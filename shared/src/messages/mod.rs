@@ -1,12 +1,20 @@
 mod auth;
 mod chat;
 pub mod mob;
+mod overlay;
 pub mod player;
 mod world;
 
+use crate::fluid::{BucketUse, FluidParticlesUpdate};
+use crate::players::{AnimationEvent, Emote, PlayerRosterUpdate, SetSpawnPoint};
+use crate::voice::{VoiceFrame, VoicePacket};
+use crate::water::WaterAuditReport;
+use crate::world::{Difficulty, HeatmapKind, ItemId, PrefabPlacement, RollbackRequest};
 pub use auth::*;
+use bevy::math::IVec3;
 pub use chat::*;
-use mob::MobUpdateEvent;
+use mob::{MobDespawnEvent, MobUpdateEvent};
+pub use overlay::*;
 pub use player::*;
 use serde::{Deserialize, Serialize};
 pub use world::*;
@@ -20,6 +28,46 @@ pub enum ClientToServerMessage {
     Exit,
     PlayerInputs(Vec<PlayerFrameInput>),
     SaveWorldRequest,
+    /// Enables or disables the water volume audit, for debugging
+    SetWaterAudit(bool),
+    /// Pauses or resumes the integrated server while the pause menu is open.
+    /// Ignored by multiplayer servers.
+    SetPause(bool),
+    /// Takes a full stack of an item from the creative catalog
+    CreativeTakeItem(ItemId),
+    Voice(VoiceFrame),
+    Emote(Emote),
+    /// Teleports the player from the waystone they used to another one they discovered
+    WaystoneTeleport {
+        from: IVec3,
+        to: IVec3,
+    },
+    /// Changes the difficulty of the world, from the `/difficulty` command
+    SetDifficulty(Difficulty),
+    /// Sets the spawn point of a player, from the `/spawnpoint` command
+    SetSpawnPoint(SetSpawnPoint),
+    /// Teleports the player to the world spawn, from the `/spawn` command
+    TeleportToSpawn,
+    /// Generates the corrupt chunks again, from the `/regenerate` command
+    RegenerateCorruptChunks,
+    /// Asks for the world seed, from the `/seed` command
+    RequestSeed,
+    /// Looks for the nearest biome or structure with the given name, from the `/locate` command
+    Locate(String),
+    /// Asks for the heatmap of a kind of block activity, from the `/heatmap` command
+    RequestHeatmap(HeatmapKind),
+    /// Undoes the block changes of a player or around the operator, from the `/rollback` command
+    Rollback(RollbackRequest),
+    /// Goes through the portal the player is standing in
+    ChangeDimension,
+    /// Builds a prefab of the client's library, creative players only
+    PlacePrefab(PrefabPlacement),
+    /// Asks again for chunks within the render distance that never arrived
+    RequestChunks(Vec<IVec3>),
+    /// Fills an empty bucket from a water block, or pours a water bucket out
+    UseBucket(BucketUse),
+    /// Gets off the boat the player rides
+    LeaveBoat,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,5 +77,22 @@ pub enum ServerToClientMessage {
     WorldUpdate(WorldUpdate),
     PlayerSpawn(PlayerSpawnEvent),
     MobUpdate(MobUpdateEvent),
+    MobDespawn(MobDespawnEvent),
     PlayerUpdate(PlayerUpdateEvent),
+    FluidParticles(FluidParticlesUpdate),
+    WaterAudit(WaterAuditReport),
+    PlayerRoster(PlayerRosterUpdate),
+    Announcement(ServerAnnouncement),
+    Weather(WeatherUpdate),
+    Voice(VoicePacket),
+    Animation(AnimationEvent),
+    Waystone(WaystoneUpdate),
+    TimeSkip(WorldTimeSkip),
+    ItemFrame(ItemFrameUpdate),
+    FarTerrain(FarTerrainUpdate),
+    Fishing(FishingUpdate),
+    Boats(BoatUpdate),
+    Heatmap(HeatmapUpdate),
+    DimensionChange(DimensionChange),
+    UiOverlay(UiOverlay),
 }
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Time taken by overlays to fade in and out, in seconds
+pub const OVERLAY_FADE_SECS: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossBarColor {
+    Red,
+    Blue,
+    Green,
+    Yellow,
+    Purple,
+    White,
+}
+
+/// Bar at the top of the screen following the progress of something, like the
+/// health of a boss or the time left in a challenge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BossBar {
+    /// Chosen by the server, sending a bar with the same id updates it
+    pub id: u32,
+    pub title: String,
+    /// From 0 to 1
+    pub progress: f32,
+    pub color: BossBarColor,
+}
+
+/// HUD elements driven by the server, shown until removed or until their time is up
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum UiOverlay {
+    /// Shows a boss bar, or updates the one with the same id
+    BossBar(BossBar),
+    RemoveBossBar(u32),
+    /// Large text in the middle of the screen
+    Title {
+        title: String,
+        subtitle: String,
+        duration_secs: f32,
+    },
+    /// Short message right above the hotbar
+    ActionBar {
+        text: String,
+        duration_secs: f32,
+    },
+}
@@ -3,7 +3,8 @@ use bevy_platform::collections::HashSet;
 use serde::{Deserialize, Serialize};
 
 use super::PlayerId;
-use crate::players::{Inventory, ViewMode};
+use crate::players::{Inventory, SpawnPoint, ViewMode};
+use crate::world::{Boat, DimensionId, StatusEffects};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Eq, Hash)]
 pub enum NetworkAction {
@@ -16,6 +17,8 @@ pub enum NetworkAction {
     ToggleFlyMode,
     LeftClick,
     RightClick,
+    /// Hits the entity the player is looking at, sent on press only
+    Attack,
 }
 
 #[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
@@ -23,6 +26,10 @@ pub struct PlayerSave {
     pub position: Vec3,
     pub camera_transform: Transform,
     pub is_flying: bool,
+    #[serde(default)]
+    pub spawn_point: Option<SpawnPoint>,
+    #[serde(default)]
+    pub dimension: DimensionId,
 }
 
 #[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -39,6 +46,13 @@ pub struct PlayerUpdateEvent {
     pub orientation: Quat,
     pub last_ack_time: u64,
     pub inventory: Inventory,
+    pub health: f32,
+    /// Air left, see `players::Breath`
+    pub air: u32,
+    pub effects: StatusEffects,
+    pub dimension: DimensionId,
+    /// Boat the player rides
+    pub boat: Option<Boat>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -51,4 +65,7 @@ pub struct PlayerFrameInput {
     pub view_mode: ViewMode,
     #[serde(skip)]
     pub position: Vec3,
+    /// Boat ridden after the input, checked along with the position
+    #[serde(skip)]
+    pub boat: Option<Boat>,
 }
@@ -1,6 +1,10 @@
 use std::collections::HashMap;
 
-use crate::world::{ItemStack, MobId, ServerChunk, ServerMob};
+use crate::messages::PlayerId;
+use crate::world::{
+    Boat, BoatId, BobberState, DimensionId, FarTerrainTile, HeatmapKind, ItemFrame, ItemStack,
+    MobId, ServerChunk, ServerMob, WaystoneEntry, WeatherKind,
+};
 use bevy::{
     math::{IVec3, Vec3},
     prelude::Event,
@@ -29,3 +33,93 @@ pub struct ChunkUpdate {
     pub position: IVec3,
     pub chunk: ServerChunk,
 }
+
+/// Sent to players when they join and whenever the weather changes
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeatherUpdate {
+    pub kind: WeatherKind,
+}
+
+/// Chat command generating again the chunks whose save was damaged, for operators
+pub const REGENERATE_CHUNKS_COMMAND: &str = "/regenerate";
+
+/// Sent to everyone when the night is skipped, with the tick the world jumped to
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldTimeSkip {
+    pub tick: u64,
+}
+
+/// Summaries of the terrain beyond the full chunks, see `far_terrain`. The rings
+/// tell the client which tiles to hide and which ones it can forget.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FarTerrainUpdate {
+    /// Columns up to this many chunks from the player are sent as full chunks
+    pub inner_radius: i32,
+    /// Columns further than this many chunks from the player aren't sent
+    pub outer_radius: i32,
+    pub tiles: Vec<FarTerrainTile>,
+}
+
+/// Content of item frames, sent to players when they join and whenever a frame
+/// changes. Empty frames were emptied or broken.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ItemFrameUpdate {
+    pub frames: Vec<(IVec3, ItemFrame)>,
+}
+
+/// Bobber of a player's fishing line, sent every tick while it is out. `None`
+/// once the line was reeled in or broke.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FishingUpdate {
+    pub player: PlayerId,
+    pub bobber: Option<FishingBobber>,
+}
+
+/// Boats nobody rides that were put on the water, moved, or were boarded or
+/// picked up (`None`). Players get all the boats when they join.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BoatUpdate {
+    pub boats: Vec<(BoatId, Option<Boat>)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct FishingBobber {
+    pub position: Vec3,
+    pub state: BobberState,
+    /// A fish is biting, reeling in now catches something
+    pub biting: bool,
+}
+
+/// Busiest chunks for a kind of block activity, with their block count, sent to
+/// the operator who asked for the heatmap
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeatmapUpdate {
+    pub kind: HeatmapKind,
+    pub chunks: Vec<(IVec3, u32)>,
+}
+
+/// Sent to a player who went through a portal, whose chunks are all replaced by
+/// the ones of the new dimension
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DimensionChange {
+    pub dimension: DimensionId,
+    pub position: Vec3,
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum WaystoneUpdate {
+    /// A player activated a waystone, sent to everyone for the effects
+    Activated { position: IVec3, player: PlayerId },
+    /// Sent to a player using an activated waystone, to pick a destination
+    Menu {
+        from: IVec3,
+        destinations: Vec<WaystoneEntry>,
+        cooldown_secs: f32,
+    },
+    /// A player teleported between two waystones, sent to everyone for the effects
+    Teleported {
+        player: PlayerId,
+        from: IVec3,
+        to: IVec3,
+    },
+}
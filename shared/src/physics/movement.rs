@@ -9,13 +9,17 @@ use bevy_rapier3d::prelude::*;
 use crate::{
     messages::{NetworkAction, PlayerFrameInput},
     physics::{
-        constants::{FLY_SPEED_MULTIPLIER, GRAVITY, JUMP_VELOCITY, PLAYER_SPEED},
+        constants::{FLY_SPEED_MULTIPLIER, GRAVITY, JUMP_VELOCITY, PLAYER_SPEED, STEP_HEIGHT},
         water as water_physics, RustcraftPhysicsBody,
     },
     players::Player,
+    water::{water_current, WATER_CURRENT_SPEED},
     world::{world_position_to_chunk_position, WorldMap},
 };
 
+/// Height above the feet of the player where the current carrying them is taken
+const CURRENT_SAMPLE_HEIGHT: f32 = 0.1;
+
 /// Recompute gravity_enabled based on whether required chunks are loaded.
 fn compute_gravity_enabled(player: &Player, world_map: &impl WorldMap) -> bool {
     let current_chunk = world_position_to_chunk_position(player.position);
@@ -66,6 +70,24 @@ pub fn simulate_player_movement_rapier<W: WorldMap>(
         return;
     }
 
+    // The boat moves instead of the player riding it
+    if let Some(mut boat) = player.boat {
+        let axis = |positive, negative| {
+            action.inputs.contains(&positive) as i32 as f32
+                - action.inputs.contains(&negative) as i32 as f32
+        };
+        let forward = axis(NetworkAction::MoveForward, NetworkAction::MoveBackward);
+        let turn = axis(NetworkAction::MoveLeft, NetworkAction::MoveRight);
+        boat.step(world_map, forward, turn, delta);
+        player.boat = Some(boat);
+        player.camera_transform = action.camera;
+        player.position = boat.seat(player.height);
+        player.velocity = Vec3::ZERO;
+        player.in_water = false;
+        player.water_submersion = 0.0;
+        return;
+    }
+
     // Handle fly mode toggle
     if action.inputs.contains(&NetworkAction::ToggleFlyMode) {
         player.is_flying = !player.is_flying;
@@ -90,8 +112,16 @@ pub fn simulate_player_movement_rapier<W: WorldMap>(
         apply_ground_physics(player, world_map, &mut direction, action, delta);
     }
 
+    // Flowing water carries swimming players downstream
+    let drift = if player.in_water && !player.is_flying {
+        let feet = player.position - Vec3::Y * (player.height / 2.0 - CURRENT_SAMPLE_HEIGHT);
+        water_current(world_map, feet) * WATER_CURRENT_SPEED
+    } else {
+        Vec3::ZERO
+    };
+
     // Apply movement with collision
-    apply_movement_with_collision(player, world_map, direction, delta);
+    apply_movement_with_collision(player, world_map, direction, drift, delta);
 
     // Safety net
     apply_safety_net(player);
@@ -194,11 +224,13 @@ fn apply_ground_physics<W: WorldMap>(
     direction.y = 0.0;
 }
 
-/// Apply movement with voxel collision detection.
+/// Apply movement with voxel collision detection, `drift` being moved by in
+/// blocks per second on top of the walking or swimming.
 fn apply_movement_with_collision<W: WorldMap>(
     player: &mut Player,
     world_map: &W,
     direction: Vec3,
+    drift: Vec3,
     delta: f32,
 ) {
     use bevy::math::bounding::Aabb3d;
@@ -207,27 +239,42 @@ fn apply_movement_with_collision<W: WorldMap>(
         PLAYER_SPEED * FLY_SPEED_MULTIPLIER
     } else {
         PLAYER_SPEED
-    };
+    } * player.effects.speed_multiplier();
 
     let horizontal_displacement = Vec3::new(
-        direction.x * speed * delta,
+        (direction.x * speed + drift.x) * delta,
         0.0,
-        direction.z * speed * delta,
+        (direction.z * speed + drift.z) * delta,
     );
     let vertical_displacement = Vec3::new(0.0, player.velocity.y * delta, 0.0);
 
     let half_extents = Vec3::new(player.width / 2.0, player.height / 2.0, player.width / 2.0);
 
+    let can_step = player.on_ground && !player.is_flying;
+    let try_horizontal = |position: Vec3| -> Option<Vec3> {
+        if !world_map.check_collision_box(&Aabb3d::new(position, half_extents)) {
+            return Some(position);
+        }
+        // Walk onto low obstacles, gravity brings the player back onto their surface
+        let stepped = position + Vec3::new(0.0, STEP_HEIGHT, 0.0);
+        (can_step && !world_map.check_collision_box(&Aabb3d::new(stepped, half_extents)))
+            .then_some(stepped)
+    };
+
     // Try horizontal movement (X axis)
-    let candidate_x = player.position + Vec3::new(horizontal_displacement.x, 0.0, 0.0);
-    if !world_map.check_collision_box(&Aabb3d::new(candidate_x, half_extents)) {
-        player.position.x = candidate_x.x;
+    if horizontal_displacement.x != 0.0 {
+        let candidate_x = player.position + Vec3::new(horizontal_displacement.x, 0.0, 0.0);
+        if let Some(position) = try_horizontal(candidate_x) {
+            player.position = position;
+        }
     }
 
     // Try horizontal movement (Z axis)
-    let candidate_z = player.position + Vec3::new(0.0, 0.0, horizontal_displacement.z);
-    if !world_map.check_collision_box(&Aabb3d::new(candidate_z, half_extents)) {
-        player.position.z = candidate_z.z;
+    if horizontal_displacement.z != 0.0 {
+        let candidate_z = player.position + Vec3::new(0.0, 0.0, horizontal_displacement.z);
+        if let Some(position) = try_horizontal(candidate_z) {
+            player.position = position;
+        }
     }
 
     // Try vertical movement
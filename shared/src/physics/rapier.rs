@@ -18,6 +18,8 @@ pub mod constants {
     pub const PLAYER_SPEED: f32 = 5.0;
     /// Fly mode speed multiplier
     pub const FLY_SPEED_MULTIPLIER: f32 = 4.0;
+    /// Obstacles up to this height (such as snow layers) are walked onto without jumping
+    pub const STEP_HEIGHT: f32 = 0.5;
 }
 
 /// Component marking an entity as using Rustcraft physics.
@@ -1,15 +1,19 @@
 //! Water physics integration for player movement.
 //!
-//! This module handles player-water interactions including:
+//! This module handles player and mob water interactions including:
 //! - Buoyancy forces
 //! - Water drag
 //! - Swimming mechanics
 //!
-//! Note: Wave motion is handled by bevy_water on the client side.
-//! This module focuses on gameplay physics (buoyancy, drag, swimming).
+//! Note: Wave motion is rendered by bevy_water on the client side, and can be
+//! sampled on the CPU with `crate::water::sample_wave_height`. The eyes of the
+//! players are under water below the waves, the rest of this module focuses on
+//! gameplay physics (buoyancy, drag, swimming).
 
-use crate::players::Player;
+use crate::players::{Player, EYE_HEIGHT};
+use crate::water::{bubble_column, is_below_water_surface, BUBBLE_COLUMN_FORCE};
 use crate::world::{BlockId, WorldMap};
+use bevy::math::{IVec3, Vec3};
 
 /// Constants for water physics
 pub mod constants {
@@ -32,41 +36,43 @@ pub mod constants {
 /// Calculate how submerged a player is in water
 /// Returns a value from 0.0 (not in water) to 1.0 (fully submerged)
 pub fn calculate_water_submersion(player: &Player, world_map: &impl WorldMap) -> f32 {
-    let player_bottom = player.position.y - player.height / 2.0;
-    let player_top = player.position.y + player.height / 2.0;
+    calculate_body_submersion(player.position, player.height, player.width, world_map)
+}
+
+/// Calculate how submerged a body centered on `position` is in water.
+/// Shared by players and mobs, returns a value from 0.0 to 1.0
+pub fn calculate_body_submersion(
+    position: Vec3,
+    height: f32,
+    width: f32,
+    world_map: &impl WorldMap,
+) -> f32 {
+    let bottom = position.y - height / 2.0;
+    let top = position.y + height / 2.0;
 
-    // Sample water level at multiple points around the player
+    // Sample water level at multiple points around the body
     let sample_positions = [
-        (player.position.x as i32, player.position.z as i32),
-        (
-            (player.position.x + player.width * 0.4) as i32,
-            player.position.z as i32,
-        ),
-        (
-            (player.position.x - player.width * 0.4) as i32,
-            player.position.z as i32,
-        ),
-        (
-            player.position.x as i32,
-            (player.position.z + player.width * 0.4) as i32,
-        ),
-        (
-            player.position.x as i32,
-            (player.position.z - player.width * 0.4) as i32,
-        ),
+        (position.x as i32, position.z as i32),
+        ((position.x + width * 0.4) as i32, position.z as i32),
+        ((position.x - width * 0.4) as i32, position.z as i32),
+        (position.x as i32, (position.z + width * 0.4) as i32),
+        (position.x as i32, (position.z - width * 0.4) as i32),
     ];
 
     let mut max_submersion: f32 = 0.0;
 
     for (sample_x, sample_z) in &sample_positions {
-        // Find the highest water block at this position
-        let water_height = find_water_surface_height(world_map, *sample_x, *sample_z);
+        let Some(water_height) =
+            find_water_surface_height(world_map, *sample_x, *sample_z, bottom, top)
+        else {
+            continue;
+        };
 
-        if water_height > player_bottom {
-            let submersion = if water_height >= player_top {
+        if water_height > bottom {
+            let submersion = if water_height >= top {
                 1.0
             } else {
-                (water_height - player_bottom) / player.height
+                (water_height - bottom) / height
             };
             max_submersion = max_submersion.max(submersion);
         }
@@ -75,29 +81,46 @@ pub fn calculate_water_submersion(player: &Player, world_map: &impl WorldMap) ->
     max_submersion.clamp(0.0, 1.0)
 }
 
-/// Find the water surface height at a given XZ position
-fn find_water_surface_height(world_map: &impl WorldMap, x: i32, z: i32) -> f32 {
-    // Search downward from maximum height
-    for y in (0..constants::MAX_WATER_SEARCH_HEIGHT).rev() {
-        if let Some(block) = world_map.get_block_by_coordinates(&bevy::math::IVec3::new(x, y, z)) {
-            if block.id == BlockId::Water {
-                // Found water, now find the surface (first air block above water)
-                for surface_y in y..constants::MAX_WATER_SEARCH_HEIGHT {
-                    if let Some(above_block) =
-                        world_map.get_block_by_coordinates(&bevy::math::IVec3::new(x, surface_y, z))
-                    {
-                        if above_block.id != BlockId::Water {
-                            return surface_y as f32;
-                        }
-                    } else {
-                        return surface_y as f32;
-                    }
-                }
-                return y as f32 + 1.0; // Default to one block above water
-            }
-        }
-    }
-    0.0 // No water found
+fn is_water_block(world_map: &impl WorldMap, cell: IVec3) -> bool {
+    world_map
+        .get_block_by_coordinates(&cell)
+        .is_some_and(|block| block.id == BlockId::Water || block.id.is_waterlogged())
+}
+
+/// Whether the eyes of a player are below the displaced water surface, with the
+/// waves sampled at `time` and `amplitude`
+pub fn eyes_under_water(
+    player: &Player,
+    world_map: &impl WorldMap,
+    time: f32,
+    amplitude: f32,
+) -> bool {
+    is_below_water_surface(
+        player.position + Vec3::Y * EYE_HEIGHT,
+        time,
+        amplitude,
+        |cell| is_water_block(world_map, cell),
+    )
+}
+
+/// Find the surface of the water a body spanning from `bottom` to `top` is in, at
+/// a given XZ position. Only the water touching the body counts, so that the air
+/// pockets enclosed under a lake are dry.
+fn find_water_surface_height(
+    world_map: &impl WorldMap,
+    x: i32,
+    z: i32,
+    bottom: f32,
+    top: f32,
+) -> Option<f32> {
+    let is_water = |y: i32| is_water_block(world_map, IVec3::new(x, y, z));
+
+    // Lowest water block the body is in, then the first block above that isn't water
+    let lowest = (bottom.floor() as i32..=top.floor() as i32).find(|y| is_water(*y))?;
+    let surface = (lowest..constants::MAX_WATER_SEARCH_HEIGHT)
+        .find(|y| !is_water(*y))
+        .unwrap_or(constants::MAX_WATER_SEARCH_HEIGHT);
+    Some(surface as f32)
 }
 
 /// Apply water physics to player movement
@@ -113,20 +136,69 @@ pub fn apply_water_physics(player: &mut Player, world_map: &impl WorldMap, delta
         return;
     }
 
+    apply_buoyancy(&mut player.velocity, submersion, delta);
+    apply_bubble_column(&mut player.velocity, player.position, world_map, delta);
+}
+
+/// Pushes a body at `position` up or down if it is in a bubble column
+pub fn apply_bubble_column(
+    velocity: &mut Vec3,
+    position: Vec3,
+    world_map: &impl WorldMap,
+    delta: f32,
+) {
+    let column = bubble_column(position.floor().as_ivec3(), |cell| {
+        world_map
+            .get_block_by_coordinates(&cell)
+            .map(|block| block.id)
+    });
+    if let Some(column) = column {
+        velocity.y += column.direction() * BUBBLE_COLUMN_FORCE * delta;
+    }
+}
+
+/// Apply buoyancy and water drag to a velocity, given the submersion of the body
+pub fn apply_buoyancy(velocity: &mut Vec3, submersion: f32, delta: f32) {
     // Apply buoyancy force (upward)
     let buoyancy = constants::BUOYANCY_FORCE * submersion * delta;
-    player.velocity.y += buoyancy;
+    velocity.y += buoyancy;
 
     // Apply water drag to all velocities
     let drag_factor = 1.0 - (constants::WATER_DRAG * submersion * delta);
-    player.velocity.x *= drag_factor;
-    player.velocity.z *= drag_factor;
-    player.velocity.y *= 1.0 - (constants::WATER_VERTICAL_DRAG * submersion * delta);
+    velocity.x *= drag_factor;
+    velocity.z *= drag_factor;
+    velocity.y *= 1.0 - (constants::WATER_VERTICAL_DRAG * submersion * delta);
 
     // Limit vertical velocity in water
     const MAX_WATER_VELOCITY: f32 = 5.0;
-    player.velocity.y = player
-        .velocity
-        .y
-        .clamp(-MAX_WATER_VELOCITY, MAX_WATER_VELOCITY);
+    velocity.y = velocity.y.clamp(-MAX_WATER_VELOCITY, MAX_WATER_VELOCITY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockData, BlockDirection, ServerChunkWorldMap};
+    use bevy::math::IVec3;
+
+    #[test]
+    fn air_pockets_under_water_are_dry() {
+        let mut world = ServerChunkWorldMap::default();
+        let water = BlockData::new(BlockId::Water, BlockDirection::Front);
+        let stone = BlockData::new(BlockId::Stone, BlockDirection::Front);
+        // A lake from y = 10 to 20, with a stone ceiling over a pocket of air below
+        for y in 10..20 {
+            world.set_block(&IVec3::new(0, y, 0), water);
+        }
+        world.set_block(&IVec3::new(0, 9, 0), stone);
+        world.set_block(&IVec3::new(0, 5, 0), stone);
+
+        let in_lake = calculate_body_submersion(Vec3::new(0.5, 15.0, 0.5), 1.8, 0.0, &world);
+        assert_eq!(in_lake, 1.0);
+
+        let at_surface = calculate_body_submersion(Vec3::new(0.5, 20.0, 0.5), 2.0, 0.0, &world);
+        assert_eq!(at_surface, 0.5);
+
+        let in_pocket = calculate_body_submersion(Vec3::new(0.5, 7.0, 0.5), 1.8, 0.0, &world);
+        assert_eq!(in_pocket, 0.0);
+    }
 }
@@ -0,0 +1,86 @@
+//! Air of the players, lost while their head is under water.
+//!
+//! Players hold `MAX_PLAYER_AIR` ticks of air. They lose a tick of it on each
+//! tick spent with their eyes under the waves, and get it back quickly once out.
+//! Without air left, they drown, losing `DROWNING_DAMAGE` every second until
+//! they surface or die.
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::TICKS_PER_SECOND;
+
+/// Air of a player out of water, in ticks they can spend under water
+pub const MAX_PLAYER_AIR: u32 = 15 * TICKS_PER_SECOND as u32;
+/// Air gained back on each tick with the head out of water
+const AIR_REFILL_PER_TICK: u32 = 5;
+/// Height of the eyes of a player above their position
+pub const EYE_HEIGHT: f32 = 0.8;
+/// Health lost to drowning, once a second
+pub const DROWNING_DAMAGE: f32 = 2.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Breath {
+    pub air: u32,
+    /// Ticks spent without air since the last drowning damage
+    #[serde(skip)]
+    drowning_ticks: u64,
+}
+
+impl Default for Breath {
+    fn default() -> Self {
+        Self {
+            air: MAX_PLAYER_AIR,
+            drowning_ticks: 0,
+        }
+    }
+}
+
+impl Breath {
+    /// Spends or refills the air for one tick, returns the damage taken by
+    /// drowning
+    pub fn tick(&mut self, head_under_water: bool) -> f32 {
+        if !head_under_water {
+            self.air = (self.air + AIR_REFILL_PER_TICK).min(MAX_PLAYER_AIR);
+            self.drowning_ticks = 0;
+            return 0.0;
+        }
+        if self.air > 0 {
+            self.air -= 1;
+            return 0.0;
+        }
+
+        self.drowning_ticks += 1;
+        if self.drowning_ticks < TICKS_PER_SECOND {
+            return 0.0;
+        }
+        self.drowning_ticks = 0;
+        DROWNING_DAMAGE
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.air >= MAX_PLAYER_AIR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_drown_once_out_of_air() {
+        let mut breath = Breath::default();
+        let damage: f32 = (0..MAX_PLAYER_AIR).map(|_| breath.tick(true)).sum();
+        assert_eq!(damage, 0.0);
+        assert_eq!(breath.air, 0);
+
+        let damage: f32 = (0..3 * TICKS_PER_SECOND).map(|_| breath.tick(true)).sum();
+        assert_eq!(damage, 3.0 * DROWNING_DAMAGE);
+
+        breath.tick(false);
+        assert_eq!(breath.air, AIR_REFILL_PER_TICK);
+        (0..MAX_PLAYER_AIR).for_each(|_| {
+            breath.tick(false);
+        });
+        assert!(breath.is_full());
+    }
+}
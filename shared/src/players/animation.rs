@@ -0,0 +1,101 @@
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::PlayerId;
+use crate::world::MobId;
+
+/// Prefix of the chat command triggering an emote, e.g. `/emote wave`
+pub const EMOTE_COMMAND: &str = "/emote";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emote {
+    Wave,
+    Sit,
+    Point,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 3] = [Emote::Wave, Emote::Sit, Emote::Point];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Emote::Wave => "wave",
+            Emote::Sit => "sit",
+            Emote::Point => "point",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|emote| emote.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses `/emote <name>`, or the `/<name>` shortcut
+pub fn parse_emote_command(message: &str) -> Option<Emote> {
+    let mut words = message.split_whitespace();
+    let command = words.next()?;
+    let name = if command == EMOTE_COMMAND {
+        words.next()?
+    } else {
+        command.strip_prefix('/')?
+    };
+
+    if words.next().is_some() {
+        return None;
+    }
+    Emote::from_name(name)
+}
+
+/// Animations that can be played on an entity
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AnimationKind {
+    Emote(Emote),
+    /// A player swinging their arm to attack
+    Swing,
+    /// An entity recoiling after being hit
+    Hurt,
+}
+
+impl AnimationKind {
+    /// Seconds the animation lasts, `None` if it lasts until the entity moves
+    pub fn duration(&self) -> Option<f32> {
+        match self {
+            AnimationKind::Emote(Emote::Wave) => Some(2.0),
+            AnimationKind::Emote(Emote::Point) => Some(1.5),
+            AnimationKind::Emote(Emote::Sit) => None,
+            AnimationKind::Swing => Some(0.3),
+            AnimationKind::Hurt => Some(0.4),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationTarget {
+    Player(PlayerId),
+    Mob(MobId),
+}
+
+/// Sent by the server so that every client plays an animation on the target
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AnimationEvent {
+    pub target: AnimationTarget,
+    pub kind: AnimationKind,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_emote_commands() {
+        assert_eq!(parse_emote_command("/emote wave"), Some(Emote::Wave));
+        assert_eq!(parse_emote_command("/emote  Sit "), Some(Emote::Sit));
+        assert_eq!(parse_emote_command("/point"), Some(Emote::Point));
+        assert_eq!(parse_emote_command("/emote"), None);
+        assert_eq!(parse_emote_command("/emote dance"), None);
+        assert_eq!(parse_emote_command("/wave at you"), None);
+        assert_eq!(parse_emote_command("wave"), None);
+    }
+}
@@ -1,9 +1,12 @@
 use crate::{
     messages::{NetworkAction, PlayerFrameInput},
     players::Player,
-    world::{raycast, BlockData, BlockDirection, FaceDirectionExt, ItemStack, ItemType, WorldMap},
+    world::{
+        item_frame_direction, raycast, BlockData, BlockDirection, BlockId, FaceDirectionExt,
+        ItemStack, ItemType, WorldMap,
+    },
 };
-use bevy::math::{NormedVectorSpace, Vec3};
+use bevy::math::{IVec3, NormedVectorSpace, Vec3};
 use bevy_log::info;
 
 #[derive(Debug, Clone, Copy)]
@@ -21,6 +24,13 @@ impl CallerType {
     }
 }
 
+/// Block changes caused by an interaction, used by the client for feedback such as sounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockInteractionOutcome {
+    Placed { block: BlockData, position: IVec3 },
+    Broken { block: BlockData, position: IVec3 },
+}
+
 const INTERACTION_DISTANCE: f32 = 5.0;
 const CUBE_SIZE: f32 = 1.0;
 
@@ -29,19 +39,21 @@ pub fn simulate_player_block_interactions(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Vec<BlockInteractionOutcome> {
     // TODO: make sure that only one interaction is processed per game tick (instead of per frame like now)
-    for network_action in &action.inputs {
-        match network_action {
+    action
+        .inputs
+        .iter()
+        .filter_map(|network_action| match network_action {
             NetworkAction::LeftClick => {
-                handle_block_breaking(player, world_map, action, caller_type);
+                handle_block_breaking(player, world_map, action, caller_type)
             }
             NetworkAction::RightClick => {
-                handle_block_placement(player, world_map, action, caller_type);
+                handle_block_placement(player, world_map, action, caller_type)
             }
-            _ => {}
-        }
-    }
+            _ => None,
+        })
+        .collect()
 }
 
 fn handle_block_breaking(
@@ -49,7 +61,7 @@ fn handle_block_breaking(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockInteractionOutcome> {
     let block_position = raycast::raycast(
         world_map,
         &action.camera,
@@ -73,7 +85,7 @@ fn handle_block_breaking(
             player.position,
             action.view_mode
         );
-        return;
+        return None;
     }
 
     let block_pos = block_position.unwrap().position;
@@ -96,7 +108,7 @@ fn handle_block_breaking(
             block_pos,
             distance
         );
-        return;
+        return None;
     }
 
     let block = world_map.get_block_mut_by_coordinates(&block_pos);
@@ -107,7 +119,7 @@ fn handle_block_breaking(
             player.id,
             block_pos
         );
-        return;
+        return None;
     }
     let block = block.unwrap();
 
@@ -128,7 +140,7 @@ fn handle_block_breaking(
             block_pos
         );
 
-        world_map.remove_block_by_coordinates(&block_pos);
+        let broken = world_map.remove_block_by_coordinates(&block_pos)?;
         // Add drops to player inventory
         for (item_id, nb) in block_id.get_drops(1) {
             player.inventory.add_item_to_inventory(ItemStack {
@@ -145,6 +157,10 @@ fn handle_block_breaking(
                 block_id
             );
         }
+        Some(BlockInteractionOutcome::Broken {
+            block: broken,
+            position: block_pos,
+        })
     } else {
         world_map.mark_block_for_update(&block_pos);
         info!(
@@ -156,6 +172,7 @@ fn handle_block_breaking(
             breaking_progress,
             break_time
         );
+        None
     }
 }
 
@@ -164,7 +181,7 @@ fn handle_block_placement(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockInteractionOutcome> {
     let raycast_response = raycast::raycast(
         world_map,
         &action.camera,
@@ -188,11 +205,16 @@ fn handle_block_placement(
             player.position,
             action.view_mode
         );
-        return;
+        return None;
     }
 
     let raycast_response = raycast_response.unwrap();
 
+    // Item frames take the held item instead, see `item_frames`
+    if raycast_response.block.id == BlockId::ItemFrame {
+        return None;
+    }
+
     let collision_pos = raycast_response.position;
     let face_direction = raycast_response.face;
 
@@ -206,13 +228,14 @@ fn handle_block_placement(
 
     let face = raycast_response.face.to_ivec3();
 
-    let block_to_create_pos = collision_pos + face;
+    // Replaceable blocks such as snow layers are overwritten by the new block
+    let block_to_create_pos = if raycast_response.block.id.is_replaceable() {
+        collision_pos
+    } else {
+        collision_pos + face
+    };
 
-    let block_to_create_pos_vec3 = Vec3::new(
-        (collision_pos.x + face.x) as f32,
-        (collision_pos.y + face.y) as f32,
-        (collision_pos.z + face.z) as f32,
-    );
+    let block_to_create_pos_vec3 = block_to_create_pos.as_vec3();
 
     let unit_cube = Vec3::new(CUBE_SIZE, CUBE_SIZE, CUBE_SIZE);
 
@@ -229,13 +252,13 @@ fn handle_block_placement(
             collision_pos,
             distance
         );
-        return;
+        return None;
     }
 
     // Check if there's already a block at that position
     if world_map
         .get_block_by_coordinates(&block_to_create_pos)
-        .is_some()
+        .is_some_and(|block| !block.id.is_replaceable())
     {
         log::warn!(
             "{} Player {} tried to place block at {:?} but a block already exists there",
@@ -243,7 +266,7 @@ fn handle_block_placement(
             player.id,
             block_to_create_pos
         );
-        return;
+        return None;
     }
 
     let delta = player.position - target_cube_center;
@@ -268,7 +291,7 @@ fn handle_block_placement(
             player.id,
             block_to_create_pos
         );
-        return;
+        return None;
     }
 
     let inventory_slot = action.hotbar_slot;
@@ -281,18 +304,34 @@ fn handle_block_placement(
             player.id,
             inventory_slot
         );
-        return;
+        return None;
     }
 
     // Try to get item from player's inventory
     if let Some(&item) = player.inventory.inner.get(&inventory_slot) {
         // Check if the item has a block counterpart
         if let ItemType::Block(block_id) = item.item_type {
+            // Item frames hang on the wall they were put on
+            let direction = if block_id == BlockId::ItemFrame {
+                let Some(direction) = item_frame_direction(face_direction) else {
+                    log::warn!(
+                        "{} Player {} tried to put an item frame on a floor or ceiling at {:?}",
+                        caller_type.as_str(),
+                        player.id,
+                        block_to_create_pos
+                    );
+                    return None;
+                };
+                direction
+            } else {
+                BlockDirection::Front
+            };
+
             // Remove item from inventory
             player.inventory.remove_item_from_stack(inventory_slot, 1);
 
             // Place the block
-            let block = BlockData::new(block_id, BlockDirection::Front);
+            let block = BlockData::new(block_id, direction);
             world_map.set_block(&block_to_create_pos, block);
 
             log::info!(
@@ -302,6 +341,12 @@ fn handle_block_placement(
                 block_id,
                 block_to_create_pos
             );
+            return Some(BlockInteractionOutcome::Placed {
+                block,
+                position: block_to_create_pos,
+            });
+        } else if let ItemType::SpawnEgg(_) | ItemType::Potion(_) = item.item_type {
+            // Mobs and effects are handled by the server only
         } else {
             log::warn!(
                 "{} Player {} tried to place item {:?} but it's not a block",
@@ -318,4 +363,5 @@ fn handle_block_placement(
             inventory_slot
         );
     }
+    None
 }
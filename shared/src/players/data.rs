@@ -5,9 +5,10 @@ use bevy::{
 use bevy_platform::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+use super::{Breath, SpawnPoint};
 use crate::{
     messages::PlayerId,
-    world::{ItemId, ItemStack, ItemType},
+    world::{Boat, DimensionId, ItemId, ItemStack, ItemType, StatusEffects},
     MAX_INVENTORY_SLOTS,
 };
 
@@ -121,6 +122,12 @@ impl Inventory {
     }
 }
 
+pub const MAX_PLAYER_HEALTH: f32 = 20.0;
+
+fn max_player_health() -> f32 {
+    MAX_PLAYER_HEALTH
+}
+
 #[derive(Component, Clone, Serialize, Deserialize, Debug)]
 pub struct Player {
     pub id: PlayerId,
@@ -131,9 +138,26 @@ pub struct Player {
     pub on_ground: bool,
     pub is_flying: bool,
     pub inventory: Inventory,
+    #[serde(default)]
+    pub game_mode: GameMode,
     pub height: f32,
     pub width: f32,
     pub last_input_processed: u64,
+    #[serde(default = "max_player_health")]
+    pub health: f32,
+    #[serde(default)]
+    pub effects: StatusEffects,
+    #[serde(default)]
+    pub breath: Breath,
+    /// Boat the player rides, which moves instead of them
+    #[serde(default)]
+    pub boat: Option<Boat>,
+    /// Where the player respawns in this world, the world spawn if unset
+    #[serde(default)]
+    pub spawn_point: Option<SpawnPoint>,
+    /// Dimension the player is in
+    #[serde(default)]
+    pub dimension: DimensionId,
     /// Cached gravity enabled state to avoid repeated chunk lookups
     #[serde(skip)]
     pub gravity_enabled: bool,
@@ -159,9 +183,16 @@ impl Player {
             on_ground: true,
             is_flying: false,
             inventory: Inventory::new(),
+            game_mode: GameMode::default(),
             height: 1.8,
             width: 0.8,
             last_input_processed: 0,
+            health: MAX_PLAYER_HEALTH,
+            effects: StatusEffects::default(),
+            breath: Breath::default(),
+            boat: None,
+            spawn_point: None,
+            dimension: DimensionId::Overworld,
             gravity_enabled: false,
             last_gravity_check_chunk: None,
             in_water: false,
@@ -186,9 +217,16 @@ impl Default for Player {
             on_ground: true,
             is_flying: false,
             inventory: Inventory::new(),
+            game_mode: GameMode::default(),
             height: 1.8,
             width: 0.8,
             last_input_processed: 0,
+            health: MAX_PLAYER_HEALTH,
+            effects: StatusEffects::default(),
+            breath: Breath::default(),
+            boat: None,
+            spawn_point: None,
+            dimension: DimensionId::Overworld,
             gravity_enabled: false,
             last_gravity_check_chunk: None,
             in_water: false,
@@ -212,3 +250,11 @@ impl ViewMode {
         };
     }
 }
+
+/// Creative players can take any item from the catalog, and don't use up spawn eggs
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Resource, Serialize, Deserialize, Default)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+}
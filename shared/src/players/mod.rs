@@ -1,7 +1,15 @@
+mod air;
+mod animation;
 pub mod blocks;
 pub mod collision;
 pub mod constants;
 mod data;
+mod roster;
 pub mod simulation;
+mod spawn;
 
+pub use air::*;
+pub use animation::*;
 pub use data::*;
+pub use roster::*;
+pub use spawn::*;
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::{Event, Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::PlayerId;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct PlayerRosterEntry {
+    pub id: PlayerId,
+    pub name: String,
+}
+
+/// Sent by the server when the set of connected players changes
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum PlayerRosterUpdate {
+    /// Every connected player, sent to a player when they join
+    Snapshot(Vec<PlayerRosterEntry>),
+    Joined(PlayerRosterEntry),
+    Left(PlayerRosterEntry),
+}
+
+/// Players connected to the server, kept up to date from roster updates
+#[derive(Resource, Default, Debug, Clone, PartialEq)]
+pub struct PlayerRoster {
+    players: BTreeMap<PlayerId, String>,
+}
+
+impl PlayerRoster {
+    pub fn apply(&mut self, update: &PlayerRosterUpdate) {
+        match update {
+            PlayerRosterUpdate::Snapshot(entries) => {
+                self.players = entries
+                    .iter()
+                    .map(|entry| (entry.id, entry.name.clone()))
+                    .collect();
+            }
+            PlayerRosterUpdate::Joined(entry) => {
+                self.players.insert(entry.id, entry.name.clone());
+            }
+            PlayerRosterUpdate::Left(entry) => {
+                self.players.remove(&entry.id);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.players.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    pub fn name(&self, id: PlayerId) -> Option<&str> {
+        self.players.get(&id).map(String::as_str)
+    }
+
+    /// Connected players sorted by name
+    pub fn entries(&self) -> Vec<(PlayerId, &str)> {
+        let mut entries: Vec<(PlayerId, &str)> = self
+            .players
+            .iter()
+            .map(|(id, name)| (*id, name.as_str()))
+            .collect();
+        entries.sort_unstable_by_key(|(_, name)| name.to_lowercase());
+        entries
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries().into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: PlayerId, name: &str) -> PlayerRosterEntry {
+        PlayerRosterEntry {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn tracks_joins_and_leaves() {
+        let mut roster = PlayerRoster::default();
+        roster.apply(&PlayerRosterUpdate::Snapshot(vec![
+            entry(1, "steve"),
+            entry(2, "Alex"),
+        ]));
+        roster.apply(&PlayerRosterUpdate::Joined(entry(3, "bob")));
+        roster.apply(&PlayerRosterUpdate::Left(entry(1, "steve")));
+
+        assert_eq!(roster.names(), vec!["Alex", "bob"]);
+        assert_eq!(roster.name(3), Some("bob"));
+        assert_eq!(roster.name(1), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Where players respawn, saved with their data in each world.
+//!
+//! Sleeping in a bed sets the spawn point to that bed, operators can also set
+//! it to any position with `/spawnpoint [player] [x y z]`. Players without a
+//! spawn point appear at the world spawn, where `/spawn` also brings them back.
+
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::world::BED_HEIGHT;
+
+/// Prefix of the chat command setting a spawn point
+pub const SPAWNPOINT_COMMAND: &str = "/spawnpoint";
+/// Chat command teleporting the player to the world spawn
+pub const SPAWN_COMMAND: &str = "/spawn";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SpawnPoint {
+    /// The bed at this block, which must still be there on respawn
+    Bed(IVec3),
+    Position(Vec3),
+}
+
+impl SpawnPoint {
+    /// Center of a player of the given height standing at the spawn point
+    pub fn standing_position(&self, player_height: f32) -> Vec3 {
+        match self {
+            SpawnPoint::Bed(bed) => {
+                bed.as_vec3() + Vec3::new(0.5, BED_HEIGHT + player_height / 2.0, 0.5)
+            }
+            SpawnPoint::Position(position) => *position,
+        }
+    }
+}
+
+/// `/spawnpoint [player] [x y z]`, both default to the player sending it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SetSpawnPoint {
+    pub player: Option<String>,
+    pub position: Option<Vec3>,
+}
+
+/// Parses `/spawnpoint [player] [x y z]`
+pub fn parse_spawnpoint_command(message: &str) -> Option<SetSpawnPoint> {
+    let mut words = message.split_whitespace();
+    if words.next()? != SPAWNPOINT_COMMAND {
+        return None;
+    }
+    let arguments: Vec<&str> = words.collect();
+    let (player, coordinates) = match arguments.len() {
+        0 | 3 => (None, &arguments[..]),
+        1 | 4 => (Some(arguments[0].to_string()), &arguments[1..]),
+        _ => return None,
+    };
+
+    let position = if coordinates.is_empty() {
+        None
+    } else {
+        let coordinates = coordinates
+            .iter()
+            .map(|coordinate| coordinate.parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Vec3::new(coordinates[0], coordinates[1], coordinates[2]))
+    };
+
+    Some(SetSpawnPoint { player, position })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spawnpoint_commands() {
+        assert_eq!(
+            parse_spawnpoint_command("/spawnpoint"),
+            Some(SetSpawnPoint {
+                player: None,
+                position: None
+            })
+        );
+        assert_eq!(
+            parse_spawnpoint_command("/spawnpoint alice"),
+            Some(SetSpawnPoint {
+                player: Some("alice".into()),
+                position: None
+            })
+        );
+        assert_eq!(
+            parse_spawnpoint_command("/spawnpoint 10 64.5 -3"),
+            Some(SetSpawnPoint {
+                player: None,
+                position: Some(Vec3::new(10.0, 64.5, -3.0))
+            })
+        );
+        assert_eq!(
+            parse_spawnpoint_command("/spawnpoint bob 1 2 3"),
+            Some(SetSpawnPoint {
+                player: Some("bob".into()),
+                position: Some(Vec3::new(1.0, 2.0, 3.0))
+            })
+        );
+        assert_eq!(parse_spawnpoint_command("/spawnpoint 1 2"), None);
+        assert_eq!(parse_spawnpoint_command("/spawnpoint bob 1 two 3"), None);
+        assert_eq!(parse_spawnpoint_command("spawnpoint"), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! System sets ordering the systems of the server and of the client.
+//!
+//! Every system of the main schedules goes in one of these sets, so that the
+//! order between plugins is explicit instead of depending on registration order.
+//! Systems of the same set are ordered between themselves with `before`/`after`
+//! where they need it.
+//!
+//! Server, in every schedule: `Input` → `Simulation` → `Water` → `Broadcast`
+//! - `Input`: network messages, console commands, idle detection
+//! - `Simulation`: players, mobs, blocks, weather, chunk generation
+//! - `Water`: water currents, fluid particles and the water audit
+//! - `Broadcast`: messages to the clients, and the spatial hash and entity history
+//!   built from the state sent to them
+//!
+//! Client, in every schedule: `NetPoll` → `Predict` → `Mesh` → `Render`
+//! - `NetPoll`: network messages and the state they carry, such as other players
+//! - `Predict`: inputs and the local simulation of the player, sent to the server
+//! - `Mesh`: chunk, water and far terrain meshes
+//! - `Render`: HUD, menus, effects, sounds and animations
+//!
+//! A test of the server dispatcher checks that every server system is in a set
+//! and that no two systems are ambiguous. The client has no such test, as its
+//! plugins need a window and a renderer: client systems left outside of a set
+//! or ambiguous are only caught in review.
+//!
+//! The transport systems stay in the `RenetReceive`/`RenetSend` sets of renet.
+
+use bevy::app::{
+    App, FixedPostUpdate, FixedPreUpdate, FixedUpdate, Last, PostUpdate, PreUpdate, Update,
+};
+use bevy::ecs::intern::Interned;
+use bevy::ecs::schedule::{NodeId, Schedule, ScheduleLabel, SystemSet};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::IntoScheduleConfigs;
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServerSet {
+    Input,
+    Simulation,
+    Water,
+    Broadcast,
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientSet {
+    NetPoll,
+    Predict,
+    Mesh,
+    Render,
+}
+
+/// Schedules in which the sets are ordered
+pub fn ordered_schedules() -> [Interned<dyn ScheduleLabel>; 7] {
+    [
+        PreUpdate.intern(),
+        Update.intern(),
+        PostUpdate.intern(),
+        Last.intern(),
+        FixedPreUpdate.intern(),
+        FixedUpdate.intern(),
+        FixedPostUpdate.intern(),
+    ]
+}
+
+pub fn configure_server_sets(app: &mut App) {
+    for schedule in ordered_schedules() {
+        app.configure_sets(
+            schedule,
+            (
+                ServerSet::Input,
+                ServerSet::Simulation,
+                ServerSet::Water,
+                ServerSet::Broadcast,
+            )
+                .chain(),
+        );
+    }
+}
+
+pub fn configure_client_sets(app: &mut App) {
+    for schedule in ordered_schedules() {
+        app.configure_sets(
+            schedule,
+            (
+                ClientSet::NetPoll,
+                ClientSet::Predict,
+                ClientSet::Mesh,
+                ClientSet::Render,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Names of the systems of an initialized schedule that are in none of `sets`,
+/// directly or through a nested set
+pub fn systems_outside_sets(schedule: &Schedule, sets: &[&dyn SystemSet]) -> Vec<String> {
+    let graph = schedule.graph();
+    let hierarchy = graph.hierarchy().graph();
+
+    // The hierarchy goes from the sets to what they contain
+    let mut inside = HashSet::new();
+    let mut stack: Vec<NodeId> = graph
+        .system_sets()
+        .filter(|(_, set, _)| sets.contains(set))
+        .map(|(node, _, _)| node)
+        .collect();
+    while let Some(node) = stack.pop() {
+        for child in hierarchy.neighbors(node) {
+            if inside.insert(child) {
+                stack.push(child);
+            }
+        }
+    }
+
+    // Once initialized, the systems are moved from the graph to the executor
+    schedule
+        .systems()
+        .expect("schedule should be initialized")
+        .filter(|(node, _)| !inside.contains(node))
+        .map(|(_, system)| system.name().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::{ResMut, Resource};
+
+    #[derive(Resource, Default)]
+    struct Counter(Vec<ServerSet>);
+
+    fn push(set: ServerSet) -> impl FnMut(ResMut<Counter>) {
+        move |mut counter: ResMut<Counter>| counter.0.push(set)
+    }
+
+    #[test]
+    fn server_sets_run_in_order_and_unsorted_systems_are_found() {
+        let mut app = App::new();
+        configure_server_sets(&mut app);
+        app.init_resource::<Counter>();
+        // registered backwards, the sets put them back in order
+        app.add_systems(
+            Update,
+            (
+                push(ServerSet::Broadcast).in_set(ServerSet::Broadcast),
+                push(ServerSet::Water).in_set(ServerSet::Water),
+                push(ServerSet::Simulation).in_set(ServerSet::Simulation),
+                push(ServerSet::Input).in_set(ServerSet::Input),
+            ),
+        );
+        app.world_mut().run_schedule(Update);
+        assert_eq!(
+            app.world().resource::<Counter>().0,
+            vec![
+                ServerSet::Input,
+                ServerSet::Simulation,
+                ServerSet::Water,
+                ServerSet::Broadcast
+            ]
+        );
+
+        fn stray(mut counter: ResMut<Counter>) {
+            counter.0.clear();
+        }
+        app.add_systems(Update, stray);
+        app.world_mut().schedule_scope(Update, |world, schedule| {
+            schedule.initialize(world).unwrap();
+            let outside = systems_outside_sets(schedule, &[&ServerSet::Input, &ServerSet::Water]);
+            assert_eq!(outside.len(), 3);
+            assert!(outside.iter().any(|name| name.ends_with("stray")));
+        });
+    }
+}
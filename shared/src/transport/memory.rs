@@ -0,0 +1,189 @@
+//! In-process transport between the game and its integrated server.
+//!
+//! Solo games run the server on a thread of the game. Instead of going through a
+//! UDP socket on the loopback interface, the renet packets of both sides are
+//! handed over through a pair of channels: no socket is bound, no firewall asks
+//! about it, and packets arrive on the next frame.
+
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::renet::{ClientId, ConnectionConfig, RenetClient, RenetServer};
+
+use super::{ClientTransport, ServerTransport, TransportError};
+
+/// One side of the pair of channels
+struct MemoryEnd {
+    sender: Sender<Vec<u8>>,
+    receiver: Mutex<Receiver<Vec<u8>>>,
+}
+
+impl MemoryEnd {
+    fn pair() -> (Self, Self) {
+        let (to_client, from_server) = mpsc::channel();
+        let (to_server, from_client) = mpsc::channel();
+        (
+            Self {
+                sender: to_client,
+                receiver: Mutex::new(from_client),
+            },
+            Self {
+                sender: to_server,
+                receiver: Mutex::new(from_server),
+            },
+        )
+    }
+
+    fn send(&self, packets: Vec<Vec<u8>>) {
+        for packet in packets {
+            // The other side is gone, it will notice by itself
+            if self.sender.send(packet).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Packets received since the last call, and whether the other side is gone
+    fn receive(&self) -> (Vec<Vec<u8>>, bool) {
+        let Ok(receiver) = self.receiver.lock() else {
+            return (Vec::new(), true);
+        };
+        let mut packets = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(packet) => packets.push(packet),
+                Err(TryRecvError::Empty) => return (packets, false),
+                Err(TryRecvError::Disconnected) => return (packets, true),
+            }
+        }
+    }
+}
+
+/// Server side of an in-process connection, with a single client
+#[derive(Resource)]
+pub struct MemoryServerTransport {
+    client_id: ClientId,
+    end: MemoryEnd,
+    /// Whether the client was added to the server yet
+    connected: bool,
+}
+
+/// Client side of an in-process connection
+#[derive(Resource)]
+pub struct MemoryClientTransport {
+    end: MemoryEnd,
+}
+
+/// Both sides of the connection of the client `client_id` to an integrated server
+pub fn memory_transport(client_id: ClientId) -> (MemoryServerTransport, MemoryClientTransport) {
+    let (server_end, client_end) = MemoryEnd::pair();
+    (
+        MemoryServerTransport {
+            client_id,
+            end: server_end,
+            connected: false,
+        },
+        MemoryClientTransport { end: client_end },
+    )
+}
+
+impl MemoryClientTransport {
+    /// Client already connected, as there is no handshake to wait for
+    pub fn new_client(config: ConnectionConfig) -> RenetClient {
+        let mut client = RenetClient::new(config);
+        client.set_connected();
+        client
+    }
+}
+
+impl ServerTransport for MemoryServerTransport {
+    fn receive(&mut self, _: Duration, server: &mut RenetServer) -> Result<(), TransportError> {
+        let client_id = self.client_id;
+        if !self.connected {
+            info!("Client {} connected in process", client_id);
+            server.add_connection(client_id);
+            self.connected = true;
+        }
+
+        let (packets, disconnected) = self.end.receive();
+        for packet in packets {
+            if let Err(e) = server.process_packet_from(&packet, client_id) {
+                warn!("Dropped a packet of client {}: {}", client_id, e);
+            }
+        }
+        if disconnected || server.disconnections_id().contains(&client_id) {
+            server.remove_connection(client_id);
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        if let Ok(packets) = server.get_packets_to_send(self.client_id) {
+            self.end.send(packets);
+        }
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        server.remove_connection(self.client_id);
+    }
+}
+
+impl ClientTransport for MemoryClientTransport {
+    fn receive(&mut self, _: Duration, client: &mut RenetClient) -> Result<(), TransportError> {
+        let (packets, disconnected) = self.end.receive();
+        for packet in packets {
+            client.process_packet(&packet);
+        }
+        if disconnected && !client.is_disconnected() {
+            client.disconnect_due_to_transport();
+            return Err(TransportError("The integrated server stopped".to_string()));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), TransportError> {
+        if !client.is_disconnected() {
+            self.end.send(client.get_packets_to_send());
+        }
+        Ok(())
+    }
+
+    /// The server notices when the transport is dropped
+    fn disconnect(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_shared_renet_config;
+    use bevy_renet::renet::DefaultChannel;
+
+    #[test]
+    fn messages_cross_the_memory_transport() {
+        let (server_transport, client_transport) = memory_transport(7);
+        let mut server = RenetServer::new(get_shared_renet_config());
+        let mut client = MemoryClientTransport::new_client(get_shared_renet_config());
+
+        server.add_connection(7);
+        client.send_message(DefaultChannel::ReliableOrdered, "hello");
+        client_transport.end.send(client.get_packets_to_send());
+
+        let (packets, disconnected) = server_transport.end.receive();
+        assert!(!disconnected);
+        for packet in packets {
+            server.process_packet_from(&packet, 7).unwrap();
+        }
+        server.update(Duration::ZERO);
+        assert_eq!(
+            server.receive_message(7, DefaultChannel::ReliableOrdered),
+            Some("hello".into())
+        );
+
+        drop(client_transport);
+        assert!(server_transport.end.receive().1);
+    }
+}
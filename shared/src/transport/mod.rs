@@ -0,0 +1,245 @@
+//! Transports carrying the packets of renet between the server and its clients.
+//!
+//! Renet takes care of the channels (see [`get_shared_renet_config`]): it splits
+//! messages into packets, resends the lost ones of the reliable channels and
+//! orders them. A transport only moves these packets, and tells the server which
+//! clients connect and disconnect:
+//!
+//! - [`netcode`], over UDP, for the game and dedicated servers, which first
+//!   hand out connect tokens over the [`secure`] handshake
+//! - [`websocket`], over TCP, for clients that can't send UDP, like browsers
+//! - [`memory`], over channels, between a solo game and its integrated server
+//!
+//! Over a reliable transport like a WebSocket, nothing is ever lost, so the
+//! unreliable channels (voice) simply always arrive, and renet never has to
+//! resend on the reliable ones.
+//!
+//! [`get_shared_renet_config`]: crate::get_shared_renet_config
+
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bevy::app::{App, AppExit, Last, Plugin, PostUpdate, PreUpdate};
+use bevy::time::Time;
+use bevy_ecs::prelude::*;
+use bevy_renet::renet::{RenetClient, RenetServer};
+use bevy_renet::{RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin};
+
+pub mod memory;
+pub mod netcode;
+pub mod secure;
+pub mod websocket;
+
+/// Prefix of the addresses of servers to reach over a WebSocket, e.g. `ws://127.0.0.1:8001`
+pub const WEBSOCKET_SCHEME: &str = "ws://";
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct TransportError(pub String);
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Server side of a transport
+pub trait ServerTransport: Resource {
+    /// Hands the packets received since the last frame to the server, adding the
+    /// clients that connected and removing the ones that are gone
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), TransportError>;
+
+    fn send(&mut self, server: &mut RenetServer);
+
+    /// Disconnects every client right away, when the server stops
+    fn disconnect_all(&mut self, server: &mut RenetServer);
+}
+
+/// Client side of a transport
+pub trait ClientTransport: Resource {
+    /// Hands the packets received since the last frame to the client
+    fn receive(&mut self, delta: Duration, client: &mut RenetClient) -> Result<(), TransportError>;
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), TransportError>;
+
+    /// Tells the server right away that the client leaves, when the game stops
+    fn disconnect(&mut self);
+}
+
+/// Runs the transport `T` of the server when it is inserted as a resource
+pub struct ServerTransportPlugin<T>(PhantomData<T>);
+
+impl<T> Default for ServerTransportPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: ServerTransport> Plugin for ServerTransportPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransportError>();
+
+        app.add_systems(
+            PreUpdate,
+            receive_server_packets::<T>
+                .in_set(RenetReceive)
+                .run_if(resource_exists::<T>)
+                .run_if(resource_exists::<RenetServer>)
+                .after(RenetServerPlugin::update_system)
+                .before(RenetServerPlugin::emit_server_events_system),
+        );
+        app.add_systems(
+            PostUpdate,
+            send_server_packets::<T>
+                .in_set(RenetSend)
+                .run_if(resource_exists::<T>)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        app.add_systems(
+            Last,
+            disconnect_clients_on_exit::<T>
+                .run_if(resource_exists::<T>)
+                .run_if(resource_exists::<RenetServer>),
+        );
+    }
+}
+
+fn receive_server_packets<T: ServerTransport>(
+    mut transport: ResMut<T>,
+    mut server: ResMut<RenetServer>,
+    time: Res<Time>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.receive(time.delta(), &mut server) {
+        errors.write(e);
+    }
+}
+
+fn send_server_packets<T: ServerTransport>(
+    mut transport: ResMut<T>,
+    mut server: ResMut<RenetServer>,
+) {
+    transport.send(&mut server);
+}
+
+fn disconnect_clients_on_exit<T: ServerTransport>(
+    exit: EventReader<AppExit>,
+    mut transport: ResMut<T>,
+    mut server: ResMut<RenetServer>,
+) {
+    if !exit.is_empty() {
+        transport.disconnect_all(&mut server);
+    }
+}
+
+/// Runs the transport `T` of the client when it is inserted as a resource
+pub struct ClientTransportPlugin<T>(PhantomData<T>);
+
+impl<T> Default for ClientTransportPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: ClientTransport> Plugin for ClientTransportPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransportError>();
+
+        app.add_systems(
+            PreUpdate,
+            receive_client_packets::<T>
+                .in_set(RenetReceive)
+                .run_if(resource_exists::<T>)
+                .run_if(resource_exists::<RenetClient>)
+                .after(RenetClientPlugin::update_system),
+        );
+        app.add_systems(
+            PostUpdate,
+            send_client_packets::<T>
+                .in_set(RenetSend)
+                .run_if(resource_exists::<T>)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(Last, disconnect_on_exit::<T>.run_if(resource_exists::<T>));
+    }
+}
+
+fn receive_client_packets<T: ClientTransport>(
+    mut transport: ResMut<T>,
+    mut client: ResMut<RenetClient>,
+    time: Res<Time>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.receive(time.delta(), &mut client) {
+        errors.write(e);
+    }
+}
+
+fn send_client_packets<T: ClientTransport>(
+    mut transport: ResMut<T>,
+    mut client: ResMut<RenetClient>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.send(&mut client) {
+        errors.write(e);
+    }
+}
+
+fn disconnect_on_exit<T: ClientTransport>(exit: EventReader<AppExit>, mut transport: ResMut<T>) {
+    if !exit.is_empty() {
+        transport.disconnect();
+    }
+}
+
+/// How a client reaches a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportProtocol {
+    Udp,
+    WebSocket,
+}
+
+/// Parses the address of a server, `ws://` ones being reached over a WebSocket
+pub fn parse_server_address(address: &str) -> Option<(SocketAddr, TransportProtocol)> {
+    let address = address.trim();
+    match address.strip_prefix(WEBSOCKET_SCHEME) {
+        Some(address) => address
+            .trim_end_matches('/')
+            .parse()
+            .ok()
+            .map(|address| (address, TransportProtocol::WebSocket)),
+        None => address
+            .parse()
+            .ok()
+            .map(|address| (address, TransportProtocol::Udp)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_addresses_name_their_protocol() {
+        let address: SocketAddr = "127.0.0.1:8000".parse().unwrap();
+        assert_eq!(
+            parse_server_address("127.0.0.1:8000"),
+            Some((address, TransportProtocol::Udp))
+        );
+        assert_eq!(
+            parse_server_address("ws://127.0.0.1:8000/"),
+            Some((address, TransportProtocol::WebSocket))
+        );
+        let address: SocketAddr = "[::1]:8000".parse().unwrap();
+        assert_eq!(
+            parse_server_address("[::1]:8000"),
+            Some((address, TransportProtocol::Udp))
+        );
+        assert_eq!(
+            parse_server_address("ws://[::1]:8000"),
+            Some((address, TransportProtocol::WebSocket))
+        );
+        assert_eq!(parse_server_address("ws://localhost"), None);
+        assert_eq!(parse_server_address("nowhere"), None);
+    }
+}
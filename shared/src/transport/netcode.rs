@@ -0,0 +1,40 @@
+//! Netcode over UDP, the transport of renet. Packets are encrypted once the
+//! connection is established.
+
+use std::time::Duration;
+
+use bevy_renet::netcode::{NetcodeClientTransport, NetcodeServerTransport};
+use bevy_renet::renet::{RenetClient, RenetServer};
+
+use super::{ClientTransport, ServerTransport, TransportError};
+
+impl ServerTransport for NetcodeServerTransport {
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), TransportError> {
+        self.update(delta, server)
+            .map_err(|e| TransportError(e.to_string()))
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        self.send_packets(server);
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        NetcodeServerTransport::disconnect_all(self, server);
+    }
+}
+
+impl ClientTransport for NetcodeClientTransport {
+    fn receive(&mut self, delta: Duration, client: &mut RenetClient) -> Result<(), TransportError> {
+        self.update(delta, client)
+            .map_err(|e| TransportError(e.to_string()))
+    }
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), TransportError> {
+        self.send_packets(client)
+            .map_err(|e| TransportError(e.to_string()))
+    }
+
+    fn disconnect(&mut self) {
+        NetcodeClientTransport::disconnect(self);
+    }
+}
@@ -0,0 +1,425 @@
+//! Handshake handing out the netcode connect tokens of dedicated servers, which
+//! then run netcode in secure mode.
+//!
+//! Before its first UDP packet, the client opens a TCP connection to the same
+//! port of the server:
+//!
+//! 1. the client sends its id and an ephemeral X25519 key
+//! 2. the server answers with its identity key, an ephemeral key of its own and
+//!    a connect token, sealed with ChaCha20-Poly1305 under a key derived from
+//!    both the ephemeral keys and the identity one
+//!
+//! Only the holder of the identity secret can seal a token the client opens, so
+//! a client that pinned the [`fingerprint`] of the identity key knows who it
+//! talks to. The token carries the keys netcode encrypts every packet with.
+//!
+//! Clients reaching the server over a WebSocket run the same handshake on the
+//! WebSocket port, then open the WebSocket with the key of their token, see
+//! [`HandshakeServer::redeem`].
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use bevy_log::{info, warn};
+use bevy_renet::netcode::{ConnectToken, NETCODE_KEY_BYTES};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::PROTOCOL_ID;
+
+/// File of the game folder holding the secret identity key of a dedicated server
+pub const IDENTITY_KEY_FILE: &str = "server_identity.key";
+
+/// Starts both messages, so that anything else connecting is turned away early
+pub(super) const MAGIC: [u8; 4] = *b"RCHS";
+const CONTEXT: &str = "rustcraft handshake session key v1";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Handshakes not done after this long are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Long enough to open the UDP connection right after the handshake
+const TOKEN_EXPIRY_SECS: u64 = 30;
+/// Seconds without packets before netcode drops the connection
+const TOKEN_TIMEOUT_SECS: i32 = 15;
+/// A written connect token, and the tag of its seal
+const MAX_SEALED_TOKEN_SIZE: usize = 4096;
+/// Bytes of the identity key hash shown to players
+const FINGERPRINT_BYTES: usize = 16;
+/// Threads answering handshakes, each busy for at most [`HANDSHAKE_TIMEOUT`]
+const HANDSHAKE_WORKERS: usize = 4;
+/// Connections waiting for a free worker, further ones are closed right away
+const HANDSHAKE_BACKLOG: usize = 64;
+
+#[derive(Debug)]
+pub enum HandshakeError {
+    Io(io::Error),
+    /// Not a server of this game, or of another version
+    Protocol,
+    /// The token could not be opened, the server does not hold its identity key
+    Seal,
+    Token(String),
+}
+
+impl Display for HandshakeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandshakeError::Io(err) => write!(f, "handshake failed: {err}"),
+            HandshakeError::Protocol => write!(f, "the server answered an unknown handshake"),
+            HandshakeError::Seal => write!(f, "the server could not prove its identity"),
+            HandshakeError::Token(err) => write!(f, "invalid connect token: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for HandshakeError {
+    fn from(err: io::Error) -> Self {
+        HandshakeError::Io(err)
+    }
+}
+
+/// Short hash of an identity key, for players to compare, e.g. `3f:a2:...`
+pub fn fingerprint(public_key: &[u8; 32]) -> String {
+    blake3::hash(public_key).as_bytes()[..FINGERPRINT_BYTES]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// The key pair dedicated servers are recognized by, kept across restarts
+pub struct ServerIdentity {
+    secret: StaticSecret,
+}
+
+impl ServerIdentity {
+    pub fn generate() -> Self {
+        Self {
+            secret: StaticSecret::random_from_rng(OsRng),
+        }
+    }
+
+    /// Reads the identity saved at `path`, or saves a new one there
+    pub fn load_or_create(path: &Path) -> io::Result<Self> {
+        if path.exists() {
+            let bytes: [u8; 32] = fs::read(path)?.try_into().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "the identity key is corrupt")
+            })?;
+            return Ok(Self {
+                secret: StaticSecret::from(bytes),
+            });
+        }
+
+        let identity = Self::generate();
+        let mut file = fs::File::create(path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(identity.secret.as_bytes())?;
+        Ok(identity)
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&self.secret).to_bytes()
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public_key())
+    }
+}
+
+fn session_cipher(
+    shared_secrets: [&[u8; 32]; 2],
+    client_key: &PublicKey,
+    server_key: &PublicKey,
+    identity: &[u8; 32],
+) -> ChaCha20Poly1305 {
+    let mut material = Vec::with_capacity(5 * 32);
+    for bytes in shared_secrets {
+        material.extend_from_slice(bytes);
+    }
+    material.extend_from_slice(client_key.as_bytes());
+    material.extend_from_slice(server_key.as_bytes());
+    material.extend_from_slice(identity);
+
+    let key = blake3::derive_key(CONTEXT, &material);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+// Every handshake derives a key of its own, so the nonce never repeats for a key
+fn nonce() -> Nonce {
+    Nonce::default()
+}
+
+/// Keys of the tokens handed out, with the client each one is for and when it
+/// expires
+type IssuedKeys = Arc<Mutex<HashMap<[u8; NETCODE_KEY_BYTES], (u64, Instant)>>>;
+
+/// Answers the handshakes of the clients on a few threads of its own, see
+/// [`spawn_handshake_server`]
+#[derive(Clone)]
+pub struct HandshakeServer {
+    sender: SyncSender<TcpStream>,
+    issued: IssuedKeys,
+}
+
+impl HandshakeServer {
+    /// Queues the handshake of `stream`, which is closed if too many are waiting
+    pub fn answer(&self, stream: TcpStream) {
+        match self.sender.try_send(stream) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(stream)) => {
+                warn!(
+                    "Too many handshakes at once, dropped the one of {:?}",
+                    stream.peer_addr().ok()
+                );
+            }
+        }
+    }
+
+    /// Client the token holding `key` was handed to, unless it expired. Every key
+    /// is only redeemed once.
+    pub fn redeem(&self, key: &[u8; NETCODE_KEY_BYTES]) -> Option<u64> {
+        let (client_id, expiry) = self.issued.lock().unwrap().remove(key)?;
+        (Instant::now() < expiry).then_some(client_id)
+    }
+}
+
+/// Answers the handshakes of the clients on a few threads of their own, with
+/// tokens for the netcode server of `private_key` listening on `public_addresses`
+pub fn spawn_handshake_server(
+    listener: TcpListener,
+    identity: ServerIdentity,
+    private_key: [u8; NETCODE_KEY_BYTES],
+    public_addresses: Vec<SocketAddr>,
+) -> HandshakeServer {
+    if let Ok(addr) = listener.local_addr() {
+        info!("Accepting handshakes on {}", addr);
+    }
+    let identity = Arc::new(identity);
+    let issued = IssuedKeys::default();
+
+    // a slow client only holds one worker back, and only until its timeout
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(HANDSHAKE_BACKLOG);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..HANDSHAKE_WORKERS {
+        let receiver = receiver.clone();
+        let identity = identity.clone();
+        let public_addresses = public_addresses.clone();
+        let issued = issued.clone();
+        thread::spawn(move || loop {
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                return;
+            };
+            let peer = stream.peer_addr().ok();
+            if let Err(err) = answer_handshake(
+                stream,
+                &identity,
+                &private_key,
+                public_addresses.clone(),
+                &issued,
+            ) {
+                warn!("Handshake with {:?} failed: {}", peer, err);
+            }
+        });
+    }
+
+    let server = HandshakeServer { sender, issued };
+    let acceptor = server.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            acceptor.answer(stream);
+        }
+    });
+    server
+}
+
+fn answer_handshake(
+    mut stream: TcpStream,
+    identity: &ServerIdentity,
+    private_key: &[u8; NETCODE_KEY_BYTES],
+    public_addresses: Vec<SocketAddr>,
+    issued: &IssuedKeys,
+) -> Result<(), HandshakeError> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    let mut hello = [0u8; 4 + 8 + 8 + 32];
+    stream.read_exact(&mut hello)?;
+    let protocol_id = u64::from_le_bytes(hello[4..12].try_into().unwrap());
+    if hello[..4] != MAGIC || protocol_id != PROTOCOL_ID {
+        return Err(HandshakeError::Protocol);
+    }
+    let client_id = u64::from_le_bytes(hello[12..20].try_into().unwrap());
+    let client_key = PublicKey::from(<[u8; 32]>::try_from(&hello[20..]).unwrap());
+
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let token = ConnectToken::generate(
+        current_time,
+        PROTOCOL_ID,
+        TOKEN_EXPIRY_SECS,
+        client_id,
+        TOKEN_TIMEOUT_SECS,
+        public_addresses,
+        None,
+        private_key,
+    )
+    .map_err(|err| HandshakeError::Token(err.to_string()))?;
+    let mut token_bytes = Vec::new();
+    token.write(&mut token_bytes)?;
+
+    let secret = EphemeralSecret::random_from_rng(OsRng);
+    let server_key = PublicKey::from(&secret);
+    let ephemeral = secret.diffie_hellman(&client_key);
+    let static_shared = identity.secret.diffie_hellman(&client_key);
+    let cipher = session_cipher(
+        [ephemeral.as_bytes(), static_shared.as_bytes()],
+        &client_key,
+        &server_key,
+        &identity.public_key(),
+    );
+    let sealed = cipher
+        .encrypt(&nonce(), token_bytes.as_slice())
+        .map_err(|_| HandshakeError::Seal)?;
+
+    let mut answer = Vec::with_capacity(4 + 32 + 32 + 2 + sealed.len());
+    answer.extend_from_slice(&MAGIC);
+    answer.extend_from_slice(&identity.public_key());
+    answer.extend_from_slice(server_key.as_bytes());
+    answer.extend_from_slice(&(sealed.len() as u16).to_le_bytes());
+    answer.extend_from_slice(&sealed);
+
+    // known before the client can use it
+    {
+        let mut issued = issued.lock().unwrap();
+        let now = Instant::now();
+        issued.retain(|_, (_, expiry)| *expiry > now);
+        issued.insert(
+            token.client_to_server_key,
+            (client_id, now + Duration::from_secs(TOKEN_EXPIRY_SECS)),
+        );
+    }
+    stream.write_all(&answer)?;
+    Ok(())
+}
+
+/// What the client learns from a handshake
+pub struct ServerHello {
+    /// Of the identity key of the server, to compare with the pinned one
+    pub fingerprint: String,
+    /// To connect to the server in secure mode
+    pub connect_token: ConnectToken,
+}
+
+/// Runs the handshake with the server at `server_addr`, for the client `client_id`
+pub fn request_connect_token(
+    server_addr: SocketAddr,
+    client_id: u64,
+) -> Result<ServerHello, HandshakeError> {
+    let mut stream = TcpStream::connect_timeout(&server_addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    // fresh for every handshake, but used for two exchanges
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let client_key = PublicKey::from(&secret);
+
+    let mut hello = Vec::with_capacity(4 + 8 + 8 + 32);
+    hello.extend_from_slice(&MAGIC);
+    hello.extend_from_slice(&PROTOCOL_ID.to_le_bytes());
+    hello.extend_from_slice(&client_id.to_le_bytes());
+    hello.extend_from_slice(client_key.as_bytes());
+    stream.write_all(&hello)?;
+
+    let mut header = [0u8; 4 + 32 + 32 + 2];
+    stream.read_exact(&mut header)?;
+    if header[..4] != MAGIC {
+        return Err(HandshakeError::Protocol);
+    }
+    let identity: [u8; 32] = header[4..36].try_into().unwrap();
+    let server_key = PublicKey::from(<[u8; 32]>::try_from(&header[36..68]).unwrap());
+    let sealed_len = u16::from_le_bytes([header[68], header[69]]) as usize;
+    if sealed_len > MAX_SEALED_TOKEN_SIZE {
+        return Err(HandshakeError::Protocol);
+    }
+    let mut sealed = vec![0u8; sealed_len];
+    stream.read_exact(&mut sealed)?;
+
+    let ephemeral = secret.diffie_hellman(&server_key);
+    let static_shared = secret.diffie_hellman(&PublicKey::from(identity));
+    let cipher = session_cipher(
+        [ephemeral.as_bytes(), static_shared.as_bytes()],
+        &client_key,
+        &server_key,
+        &identity,
+    );
+    let token_bytes = cipher
+        .decrypt(&nonce(), sealed.as_slice())
+        .map_err(|_| HandshakeError::Seal)?;
+    let mut connect_token = ConnectToken::read(&mut token_bytes.as_slice())
+        .map_err(|err| HandshakeError::Token(err.to_string()))?;
+
+    // The server checks the addresses it listens on, from the private part of the
+    // token, but those can't be reached from behind a router: send to the one the
+    // handshake went through
+    connect_token.server_addresses = [None; 32];
+    connect_token.server_addresses[0] = Some(server_addr);
+
+    Ok(ServerHello {
+        fingerprint: fingerprint(&identity),
+        connect_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clients_get_a_token_from_the_server_they_pinned() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let identity = ServerIdentity::generate();
+        let expected = identity.fingerprint();
+        let private_key = [7; NETCODE_KEY_BYTES];
+        let server = spawn_handshake_server(listener, identity, private_key, vec![addr]);
+
+        let hello = request_connect_token(addr, 42).unwrap();
+        assert_eq!(hello.fingerprint, expected);
+        assert_eq!(hello.connect_token.client_id, 42);
+        assert_eq!(hello.connect_token.server_addresses[0], Some(addr));
+
+        let key = hello.connect_token.client_to_server_key;
+        assert_eq!(server.redeem(&key), Some(42));
+        assert_eq!(server.redeem(&key), None);
+
+        // another server, or someone in between, shows another identity
+        assert_ne!(ServerIdentity::generate().fingerprint(), expected);
+    }
+
+    #[test]
+    fn identities_survive_restarts() {
+        let path = std::env::temp_dir().join(format!("rustcraft-identity-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let first = ServerIdentity::load_or_create(&path).unwrap();
+        let second = ServerIdentity::load_or_create(&path).unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+
+        fs::write(&path, b"short").unwrap();
+        assert!(ServerIdentity::load_or_create(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,546 @@
+//! WebSockets over TCP, for clients that can't use UDP, like a game built for the
+//! browser.
+//!
+//! Clients first run the [`secure`] handshake on the WebSocket port, then open
+//! `ws://<server>/rustcraft/<protocol id>/<key>` with the client to server key of
+//! the token they got, which tells the server who they are. Each renet packet
+//! then travels in a binary message. Unlike the netcode of dedicated servers,
+//! nothing is encrypted after the handshake.
+//!
+//! [`secure`]: super::secure
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use bevy_ecs::prelude::*;
+use bevy_log::{info, warn};
+use bevy_renet::netcode::{ConnectToken, NETCODE_KEY_BYTES};
+use bevy_renet::renet::{ClientId, RenetClient, RenetServer};
+use tungstenite::handshake::client::ClientHandshake;
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response, ServerHandshake};
+use tungstenite::handshake::{HandshakeError, MidHandshake};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+use super::secure::{HandshakeServer, MAGIC};
+use super::{ClientTransport, ServerTransport, TransportError};
+use crate::PROTOCOL_ID;
+
+/// Much larger than a renet packet, larger messages close the connection
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+/// Connections still not upgraded after this long are closed
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Clients that can't keep up with this much data waiting to be sent are disconnected
+const MAX_PENDING_BYTES: usize = 16 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn config() -> WebSocketConfig {
+    WebSocketConfig {
+        max_message_size: Some(MAX_MESSAGE_SIZE),
+        max_frame_size: Some(MAX_MESSAGE_SIZE),
+        max_write_buffer_size: MAX_PENDING_BYTES,
+        ..Default::default()
+    }
+}
+
+fn upgrade_path(key: &[u8; NETCODE_KEY_BYTES]) -> String {
+    format!("/rustcraft/{PROTOCOL_ID}/{}", BASE64.encode(key))
+}
+
+/// Key of the connect token in the path of the upgrade request of a client
+fn parse_upgrade_path(path: &str) -> Result<[u8; NETCODE_KEY_BYTES], String> {
+    let mut parts = path.trim_matches('/').split('/');
+    if parts.next() != Some("rustcraft") {
+        return Err(format!("Unknown path {}", path));
+    }
+    if parts.next().and_then(|id| id.parse::<u64>().ok()) != Some(PROTOCOL_ID) {
+        return Err("Client of another version of the game".to_string());
+    }
+    parts
+        .next()
+        .and_then(|key| BASE64.decode(key).ok())
+        .and_then(|key| key.try_into().ok())
+        .ok_or("Missing the key of a connect token".to_string())
+}
+
+/// Why a WebSocket can't be used anymore
+enum SocketError {
+    /// By the other end
+    Closed,
+    Failed(String),
+}
+
+impl Display for SocketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketError::Closed => write!(f, "the WebSocket was closed"),
+            SocketError::Failed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// Blocking is only a sign to try again later
+fn socket_result<T>(result: tungstenite::Result<T>, would_block: T) -> Result<T, SocketError> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(would_block),
+        Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Err(SocketError::Closed)
+        }
+        Err(e) => Err(SocketError::Failed(e.to_string())),
+    }
+}
+
+/// Messages received since the last call, tungstenite answering pings and closes
+fn read_messages(socket: &mut WebSocket<TcpStream>) -> Result<Vec<Vec<u8>>, SocketError> {
+    let mut messages = Vec::new();
+    while let Some(message) = socket_result(socket.read().map(Some), None)? {
+        if let Message::Binary(message) = message {
+            messages.push(message);
+        }
+    }
+    Ok(messages)
+}
+
+/// Queues `message`, the socket sending it once it takes more data
+fn write_message(socket: &mut WebSocket<TcpStream>, message: Vec<u8>) -> Result<(), SocketError> {
+    socket_result(socket.write(Message::Binary(message)), ())
+}
+
+/// Sends as much as the socket takes of what is waiting to be sent
+fn flush(socket: &mut WebSocket<TcpStream>) -> Result<(), SocketError> {
+    socket_result(socket.flush(), ())
+}
+
+fn close(socket: &mut WebSocket<TcpStream>) {
+    let _ = socket.close(None);
+    let _ = flush(socket);
+}
+
+/// Redeems the key in the upgrade request of a client, keeping who the client is
+struct UpgradeRequest {
+    handshakes: HandshakeServer,
+    client_id: Arc<OnceLock<ClientId>>,
+}
+
+impl Callback for UpgradeRequest {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let client_id = parse_upgrade_path(request.uri().path()).and_then(|key| {
+            self.handshakes
+                .redeem(&key)
+                .ok_or("Unknown or expired connect token".to_string())
+        });
+        match client_id {
+            Ok(client_id) => {
+                let _ = self.client_id.set(client_id);
+                Ok(response)
+            }
+            Err(reason) => {
+                let mut refusal = ErrorResponse::new(Some(reason));
+                *refusal.status_mut() = StatusCode::FORBIDDEN;
+                Err(refusal)
+            }
+        }
+    }
+}
+
+enum PendingState {
+    /// Too little arrived yet to tell a handshake from an upgrade request
+    New(TcpStream),
+    Upgrading(
+        Box<MidHandshake<ServerHandshake<TcpStream, UpgradeRequest>>>,
+        Arc<OnceLock<ClientId>>,
+    ),
+}
+
+/// A connection not upgraded to a WebSocket yet
+struct PendingConnection {
+    state: PendingState,
+    age: Duration,
+}
+
+#[derive(Resource)]
+pub struct WebSocketServerTransport {
+    listener: TcpListener,
+    handshakes: HandshakeServer,
+    pending: Vec<PendingConnection>,
+    clients: HashMap<ClientId, WebSocket<TcpStream>>,
+}
+
+impl WebSocketServerTransport {
+    /// Accepts WebSockets on `listener`, whose clients get their connect token from
+    /// `handshakes` over the same port
+    pub fn new(listener: TcpListener, handshakes: HandshakeServer) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            handshakes,
+            pending: Vec::new(),
+            clients: HashMap::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(true).is_ok() && stream.set_nodelay(true).is_ok() {
+                        self.pending.push(PendingConnection {
+                            state: PendingState::New(stream),
+                            age: Duration::ZERO,
+                        });
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    warn!("Failed to accept a WebSocket connection: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Hands the handshakes to the handshake server, upgrades the connections whose
+    /// request arrived, and drops the invalid ones
+    fn progress_pending(&mut self, delta: Duration, server: &mut RenetServer) {
+        for mut connection in mem::take(&mut self.pending) {
+            connection.age += delta;
+            if connection.age >= HANDSHAKE_TIMEOUT {
+                continue;
+            }
+
+            let (upgrade, client_id) = match connection.state {
+                PendingState::New(stream) => {
+                    let mut start = [0; MAGIC.len()];
+                    match stream.peek(&mut start) {
+                        Ok(length) if length == start.len() && start == MAGIC => {
+                            if stream.set_nonblocking(false).is_ok() {
+                                self.handshakes.answer(stream);
+                            }
+                            continue;
+                        }
+                        Ok(length) if length == start.len() => {
+                            let client_id = Arc::new(OnceLock::new());
+                            let request = UpgradeRequest {
+                                handshakes: self.handshakes.clone(),
+                                client_id: client_id.clone(),
+                            };
+                            let upgrade =
+                                ServerHandshake::start(stream, request, Some(config())).handshake();
+                            (upgrade, client_id)
+                        }
+                        Ok(0) => continue,
+                        Err(e) if e.kind() != ErrorKind::WouldBlock => continue,
+                        _ => {
+                            connection.state = PendingState::New(stream);
+                            self.pending.push(connection);
+                            continue;
+                        }
+                    }
+                }
+                PendingState::Upgrading(upgrade, client_id) => ((*upgrade).handshake(), client_id),
+            };
+
+            match upgrade {
+                Ok(mut socket) => {
+                    let Some(&client_id) = client_id.get() else {
+                        continue;
+                    };
+                    if self.clients.contains_key(&client_id)
+                        || server.clients_id().contains(&client_id)
+                    {
+                        warn!(
+                            "Refused a WebSocket connection: client {} is already connected",
+                            client_id
+                        );
+                        close(&mut socket);
+                        continue;
+                    }
+                    info!("Client {} connected over a WebSocket", client_id);
+                    server.add_connection(client_id);
+                    self.clients.insert(client_id, socket);
+                }
+                Err(HandshakeError::Interrupted(upgrade)) => {
+                    connection.state = PendingState::Upgrading(Box::new(upgrade), client_id);
+                    self.pending.push(connection);
+                }
+                Err(HandshakeError::Failure(e)) => {
+                    warn!("Refused a WebSocket connection: {}", e);
+                }
+            }
+        }
+    }
+}
+
+impl ServerTransport for WebSocketServerTransport {
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), TransportError> {
+        self.accept();
+        self.progress_pending(delta, server);
+
+        let disconnected = server.disconnections_id();
+        self.clients.retain(|client_id, socket| {
+            let open = !disconnected.contains(client_id)
+                && match read_messages(socket) {
+                    Ok(messages) => {
+                        for message in messages {
+                            if let Err(e) = server.process_packet_from(&message, *client_id) {
+                                warn!("Dropped a packet of client {}: {}", client_id, e);
+                            }
+                        }
+                        true
+                    }
+                    Err(SocketError::Closed) => false,
+                    Err(e) => {
+                        warn!("WebSocket of client {} failed: {}", client_id, e);
+                        false
+                    }
+                };
+            if !open {
+                close(socket);
+                server.remove_connection(*client_id);
+            }
+            open
+        });
+        Ok(())
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        self.clients.retain(|client_id, socket| {
+            let packets = server.get_packets_to_send(*client_id).unwrap_or_default();
+            let sent = packets
+                .into_iter()
+                .try_for_each(|packet| write_message(socket, packet))
+                .and_then(|_| flush(socket));
+            if let Err(e) = sent {
+                warn!("Failed to send to client {}: {}", client_id, e);
+                server.remove_connection(*client_id);
+                return false;
+            }
+            true
+        });
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        for (client_id, mut socket) in self.clients.drain() {
+            close(&mut socket);
+            server.remove_connection(client_id);
+        }
+    }
+}
+
+enum ClientSocket {
+    Upgrading(MidHandshake<ClientHandshake<TcpStream>>),
+    Open(WebSocket<TcpStream>),
+    Closed,
+}
+
+#[derive(Resource)]
+pub struct WebSocketClientTransport {
+    socket: ClientSocket,
+}
+
+impl WebSocketClientTransport {
+    /// Connects to the server with the token of a handshake run on the same port,
+    /// the upgrade going on while the game runs
+    pub fn connect(server_addr: SocketAddr, connect_token: &ConnectToken) -> io::Result<Self> {
+        let stream = TcpStream::connect_timeout(&server_addr, CONNECT_TIMEOUT)?;
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        let url = format!(
+            "ws://{server_addr}{}",
+            upgrade_path(&connect_token.client_to_server_key)
+        );
+        let socket = match tungstenite::client::client_with_config(url, stream, Some(config())) {
+            Ok((socket, _)) => ClientSocket::Open(socket),
+            Err(HandshakeError::Interrupted(upgrade)) => ClientSocket::Upgrading(upgrade),
+            Err(HandshakeError::Failure(e)) => return Err(io::Error::other(e)),
+        };
+        Ok(Self { socket })
+    }
+
+    /// Checks the answer of the server to the upgrade once it arrived
+    fn progress_upgrade(&mut self) -> Result<bool, String> {
+        match mem::replace(&mut self.socket, ClientSocket::Closed) {
+            ClientSocket::Upgrading(upgrade) => match upgrade.handshake() {
+                Ok((socket, _)) => self.socket = ClientSocket::Open(socket),
+                Err(HandshakeError::Interrupted(upgrade)) => {
+                    self.socket = ClientSocket::Upgrading(upgrade);
+                    return Ok(false);
+                }
+                Err(HandshakeError::Failure(e)) => {
+                    return Err(format!("The server refused the WebSocket: {}", e));
+                }
+            },
+            ClientSocket::Open(socket) => self.socket = ClientSocket::Open(socket),
+            ClientSocket::Closed => return Err("The WebSocket is closed".to_string()),
+        }
+        Ok(true)
+    }
+}
+
+impl ClientTransport for WebSocketClientTransport {
+    fn receive(&mut self, _: Duration, client: &mut RenetClient) -> Result<(), TransportError> {
+        if client.is_disconnected() {
+            self.disconnect();
+            return Ok(());
+        }
+
+        match self.progress_upgrade() {
+            Ok(true) => client.set_connected(),
+            Ok(false) => {
+                client.set_connecting();
+                return Ok(());
+            }
+            Err(e) => {
+                client.disconnect_due_to_transport();
+                return Err(TransportError(e));
+            }
+        }
+
+        let ClientSocket::Open(socket) = &mut self.socket else {
+            return Ok(());
+        };
+        match read_messages(socket) {
+            Ok(messages) => {
+                for message in messages {
+                    client.process_packet(&message);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.socket = ClientSocket::Closed;
+                client.disconnect_due_to_transport();
+                Err(TransportError(match e {
+                    SocketError::Closed => "The server closed the WebSocket".to_string(),
+                    e => e.to_string(),
+                }))
+            }
+        }
+    }
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), TransportError> {
+        let ClientSocket::Open(socket) = &mut self.socket else {
+            return Ok(());
+        };
+        if !client.is_disconnected() {
+            for packet in client.get_packets_to_send() {
+                write_message(socket, packet).map_err(|e| TransportError(e.to_string()))?;
+            }
+        }
+        flush(socket).map_err(|e| TransportError(e.to_string()))
+    }
+
+    fn disconnect(&mut self) {
+        if let ClientSocket::Open(socket) = &mut self.socket {
+            close(socket);
+        }
+        self.socket = ClientSocket::Closed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_shared_renet_config;
+    use crate::transport::secure::{request_connect_token, spawn_handshake_server, ServerIdentity};
+    use bevy_renet::renet::DefaultChannel;
+    use std::thread;
+
+    #[test]
+    fn upgrade_paths_carry_the_key_of_a_token() {
+        let key = [3; NETCODE_KEY_BYTES];
+        assert_eq!(parse_upgrade_path(&upgrade_path(&key)), Ok(key));
+        assert!(parse_upgrade_path(&format!("/rustcraft/{PROTOCOL_ID}/42")).is_err());
+        assert!(parse_upgrade_path("/other/0/42").is_err());
+    }
+
+    #[test]
+    fn messages_cross_a_websocket() {
+        let handshake_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let handshake_addr = handshake_listener.local_addr().unwrap();
+        let handshakes = spawn_handshake_server(
+            handshake_listener,
+            ServerIdentity::generate(),
+            [7; NETCODE_KEY_BYTES],
+            vec![handshake_addr],
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server_transport = WebSocketServerTransport::new(listener, handshakes).unwrap();
+        let mut server = RenetServer::new(get_shared_renet_config());
+        let tick = Duration::from_millis(10);
+
+        // the handshake goes through the WebSocket port, while the server runs
+        let hello = thread::spawn(move || request_connect_token(addr, 7));
+        while !hello.is_finished() {
+            server_transport.receive(tick, &mut server).unwrap();
+            thread::sleep(tick);
+        }
+        let token = hello.join().unwrap().unwrap().connect_token;
+
+        let mut client_transport = WebSocketClientTransport::connect(addr, &token).unwrap();
+        let mut client = RenetClient::new(get_shared_renet_config());
+        let mut replay = None;
+        let mut replay_refused = false;
+
+        client.send_message(DefaultChannel::ReliableOrdered, "hello");
+        let mut received = (None, None);
+        for _ in 0..200 {
+            server.update(tick);
+            server_transport.receive(tick, &mut server).unwrap();
+            if replay.is_none() && server.is_connected(7) {
+                server.send_message(7, DefaultChannel::ReliableOrdered, "welcome");
+                // keys only open a single connection
+                replay = Some((
+                    WebSocketClientTransport::connect(addr, &token).unwrap(),
+                    RenetClient::new(get_shared_renet_config()),
+                ));
+            }
+            server_transport.send(&mut server);
+            client.update(tick);
+            client_transport.receive(tick, &mut client).unwrap();
+            client_transport.send(&mut client).unwrap();
+            if let Some((transport, client)) = replay.as_mut() {
+                replay_refused |= transport.receive(tick, client).is_err();
+            }
+
+            received.0 = received
+                .0
+                .or(server.receive_message(7, DefaultChannel::ReliableOrdered));
+            received.1 = received
+                .1
+                .or(client.receive_message(DefaultChannel::ReliableOrdered));
+            if replay_refused && received.0.is_some() && received.1.is_some() {
+                break;
+            }
+            thread::sleep(tick);
+        }
+        assert_eq!(received.0, Some("hello".into()));
+        assert_eq!(received.1, Some("welcome".into()));
+        assert!(replay_refused);
+
+        client_transport.disconnect();
+        for _ in 0..200 {
+            server_transport.receive(tick, &mut server).unwrap();
+            if !server.is_connected(7) {
+                break;
+            }
+            thread::sleep(tick);
+        }
+        assert!(!server.is_connected(7));
+    }
+}
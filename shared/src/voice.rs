@@ -0,0 +1,250 @@
+//! Proximity voice chat.
+//!
+//! Clients capture their microphone while push-to-talk is held, and send it to the
+//! server in frames of [`VOICE_FRAME_SAMPLES`] mono samples at [`VOICE_SAMPLE_RATE`].
+//! The server relays each frame to the players within [`VOICE_RANGE`], along with
+//! the speaker position, and clients mix the voices they receive with an
+//! attenuation depending on the distance to the speaker.
+//!
+//! Frames go through the unreliable voice channels: a lost frame is a short gap,
+//! while a resent one would only add latency.
+//!
+//! Frames are encoded with Opus at [`VOICE_BITRATE`]. Opus decoders carry state
+//! from one frame to the next, so receivers keep a [`VoiceDecoder`] per speaker.
+
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+use unsafe_libopus::{
+    opus_decode_float, opus_decoder_create, opus_decoder_destroy, opus_encode_float,
+    opus_encoder_create, opus_encoder_ctl, opus_encoder_destroy, OpusDecoder, OpusEncoder,
+    OPUS_APPLICATION_VOIP, OPUS_OK, OPUS_SET_BITRATE_REQUEST,
+};
+
+use crate::messages::PlayerId;
+
+pub const VOICE_SAMPLE_RATE: u32 = 16_000;
+/// 20 ms of audio
+pub const VOICE_FRAME_SAMPLES: usize = 320;
+/// Bits per second, plenty for speech at 16 kHz
+pub const VOICE_BITRATE: i32 = 24_000;
+/// Encoded frames never exceed this, the encoder is given no more room
+pub const MAX_VOICE_FRAME_BYTES: usize = 256;
+
+/// Speakers further away than this can't be heard
+pub const VOICE_RANGE: f32 = 32.0;
+/// Distance under which voices are played at full volume
+const VOICE_FULL_VOLUME_DISTANCE: f32 = 4.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceFrame {
+    /// Increases by one every frame, so that receivers can detect gaps
+    pub sequence: u32,
+    pub data: Vec<u8>,
+}
+
+/// A voice frame relayed by the server
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoicePacket {
+    pub speaker: PlayerId,
+    pub position: Vec3,
+    pub frame: VoiceFrame,
+}
+
+/// Volume of a speaker at `distance`, between 0 and 1
+pub fn voice_attenuation(distance: f32) -> f32 {
+    if distance <= VOICE_FULL_VOLUME_DISTANCE {
+        return 1.0;
+    }
+    let t = (distance - VOICE_FULL_VOLUME_DISTANCE) / (VOICE_RANGE - VOICE_FULL_VOLUME_DISTANCE);
+    // Quadratic falloff sounds more natural than a linear one
+    (1.0 - t).clamp(0.0, 1.0).powi(2)
+}
+
+/// Opus encoder for the local microphone
+pub struct VoiceEncoder {
+    encoder: *mut OpusEncoder,
+}
+
+// The encoder is a plain allocation owned by this struct, only used through `&mut self`
+unsafe impl Send for VoiceEncoder {}
+unsafe impl Sync for VoiceEncoder {}
+
+impl VoiceEncoder {
+    pub fn new() -> Result<Self, String> {
+        let mut error = 0;
+        let encoder = unsafe {
+            opus_encoder_create(
+                VOICE_SAMPLE_RATE as i32,
+                1,
+                OPUS_APPLICATION_VOIP,
+                &mut error,
+            )
+        };
+        if error != OPUS_OK || encoder.is_null() {
+            return Err(format!("cannot create the Opus encoder (error {error})"));
+        }
+        let encoder = Self { encoder };
+
+        let error =
+            unsafe { opus_encoder_ctl!(encoder.encoder, OPUS_SET_BITRATE_REQUEST, VOICE_BITRATE) };
+        if error != OPUS_OK {
+            return Err(format!("cannot set the Opus bitrate (error {error})"));
+        }
+        Ok(encoder)
+    }
+
+    /// Encodes a frame of [`VOICE_FRAME_SAMPLES`] samples between -1 and 1
+    pub fn encode(&mut self, samples: &[f32]) -> Option<Vec<u8>> {
+        if samples.len() != VOICE_FRAME_SAMPLES {
+            return None;
+        }
+        let mut data = vec![0; MAX_VOICE_FRAME_BYTES];
+        let len = unsafe {
+            opus_encode_float(
+                self.encoder,
+                samples.as_ptr(),
+                VOICE_FRAME_SAMPLES as i32,
+                data.as_mut_ptr(),
+                data.len() as i32,
+            )
+        };
+        if len < 0 {
+            return None;
+        }
+        data.truncate(len as usize);
+        Some(data)
+    }
+}
+
+impl Drop for VoiceEncoder {
+    fn drop(&mut self) {
+        unsafe { opus_encoder_destroy(self.encoder) };
+    }
+}
+
+/// Opus decoder for the frames of one speaker
+pub struct VoiceDecoder {
+    decoder: *mut OpusDecoder,
+}
+
+// Same as `VoiceEncoder`
+unsafe impl Send for VoiceDecoder {}
+unsafe impl Sync for VoiceDecoder {}
+
+impl VoiceDecoder {
+    pub fn new() -> Result<Self, String> {
+        let mut error = 0;
+        let decoder = unsafe { opus_decoder_create(VOICE_SAMPLE_RATE as i32, 1, &mut error) };
+        if error != OPUS_OK || decoder.is_null() {
+            return Err(format!("cannot create the Opus decoder (error {error})"));
+        }
+        Ok(Self { decoder })
+    }
+
+    /// Decodes a frame, `None` if it isn't valid Opus. Frames come from other
+    /// players, so they can't be trusted.
+    pub fn decode(&mut self, data: &[u8]) -> Option<Vec<f32>> {
+        if data.is_empty() || data.len() > MAX_VOICE_FRAME_BYTES {
+            return None;
+        }
+        let mut samples = vec![0.0; VOICE_FRAME_SAMPLES];
+        let len = unsafe {
+            opus_decode_float(
+                self.decoder,
+                data.as_ptr(),
+                data.len() as i32,
+                samples.as_mut_ptr(),
+                samples.len() as i32,
+                0,
+            )
+        };
+        if len < 0 {
+            return None;
+        }
+        samples.truncate(len as usize);
+        Some(samples)
+    }
+}
+
+impl Drop for VoiceDecoder {
+    fn drop(&mut self) {
+        unsafe { opus_decoder_destroy(self.decoder) };
+    }
+}
+
+/// Linear resampling, good enough for voice
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let output_len = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let t = (position - index as f64) as f32;
+            let a = samples[index.min(samples.len() - 1)];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+            a + (b - a) * t
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opus_round_trip_keeps_the_signal() {
+        let mut encoder = VoiceEncoder::new().unwrap();
+        let mut decoder = VoiceDecoder::new().unwrap();
+        // 400 Hz tone
+        let tone = |i: usize| {
+            (i as f32 * 400.0 * std::f32::consts::TAU / VOICE_SAMPLE_RATE as f32).sin() * 0.5
+        };
+
+        let mut original = Vec::new();
+        let mut decoded = Vec::new();
+        for frame in 0..10 {
+            let samples: Vec<f32> = (0..VOICE_FRAME_SAMPLES)
+                .map(|i| tone(frame * VOICE_FRAME_SAMPLES + i))
+                .collect();
+            let data = encoder.encode(&samples).unwrap();
+            assert!(data.len() < VOICE_FRAME_SAMPLES / 2);
+            decoded.extend(decoder.decode(&data).unwrap());
+            original.extend(samples);
+        }
+        assert_eq!(decoded.len(), original.len());
+
+        // Opus delays the signal, compare the energy of the last frames instead
+        let energy = |samples: &[f32]| samples.iter().map(|s| s * s).sum::<f32>();
+        let tail = 5 * VOICE_FRAME_SAMPLES;
+        let ratio = energy(&decoded[tail..]) / energy(&original[tail..]);
+        assert!((0.7..1.3).contains(&ratio), "energy ratio {ratio}");
+    }
+
+    #[test]
+    fn garbage_frames_are_rejected() {
+        let mut decoder = VoiceDecoder::new().unwrap();
+        assert!(decoder.decode(&[]).is_none());
+        assert!(decoder.decode(&[0xFF; MAX_VOICE_FRAME_BYTES + 1]).is_none());
+    }
+
+    #[test]
+    fn attenuation_falls_to_zero_at_range() {
+        assert_eq!(voice_attenuation(0.0), 1.0);
+        assert_eq!(voice_attenuation(VOICE_FULL_VOLUME_DISTANCE), 1.0);
+        assert!(voice_attenuation(VOICE_RANGE / 2.0) < 1.0);
+        assert_eq!(voice_attenuation(VOICE_RANGE), 0.0);
+        assert_eq!(voice_attenuation(VOICE_RANGE * 2.0), 0.0);
+    }
+
+    #[test]
+    fn resampling_scales_the_length() {
+        let samples = vec![0.5; 480];
+        assert_eq!(resample(&samples, 48_000, VOICE_SAMPLE_RATE).len(), 160);
+        assert_eq!(resample(&samples, VOICE_SAMPLE_RATE, 48_000).len(), 1440);
+    }
+}
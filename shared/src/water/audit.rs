@@ -0,0 +1,266 @@
+//! Water volume conservation auditing.
+//!
+//! Water is sampled periodically as a set of block positions. Each sample is split
+//! into connected water bodies, and bodies are matched with the previous sample by
+//! overlap. Within a group of matched bodies (which handles bodies merging and
+//! splitting), the change in volume should be fully explained by explicit sources
+//! and sinks, such as a player removing a water block. Anything else means the
+//! simulation created or destroyed water.
+
+use bevy::math::IVec3;
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Volume changes smaller than this are ignored
+pub const WATER_AUDIT_EPSILON: f32 = 1e-3;
+
+/// Volume of a single full water block
+pub const WATER_BLOCK_VOLUME: f32 = 1.0;
+
+/// Offsets of the neighbours sharing a face with a block, which swimming mobs
+/// move between too (see `navigation`)
+pub(crate) const FACE_NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// A set of face-connected water blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterBody {
+    /// Lowest block position of the body, used to identify it in logs
+    pub anchor: IVec3,
+    pub blocks: Vec<IVec3>,
+    pub volume: f32,
+}
+
+/// Splits water blocks into face-connected bodies.
+/// Bodies are sorted by anchor so that results are deterministic.
+pub fn find_water_bodies(water_blocks: &HashSet<IVec3>) -> Vec<WaterBody> {
+    let mut visited = HashSet::with_capacity(water_blocks.len());
+    let mut bodies = Vec::new();
+
+    for start in water_blocks.iter() {
+        if !visited.insert(*start) {
+            continue;
+        }
+
+        let mut blocks = Vec::new();
+        let mut stack = vec![*start];
+        while let Some(pos) = stack.pop() {
+            blocks.push(pos);
+            for offset in FACE_NEIGHBOURS {
+                let neighbour = pos + offset;
+                if water_blocks.contains(&neighbour) && visited.insert(neighbour) {
+                    stack.push(neighbour);
+                }
+            }
+        }
+
+        let anchor = blocks
+            .iter()
+            .copied()
+            .min_by_key(|pos| (pos.y, pos.x, pos.z))
+            .unwrap_or(*start);
+        let volume = blocks.len() as f32 * WATER_BLOCK_VOLUME;
+        bodies.push(WaterBody {
+            anchor,
+            blocks,
+            volume,
+        });
+    }
+
+    bodies.sort_by_key(|body| (body.anchor.y, body.anchor.x, body.anchor.z));
+    bodies
+}
+
+/// Net creation or destruction of water that wasn't explained by a source or sink
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaterVolumeViolation {
+    /// Anchor of the largest body involved, in the current sample if it still exists
+    pub anchor: IVec3,
+    pub previous_volume: f32,
+    pub current_volume: f32,
+    /// Volume added (positive) or removed (negative) by sources and sinks
+    pub explained_change: f32,
+}
+
+impl WaterVolumeViolation {
+    pub fn unexplained_change(&self) -> f32 {
+        self.current_volume - self.previous_volume - self.explained_change
+    }
+}
+
+/// Compares two samples of the same region, `explicit_changes` being the volume
+/// added or removed at given positions between them.
+pub fn audit_water_volume(
+    previous: &[WaterBody],
+    current: &[WaterBody],
+    explicit_changes: &[(IVec3, f32)],
+) -> Vec<WaterVolumeViolation> {
+    // Bodies of both samples are nodes of a union-find, previous ones first
+    let offset = previous.len();
+    let mut parents: Vec<usize> = (0..previous.len() + current.len()).collect();
+
+    fn find(parents: &mut [usize], mut node: usize) -> usize {
+        while parents[node] != node {
+            parents[node] = parents[parents[node]];
+            node = parents[node];
+        }
+        node
+    }
+
+    let mut previous_owner = HashMap::new();
+    for (index, body) in previous.iter().enumerate() {
+        for pos in body.blocks.iter() {
+            previous_owner.insert(*pos, index);
+        }
+    }
+
+    let mut current_owner = HashMap::new();
+    for (index, body) in current.iter().enumerate() {
+        for pos in body.blocks.iter() {
+            current_owner.insert(*pos, offset + index);
+            if let Some(previous_index) = previous_owner.get(pos) {
+                let a = find(&mut parents, *previous_index);
+                let b = find(&mut parents, offset + index);
+                parents[a] = b;
+            }
+        }
+    }
+
+    // (previous volume, current volume, explained change, largest body)
+    let mut groups: HashMap<usize, (f32, f32, f32, Option<(f32, IVec3)>)> = HashMap::new();
+
+    for (index, body) in previous.iter().enumerate() {
+        let group = groups.entry(find(&mut parents, index)).or_default();
+        group.0 += body.volume;
+    }
+    for (index, body) in current.iter().enumerate() {
+        let group = groups
+            .entry(find(&mut parents, offset + index))
+            .or_default();
+        group.1 += body.volume;
+        if group.3.is_none_or(|(volume, _)| body.volume > volume) {
+            group.3 = Some((body.volume, body.anchor));
+        }
+    }
+    for (pos, change) in explicit_changes {
+        // A change inside a body belongs to it, otherwise it can only come from
+        // a body that has since disappeared
+        let Some(node) = current_owner
+            .get(pos)
+            .or_else(|| previous_owner.get(pos))
+            .copied()
+        else {
+            continue;
+        };
+        let group = groups.entry(find(&mut parents, node)).or_default();
+        group.2 += change;
+    }
+
+    let mut violations: Vec<WaterVolumeViolation> = groups
+        .into_iter()
+        .filter_map(
+            |(root, (previous_volume, current_volume, explained, largest))| {
+                let unexplained = current_volume - previous_volume - explained;
+                if unexplained.abs() <= WATER_AUDIT_EPSILON {
+                    return None;
+                }
+                // Groups without a current body only contain previous bodies
+                let anchor = largest
+                    .map(|(_, anchor)| anchor)
+                    .unwrap_or_else(|| previous[root].anchor);
+                Some(WaterVolumeViolation {
+                    anchor,
+                    previous_volume,
+                    current_volume,
+                    explained_change: explained,
+                })
+            },
+        )
+        .collect();
+
+    violations.sort_by_key(|v| (v.anchor.y, v.anchor.x, v.anchor.z));
+    violations
+}
+
+/// Audit results sent to clients, displayed in the debug HUD
+#[derive(Event, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaterAuditReport {
+    pub enabled: bool,
+    pub body_count: usize,
+    pub total_volume: f32,
+    /// Change of the total volume since the previous sample, explained or not
+    pub net_change: f32,
+    /// Violations found by the last sample
+    pub violations: Vec<WaterVolumeViolation>,
+    /// Violations found since the audit was enabled
+    pub total_violations: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(positions: &[(i32, i32, i32)]) -> HashSet<IVec3> {
+        positions
+            .iter()
+            .map(|(x, y, z)| IVec3::new(*x, *y, *z))
+            .collect()
+    }
+
+    #[test]
+    fn separates_bodies_that_only_touch_diagonally() {
+        let bodies = find_water_bodies(&blocks(&[(0, 0, 0), (1, 0, 0), (2, 1, 0), (5, 5, 5)]));
+
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[0].anchor, IVec3::new(0, 0, 0));
+        assert_eq!(bodies[0].volume, 2.0);
+    }
+
+    #[test]
+    fn flowing_water_is_conserved() {
+        let previous = find_water_bodies(&blocks(&[(0, 1, 0), (0, 0, 0)]));
+        let current = find_water_bodies(&blocks(&[(0, 0, 0), (1, 0, 0)]));
+
+        assert!(audit_water_volume(&previous, &current, &[]).is_empty());
+    }
+
+    #[test]
+    fn merging_bodies_are_audited_together() {
+        // The top of the left column flows into the gap, joining the right puddle
+        let previous = find_water_bodies(&blocks(&[(0, 0, 0), (0, 1, 0), (2, 0, 0)]));
+        let current = find_water_bodies(&blocks(&[(0, 0, 0), (1, 0, 0), (2, 0, 0)]));
+        assert_eq!(previous.len(), 2);
+        assert_eq!(current.len(), 1);
+
+        assert!(audit_water_volume(&previous, &current, &[]).is_empty());
+    }
+
+    #[test]
+    fn reports_unexplained_creation() {
+        let previous = find_water_bodies(&blocks(&[(0, 0, 0)]));
+        let current = find_water_bodies(&blocks(&[(0, 0, 0), (1, 0, 0)]));
+
+        let violations = audit_water_volume(&previous, &current, &[]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].unexplained_change(), 1.0);
+    }
+
+    #[test]
+    fn explicit_sinks_explain_removed_water() {
+        let previous = find_water_bodies(&blocks(&[(0, 0, 0), (1, 0, 0)]));
+        let current = find_water_bodies(&blocks(&[(0, 0, 0)]));
+
+        let changes = [(IVec3::new(1, 0, 0), -WATER_BLOCK_VOLUME)];
+        assert!(audit_water_volume(&previous, &current, &changes).is_empty());
+
+        let unrelated = [(IVec3::new(8, 0, 0), -WATER_BLOCK_VOLUME)];
+        assert_eq!(audit_water_volume(&previous, &current, &unrelated).len(), 1);
+    }
+}
@@ -0,0 +1,189 @@
+//! Currents of flowing water, which sweep mobs and dropped items along.
+//!
+//! Water flows sideways from the cells holding more of it to the open cells
+//! holding less, so the current at a position is the gradient of the water
+//! volume across the horizontal neighbours of its cell. Still water, where every
+//! open neighbour is as full, has no current. Classic flowing water holds less
+//! the further it is from its source, so it flows downstream.
+//!
+//! Bubble columns rise from soul sand and sink towards magma, through the water
+//! stacked right above the block.
+
+use bevy::math::{IVec3, Vec3};
+
+use crate::fluid::FluidKind;
+use crate::world::{BlockHitbox, BlockId, WorldMap};
+
+/// Speed in blocks per second of the items carried by the strongest current
+pub const WATER_CURRENT_SPEED: f32 = 1.5;
+/// Vertical acceleration of the bodies caught in a bubble column, strong enough
+/// to overcome buoyancy in the sinking ones
+pub const BUBBLE_COLUMN_FORCE: f32 = 25.0;
+/// Bubble columns reach at most this many blocks above their source block
+pub const BUBBLE_COLUMN_MAX_HEIGHT: i32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BubbleColumn {
+    /// Over soul sand
+    Rising,
+    /// Over magma
+    Sinking,
+}
+
+impl BubbleColumn {
+    /// Block at the bottom of the column making it, if any
+    pub fn from_source(block: BlockId) -> Option<Self> {
+        match block {
+            BlockId::SoulSand => Some(Self::Rising),
+            BlockId::Magma => Some(Self::Sinking),
+            _ => None,
+        }
+    }
+
+    /// 1 for the rising columns, -1 for the sinking ones
+    pub fn direction(&self) -> f32 {
+        match self {
+            Self::Rising => 1.0,
+            Self::Sinking => -1.0,
+        }
+    }
+}
+
+/// Bubble column going through the water cell at `cell`, found by following the
+/// water down to the block under it. `block` gives the block of a cell, if any.
+pub fn bubble_column(
+    cell: IVec3,
+    block: impl Fn(IVec3) -> Option<BlockId>,
+) -> Option<BubbleColumn> {
+    if block(cell) != Some(BlockId::Water) {
+        return None;
+    }
+    (1..=BUBBLE_COLUMN_MAX_HEIGHT)
+        .map(|depth| block(cell - IVec3::Y * depth))
+        .find(|below| *below != Some(BlockId::Water))
+        .flatten()
+        .and_then(BubbleColumn::from_source)
+}
+
+const HORIZONTAL_NEIGHBOURS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// Direction and strength, at most 1, of the current at `position`.
+///
+/// `volume` gives the water volume of a cell, or `None` if the cell is solid and
+/// water can't flow into it.
+pub fn water_flow(position: Vec3, volume: impl Fn(IVec3) -> Option<f32>) -> Vec3 {
+    let cell = position.floor().as_ivec3();
+    let Some(own) = volume(cell).filter(|own| *own > 0.0) else {
+        return Vec3::ZERO;
+    };
+
+    let flow = HORIZONTAL_NEIGHBOURS
+        .into_iter()
+        .filter_map(|offset| {
+            let neighbour = volume(cell + offset)?;
+            Some(offset.as_vec3() * (own - neighbour))
+        })
+        .sum::<Vec3>();
+    flow.clamp_length_max(1.0)
+}
+
+/// Water volume of the cell, `None` if water can't flow through it
+pub fn water_cell_volume(world_map: &impl WorldMap, cell: IVec3) -> Option<f32> {
+    match world_map.get_block_by_coordinates(&cell) {
+        None => Some(0.0),
+        Some(block) if matches!(block.get_collision_hitbox(), BlockHitbox::FullBlock) => None,
+        Some(block) => match block.id.stored_fluid() {
+            Some((FluidKind::Water, volume)) => {
+                let spread = FluidKind::Water.max_spread() as f32 + 1.0;
+                Some(volume * (1.0 - block.level as f32 / spread))
+            }
+            // water doesn't flow through lava
+            Some((FluidKind::Lava, _)) => None,
+            None => Some(0.0),
+        },
+    }
+}
+
+/// Current of the water at `position` in the world, see [`water_flow`]
+pub fn water_current(world_map: &impl WorldMap, position: Vec3) -> Vec3 {
+    water_flow(position, |cell| water_cell_volume(world_map, cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockData, BlockDirection, ServerChunkWorldMap};
+    use std::collections::HashMap;
+
+    #[test]
+    fn water_flows_towards_the_open_cells_holding_less() {
+        // A channel along x, open at its end, between two stone walls
+        let mut cells = HashMap::new();
+        for x in 0..4 {
+            cells.insert(IVec3::new(x, 0, 0), Some(1.0));
+            cells.insert(IVec3::new(x, 0, 1), None);
+            cells.insert(IVec3::new(x, 0, -1), None);
+        }
+        cells.insert(IVec3::new(-1, 0, 0), None);
+        let volume = |cell: IVec3| cells.get(&cell).copied().unwrap_or(Some(0.0));
+
+        let still = water_flow(Vec3::new(1.5, 0.5, 0.5), volume);
+        assert_eq!(still, Vec3::ZERO);
+
+        let edge = water_flow(Vec3::new(3.5, 0.5, 0.5), volume);
+        assert_eq!(edge, Vec3::X);
+
+        let dry = water_flow(Vec3::new(4.5, 0.5, 0.5), volume);
+        assert_eq!(dry, Vec3::ZERO);
+    }
+
+    #[test]
+    fn classic_water_flows_away_from_its_source() {
+        let mut world = ServerChunkWorldMap::default();
+        for x in 0..4 {
+            world.set_block(
+                &IVec3::new(x, 0, 0),
+                BlockData {
+                    level: x as u8,
+                    ..BlockData::new(BlockId::Water, BlockDirection::Front)
+                },
+            );
+            for z in [-1, 1] {
+                world.set_block(
+                    &IVec3::new(x, 0, z),
+                    BlockData::new(BlockId::Stone, BlockDirection::Front),
+                );
+            }
+        }
+        world.set_block(
+            &IVec3::new(-1, 0, 0),
+            BlockData::new(BlockId::Stone, BlockDirection::Front),
+        );
+
+        let current = water_current(&world, Vec3::new(1.5, 0.5, 0.5));
+        assert!(current.x > 0.0);
+        assert_eq!(current.z, 0.0);
+    }
+
+    #[test]
+    fn bubble_columns_go_through_the_water_above_their_source() {
+        let mut blocks = HashMap::from([
+            (IVec3::ZERO, BlockId::SoulSand),
+            (IVec3::new(1, 0, 0), BlockId::Magma),
+            (IVec3::new(1, 1, 0), BlockId::Stone),
+        ]);
+        for y in 1..5 {
+            blocks.insert(IVec3::new(0, y, 0), BlockId::Water);
+            blocks.insert(IVec3::new(1, y + 1, 0), BlockId::Water);
+        }
+        let block = |cell: IVec3| blocks.get(&cell).copied();
+
+        assert_eq!(
+            bubble_column(IVec3::new(0, 4, 0), block),
+            Some(BubbleColumn::Rising)
+        );
+        // The stone between the magma and the water blocks the column
+        assert_eq!(bubble_column(IVec3::new(1, 5, 0), block), None);
+        assert_eq!(bubble_column(IVec3::new(0, 5, 0), block), None);
+    }
+}
@@ -0,0 +1,20 @@
+//! Water surface shared by the client renderer and gameplay systems.
+//!
+//! The rendered water surface is displaced on the GPU by bevy_water's `water_functions.wgsl`.
+//! Gameplay code (boats, floating items, swimming eye-level detection, mob AI avoiding
+//! deep water) needs to know where that surface is, so the same wave function is
+//! reimplemented on the CPU here.
+//!
+//! The `audit` module checks that water volume is conserved by the simulation,
+//! `navigation` finds paths through water for mob AI, and `currents` gives the
+//! flow that sweeps entities along.
+
+pub mod audit;
+pub mod currents;
+pub mod navigation;
+pub mod waves;
+
+pub use audit::*;
+pub use currents::*;
+pub use navigation::*;
+pub use waves::*;
@@ -0,0 +1,141 @@
+//! Navigation through water, used by mob AI.
+//!
+//! Aquatic mobs only move between face-connected water blocks, so the water
+//! storage itself is the navigation graph. Land mobs use the depth of the water
+//! ahead of them to decide whether they can swim across it.
+
+use bevy::math::IVec3;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::audit::FACE_NEIGHBOURS;
+
+/// Number of water blocks stacked from `top` downwards, capped at `max_depth`
+pub fn water_depth(top: IVec3, max_depth: u32, is_water: impl Fn(IVec3) -> bool) -> u32 {
+    (0..max_depth)
+        .take_while(|depth| is_water(top - IVec3::Y * *depth as i32))
+        .count() as u32
+}
+
+/// Water blocks connected to `start`, visited in breadth-first order.
+/// At most `max_blocks` are returned, so that oceans don't have to be explored.
+pub fn reachable_water_blocks(
+    start: IVec3,
+    max_blocks: usize,
+    is_water: impl Fn(IVec3) -> bool,
+) -> Vec<IVec3> {
+    if !is_water(start) {
+        return Vec::new();
+    }
+
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    let mut blocks = Vec::new();
+
+    while let Some(pos) = queue.pop_front() {
+        blocks.push(pos);
+        if blocks.len() >= max_blocks {
+            break;
+        }
+        for offset in FACE_NEIGHBOURS {
+            let neighbour = pos + offset;
+            if is_water(neighbour) && visited.insert(neighbour) {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Shortest path between two water blocks that stays within water, both ends
+/// included. Returns `None` if `goal` isn't reached after exploring `max_blocks`.
+pub fn find_water_path(
+    start: IVec3,
+    goal: IVec3,
+    max_blocks: usize,
+    is_water: impl Fn(IVec3) -> bool,
+) -> Option<Vec<IVec3>> {
+    if !is_water(start) || !is_water(goal) {
+        return None;
+    }
+
+    let mut came_from: HashMap<IVec3, IVec3> = HashMap::from([(start, start)]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = came_from[&current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if came_from.len() >= max_blocks {
+            continue;
+        }
+        for offset in FACE_NEIGHBOURS {
+            let neighbour = pos + offset;
+            if is_water(neighbour) && !came_from.contains_key(&neighbour) {
+                came_from.insert(neighbour, pos);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn water(positions: &[(i32, i32, i32)]) -> HashSet<IVec3> {
+        positions
+            .iter()
+            .map(|(x, y, z)| IVec3::new(*x, *y, *z))
+            .collect()
+    }
+
+    #[test]
+    fn measures_depth_of_stacked_water() {
+        let pool = water(&[(0, 3, 0), (0, 2, 0), (0, 1, 0)]);
+
+        assert_eq!(
+            water_depth(IVec3::new(0, 3, 0), 8, |p| pool.contains(&p)),
+            3
+        );
+        assert_eq!(
+            water_depth(IVec3::new(0, 3, 0), 2, |p| pool.contains(&p)),
+            2
+        );
+        assert_eq!(
+            water_depth(IVec3::new(1, 3, 0), 8, |p| pool.contains(&p)),
+            0
+        );
+    }
+
+    #[test]
+    fn paths_stay_within_connected_water() {
+        // An L-shaped channel, plus a separate pond
+        let channel = water(&[
+            (0, 0, 0),
+            (1, 0, 0),
+            (2, 0, 0),
+            (2, 0, 1),
+            (2, 0, 2),
+            (5, 0, 5),
+        ]);
+        let is_water = |p: IVec3| channel.contains(&p);
+
+        let path = find_water_path(IVec3::ZERO, IVec3::new(2, 0, 2), 64, is_water).unwrap();
+        assert_eq!(path.len(), 5);
+        assert!(path.iter().all(|p| channel.contains(p)));
+
+        assert!(find_water_path(IVec3::ZERO, IVec3::new(5, 0, 5), 64, is_water).is_none());
+        assert_eq!(reachable_water_blocks(IVec3::ZERO, 64, is_water).len(), 5);
+        assert_eq!(reachable_water_blocks(IVec3::ZERO, 2, is_water).len(), 2);
+    }
+}
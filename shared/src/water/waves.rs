@@ -0,0 +1,216 @@
+//! CPU implementation of the water wave displacement.
+//!
+//! This is a line-by-line port of `wave` and `get_wave_height` from bevy_water's
+//! `water_functions.wgsl` (and the noise functions it imports), evaluated at the
+//! highest shader quality. Keep it in sync when upgrading bevy_water: the test below
+//! checks it against bevy_water's own CPU mirror of the shader.
+
+use bevy::math::{IVec3, Mat2, Vec2, Vec3, Vec3Swizzles};
+
+use super::navigation::water_depth;
+
+/// Wave amplitude of the rendered water at the highest quality preset
+pub const WAVE_AMPLITUDE: f32 = 0.2;
+
+/// Height of the undisplaced water surface above the bottom of a water block
+/// (14/16 of a block), matching the client water meshes.
+pub const WATER_SURFACE_OFFSET: f32 = 0.875;
+
+const M2: Mat2 = Mat2::from_cols(Vec2::new(0.8, 0.6), Vec2::new(-0.6, 0.8));
+
+// WGSL compatible `fract`
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn mix(x: f32, y: f32, a: f32) -> f32 {
+    x * (1.0 - a) + y * a
+}
+
+fn random2d(v: Vec2) -> f32 {
+    fract(v.dot(Vec2::new(12.9898, 78.233)).sin() * 43758.547)
+}
+
+fn vnoise2d(v: Vec2) -> f32 {
+    let i = v.floor();
+    let f = v - i;
+
+    let a = random2d(i);
+    let b = random2d(i + Vec2::new(1.0, 0.0));
+    let c = random2d(i + Vec2::new(0.0, 1.0));
+    let d = random2d(i + Vec2::new(1.0, 1.0));
+
+    let u = Vec2::new(smoothstep(0.0, 1.0, f.x), smoothstep(0.0, 1.0, f.y));
+
+    mix(a, b, u.x) + (c - a) * u.y * (1.0 - u.x) + (d - b) * u.x * u.y
+}
+
+fn fbm(mut p: Vec2) -> f32 {
+    let mut f = 0.5 * vnoise2d(p);
+    p = M2 * p * 2.02;
+    f += 0.25 * vnoise2d(p);
+    p = M2 * p * 2.03;
+    f += 0.125 * vnoise2d(p);
+    p = M2 * p * 2.01;
+    f += 0.0625 * vnoise2d(p);
+    f / 0.9375
+}
+
+fn wave(global_time: f32, p: Vec2) -> f32 {
+    let time = global_time * 0.5 + 23.0;
+
+    let time_x = time / 1.0;
+    let time_y = time / 0.5;
+    let wave_len_x = 5.0;
+    let wave_len_y = 2.0;
+    let wave_x = (p.x / wave_len_x + time_x).cos();
+    let wave_y = smoothstep(1.0, 0.0, (p.y / wave_len_y + wave_x + time_y).sin().abs());
+    let n = fbm(p) / 2.0 - 1.0;
+    wave_y + n
+}
+
+/// Vertical displacement of the water surface at a world XZ position, using the
+/// default amplitude. See [`sample_wave_height_with_amplitude`].
+pub fn sample_wave_height(world_pos: Vec2, time: f32) -> f32 {
+    sample_wave_height_with_amplitude(world_pos, time, WAVE_AMPLITUDE)
+}
+
+/// Vertical displacement of the water surface at a world XZ position.
+///
+/// `time` must be the same clock the shader reads from `globals.time`, which is
+/// `Time::elapsed_secs_wrapped()` on the client, for the result to match the drawn
+/// water. The server samples its own clock, so its waves have the same shape out
+/// of phase. The result is relative to the undisplaced surface, i.e.
+/// `water block y + WATER_SURFACE_OFFSET`.
+pub fn sample_wave_height_with_amplitude(world_pos: Vec2, time: f32, amplitude: f32) -> f32 {
+    let half_time = time / 2.0;
+    let mut d = wave(time, (world_pos - half_time) * 0.3) * 0.3;
+    d += wave(time, (world_pos + half_time) * 0.4) * 0.3;
+    d += wave(time, (world_pos + half_time) * 0.5) * 0.2;
+    d += wave(time, (world_pos - half_time) * 0.6) * 0.2;
+    amplitude * d
+}
+
+/// World-space height of the displaced water surface above a surface water block.
+/// `amplitude` is the one of the water quality drawn, `WAVE_AMPLITUDE` for gameplay.
+pub fn water_surface_height(water_block_y: i32, world_pos: Vec2, time: f32, amplitude: f32) -> f32 {
+    water_block_y as f32
+        + WATER_SURFACE_OFFSET
+        + sample_wave_height_with_amplitude(world_pos, time, amplitude)
+}
+
+/// Whether `point` is under the displaced water surface. Only the top water block
+/// of a column has waves, the blocks below it are under water all the way up.
+pub fn is_below_water_surface(
+    point: Vec3,
+    time: f32,
+    amplitude: f32,
+    is_water: impl Fn(IVec3) -> bool,
+) -> bool {
+    let cell = point.floor().as_ivec3();
+    is_water(cell)
+        && (is_water(cell + IVec3::Y)
+            || point.y < water_surface_height(cell.y, point.xz(), time, amplitude))
+}
+
+/// Height of the displaced water surface above the floor of the water column
+/// through `cell`, 0 if `cell` isn't water. The column is followed for at most
+/// `max_blocks` blocks up and down from `cell`.
+pub fn water_column_depth(
+    cell: IVec3,
+    max_blocks: u32,
+    time: f32,
+    amplitude: f32,
+    is_water: impl Fn(IVec3) -> bool,
+) -> f32 {
+    let below = water_depth(cell, max_blocks, &is_water);
+    if below == 0 {
+        return 0.0;
+    }
+    let above = (1..max_blocks as i32)
+        .take_while(|dy| is_water(cell + IVec3::Y * *dy))
+        .count() as i32;
+    let floor = cell.y - below as i32 + 1;
+    let center = cell.xz().as_vec2() + Vec2::splat(0.5);
+    water_surface_height(cell.y + above, center, time, amplitude) - floor as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// bevy_water ships a CPU mirror of its shader, validated upstream against the GPU
+    #[test]
+    fn matches_bevy_water_displacement() {
+        const TOLERANCE: f32 = 1e-4;
+
+        for &time in &[0.0, 1.5, 42.0, 600.25, 3599.0] {
+            for x in (-64..64).step_by(7) {
+                for z in (-64..64).step_by(11) {
+                    let pos = Vec2::new(x as f32 + 0.3, z as f32 + 0.7);
+                    let expected = bevy_water::get_wave_height(
+                        time,
+                        0.0,
+                        WAVE_AMPLITUDE,
+                        Vec3::new(pos.x, 0.0, pos.y),
+                    );
+                    let actual = sample_wave_height(pos, time);
+                    assert!(
+                        (expected - actual).abs() < TOLERANCE,
+                        "wave height mismatch at {pos} t={time}: expected {expected}, got {actual}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn displacement_is_bounded_by_amplitude() {
+        for x in -32..32 {
+            let height = sample_wave_height(Vec2::new(x as f32, 3.0), 10.0);
+            // The sum of the wave weights is 1.0, each wave is in [-1, 1]
+            assert!(height.abs() <= WAVE_AMPLITUDE);
+        }
+    }
+
+    #[test]
+    fn waves_only_move_the_top_water_block() {
+        // Water from y = 0 to y = 2
+        let is_water = |cell: IVec3| (0..3).contains(&cell.y);
+        let time = 7.0;
+        let pos = Vec2::new(4.5, 9.5);
+        let surface = water_surface_height(2, pos, time, WAVE_AMPLITUDE);
+
+        let at = |y: f32| Vec3::new(pos.x, y, pos.y);
+        assert!(is_below_water_surface(
+            at(1.99),
+            time,
+            WAVE_AMPLITUDE,
+            is_water
+        ));
+        assert!(is_below_water_surface(
+            at(surface - 0.01),
+            time,
+            WAVE_AMPLITUDE,
+            is_water
+        ));
+        assert!(!is_below_water_surface(
+            at(surface + 0.01),
+            time,
+            WAVE_AMPLITUDE,
+            is_water
+        ));
+
+        let depth = water_column_depth(IVec3::new(4, 1, 9), 8, time, WAVE_AMPLITUDE, is_water);
+        assert!((depth - surface).abs() < 1e-5);
+        assert_eq!(
+            water_column_depth(IVec3::new(4, 5, 9), 8, time, WAVE_AMPLITUDE, is_water),
+            0.0
+        );
+    }
+}
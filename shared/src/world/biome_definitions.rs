@@ -0,0 +1,367 @@
+//! Biomes defined in RON files, in the `biomes` folder of the assets.
+//!
+//! A file whose name is the one of a built-in biome replaces its parameters. Any
+//! other one adds a biome, which takes over a range of the climate and behaves
+//! like its `base` biome for what isn't a parameter: tree species, colors,
+//! sounds... For instance, `biomes/red_desert.ron`:
+//!
+//! ```text
+//! (
+//!     name: "Red Desert",
+//!     base: Some(Desert),
+//!     climate: Some((temperature: (0.9, 1.0), humidity: (0.0, 0.1))),
+//!     base_height: 68,
+//!     height_variation: 3,
+//!     surface_block: Gravel,
+//!     sub_surface_block: Sand,
+//!     flora: (cactus: 0.02),
+//! )
+//! ```
+//!
+//! The server checks the definitions when it starts, and refuses to start with
+//! an invalid one rather than generating a broken world.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, PoisonError, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::{get_biome_data, Biome, BiomeClimate, BiomeType, BlockId, BlockTransparency};
+
+/// Folder of the biome definitions, in the assets folder
+pub const BIOMES_FOLDER: &str = "biomes";
+/// No ground is generated above this height
+const MAX_BIOME_HEIGHT: i32 = 255;
+
+/// Chance for each surface block to grow a kind of flora, between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloraThresholds {
+    pub flower: f32,
+    pub tall_grass: f32,
+    pub tree: f32,
+    pub cactus: f32,
+    pub giant_mushroom: f32,
+    /// Kelp grows on the ocean floor
+    pub kelp: f32,
+    /// Coral only grows in warm water, see `WARM_WATER_TEMPERATURE`
+    pub coral: f32,
+    /// Clusters of mossy boulders
+    pub boulder: f32,
+    /// Tall pillars of stone
+    pub rock_spire: f32,
+}
+
+/// Temperatures and humidities where a biome is found, bounds included
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClimateRange {
+    pub temperature: (f64, f64),
+    pub humidity: (f64, f64),
+}
+
+impl ClimateRange {
+    fn contains(&self, climate: BiomeClimate) -> bool {
+        (self.temperature.0..=self.temperature.1).contains(&climate.temperature)
+            && (self.humidity.0..=self.humidity.1).contains(&climate.humidity)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BiomeDefinition {
+    pub name: String,
+    /// Built-in biome a new biome behaves like
+    #[serde(default)]
+    pub base: Option<BiomeType>,
+    /// Where a new biome replaces the built-in ones
+    #[serde(default)]
+    pub climate: Option<ClimateRange>,
+    pub base_height: i32,
+    pub height_variation: i32,
+    #[serde(default)]
+    pub ridge_height: i32,
+    #[serde(default)]
+    pub terrace_weight: f64,
+    pub surface_block: BlockId,
+    pub sub_surface_block: BlockId,
+    #[serde(default)]
+    pub flora: FloraThresholds,
+}
+
+impl BiomeDefinition {
+    /// The built-in biome this definition replaces, or the one a new biome is based on
+    fn validate(&self) -> Result<BiomeType, String> {
+        if self.name.trim().is_empty() {
+            return Err("the name is empty".to_string());
+        }
+
+        let biome_type = match BiomeType::from_name(&self.name) {
+            Some(built_in) => {
+                if self.base.is_some_and(|base| base != built_in) {
+                    return Err(format!(
+                        "it replaces {}, it can't be based on another biome",
+                        built_in.name()
+                    ));
+                }
+                if self.climate.is_some() {
+                    return Err(format!(
+                        "it replaces {}, which keeps its climate",
+                        built_in.name()
+                    ));
+                }
+                built_in
+            }
+            None => {
+                let Some(base) = self.base else {
+                    return Err("a new biome needs a base biome".to_string());
+                };
+                let Some(climate) = self.climate else {
+                    return Err("a new biome needs a climate".to_string());
+                };
+                for (bounds, what) in [
+                    (climate.temperature, "temperature"),
+                    (climate.humidity, "humidity"),
+                ] {
+                    if !(0.0..=1.0).contains(&bounds.0)
+                        || !(0.0..=1.0).contains(&bounds.1)
+                        || bounds.0 > bounds.1
+                    {
+                        return Err(format!(
+                            "the {} range {:?} is not an increasing range between 0 and 1",
+                            what, bounds
+                        ));
+                    }
+                }
+                base
+            }
+        };
+
+        if self.height_variation < 0 || self.ridge_height < 0 {
+            return Err("heights can't vary by a negative amount".to_string());
+        }
+        if self.base_height - self.height_variation < 1
+            || self.base_height + self.height_variation + self.ridge_height > MAX_BIOME_HEIGHT
+        {
+            return Err(format!(
+                "the ground must stay between 1 and {}",
+                MAX_BIOME_HEIGHT
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.terrace_weight) {
+            return Err("the terrace weight must be between 0 and 1".to_string());
+        }
+        let flora = self.flora;
+        if [
+            flora.flower,
+            flora.tall_grass,
+            flora.tree,
+            flora.cactus,
+            flora.giant_mushroom,
+            flora.kelp,
+            flora.coral,
+            flora.boulder,
+            flora.rock_spire,
+        ]
+        .iter()
+        .any(|threshold| !(0.0..=1.0).contains(threshold))
+        {
+            return Err("flora thresholds must be between 0 and 1".to_string());
+        }
+        for block in [self.surface_block, self.sub_surface_block] {
+            if !matches!(
+                block.get_visibility(),
+                BlockTransparency::Solid | BlockTransparency::Transparent
+            ) {
+                return Err(format!("{:?} can't make the ground", block));
+            }
+        }
+
+        Ok(biome_type)
+    }
+
+    fn biome(&self, biome_type: BiomeType) -> Biome {
+        Biome {
+            biome_type,
+            base_height: self.base_height,
+            height_variation: self.height_variation,
+            ridge_height: self.ridge_height,
+            terrace_weight: self.terrace_weight,
+            surface_block: self.surface_block,
+            sub_surface_block: self.sub_surface_block,
+            flora: self.flora,
+        }
+    }
+}
+
+/// Biomes of the terrain generation, the built-in ones unless replaced by definitions
+#[derive(Debug, Clone, Default)]
+pub struct BiomeRegistry {
+    replaced: HashMap<BiomeType, Biome>,
+    /// New biomes, the first one whose climate matches winning
+    added: Vec<(ClimateRange, Biome)>,
+}
+
+static BIOMES: LazyLock<RwLock<BiomeRegistry>> =
+    LazyLock::new(|| RwLock::new(BiomeRegistry::default()));
+
+impl BiomeRegistry {
+    /// Builds the registry, or lists the problems of every invalid definition
+    pub fn from_definitions(definitions: &[BiomeDefinition]) -> Result<Self, Vec<String>> {
+        let mut registry = Self::default();
+        let mut names: Vec<String> = Vec::new();
+        let mut errors = Vec::new();
+
+        for definition in definitions {
+            let name = definition.name.trim().to_lowercase();
+            if names.contains(&name) {
+                errors.push(format!("Biome {} is defined twice", definition.name));
+                continue;
+            }
+            names.push(name);
+
+            match definition.validate() {
+                Ok(biome_type) => match definition.climate {
+                    Some(climate) => registry.added.push((climate, definition.biome(biome_type))),
+                    None => {
+                        registry
+                            .replaced
+                            .insert(biome_type, definition.biome(biome_type));
+                    }
+                },
+                Err(error) => errors.push(format!("Biome {}: {}", definition.name, error)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(registry)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn biome(&self, climate: BiomeClimate) -> Biome {
+        if let Some((_, biome)) = self.added.iter().find(|(range, _)| range.contains(climate)) {
+            return *biome;
+        }
+        let biome_type = BiomeType::from_climate(climate);
+        self.replaced
+            .get(&biome_type)
+            .copied()
+            .unwrap_or_else(|| get_biome_data(biome_type))
+    }
+
+    /// Number of built-in biomes replaced, and of biomes added
+    pub fn counts(&self) -> (usize, usize) {
+        (self.replaced.len(), self.added.len())
+    }
+
+    /// Makes the terrain generation use these biomes
+    pub fn install(self) {
+        *BIOMES.write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+}
+
+/// Biome of the terrain generation for a climate
+pub fn biome_for_climate(climate: BiomeClimate) -> Biome {
+    BIOMES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .biome(climate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red_desert() -> BiomeDefinition {
+        BiomeDefinition {
+            name: "Red Desert".to_string(),
+            base: Some(BiomeType::Desert),
+            climate: Some(ClimateRange {
+                temperature: (0.9, 1.0),
+                humidity: (0.0, 0.1),
+            }),
+            base_height: 68,
+            height_variation: 3,
+            ridge_height: 0,
+            terrace_weight: 0.0,
+            surface_block: BlockId::Gravel,
+            sub_surface_block: BlockId::Sand,
+            flora: FloraThresholds {
+                cactus: 0.02,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn definitions_replace_and_add_biomes() {
+        let plains = BiomeDefinition {
+            name: "plains".to_string(),
+            base: None,
+            climate: None,
+            surface_block: BlockId::Sand,
+            ..red_desert()
+        };
+        let registry = BiomeRegistry::from_definitions(&[plains, red_desert()]).unwrap();
+        assert_eq!(registry.counts(), (1, 1));
+
+        let hot = BiomeClimate {
+            temperature: 0.95,
+            humidity: 0.05,
+        };
+        let biome = registry.biome(hot);
+        assert_eq!(biome.biome_type, BiomeType::Desert);
+        assert_eq!(biome.surface_block, BlockId::Gravel);
+
+        let temperate = BiomeClimate {
+            temperature: 0.45,
+            humidity: 0.3,
+        };
+        assert_eq!(BiomeType::from_climate(temperate), BiomeType::Plains);
+        assert_eq!(registry.biome(temperate).surface_block, BlockId::Sand);
+
+        let cold = BiomeClimate {
+            temperature: 0.1,
+            humidity: 0.1,
+        };
+        assert_eq!(
+            registry.biome(cold).surface_block,
+            get_biome_data(BiomeType::from_climate(cold)).surface_block
+        );
+    }
+
+    #[test]
+    fn invalid_definitions_are_all_reported() {
+        let no_base = BiomeDefinition {
+            name: "Nowhere".to_string(),
+            base: None,
+            ..red_desert()
+        };
+        let too_high = BiomeDefinition {
+            name: "Sky".to_string(),
+            base_height: 250,
+            height_variation: 10,
+            ..red_desert()
+        };
+        let liquid_ground = BiomeDefinition {
+            name: "Lake".to_string(),
+            surface_block: BlockId::Water,
+            ..red_desert()
+        };
+        let moved_built_in = BiomeDefinition {
+            name: "Desert".to_string(),
+            ..red_desert()
+        };
+        let errors = BiomeRegistry::from_definitions(&[
+            red_desert(),
+            red_desert(),
+            no_base,
+            too_high,
+            liquid_ground,
+            moved_built_in,
+        ])
+        .unwrap_err();
+        assert_eq!(errors.len(), 5);
+        assert!(errors[0].contains("twice"));
+    }
+}
@@ -1,11 +1,21 @@
 use std::collections::HashMap;
 
 use super::{GameElementId, ItemId};
+use crate::fluid::FluidKind;
 use bevy::math::{bounding::Aabb3d, Vec3A};
 use nonempty::{nonempty, NonEmpty};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+/// Number of layers making up a full block of snow
+pub const SNOW_LAYER_COUNT: u8 = 8;
+/// Half the width of a fence post, which stands in the middle of its block
+pub const FENCE_POST_HALF_WIDTH: f32 = 0.125;
+/// Height of a bed, relative to a full block
+pub const BED_HEIGHT: f32 = 0.5625;
+/// Height of the water of a puddle, relative to a full block
+pub const PUDDLE_HEIGHT: f32 = 0.0625;
+
 #[derive(Copy, Clone)]
 struct RayHitboxArgs {
     center: [f32; 3],
@@ -19,6 +29,13 @@ impl RayHitboxArgs {
             half_size: [0.3, 0.3, 0.3],
         }
     }
+
+    fn hanging() -> Self {
+        RayHitboxArgs {
+            center: [0.5, 0.5, 0.5],
+            half_size: [0.4, 0.5, 0.4],
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -182,6 +199,59 @@ pub enum BlockId {
     SpruceLeaves,
     SpruceLog,
     Water,
+    /// Thin snow covering a surface, see `BlockData::level` for its height
+    SnowLayer,
+    /// Post that mobs can be tied to with a lead
+    OakFence,
+    /// Teleports players between the waystones they discovered, see `waystones`
+    Waystone,
+    /// Skips the night once enough players sleep in one
+    Bed,
+    /// Displays an item on a wall, see `item_frames`
+    ItemFrame,
+    CoalOre,
+    IronOre,
+    GoldOre,
+    DiamondOre,
+    /// Pulls the water above it down in a bubble column
+    Magma,
+    /// Pushes the water above it up in a bubble column
+    SoulSand,
+    Gravel,
+    /// Darker grass of the swamps
+    SwampGrass,
+    Mycelium,
+    MushroomStem,
+    RedMushroomBlock,
+    /// Hangs from the leaves of swamp trees
+    Vine,
+    BirchLog,
+    BirchLeaves,
+    JungleLog,
+    JungleLeaves,
+    /// Stone of the depths, below `GenerationConfig::deepslate_level`
+    Deepslate,
+    /// Fills the pools near the bedrock, see `GenerationConfig::lava_level`
+    Lava,
+    /// Sends the players walking into it to the other dimension, see `dimensions`
+    Portal,
+    /// Stone of the nether
+    Netherrack,
+    /// Tilled dirt, hydrated (`BlockData::level` 1) near water or under the rain
+    Farmland,
+    /// Soaked dirt of humid shores
+    Mud,
+    /// Soaked sand and gravel of shores
+    Clay,
+    /// Shallow rain water lying on the ground, evaporates once the rain stops
+    Puddle,
+    /// Grows in columns from the ocean floor, see `BlockId::is_waterlogged`
+    Kelp,
+    /// Reefs of the warm shallow oceans
+    Coral,
+    MossyCobblestone,
+    /// Hardened lava, left where water meets a lava source
+    Obsidian,
 }
 
 static BLOCK_PROPERTIES: std::sync::LazyLock<HashMap<BlockId, BlockProperties>> =
@@ -225,7 +295,10 @@ static BLOCK_PROPERTIES: std::sync::LazyLock<HashMap<BlockId, BlockProperties>>
             ),
             (
                 BlockId::Ice,
-                BlockProperties::full_solid_block_single_drop_item(30, ItemId::Ice),
+                BlockProperties::full_transparent_block(Some(BlockBreakability {
+                    break_time: 30,
+                    drop_table: Some(nonempty![DropStatistics::with_base_chance(ItemId::Ice)]),
+                })),
             ),
             (
                 BlockId::Glass,
@@ -269,6 +342,58 @@ static BLOCK_PROPERTIES: std::sync::LazyLock<HashMap<BlockId, BlockProperties>>
                 BlockId::Cobblestone,
                 BlockProperties::full_solid_block_single_drop_item(12, ItemId::Cobblestone),
             ),
+            (
+                BlockId::CoalOre,
+                BlockProperties::full_solid_block_single_drop_item(90, ItemId::Coal),
+            ),
+            (
+                BlockId::IronOre,
+                BlockProperties::full_solid_block_single_drop_item(90, ItemId::IronOre),
+            ),
+            (
+                BlockId::GoldOre,
+                BlockProperties::full_solid_block_single_drop_item(90, ItemId::GoldOre),
+            ),
+            (
+                BlockId::DiamondOre,
+                BlockProperties::full_solid_block_single_drop_item(120, ItemId::Diamond),
+            ),
+            (
+                BlockId::Magma,
+                BlockProperties::full_solid_block_single_drop_item(60, ItemId::Magma),
+            ),
+            (
+                BlockId::SoulSand,
+                BlockProperties::full_solid_block_single_drop_item(30, ItemId::SoulSand),
+            ),
+            (
+                BlockId::Gravel,
+                BlockProperties::full_solid_block_single_drop_item(36, ItemId::Gravel),
+            ),
+            (
+                BlockId::SwampGrass,
+                BlockProperties::full_solid_block_single_drop_item(36, ItemId::Dirt),
+            ),
+            (
+                BlockId::Mycelium,
+                BlockProperties::full_solid_block_single_drop_item(36, ItemId::Dirt),
+            ),
+            (
+                BlockId::MushroomStem,
+                BlockProperties::full_solid_block_single_drop_item(18, ItemId::MushroomStem),
+            ),
+            (
+                BlockId::RedMushroomBlock,
+                BlockProperties::full_solid_block_single_drop_item(18, ItemId::RedMushroomBlock),
+            ),
+            (
+                BlockId::Vine,
+                BlockProperties::decoration_block_single_drop(
+                    6,
+                    ItemId::Vine,
+                    RayHitboxArgs::hanging(),
+                ),
+            ),
             (
                 BlockId::Snow,
                 BlockProperties::full_solid_block_single_drop(
@@ -288,6 +413,93 @@ static BLOCK_PROPERTIES: std::sync::LazyLock<HashMap<BlockId, BlockProperties>>
                 BlockId::SpruceLog,
                 BlockProperties::full_solid_block_single_drop_item(60, ItemId::SpruceLog),
             ),
+            (
+                BlockId::BirchLeaves,
+                BlockProperties::full_transparent_block_no_drop(12),
+            ),
+            (
+                BlockId::BirchLog,
+                BlockProperties::full_solid_block_single_drop_item(60, ItemId::BirchLog),
+            ),
+            (
+                BlockId::JungleLeaves,
+                BlockProperties::full_transparent_block_no_drop(12),
+            ),
+            (
+                BlockId::JungleLog,
+                BlockProperties::full_solid_block_single_drop_item(60, ItemId::JungleLog),
+            ),
+            (
+                BlockId::SnowLayer,
+                BlockProperties {
+                    breakability: Some(BlockBreakability {
+                        break_time: 6,
+                        drop_table: Some(nonempty![DropStatistics::with_base_chance(
+                            ItemId::Snowball
+                        )]),
+                    }),
+                    // The actual hitboxes depend on the number of layers, see `BlockData`
+                    hitbox: Hitbox::Pathable {
+                        ray_hitbox: BlockHitbox::None,
+                    },
+                    visibility: BlockTransparency::Decoration,
+                },
+            ),
+            (
+                BlockId::Bed,
+                BlockProperties {
+                    breakability: Some(BlockBreakability {
+                        break_time: 12,
+                        drop_table: Some(nonempty![DropStatistics::with_base_chance(ItemId::Bed)]),
+                    }),
+                    hitbox: Hitbox::Solid {
+                        collision_hitbox: BlockHitbox::from_args(
+                            [0.5, BED_HEIGHT / 2.0, 0.5],
+                            [0.5, BED_HEIGHT / 2.0, 0.5],
+                        ),
+                    },
+                    visibility: BlockTransparency::Decoration,
+                },
+            ),
+            (
+                BlockId::Waystone,
+                BlockProperties::full_solid_block_single_drop_item(90, ItemId::Waystone),
+            ),
+            (
+                BlockId::ItemFrame,
+                BlockProperties {
+                    breakability: Some(BlockBreakability {
+                        break_time: 6,
+                        drop_table: Some(nonempty![DropStatistics::with_base_chance(
+                            ItemId::ItemFrame
+                        )]),
+                    }),
+                    // The actual hitbox depends on the wall the frame hangs on, see `BlockData`
+                    hitbox: Hitbox::Pathable {
+                        ray_hitbox: BlockHitbox::None,
+                    },
+                    visibility: BlockTransparency::Decoration,
+                },
+            ),
+            (
+                BlockId::OakFence,
+                BlockProperties {
+                    breakability: Some(BlockBreakability {
+                        break_time: 40,
+                        drop_table: Some(nonempty![DropStatistics::with_base_chance(
+                            ItemId::OakFence
+                        )]),
+                    }),
+                    hitbox: Hitbox::Solid {
+                        collision_hitbox: BlockHitbox::from_args(
+                            [0.5, 0.5, 0.5],
+                            [FENCE_POST_HALF_WIDTH, 0.5, FENCE_POST_HALF_WIDTH],
+                        ),
+                    },
+                    // Doesn't hide the faces of its neighbours
+                    visibility: BlockTransparency::Decoration,
+                },
+            ),
             (
                 BlockId::Water,
                 BlockProperties {
@@ -298,6 +510,81 @@ static BLOCK_PROPERTIES: std::sync::LazyLock<HashMap<BlockId, BlockProperties>>
                     visibility: BlockTransparency::Liquid,
                 },
             ),
+            (
+                BlockId::Deepslate,
+                BlockProperties::full_solid_block_single_drop_item(90, ItemId::Deepslate),
+            ),
+            (
+                BlockId::Lava,
+                BlockProperties {
+                    breakability: None,
+                    hitbox: Hitbox::Pathable {
+                        ray_hitbox: BlockHitbox::None,
+                    },
+                    visibility: BlockTransparency::Liquid,
+                },
+            ),
+            (
+                BlockId::Portal,
+                BlockProperties {
+                    breakability: Some(BlockBreakability {
+                        break_time: 30,
+                        drop_table: Some(nonempty![DropStatistics::with_base_chance(
+                            ItemId::Portal
+                        )]),
+                    }),
+                    hitbox: Hitbox::Pathable {
+                        ray_hitbox: BlockHitbox::FullBlock,
+                    },
+                    visibility: BlockTransparency::Transparent,
+                },
+            ),
+            (
+                BlockId::Netherrack,
+                BlockProperties::full_solid_block_single_drop_item(30, ItemId::Netherrack),
+            ),
+            (
+                BlockId::Farmland,
+                BlockProperties::full_solid_block_single_drop_item(12, ItemId::Dirt),
+            ),
+            (
+                BlockId::Mud,
+                BlockProperties::full_solid_block_single_drop_item(12, ItemId::Mud),
+            ),
+            (
+                BlockId::Clay,
+                BlockProperties::full_solid_block_single_drop_item(15, ItemId::Clay),
+            ),
+            (
+                BlockId::Puddle,
+                BlockProperties {
+                    breakability: None,
+                    hitbox: Hitbox::Pathable {
+                        ray_hitbox: BlockHitbox::None,
+                    },
+                    visibility: BlockTransparency::Decoration,
+                },
+            ),
+            (
+                BlockId::Kelp,
+                BlockProperties::decoration_block_single_drop(
+                    6,
+                    ItemId::Kelp,
+                    RayHitboxArgs::hanging(),
+                ),
+            ),
+            (
+                BlockId::Coral,
+                BlockProperties::full_solid_block_single_drop_item(15, ItemId::Coral),
+            ),
+            (
+                BlockId::MossyCobblestone,
+                BlockProperties::full_solid_block_single_drop_item(12, ItemId::MossyCobblestone),
+            ),
+            (
+                BlockId::Obsidian,
+                BlockProperties::full_solid_block_single_drop_item(150, ItemId::Obsidian),
+            ),
         ])
     });
 
@@ -315,6 +602,11 @@ pub struct BlockData {
     pub id: BlockId,
     pub direction: BlockDirection,
     pub breaking_progress: u8,
+    /// Block specific level, such as the number of layers of a `SnowLayer`, or
+    /// the distance of flowing water and lava from their source.
+    /// Unused by most blocks.
+    #[serde(default)]
+    pub level: u8,
 }
 
 impl BlockData {
@@ -323,12 +615,65 @@ impl BlockData {
             id,
             direction,
             breaking_progress: 0,
+            level: 0,
+        }
+    }
+
+    pub fn snow_layer(layers: u8) -> Self {
+        BlockData {
+            level: layers.clamp(1, SNOW_LAYER_COUNT),
+            ..BlockData::new(BlockId::SnowLayer, BlockDirection::Front)
         }
     }
 
     pub fn get_breaking_level(&self) -> u8 {
         ((self.breaking_progress as u16 * 10) / self.id.get_break_time() as u16) as u8
     }
+
+    /// Height of the block's top surface, between 0 and 1
+    pub fn height(&self) -> f32 {
+        match self.id {
+            BlockId::SnowLayer => self.level as f32 / SNOW_LAYER_COUNT as f32,
+            BlockId::Bed => BED_HEIGHT,
+            BlockId::Puddle => PUDDLE_HEIGHT,
+            _ => 1.0,
+        }
+    }
+
+    /// Hitbox used for collisions with players and mobs, relative to the block position
+    pub fn get_collision_hitbox(&self) -> BlockHitbox {
+        match self.id {
+            // The top layer can be walked through, like fresh snow
+            BlockId::SnowLayer => snow_layers_hitbox(self.level.saturating_sub(1)),
+            _ => match self.id.properties() {
+                Some(BlockProperties {
+                    hitbox: Hitbox::Solid { collision_hitbox },
+                    ..
+                }) => *collision_hitbox,
+                Some(_) => BlockHitbox::None,
+                None => BlockHitbox::FullBlock,
+            },
+        }
+    }
+
+    /// Hitbox used for raycasting, relative to the block position
+    pub fn get_ray_hitbox(&self) -> BlockHitbox {
+        match self.id {
+            BlockId::SnowLayer => snow_layers_hitbox(self.level.max(1)),
+            BlockId::ItemFrame => super::item_frame_hitbox(self.direction),
+            _ => self.id.get_ray_hitbox(),
+        }
+    }
+}
+
+fn snow_layers_hitbox(layers: u8) -> BlockHitbox {
+    if layers == 0 {
+        return BlockHitbox::None;
+    }
+    BlockHitbox::Aabb(Aabb3d {
+        min: Vec3A::ZERO,
+        max: Vec3A::new(1.0, layers as f32 / SNOW_LAYER_COUNT as f32, 1.0),
+    })
 }
 
 pub enum BlockTags {
@@ -336,6 +681,40 @@ pub enum BlockTags {
     Stone,
 }
 
+/// Family of sounds played when a block is placed, broken or walked on
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum SoundGroup {
+    Stone,
+    Wood,
+    Grass,
+    Sand,
+    Glass,
+    Water,
+}
+
+impl SoundGroup {
+    pub const ALL: [SoundGroup; 6] = [
+        SoundGroup::Stone,
+        SoundGroup::Wood,
+        SoundGroup::Grass,
+        SoundGroup::Sand,
+        SoundGroup::Glass,
+        SoundGroup::Water,
+    ];
+
+    /// Name used in the sound file names, e.g. `stone_place.ogg`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundGroup::Stone => "stone",
+            SoundGroup::Wood => "wood",
+            SoundGroup::Grass => "grass",
+            SoundGroup::Sand => "sand",
+            SoundGroup::Glass => "glass",
+            SoundGroup::Water => "water",
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum BlockTransparency {
     Transparent,
@@ -360,6 +739,63 @@ impl BlockHitbox {
 }
 
 impl BlockId {
+    /// Every block, by id: blocks are sent and saved as their index in this list
+    pub const ALL: [BlockId; 53] = [
+        Self::Dirt,
+        Self::Debug,
+        Self::Grass,
+        Self::Stone,
+        Self::OakLog,
+        Self::OakPlanks,
+        Self::OakLeaves,
+        Self::Sand,
+        Self::Cactus,
+        Self::Ice,
+        Self::Glass,
+        Self::Bedrock,
+        Self::Dandelion,
+        Self::Poppy,
+        Self::TallGrass,
+        Self::Cobblestone,
+        Self::Snow,
+        Self::SpruceLeaves,
+        Self::SpruceLog,
+        Self::Water,
+        Self::SnowLayer,
+        Self::OakFence,
+        Self::Waystone,
+        Self::Bed,
+        Self::ItemFrame,
+        Self::CoalOre,
+        Self::IronOre,
+        Self::GoldOre,
+        Self::DiamondOre,
+        Self::Magma,
+        Self::SoulSand,
+        Self::Gravel,
+        Self::SwampGrass,
+        Self::Mycelium,
+        Self::MushroomStem,
+        Self::RedMushroomBlock,
+        Self::Vine,
+        Self::BirchLog,
+        Self::BirchLeaves,
+        Self::JungleLog,
+        Self::JungleLeaves,
+        Self::Deepslate,
+        Self::Lava,
+        Self::Portal,
+        Self::Netherrack,
+        Self::Farmland,
+        Self::Mud,
+        Self::Clay,
+        Self::Puddle,
+        Self::Kelp,
+        Self::Coral,
+        Self::MossyCobblestone,
+        Self::Obsidian,
+    ];
+
     fn properties(&self) -> Option<&BlockProperties> {
         BLOCK_PROPERTIES.get(self)
     }
@@ -443,6 +879,96 @@ impl BlockId {
             None => BlockTransparency::Solid,
         }
     }
+
+    pub fn sound_group(&self) -> SoundGroup {
+        match self {
+            BlockId::Debug
+            | BlockId::Stone
+            | BlockId::Cobblestone
+            | BlockId::MossyCobblestone
+            | BlockId::Obsidian
+            | BlockId::CoalOre
+            | BlockId::IronOre
+            | BlockId::GoldOre
+            | BlockId::DiamondOre
+            | BlockId::Magma
+            | BlockId::Coral
+            | BlockId::Deepslate
+            | BlockId::Netherrack
+            | BlockId::Bedrock
+            | BlockId::Waystone => SoundGroup::Stone,
+            BlockId::OakLog
+            | BlockId::OakPlanks
+            | BlockId::OakFence
+            | BlockId::Bed
+            | BlockId::ItemFrame
+            | BlockId::SpruceLog
+            | BlockId::BirchLog
+            | BlockId::JungleLog
+            | BlockId::Cactus
+            | BlockId::MushroomStem
+            | BlockId::RedMushroomBlock => SoundGroup::Wood,
+            BlockId::Dirt
+            | BlockId::Farmland
+            | BlockId::Mud
+            | BlockId::Grass
+            | BlockId::SwampGrass
+            | BlockId::Mycelium
+            | BlockId::Vine
+            | BlockId::Kelp
+            | BlockId::OakLeaves
+            | BlockId::SpruceLeaves
+            | BlockId::BirchLeaves
+            | BlockId::JungleLeaves
+            | BlockId::Dandelion
+            | BlockId::Poppy
+            | BlockId::TallGrass => SoundGroup::Grass,
+            BlockId::Sand
+            | BlockId::SoulSand
+            | BlockId::Gravel
+            | BlockId::Clay
+            | BlockId::Snow
+            | BlockId::SnowLayer => SoundGroup::Sand,
+            BlockId::Ice | BlockId::Glass | BlockId::Portal => SoundGroup::Glass,
+            BlockId::Water | BlockId::Lava | BlockId::Puddle => SoundGroup::Water,
+        }
+    }
+
+    /// Whether placing a block on this one replaces it instead of being placed next to it
+    pub fn is_replaceable(&self) -> bool {
+        matches!(self, BlockId::SnowLayer | BlockId::Puddle)
+    }
+
+    /// Whether the block gives off heat, keeping the water around it from freezing
+    pub fn is_heat_source(&self) -> bool {
+        matches!(self, BlockId::Lava | BlockId::Magma)
+    }
+
+    /// Whether the block grows in water, bodies swimming through it like through
+    /// the water around
+    pub fn is_waterlogged(&self) -> bool {
+        matches!(self, BlockId::Kelp)
+    }
+
+    /// Fluid held by the block and its volume. Ice keeps the volume of the water it
+    /// froze from, so freezing and melting conserve water.
+    pub fn stored_fluid(&self) -> Option<(FluidKind, f32)> {
+        match self {
+            BlockId::Water | BlockId::Ice => {
+                Some((FluidKind::Water, FluidKind::Water.block_volume()))
+            }
+            BlockId::Lava => Some((FluidKind::Lava, FluidKind::Lava.block_volume())),
+            _ => None,
+        }
+    }
+
+    /// Volume of `kind` held by the block
+    pub fn stored_fluid_volume(&self, kind: FluidKind) -> f32 {
+        match self.stored_fluid() {
+            Some((stored, volume)) if stored == kind => volume,
+            _ => 0.0,
+        }
+    }
 }
 
 impl GameElementId for BlockId {}
@@ -0,0 +1,200 @@
+//! Boats, floating on water and steered by the player riding them.
+//!
+//! Using a boat item on water puts a boat on it, and using the boat boards it.
+//! The player riding a boat carries it in `Player::boat`: the movement keys steer
+//! it through the shared player simulation, so clients predict the ride like any
+//! other movement, and sneaking leaves it. The server keeps the boats nobody
+//! rides as [`ServerBoat`]s and sends them with
+//! [`BoatUpdate`](crate::messages::BoatUpdate).
+//!
+//! Boats float on the still water surface, so that clients and server agree on
+//! where they are. Clients rock them on the animated waves when drawing them,
+//! like fishing bobbers.
+
+use bevy::math::bounding::Aabb3d;
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockHitbox, BlockId, WorldMap};
+use crate::water::WATER_SURFACE_OFFSET;
+
+pub type BoatId = u128;
+
+/// Farthest a boat is put on water or boarded from
+pub const BOAT_REACH: f32 = 5.0;
+/// Half the size of the hull, a boat being as long as it is wide for collisions
+pub const BOAT_HALF_SIZE: Vec3 = Vec3::new(0.7, 0.3, 0.7);
+/// Depth of the hull under the water surface
+const BOAT_DRAFT: f32 = 0.3;
+/// Height of the deck above the water surface, where the rider stands
+const BOAT_DECK_HEIGHT: f32 = 0.1;
+/// Acceleration of a boat paddled forward, in blocks per second squared
+const BOAT_ACCELERATION: f32 = 10.0;
+/// Fraction of its speed a boat keeps every second on water, and on land
+const BOAT_WATER_DRAG: f32 = 0.25;
+const BOAT_GROUND_DRAG: f32 = 0.001;
+/// Turning speed, in radians per second
+const BOAT_TURN_SPEED: f32 = 2.0;
+/// Pull of the water surface on a boat above or below it, per block
+const BOAT_BUOYANCY: f32 = 40.0;
+/// Damping of the bobbing of a boat around the water surface
+const BOAT_BOB_DAMPING: f32 = 8.0;
+const BOAT_GRAVITY: f32 = -20.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Boat {
+    /// Center of the hull at the water line
+    pub position: Vec3,
+    /// Heading, as a rotation around the Y axis
+    pub yaw: f32,
+    pub velocity: Vec3,
+}
+
+/// Boat put on the water, which nobody rides
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ServerBoat {
+    pub boat: Boat,
+}
+
+fn is_water(world_map: &impl WorldMap, cell: IVec3) -> bool {
+    world_map
+        .get_block_by_coordinates(&cell)
+        .is_some_and(|block| block.id == BlockId::Water)
+}
+
+impl Boat {
+    pub fn new(position: Vec3, yaw: f32) -> Self {
+        Self {
+            position,
+            yaw,
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    /// Boat floating on the water block at `water`, heading along `yaw`
+    pub fn on_water(water: IVec3, yaw: f32) -> Self {
+        Self::new(
+            Vec3::new(
+                water.x as f32 + 0.5,
+                water.y as f32 + WATER_SURFACE_OFFSET,
+                water.z as f32 + 0.5,
+            ),
+            yaw,
+        )
+    }
+
+    /// Direction the boat moves forward in
+    pub fn heading(&self) -> Vec3 {
+        Vec3::new(-self.yaw.sin(), 0.0, -self.yaw.cos())
+    }
+
+    pub fn bounds(&self) -> Aabb3d {
+        Aabb3d::new(self.position, BOAT_HALF_SIZE)
+    }
+
+    /// Where a player of `height` riding the boat stands, their position being
+    /// their center
+    pub fn seat(&self, height: f32) -> Vec3 {
+        self.position + Vec3::Y * (BOAT_DECK_HEIGHT + height / 2.0)
+    }
+
+    /// Top water block the hull floats on, `None` out of water
+    pub fn water_block(&self, world_map: &impl WorldMap) -> Option<IVec3> {
+        let hull = (self.position - Vec3::Y * BOAT_DRAFT).floor().as_ivec3();
+        let mut water = [hull, hull - IVec3::Y]
+            .into_iter()
+            .find(|cell| is_water(world_map, *cell))?;
+        while is_water(world_map, water + IVec3::Y) {
+            water += IVec3::Y;
+        }
+        Some(water)
+    }
+
+    fn collides(&self, world_map: &impl WorldMap, position: Vec3) -> bool {
+        let min = (position - BOAT_HALF_SIZE).floor().as_ivec3();
+        let max = (position + BOAT_HALF_SIZE).floor().as_ivec3();
+        (min.x..=max.x).any(|x| {
+            (min.y..=max.y).any(|y| {
+                (min.z..=max.z).any(|z| {
+                    world_map
+                        .get_block_by_coordinates(&IVec3::new(x, y, z))
+                        .is_some_and(|block| {
+                            !matches!(block.get_collision_hitbox(), BlockHitbox::None)
+                        })
+                })
+            })
+        })
+    }
+
+    /// Moves the boat for `delta` seconds. `forward` paddles it forward (1) or
+    /// backward (-1), `turn` turns it left (1) or right (-1).
+    pub fn step(&mut self, world_map: &impl WorldMap, forward: f32, turn: f32, delta: f32) {
+        self.yaw += turn * BOAT_TURN_SPEED * delta;
+
+        match self.water_block(world_map) {
+            Some(water) => {
+                self.velocity += self.heading() * forward * BOAT_ACCELERATION * delta;
+                let kept = BOAT_WATER_DRAG.powf(delta);
+                self.velocity.x *= kept;
+                self.velocity.z *= kept;
+
+                let surface = water.y as f32 + WATER_SURFACE_OFFSET;
+                self.velocity.y += ((surface - self.position.y) * BOAT_BUOYANCY
+                    - self.velocity.y * BOAT_BOB_DAMPING)
+                    * delta;
+            }
+            None => {
+                let kept = BOAT_GROUND_DRAG.powf(delta);
+                self.velocity.x *= kept;
+                self.velocity.z *= kept;
+                self.velocity.y += BOAT_GRAVITY * delta;
+            }
+        }
+
+        // One axis at a time, so that boats slide along the shore
+        for axis in [Vec3::X, Vec3::Z, Vec3::Y] {
+            let next = self.position + axis * self.velocity * delta;
+            if self.collides(world_map, next) {
+                self.velocity *= Vec3::ONE - axis;
+            } else {
+                self.position = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockData, BlockDirection, ServerChunkWorldMap};
+
+    #[test]
+    fn boats_float_and_stop_at_the_shore() {
+        let mut world = ServerChunkWorldMap::default();
+        let block = |id| BlockData::new(id, BlockDirection::Front);
+        for x in 0..12 {
+            for z in 0..3 {
+                world.set_block(&IVec3::new(x, 0, z), block(BlockId::Stone));
+                world.set_block(&IVec3::new(x, 1, z), block(BlockId::Water));
+            }
+        }
+        for z in 0..3 {
+            world.set_block(&IVec3::new(12, 1, z), block(BlockId::Stone));
+        }
+
+        // Dropped a bit above the water, heading towards +X
+        let mut boat = Boat::new(Vec3::new(2.5, 2.5, 1.5), -std::f32::consts::FRAC_PI_2);
+        for _ in 0..100 {
+            boat.step(&world, 0.0, 0.0, 0.05);
+        }
+        assert!((boat.position.y - (1.0 + WATER_SURFACE_OFFSET)).abs() < 0.05);
+        assert!(boat.heading().x > 0.99);
+
+        for _ in 0..200 {
+            boat.step(&world, 1.0, 0.0, 0.05);
+        }
+        assert!(boat.position.x > 10.0);
+        assert!(boat.position.x + BOAT_HALF_SIZE.x <= 12.0);
+        assert_eq!(boat.water_block(&world), Some(IVec3::new(11, 1, 1)));
+    }
+}
@@ -0,0 +1,74 @@
+//! Melee combat: players hit the mob they are looking at with left click.
+//!
+//! Attacks are validated by the server, which applies the damage of the held
+//! item, knocks the mob back and makes it invulnerable for a short while.
+
+use bevy::math::Vec3;
+
+use crate::TICKS_PER_SECOND;
+
+use super::ItemStack;
+
+/// Mobs further away than this can't be hit
+pub const MELEE_REACH: f32 = 3.5;
+/// Ticks between two attacks of a player
+pub const ATTACK_COOLDOWN_TICKS: u64 = TICKS_PER_SECOND / 2;
+/// Ticks during which a mob that was just hit can't be hurt again
+pub const INVULNERABILITY_TICKS: u64 = TICKS_PER_SECOND / 2;
+
+/// Damage dealt with an empty hand
+const FIST_DAMAGE: f32 = 1.0;
+const KNOCKBACK_HORIZONTAL_SPEED: f32 = 6.0;
+const KNOCKBACK_VERTICAL_SPEED: f32 = 4.0;
+
+/// Damage dealt by hitting a mob while holding `held`
+pub fn melee_damage(held: Option<&ItemStack>) -> f32 {
+    held.map(|stack| stack.item_id.attack_damage())
+        .unwrap_or(FIST_DAMAGE)
+}
+
+/// Velocity given to an entity at `target` that was hit from `attacker`,
+/// pushing it away and slightly upwards
+pub fn knockback_velocity(attacker: Vec3, target: Vec3) -> Vec3 {
+    let away = (target - attacker)
+        .with_y(0.0)
+        .try_normalize()
+        .unwrap_or(Vec3::X);
+    away * KNOCKBACK_HORIZONTAL_SPEED + Vec3::Y * KNOCKBACK_VERTICAL_SPEED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ItemId, MobKind, ServerMob};
+
+    #[test]
+    fn hits_wait_for_invulnerability_to_end() {
+        let mut mob = ServerMob::new(MobKind::Fox, Vec3::ZERO);
+        let hits = (MobKind::Fox.max_health() / FIST_DAMAGE).ceil() as u64;
+        let knockback = knockback_velocity(Vec3::new(-2.0, 0.0, 0.0), Vec3::ZERO);
+        assert!(knockback.x > 0.0 && knockback.y > 0.0);
+
+        let mut tick = 0;
+        for _ in 1..hits {
+            assert!(!mob.hurt(FIST_DAMAGE, knockback, tick));
+            assert!(mob.is_invulnerable(tick + 1));
+            // Ignored while invulnerable
+            assert!(!mob.hurt(FIST_DAMAGE, knockback, tick + 1));
+            tick += INVULNERABILITY_TICKS;
+        }
+        assert!(mob.hurt(FIST_DAMAGE, knockback, tick));
+        assert_eq!(mob.health(), 0.0);
+    }
+
+    #[test]
+    fn held_items_change_the_damage() {
+        let log = ItemStack {
+            item_id: ItemId::OakLog,
+            item_type: ItemId::OakLog.get_default_type(),
+            nb: 1,
+        };
+        assert_eq!(melee_damage(None), FIST_DAMAGE);
+        assert!(melee_damage(Some(&log)) > FIST_DAMAGE);
+    }
+}
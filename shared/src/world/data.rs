@@ -1,15 +1,20 @@
+use crate::fluid::FluidKind;
 use crate::messages::PlayerId;
 use crate::players::Player;
 use crate::world::{block_to_chunk_coord, global_to_chunk_local, BlockHitbox, BlockId};
+use crate::SEA_LEVEL;
 use bevy::math::{bounding::Aabb3d, IVec3, Vec2, Vec3};
 use bevy_ecs::resource::Resource;
-use bevy_log::info;
+use bevy_log::debug;
 use noiz::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
-use super::{BlockData, ItemId, ItemType, MobId, ServerMob};
+use super::{
+    BlockData, BoatId, Difficulty, DimensionId, FloraThresholds, ItemFrameRegistry, ItemId,
+    ItemType, MobId, ServerBoat, ServerMob, WaystoneRegistry,
+};
 
 // Biome generation constants - shared between client and server
 /// Scale factor for biome noise generation
@@ -18,9 +23,27 @@ pub const BIOME_SCALE: f32 = 0.01;
 pub const TEMP_SEED_OFFSET: u32 = 1;
 /// Seed offset for humidity noise generation
 pub const HUMIDITY_SEED_OFFSET: u32 = 2;
-
-/// Represents a type of flora that can be requested for generation in the chunk above.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Seed offset for the ridge noise of the mountain ranges
+pub const RIDGE_SEED_OFFSET: u32 = 3;
+/// Seed offset for the 3D density noise of the overhangs and floating islands
+pub const DENSITY_SEED_OFFSET: u32 = 4;
+/// Seed offset for the river noise
+pub const RIVER_SEED_OFFSET: u32 = 5;
+/// Seed offset for the path noise of the ravines
+pub const RAVINE_SEED_OFFSET: u32 = 6;
+/// Seed offset for the width noise of the beaches
+pub const BEACH_SEED_OFFSET: u32 = 7;
+/// Seed offset for the 3D noise of the lava pools
+pub const LAVA_SEED_OFFSET: u32 = 8;
+/// Seed offset for the density noise of the nether
+pub const NETHER_SEED_OFFSET: u32 = 9;
+/// Seed offset for the noise of the gravel and clay patches of the ocean floor
+pub const SEABED_SEED_OFFSET: u32 = 10;
+/// Seed offset for the noise of the flower meadows
+pub const FLOWER_SEED_OFFSET: u32 = 11;
+
+/// Represents a type of flora growing on the surface of a chunk
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum FloraType {
     /// A flower (Dandelion or Poppy)
     Flower,
@@ -32,25 +55,40 @@ pub enum FloraType {
     BigTree,
     /// A cactus
     Cactus,
+    /// A thin layer of snow, on cold surfaces
+    SnowLayer,
+    /// A giant red mushroom (Mushroom Fields biome)
+    GiantMushroom,
+    /// A column of kelp, on the ocean floor
+    Kelp,
+    /// A mound of coral, on the floor of warm shallow oceans
+    Coral,
+    /// A cluster of mossy boulders (Forest and mountain biomes)
+    Boulder,
+    /// A tapering pillar of stone (mountain biomes)
+    RockSpire,
 }
 
-/// Represents a request for flora generation to be fulfilled in a target chunk.
-/// This is created when a chunk's top layer (y = CHUNK_SIZE - 1) has a valid
-/// surface block and rolls successfully for flora generation.
+/// Flora chosen while generating the terrain of a chunk, grown by the decoration
+/// pass once the neighbouring chunks it may reach into are generated too
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FloraRequest {
-    /// The local x position within the target chunk.
-    /// Valid range: 0 to CHUNK_SIZE - 1 (uses i32 for consistency with chunk coordinate types)
-    pub local_x: i32,
-    /// The local z position within the target chunk.
-    /// Valid range: 0 to CHUNK_SIZE - 1 (uses i32 for consistency with chunk coordinate types)
-    pub local_z: i32,
+pub struct FloraSite {
+    /// Position of the base of the flora, right above the ground, local to the
+    /// chunk. Its y is `CHUNK_SIZE` when the ground is the top layer of the chunk.
+    pub position: IVec3,
     /// The type of flora to generate
     pub flora_type: FloraType,
     /// The biome type where this flora should be generated
     pub biome_type: BiomeType,
 }
 
+/// Blocks of a structure to place in a target chunk once it is generated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StructureRequest {
+    /// Positions local to the target chunk, `None` carving air
+    pub blocks: Vec<(IVec3, Option<BlockData>)>,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct ServerItemStack {
     pub id: u128,
@@ -74,26 +112,83 @@ pub struct ServerChunk {
 #[derive(Resource, Default, Clone, Serialize, Deserialize, Debug)]
 pub struct ServerWorldMap {
     pub name: String,
+    /// Chunks of the overworld
     pub chunks: ServerChunkWorldMap,
+    /// Chunks of the other dimensions, see `dimensions`
+    #[serde(default)]
+    pub dimensions: HashMap<DimensionId, ServerChunkWorldMap>,
     pub players: HashMap<PlayerId, Player>,
     pub mobs: HashMap<MobId, ServerMob>,
     pub item_stacks: Vec<ServerItemStack>,
     pub time: u64,
+    pub waystones: WaystoneRegistry,
+    pub item_frames: ItemFrameRegistry,
+    pub difficulty: Difficulty,
+    /// Boats on the water, without the ones players ride
+    #[serde(default)]
+    pub boats: HashMap<BoatId, ServerBoat>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize, Debug)]
 pub struct ServerChunkWorldMap {
     pub map: HashMap<IVec3, ServerChunk>,
     pub chunks_to_update: Vec<IVec3>,
-    /// Pending flora generation requests, keyed by the target chunk position.
-    /// When a chunk is generated, it checks this map for any pending requests
-    /// and processes them before generating its own flora.
-    pub generation_requests: HashMap<IVec3, Vec<FloraRequest>>,
+    /// Flora of the chunks waiting for the decoration pass, which runs once all
+    /// the neighbours of the chunk are generated
+    pub pending_flora: HashMap<IVec3, Vec<FloraSite>>,
+    /// Parts of structures waiting for their chunk to be generated, keyed by that chunk
+    pub structure_requests: HashMap<IVec3, Vec<StructureRequest>>,
+    /// Fluid added (positive) or removed (negative) by explicit block edits, drained
+    /// by the water volume audit which treats them as sources and sinks
+    #[serde(skip)]
+    pub fluid_edits: Vec<(IVec3, FluidKind, f32)>,
+    /// Blocks set or removed by explicit edits, drained by the fluid flow which
+    /// wakes the fluids around them up
+    #[serde(skip)]
+    pub block_edits: Vec<IVec3>,
+    /// Timestamp of each chunk as last written to disk, so that saves skip the
+    /// chunks which weren't updated since. Chunks unloaded by the server keep
+    /// theirs while they are out of `map`.
+    #[serde(skip)]
+    pub saved_ts: HashMap<IVec3, u64>,
 }
 
-#[derive(Resource, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Resource, Clone, Copy, Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
 pub struct WorldSeed(pub u32);
 
+/// Ground block of the world spawn, see `find_world_spawn`. Chosen by the server
+/// and sent to the clients when they join.
+#[derive(Resource, Clone, Copy, Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct WorldSpawn(pub IVec3);
+
+impl WorldSpawn {
+    /// Center of a player of the given height standing on the world spawn
+    pub fn standing_position(&self, player_height: f32) -> Vec3 {
+        self.0.as_vec3() + Vec3::new(0.5, 1.0 + player_height / 2.0, 0.5)
+    }
+}
+
+impl WorldSeed {
+    /// Seed typed by a player: numbers are used as they are, negative ones
+    /// wrapping around, and any other text is hashed. An empty input leaves the
+    /// seed to chance.
+    pub fn from_input(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        if let Ok(seed) = input.parse::<i64>() {
+            return Some(Self(seed as u32));
+        }
+        // FNV-1a, which unlike the hasher of the standard library gives the same
+        // seed on every build
+        let hash = input.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+        Some(Self(hash))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, Default, PartialEq)]
 pub struct ItemStack {
     pub item_id: ItemId,
@@ -110,6 +205,8 @@ pub enum BiomeType {
     Desert,
     IcePlain,
     FlowerPlains,
+    Swamp,
+    MushroomFields,
     ShallowOcean,
     Ocean,
     DeepOcean,
@@ -126,6 +223,8 @@ impl BiomeType {
             BiomeType::Desert => "Desert",
             BiomeType::IcePlain => "Ice Plain",
             BiomeType::FlowerPlains => "Flower Plains",
+            BiomeType::Swamp => "Swamp",
+            BiomeType::MushroomFields => "Mushroom Fields",
             BiomeType::ShallowOcean => "Shallow Ocean",
             BiomeType::Ocean => "Ocean",
             BiomeType::DeepOcean => "Deep Ocean",
@@ -148,6 +247,9 @@ impl BiomeType {
         const LAND_HUMID_THRESHOLD: f64 = SHALLOW_OCEAN_THRESHOLD / 2.0;
         const LAND_HIGH_HUMID_THRESHOLD: f64 = 2.0 * SHALLOW_OCEAN_THRESHOLD / 3.0;
         const LAND_MID_HUMID_THRESHOLD: f64 = SHALLOW_OCEAN_THRESHOLD / 3.0;
+        // Swamps and mushroom fields are the wettest lands, along the coasts
+        const COASTAL_THRESHOLD: f64 = SHALLOW_OCEAN_THRESHOLD - 0.07;
+        const TROPICAL_TEMPERATURE: f64 = 0.75;
 
         match (climate.temperature, climate.humidity) {
             // Ocean biomes (determined primarily by humidity)
@@ -156,10 +258,14 @@ impl BiomeType {
             (_, h) if h > SHALLOW_OCEAN_THRESHOLD => BiomeType::ShallowOcean,
 
             // Land biomes - Hot climate (temperature > 0.6)
+            (t, h) if t > TROPICAL_TEMPERATURE && h > COASTAL_THRESHOLD => {
+                BiomeType::MushroomFields
+            }
             (t, h) if t > 0.6 && h > LAND_HUMID_THRESHOLD => BiomeType::Forest,
             (t, _) if t > 0.6 => BiomeType::Desert,
 
             // Land biomes - Temperate climate (0.3 < temperature <= 0.6)
+            (t, h) if t > 0.3 && h > COASTAL_THRESHOLD => BiomeType::Swamp,
             (t, h) if t > 0.3 && h > LAND_HIGH_HUMID_THRESHOLD => BiomeType::FlowerPlains,
             (t, h) if t > 0.3 && h > LAND_MID_HUMID_THRESHOLD => BiomeType::Plains,
             (t, _) if t > 0.3 => BiomeType::MediumMountain,
@@ -181,8 +287,29 @@ pub struct Biome {
     pub biome_type: BiomeType,
     pub base_height: i32,
     pub height_variation: i32,
+    /// Height added on the crests of the mountain ranges
+    pub ridge_height: i32,
+    /// How much the terrain is flattened into plateaus, between 0 and 1
+    pub terrace_weight: f64,
     pub surface_block: BlockId,
     pub sub_surface_block: BlockId,
+    pub flora: FloraThresholds,
+}
+
+impl Biome {
+    /// The biome with its ground layered by altitude, for a column whose surface is
+    /// at height `y`: the soil of the biome down in the valleys, bare stone above
+    /// the rock line and snow above the snow line, whatever the climate
+    pub fn at_altitude(mut self, y: i32) -> Self {
+        if y >= SNOW_LINE {
+            self.surface_block = BlockId::Snow;
+            self.sub_surface_block = BlockId::Stone;
+        } else if y >= ROCK_LINE {
+            self.surface_block = BlockId::Stone;
+            self.sub_surface_block = BlockId::Stone;
+        }
+        self
+    }
 }
 
 pub fn get_biome_data(biome_type: BiomeType) -> Biome {
@@ -191,71 +318,171 @@ pub fn get_biome_data(biome_type: BiomeType) -> Biome {
             biome_type: BiomeType::Plains,
             base_height: 64,
             height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Grass,
             sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                flower: 0.02,
+                tall_grass: 0.1,
+                ..Default::default()
+            },
         },
         BiomeType::Forest => Biome {
             biome_type: BiomeType::Forest,
             base_height: 64,
             height_variation: 2,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Grass,
             sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                flower: 0.02,
+                tall_grass: 0.1,
+                tree: 0.06,
+                boulder: 0.001,
+                ..Default::default()
+            },
         },
         BiomeType::MediumMountain => Biome {
             biome_type: BiomeType::MediumMountain,
             base_height: 70,
             height_variation: 4,
+            ridge_height: 12,
+            terrace_weight: 0.6,
             surface_block: BlockId::Grass,
             sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                flower: 0.02,
+                tall_grass: 0.1,
+                tree: 0.02,
+                boulder: 0.002,
+                rock_spire: 0.0005,
+                ..Default::default()
+            },
         },
         BiomeType::HighMountainGrass => Biome {
             biome_type: BiomeType::HighMountainGrass,
             base_height: 75,
             height_variation: 7,
+            ridge_height: 24,
+            terrace_weight: 0.3,
             surface_block: BlockId::Grass,
             sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                boulder: 0.001,
+                rock_spire: 0.001,
+                ..Default::default()
+            },
         },
         BiomeType::Desert => Biome {
             biome_type: BiomeType::Desert,
             base_height: 64,
             height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Sand,
             sub_surface_block: BlockId::Sand,
+            flora: FloraThresholds {
+                cactus: 0.01,
+                ..Default::default()
+            },
         },
         BiomeType::IcePlain => Biome {
             biome_type: BiomeType::IcePlain,
             base_height: 64,
             height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Snow,
             sub_surface_block: BlockId::Ice,
+            flora: FloraThresholds::default(),
         },
         BiomeType::FlowerPlains => Biome {
             biome_type: BiomeType::FlowerPlains,
             base_height: 64,
             height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Grass,
             sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                flower: 0.1,
+                tall_grass: 0.1,
+                tree: 0.02,
+                ..Default::default()
+            },
+        },
+        // Right at the sea level, so that the lowest ground is flooded
+        BiomeType::Swamp => Biome {
+            biome_type: BiomeType::Swamp,
+            base_height: SEA_LEVEL,
+            height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
+            surface_block: BlockId::SwampGrass,
+            sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                tall_grass: 0.1,
+                tree: 0.03,
+                ..Default::default()
+            },
+        },
+        BiomeType::MushroomFields => Biome {
+            biome_type: BiomeType::MushroomFields,
+            base_height: 66,
+            height_variation: 2,
+            ridge_height: 0,
+            terrace_weight: 0.0,
+            surface_block: BlockId::Mycelium,
+            sub_surface_block: BlockId::Dirt,
+            flora: FloraThresholds {
+                giant_mushroom: 0.005,
+                ..Default::default()
+            },
         },
         BiomeType::ShallowOcean => Biome {
             biome_type: BiomeType::ShallowOcean,
             base_height: 60,
             height_variation: 1,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Sand,
             sub_surface_block: BlockId::Sand,
+            flora: FloraThresholds {
+                tall_grass: 0.1,
+                kelp: 0.01,
+                coral: 0.03,
+                ..Default::default()
+            },
         },
         BiomeType::Ocean => Biome {
-            biome_type: BiomeType::DeepOcean,
+            biome_type: BiomeType::Ocean,
             base_height: 55,
             height_variation: 2,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Sand,
             sub_surface_block: BlockId::Sand,
+            flora: FloraThresholds {
+                tall_grass: 0.1,
+                kelp: 0.03,
+                ..Default::default()
+            },
         },
         BiomeType::DeepOcean => Biome {
             biome_type: BiomeType::DeepOcean,
             base_height: 50,
             height_variation: 3,
+            ridge_height: 0,
+            terrace_weight: 0.0,
             surface_block: BlockId::Sand,
             sub_surface_block: BlockId::Sand,
+            flora: FloraThresholds {
+                tall_grass: 0.1,
+                kelp: 0.05,
+                ..Default::default()
+            },
         },
     }
 }
@@ -269,6 +496,27 @@ pub struct BiomeClimate {
     pub humidity: f64,
 }
 
+/// Temperature at or below which surface water freezes, matching the cold biomes
+pub const FREEZING_TEMPERATURE: f64 = 0.3;
+/// Temperature above which coral grows, matching the hot biomes
+pub const WARM_WATER_TEMPERATURE: f64 = 0.6;
+/// Height from which it is freezing whatever the climate, so that high peaks are
+/// snowy
+pub const SNOW_LINE: i32 = 96;
+/// Height from which the soil gives way to bare stone, up to the snow line
+pub const ROCK_LINE: i32 = 88;
+
+impl BiomeClimate {
+    pub fn is_freezing(&self) -> bool {
+        self.temperature <= FREEZING_TEMPERATURE
+    }
+
+    /// Whether it is freezing at height `y`, under this climate
+    pub fn is_freezing_at(&self, y: i32) -> bool {
+        self.is_freezing() || y >= SNOW_LINE
+    }
+}
+
 /// Calculates the temperature and humidity at a given world position using Perlin noise.
 /// This ensures the client and server use identical noise generation parameters.
 ///
@@ -380,7 +628,7 @@ pub trait WorldMap {
             for y in (hitbox.min.y.floor() as i32)..=(hitbox.max.y.floor() as i32) {
                 for z in (hitbox.min.z.floor() as i32)..=(hitbox.max.z.floor() as i32) {
                     if let Some(block) = self.get_block_by_coordinates(&IVec3::new(x, y, z)) {
-                        match block.id.get_hitbox() {
+                        match block.get_collision_hitbox() {
                             BlockHitbox::FullBlock => return true,
                             BlockHitbox::None => {
                                 // Check if this is a water block and if we should check dynamic surface
@@ -400,8 +648,9 @@ pub trait WorldMap {
                                 continue;
                             }
                             BlockHitbox::Aabb(block_hitbox) => {
-                                let min = hitbox.min.max(block_hitbox.min);
-                                let max = hitbox.max.min(block_hitbox.max);
+                                let offset = IVec3::new(x, y, z).as_vec3a();
+                                let min = hitbox.min.max(block_hitbox.min + offset);
+                                let max = hitbox.max.min(block_hitbox.max + offset);
 
                                 if min == max.min(min) {
                                     return true;
@@ -454,7 +703,7 @@ impl WorldMap for ServerChunkWorldMap {
     }
 
     fn remove_block_by_coordinates(&mut self, global_block_pos: &IVec3) -> Option<BlockData> {
-        info!("Trying to remove block at pos {:?}", global_block_pos);
+        debug!("Trying to remove block at pos {:?}", global_block_pos);
         let block: &BlockData = self.get_block_by_coordinates(global_block_pos)?;
         let kind: BlockData = *block;
 
@@ -463,6 +712,11 @@ impl WorldMap for ServerChunkWorldMap {
 
         chunk_map.map.remove(&local_block_pos);
         self.chunks_to_update.push(chunk_pos);
+        self.block_edits.push(*global_block_pos);
+
+        if let Some((fluid, volume)) = kind.id.stored_fluid() {
+            self.fluid_edits.push((*global_block_pos, fluid, -volume));
+        }
 
         Some(kind)
     }
@@ -471,8 +725,19 @@ impl WorldMap for ServerChunkWorldMap {
         let (chunk_pos, local_pos) = global_to_chunk_local(position);
         let chunk: &mut ServerChunk = self.map.entry(chunk_pos).or_default();
 
-        chunk.map.insert(local_pos, block);
+        let replaced = chunk.map.insert(local_pos, block);
         self.chunks_to_update.push(chunk_pos);
+        self.block_edits.push(*position);
+
+        // Replacing water with lava removes the one and adds the other
+        for fluid in [FluidKind::Water, FluidKind::Lava] {
+            let previous_volume =
+                replaced.map_or(0.0, |replaced| replaced.id.stored_fluid_volume(fluid));
+            let volume_change = block.id.stored_fluid_volume(fluid) - previous_volume;
+            if volume_change != 0.0 {
+                self.fluid_edits.push((*position, fluid, volume_change));
+            }
+        }
     }
 
     fn mark_block_for_update(&mut self, position: &IVec3) {
@@ -494,6 +759,7 @@ pub trait GameElementId: std::hash::Hash + Eq + PartialEq + Copy + Clone + Defau
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::world::BlockDirection;
 
     #[test]
     fn sent_to_clients_deduplicates_players() {
@@ -504,6 +770,46 @@ mod tests {
         assert_eq!(chunk.sent_to_clients.len(), 1);
     }
 
+    #[test]
+    fn block_edits_record_the_volume_of_each_fluid() {
+        let mut world_map = ServerChunkWorldMap::default();
+        let position = IVec3::new(3, 20, -7);
+        world_map.set_block(
+            &position,
+            BlockData::new(BlockId::Water, BlockDirection::Front),
+        );
+        world_map.set_block(
+            &position,
+            BlockData::new(BlockId::Lava, BlockDirection::Front),
+        );
+        world_map.remove_block_by_coordinates(&position);
+
+        assert_eq!(
+            world_map.fluid_edits,
+            vec![
+                (position, FluidKind::Water, 1.0),
+                (position, FluidKind::Water, -1.0),
+                (position, FluidKind::Lava, 1.0),
+                (position, FluidKind::Lava, -1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn typed_seeds_are_numbers_or_hashed_text() {
+        assert_eq!(WorldSeed::from_input("  "), None);
+        assert_eq!(WorldSeed::from_input("1234"), Some(WorldSeed(1234)));
+        assert_eq!(WorldSeed::from_input("-1"), Some(WorldSeed(u32::MAX)));
+        assert_eq!(
+            WorldSeed::from_input("rustcraft"),
+            WorldSeed::from_input(" rustcraft ")
+        );
+        assert_ne!(
+            WorldSeed::from_input("rustcraft"),
+            WorldSeed::from_input("Rustcraft")
+        );
+    }
+
     #[test]
     fn calculate_temperature_humidity_is_deterministic_and_bounded() {
         let first = calculate_temperature_humidity(10, -5, 123);
@@ -515,4 +821,53 @@ mod tests {
         assert!((first.temperature - second.temperature).abs() < f32::EPSILON as f64);
         assert!((first.humidity - second.humidity).abs() < f32::EPSILON as f64);
     }
+
+    #[test]
+    fn mountains_turn_to_stone_then_snow_with_altitude() {
+        let mountain = get_biome_data(BiomeType::HighMountainGrass);
+        let surface = |y| mountain.at_altitude(y).surface_block;
+        assert_eq!(surface(ROCK_LINE - 1), BlockId::Grass);
+        assert_eq!(surface(ROCK_LINE), BlockId::Stone);
+        assert_eq!(surface(SNOW_LINE - 1), BlockId::Stone);
+        assert_eq!(surface(SNOW_LINE), BlockId::Snow);
+        assert_eq!(
+            mountain.at_altitude(SNOW_LINE).sub_surface_block,
+            BlockId::Stone
+        );
+    }
+
+    #[test]
+    fn peaks_above_the_snow_line_are_freezing() {
+        let warm = BiomeClimate {
+            temperature: 0.8,
+            humidity: 0.2,
+        };
+        assert!(!warm.is_freezing_at(SNOW_LINE - 1));
+        assert!(warm.is_freezing_at(SNOW_LINE));
+
+        let cold = BiomeClimate {
+            temperature: FREEZING_TEMPERATURE,
+            ..warm
+        };
+        assert!(cold.is_freezing_at(0));
+    }
+
+    #[test]
+    fn wet_coasts_are_swamps_or_mushroom_fields() {
+        let coast = |temperature| {
+            BiomeType::from_climate(BiomeClimate {
+                temperature,
+                humidity: 0.62,
+            })
+        };
+        assert_eq!(coast(0.5), BiomeType::Swamp);
+        assert_eq!(coast(0.9), BiomeType::MushroomFields);
+        assert_eq!(coast(0.2), BiomeType::IcePlain);
+
+        let inland = BiomeClimate {
+            temperature: 0.5,
+            humidity: 0.5,
+        };
+        assert_eq!(BiomeType::from_climate(inland), BiomeType::FlowerPlains);
+    }
 }
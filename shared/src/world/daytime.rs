@@ -0,0 +1,48 @@
+//! Day and night cycle, derived from the server tick.
+//!
+//! The sun rises at half of the day, and sets at its end: the first half of each
+//! day is the night.
+
+pub const DAY_DURATION_IN_TICKS: u64 = 20 * 60; // 20 ticks per second * 60 seconds = 1 minute
+
+/// Time of the day at which players wake up after sleeping, just after sunrise
+const MORNING: f32 = 0.52;
+
+/// Time elapsed in the current day, between 0 and 1
+pub fn day_progress(tick: u64) -> f32 {
+    (tick % DAY_DURATION_IN_TICKS) as f32 / DAY_DURATION_IN_TICKS as f32
+}
+
+pub fn is_night(tick: u64) -> bool {
+    day_progress(tick) < 0.5
+}
+
+/// First tick of the next morning after `tick`
+pub fn next_morning(tick: u64) -> u64 {
+    let day_start = tick - tick % DAY_DURATION_IN_TICKS;
+    let morning = day_start + (DAY_DURATION_IN_TICKS as f32 * MORNING) as u64;
+    if morning > tick {
+        morning
+    } else {
+        morning + DAY_DURATION_IN_TICKS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nights_end_in_the_morning() {
+        let midnight = 3 * DAY_DURATION_IN_TICKS + DAY_DURATION_IN_TICKS / 4;
+        assert!(is_night(midnight));
+
+        let morning = next_morning(midnight);
+        assert!(!is_night(morning));
+        assert!(morning > midnight);
+        assert!(morning - midnight < DAY_DURATION_IN_TICKS);
+
+        // Already morning, the next one is a day later
+        assert_eq!(next_morning(morning), morning + DAY_DURATION_IN_TICKS);
+    }
+}
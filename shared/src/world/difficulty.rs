@@ -0,0 +1,73 @@
+//! Per-world difficulty, saved with the world and changed with `/difficulty <name>`.
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the chat command changing the difficulty, e.g. `/difficulty hard`
+pub const DIFFICULTY_COMMAND: &str = "/difficulty";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Peaceful,
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses `/difficulty <name>`
+pub fn parse_difficulty_command(message: &str) -> Option<Difficulty> {
+    let mut words = message.split_whitespace();
+    if words.next()? != DIFFICULTY_COMMAND {
+        return None;
+    }
+    let name = words.next()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Difficulty::from_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_difficulty_commands() {
+        assert_eq!(
+            parse_difficulty_command("/difficulty hard"),
+            Some(Difficulty::Hard)
+        );
+        assert_eq!(
+            parse_difficulty_command(" /difficulty  Peaceful "),
+            Some(Difficulty::Peaceful)
+        );
+        assert_eq!(parse_difficulty_command("/difficulty"), None);
+        assert_eq!(parse_difficulty_command("/difficulty insane"), None);
+        assert_eq!(parse_difficulty_command("/difficulty easy now"), None);
+        assert_eq!(parse_difficulty_command("difficulty easy"), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Dimensions of a world, each with chunks of its own.
+//!
+//! Players join in the overworld and reach the nether by walking into a portal.
+//! The nether is a cavern closed by two layers of bedrock, with a sea of lava at
+//! its bottom. Horizontal distances are eight times shorter there, so that a short
+//! walk in the nether leads far away in the overworld.
+//!
+//! The chunks of the overworld stay in `ServerWorldMap::chunks`, the ones of the
+//! other dimensions are kept in `ServerWorldMap::dimensions` and saved in their own
+//! folder of the world.
+
+use std::collections::HashMap;
+
+use bevy::math::{IVec3, Vec3};
+use noiz::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::players::Player;
+use crate::TICKS_PER_SECOND;
+
+use super::{BlockId, ServerChunkWorldMap, ServerWorldMap, WorldMap, NETHER_SEED_OFFSET};
+
+/// Horizontal distances of the overworld are this many times longer than in the nether
+pub const NETHER_SCALE: f32 = 8.0;
+/// Height of the bedrock ceiling of the nether, its floor being at 0
+pub const NETHER_CEILING: i32 = 127;
+/// Surface of the sea of lava at the bottom of the nether
+pub const NETHER_LAVA_LEVEL: i32 = 31;
+/// Players can't go through a portal again before this many ticks
+pub const PORTAL_COOLDOWN_TICKS: u64 = 5 * TICKS_PER_SECOND;
+
+/// Frequency of the density noise of the nether, stretched twice as wide as tall
+const NETHER_DENSITY_SCALE: f32 = 0.04;
+/// Blocks over which the rock thickens towards the floor and the ceiling
+const NETHER_SHELL_THICKNESS: f32 = 24.0;
+/// Rock is where the density is above this, higher values give wider caverns
+const NETHER_SOLID_THRESHOLD: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum DimensionId {
+    #[default]
+    Overworld,
+    Nether,
+}
+
+impl DimensionId {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DimensionId::Overworld => "overworld",
+            DimensionId::Nether => "nether",
+        }
+    }
+
+    /// Dimension the portals of this one lead to
+    pub fn portal_destination(&self) -> DimensionId {
+        match self {
+            DimensionId::Overworld => DimensionId::Nether,
+            DimensionId::Nether => DimensionId::Overworld,
+        }
+    }
+
+    /// Where a player going through a portal at `position` of this dimension
+    /// arrives in the destination, before looking for room to stand there
+    pub fn portal_arrival(&self, position: Vec3) -> Vec3 {
+        match self {
+            DimensionId::Overworld => Vec3::new(
+                position.x / NETHER_SCALE,
+                position
+                    .y
+                    .clamp((NETHER_LAVA_LEVEL + 1) as f32, (NETHER_CEILING - 8) as f32),
+                position.z / NETHER_SCALE,
+            ),
+            DimensionId::Nether => Vec3::new(
+                position.x * NETHER_SCALE,
+                position.y,
+                position.z * NETHER_SCALE,
+            ),
+        }
+    }
+
+    /// Folder of the world holding the chunks of this dimension, the overworld
+    /// keeping them at the root of the world for older saves
+    pub fn save_folder(&self) -> Option<&'static str> {
+        match self {
+            DimensionId::Overworld => None,
+            DimensionId::Nether => Some("nether"),
+        }
+    }
+
+    /// Every dimension other than the overworld
+    pub fn others() -> [DimensionId; 1] {
+        [DimensionId::Nether]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        std::iter::once(DimensionId::Overworld)
+            .chain(Self::others())
+            .find(|dimension| dimension.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Whether the body of a player, grown by `margin` on every side, reaches into a
+/// portal block
+pub fn is_in_portal(world_map: &impl WorldMap, player: &Player, margin: f32) -> bool {
+    let half_extents = Vec3::new(player.width, player.height, player.width) / 2.0 + margin;
+    let min = (player.position - half_extents).floor().as_ivec3();
+    let max = (player.position + half_extents).floor().as_ivec3();
+    (min.x..=max.x).any(|x| {
+        (min.y..=max.y).any(|y| {
+            (min.z..=max.z).any(|z| {
+                world_map
+                    .get_block_by_coordinates(&IVec3::new(x, y, z))
+                    .is_some_and(|block| block.id == BlockId::Portal)
+            })
+        })
+    })
+}
+
+/// Chunks of a dimension, borrowed from the fields of a `ServerWorldMap` so that
+/// its players can be borrowed at the same time
+pub fn dimension_chunks_mut<'a>(
+    overworld: &'a mut ServerChunkWorldMap,
+    dimensions: &'a mut HashMap<DimensionId, ServerChunkWorldMap>,
+    dimension: DimensionId,
+) -> &'a mut ServerChunkWorldMap {
+    match dimension {
+        DimensionId::Overworld => overworld,
+        _ => dimensions.entry(dimension).or_default(),
+    }
+}
+
+impl ServerWorldMap {
+    /// Chunks of a dimension, none if nobody went there yet
+    pub fn dimension_chunks(&self, dimension: DimensionId) -> Option<&ServerChunkWorldMap> {
+        match dimension {
+            DimensionId::Overworld => Some(&self.chunks),
+            _ => self.dimensions.get(&dimension),
+        }
+    }
+
+    pub fn dimension_chunks_mut(&mut self, dimension: DimensionId) -> &mut ServerChunkWorldMap {
+        dimension_chunks_mut(&mut self.chunks, &mut self.dimensions, dimension)
+    }
+
+    /// Chunks of every dimension, the overworld first
+    pub fn all_dimension_chunks_mut(&mut self) -> impl Iterator<Item = &mut ServerChunkWorldMap> {
+        std::iter::once(&mut self.chunks).chain(self.dimensions.values_mut())
+    }
+
+    /// Players currently in a dimension
+    pub fn players_in(&self, dimension: DimensionId) -> impl Iterator<Item = &Player> {
+        self.players
+            .values()
+            .filter(move |player| player.dimension == dimension)
+    }
+}
+
+/// Terrain of the nether, shared like `TerrainNoise` so that both ends agree on it
+pub struct NetherNoise {
+    density: Noise<common_noise::Perlin>,
+}
+
+impl NetherNoise {
+    pub fn new(seed: u32) -> Self {
+        let mut density = Noise::<common_noise::Perlin>::default();
+        density.set_seed(seed + NETHER_SEED_OFFSET);
+        Self { density }
+    }
+
+    /// Block generated at `x`, `y`, `z`, none for the air of the caverns
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockId> {
+        if y <= 0 || y >= NETHER_CEILING {
+            return (y == 0 || y == NETHER_CEILING).then_some(BlockId::Bedrock);
+        }
+
+        // The rock closes in on the floor and the ceiling
+        let from_shell = y.min(NETHER_CEILING - y) as f32;
+        let shell = (1.0 - from_shell / NETHER_SHELL_THICKNESS).max(0.0);
+        let sample_pos = Vec3::new(x as f32, 2.0 * y as f32, z as f32) * NETHER_DENSITY_SCALE;
+        let density = self.density.sample_for::<f32>(sample_pos) + shell;
+
+        if density > NETHER_SOLID_THRESHOLD {
+            Some(BlockId::Netherrack)
+        } else if y <= NETHER_LAVA_LEVEL {
+            Some(BlockId::Lava)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portals_lead_back_where_they_came_from() {
+        let position = Vec3::new(800.0, 70.0, -240.0);
+        let nether = DimensionId::Overworld.portal_arrival(position);
+        assert_eq!(nether, Vec3::new(100.0, 70.0, -30.0));
+        assert_eq!(DimensionId::Nether.portal_arrival(nether), position);
+        assert_eq!(
+            DimensionId::Overworld
+                .portal_destination()
+                .portal_destination(),
+            DimensionId::Overworld
+        );
+    }
+
+    #[test]
+    fn dimensions_are_found_by_name() {
+        assert_eq!(DimensionId::from_name("nether"), Some(DimensionId::Nether));
+        assert_eq!(
+            DimensionId::from_name("Overworld"),
+            Some(DimensionId::Overworld)
+        );
+        assert_eq!(DimensionId::from_name("end"), None);
+    }
+
+    #[test]
+    fn nether_is_a_closed_cavern() {
+        let nether = NetherNoise::new(7);
+        let (mut blocks, mut air) = (0, 0);
+        for x in (-64..64).step_by(4) {
+            for z in (-64..64).step_by(4) {
+                assert_eq!(nether.block_at(x, 0, z), Some(BlockId::Bedrock));
+                assert_eq!(
+                    nether.block_at(x, NETHER_CEILING, z),
+                    Some(BlockId::Bedrock)
+                );
+                assert_eq!(nether.block_at(x, NETHER_CEILING + 1, z), None);
+                for y in 1..NETHER_CEILING {
+                    let block = nether.block_at(x, y, z);
+                    if y <= NETHER_LAVA_LEVEL {
+                        assert!(block.is_some());
+                    }
+                    blocks += 1;
+                    if block.is_none() {
+                        air += 1;
+                    }
+                }
+            }
+        }
+        assert!(air * 10 > blocks, "{air} of {blocks} blocks are air");
+        assert!(air * 10 < blocks * 9, "{air} of {blocks} blocks are air");
+    }
+}
@@ -0,0 +1,214 @@
+//! Status effects applied to players and mobs, e.g. by drinking potions.
+//!
+//! Effects last a number of ticks and have an amplifier, 0 being level I. They
+//! are ticked by the server: speed and slowness scale the movement speed, while
+//! poison and regeneration periodically change health. Poison never kills.
+
+use serde::{Deserialize, Serialize};
+
+use crate::TICKS_PER_SECOND;
+
+use super::ItemId;
+
+const SPEED_BONUS_PER_LEVEL: f32 = 0.2;
+const SLOWNESS_MALUS_PER_LEVEL: f32 = 0.15;
+/// Ticks between two poison damages at level I, halved by each level above
+const POISON_INTERVAL_TICKS: u64 = 25;
+/// Ticks between two regeneration heals at level I, halved by each level above
+const REGENERATION_INTERVAL_TICKS: u64 = 50;
+/// Health lost or gained on each poison or regeneration tick
+const HEALTH_PER_PULSE: f32 = 1.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffectKind {
+    Speed,
+    Slowness,
+    Poison,
+    Regeneration,
+}
+
+impl StatusEffectKind {
+    pub const ALL: [StatusEffectKind; 4] = [
+        StatusEffectKind::Speed,
+        StatusEffectKind::Slowness,
+        StatusEffectKind::Poison,
+        StatusEffectKind::Regeneration,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusEffectKind::Speed => "Speed",
+            StatusEffectKind::Slowness => "Slowness",
+            StatusEffectKind::Poison => "Poison",
+            StatusEffectKind::Regeneration => "Regeneration",
+        }
+    }
+
+    /// Potion applying this effect, its icon is also the one of the effect
+    pub fn potion(&self) -> ItemId {
+        match self {
+            StatusEffectKind::Speed => ItemId::SpeedPotion,
+            StatusEffectKind::Slowness => ItemId::SlownessPotion,
+            StatusEffectKind::Poison => ItemId::PoisonPotion,
+            StatusEffectKind::Regeneration => ItemId::RegenerationPotion,
+        }
+    }
+
+    /// How long the effect of a potion lasts
+    pub fn potion_duration_ticks(&self) -> u64 {
+        match self {
+            StatusEffectKind::Speed | StatusEffectKind::Slowness => 60 * TICKS_PER_SECOND,
+            StatusEffectKind::Poison | StatusEffectKind::Regeneration => 20 * TICKS_PER_SECOND,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// Strength of the effect, 0 being level I
+    pub amplifier: u8,
+    pub remaining_ticks: u64,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusEffectKind, amplifier: u8, duration_ticks: u64) -> Self {
+        Self {
+            kind,
+            amplifier,
+            remaining_ticks: duration_ticks,
+        }
+    }
+
+    /// Level shown to players, e.g. 2 for Speed II
+    pub fn level(&self) -> u32 {
+        self.amplifier as u32 + 1
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        self.remaining_ticks as f32 / TICKS_PER_SECOND as f32
+    }
+
+    /// Whether a periodic effect triggers on the current tick
+    fn pulses(&self, interval_ticks: u64) -> bool {
+        let interval = (interval_ticks >> self.amplifier.min(4)).max(1);
+        self.remaining_ticks.is_multiple_of(interval)
+    }
+}
+
+/// Effects currently applied to a player or a mob, at most one of each kind
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, kind: StatusEffectKind) -> Option<&StatusEffect> {
+        self.0.iter().find(|effect| effect.kind == kind)
+    }
+
+    /// Applies `effect`. If the entity already has an effect of this kind, the
+    /// stronger one wins, and the longer one on equal strength.
+    pub fn add(&mut self, effect: StatusEffect) {
+        let Some(existing) = self.0.iter_mut().find(|other| other.kind == effect.kind) else {
+            self.0.push(effect);
+            return;
+        };
+        if effect.amplifier > existing.amplifier
+            || (effect.amplifier == existing.amplifier
+                && effect.remaining_ticks > existing.remaining_ticks)
+        {
+            *existing = effect;
+        }
+    }
+
+    /// Factor applied to the movement speed
+    pub fn speed_multiplier(&self) -> f32 {
+        let speed = self
+            .get(StatusEffectKind::Speed)
+            .map(|effect| 1.0 + SPEED_BONUS_PER_LEVEL * effect.level() as f32)
+            .unwrap_or(1.0);
+        let slowness = self
+            .get(StatusEffectKind::Slowness)
+            .map(|effect| (1.0 - SLOWNESS_MALUS_PER_LEVEL * effect.level() as f32).max(0.0))
+            .unwrap_or(1.0);
+        speed * slowness
+    }
+
+    /// Advances the effects by one tick, dropping the ones that ran out.
+    /// Returns the health gained this tick, negative when poisoned.
+    pub fn tick(&mut self) -> f32 {
+        let mut health_change = 0.0;
+        for effect in self.0.iter_mut() {
+            effect.remaining_ticks = effect.remaining_ticks.saturating_sub(1);
+            match effect.kind {
+                StatusEffectKind::Poison if effect.pulses(POISON_INTERVAL_TICKS) => {
+                    health_change -= HEALTH_PER_PULSE;
+                }
+                StatusEffectKind::Regeneration if effect.pulses(REGENERATION_INTERVAL_TICKS) => {
+                    health_change += HEALTH_PER_PULSE;
+                }
+                _ => {}
+            }
+        }
+        self.0.retain(|effect| effect.remaining_ticks > 0);
+        health_change
+    }
+}
+
+/// Health after gaining `change`, capped to `max_health`. Losses from effects
+/// never take the last point of health.
+pub fn apply_effect_health_change(health: f32, change: f32, max_health: f32) -> f32 {
+    if change < 0.0 {
+        (health + change).max(health.min(1.0))
+    } else {
+        (health + change).min(max_health.max(health))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stronger_or_longer_effects_replace_existing_ones() {
+        let mut effects = StatusEffects::default();
+        effects.add(StatusEffect::new(StatusEffectKind::Speed, 1, 100));
+        effects.add(StatusEffect::new(StatusEffectKind::Speed, 0, 400));
+        assert_eq!(effects.0.len(), 1);
+        assert_eq!(effects.get(StatusEffectKind::Speed).unwrap().amplifier, 1);
+
+        effects.add(StatusEffect::new(StatusEffectKind::Speed, 1, 200));
+        assert_eq!(
+            effects
+                .get(StatusEffectKind::Speed)
+                .unwrap()
+                .remaining_ticks,
+            200
+        );
+
+        effects.add(StatusEffect::new(StatusEffectKind::Slowness, 0, 200));
+        assert!((effects.speed_multiplier() - 1.4 * 0.85).abs() < 1e-5);
+    }
+
+    #[test]
+    fn poison_hurts_until_it_expires_without_killing() {
+        let mut effects = StatusEffects::default();
+        effects.add(StatusEffect::new(
+            StatusEffectKind::Poison,
+            0,
+            4 * POISON_INTERVAL_TICKS,
+        ));
+
+        let mut health = 3.0;
+        while !effects.is_empty() {
+            health = apply_effect_health_change(health, effects.tick(), 20.0);
+        }
+        assert_eq!(health, 1.0);
+        assert_eq!(effects.tick(), 0.0);
+
+        assert_eq!(apply_effect_health_change(19.5, 1.0, 20.0), 20.0);
+    }
+}
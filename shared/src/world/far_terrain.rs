@@ -0,0 +1,154 @@
+//! Coarse summaries of the terrain beyond the full detail render distance.
+//!
+//! The server can't stream every chunk up to the horizon, so for a ring of chunk
+//! columns around each player it sends a [`FarTerrainTile`] instead: the height
+//! and surface block of a few samples per column, read from the generation noise
+//! without generating the chunks. The client draws them as a low poly landscape
+//! and hides the tiles that full chunks cover.
+
+use bevy::math::IVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{CHUNK_SIZE, SEA_LEVEL};
+
+use super::{
+    biome_for_climate, calculate_temperature_humidity_with_noises, BlockId, ClimateNoises,
+    TerrainNoise,
+};
+
+/// Samples along each side of a tile
+pub const FAR_TERRAIN_SAMPLES: i32 = 4;
+/// Blocks along the side of a sample
+pub const FAR_TERRAIN_SAMPLE_SIZE: i32 = CHUNK_SIZE / FAR_TERRAIN_SAMPLES;
+
+/// Summary of a column of chunks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FarTerrainTile {
+    /// Position of the column, in chunks along `x` and `z`
+    pub column: IVec2,
+    /// Height of the top block of each sample, row by row along `x`
+    pub heights: Vec<i16>,
+    /// Top block of each sample, in the same order as `heights`
+    pub blocks: Vec<BlockId>,
+}
+
+impl FarTerrainTile {
+    /// Samples the generated terrain of `column`, seas being flat water
+    pub fn generate(
+        column: IVec2,
+        terrain: &mut TerrainNoise,
+        climate: &mut ClimateNoises,
+    ) -> Self {
+        let count = (FAR_TERRAIN_SAMPLES * FAR_TERRAIN_SAMPLES) as usize;
+        let mut heights = Vec::with_capacity(count);
+        let mut blocks = Vec::with_capacity(count);
+        for sz in 0..FAR_TERRAIN_SAMPLES {
+            for sx in 0..FAR_TERRAIN_SAMPLES {
+                let x = column.x * CHUNK_SIZE
+                    + sx * FAR_TERRAIN_SAMPLE_SIZE
+                    + FAR_TERRAIN_SAMPLE_SIZE / 2;
+                let z = column.y * CHUNK_SIZE
+                    + sz * FAR_TERRAIN_SAMPLE_SIZE
+                    + FAR_TERRAIN_SAMPLE_SIZE / 2;
+                let height = terrain.height(x, z);
+                let (height, block) = if height < SEA_LEVEL {
+                    (SEA_LEVEL, BlockId::Water)
+                } else {
+                    let climate = calculate_temperature_humidity_with_noises(x, z, climate);
+                    (
+                        height,
+                        biome_for_climate(climate).at_altitude(height).surface_block,
+                    )
+                };
+                heights.push(height as i16);
+                blocks.push(block);
+            }
+        }
+        Self {
+            column,
+            heights,
+            blocks,
+        }
+    }
+
+    /// Height and block of the sample at `(sx, sz)`, clamped to the tile
+    pub fn sample(&self, sx: i32, sz: i32) -> (i32, BlockId) {
+        let sx = sx.clamp(0, FAR_TERRAIN_SAMPLES - 1);
+        let sz = sz.clamp(0, FAR_TERRAIN_SAMPLES - 1);
+        let index = (sz * FAR_TERRAIN_SAMPLES + sx) as usize;
+        (
+            self.heights.get(index).copied().unwrap_or(0) as i32,
+            self.blocks.get(index).copied().unwrap_or_default(),
+        )
+    }
+}
+
+/// Whether `column` is in the far terrain ring of a player standing in `center`,
+/// farther than the full chunks and up to `outer_radius` columns away
+pub fn is_far_terrain_column(
+    center: IVec2,
+    column: IVec2,
+    inner_radius: i32,
+    outer_radius: i32,
+) -> bool {
+    let distance_sq = (column - center).length_squared();
+    distance_sq > inner_radius * inner_radius && distance_sq <= outer_radius * outer_radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::GenerationConfig;
+
+    #[test]
+    fn tiles_follow_the_generated_terrain() {
+        let mut terrain = TerrainNoise::new(5, GenerationConfig::default());
+        let mut climate = ClimateNoises::new(5);
+        let column = IVec2::new(12, -7);
+        let tile = FarTerrainTile::generate(column, &mut terrain, &mut climate);
+        assert_eq!(
+            tile,
+            FarTerrainTile::generate(column, &mut terrain, &mut climate)
+        );
+        assert_eq!(
+            tile.heights.len(),
+            (FAR_TERRAIN_SAMPLES * FAR_TERRAIN_SAMPLES) as usize
+        );
+
+        let (height, block) = tile.sample(1, 2);
+        let x = 12 * CHUNK_SIZE + FAR_TERRAIN_SAMPLE_SIZE + FAR_TERRAIN_SAMPLE_SIZE / 2;
+        let z = -7 * CHUNK_SIZE + 2 * FAR_TERRAIN_SAMPLE_SIZE + FAR_TERRAIN_SAMPLE_SIZE / 2;
+        assert_eq!(height, terrain.height(x, z).max(SEA_LEVEL));
+        assert_eq!(block == BlockId::Water, terrain.height(x, z) < SEA_LEVEL);
+    }
+
+    #[test]
+    fn far_terrain_rings_skip_the_full_chunks() {
+        let center = IVec2::new(3, 3);
+        assert!(!is_far_terrain_column(center, center, 12, 32));
+        assert!(!is_far_terrain_column(
+            center,
+            center + IVec2::new(12, 0),
+            12,
+            32
+        ));
+        assert!(is_far_terrain_column(
+            center,
+            center + IVec2::new(12, 1),
+            12,
+            32
+        ));
+        assert!(is_far_terrain_column(
+            center,
+            center + IVec2::new(0, -32),
+            12,
+            32
+        ));
+        assert!(!is_far_terrain_column(
+            center,
+            center + IVec2::new(23, 23),
+            12,
+            32
+        ));
+    }
+}
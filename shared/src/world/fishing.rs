@@ -0,0 +1,167 @@
+//! Fishing with a rod.
+//!
+//! Using a fishing rod casts a bobber, which flies until it lands in water or on
+//! the ground. Once floating, a fish bites after a random wait: the bobber dips
+//! for a moment, and reeling in during the bite rolls [`FISHING_LOOT`]. Reeling in
+//! at any other time only brings the bobber back. The server simulates the
+//! bobbers and sends them with [`FishingUpdate`](crate::messages::FishingUpdate),
+//! clients put the floating ones on the animated water surface.
+
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockHitbox, BlockId, ItemId, ItemStack, WorldMap};
+use crate::water::WATER_SURFACE_OFFSET;
+
+/// Speed of the bobber when cast, in blocks per second
+pub const FISHING_CAST_SPEED: f32 = 12.0;
+/// The line breaks when the bobber gets further than this from the player
+pub const FISHING_LINE_MAX_LENGTH: f32 = 32.0;
+/// Shortest and longest wait for a bite, in seconds
+pub const FISHING_BITE_WAIT_SECS: (f32, f32) = (5.0, 20.0);
+/// Time left to reel in once a fish bites, in seconds
+pub const FISHING_BITE_WINDOW_SECS: f32 = 1.0;
+const BOBBER_GRAVITY: f32 = -20.0;
+/// Fraction of its velocity the bobber keeps every second while flying
+const BOBBER_AIR_DRAG: f32 = 0.4;
+
+/// What reeling in during a bite can bring up: weight, item and number of items
+pub const FISHING_LOOT: &[(u32, ItemId, u32)] = &[
+    (60, ItemId::RawFish, 1),
+    (20, ItemId::RawFish, 2),
+    (8, ItemId::Lead, 1),
+    (6, ItemId::IronOre, 2),
+    (4, ItemId::GoldOre, 1),
+    (1, ItemId::Diamond, 1),
+    (1, ItemId::RegenerationPotion, 1),
+];
+
+/// Item stack of the loot table at `roll`, taken modulo the total weight
+pub fn roll_fishing_loot(roll: u32) -> ItemStack {
+    let total: u32 = FISHING_LOOT.iter().map(|(weight, ..)| weight).sum();
+    let mut roll = roll % total;
+    let (_, item_id, nb) = FISHING_LOOT
+        .iter()
+        .find(|(weight, ..)| {
+            if roll < *weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })
+        .copied()
+        .unwrap_or(FISHING_LOOT[0]);
+    ItemStack {
+        item_id,
+        item_type: item_id.get_default_type(),
+        nb,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BobberState {
+    Flying,
+    /// Floating on the water block at this height
+    Floating {
+        water_y: i32,
+    },
+    /// Lying on the ground, where no fish can bite
+    Landed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bobber {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub state: BobberState,
+}
+
+impl Bobber {
+    /// Bobber thrown from `origin` towards `direction`
+    pub fn cast(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            position: origin,
+            velocity: direction.normalize_or_zero() * FISHING_CAST_SPEED,
+            state: BobberState::Flying,
+        }
+    }
+
+    /// Moves a flying bobber, until it touches water or the ground
+    pub fn step(&mut self, world_map: &impl WorldMap, delta: f32) {
+        if self.state != BobberState::Flying {
+            return;
+        }
+
+        self.velocity.y += BOBBER_GRAVITY * delta;
+        self.velocity *= BOBBER_AIR_DRAG.powf(delta);
+        let next = self.position + self.velocity * delta;
+
+        let cell = next.floor().as_ivec3();
+        match world_map.get_block_by_coordinates(&cell) {
+            Some(block) if block.id == BlockId::Water => {
+                let water_y = surface_water_block(world_map, cell);
+                self.position = Vec3::new(next.x, water_y as f32 + WATER_SURFACE_OFFSET, next.z);
+                self.velocity = Vec3::ZERO;
+                self.state = BobberState::Floating { water_y };
+            }
+            Some(block) if !matches!(block.get_collision_hitbox(), BlockHitbox::None) => {
+                self.velocity = Vec3::ZERO;
+                self.state = BobberState::Landed;
+            }
+            _ => self.position = next,
+        }
+    }
+}
+
+/// Top water block of the water column going through `cell`
+fn surface_water_block(world_map: &impl WorldMap, cell: IVec3) -> i32 {
+    let mut top = cell;
+    while world_map
+        .get_block_by_coordinates(&(top + IVec3::Y))
+        .is_some_and(|block| block.id == BlockId::Water)
+    {
+        top += IVec3::Y;
+    }
+    top.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockData, BlockDirection, ServerChunkWorldMap};
+
+    #[test]
+    fn bobbers_float_on_the_top_of_the_water() {
+        let mut world = ServerChunkWorldMap::default();
+        for x in 0..16 {
+            for y in 0..3 {
+                world.set_block(
+                    &IVec3::new(x, y, 0),
+                    BlockData::new(BlockId::Water, BlockDirection::Front),
+                );
+            }
+        }
+
+        let mut bobber = Bobber::cast(Vec3::new(0.5, 5.0, 0.5), Vec3::new(1.0, 0.5, 0.0));
+        for _ in 0..200 {
+            bobber.step(&world, 0.05);
+        }
+        assert_eq!(bobber.state, BobberState::Floating { water_y: 2 });
+        assert_eq!(bobber.position.y, 2.0 + WATER_SURFACE_OFFSET);
+        assert!(bobber.position.x > 1.0);
+    }
+
+    #[test]
+    fn loot_rolls_follow_the_weights() {
+        let total: u32 = FISHING_LOOT.iter().map(|(weight, ..)| weight).sum();
+        let fish = (0..total)
+            .filter(|roll| roll_fishing_loot(*roll).item_id == ItemId::RawFish)
+            .count();
+        assert_eq!(fish, 80);
+        assert_eq!(
+            roll_fishing_loot(total - 1).item_id,
+            ItemId::RegenerationPotion
+        );
+        assert_eq!(roll_fishing_loot(total).item_id, ItemId::RawFish);
+    }
+}
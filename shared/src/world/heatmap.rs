@@ -0,0 +1,99 @@
+//! Block activity heatmaps, for operators.
+//!
+//! The server counts the blocks mined and placed by players in every chunk, and
+//! the "grief" ones: blocks broken by someone else than the player who placed
+//! them. Operators ask for a heatmap with `/heatmap <kind>` and get the busiest
+//! chunks, drawn over the world until `/heatmap off`.
+
+use serde::{Deserialize, Serialize};
+
+pub const HEATMAP_COMMAND: &str = "/heatmap";
+/// At most this many chunks are sent in a heatmap, the busiest ones
+pub const HEATMAP_MAX_CHUNKS: usize = 256;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeatmapKind {
+    Mined,
+    Placed,
+    /// Blocks broken by someone else than the player who placed them
+    Grief,
+}
+
+impl HeatmapKind {
+    pub const ALL: [HeatmapKind; 3] = [HeatmapKind::Mined, HeatmapKind::Placed, HeatmapKind::Grief];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeatmapKind::Mined => "mined",
+            HeatmapKind::Placed => "placed",
+            HeatmapKind::Grief => "grief",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Block changes counted in a chunk
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkActivity {
+    pub mined: u32,
+    pub placed: u32,
+    pub grief: u32,
+}
+
+impl ChunkActivity {
+    pub fn count(&self, kind: HeatmapKind) -> u32 {
+        match kind {
+            HeatmapKind::Mined => self.mined,
+            HeatmapKind::Placed => self.placed,
+            HeatmapKind::Grief => self.grief,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapCommand {
+    Show(HeatmapKind),
+    Hide,
+}
+
+/// Parses `/heatmap <mined|placed|grief>` and `/heatmap off`
+pub fn parse_heatmap_command(message: &str) -> Option<HeatmapCommand> {
+    let mut words = message.split_whitespace();
+    if words.next()? != HEATMAP_COMMAND {
+        return None;
+    }
+    let command = match words.next()? {
+        "off" => HeatmapCommand::Hide,
+        name => HeatmapCommand::Show(HeatmapKind::from_name(name)?),
+    };
+    words.next().is_none().then_some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heatmap_commands_name_a_kind_or_turn_it_off() {
+        assert_eq!(
+            parse_heatmap_command("/heatmap grief"),
+            Some(HeatmapCommand::Show(HeatmapKind::Grief))
+        );
+        assert_eq!(
+            parse_heatmap_command("/heatmap Mined"),
+            Some(HeatmapCommand::Show(HeatmapKind::Mined))
+        );
+        assert_eq!(
+            parse_heatmap_command("/heatmap off"),
+            Some(HeatmapCommand::Hide)
+        );
+        assert_eq!(parse_heatmap_command("/heatmap"), None);
+        assert_eq!(parse_heatmap_command("/heatmap lava"), None);
+        assert_eq!(parse_heatmap_command("/heatmap placed twice"), None);
+    }
+}
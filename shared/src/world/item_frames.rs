@@ -0,0 +1,137 @@
+//! Item frames are thin blocks hung on walls that display an item.
+//!
+//! Using an empty frame puts one of the held items in it, using it again turns the
+//! item by an eighth of a turn. Breaking the frame gives back both the frame and
+//! its item. The content of the frames is kept apart from the chunks, like the
+//! waystones, and sent to the players with [`ItemFrameUpdate`](crate::messages::ItemFrameUpdate).
+
+use std::collections::HashMap;
+
+use bevy::math::{bounding::Aabb3d, IVec3, Vec3, Vec3A};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockDirection, BlockHitbox, FaceDirection, ItemId};
+
+/// Number of positions an item can be turned to in its frame
+pub const ITEM_FRAME_ROTATIONS: u8 = 8;
+/// Thickness of the frame against its wall
+pub const ITEM_FRAME_THICKNESS: f32 = 0.0625;
+/// Item frames can't be used from further away than this
+pub const ITEM_FRAME_MAX_DISTANCE: f32 = 5.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ItemFrame {
+    pub item: Option<ItemId>,
+    /// Eighths of a turn the item was turned by
+    pub rotation: u8,
+}
+
+impl ItemFrame {
+    /// Puts `held` in the frame if it is empty, or turns its item otherwise.
+    /// Returns whether the held item was taken.
+    pub fn interact(&mut self, held: Option<ItemId>) -> bool {
+        if self.item.is_some() {
+            self.rotation = (self.rotation + 1) % ITEM_FRAME_ROTATIONS;
+            return false;
+        }
+        self.item = held;
+        self.rotation = 0;
+        held.is_some()
+    }
+
+    /// Angle of the item around the normal of the frame, in radians
+    pub fn angle(&self) -> f32 {
+        self.rotation as f32 * std::f32::consts::TAU / ITEM_FRAME_ROTATIONS as f32
+    }
+}
+
+/// Content of every item frame of the world, saved with it. Empty frames aren't listed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemFrameRegistry {
+    pub frames: HashMap<IVec3, ItemFrame>,
+}
+
+impl ItemFrameRegistry {
+    pub fn get(&self, position: IVec3) -> ItemFrame {
+        self.frames.get(&position).copied().unwrap_or_default()
+    }
+
+    /// Removes the frame at `position`, returning the item it held
+    pub fn take(&mut self, position: IVec3) -> Option<ItemId> {
+        self.frames.remove(&position).and_then(|frame| frame.item)
+    }
+}
+
+/// Direction of a frame hung on the given face of a block, frames can't be put
+/// on floors or ceilings
+pub fn item_frame_direction(face: FaceDirection) -> Option<BlockDirection> {
+    match face {
+        FaceDirection::MinusZ => Some(BlockDirection::Front),
+        FaceDirection::PlusZ => Some(BlockDirection::Back),
+        FaceDirection::PlusX => Some(BlockDirection::Right),
+        FaceDirection::MinusX => Some(BlockDirection::Left),
+        FaceDirection::PlusY | FaceDirection::MinusY => None,
+    }
+}
+
+/// Direction the front of a frame with this direction faces, away from its wall
+pub fn item_frame_normal(direction: BlockDirection) -> Vec3 {
+    match direction {
+        BlockDirection::Front => Vec3::NEG_Z,
+        BlockDirection::Back => Vec3::Z,
+        BlockDirection::Right => Vec3::X,
+        BlockDirection::Left => Vec3::NEG_X,
+    }
+}
+
+/// Thin box against the wall behind the frame, relative to the block position
+pub fn item_frame_hitbox(direction: BlockDirection) -> BlockHitbox {
+    let normal = Vec3A::from(item_frame_normal(direction));
+    let center = Vec3A::splat(0.5) - normal * (0.5 - ITEM_FRAME_THICKNESS / 2.0);
+    let half_size = Vec3A::splat(0.5) - normal.abs() * (0.5 - ITEM_FRAME_THICKNESS / 2.0);
+    BlockHitbox::Aabb(Aabb3d {
+        min: center - half_size,
+        max: center + half_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_take_one_item_then_turn_it() {
+        let mut frame = ItemFrame::default();
+        assert!(!frame.interact(None));
+        assert_eq!(frame.item, None);
+
+        assert!(frame.interact(Some(ItemId::Poppy)));
+        assert_eq!(frame.item, Some(ItemId::Poppy));
+
+        for expected in (1..ITEM_FRAME_ROTATIONS).chain([0]) {
+            assert!(!frame.interact(Some(ItemId::Dirt)));
+            assert_eq!(frame.rotation, expected);
+        }
+        assert_eq!(frame.item, Some(ItemId::Poppy));
+
+        let mut registry = ItemFrameRegistry::default();
+        registry.frames.insert(IVec3::ONE, frame);
+        assert_eq!(registry.take(IVec3::ONE), Some(ItemId::Poppy));
+        assert_eq!(registry.get(IVec3::ONE), ItemFrame::default());
+    }
+
+    #[test]
+    fn frame_hitboxes_lie_against_their_wall() {
+        let BlockHitbox::Aabb(hitbox) = item_frame_hitbox(BlockDirection::Right) else {
+            panic!("Item frames have a box hitbox");
+        };
+        assert_eq!(hitbox.min, Vec3A::ZERO);
+        assert_eq!(hitbox.max, Vec3A::new(ITEM_FRAME_THICKNESS, 1.0, 1.0));
+
+        let BlockHitbox::Aabb(hitbox) = item_frame_hitbox(BlockDirection::Front) else {
+            panic!("Item frames have a box hitbox");
+        };
+        assert_eq!(hitbox.min, Vec3A::new(0.0, 0.0, 1.0 - ITEM_FRAME_THICKNESS));
+        assert_eq!(hitbox.max, Vec3A::ONE);
+    }
+}
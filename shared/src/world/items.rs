@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use serde::{Deserialize, Serialize};
 
-use super::{BlockId, GameElementId};
+use super::{BlockId, GameElementId, MobKind, StatusEffectKind};
 
 #[derive(
     Debug,
@@ -37,11 +37,135 @@ pub enum ItemId {
     Snow,
     Snowball,
     SpruceLog,
+    FoxSpawnEgg,
+    FishSpawnEgg,
+    OakFence,
+    Lead,
+    Waystone,
+    Bed,
+    ItemFrame,
+    Coal,
+    IronOre,
+    GoldOre,
+    Diamond,
+    Magma,
+    SoulSand,
+    Gravel,
+    SwampGrass,
+    Mycelium,
+    MushroomStem,
+    RedMushroomBlock,
+    Vine,
+    BirchLog,
+    JungleLog,
+    FishingRod,
+    RawFish,
+    SpeedPotion,
+    SlownessPotion,
+    PoisonPotion,
+    RegenerationPotion,
+    Deepslate,
+    Portal,
+    Netherrack,
+    Farmland,
+    Mud,
+    Clay,
+    Kelp,
+    Coral,
+    MossyCobblestone,
+    Obsidian,
+    Bucket,
+    WaterBucket,
+    Boat,
 }
 
 impl ItemId {
+    /// Every item, in the order they are listed in the creative catalog
+    pub const ALL: [ItemId; 58] = [
+        Self::Dirt,
+        Self::Farmland,
+        Self::Mud,
+        Self::Grass,
+        Self::SwampGrass,
+        Self::Mycelium,
+        Self::Stone,
+        Self::Cobblestone,
+        Self::MossyCobblestone,
+        Self::Deepslate,
+        Self::Bedrock,
+        Self::Obsidian,
+        Self::IronOre,
+        Self::GoldOre,
+        Self::Magma,
+        Self::Netherrack,
+        Self::Waystone,
+        Self::Portal,
+        Self::Bed,
+        Self::Sand,
+        Self::SoulSand,
+        Self::Gravel,
+        Self::Clay,
+        Self::OakLog,
+        Self::OakPlanks,
+        Self::OakFence,
+        Self::ItemFrame,
+        Self::OakLeaves,
+        Self::SpruceLog,
+        Self::BirchLog,
+        Self::JungleLog,
+        Self::MushroomStem,
+        Self::RedMushroomBlock,
+        Self::Cactus,
+        Self::Glass,
+        Self::Ice,
+        Self::Snow,
+        Self::Snowball,
+        Self::Coal,
+        Self::Diamond,
+        Self::Dandelion,
+        Self::Poppy,
+        Self::TallGrass,
+        Self::Vine,
+        Self::Kelp,
+        Self::Coral,
+        Self::Lead,
+        Self::FishingRod,
+        Self::Bucket,
+        Self::WaterBucket,
+        Self::Boat,
+        Self::RawFish,
+        Self::FoxSpawnEgg,
+        Self::FishSpawnEgg,
+        Self::SpeedPotion,
+        Self::SlownessPotion,
+        Self::PoisonPotion,
+        Self::RegenerationPotion,
+    ];
+
     pub fn get_max_stack(&self) -> u32 {
-        64
+        match self.get_default_type() {
+            ItemType::Potion(_) => 1,
+            _ if matches!(self, Self::FishingRod | Self::WaterBucket | Self::Boat) => 1,
+            _ if *self == Self::Bucket => 16,
+            _ => 64,
+        }
+    }
+
+    /// Damage dealt to mobs when hitting them with this item
+    pub fn attack_damage(&self) -> f32 {
+        match self {
+            Self::OakLog
+            | Self::SpruceLog
+            | Self::BirchLog
+            | Self::JungleLog
+            | Self::Stone
+            | Self::Cobblestone
+            | Self::MossyCobblestone
+            | Self::Deepslate
+            | Self::Bedrock
+            | Self::Obsidian => 2.0,
+            _ => 1.0,
+        }
     }
 
     pub fn get_default_type(&self) -> ItemType {
@@ -63,8 +187,50 @@ impl ItemId {
             Self::Cobblestone => ItemType::Block(BlockId::Cobblestone),
             Self::Snow => ItemType::Block(BlockId::Snow),
             Self::SpruceLog => ItemType::Block(BlockId::SpruceLog),
+            Self::BirchLog => ItemType::Block(BlockId::BirchLog),
+            Self::JungleLog => ItemType::Block(BlockId::JungleLog),
+            Self::OakFence => ItemType::Block(BlockId::OakFence),
+            Self::Waystone => ItemType::Block(BlockId::Waystone),
+            Self::Bed => ItemType::Block(BlockId::Bed),
+            Self::ItemFrame => ItemType::Block(BlockId::ItemFrame),
+            Self::IronOre => ItemType::Block(BlockId::IronOre),
+            Self::GoldOre => ItemType::Block(BlockId::GoldOre),
+            Self::Magma => ItemType::Block(BlockId::Magma),
+            Self::SoulSand => ItemType::Block(BlockId::SoulSand),
+            Self::Gravel => ItemType::Block(BlockId::Gravel),
+            Self::SwampGrass => ItemType::Block(BlockId::SwampGrass),
+            Self::Mycelium => ItemType::Block(BlockId::Mycelium),
+            Self::MushroomStem => ItemType::Block(BlockId::MushroomStem),
+            Self::RedMushroomBlock => ItemType::Block(BlockId::RedMushroomBlock),
+            Self::Vine => ItemType::Block(BlockId::Vine),
+            Self::Deepslate => ItemType::Block(BlockId::Deepslate),
+            Self::Portal => ItemType::Block(BlockId::Portal),
+            Self::Netherrack => ItemType::Block(BlockId::Netherrack),
+            Self::Farmland => ItemType::Block(BlockId::Farmland),
+            Self::Mud => ItemType::Block(BlockId::Mud),
+            Self::Clay => ItemType::Block(BlockId::Clay),
+            Self::Kelp => ItemType::Block(BlockId::Kelp),
+            Self::Coral => ItemType::Block(BlockId::Coral),
+            Self::MossyCobblestone => ItemType::Block(BlockId::MossyCobblestone),
+            Self::Obsidian => ItemType::Block(BlockId::Obsidian),
+
+            Self::Snowball
+            | Self::Lead
+            | Self::Coal
+            | Self::Diamond
+            | Self::FishingRod
+            | Self::Bucket
+            | Self::WaterBucket
+            | Self::Boat
+            | Self::RawFish => ItemType::Generic,
+
+            Self::FoxSpawnEgg => ItemType::SpawnEgg(MobKind::Fox),
+            Self::FishSpawnEgg => ItemType::SpawnEgg(MobKind::Fish),
 
-            Self::Snowball => ItemType::Generic,
+            Self::SpeedPotion => ItemType::Potion(StatusEffectKind::Speed),
+            Self::SlownessPotion => ItemType::Potion(StatusEffectKind::Slowness),
+            Self::PoisonPotion => ItemType::Potion(StatusEffectKind::Poison),
+            Self::RegenerationPotion => ItemType::Potion(StatusEffectKind::Regeneration),
         }
     }
 }
@@ -94,8 +260,14 @@ pub enum ArmorType {
 pub enum ItemType {
     Generic,
     Block(BlockId),
-    Tool { durability: u16 },
+    Tool {
+        durability: u16,
+    },
     Armor(ArmorType),
+    /// Spawns a mob of the given kind when used
+    SpawnEgg(MobKind),
+    /// Applies the given status effect to the player drinking it
+    Potion(StatusEffectKind),
 }
 
 impl Default for ItemType {
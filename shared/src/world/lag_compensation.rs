@@ -0,0 +1,128 @@
+//! Lag compensation for hit detection.
+//!
+//! Players aim at the mobs where they see them, which is where the server had them
+//! about a round trip before their attack arrives. The server keeps the bounding
+//! boxes of every entity for the last few ticks, and checks attacks against the
+//! boxes at the time the attacker saw instead of the current ones. That time is
+//! always computed from the server clock, never taken from the client.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::math::{bounding::Aabb3d, Vec3};
+use bevy_ecs::resource::Resource;
+
+use crate::TICKS_PER_SECOND;
+
+use super::{raycast_entities, EntityRaycastResponse, SpatialEntity, SpatialHash};
+
+/// Number of ticks kept, attacks can't be rewound further back than this
+pub const LAG_COMPENSATION_TICKS: usize = TICKS_PER_SECOND as usize / 2;
+
+#[derive(Debug, Clone)]
+struct EntitySnapshot {
+    time_ms: u64,
+    bounds: HashMap<SpatialEntity, Aabb3d>,
+}
+
+/// Bounding boxes of the entities over the last [`LAG_COMPENSATION_TICKS`] ticks
+#[derive(Resource, Default, Debug, Clone)]
+pub struct EntityHistory {
+    snapshots: VecDeque<EntitySnapshot>,
+}
+
+impl EntityHistory {
+    /// Saves the current content of the broadphase, dropping the oldest tick if needed
+    pub fn record(&mut self, time_ms: u64, spatial: &SpatialHash) {
+        if self.snapshots.len() >= LAG_COMPENSATION_TICKS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(EntitySnapshot {
+            time_ms,
+            bounds: spatial.entities().collect(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Time of the latest snapshot minus `rewind_ms`, on the clock the snapshots
+    /// were recorded with
+    pub fn rewound(&self, rewind_ms: u64) -> Option<u64> {
+        let latest = self.snapshots.back()?;
+        Some(latest.time_ms.saturating_sub(rewind_ms))
+    }
+
+    /// Latest snapshot taken at or before `time_ms`, or the oldest one if the
+    /// history doesn't go back that far
+    fn at(&self, time_ms: u64) -> Option<&EntitySnapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.time_ms <= time_ms)
+            .or_else(|| self.snapshots.front())
+    }
+
+    /// Nearest entity hit by the ray within `max_distance`, with the entities
+    /// where they were at `time_ms`
+    pub fn raycast(
+        &self,
+        time_ms: u64,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<EntityRaycastResponse> {
+        let snapshot = self.at(time_ms)?;
+        raycast_entities(
+            snapshot
+                .bounds
+                .iter()
+                .map(|(entity, bounds)| (*entity, *bounds)),
+            origin,
+            direction,
+            max_distance,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::entity_bounds;
+
+    #[test]
+    fn rays_hit_entities_where_they_were() {
+        let mob = SpatialEntity::Mob(1);
+        let mut spatial = SpatialHash::default();
+        let mut history = EntityHistory::default();
+        for tick in 0..LAG_COMPENSATION_TICKS as u64 + 5 {
+            // Walks along +x by one block per tick of 50 ms
+            spatial.insert(
+                mob,
+                entity_bounds(Vec3::new(tick as f32, 0.5, 0.0), 1.0, 1.0, 1.0),
+            );
+            history.record(tick * 50, &spatial);
+        }
+        assert_eq!(history.len(), LAG_COMPENSATION_TICKS);
+
+        let hit_at = |time_ms: u64, x: f32| {
+            history
+                .raycast(time_ms, Vec3::new(x, 0.5, -3.0), Vec3::Z, 5.0)
+                .map(|hit| hit.entity)
+        };
+        let last = LAG_COMPENSATION_TICKS as u64 + 4;
+        assert_eq!(hit_at(last * 50, last as f32), Some(mob));
+        assert_eq!(hit_at((last - 3) * 50 + 20, (last - 3) as f32), Some(mob));
+        assert_eq!(hit_at((last - 3) * 50, last as f32), None);
+        // Too far back, the oldest tick is used
+        let oldest = last + 1 - LAG_COMPENSATION_TICKS as u64;
+        assert_eq!(hit_at(0, oldest as f32), Some(mob));
+
+        assert_eq!(history.rewound(150), Some((last - 3) * 50));
+        assert_eq!(EntityHistory::default().rewound(150), None);
+    }
+}
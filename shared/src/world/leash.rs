@@ -0,0 +1,157 @@
+//! Leads tether a mob to a player or to a fence post.
+//!
+//! The server pulls leashed mobs towards their anchor with a damped spring once the
+//! lead is stretched beyond [`LEASH_LENGTH`], and the lead snaps past
+//! [`LEASH_BREAK_DISTANCE`]. Clients draw it as a catenary between both ends.
+
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::PlayerId;
+
+/// Length of a lead, the mob can move freely within it
+pub const LEASH_LENGTH: f32 = 5.0;
+/// Distance at which a lead snaps
+pub const LEASH_BREAK_DISTANCE: f32 = 10.0;
+/// Pull per block of stretch, in blocks/s²
+const LEASH_STIFFNESS: f32 = 12.0;
+/// Damping of the velocity along the lead, so that mobs don't bounce around
+const LEASH_DAMPING: f32 = 4.0;
+
+/// What a leashed mob is tied to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeashAnchor {
+    Player(PlayerId),
+    /// Position of an oak fence block
+    Fence(IVec3),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LeashTension {
+    Slack,
+    /// The lead is stretched, and accelerates the mob towards the anchor
+    Taut(Vec3),
+    Snapped,
+}
+
+/// Where a lead is tied on a fence post
+pub fn fence_knot_position(fence: IVec3) -> Vec3 {
+    fence.as_vec3() + Vec3::new(0.5, 0.8, 0.5)
+}
+
+/// Force applied by a lead tied at `anchor` on a mob at `position`
+pub fn leash_tension(position: Vec3, velocity: Vec3, anchor: Vec3) -> LeashTension {
+    let offset = anchor - position;
+    let distance = offset.length();
+
+    if distance > LEASH_BREAK_DISTANCE {
+        return LeashTension::Snapped;
+    }
+    if distance <= LEASH_LENGTH {
+        return LeashTension::Slack;
+    }
+
+    let direction = offset / distance;
+    let stretch = distance - LEASH_LENGTH;
+    let radial_velocity = velocity.dot(direction);
+    LeashTension::Taut(direction * (stretch * LEASH_STIFFNESS - radial_velocity * LEASH_DAMPING))
+}
+
+/// Points along a rope of `length` hanging between `start` and `end`, both included.
+///
+/// The rope follows a catenary, or a straight line when it is stretched or vertical.
+pub fn catenary_points(start: Vec3, end: Vec3, length: f32, segments: usize) -> Vec<Vec3> {
+    let segments = segments.max(1);
+    let offset = end - start;
+    let horizontal = Vec3::new(offset.x, 0.0, offset.z);
+    let span = horizontal.length();
+    let rise = offset.y;
+
+    let straight = || {
+        (0..=segments)
+            .map(|i| start.lerp(end, i as f32 / segments as f32))
+            .collect()
+    };
+    if span < 1e-3 || length * length - rise * rise <= span * span * (1.0 + 1e-4) {
+        return straight();
+    }
+
+    // Solve 2a·sinh(span / 2a) = sqrt(length² - rise²) for the catenary parameter `a`,
+    // the left hand side decreasing from infinity to `span` as `a` grows
+    let target = (length * length - rise * rise).sqrt();
+    let (mut low, mut high) = (span * 1e-3, span * 1e3);
+    for _ in 0..60 {
+        let a = (low * high).sqrt();
+        if 2.0 * a * (span / (2.0 * a)).sinh() > target {
+            low = a;
+        } else {
+            high = a;
+        }
+    }
+    let a = (low * high).sqrt();
+
+    // Horizontal position of the lowest point, and the height making the rope start at `start`
+    let lowest = span / 2.0 - a * (rise / length).atanh();
+    let base = -a * (-lowest / a).cosh();
+
+    let direction = horizontal / span;
+    (0..=segments)
+        .map(|i| {
+            let x = span * i as f32 / segments as f32;
+            let y = a * ((x - lowest) / a).cosh() + base;
+            start + direction * x + Vec3::Y * y
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lead_pulls_only_when_stretched() {
+        let anchor = Vec3::ZERO;
+        assert_eq!(
+            leash_tension(Vec3::X * 3.0, Vec3::ZERO, anchor),
+            LeashTension::Slack
+        );
+        assert_eq!(
+            leash_tension(Vec3::X * (LEASH_BREAK_DISTANCE + 1.0), Vec3::ZERO, anchor),
+            LeashTension::Snapped
+        );
+
+        let LeashTension::Taut(pull) = leash_tension(Vec3::X * 7.0, Vec3::ZERO, anchor) else {
+            panic!("lead should be taut");
+        };
+        assert!(pull.x < 0.0);
+
+        // Moving away from the anchor is resisted more than standing still
+        let LeashTension::Taut(damped) = leash_tension(Vec3::X * 7.0, Vec3::X, anchor) else {
+            panic!("lead should be taut");
+        };
+        assert!(damped.x < pull.x);
+    }
+
+    #[test]
+    fn catenary_hangs_between_its_ends() {
+        let start = Vec3::new(0.0, 2.0, 0.0);
+        let end = Vec3::new(4.0, 3.0, 0.0);
+        let points = catenary_points(start, end, 6.0, 32);
+
+        assert_eq!(points.len(), 33);
+        assert!(points[0].distance(start) < 1e-3);
+        assert!(points[32].distance(end) < 1e-3);
+
+        // Sags below the straight line, with about the length of the rope
+        let middle = points[16];
+        assert!(middle.y < start.lerp(end, 0.5).y - 0.5);
+        let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+        assert!((length - 6.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn stretched_rope_is_straight() {
+        let points = catenary_points(Vec3::ZERO, Vec3::new(8.0, 0.0, 0.0), 5.0, 4);
+        assert_eq!(points[2], Vec3::new(4.0, 0.0, 0.0));
+    }
+}
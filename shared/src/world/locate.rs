@@ -0,0 +1,131 @@
+//! Finding biomes without generating chunks, for the `/locate <biome>` command.
+//!
+//! Biomes only depend on the climate noise, which can be sampled anywhere. The
+//! search goes through rings of sample points around the player, and stops at
+//! the first ring where the biome shows up.
+
+use bevy::math::IVec2;
+
+use super::{calculate_temperature_humidity_with_noises, BiomeType, ClimateNoises};
+
+/// Prefix of the chat command giving the world seed, e.g. `/seed`
+pub const SEED_COMMAND: &str = "/seed";
+/// Prefix of the chat command finding the nearest biome or structure, e.g. `/locate desert`
+pub const LOCATE_COMMAND: &str = "/locate";
+
+/// Distance between two sampled positions, biomes are much larger than this
+const LOCATE_STEP: i32 = 32;
+/// Biomes further away than this are not searched for
+pub const LOCATE_MAX_DISTANCE: i32 = 8192;
+
+impl BiomeType {
+    pub const ALL: [BiomeType; 12] = [
+        BiomeType::Plains,
+        BiomeType::Forest,
+        BiomeType::MediumMountain,
+        BiomeType::HighMountainGrass,
+        BiomeType::Desert,
+        BiomeType::IcePlain,
+        BiomeType::FlowerPlains,
+        BiomeType::Swamp,
+        BiomeType::MushroomFields,
+        BiomeType::ShallowOcean,
+        BiomeType::Ocean,
+        BiomeType::DeepOcean,
+    ];
+
+    /// Finds a biome by its name, ignoring case, spaces and underscores
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| !c.is_whitespace() && *c != '_')
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let name = normalize(name);
+        Self::ALL
+            .into_iter()
+            .find(|biome| normalize(biome.name()) == name)
+    }
+}
+
+/// Parses `/locate <name>`, returning the name which may contain spaces
+pub fn parse_locate_command(message: &str) -> Option<String> {
+    let mut words = message.split_whitespace();
+    if words.next()? != LOCATE_COMMAND {
+        return None;
+    }
+    let name = words.collect::<Vec<_>>().join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+/// Position of the nearest sample of `biome` around `origin`, in world x and z
+pub fn locate_biome(seed: u32, origin: IVec2, biome: BiomeType) -> Option<IVec2> {
+    let mut noises = ClimateNoises::new(seed);
+    let mut is_biome = |position: IVec2| {
+        BiomeType::from_climate(calculate_temperature_humidity_with_noises(
+            position.x,
+            position.y,
+            &mut noises,
+        )) == biome
+    };
+
+    if is_biome(origin) {
+        return Some(origin);
+    }
+
+    for ring in 1..=LOCATE_MAX_DISTANCE / LOCATE_STEP {
+        let found = (-ring..=ring)
+            .flat_map(|a| {
+                [
+                    IVec2::new(a, -ring),
+                    IVec2::new(a, ring),
+                    IVec2::new(-ring, a),
+                    IVec2::new(ring, a),
+                ]
+            })
+            .map(|offset| origin + offset * LOCATE_STEP)
+            .filter(|position| is_biome(*position))
+            .min_by_key(|position| position.distance_squared(origin));
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::calculate_biome_at_position;
+
+    #[test]
+    fn parses_locate_commands() {
+        assert_eq!(
+            parse_locate_command("/locate flower plains"),
+            Some("flower plains".into())
+        );
+        assert_eq!(parse_locate_command("/locate"), None);
+        assert_eq!(parse_locate_command("locate desert"), None);
+
+        assert_eq!(
+            BiomeType::from_name("Flower_Plains"),
+            Some(BiomeType::FlowerPlains)
+        );
+        assert_eq!(
+            BiomeType::from_name("deepocean"),
+            Some(BiomeType::DeepOcean)
+        );
+        assert_eq!(BiomeType::from_name("nether"), None);
+    }
+
+    #[test]
+    fn located_biomes_are_where_they_are_said_to_be() {
+        let seed = 42;
+        let origin = IVec2::new(100, -300);
+        for biome in [BiomeType::Desert, BiomeType::Ocean, BiomeType::Plains] {
+            let found = locate_biome(seed, origin, biome).unwrap();
+            assert_eq!(calculate_biome_at_position(found.x, found.y, seed), biome);
+        }
+    }
+}
@@ -1,8 +1,8 @@
-/// Level of Detail (LOD) types and utilities for chunk rendering.
-///
-/// LOD reduces mesh complexity for distant chunks:
-/// - LOD 0: Full detail, 1 block = 1 rendered block
-/// - LOD 1: Reduced detail, 2×2×2 blocks = 1 rendered block (at 2× scale)
+//! Level of Detail (LOD) types and utilities for chunk rendering.
+//!
+//! LOD reduces mesh complexity for distant chunks:
+//! - LOD 0: Full detail, 1 block = 1 rendered block
+//! - LOD 1: Reduced detail, 2×2×2 blocks = 1 rendered block (at 2× scale)
 
 /// Level of Detail for chunk rendering
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
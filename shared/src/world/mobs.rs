@@ -3,11 +3,40 @@ use serde::{Deserialize, Serialize};
 
 use crate::messages::PlayerId;
 
+use super::{LeashAnchor, StatusEffects, INVULNERABILITY_TICKS};
+
 pub type MobId = u128;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum MobKind {
     Fox,
+    Fish,
+}
+
+impl MobKind {
+    /// Aquatic mobs only move within water
+    pub fn is_aquatic(&self) -> bool {
+        matches!(self, MobKind::Fish)
+    }
+
+    /// Hostile mobs go after the players they see, the others run away when hit
+    pub fn is_hostile(&self) -> bool {
+        match self {
+            MobKind::Fox | MobKind::Fish => false,
+        }
+    }
+
+    pub fn max_health(&self) -> f32 {
+        match self {
+            MobKind::Fox => 10.0,
+            MobKind::Fish => 3.0,
+        }
+    }
+
+    /// Whether the mob can be tied with a lead
+    pub fn is_leashable(&self) -> bool {
+        !self.is_aquatic()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -38,4 +67,63 @@ pub struct ServerMob {
     pub on_ground: bool,
     pub velocity: Vec3,
     pub depth: f32,
+    /// What the mob is tied to with a lead, if anything
+    #[serde(default)]
+    pub leash: Option<LeashAnchor>,
+    /// Health lost since the mob spawned
+    #[serde(default)]
+    pub damage_taken: f32,
+    /// Tick until which the mob can't be hurt, after being hit
+    #[serde(default)]
+    pub invulnerable_until: u64,
+    #[serde(default)]
+    pub effects: StatusEffects,
+}
+
+impl ServerMob {
+    /// A new idle mob, sized according to its kind
+    pub fn new(kind: MobKind, position: Vec3) -> Self {
+        let (height, width, depth) = match kind {
+            MobKind::Fox => (1.0, 1.0, 1.5),
+            MobKind::Fish => (0.4, 0.4, 0.6),
+        };
+
+        Self {
+            kind,
+            target: MobTarget::Position(position),
+            action: MobAction::Idle,
+            position,
+            rotation: Quat::IDENTITY,
+            height,
+            width,
+            on_ground: false,
+            velocity: Vec3::ZERO,
+            depth,
+            leash: None,
+            damage_taken: 0.0,
+            invulnerable_until: 0,
+            effects: StatusEffects::default(),
+        }
+    }
+
+    pub fn health(&self) -> f32 {
+        (self.kind.max_health() - self.damage_taken).max(0.0)
+    }
+
+    pub fn is_invulnerable(&self, tick: u64) -> bool {
+        tick < self.invulnerable_until
+    }
+
+    /// Deals `damage` to the mob and knocks it back, unless it is invulnerable.
+    /// Returns whether the mob died.
+    pub fn hurt(&mut self, damage: f32, knockback: Vec3, tick: u64) -> bool {
+        if self.is_invulnerable(tick) {
+            return false;
+        }
+        self.damage_taken += damage;
+        self.invulnerable_until = tick + INVULNERABILITY_TICKS;
+        self.velocity = knockback;
+        self.on_ground = false;
+        self.health() <= 0.0
+    }
 }
@@ -1,15 +1,59 @@
+pub mod biome_definitions;
 pub mod blocks;
+pub mod boats;
+pub mod combat;
 pub mod data;
+pub mod daytime;
+pub mod difficulty;
+pub mod dimensions;
+pub mod effects;
+pub mod far_terrain;
+pub mod fishing;
+pub mod heatmap;
+pub mod item_frames;
 pub mod items;
+pub mod lag_compensation;
+pub mod leash;
+pub mod locate;
 pub mod lod;
 pub mod mobs;
+pub mod ores;
+pub mod prefabs;
 pub mod raycast;
+pub mod registry;
+pub mod rollback;
+pub mod spatial;
+pub mod terrain;
 mod utils;
+pub mod waystones;
+pub mod weather;
 
+pub use biome_definitions::*;
 pub use blocks::*;
+pub use boats::*;
+pub use combat::*;
 pub use data::*;
+pub use daytime::*;
+pub use difficulty::*;
+pub use dimensions::*;
+pub use effects::*;
+pub use far_terrain::*;
+pub use fishing::*;
+pub use heatmap::*;
+pub use item_frames::*;
 pub use items::*;
+pub use lag_compensation::*;
+pub use leash::*;
+pub use locate::*;
 pub use lod::*;
 pub use mobs::*;
+pub use ores::*;
+pub use prefabs::*;
 pub use raycast::*;
+pub use registry::*;
+pub use rollback::*;
+pub use spatial::*;
+pub use terrain::*;
 pub use utils::*;
+pub use waystones::*;
+pub use weather::*;
@@ -0,0 +1,177 @@
+//! Ore veins scattered in the stone by the world generation.
+//!
+//! Each ore has a band of heights where its veins start, a number of blocks per vein
+//! and a number of veins per chunk, set in the `ores` field of the generation
+//! config. Veins are random walks drawn from the world seed and the position of the
+//! chunk they start in, so that regenerating a chunk yields the same ores. Veins
+//! can cross into the neighbouring chunks: every chunk also walks the veins of its
+//! neighbours and keeps the blocks that fall inside it.
+
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+
+use crate::CHUNK_SIZE;
+
+use super::{BlockId, SeededRng};
+
+/// Where and how often the veins of one ore are generated
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OreBand {
+    /// Lowest height a vein can start at
+    pub min_y: i32,
+    /// Highest height a vein can start at
+    pub max_y: i32,
+    /// Number of blocks of a vein, at most a chunk wide
+    pub vein_size: u32,
+    /// Average number of veins started in a chunk lying entirely in the band
+    pub veins_per_chunk: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct OreDistribution {
+    pub coal: OreBand,
+    pub iron: OreBand,
+    pub gold: OreBand,
+    pub diamond: OreBand,
+}
+
+impl Default for OreDistribution {
+    fn default() -> Self {
+        Self {
+            coal: OreBand {
+                min_y: 5,
+                max_y: 128,
+                vein_size: 12,
+                veins_per_chunk: 3.0,
+            },
+            iron: OreBand {
+                min_y: 5,
+                max_y: 64,
+                vein_size: 8,
+                veins_per_chunk: 2.0,
+            },
+            gold: OreBand {
+                min_y: 5,
+                max_y: 32,
+                vein_size: 8,
+                veins_per_chunk: 0.5,
+            },
+            diamond: OreBand {
+                min_y: 5,
+                max_y: 16,
+                vein_size: 6,
+                veins_per_chunk: 0.25,
+            },
+        }
+    }
+}
+
+impl OreDistribution {
+    /// Every ore with its band, in the order their veins are placed
+    pub fn bands(&self) -> [(BlockId, OreBand); 4] {
+        [
+            (BlockId::DiamondOre, self.diamond),
+            (BlockId::GoldOre, self.gold),
+            (BlockId::IronOre, self.iron),
+            (BlockId::CoalOre, self.coal),
+        ]
+    }
+}
+
+/// Blocks of the veins of `ore` that start in the chunk at `chunk_pos`, in global coordinates
+fn veins_started_in(seed: u32, chunk_pos: IVec3, ore: BlockId, band: &OreBand) -> Vec<IVec3> {
+    let mut rng = SeededRng::new(seed, chunk_pos, ore as u64);
+    let count = band.veins_per_chunk.floor() as u32
+        + u32::from(rng.next_f32() < band.veins_per_chunk.fract());
+    let size = band.vein_size.min(CHUNK_SIZE as u32);
+
+    let mut blocks = Vec::new();
+    for _ in 0..count {
+        let mut position = chunk_pos * CHUNK_SIZE
+            + IVec3::new(
+                rng.below(CHUNK_SIZE),
+                rng.below(CHUNK_SIZE),
+                rng.below(CHUNK_SIZE),
+            );
+        // Drawn even when out of the band so that the next veins don't depend on it
+        if !(band.min_y..=band.max_y).contains(&position.y) {
+            continue;
+        }
+        for _ in 0..size {
+            blocks.push(position);
+            let axis = rng.below(3) as usize;
+            position[axis] += if rng.next_u64() & 1 == 0 { 1 } else { -1 };
+        }
+    }
+    blocks
+}
+
+/// Ore blocks of the chunk at `chunk_pos`, in local coordinates. The caller only
+/// turns stone into ores, the first ore listed at a position wins.
+pub fn ore_veins(seed: u32, chunk_pos: IVec3, ores: &OreDistribution) -> Vec<(IVec3, BlockId)> {
+    let origin = chunk_pos * CHUNK_SIZE;
+    let mut blocks = Vec::new();
+    for (ore, band) in ores.bands() {
+        if band.veins_per_chunk <= 0.0 || band.vein_size == 0 {
+            continue;
+        }
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let start_chunk = chunk_pos + IVec3::new(x, y, z);
+                    for position in veins_started_in(seed, start_chunk, ore, &band) {
+                        let local = position - origin;
+                        if local.cmpge(IVec3::ZERO).all()
+                            && local.cmplt(IVec3::splat(CHUNK_SIZE)).all()
+                        {
+                            blocks.push((local, ore));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn veins_only_depend_on_the_seed_and_chunk() {
+        let ores = OreDistribution::default();
+        let chunk_pos = IVec3::new(3, 1, -2);
+        let veins = ore_veins(42, chunk_pos, &ores);
+        assert!(!veins.is_empty());
+        assert_eq!(veins, ore_veins(42, chunk_pos, &ores));
+        assert_ne!(veins, ore_veins(43, chunk_pos, &ores));
+    }
+
+    #[test]
+    fn veins_start_in_their_depth_band() {
+        let ores = OreDistribution::default();
+        let mut diamonds = 0;
+        for x in -8..8 {
+            for z in -8..8 {
+                for y in 0..6 {
+                    for (local, ore) in ore_veins(7, IVec3::new(x, y, z), &ores) {
+                        let height = y * CHUNK_SIZE + local.y;
+                        let band = ores
+                            .bands()
+                            .into_iter()
+                            .find(|(id, _)| *id == ore)
+                            .unwrap()
+                            .1;
+                        // A vein wanders at most its size away from where it started
+                        assert!(height >= band.min_y - band.vein_size as i32);
+                        assert!(height <= band.max_y + band.vein_size as i32);
+                        diamonds += usize::from(ore == BlockId::DiamondOre);
+                    }
+                }
+            }
+        }
+        assert!(diamonds > 0, "no diamond was generated");
+    }
+}
@@ -0,0 +1,236 @@
+//! Prefabs, regions of the world saved by creative players to be built again.
+//!
+//! Players mark the corners of a region with `/prefab pos1` and `/prefab pos2` on
+//! the blocks they look at, then save it with `/prefab save <name>`. Prefabs are
+//! stored by the client and picked from a panel. Placing one sends all of its
+//! blocks to the server at once in a `PrefabPlacement`, which only fills the empty
+//! blocks of the region so that nothing already built gets replaced.
+
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+
+use super::{BlockData, WorldMap};
+
+pub const PREFAB_COMMAND: &str = "/prefab";
+/// Longest side of a prefab, in blocks
+pub const MAX_PREFAB_SIZE: i32 = 32;
+/// Longest name of a prefab, which is also the name of its file
+pub const MAX_PREFAB_NAME_LENGTH: usize = 32;
+/// Prefabs can only be placed this close to the player
+pub const PREFAB_PLACE_DISTANCE: f32 = 16.0;
+
+/// Blocks of a region, relative to its lowest corner
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Prefab {
+    pub name: String,
+    /// Size of the region, the position of every block being between zero and it
+    pub size: IVec3,
+    pub blocks: Vec<(IVec3, BlockData)>,
+}
+
+/// Blocks of a prefab sent to the server to be built at `origin`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PrefabPlacement {
+    /// Lowest corner of the prefab in the world
+    pub origin: IVec3,
+    pub size: IVec3,
+    pub blocks: Vec<(IVec3, BlockData)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefabError {
+    TooLarge,
+    Empty,
+    /// A block lies outside of the size of the prefab
+    OutOfBounds,
+    InvalidName,
+}
+
+impl PrefabError {
+    pub fn message(&self) -> String {
+        match self {
+            PrefabError::TooLarge => {
+                format!("Prefabs are at most {MAX_PREFAB_SIZE} blocks on each side")
+            }
+            PrefabError::Empty => "There are no blocks in this region".into(),
+            PrefabError::OutOfBounds => "This prefab is damaged".into(),
+            PrefabError::InvalidName => format!(
+                "Prefab names are up to {MAX_PREFAB_NAME_LENGTH} letters, digits, '-' or '_'"
+            ),
+        }
+    }
+}
+
+/// Names are used as file names, so they are kept to a safe set of characters
+pub fn is_valid_prefab_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PREFAB_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn check_size(size: IVec3) -> Result<(), PrefabError> {
+    if size.cmplt(IVec3::ONE).any() || size.max_element() > MAX_PREFAB_SIZE {
+        return Err(PrefabError::TooLarge);
+    }
+    Ok(())
+}
+
+impl Prefab {
+    /// Copies the blocks between two corners of the world, both included
+    pub fn from_region(
+        world_map: &impl WorldMap,
+        name: &str,
+        corner: IVec3,
+        other_corner: IVec3,
+    ) -> Result<Self, PrefabError> {
+        if !is_valid_prefab_name(name) {
+            return Err(PrefabError::InvalidName);
+        }
+        let min = corner.min(other_corner);
+        let size = corner.max(other_corner) - min + IVec3::ONE;
+        check_size(size)?;
+
+        let mut blocks = Vec::new();
+        for x in 0..size.x {
+            for y in 0..size.y {
+                for z in 0..size.z {
+                    let offset = IVec3::new(x, y, z);
+                    if let Some(block) = world_map.get_block_by_coordinates(&(min + offset)) {
+                        blocks.push((offset, *block));
+                    }
+                }
+            }
+        }
+        if blocks.is_empty() {
+            return Err(PrefabError::Empty);
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            size,
+            blocks,
+        })
+    }
+
+    pub fn placement(&self, origin: IVec3) -> PrefabPlacement {
+        PrefabPlacement {
+            origin,
+            size: self.size,
+            blocks: self.blocks.clone(),
+        }
+    }
+}
+
+impl PrefabPlacement {
+    /// Checks the shape of the placement, the server checking where it goes
+    pub fn validate(&self) -> Result<(), PrefabError> {
+        check_size(self.size)?;
+        if self.blocks.is_empty() {
+            return Err(PrefabError::Empty);
+        }
+        let inside =
+            |offset: IVec3| offset.cmpge(IVec3::ZERO).all() && offset.cmplt(self.size).all();
+        if !self.blocks.iter().all(|(offset, _)| inside(*offset)) {
+            return Err(PrefabError::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    /// Blocks of the prefab at their position in the world
+    pub fn world_blocks(&self) -> impl Iterator<Item = (IVec3, BlockData)> + '_ {
+        self.blocks
+            .iter()
+            .map(|(offset, block)| (self.origin + *offset, *block))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefabCommand {
+    /// Marks the targeted block as the first corner of the region
+    FirstCorner,
+    SecondCorner,
+    Save(String),
+    Delete(String),
+}
+
+/// Parses `/prefab pos1`, `/prefab pos2`, `/prefab save <name>` and `/prefab delete <name>`
+pub fn parse_prefab_command(message: &str) -> Option<PrefabCommand> {
+    let words: Vec<&str> = message.split_whitespace().collect();
+    match words.as_slice() {
+        [PREFAB_COMMAND, "pos1"] => Some(PrefabCommand::FirstCorner),
+        [PREFAB_COMMAND, "pos2"] => Some(PrefabCommand::SecondCorner),
+        [PREFAB_COMMAND, "save", name] => Some(PrefabCommand::Save(name.to_string())),
+        [PREFAB_COMMAND, "delete", name] => Some(PrefabCommand::Delete(name.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockDirection, BlockId, ServerChunkWorldMap};
+
+    #[test]
+    fn prefab_commands_mark_corners_and_name_prefabs() {
+        assert_eq!(
+            parse_prefab_command("/prefab pos1"),
+            Some(PrefabCommand::FirstCorner)
+        );
+        assert_eq!(
+            parse_prefab_command("/prefab pos2"),
+            Some(PrefabCommand::SecondCorner)
+        );
+        assert_eq!(
+            parse_prefab_command("/prefab save tower"),
+            Some(PrefabCommand::Save("tower".into()))
+        );
+        assert_eq!(
+            parse_prefab_command("/prefab delete tower"),
+            Some(PrefabCommand::Delete("tower".into()))
+        );
+        assert_eq!(parse_prefab_command("/prefab save"), None);
+        assert_eq!(parse_prefab_command("/prefab pos3"), None);
+        assert!(is_valid_prefab_name("small_house-2"));
+        assert!(!is_valid_prefab_name("../house"));
+        assert!(!is_valid_prefab_name(""));
+    }
+
+    #[test]
+    fn prefabs_keep_the_blocks_of_their_region() {
+        let mut world_map = ServerChunkWorldMap::default();
+        let stone = BlockData::new(BlockId::Stone, BlockDirection::Front);
+        world_map.set_block(&IVec3::new(10, 5, -3), stone);
+        world_map.set_block(&IVec3::new(12, 7, -1), stone);
+        world_map.set_block(&IVec3::new(13, 7, -1), stone);
+
+        let prefab = Prefab::from_region(
+            &world_map,
+            "pillar",
+            IVec3::new(12, 7, -1),
+            IVec3::new(10, 5, -3),
+        )
+        .unwrap();
+        assert_eq!(prefab.size, IVec3::new(3, 3, 3));
+        assert_eq!(prefab.blocks.len(), 2);
+
+        let placement = prefab.placement(IVec3::new(0, 64, 0));
+        assert_eq!(placement.validate(), Ok(()));
+        let mut placed: Vec<IVec3> = placement.world_blocks().map(|(pos, _)| pos).collect();
+        placed.sort_by_key(|pos| pos.x);
+        assert_eq!(placed, vec![IVec3::new(0, 64, 0), IVec3::new(2, 66, 2)]);
+
+        let mut damaged = placement.clone();
+        damaged.blocks.push((IVec3::new(3, 0, 0), stone));
+        assert_eq!(damaged.validate(), Err(PrefabError::OutOfBounds));
+        assert_eq!(
+            Prefab::from_region(&world_map, "big", IVec3::ZERO, IVec3::splat(40)),
+            Err(PrefabError::TooLarge)
+        );
+        assert_eq!(
+            Prefab::from_region(&world_map, "air", IVec3::splat(100), IVec3::splat(101)),
+            Err(PrefabError::Empty)
+        );
+    }
+}
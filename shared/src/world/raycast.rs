@@ -5,7 +5,7 @@ use bevy::{
 
 use crate::{
     players::ViewMode,
-    world::{BlockData, BlockHitbox, WorldMap},
+    world::{BlockData, BlockHitbox, SpatialEntity, SpatialHash, WorldMap},
     HALF_BLOCK,
 };
 
@@ -44,50 +44,89 @@ pub struct RaycastResponse {
     pub bbox: Aabb3d,
 }
 
-pub fn raycast(
-    world_map: &impl WorldMap,
-    camera_transform: &Transform,
-    player_position: &Vec3,
-    view_mode: ViewMode,
-) -> Option<RaycastResponse> {
-    match view_mode {
-        ViewMode::FirstPerson => first_person_raycast(world_map, camera_transform),
-        ViewMode::ThirdPerson => third_person_raycast(world_map, camera_transform, player_position),
-    }
+/// Entity hit by a ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntityRaycastResponse<E = SpatialEntity> {
+    pub entity: E,
+    /// Where the ray enters the entity's bounding box
+    pub position: Vec3,
+    /// Distance from the ray origin to `position`
+    pub distance: f32,
 }
 
-fn first_person_raycast(
-    world_map: &impl WorldMap,
+/// Origin and direction of the ray cast by a player looking through `camera_transform`.
+/// In third person, the ray starts from the player rather than from the camera behind them.
+pub fn camera_ray(
     camera_transform: &Transform,
-) -> Option<RaycastResponse> {
-    let camera_position = camera_transform.translation;
-    let camera_rotation = camera_transform.rotation;
-
-    let direction = camera_rotation
+    player_position: &Vec3,
+    view_mode: ViewMode,
+) -> (Vec3, Vec3) {
+    let direction = camera_transform
+        .rotation
         .mul_vec3(Vec3::new(0.0, 0.0, -1.0))
         .normalize();
 
-    let current_position = camera_position;
-
-    raycast_from_source_position_and_direction(world_map, current_position, direction)
+    let origin = match view_mode {
+        ViewMode::FirstPerson => camera_transform.translation,
+        ViewMode::ThirdPerson => *player_position,
+    };
+    (origin, direction)
 }
 
-fn third_person_raycast(
+pub fn raycast(
     world_map: &impl WorldMap,
     camera_transform: &Transform,
     player_position: &Vec3,
+    view_mode: ViewMode,
 ) -> Option<RaycastResponse> {
-    let camera_rotation = camera_transform.rotation;
-
-    let camera_direction = camera_rotation
-        .mul_vec3(Vec3::new(0.0, 0.0, -1.0))
-        .normalize();
-
-    let direction = camera_direction;
+    let (origin, direction) = camera_ray(camera_transform, player_position, view_mode);
+    raycast_from_source_position_and_direction(world_map, origin, direction)
+}
 
-    let current_position = *player_position;
+/// Nearest of the `candidates` bounding boxes hit by the ray within `max_distance`.
+/// Rays starting inside a box don't hit it, so that players don't hit themselves.
+pub fn raycast_entities<E>(
+    candidates: impl IntoIterator<Item = (E, Aabb3d)>,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<EntityRaycastResponse<E>> {
+    let inv_dir = 1. / direction;
+    candidates
+        .into_iter()
+        .filter_map(|(entity, bounds)| {
+            let min = (Vec3::from(bounds.min) - origin) * inv_dir;
+            let max = (Vec3::from(bounds.max) - origin) * inv_dir;
+            let near = min.min(max).max_element();
+            let far = min.max(max).min_element();
+            (near <= far && near >= 0.0 && near <= max_distance).then(|| EntityRaycastResponse {
+                entity,
+                position: origin + direction * near,
+                distance: near,
+            })
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
 
-    raycast_from_source_position_and_direction(world_map, current_position, direction)
+impl SpatialHash {
+    /// Nearest player or mob hit by the ray within `max_distance`
+    pub fn raycast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<EntityRaycastResponse> {
+        let end = origin + direction * max_distance;
+        let reach = Aabb3d {
+            min: origin.min(end).into(),
+            max: origin.max(end).into(),
+        };
+        let candidates = self
+            .query_aabb(&reach)
+            .into_iter()
+            .filter_map(|entity| self.bounds(entity).map(|bounds| (entity, bounds)));
+        raycast_entities(candidates, origin, direction, max_distance)
+    }
 }
 
 // Amanatides-Woo fast traversal algorithm
@@ -126,7 +165,7 @@ pub fn raycast_from_source_position_and_direction(
     // Actual raycast loop
     while distance < 20.0 {
         if let Some(block) = world_map.get_block_by_coordinates(&voxel) {
-            match block.id.get_ray_hitbox() {
+            match block.get_ray_hitbox() {
                 BlockHitbox::FullBlock => {
                     return Some(RaycastResponse {
                         block: *block,
@@ -233,3 +272,36 @@ pub fn aabb_ray_hit(
 
     Some((hit_pos, face))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::entity_bounds;
+
+    #[test]
+    fn entity_raycast_hits_the_nearest_entity() {
+        let mut spatial = SpatialHash::default();
+        let shooter = SpatialEntity::Player(1);
+        let near = SpatialEntity::Mob(2);
+        let far = SpatialEntity::Mob(3);
+        spatial.insert(
+            shooter,
+            entity_bounds(Vec3::new(0.0, 0.9, 0.0), 0.8, 1.8, 0.8),
+        );
+        spatial.insert(
+            near,
+            entity_bounds(Vec3::new(0.0, 1.0, -3.0), 1.0, 1.0, 1.0),
+        );
+        spatial.insert(far, entity_bounds(Vec3::new(0.0, 1.0, -8.0), 1.0, 1.0, 1.0));
+
+        let origin = Vec3::new(0.0, 1.2, 0.0);
+        let hit = spatial.raycast(origin, Vec3::NEG_Z, 10.0).unwrap();
+        assert_eq!(hit.entity, near);
+        assert!((hit.distance - 2.5).abs() < 1e-4);
+        assert!((hit.position - Vec3::new(0.0, 1.2, -2.5)).length() < 1e-4);
+
+        // Out of reach, or looking away
+        assert!(spatial.raycast(origin, Vec3::NEG_Z, 2.0).is_none());
+        assert!(spatial.raycast(origin, Vec3::Z, 10.0).is_none());
+    }
+}
@@ -0,0 +1,67 @@
+//! Blocks known by a build of the game. A client compares its registry with the
+//! one of the server when joining, since blocks are sent by their id: a block the
+//! client doesn't know under the same id would be read as another one.
+
+use super::BlockId;
+
+/// Names of the blocks, by id
+pub fn block_registry() -> Vec<String> {
+    BlockId::ALL
+        .iter()
+        .map(|block| format!("{block:?}"))
+        .collect()
+}
+
+/// FNV-1a hash of the block registry, exchanged when joining a server
+pub fn block_registry_hash() -> u64 {
+    block_registry()
+        .iter()
+        .flat_map(|name| name.bytes().chain(std::iter::once(b'\n')))
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Blocks of a server registry that this build doesn't know under the same id.
+/// Blocks only this build knows don't matter, the server never sends them.
+pub fn unknown_blocks(server_registry: &[String]) -> Vec<String> {
+    let registry = block_registry();
+    server_registry
+        .iter()
+        .enumerate()
+        .filter(|(id, name)| registry.get(*id) != Some(*name))
+        .map(|(id, name)| format!("{name} (id {id})"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_listed_by_id() {
+        for (id, block) in BlockId::ALL.iter().enumerate() {
+            assert_eq!(*block as usize, id, "{block:?} is out of place");
+        }
+        assert_eq!(BlockId::ALL.len(), BlockId::Obsidian as usize + 1);
+    }
+
+    #[test]
+    fn unknown_blocks_are_the_ones_with_another_id() {
+        let mut server_registry = block_registry();
+        assert!(unknown_blocks(&server_registry).is_empty());
+        assert!(unknown_blocks(&server_registry[..3]).is_empty());
+
+        server_registry.swap(0, 1);
+        server_registry.push("Netherite".into());
+        let id = server_registry.len() - 1;
+        assert_eq!(
+            unknown_blocks(&server_registry),
+            vec![
+                "Debug (id 0)".to_string(),
+                "Dirt (id 1)".to_string(),
+                format!("Netherite (id {id})"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Rolling back the blocks changed by players, for operators.
+//!
+//! The server logs every block placed or broken by a player. Operators undo the
+//! changes of a griefer with `/rollback player <name> <duration>`, or every change
+//! around them with `/rollback radius <blocks> <duration>`. Durations are a number
+//! followed by `s`, `m`, `h` or `d`, like `30m`.
+
+use serde::{Deserialize, Serialize};
+
+pub const ROLLBACK_COMMAND: &str = "/rollback";
+/// Largest radius of a `/rollback radius` command, in blocks
+pub const ROLLBACK_MAX_RADIUS: u32 = 128;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RollbackTarget {
+    /// Every change made by the player with this name
+    Player(String),
+    /// Every change within this many blocks of the operator, horizontally
+    Radius(u32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RollbackRequest {
+    pub target: RollbackTarget,
+    /// Only the changes made during the last `since_secs` seconds are undone
+    pub since_secs: u64,
+}
+
+/// Parses durations like `90s`, `30m`, `2h` or `1d` into seconds
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let unit = match text.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    let amount: u64 = text[..text.len() - 1].parse().ok()?;
+    amount.checked_mul(unit)
+}
+
+/// Parses `/rollback player <name> <duration>` and `/rollback radius <blocks> <duration>`
+pub fn parse_rollback_command(message: &str) -> Option<RollbackRequest> {
+    let words: Vec<&str> = message.split_whitespace().collect();
+    let target = match words.as_slice() {
+        [ROLLBACK_COMMAND, "player", name, _] => RollbackTarget::Player(name.to_string()),
+        [ROLLBACK_COMMAND, "radius", radius, _] => {
+            let radius: u32 = radius.parse().ok()?;
+            RollbackTarget::Radius(radius.min(ROLLBACK_MAX_RADIUS))
+        }
+        _ => return None,
+    };
+    Some(RollbackRequest {
+        target,
+        since_secs: parse_duration(words[3])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback_commands_name_a_target_and_a_duration() {
+        assert_eq!(parse_duration("90s"), Some(90));
+        assert_eq!(parse_duration("30m"), Some(30 * 60));
+        assert_eq!(parse_duration("2h"), Some(2 * 60 * 60));
+        assert_eq!(parse_duration("1d"), Some(24 * 60 * 60));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("m"), None);
+
+        assert_eq!(
+            parse_rollback_command("/rollback player Steve 30m"),
+            Some(RollbackRequest {
+                target: RollbackTarget::Player("Steve".into()),
+                since_secs: 30 * 60,
+            })
+        );
+        assert_eq!(
+            parse_rollback_command("/rollback radius 1000 1h"),
+            Some(RollbackRequest {
+                target: RollbackTarget::Radius(ROLLBACK_MAX_RADIUS),
+                since_secs: 60 * 60,
+            })
+        );
+        assert_eq!(parse_rollback_command("/rollback player Steve"), None);
+        assert_eq!(parse_rollback_command("/rollback radius ten 1h"), None);
+        assert_eq!(parse_rollback_command("/rollback everything 1h"), None);
+    }
+}
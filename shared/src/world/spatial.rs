@@ -0,0 +1,204 @@
+//! Broadphase for entity queries, so that finding the entities around a point
+//! doesn't require going through every mob and player.
+//!
+//! Entities are bucketed in a uniform grid of [`SPATIAL_CELL_SIZE`] cells, by the
+//! cells their bounding box overlaps. Queries only look at the cells they cover,
+//! then check the exact bounding boxes.
+
+use std::collections::HashMap;
+
+use bevy::math::{bounding::Aabb3d, IVec3, Vec3};
+use bevy_ecs::resource::Resource;
+
+use crate::messages::PlayerId;
+
+use super::{DimensionId, MobId, ServerWorldMap};
+
+/// Side of a grid cell, a bit larger than the biggest entity
+pub const SPATIAL_CELL_SIZE: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SpatialEntity {
+    Player(PlayerId),
+    Mob(MobId),
+}
+
+/// Bounding box of a mob or player standing at `position`, which is its center
+pub fn entity_bounds(position: Vec3, width: f32, height: f32, depth: f32) -> Aabb3d {
+    Aabb3d::new(position, Vec3::new(width, height, depth) / 2.0)
+}
+
+fn aabbs_overlap(a: &Aabb3d, b: &Aabb3d) -> bool {
+    a.min.cmple(b.max).all() && b.min.cmple(a.max).all()
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<SpatialEntity>>,
+    bounds: HashMap<SpatialEntity, Aabb3d>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(SPATIAL_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    fn cell_range(&self, bounds: &Aabb3d) -> (IVec3, IVec3) {
+        let min = (Vec3::from(bounds.min) / self.cell_size).floor().as_ivec3();
+        let max = (Vec3::from(bounds.max) / self.cell_size).floor().as_ivec3();
+        (min, max)
+    }
+
+    fn cells_in(&self, bounds: &Aabb3d) -> impl Iterator<Item = IVec3> {
+        let (min, max) = self.cell_range(bounds);
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// Adds the entity, or moves it if it was already in the grid
+    pub fn insert(&mut self, entity: SpatialEntity, bounds: Aabb3d) {
+        self.remove(entity);
+        for cell in self.cells_in(&bounds).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.bounds.insert(entity, bounds);
+    }
+
+    pub fn remove(&mut self, entity: SpatialEntity) {
+        let Some(bounds) = self.bounds.remove(&entity) else {
+            return;
+        };
+        for cell in self.cells_in(&bounds).collect::<Vec<_>>() {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|other| *other != entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    pub fn bounds(&self, entity: SpatialEntity) -> Option<Aabb3d> {
+        self.bounds.get(&entity).copied()
+    }
+
+    /// Every entity of the grid with its bounding box
+    pub fn entities(&self) -> impl Iterator<Item = (SpatialEntity, Aabb3d)> + '_ {
+        self.bounds
+            .iter()
+            .map(|(entity, bounds)| (*entity, *bounds))
+    }
+
+    /// Replaces the content of the grid with every player and mob of the world.
+    /// Mobs only live in the overworld, so players of other dimensions are left out.
+    pub fn rebuild(&mut self, world_map: &ServerWorldMap) {
+        self.clear();
+        for player in world_map.players_in(DimensionId::Overworld) {
+            self.insert(
+                SpatialEntity::Player(player.id),
+                entity_bounds(player.position, player.width, player.height, player.width),
+            );
+        }
+        for (id, mob) in world_map.mobs.iter() {
+            self.insert(
+                SpatialEntity::Mob(*id),
+                entity_bounds(mob.position, mob.width, mob.height, mob.depth),
+            );
+        }
+    }
+
+    /// Entities whose bounding box overlaps `area`, sorted players first
+    pub fn query_aabb(&self, area: &Aabb3d) -> Vec<SpatialEntity> {
+        let mut found: Vec<SpatialEntity> = self
+            .cells_in(area)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|entity| aabbs_overlap(&self.bounds[entity], area))
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Entities whose bounding box is at most `radius` away from `center`
+    pub fn query_radius(&self, center: Vec3, radius: f32) -> Vec<SpatialEntity> {
+        let mut found = self.query_aabb(&Aabb3d::new(center, Vec3::splat(radius)));
+        found.retain(|entity| {
+            let bounds = &self.bounds[entity];
+            let closest = center.clamp(bounds.min.into(), bounds.max.into());
+            closest.distance_squared(center) <= radius * radius
+        });
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_only_return_nearby_entities() {
+        let mut hash = SpatialHash::default();
+        let near = SpatialEntity::Mob(1);
+        let far = SpatialEntity::Mob(2);
+        let player = SpatialEntity::Player(3);
+        hash.insert(near, entity_bounds(Vec3::new(1.0, 0.5, 1.0), 1.0, 1.0, 1.0));
+        hash.insert(
+            far,
+            entity_bounds(Vec3::new(40.0, 0.5, -40.0), 1.0, 1.0, 1.0),
+        );
+        hash.insert(
+            player,
+            entity_bounds(Vec3::new(-3.5, 0.9, 0.0), 0.8, 1.8, 0.8),
+        );
+
+        assert_eq!(hash.query_radius(Vec3::ZERO, 2.0), vec![near]);
+        assert_eq!(hash.query_radius(Vec3::ZERO, 4.0), vec![player, near]);
+        assert_eq!(
+            hash.query_aabb(&Aabb3d::new(Vec3::new(40.0, 0.0, -40.0), Vec3::splat(1.0))),
+            vec![far]
+        );
+    }
+
+    #[test]
+    fn moved_entities_leave_their_old_cells() {
+        let mut hash = SpatialHash::default();
+        let mob = SpatialEntity::Mob(1);
+        hash.insert(mob, entity_bounds(Vec3::new(0.5, 0.5, 0.5), 1.0, 1.0, 1.0));
+        hash.insert(mob, entity_bounds(Vec3::new(20.5, 0.5, 0.5), 1.0, 1.0, 1.0));
+
+        assert_eq!(hash.len(), 1);
+        assert!(hash.query_radius(Vec3::ZERO, 2.0).is_empty());
+        assert_eq!(hash.query_radius(Vec3::new(20.0, 0.0, 0.0), 2.0), vec![mob]);
+
+        hash.remove(mob);
+        assert!(hash.is_empty());
+        assert!(hash.cells.is_empty());
+    }
+}
@@ -0,0 +1,1074 @@
+//! Height of the terrain, in shared code so the noise playground of the client can
+//! preview the terrain with the exact same functions as the world generation.
+//!
+//! The height of each column blends the parameters of the nearby biomes, then adds
+//! several octaves of noise (fractal Brownian motion) for the hills, ridge noise on
+//! the mountain ranges, and rounds part of the height to steps for plateaus.
+//! Rivers follow the lines where a last noise crosses zero: the ground is lowered
+//! around them down to a bed under the sea level, so that their channels fill with
+//! water and join the lakes and oceans. They fade out in the hills.
+//!
+//! Ravines follow the zero lines of their own path noise too, but only where a
+//! coarser sample of it is high, which keeps them rare and gives them ends. They
+//! cut from the surface down to near the bottom of the world, across as many
+//! chunks as needed, and stay dry.
+//!
+//! Depending on the world type, a 3D density noise is then added around the
+//! surface (Minecraft 1.18 style): a block is solid when its height below the
+//! surface plus the density is positive, which carves overhangs, arches and caves
+//! into the cliffs. Floating islands add a second band of density high in the sky.
+//!
+//! The parameters come from `generation.ron` in the game folder when a world is
+//! created, adjusted by the preset of the world, and are then saved with the world
+//! so that its chunks stay seamless when the file or the preset changes.
+
+use std::str::FromStr;
+
+use bevy::math::{IVec2, IVec3, Vec2, Vec3};
+use bevy_ecs::resource::Resource;
+use noiz::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::SEA_LEVEL;
+
+use super::{
+    biome_for_climate, calculate_temperature_humidity_with_noises, Biome, BlockId, ClimateNoises,
+    OreDistribution, BEACH_SEED_OFFSET, DENSITY_SEED_OFFSET, FLOWER_SEED_OFFSET, LAVA_SEED_OFFSET,
+    RAVINE_SEED_OFFSET, RIDGE_SEED_OFFSET, RIVER_SEED_OFFSET, SEABED_SEED_OFFSET,
+};
+
+/// File of the game folder holding the parameters used for new worlds
+pub const GENERATION_CONFIG_FILE: &str = "generation.ron";
+
+/// Half thickness of the band of the floating islands
+const ISLAND_THICKNESS: f64 = 12.0;
+/// Density above which the island noise is solid, higher values give smaller islands
+const ISLAND_THRESHOLD: f64 = 0.35;
+
+/// Depth of the bed of the rivers under the sea level, in their middle
+const RIVER_DEPTH: f64 = 3.0;
+/// Rivers don't carve ground higher than this above the sea level
+const RIVER_MAX_BANK_HEIGHT: f64 = 32.0;
+
+/// Lowest floor of the ravines, in their deepest part
+const RAVINE_BOTTOM: i32 = 12;
+/// Ravines are only found where the coarse ravine noise is above this
+const RAVINE_THRESHOLD: f64 = 0.3;
+/// Frequency of the coarse ravine noise relative to the path noise
+const RAVINE_MASK_SCALE: f32 = 0.15;
+/// Ravines don't open this close to the water, which would pour into them
+const RAVINE_MIN_SHORE_HEIGHT: i32 = 3;
+/// Scale of the beach noise, a few widths along a hundred blocks of coast
+const BEACH_SCALE: f32 = 0.03;
+/// Beaches go this many blocks under the sea, and at most this many above it
+const BEACH_DEPTH: i32 = 3;
+const BEACH_MAX_HEIGHT: i32 = 4;
+/// The narrowest stretches of coast, under this beach noise, are gravel
+const GRAVEL_BEACH_THRESHOLD: f32 = -0.45;
+/// Scale of the seabed noise, patches a few dozen blocks wide
+const SEABED_SCALE: f32 = 0.05;
+/// The ocean floor is gravel above this seabed noise, and clay under its opposite
+const SEABED_PATCH_THRESHOLD: f32 = 0.35;
+/// Scale of the flower noise, meadows a few dozen blocks wide
+const FLOWER_SCALE: f32 = 0.04;
+/// Flowers only grow where their noise is above this
+const FLOWER_MEADOW_THRESHOLD: f32 = 0.15;
+/// Flower density in the heart of a meadow, relative to the biome's
+const FLOWER_MEADOW_DENSITY: f32 = 8.0;
+/// Frequency of the lava pool noise, pools being twice as wide as tall
+const LAVA_POOL_SCALE: f32 = 0.06;
+/// Lava pools are where their noise is above this, higher values give fewer pools
+const LAVA_POOL_THRESHOLD: f32 = 0.7;
+/// Height of the cave right above the lava of a pool
+const LAVA_POOL_HEADROOM: i32 = 3;
+/// Frequency of the lava pocket noise, pockets being smaller than the pools
+const LAVA_POCKET_SCALE: f32 = 0.15;
+/// Lava pockets are where their noise is above this
+const LAVA_POCKET_THRESHOLD: f32 = 0.75;
+/// Lava pockets sample the lava noise this far away, so they don't line up with the pools
+const LAVA_POCKET_OFFSET: f32 = 1000.0;
+/// Lava springs only break out of the slopes at or above this height
+const LAVA_SPRING_MIN_HEIGHT: i32 = SEA_LEVEL + 24;
+/// Drop to the lowest neighbouring column for a slope to hold a spring
+const LAVA_SPRING_MIN_SLOPE: i32 = 3;
+/// Frequency and threshold of the noise picking the columns of the springs, a
+/// high frequency giving scattered springs rather than clusters
+const LAVA_SPRING_SCALE: f32 = 0.8;
+const LAVA_SPRING_THRESHOLD: f32 = 0.45;
+
+/// Height of the ground of superflat worlds, above the sea level so they stay dry
+pub const SUPERFLAT_HEIGHT: i32 = SEA_LEVEL + 2;
+/// Blocks by which the terrain of island worlds is lowered, leaving only the hills
+/// and the mountains above the sea
+const ISLANDS_SINK: f64 = 16.0;
+
+/// Depth of the density noise of the overhangs preset, relative to the config, so
+/// that arches and cliffs reach further from the surface
+const OVERHANGS_DENSITY_MULTIPLIER: f64 = 1.5;
+
+/// Distance between two columns tried as the world spawn
+const SPAWN_SEARCH_STEP: i32 = 8;
+/// The world spawn is looked for at most this far from the origin
+const SPAWN_SEARCH_RADIUS: i32 = 1024;
+/// Largest height difference around the world spawn
+const SPAWN_MAX_SLOPE: i32 = 2;
+
+/// Shape of the terrain of a world
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldType {
+    /// Ground following the height of each column
+    #[default]
+    Heightmap,
+    /// Heightmap reshaped by the density noise
+    Overhangs,
+    /// Overhangs with islands floating above the ground
+    FloatingIslands,
+}
+
+impl WorldType {
+    pub const ALL: [WorldType; 3] = [
+        WorldType::Heightmap,
+        WorldType::Overhangs,
+        WorldType::FloatingIslands,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldType::Heightmap => "Heightmap",
+            WorldType::Overhangs => "Overhangs",
+            WorldType::FloatingIslands => "Floating islands",
+        }
+    }
+}
+
+/// Kind of world picked when creating it, from the `--world-type` argument of the
+/// server or the solo menu
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldGenPreset {
+    /// The generation config as it is
+    #[default]
+    Default,
+    /// Flat grass, without water, ravines nor trees
+    Superflat,
+    /// Much higher mountains, with overhangs
+    Amplified,
+    /// Ocean with scattered islands
+    Islands,
+    /// The default heights carved by the density noise into overhangs, arches and cliffs
+    Overhangs,
+}
+
+impl WorldGenPreset {
+    pub const ALL: [WorldGenPreset; 5] = [
+        WorldGenPreset::Default,
+        WorldGenPreset::Superflat,
+        WorldGenPreset::Amplified,
+        WorldGenPreset::Islands,
+        WorldGenPreset::Overhangs,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldGenPreset::Default => "Default",
+            WorldGenPreset::Superflat => "Superflat",
+            WorldGenPreset::Amplified => "Amplified",
+            WorldGenPreset::Islands => "Islands",
+            WorldGenPreset::Overhangs => "Overhangs",
+        }
+    }
+
+    /// Generation config of a new world of this preset, based on `config`
+    pub fn apply(self, config: GenerationConfig) -> GenerationConfig {
+        let config = GenerationConfig {
+            preset: self,
+            ..config
+        };
+        match self {
+            WorldGenPreset::Default => config,
+            WorldGenPreset::Superflat => GenerationConfig {
+                world_type: WorldType::Heightmap,
+                river_width: 0.0,
+                ravine_width: 0.0,
+                lava_level: 0,
+                lava_pocket_level: 0,
+                lava_springs: false,
+                ..config
+            },
+            WorldGenPreset::Amplified => GenerationConfig {
+                height_variation_multiplier: config.height_variation_multiplier * 2.5,
+                ridge_multiplier: config.ridge_multiplier * 2.0,
+                world_type: match config.world_type {
+                    WorldType::Heightmap => WorldType::Overhangs,
+                    world_type => world_type,
+                },
+                ..config
+            },
+            WorldGenPreset::Islands => GenerationConfig {
+                river_width: 0.0,
+                ..config
+            },
+            WorldGenPreset::Overhangs => GenerationConfig {
+                density_strength: config.density_strength * OVERHANGS_DENSITY_MULTIPLIER,
+                world_type: match config.world_type {
+                    WorldType::Heightmap => WorldType::Overhangs,
+                    world_type => world_type,
+                },
+                ..config
+            },
+        }
+    }
+}
+
+impl FromStr for WorldGenPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown world type {s}, expected default, superflat, amplified, islands or overhangs"
+                )
+            })
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct GenerationConfig {
+    /// Preset the world was created with
+    pub preset: WorldGenPreset,
+    /// Frequency of the terrain height noise, higher values give steeper hills
+    pub terrain_scale: f32,
+    /// Distance in blocks at which neighboring biomes blend their heights
+    pub biome_blend_distance: i32,
+    /// Factor applied to the height variation of every biome
+    pub height_variation_multiplier: f64,
+    /// Number of noise layers of the hills, each one finer than the previous
+    pub octaves: u32,
+    /// Amplitude of each octave relative to the previous one
+    pub persistence: f64,
+    /// Frequency of each octave relative to the previous one
+    pub lacunarity: f32,
+    /// Frequency of the ridge noise, lower values give longer mountain ranges
+    pub ridge_scale: f32,
+    /// Factor applied to the ridge height of every biome
+    pub ridge_multiplier: f64,
+    /// Height of a plateau step, 0 disables the terraces
+    pub terrace_height: i32,
+    pub world_type: WorldType,
+    /// Frequency of the density noise
+    pub density_scale: f32,
+    /// Blocks by which the density noise can move the surface up or down
+    pub density_strength: f64,
+    /// Height of the middle of the floating islands
+    pub island_altitude: i32,
+    /// Frequency of the river noise, lower values give longer and straighter rivers
+    pub river_scale: f32,
+    /// Width of the rivers with their banks, in river noise units. 0 disables the
+    /// rivers, which is the case of the worlds saved before they existed.
+    #[serde(default)]
+    pub river_width: f64,
+    /// Frequency of the ravine path noise
+    pub ravine_scale: f32,
+    /// Width of the ravines, in ravine noise units. 0 disables the ravines, which
+    /// is the case of the worlds saved before they existed.
+    #[serde(default)]
+    pub ravine_width: f64,
+    /// Depth bands, sizes and frequencies of the ore veins
+    pub ores: OreDistribution,
+    /// Stone turns into deepslate at and below this height. 0 disables the
+    /// deepslate, which is the case of the worlds saved before it existed.
+    #[serde(default)]
+    pub deepslate_level: i32,
+    /// Height of the surface of the lava pools near the bedrock, 0 disables them
+    #[serde(default)]
+    pub lava_level: i32,
+    /// Pockets of lava are enclosed in the stone between the lava pools and this
+    /// height. 0 disables them, which is the case of the worlds saved before they existed.
+    #[serde(default)]
+    pub lava_pocket_level: i32,
+    /// Whether lava springs break out of the mountain slopes
+    #[serde(default)]
+    pub lava_springs: bool,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            preset: WorldGenPreset::Default,
+            terrain_scale: 0.1,
+            biome_blend_distance: 4,
+            height_variation_multiplier: 1.0,
+            octaves: 4,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            ridge_scale: 0.01,
+            ridge_multiplier: 1.0,
+            terrace_height: 4,
+            world_type: WorldType::Heightmap,
+            density_scale: 0.05,
+            density_strength: 8.0,
+            island_altitude: 120,
+            river_scale: 0.004,
+            river_width: 0.06,
+            ravine_scale: 0.008,
+            ravine_width: 0.025,
+            ores: OreDistribution::default(),
+            deepslate_level: 16,
+            lava_level: 10,
+            lava_pocket_level: 40,
+            lava_springs: true,
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// Single octave terrain without ridges nor terraces, of the worlds created
+    /// before the generation was configurable
+    pub fn classic() -> Self {
+        Self {
+            octaves: 1,
+            ridge_multiplier: 0.0,
+            terrace_height: 0,
+            river_width: 0.0,
+            ravine_width: 0.0,
+            deepslate_level: 0,
+            lava_level: 0,
+            lava_pocket_level: 0,
+            lava_springs: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// Noises of the terrain height for a seed, to build once per chunk or preview
+#[derive(Clone)]
+pub struct TerrainNoise {
+    pub config: GenerationConfig,
+    perlin: Noise<common_noise::Perlin>,
+    ridges: Noise<common_noise::Perlin>,
+    density: Noise<common_noise::Perlin>,
+    rivers: Noise<common_noise::Perlin>,
+    ravines: Noise<common_noise::Perlin>,
+    beaches: Noise<common_noise::Perlin>,
+    lava_pools: Noise<common_noise::Perlin>,
+    seabed: Noise<common_noise::Perlin>,
+    flowers: Noise<common_noise::Perlin>,
+    climate: ClimateNoises,
+}
+
+impl TerrainNoise {
+    pub fn new(seed: u32, config: GenerationConfig) -> Self {
+        let mut perlin = Noise::<common_noise::Perlin>::default();
+        perlin.set_seed(seed);
+
+        let mut ridges = Noise::<common_noise::Perlin>::default();
+        ridges.set_seed(seed + RIDGE_SEED_OFFSET);
+
+        let mut density = Noise::<common_noise::Perlin>::default();
+        density.set_seed(seed + DENSITY_SEED_OFFSET);
+
+        let mut rivers = Noise::<common_noise::Perlin>::default();
+        rivers.set_seed(seed + RIVER_SEED_OFFSET);
+
+        let mut ravines = Noise::<common_noise::Perlin>::default();
+        ravines.set_seed(seed + RAVINE_SEED_OFFSET);
+
+        let mut beaches = Noise::<common_noise::Perlin>::default();
+        beaches.set_seed(seed + BEACH_SEED_OFFSET);
+
+        let mut lava_pools = Noise::<common_noise::Perlin>::default();
+        lava_pools.set_seed(seed + LAVA_SEED_OFFSET);
+
+        let mut seabed = Noise::<common_noise::Perlin>::default();
+        seabed.set_seed(seed + SEABED_SEED_OFFSET);
+
+        let mut flowers = Noise::<common_noise::Perlin>::default();
+        flowers.set_seed(seed + FLOWER_SEED_OFFSET);
+
+        Self {
+            config,
+            perlin,
+            ridges,
+            density,
+            rivers,
+            ravines,
+            beaches,
+            lava_pools,
+            seabed,
+            flowers,
+            climate: ClimateNoises::new(seed),
+        }
+    }
+
+    fn biome_at(&mut self, x: i32, z: i32) -> Biome {
+        biome_for_climate(calculate_temperature_humidity_with_noises(
+            x,
+            z,
+            &mut self.climate,
+        ))
+    }
+
+    /// Sum of the octaves of the hills noise, between -1 and 1
+    fn fbm(&self, x: i32, z: i32) -> f64 {
+        let mut frequency = self.config.terrain_scale;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut total_amplitude = 0.0;
+        for _ in 0..self.config.octaves.max(1) {
+            let sample_pos = Vec2::new(x as f32 * frequency, z as f32 * frequency);
+            total += self.perlin.sample_for::<f32>(sample_pos) as f64 * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= self.config.persistence;
+            frequency *= self.config.lacunarity;
+        }
+        total / total_amplitude
+    }
+
+    /// Ridge noise, 1 on the crests of the mountain ranges and 0 far from them
+    fn ridge(&self, x: i32, z: i32) -> f64 {
+        let scale = self.config.ridge_scale;
+        let sample_pos = Vec2::new(x as f32 * scale, z as f32 * scale);
+        let noise = self.ridges.sample_for::<f32>(sample_pos) as f64;
+        (1.0 - noise.abs()).powi(2)
+    }
+
+    /// River noise, 1 in the middle of a river and 0 away from it and its banks
+    pub fn river(&self, x: i32, z: i32) -> f64 {
+        let width = self.config.river_width;
+        if width <= 0.0 {
+            return 0.0;
+        }
+        let scale = self.config.river_scale;
+        let sample_pos = Vec2::new(x as f32 * scale, z as f32 * scale);
+        let distance = (self.rivers.sample_for::<f32>(sample_pos) as f64).abs() / width;
+        if distance >= 1.0 {
+            return 0.0;
+        }
+        let closeness = 1.0 - distance;
+        closeness * closeness * (3.0 - 2.0 * closeness)
+    }
+
+    /// Block covering the column at `x`, `z` whose surface is at `height`, if it
+    /// is on a beach: sand, or gravel where the coast is the narrowest. The width
+    /// of the beach above the sea level follows a noise, so coastlines don't all
+    /// stop at the same height.
+    pub fn beach(&self, x: i32, z: i32, height: i32) -> Option<BlockId> {
+        if self.config.preset == WorldGenPreset::Superflat {
+            return None;
+        }
+        let sample_pos = Vec2::new(x as f32, z as f32) * BEACH_SCALE;
+        let noise = self.beaches.sample_for::<f32>(sample_pos);
+        let width = ((noise + 1.0) / 2.0 * (BEACH_MAX_HEIGHT + 1) as f32) as i32;
+        if height < SEA_LEVEL - BEACH_DEPTH || height > SEA_LEVEL + width {
+            return None;
+        }
+        if noise < GRAVEL_BEACH_THRESHOLD {
+            Some(BlockId::Gravel)
+        } else {
+            Some(BlockId::Sand)
+        }
+    }
+
+    /// Block covering the ocean floor at `x`, `z` where it isn't sand: patches
+    /// of gravel and of clay
+    pub fn seabed_patch(&self, x: i32, z: i32) -> Option<BlockId> {
+        let sample_pos = Vec2::new(x as f32, z as f32) * SEABED_SCALE;
+        let noise = self.seabed.sample_for::<f32>(sample_pos);
+        if noise > SEABED_PATCH_THRESHOLD {
+            Some(BlockId::Gravel)
+        } else if noise < -SEABED_PATCH_THRESHOLD {
+            Some(BlockId::Clay)
+        } else {
+            None
+        }
+    }
+
+    /// How many more flowers grow at `x`, `z` than the biome's flower threshold
+    /// says: none between the meadows, and more and more towards their heart
+    pub fn flower_density(&self, x: i32, z: i32) -> f32 {
+        let sample_pos = Vec2::new(x as f32, z as f32) * FLOWER_SCALE;
+        let noise = self.flowers.sample_for::<f32>(sample_pos);
+        let meadow = (noise - FLOWER_MEADOW_THRESHOLD) / (1.0 - FLOWER_MEADOW_THRESHOLD);
+        meadow.max(0.0) * FLOWER_MEADOW_DENSITY
+    }
+
+    /// Lowest block cut by a ravine in the column at `x`, `z` whose surface is at
+    /// `height`, everything from there up to the surface being air
+    pub fn ravine_floor(&self, x: i32, z: i32, height: i32) -> Option<i32> {
+        let width = self.config.ravine_width;
+        if width <= 0.0 || height < SEA_LEVEL + RAVINE_MIN_SHORE_HEIGHT {
+            return None;
+        }
+
+        let scale = self.config.ravine_scale;
+        let sample_pos = Vec2::new(x as f32 * scale, z as f32 * scale);
+        // sampled far from the path noise so that both are unrelated
+        let mask = self
+            .ravines
+            .sample_for::<f32>(sample_pos * RAVINE_MASK_SCALE + Vec2::splat(1000.5))
+            as f64;
+        if mask <= RAVINE_THRESHOLD {
+            return None;
+        }
+        let distance = (self.ravines.sample_for::<f32>(sample_pos) as f64).abs() / width;
+        if distance >= 1.0 {
+            return None;
+        }
+
+        // V shaped, and shallower towards the ends
+        let ends = ((mask - RAVINE_THRESHOLD) / (1.0 - RAVINE_THRESHOLD) * 4.0).min(1.0);
+        let profile = (1.0 - distance).sqrt() * ends;
+        let floor = height - ((height - RAVINE_BOTTOM) as f64 * profile).round() as i32;
+        (floor <= height).then_some(floor.max(1))
+    }
+
+    /// Height of the ground at `x`, `z`, blending the heights of the nearby biomes
+    pub fn height(&mut self, x: i32, z: i32) -> i32 {
+        if self.config.preset == WorldGenPreset::Superflat {
+            return SUPERFLAT_HEIGHT;
+        }
+
+        // get the properties of the main biome at (x, z)
+        let biome = self.biome_at(x, z);
+
+        // initialize weighted values
+        let mut weighted_base_height = biome.base_height as f64;
+        let mut weighted_variation = biome.height_variation as f64;
+        let mut weighted_ridge = biome.ridge_height as f64;
+        let mut weighted_terrace = biome.terrace_weight;
+        let mut total_weight = 1.0;
+
+        // loop through neighboring blocks to get influences
+        let blend = self.config.biome_blend_distance.max(1);
+        for offset_x in [-blend, 0, blend] {
+            for offset_z in [-blend, 0, blend] {
+                if offset_x == 0 && offset_z == 0 {
+                    continue; // ignore the central position
+                }
+
+                let neighbor_biome = self.biome_at(x + offset_x, z + offset_z);
+
+                // weight by distance (the farther a neighbor is, the less influence it has)
+                let distance = ((offset_x.pow(2) + offset_z.pow(2)) as f64).sqrt();
+                let weight = 1.0 / (distance + 1.0); // distance +1 to avoid division by zero
+
+                // update weighted values
+                weighted_base_height += neighbor_biome.base_height as f64 * weight;
+                weighted_variation += neighbor_biome.height_variation as f64 * weight;
+                weighted_ridge += neighbor_biome.ridge_height as f64 * weight;
+                weighted_terrace += neighbor_biome.terrace_weight * weight;
+                total_weight += weight;
+            }
+        }
+
+        // normalize weighted values
+        weighted_base_height /= total_weight;
+        weighted_variation *= self.config.height_variation_multiplier / total_weight;
+        weighted_ridge *= self.config.ridge_multiplier / total_weight;
+        weighted_terrace /= total_weight;
+
+        let mut height = weighted_base_height + weighted_variation * self.fbm(x, z);
+        if weighted_ridge > 0.0 {
+            height += weighted_ridge * self.ridge(x, z);
+        }
+
+        // flatten part of the slopes into steps for plateaus
+        if self.config.terrace_height > 0 && weighted_terrace > 0.0 {
+            let step = self.config.terrace_height as f64;
+            let terraced = (height / step).floor() * step;
+            height += (terraced - height) * weighted_terrace.min(1.0);
+        }
+
+        if self.config.preset == WorldGenPreset::Islands {
+            height -= ISLANDS_SINK;
+        }
+
+        // carved last, so that the terraces don't put steps in the river beds
+        let river = self.river(x, z);
+        if river > 0.0 {
+            // the middle of the river is dug down to its bed, unless the banks are
+            // high enough for the river to fade out
+            let bank = (height - SEA_LEVEL as f64) / RIVER_MAX_BANK_HEIGHT;
+            let fade = (2.0 - 2.0 * bank).clamp(0.0, 1.0);
+            let carve = (2.0 * river).min(1.0) * fade;
+            let bed = SEA_LEVEL as f64 - RIVER_DEPTH * river;
+            height = height.min(height + (bed - height) * carve);
+        }
+
+        height.round() as i32
+    }
+
+    fn density_noise(&self, x: i32, y: i32, z: i32, stretch: f32) -> f64 {
+        let scale = self.config.density_scale;
+        let sample_pos = Vec3::new(
+            x as f32 * scale / stretch,
+            y as f32 * scale,
+            z as f32 * scale / stretch,
+        );
+        self.density.sample_for::<f32>(sample_pos) as f64
+    }
+
+    /// Whether the stone at height `y` is deepslate
+    pub fn is_deepslate(&self, y: i32) -> bool {
+        y <= self.config.deepslate_level
+    }
+
+    /// Whether the block at `x`, `y`, `z` is part of a lava pool: lava up to the
+    /// lava level, and the air of the cave above it
+    pub fn is_lava_pool(&self, x: i32, y: i32, z: i32) -> bool {
+        let level = self.config.lava_level;
+        if level <= 0 || y < 1 || y > level + LAVA_POOL_HEADROOM {
+            return false;
+        }
+        let sample_pos = Vec3::new(x as f32, 2.0 * y as f32, z as f32) * LAVA_POOL_SCALE;
+        self.lava_pools.sample_for::<f32>(sample_pos) > LAVA_POOL_THRESHOLD
+    }
+
+    /// Whether the stone at `x`, `y`, `z` is a pocket of lava, between the lava
+    /// pools and the lava pocket level
+    pub fn is_lava_pocket(&self, x: i32, y: i32, z: i32) -> bool {
+        let level = self.config.lava_pocket_level;
+        if level <= 0 || y <= self.config.lava_level + LAVA_POOL_HEADROOM || y > level {
+            return false;
+        }
+        let sample_pos =
+            Vec3::new(x as f32, y as f32, z as f32) * LAVA_POCKET_SCALE + LAVA_POCKET_OFFSET;
+        self.lava_pools.sample_for::<f32>(sample_pos) > LAVA_POCKET_THRESHOLD
+    }
+
+    /// Whether lava springs out of the slope of the column at `x`, `z`, whose
+    /// surface is at `height`. The spring is the block right under the surface,
+    /// open to the air on the lower side of the slope.
+    pub fn is_lava_spring(&mut self, x: i32, z: i32, height: i32) -> bool {
+        if !self.config.lava_springs || height < LAVA_SPRING_MIN_HEIGHT {
+            return false;
+        }
+        // cheap checks first, the height of the neighbours blends several biomes
+        let sample_pos = Vec2::new(x as f32, z as f32) * LAVA_SPRING_SCALE;
+        if self.lava_pools.sample_for::<f32>(sample_pos) <= LAVA_SPRING_THRESHOLD {
+            return false;
+        }
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .any(|(dx, dz)| height - self.height(x + dx, z + dz) >= LAVA_SPRING_MIN_SLOPE)
+    }
+
+    /// Highest block that can be solid in a column whose surface is at `height`
+    pub fn top(&self, height: i32) -> i32 {
+        let overhangs = height + self.config.density_strength.max(0.0).ceil() as i32;
+        match self.config.world_type {
+            WorldType::Heightmap => height,
+            WorldType::Overhangs => overhangs,
+            WorldType::FloatingIslands => {
+                overhangs.max(self.config.island_altitude + ISLAND_THICKNESS as i32)
+            }
+        }
+    }
+
+    /// Whether the block at `x`, `y`, `z` is part of the ground, in a column whose
+    /// surface is at `height`
+    pub fn is_solid(&self, x: i32, y: i32, z: i32, height: i32) -> bool {
+        if self.config.world_type == WorldType::Heightmap {
+            return y <= height;
+        }
+
+        let strength = self.config.density_strength.max(0.0);
+        let depth = (height - y) as f64;
+        let ground = if depth > strength {
+            true
+        } else if depth < -strength {
+            false
+        } else {
+            depth + strength * self.density_noise(x, y, z, 1.0) >= 0.0
+        };
+        if ground || self.config.world_type != WorldType::FloatingIslands {
+            return ground;
+        }
+
+        // Islands are wider than tall, and get thinner away from their altitude
+        let distance = (y - self.config.island_altitude).abs() as f64;
+        if distance > ISLAND_THICKNESS {
+            return false;
+        }
+        let threshold = ISLAND_THRESHOLD + (1.0 - ISLAND_THRESHOLD) * distance / ISLAND_THICKNESS;
+        self.density_noise(x, y, z, 2.0) > threshold
+    }
+
+    /// Number of solid blocks right above this solid block, up to `max`, or `None`
+    /// if the block is not solid
+    pub fn depth(&self, x: i32, y: i32, z: i32, height: i32, max: i32) -> Option<i32> {
+        if !self.is_solid(x, y, z, height) {
+            return None;
+        }
+        if self.config.world_type == WorldType::Heightmap {
+            return Some((height - y).min(max));
+        }
+        Some(
+            (1..=max)
+                .find(|above| !self.is_solid(x, y + above, z, height))
+                .map_or(max, |above| above - 1),
+        )
+    }
+}
+
+/// Whether players can be dropped on the ground block at `x`, `height`, `z`: dry
+/// land with room above, no overhang, and flat around
+fn is_valid_spawn(terrain: &mut TerrainNoise, x: i32, z: i32, height: i32) -> bool {
+    if height <= SEA_LEVEL || !terrain.is_solid(x, height, z, height) {
+        return false;
+    }
+    if (height + 1..=terrain.top(height)).any(|y| terrain.is_solid(x, y, z, height)) {
+        return false;
+    }
+    [(-2, 0), (2, 0), (0, -2), (0, 2)]
+        .into_iter()
+        .all(|(dx, dz)| (terrain.height(x + dx, z + dz) - height).abs() <= SPAWN_MAX_SLOPE)
+}
+
+/// Ground block nearest to the origin where players can spawn, looked for once
+/// when the world is created. Falls back to the origin if there is only sea around.
+pub fn find_world_spawn(seed: u32, config: GenerationConfig) -> IVec3 {
+    let mut terrain = TerrainNoise::new(seed, config);
+
+    for ring in 0..=SPAWN_SEARCH_RADIUS / SPAWN_SEARCH_STEP {
+        let found = (-ring..=ring)
+            .flat_map(|a| {
+                [
+                    IVec2::new(a, -ring),
+                    IVec2::new(a, ring),
+                    IVec2::new(-ring, a),
+                    IVec2::new(ring, a),
+                ]
+            })
+            .map(|offset| offset * SPAWN_SEARCH_STEP)
+            .filter_map(|column| {
+                let height = terrain.height(column.x, column.y);
+                is_valid_spawn(&mut terrain, column.x, column.y, height)
+                    .then_some(IVec3::new(column.x, height, column.y))
+            })
+            .min_by_key(|spawn| spawn.x * spawn.x + spawn.z * spawn.z);
+        if let Some(found) = found {
+            return found;
+        }
+    }
+
+    IVec3::new(0, terrain.height(0, 0).max(SEA_LEVEL), 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn flat_config_only_keeps_the_biome_base_heights() {
+        let flat = GenerationConfig {
+            height_variation_multiplier: 0.0,
+            ..GenerationConfig::classic()
+        };
+        let mut terrain = TerrainNoise::new(42, flat);
+        let mut default_terrain = TerrainNoise::new(42, GenerationConfig::classic());
+
+        for (x, z) in [(0, 0), (100, -250), (-4000, 1234)] {
+            let height = terrain.height(x, z);
+            assert_eq!(height, terrain.height(x, z));
+            assert!((50..=75).contains(&height));
+            // Blending never leaves the range of the nearby biomes' variations
+            assert!((default_terrain.height(x, z) - height).abs() <= 7);
+        }
+    }
+
+    #[test]
+    fn ridges_only_raise_the_terrain() {
+        let without_ridges = GenerationConfig {
+            ridge_multiplier: 0.0,
+            terrace_height: 0,
+            ..Default::default()
+        };
+        let with_ridges = GenerationConfig {
+            ridge_multiplier: 2.0,
+            ..without_ridges
+        };
+        let mut lower = TerrainNoise::new(7, without_ridges);
+        let mut higher = TerrainNoise::new(7, with_ridges);
+
+        let mut raised = false;
+        for x in (-2048..2048).step_by(64) {
+            for z in (-2048..2048).step_by(64) {
+                let (low, high) = (lower.height(x, z), higher.height(x, z));
+                assert!(high >= low);
+                raised |= high > low;
+            }
+        }
+        assert!(raised, "no mountain range was found");
+    }
+
+    #[test]
+    fn density_carves_the_surface_but_keeps_the_depths() {
+        let config = GenerationConfig {
+            world_type: WorldType::Overhangs,
+            ..Default::default()
+        };
+        let mut terrain = TerrainNoise::new(3, config);
+        let strength = config.density_strength as i32;
+
+        let mut reshaped = false;
+        for x in (0..512).step_by(16) {
+            let height = terrain.height(x, 0);
+            assert!(terrain.is_solid(x, height - strength - 1, 0, height));
+            assert!(!terrain.is_solid(x, terrain.top(height) + 1, 0, height));
+            for y in height - strength..=height + strength {
+                reshaped |= terrain.is_solid(x, y, 0, height) != (y <= height);
+            }
+        }
+        assert!(reshaped, "the density noise didn't change any block");
+
+        let heightmap = TerrainNoise::new(3, GenerationConfig::default());
+        assert_eq!(heightmap.depth(0, 60, 0, 64, 5), Some(4));
+        assert_eq!(heightmap.depth(0, 64, 0, 64, 5), Some(0));
+        assert_eq!(heightmap.depth(0, 65, 0, 64, 5), None);
+    }
+
+    #[test]
+    fn rivers_carve_channels_under_the_sea_level() {
+        let without_rivers = GenerationConfig {
+            river_width: 0.0,
+            ..Default::default()
+        };
+        let mut dry = TerrainNoise::new(11, without_rivers);
+        let mut carved = TerrainNoise::new(11, GenerationConfig::default());
+
+        let mut channels = 0;
+        for x in (-2048..2048).step_by(8) {
+            for z in (-2048..2048).step_by(64) {
+                let (before, after) = (dry.height(x, z), carved.height(x, z));
+                if carved.river(x, z) == 0.0 {
+                    assert_eq!(before, after);
+                    continue;
+                }
+                assert!(after <= before);
+                if carved.river(x, z) > 0.95 && (SEA_LEVEL..SEA_LEVEL + 16).contains(&before) {
+                    assert!(after < SEA_LEVEL);
+                    channels += 1;
+                }
+            }
+        }
+        assert!(channels > 0, "no river was found");
+    }
+
+    #[test]
+    fn ravines_are_rare_and_deep() {
+        let mut terrain = TerrainNoise::new(5, GenerationConfig::default());
+
+        let (mut columns, mut cut, mut deepest) = (0, 0, i32::MAX);
+        for x in (-2048..2048).step_by(4) {
+            for z in (-2048..2048).step_by(64) {
+                let height = terrain.height(x, z);
+                columns += 1;
+                let Some(floor) = terrain.ravine_floor(x, z, height) else {
+                    continue;
+                };
+                assert!(height > SEA_LEVEL);
+                assert!((1..=height).contains(&floor));
+                cut += 1;
+                deepest = deepest.min(floor);
+            }
+        }
+        assert!(cut > 0, "no ravine was found");
+        assert!(cut * 50 < columns, "{cut} of {columns} columns are ravines");
+        assert!(deepest < SEA_LEVEL - 16, "the ravines are too shallow");
+    }
+
+    #[test]
+    fn lava_pools_stay_near_the_bedrock() {
+        let config = GenerationConfig::default();
+        let terrain = TerrainNoise::new(11, config);
+
+        let (mut blocks, mut pools) = (0, 0);
+        for x in (-256..256).step_by(2) {
+            for z in (-256..256).step_by(2) {
+                for y in 0..=32 {
+                    blocks += 1;
+                    if terrain.is_lava_pool(x, y, z) {
+                        assert!((1..=config.lava_level + LAVA_POOL_HEADROOM).contains(&y));
+                        pools += 1;
+                    }
+                }
+            }
+        }
+        assert!(pools > 0, "no lava pool was found");
+        assert!(
+            pools * 20 < blocks,
+            "{pools} of {blocks} blocks are lava pools"
+        );
+
+        let classic = TerrainNoise::new(11, GenerationConfig::classic());
+        assert!(!(1..16).any(|y| classic.is_lava_pool(0, y, 0)));
+        assert!(!classic.is_deepslate(1));
+        assert!(terrain.is_deepslate(config.deepslate_level));
+        assert!(!terrain.is_deepslate(config.deepslate_level + 1));
+    }
+
+    #[test]
+    fn lava_pockets_and_springs_are_rare() {
+        let config = GenerationConfig::default();
+        let mut terrain = TerrainNoise::new(11, config);
+
+        let (mut blocks, mut pockets) = (0, 0);
+        for x in (-256..256).step_by(4) {
+            for z in (-256..256).step_by(4) {
+                for y in 0..=64 {
+                    blocks += 1;
+                    if terrain.is_lava_pocket(x, y, z) {
+                        assert!(y > config.lava_level + LAVA_POOL_HEADROOM);
+                        assert!(y <= config.lava_pocket_level);
+                        pockets += 1;
+                    }
+                }
+            }
+        }
+        assert!(pockets > 0, "no lava pocket was found");
+        assert!(
+            pockets * 50 < blocks,
+            "{pockets} of {blocks} blocks are lava pockets"
+        );
+
+        let (mut columns, mut springs) = (0, 0);
+        for x in (-1024..1024).step_by(2) {
+            for z in (-1024..1024).step_by(16) {
+                let height = terrain.height(x, z);
+                columns += 1;
+                if terrain.is_lava_spring(x, z, height) {
+                    assert!(height >= LAVA_SPRING_MIN_HEIGHT);
+                    springs += 1;
+                }
+            }
+        }
+        assert!(springs > 0, "no lava spring was found");
+        assert!(
+            springs * 200 < columns,
+            "{springs} of {columns} columns are lava springs"
+        );
+
+        let mut classic = TerrainNoise::new(11, GenerationConfig::classic());
+        assert!(!(0..64).any(|y| classic.is_lava_pocket(0, y, 0)));
+        assert!(!classic.is_lava_spring(0, 0, 200));
+    }
+
+    #[test]
+    fn world_spawn_is_on_flat_dry_land() {
+        for seed in [1, 2, 3] {
+            let config = GenerationConfig::default();
+            let spawn = find_world_spawn(seed, config);
+            let mut terrain = TerrainNoise::new(seed, config);
+
+            assert!(spawn.y > SEA_LEVEL);
+            assert_eq!(terrain.height(spawn.x, spawn.z), spawn.y);
+            assert!(is_valid_spawn(&mut terrain, spawn.x, spawn.z, spawn.y));
+        }
+    }
+
+    #[test]
+    fn presets_reshape_the_terrain() {
+        assert_eq!("superflat".parse(), Ok(WorldGenPreset::Superflat));
+        assert_eq!("Islands".parse(), Ok(WorldGenPreset::Islands));
+        assert!("hills".parse::<WorldGenPreset>().is_err());
+
+        let config = GenerationConfig::default();
+        let mut default = TerrainNoise::new(9, config);
+        let mut flat = TerrainNoise::new(9, WorldGenPreset::Superflat.apply(config));
+        let mut amplified = TerrainNoise::new(9, WorldGenPreset::Amplified.apply(config));
+        let mut islands = TerrainNoise::new(9, WorldGenPreset::Islands.apply(config));
+
+        let (mut highest, mut highest_amplified) = (i32::MIN, i32::MIN);
+        let (mut land, mut island_land) = (0, 0);
+        for x in (-2048..2048).step_by(64) {
+            for z in (-2048..2048).step_by(64) {
+                assert_eq!(flat.height(x, z), SUPERFLAT_HEIGHT);
+                let height = default.height(x, z);
+                highest = highest.max(height);
+                highest_amplified = highest_amplified.max(amplified.height(x, z));
+                land += (height > SEA_LEVEL) as i32;
+                island_land += (islands.height(x, z) > SEA_LEVEL) as i32;
+            }
+        }
+        assert!(highest_amplified > highest + 16);
+        assert!(island_land > 0, "no island was found");
+        assert!(island_land * 2 < land);
+
+        let spawn = find_world_spawn(9, WorldGenPreset::Superflat.apply(config));
+        assert_eq!(spawn.y, SUPERFLAT_HEIGHT);
+
+        // Same heights, but with ground hanging over the air
+        assert_eq!("overhangs".parse(), Ok(WorldGenPreset::Overhangs));
+        let mut overhangs = TerrainNoise::new(9, WorldGenPreset::Overhangs.apply(config));
+        let mut overhanging = 0;
+        for x in (-512..512).step_by(16) {
+            let height = overhangs.height(x, 0);
+            assert_eq!(height, default.height(x, 0));
+            overhanging += (height - 8..overhangs.top(height)).any(|y| {
+                !overhangs.is_solid(x, y, 0, height) && overhangs.is_solid(x, y + 1, 0, height)
+            }) as i32;
+        }
+        assert!(overhanging > 0, "no overhang was found");
+    }
+
+    #[test]
+    fn beaches_line_the_sea_level() {
+        let terrain = TerrainNoise::new(4, GenerationConfig::default());
+        let (mut sand, mut gravel, mut widths) = (0, 0, HashSet::new());
+        for x in (-1024..1024).step_by(8) {
+            for z in (-1024..1024).step_by(8) {
+                assert_eq!(terrain.beach(x, z, SEA_LEVEL - BEACH_DEPTH - 1), None);
+                assert_eq!(terrain.beach(x, z, SEA_LEVEL + BEACH_MAX_HEIGHT + 1), None);
+                match terrain.beach(x, z, SEA_LEVEL) {
+                    Some(BlockId::Sand) => sand += 1,
+                    Some(BlockId::Gravel) => gravel += 1,
+                    other => panic!("no beach at the sea level: {other:?}"),
+                }
+                widths.insert(
+                    (SEA_LEVEL..=SEA_LEVEL + BEACH_MAX_HEIGHT)
+                        .take_while(|height| terrain.beach(x, z, *height).is_some())
+                        .count(),
+                );
+            }
+        }
+        assert!(gravel > 0 && sand > gravel);
+        assert!(widths.len() > 2, "beaches all have the same width");
+    }
+
+    #[test]
+    fn seabeds_are_mostly_sand_with_patches() {
+        let terrain = TerrainNoise::new(5, GenerationConfig::default());
+        let (mut sand, mut gravel, mut clay) = (0, 0, 0);
+        for x in (-1024..1024).step_by(8) {
+            for z in (-1024..1024).step_by(8) {
+                match terrain.seabed_patch(x, z) {
+                    None => sand += 1,
+                    Some(BlockId::Gravel) => gravel += 1,
+                    Some(BlockId::Clay) => clay += 1,
+                    Some(block) => panic!("{block:?} patch on the seabed"),
+                }
+            }
+        }
+        assert!(gravel > 0 && clay > 0);
+        assert!(sand > gravel + clay);
+    }
+
+    #[test]
+    fn flowers_grow_in_meadows() {
+        let terrain = TerrainNoise::new(5, GenerationConfig::default());
+        let densities: Vec<f32> = (-1024..1024)
+            .step_by(8)
+            .flat_map(|x| (-1024..1024).step_by(8).map(move |z| (x, z)))
+            .map(|(x, z)| terrain.flower_density(x, z))
+            .collect();
+        let bare = densities.iter().filter(|density| **density == 0.0).count();
+        let dense = densities.iter().filter(|density| **density > 1.0).count();
+        assert!(bare > densities.len() / 3, "flowers grow everywhere");
+        assert!(dense > 0, "no dense meadow");
+    }
+}
@@ -2,6 +2,39 @@ use bevy::math::{IVec3, Vec3};
 
 use crate::CHUNK_SIZE;
 
+/// Small deterministic generator (SplitMix64) for the world generation, independent
+/// from the version of `rand` so that regenerated chunks stay the same
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    /// Generator for the world `seed` at `position`, `salt` telling apart the users
+    pub fn new(seed: u32, position: IVec3, salt: u64) -> Self {
+        let mut rng = Self(seed as u64 ^ (salt << 32));
+        for coordinate in [position.x, position.y, position.z] {
+            rng.0 ^= rng.next_u64() ^ coordinate as u32 as u64;
+        }
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in `[0, bound)`
+    pub fn below(&mut self, bound: i32) -> i32 {
+        (self.next_u64() % bound as u64) as i32
+    }
+}
+
 pub fn block_to_chunk_coord(x: i32) -> i32 {
     if x >= 0 {
         x / CHUNK_SIZE
@@ -68,10 +101,54 @@ pub fn chunk_in_radius(player_pos: &IVec3, chunk_pos: &IVec3, radius: i32) -> bo
     (player_pos.x - chunk_pos.x).abs() <= radius && (player_pos.z - chunk_pos.z).abs() <= radius
 }
 
+/// Horizontal distance, in chunks, of the ring around a joining player that is
+/// streamed before anything else
+pub const SPAWN_GROUND_RING: i32 = 1;
+
+/// Rank of `chunk_pos` among the chunks holding the ground under a player standing
+/// in `player_chunk_pos`: the column under the player first, then the ring around
+/// it. `None` for the other chunks.
+pub fn spawn_ground_rank(chunk_pos: IVec3, player_chunk_pos: IVec3) -> Option<i32> {
+    let offset = chunk_pos - player_chunk_pos;
+    let ring = offset.x.abs().max(offset.z.abs());
+    (ring <= SPAWN_GROUND_RING && (-1..=0).contains(&offset.y)).then_some(ring * 2 - offset.y)
+}
+
+/// Chunks holding the ground under a player standing in `player_chunk_pos`
+pub fn spawn_ground_chunks(player_chunk_pos: IVec3) -> impl Iterator<Item = IVec3> {
+    (-SPAWN_GROUND_RING..=SPAWN_GROUND_RING).flat_map(move |x| {
+        (-SPAWN_GROUND_RING..=SPAWN_GROUND_RING)
+            .flat_map(move |z| [0, -1].map(|y| player_chunk_pos + IVec3::new(x, y, z)))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn spawn_column_comes_before_its_ring() {
+        let player = IVec3::new(3, 4, -2);
+        assert_eq!(spawn_ground_rank(player, player), Some(0));
+        assert_eq!(spawn_ground_rank(player - IVec3::Y, player), Some(1));
+        assert_eq!(spawn_ground_rank(player + IVec3::X, player), Some(2));
+        assert_eq!(
+            spawn_ground_rank(player + IVec3::new(1, -1, 1), player),
+            Some(3)
+        );
+        assert_eq!(spawn_ground_rank(player + IVec3::Y, player), None);
+        assert_eq!(
+            spawn_ground_rank(player + IVec3::new(2, 0, 0), player),
+            None
+        );
+
+        let chunks: Vec<IVec3> = spawn_ground_chunks(player).collect();
+        assert_eq!(chunks.len(), 18);
+        assert!(chunks
+            .iter()
+            .all(|chunk| spawn_ground_rank(*chunk, player).is_some()));
+    }
+
     #[test]
     fn global_to_chunk_local_handles_positive_and_negative_coords() {
         let position = IVec3::new(16, 0, -1);
@@ -0,0 +1,132 @@
+//! Waystones are blocks players can teleport between.
+//!
+//! A player first activates a waystone by using it, which registers it for them.
+//! Using an activated waystone opens the list of every waystone they discovered,
+//! and teleporting to one of them starts a cooldown of [`WAYSTONE_COOLDOWN_TICKS`].
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{messages::PlayerId, TICKS_PER_SECOND};
+
+/// Ticks between two teleports of a player
+pub const WAYSTONE_COOLDOWN_TICKS: u64 = 30 * TICKS_PER_SECOND;
+/// Waystones can't be used from further away than this
+pub const WAYSTONE_MAX_DISTANCE: f32 = 5.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Waystone {
+    pub name: String,
+    pub discovered_by: HashSet<PlayerId>,
+}
+
+/// A waystone, as listed to a player
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WaystoneEntry {
+    pub position: IVec3,
+    pub name: String,
+}
+
+/// Every waystone of the world, saved with it
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WaystoneRegistry {
+    pub waystones: HashMap<IVec3, Waystone>,
+    /// Tick of the last teleport of each player, reset when the server restarts
+    #[serde(skip)]
+    pub last_teleports: HashMap<PlayerId, u64>,
+}
+
+/// Where players arrive when teleporting to the waystone at `position`
+pub fn waystone_arrival_position(position: IVec3) -> Vec3 {
+    position.as_vec3() + Vec3::new(0.5, 2.0, 0.5)
+}
+
+impl WaystoneRegistry {
+    /// Registers the waystone at `position` for `player`.
+    /// Returns `false` if they had already discovered it.
+    pub fn activate(&mut self, position: IVec3, player: PlayerId) -> bool {
+        let waystone = self.waystones.entry(position).or_insert_with(|| Waystone {
+            name: format!("Waystone ({}, {}, {})", position.x, position.y, position.z),
+            discovered_by: HashSet::new(),
+        });
+        waystone.discovered_by.insert(player)
+    }
+
+    pub fn is_discovered(&self, position: IVec3, player: PlayerId) -> bool {
+        self.waystones
+            .get(&position)
+            .is_some_and(|waystone| waystone.discovered_by.contains(&player))
+    }
+
+    /// Waystones discovered by `player`, sorted by name
+    pub fn discovered(&self, player: PlayerId) -> Vec<WaystoneEntry> {
+        let mut entries: Vec<WaystoneEntry> = self
+            .waystones
+            .iter()
+            .filter(|(_, waystone)| waystone.discovered_by.contains(&player))
+            .map(|(position, waystone)| WaystoneEntry {
+                position: *position,
+                name: waystone.name.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    /// Ticks left before `player` can teleport again
+    pub fn cooldown_remaining(&self, player: PlayerId, tick: u64) -> u64 {
+        self.last_teleports
+            .get(&player)
+            .map(|last| (last + WAYSTONE_COOLDOWN_TICKS).saturating_sub(tick))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waystones_are_discovered_per_player() {
+        let mut registry = WaystoneRegistry::default();
+        let first = IVec3::new(10, 64, -3);
+        let second = IVec3::new(-40, 70, 12);
+
+        assert!(registry.activate(first, 1));
+        assert!(!registry.activate(first, 1));
+        assert!(registry.activate(second, 2));
+
+        assert!(registry.is_discovered(first, 1));
+        assert!(!registry.is_discovered(second, 1));
+        assert_eq!(
+            registry
+                .discovered(1)
+                .iter()
+                .map(|entry| entry.position)
+                .collect::<Vec<_>>(),
+            vec![first]
+        );
+
+        registry.activate(second, 1);
+        assert_eq!(registry.discovered(1).len(), 2);
+    }
+
+    #[test]
+    fn teleport_cooldown_expires() {
+        let mut registry = WaystoneRegistry::default();
+        assert_eq!(registry.cooldown_remaining(1, 100), 0);
+
+        registry.last_teleports.insert(1, 100);
+        assert_eq!(
+            registry.cooldown_remaining(1, 110),
+            WAYSTONE_COOLDOWN_TICKS - 10
+        );
+        assert_eq!(
+            registry.cooldown_remaining(1, 100 + WAYSTONE_COOLDOWN_TICKS),
+            0
+        );
+        assert_eq!(registry.cooldown_remaining(2, 110), 0);
+    }
+}
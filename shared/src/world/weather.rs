@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Global weather, decided by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    /// Rain, or snow in freezing climates
+    Precipitation,
+}